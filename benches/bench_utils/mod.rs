@@ -0,0 +1,81 @@
+//! Utilities for benchmarking individual audio processors
+//!
+//! Each benchmark renders a looping noise buffer through the node under test in an
+//! `OfflineAudioContext`, for a range of channel counts. The render quantum size is fixed at 128
+//! frames by the implementation, the render length is expressed in number of render quanta.
+
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+
+use web_audio_api::context::{BaseAudioContext, OfflineAudioContext};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+use web_audio_api::{AudioBuffer, SeededRng};
+
+pub const SAMPLE_RATE: f32 = 48000.;
+pub const RENDER_QUANTUM_SIZE: usize = 128;
+
+/// Channel counts to measure: mono, stereo and 5.1
+pub const CHANNEL_COUNTS: [usize; 3] = [1, 2, 6];
+
+/// Number of render quanta rendered per iteration (~1 second of audio)
+pub const NUMBER_OF_QUANTA: usize = 375;
+
+/// Create a buffer filled with deterministic white noise
+pub fn noise_buffer(number_of_channels: usize, length: usize, sample_rate: f32) -> AudioBuffer {
+    let mut rng = SeededRng::new(0);
+    let channels = (0..number_of_channels)
+        .map(|_| (0..length).map(|_| rng.next_bipolar()).collect())
+        .collect();
+
+    AudioBuffer::from(channels, sample_rate)
+}
+
+/// Render the given input through the node created by `build`
+///
+/// The node is connected to the destination, which has the same number of channels as the
+/// input.
+pub fn render_through<N, F>(input: &AudioBuffer, number_of_quanta: usize, build: F) -> AudioBuffer
+where
+    N: AudioNode,
+    F: FnOnce(&OfflineAudioContext) -> N,
+{
+    let length = number_of_quanta * RENDER_QUANTUM_SIZE;
+    let number_of_channels = input.number_of_channels();
+    let mut context = OfflineAudioContext::new(number_of_channels, length, input.sample_rate());
+
+    let node = build(&context);
+    node.connect(&context.destination());
+
+    let mut src = context.create_buffer_source();
+    src.set_buffer(input.clone());
+    src.set_loop(true);
+    src.connect(&node);
+    src.start();
+
+    context.start_rendering_sync()
+}
+
+/// Benchmark the node created by `build` for all channel counts in [`CHANNEL_COUNTS`]
+///
+/// The throughput is reported in sample frames per second.
+pub fn bench_node<N, F>(c: &mut Criterion, name: &str, build: F)
+where
+    N: AudioNode,
+    F: Fn(&OfflineAudioContext) -> N,
+{
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(
+        (NUMBER_OF_QUANTA * RENDER_QUANTUM_SIZE) as u64,
+    ));
+
+    for number_of_channels in CHANNEL_COUNTS {
+        let input = noise_buffer(number_of_channels, SAMPLE_RATE as usize, SAMPLE_RATE);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(number_of_channels),
+            &input,
+            |b, input| b.iter(|| render_through(input, black_box(NUMBER_OF_QUANTA), &build)),
+        );
+    }
+
+    group.finish();
+}
@@ -0,0 +1,135 @@
+//! Benchmarks of the individual built-in audio processors
+//!
+//! `cargo bench --bench processors`, or `cargo bench --bench processors -- <name>` to measure a
+//! single node.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use web_audio_api::context::BaseAudioContext;
+use web_audio_api::node::{LimiterNode, LimiterOptions, OverSampleType, PanningModelType};
+
+mod bench_utils;
+use bench_utils::{bench_node, noise_buffer};
+
+fn bench_gain(c: &mut Criterion) {
+    bench_node(c, "gain", |context| {
+        let gain = context.create_gain();
+        gain.gain().set_value(0.5); // avoid happy path
+        gain
+    });
+}
+
+fn bench_delay(c: &mut Criterion) {
+    bench_node(c, "delay", |context| {
+        let delay = context.create_delay(1.);
+        delay.delay_time().set_value(0.2);
+        delay
+    });
+}
+
+fn bench_biquad_filter(c: &mut Criterion) {
+    bench_node(c, "biquad_filter", |context| {
+        let biquad = context.create_biquad_filter();
+        biquad.frequency().set_value(200.);
+        biquad
+    });
+}
+
+fn bench_iir_filter(c: &mut Criterion) {
+    bench_node(c, "iir_filter", |context| {
+        // lowpass filter at 200Hz (calculated from biquad)
+        let feedforward = vec![
+            0.0002029799640409502,
+            0.0004059599280819004,
+            0.0002029799640409502,
+        ];
+        let feedback = vec![1.0126964557853775, -1.9991880801438362, 0.9873035442146225];
+        context.create_iir_filter(feedforward, feedback)
+    });
+}
+
+fn bench_convolver(c: &mut Criterion) {
+    bench_node(c, "convolver", |context| {
+        let mut convolver = context.create_convolver();
+        // stereo impulse response of 0.5 seconds
+        let length = (context.sample_rate() * 0.5) as usize;
+        convolver.set_buffer(noise_buffer(2, length, context.sample_rate()));
+        convolver
+    });
+}
+
+fn bench_dynamics_compressor(c: &mut Criterion) {
+    bench_node(c, "dynamics_compressor", |context| {
+        context.create_dynamics_compressor()
+    });
+}
+
+fn bench_limiter(c: &mut Criterion) {
+    bench_node(c, "limiter", |context| {
+        LimiterNode::new(context, LimiterOptions::default())
+    });
+    bench_node(c, "limiter_true_peak", |context| {
+        let options = LimiterOptions {
+            true_peak: true,
+            ..LimiterOptions::default()
+        };
+        LimiterNode::new(context, options)
+    });
+}
+
+fn bench_stereo_panner(c: &mut Criterion) {
+    bench_node(c, "stereo_panner", |context| {
+        let panner = context.create_stereo_panner();
+        panner.pan().set_value(0.3);
+        panner
+    });
+}
+
+fn bench_panner(c: &mut Criterion) {
+    bench_node(c, "panner_equal_power", |context| {
+        let panner = context.create_panner();
+        panner.position_x().set_value(1.);
+        panner
+    });
+    bench_node(c, "panner_hrtf", |context| {
+        let mut panner = context.create_panner();
+        panner.set_panning_model(PanningModelType::HRTF);
+        panner.position_x().set_value(1.);
+        panner
+    });
+}
+
+fn bench_wave_shaper(c: &mut Criterion) {
+    bench_node(c, "wave_shaper_4x", |context| {
+        let mut shaper = context.create_wave_shaper();
+        shaper.set_curve(vec![-0.5, 0., 0.5]);
+        shaper.set_oversample(OverSampleType::X4);
+        shaper
+    });
+    bench_node(c, "wave_shaper_16x", |context| {
+        let mut shaper = context.create_wave_shaper();
+        shaper.set_curve(vec![-0.5, 0., 0.5]);
+        shaper.set_oversample(OverSampleType::X16);
+        shaper
+    });
+}
+
+fn bench_analyser(c: &mut Criterion) {
+    bench_node(c, "analyser", |context| context.create_analyser());
+}
+
+criterion_group!(
+    benches,
+    bench_gain,
+    bench_delay,
+    bench_biquad_filter,
+    bench_iir_filter,
+    bench_convolver,
+    bench_dynamics_compressor,
+    bench_limiter,
+    bench_stereo_panner,
+    bench_panner,
+    bench_wave_shaper,
+    bench_analyser,
+);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+use std::io::Write;
+
+use web_audio_api::context::GraphDescription;
+use web_audio_api::offline_render::{render_graph_to_file, OfflineRenderOptions};
+
+// Render a JSON graph description to a WAV file, e.g. a graph exported with
+// `serde_json::to_string(&context.export_graph())`
+//
+// The `AudioBufferSourceNode`s of the graph play the file given for their label.
+//
+// `cargo run --release --features serde --example render_graph -- graph.json out.wav 10 drums=samples/sample.wav`
+fn main() {
+    env_logger::init();
+
+    let mut args = std::env::args().skip(1);
+    let usage = "usage: render_graph <graph.json> <out.wav> [duration] [label=asset ...]";
+    let graph_path = args.next().expect(usage);
+    let out_path = args.next().expect(usage);
+
+    let mut options = OfflineRenderOptions::default();
+    if let Some(duration) = args.next() {
+        options.duration = duration.parse().expect(usage);
+    }
+    for asset in args {
+        let (label, path) = asset.split_once('=').expect(usage);
+        options.assets.insert(label.to_string(), path.into());
+    }
+
+    let json = std::fs::read(graph_path).unwrap();
+    let description: GraphDescription = serde_json::from_slice(&json).unwrap();
+
+    render_graph_to_file(&description, &options, &out_path, |progress| {
+        print!("\rRendering {:>3.0}%", progress * 100.);
+        std::io::stdout().flush().unwrap();
+    })
+    .unwrap();
+    println!("\nWritten to {out_path}");
+}
@@ -56,6 +56,8 @@ fn main() {
     shaper.set_oversample(OverSampleType::None);
     // shaper.set_oversample(OverSampleType::X2);
     // shaper.set_oversample(OverSampleType::X4);
+    // shaper.set_oversample(OverSampleType::X8);
+    // shaper.set_oversample(OverSampleType::X16);
     shaper.connect(&post_gain);
     shaper.set_curve(curve);
 
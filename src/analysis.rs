@@ -4,7 +4,7 @@
 
 use std::f32::consts::PI;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 
 use realfft::{num_complex::Complex, RealFftPlanner};
 
@@ -23,16 +23,120 @@ fn generate_blackman(size: usize) -> impl Iterator<Item = f32> {
     })
 }
 
+/// Window function applied to the time domain data before the FFT of the
+/// [`AnalyserNode`](crate::node::AnalyserNode)
+///
+/// The specification mandates the Blackman window, the other windows are not part of the Web
+/// Audio API specification. All of them are cosine-sum windows, generated in their periodic form.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum WindowFunction {
+    /// Blackman window with alpha = 0.16, as defined by the specification
+    #[default]
+    Blackman,
+    /// Hann window, a good default for general purpose spectrum analysis
+    Hann,
+    /// Hamming window, with a lower first side lobe than the Hann window
+    Hamming,
+    /// Rectangular window, i.e. no windowing, for signals periodic in the FFT frame
+    Rectangular,
+    /// Flat-top window, for accurate amplitude measurements of sinusoids
+    FlatTop,
+}
+
+impl WindowFunction {
+    /// Coefficients `a_k` of the window `w[n] = sum_k (-1)^k a_k cos(2 pi k n / N)`
+    fn coefficients(self) -> &'static [f32] {
+        match self {
+            Self::Blackman => &[0.42, 0.5, 0.08],
+            Self::Hann => &[0.5, 0.5],
+            Self::Hamming => &[0.54, 0.46],
+            Self::Rectangular => &[1.],
+            Self::FlatTop => &[
+                0.215_578_95,
+                0.416_631_58,
+                0.277_263_16,
+                0.083_578_95,
+                0.006_947_368,
+            ],
+        }
+    }
+
+    /// Factor correcting the amplitude of a sinusoid measured through the window, i.e. the
+    /// inverse of the coherent gain (mean value) of the window
+    pub fn amplitude_correction(self) -> f32 {
+        1. / self.coefficients()[0]
+    }
+
+    /// Factor correcting the energy of a broadband signal measured through the window, i.e.
+    /// the inverse of the root mean square value of the window
+    pub fn energy_correction(self) -> f32 {
+        let coefficients = self.coefficients();
+        let mean_square =
+            coefficients[0].powi(2) + coefficients[1..].iter().map(|a| a * a / 2.).sum::<f32>();
+        1. / mean_square.sqrt()
+    }
+
+    /// Equivalent noise bandwidth of the window, in FFT bins
+    pub fn equivalent_noise_bandwidth(self) -> f32 {
+        let coherent_gain = 1. / self.amplitude_correction();
+        let rms = 1. / self.energy_correction();
+        (rms / coherent_gain).powi(2)
+    }
+
+    /// Window values iterator for the given size
+    pub(crate) fn generate(self, size: usize) -> Box<dyn Iterator<Item = f32>> {
+        if self == Self::Blackman {
+            return Box::new(generate_blackman(size));
+        }
+
+        let coefficients = self.coefficients();
+        Box::new((0..size).map(move |i| {
+            let phase = 2. * PI * i as f32 / size as f32;
+            coefficients
+                .iter()
+                .enumerate()
+                .map(|(k, a)| {
+                    let sign = if k % 2 == 0 { 1. } else { -1. };
+                    sign * a * (k as f32 * phase).cos()
+                })
+                .sum()
+        }))
+    }
+}
+
 pub(crate) const DEFAULT_SMOOTHING_TIME_CONSTANT: f64 = 0.8;
 pub(crate) const DEFAULT_MIN_DECIBELS: f64 = -100.;
 pub(crate) const DEFAULT_MAX_DECIBELS: f64 = -30.;
 pub(crate) const DEFAULT_FFT_SIZE: usize = 2048;
 
 const MIN_FFT_SIZE: usize = 32;
-const MAX_FFT_SIZE: usize = 32768;
+/// Largest fft size of the specification, the analyser buffers are preallocated for this size
+const SPEC_MAX_FFT_SIZE: usize = 32768;
+/// Largest fft size, larger than the specification to allow for low frequency measurements
+const MAX_FFT_SIZE: usize = 131072;
+const MAX_ZERO_PADDING: usize = 8;
+
+/// FFT planner shared by all analysers, so each FFT size is planned only once
+pub(crate) fn fft_planner() -> &'static Mutex<RealFftPlanner<f32>> {
+    // RealFftPlanner is not `Sync` on all platforms
+    static INSTANCE: OnceLock<Mutex<RealFftPlanner<f32>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| Mutex::new(RealFftPlanner::new()))
+}
+
+/// Plan the FFTs of all analyser sizes of the specification ahead of time
+pub(crate) fn prime_fft_plans() {
+    let mut planner = fft_planner().lock().unwrap();
+    let mut fft_size = MIN_FFT_SIZE;
+    while fft_size <= SPEC_MAX_FFT_SIZE {
+        planner.plan_fft_forward(fft_size);
+        fft_size *= 2;
+    }
+}
 
 // [spec] This MUST be a power of two in the range 32 to 32768, otherwise an
 // IndexSizeError exception MUST be thrown.
+//
+// We extend the range up to 131072, see MAX_FFT_SIZE.
 #[allow(clippy::manual_range_contains)]
 fn assert_valid_fft_size(fft_size: usize) {
     assert!(
@@ -71,9 +175,40 @@ fn assert_valid_decibels(min_decibels: f64, max_decibels: f64) {
     );
 }
 
+#[track_caller]
+fn assert_valid_zero_padding(zero_padding: usize) {
+    assert!(
+        zero_padding.is_power_of_two() && zero_padding <= MAX_ZERO_PADDING,
+        "IndexSizeError - Invalid zero padding: {:?} is not a power of two in range [1, {:?}]",
+        zero_padding,
+        MAX_ZERO_PADDING
+    );
+}
+
+#[track_caller]
+fn assert_valid_frequency_bands(bands: usize, min_frequency: f32, max_frequency: f32) {
+    assert!(bands > 0, "IndexSizeError - Invalid number of bands: 0");
+    assert!(
+        min_frequency > 0. && min_frequency < max_frequency && max_frequency.is_finite(),
+        "RangeError - Invalid frequency range: [{:?}, {:?}] must be strictly positive and increasing",
+        min_frequency,
+        max_frequency
+    );
+}
+
+/// Scale a magnitude between the min and max decibels to a byte value
+#[inline(always)]
+fn magnitude_to_byte(magnitude: f32, min_decibels: f32, max_decibels: f32) -> u8 {
+    let db = 20. * magnitude.log10();
+    // 𝑏[𝑘] = ⌊255 / dB𝑚𝑎𝑥−dB𝑚𝑖𝑛 * (𝑌[𝑘]−dB𝑚𝑖𝑛)⌋
+    let scaled = 255. / (max_decibels - min_decibels) * (db - min_decibels);
+    let clamped = scaled.clamp(0., 255.);
+    clamped as u8
+}
+
 // as the queue is composed of AtomicF32 having only 1 render quantum of extra
 // room should be enough
-const RING_BUFFER_SIZE: usize = MAX_FFT_SIZE + RENDER_QUANTUM_SIZE;
+const RING_BUFFER_SIZE: usize = SPEC_MAX_FFT_SIZE + RENDER_QUANTUM_SIZE;
 
 // single producer / multiple consumer ring buffer
 #[derive(Clone)]
@@ -84,8 +219,12 @@ pub(crate) struct AnalyserRingBuffer {
 
 impl AnalyserRingBuffer {
     pub fn new() -> Self {
-        let mut buffer = Vec::with_capacity(RING_BUFFER_SIZE);
-        buffer.resize_with(RING_BUFFER_SIZE, || AtomicF32::new(0.));
+        Self::with_len(RING_BUFFER_SIZE)
+    }
+
+    fn with_len(len: usize) -> Self {
+        let mut buffer = Vec::with_capacity(len);
+        buffer.resize_with(len, || AtomicF32::new(0.));
 
         Self {
             buffer: buffer.into(),
@@ -93,19 +232,25 @@ impl AnalyserRingBuffer {
         }
     }
 
+    /// Largest number of frames that can be read
+    pub fn capacity(&self) -> usize {
+        self.buffer.len() - RENDER_QUANTUM_SIZE
+    }
+
     pub fn write(&self, src: &[f32]) {
         let mut write_index = self.write_index.load(Ordering::SeqCst);
         let len = src.len();
+        let size = self.buffer.len();
 
         src.iter().enumerate().for_each(|(index, value)| {
-            let position = (write_index + index) % RING_BUFFER_SIZE;
+            let position = (write_index + index) % size;
             self.buffer[position].store(*value, Ordering::Relaxed);
         });
 
         write_index += len;
 
-        if write_index >= RING_BUFFER_SIZE {
-            write_index -= RING_BUFFER_SIZE;
+        if write_index >= size {
+            write_index -= size;
         }
 
         self.write_index.store(write_index, Ordering::SeqCst);
@@ -115,21 +260,47 @@ impl AnalyserRingBuffer {
         let write_index = self.write_index.load(Ordering::SeqCst);
         // let fft_size = self.fft_size.load(Ordering::SeqCst);
         let len = dst.len().min(max_len);
+        let size = self.buffer.len();
 
         dst.iter_mut()
             .take(len)
             .enumerate()
             .for_each(|(index, value)| {
-                // offset calculation by RING_BUFFER_SIZE so we can't negative values
-                let position = (RING_BUFFER_SIZE + write_index - len + index) % RING_BUFFER_SIZE;
+                // offset calculation by the buffer size so we can't negative values
+                let position = (size + write_index - len + index) % size;
                 *value = self.buffer[position].load(Ordering::Relaxed);
             });
     }
 
+    /// Replace the content with the most recent frames of `other`
+    ///
+    /// Used by the renderer when it swaps to a larger ring buffer, so the frames written to the
+    /// old one after the node copied it are not lost.
+    pub fn copy_from(&self, other: &Self) {
+        let other_size = other.buffer.len();
+        let other_index = other.write_index.load(Ordering::SeqCst);
+        let len = other_size.min(self.buffer.len());
+
+        self.buffer
+            .iter()
+            .take(len)
+            .enumerate()
+            .for_each(|(index, value)| {
+                let position = (other_size + other_index - len + index) % other_size;
+                value.store(
+                    other.buffer[position].load(Ordering::Relaxed),
+                    Ordering::Relaxed,
+                );
+            });
+
+        self.write_index
+            .store(len % self.buffer.len(), Ordering::SeqCst);
+    }
+
     // to simply share tests with the unsafe version
     #[cfg(test)]
     fn raw(&self) -> Vec<f32> {
-        let mut slice = vec![0.; RING_BUFFER_SIZE];
+        let mut slice = vec![0.; self.buffer.len()];
 
         self.buffer.iter().zip(slice.iter_mut()).for_each(|(a, b)| {
             *b = a.load(Ordering::SeqCst);
@@ -144,25 +315,28 @@ impl AnalyserRingBuffer {
 pub(crate) struct Analyser {
     ring_buffer: AnalyserRingBuffer,
     fft_size: usize,
+    zero_padding: usize,
     smoothing_time_constant: f64,
     min_decibels: f64,
     max_decibels: f64,
-    fft_planner: Mutex<RealFftPlanner<f32>>, // RealFftPlanner is not `Sync` on all platforms
     fft_input: Vec<f32>,
     fft_scratch: Vec<Complex<f32>>,
     fft_output: Vec<Complex<f32>>,
     last_fft_output: Vec<f32>,
     last_fft_time: f64,
-    blackman: Vec<f32>,
+    window_function: WindowFunction,
+    window: Vec<f32>,
 }
 
 impl std::fmt::Debug for Analyser {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Analyser")
             .field("fft_size", &self.fft_size())
+            .field("zero_padding", &self.zero_padding())
             .field("smoothing_time_constant", &self.smoothing_time_constant())
             .field("min_decibels", &self.min_decibels())
             .field("max_decibels", &self.max_decibels())
+            .field("window_function", &self.window_function())
             .finish_non_exhaustive()
     }
 }
@@ -171,8 +345,10 @@ impl Analyser {
     pub fn new() -> Self {
         let ring_buffer = AnalyserRingBuffer::new();
         // FFT utils
-        let mut fft_planner = RealFftPlanner::<f32>::new();
-        let max_fft = fft_planner.plan_fft_forward(MAX_FFT_SIZE);
+        let max_fft = fft_planner()
+            .lock()
+            .unwrap()
+            .plan_fft_forward(SPEC_MAX_FFT_SIZE);
 
         let fft_input = max_fft.make_input_vec();
         let fft_scratch = max_fft.make_scratch_vec();
@@ -180,23 +356,25 @@ impl Analyser {
         let mut last_fft_output = Vec::with_capacity(fft_output.len());
         last_fft_output.resize_with(fft_output.len(), || 0.);
 
-        // precalculate Blackman window values, reserve enough space for all input sizes
-        let mut blackman = Vec::with_capacity(fft_input.len());
-        generate_blackman(DEFAULT_FFT_SIZE).for_each(|v| blackman.push(v));
+        // precalculate window values, reserve enough space for all input sizes
+        let window_function = WindowFunction::default();
+        let mut window = Vec::with_capacity(fft_input.len());
+        window.extend(window_function.generate(DEFAULT_FFT_SIZE));
 
         Self {
             ring_buffer,
             fft_size: DEFAULT_FFT_SIZE,
+            zero_padding: 1,
             smoothing_time_constant: DEFAULT_SMOOTHING_TIME_CONSTANT,
             min_decibels: DEFAULT_MIN_DECIBELS,
             max_decibels: DEFAULT_MAX_DECIBELS,
-            fft_planner: Mutex::new(fft_planner),
             fft_input,
             fft_scratch,
             fft_output,
             last_fft_output,
             last_fft_time: f64::NEG_INFINITY,
-            blackman,
+            window_function,
+            window,
         }
     }
 
@@ -208,22 +386,75 @@ impl Analyser {
         self.fft_size
     }
 
+    /// Set the fft size, the ring buffer is replaced by a larger one if it is too small, in
+    /// which case the new ring buffer must be sent to the renderer
     pub fn set_fft_size(&mut self, fft_size: usize) {
         assert_valid_fft_size(fft_size);
 
         let current_fft_size = self.fft_size;
 
         if current_fft_size != fft_size {
+            self.grow_buffers(fft_size, self.zero_padding);
             // reset last fft buffer
             self.last_fft_output.iter_mut().for_each(|v| *v = 0.);
-            // generate blackman window
-            self.blackman.clear();
-            generate_blackman(fft_size).for_each(|v| self.blackman.push(v));
+            // generate window
+            self.window.clear();
+            self.window.extend(self.window_function.generate(fft_size));
 
             self.fft_size = fft_size;
         }
     }
 
+    pub fn zero_padding(&self) -> usize {
+        self.zero_padding
+    }
+
+    pub fn set_zero_padding(&mut self, zero_padding: usize) {
+        assert_valid_zero_padding(zero_padding);
+
+        if self.zero_padding != zero_padding {
+            self.grow_buffers(self.fft_size, zero_padding);
+            self.last_fft_output.iter_mut().for_each(|v| *v = 0.);
+            self.zero_padding = zero_padding;
+        }
+    }
+
+    /// Grow the buffers beyond the size preallocated for the specification if needed
+    fn grow_buffers(&mut self, fft_size: usize, zero_padding: usize) {
+        if fft_size > self.ring_buffer.capacity() {
+            // keep the most recent frames
+            let ring_buffer = AnalyserRingBuffer::with_len(fft_size + RENDER_QUANTUM_SIZE);
+            let mut frames = vec![0.; self.ring_buffer.capacity()];
+            self.ring_buffer.read(&mut frames, usize::MAX);
+            ring_buffer.write(&frames);
+            self.ring_buffer = ring_buffer;
+        }
+
+        let padded_size = fft_size * zero_padding;
+        let fft = fft_planner().lock().unwrap().plan_fft_forward(padded_size);
+        if padded_size > self.fft_input.len() {
+            self.fft_input = fft.make_input_vec();
+            self.fft_output = fft.make_output_vec();
+            self.last_fft_output.resize(self.fft_output.len(), 0.);
+        }
+
+        if fft.get_scratch_len() > self.fft_scratch.len() {
+            self.fft_scratch = fft.make_scratch_vec();
+        }
+    }
+
+    pub fn window_function(&self) -> WindowFunction {
+        self.window_function
+    }
+
+    pub fn set_window_function(&mut self, window_function: WindowFunction) {
+        if self.window_function != window_function {
+            self.window.clear();
+            self.window.extend(window_function.generate(self.fft_size));
+            self.window_function = window_function;
+        }
+    }
+
     pub fn smoothing_time_constant(&self) -> f64 {
         self.smoothing_time_constant
     }
@@ -250,7 +481,7 @@ impl Analyser {
     }
 
     pub fn frequency_bin_count(&self) -> usize {
-        self.fft_size() / 2
+        self.fft_size() * self.zero_padding() / 2
     }
 
     // [spec] Write the current time-domain data (waveform data) into array.
@@ -277,25 +508,31 @@ impl Analyser {
 
     fn compute_fft(&mut self) {
         let fft_size = self.fft_size();
+        let padded_size = fft_size * self.zero_padding();
         let smoothing_time_constant = self.smoothing_time_constant() as f32;
         // setup FFT planner and properly sized buffers
-        let r2c = self.fft_planner.lock().unwrap().plan_fft_forward(fft_size);
-        let input = &mut self.fft_input[..fft_size];
-        let output = &mut self.fft_output[..fft_size / 2 + 1];
+        let r2c = fft_planner().lock().unwrap().plan_fft_forward(padded_size);
+        let input = &mut self.fft_input[..padded_size];
+        let output = &mut self.fft_output[..padded_size / 2 + 1];
         let scratch = &mut self.fft_scratch[..r2c.get_scratch_len()];
         // we ignore the Nyquist bin in output, see comment below
-        let last_fft_output = &mut self.last_fft_output[..fft_size / 2];
+        let last_fft_output = &mut self.last_fft_output[..padded_size / 2];
 
         // Compute the current time-domain data.
         // The most recent fftSize frames are used in computing the frequency data.
+        let (input, padding) = input.split_at_mut(fft_size);
         self.ring_buffer.read(input, fft_size);
 
-        // Apply a Blackman window to the time domain input data.
+        // Apply the window to the time domain input data (Blackman by default).
         input
             .iter_mut()
-            .zip(self.blackman.iter())
+            .zip(self.window.iter())
             .for_each(|(i, b)| *i *= *b);
 
+        // Zero-pad the windowed data, to interpolate the spectrum
+        padding.fill(0.);
+        let input = &mut self.fft_input[..padded_size];
+
         // Apply a Fourier transform to the windowed time domain input data to
         // get real and imaginary frequency data.
         r2c.process_with_scratch(input, output, scratch).unwrap();
@@ -391,12 +628,62 @@ impl Analyser {
         dst.iter_mut()
             .take(len)
             .zip(self.last_fft_output.iter())
-            .for_each(|(v, b)| {
-                let db = 20. * b.log10();
-                // 𝑏[𝑘] = ⌊255 / dB𝑚𝑎𝑥−dB𝑚𝑖𝑛 * (𝑌[𝑘]−dB𝑚𝑖𝑛)⌋
-                let scaled = 255. / (max_decibels - min_decibels) * (db - min_decibels);
-                let clamped = scaled.clamp(0., 255.);
-                *v = clamped as u8;
+            .for_each(|(v, b)| *v = magnitude_to_byte(*b, min_decibels, max_decibels));
+    }
+
+    /// Aggregate the frequency data into `bands` log-spaced bands between `min_frequency` and
+    /// `max_frequency`, scaled between min and max decibels
+    pub fn get_byte_frequency_bands(
+        &mut self,
+        dst: &mut [u8],
+        bands: usize,
+        min_frequency: f32,
+        max_frequency: f32,
+        sample_rate: f32,
+        current_time: f64,
+    ) {
+        assert_valid_frequency_bands(bands, min_frequency, max_frequency);
+
+        let frequency_bin_count = self.frequency_bin_count();
+        let min_decibels = self.min_decibels() as f32;
+        let max_decibels = self.max_decibels() as f32;
+
+        if current_time != self.last_fft_time {
+            self.compute_fft();
+            self.last_fft_time = current_time;
+        }
+
+        let bins = &self.last_fft_output[..frequency_bin_count];
+        let bin_width = sample_rate / (self.fft_size() * self.zero_padding()) as f32;
+        let ratio = (max_frequency / min_frequency).powf(1. / bands as f32);
+
+        dst.iter_mut()
+            .take(bands)
+            .enumerate()
+            .for_each(|(index, v)| {
+                let low = min_frequency * ratio.powi(index as i32);
+                let high = low * ratio;
+                // bins whose center frequency is in [low, high[
+                let first = ((low / bin_width).ceil() as usize).min(bins.len());
+                let last = ((high / bin_width).ceil() as usize).min(bins.len());
+
+                let magnitude = if first < last {
+                    // average the power of the bins
+                    let power = bins[first..last].iter().map(|b| b * b).sum::<f32>();
+                    (power / (last - first) as f32).sqrt()
+                } else {
+                    // the band is narrower than a bin, interpolate at its center frequency
+                    let position = (low * high).sqrt() / bin_width;
+                    let bin = position as usize;
+                    if bin + 1 < bins.len() {
+                        let frac = position - bin as f32;
+                        bins[bin] * (1. - frac) + bins[bin + 1] * frac
+                    } else {
+                        0.
+                    }
+                };
+
+                *v = magnitude_to_byte(magnitude, min_decibels, max_decibels);
             });
     }
 }
@@ -436,6 +723,78 @@ mod tests {
         assert_eq!(max_pos, 1024);
     }
 
+    #[test]
+    fn test_window_functions() {
+        let size = 2048;
+        for window_function in [
+            WindowFunction::Blackman,
+            WindowFunction::Hann,
+            WindowFunction::Hamming,
+            WindowFunction::Rectangular,
+            WindowFunction::FlatTop,
+        ] {
+            let values: Vec<f32> = window_function.generate(size).collect();
+            assert_eq!(values.len(), size);
+
+            // symmetric around the center, peak of ~1 at the center
+            assert_float_eq!(values[1], values[size - 1], abs <= 1e-6);
+            assert_float_eq!(values[size / 2], 1., abs <= 1e-3);
+
+            // the correction factors match the actual window values
+            let mean = values.iter().sum::<f32>() / size as f32;
+            let mean_square = values.iter().map(|v| v * v).sum::<f32>() / size as f32;
+            assert_float_eq!(
+                window_function.amplitude_correction(),
+                1. / mean,
+                rmax <= 1e-4
+            );
+            assert_float_eq!(
+                window_function.energy_correction(),
+                1. / mean_square.sqrt(),
+                rmax <= 1e-4
+            );
+        }
+
+        assert_float_eq!(
+            WindowFunction::Rectangular.equivalent_noise_bandwidth(),
+            1.,
+            abs <= 1e-6
+        );
+        assert_float_eq!(
+            WindowFunction::Hann.equivalent_noise_bandwidth(),
+            1.5,
+            abs <= 1e-6
+        );
+    }
+
+    #[test]
+    fn test_flat_top_amplitude() {
+        // a sinusoid between two bins is measured accurately with the flat-top window
+        let sample_rate = 44100.;
+        let fft_size = 1024;
+        let freq = 10.5 * sample_rate / fft_size as f32;
+
+        let mut analyser = Analyser::new();
+        analyser.set_fft_size(fft_size);
+        analyser.set_smoothing_time_constant(0.);
+        analyser.set_window_function(WindowFunction::FlatTop);
+        assert_eq!(analyser.window_function(), WindowFunction::FlatTop);
+
+        let signal: Vec<f32> = (0..fft_size)
+            .map(|i| (2. * PI * freq * i as f32 / sample_rate).sin())
+            .collect();
+        analyser.get_ring_buffer_clone().write(&signal);
+
+        let mut bins = vec![0.; analyser.frequency_bin_count()];
+        analyser.get_float_frequency_data(&mut bins, 0.);
+
+        // the normalized magnitude of a sine of amplitude 1 is 0.5 times the coherent gain
+        let correction = WindowFunction::FlatTop.amplitude_correction();
+        let peak = bins.iter().fold(f32::MIN, |max, &v| max.max(v));
+        let amplitude = 2. * 10_f32.powf(peak / 20.) * correction;
+        assert_float_eq!(amplitude, 1., abs <= 0.01);
+    }
+
     #[test]
     fn test_ring_buffer_write_simple() {
         let ring_buffer = AnalyserRingBuffer::new();
@@ -557,6 +916,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ring_buffer_copy_from() {
+        let ring_buffer = AnalyserRingBuffer::new();
+        let data: Vec<f32> = (0..RING_BUFFER_SIZE + 100).map(|i| i as f32).collect();
+        ring_buffer.write(&data);
+
+        // the frames copied by the node are replaced by the ones of the renderer
+        let grown = AnalyserRingBuffer::with_len(RING_BUFFER_SIZE * 2);
+        grown.write(&[-1.; 1000]);
+        grown.copy_from(&ring_buffer);
+
+        let mut read_buffer = vec![0.; ring_buffer.capacity()];
+        grown.read(&mut read_buffer, usize::MAX);
+        let expected = &data[data.len() - ring_buffer.capacity()..];
+        assert_eq!(&read_buffer[..], expected);
+
+        // subsequent frames follow the copied ones
+        grown.write(&[-2.; RENDER_QUANTUM_SIZE]);
+        let mut read_buffer = vec![0.; RENDER_QUANTUM_SIZE + 1];
+        grown.read(&mut read_buffer, usize::MAX);
+        assert_eq!(read_buffer[0], *data.last().unwrap());
+        assert!(read_buffer[1..].iter().all(|v| *v == -2.));
+    }
+
     #[test]
     fn test_ring_buffer_read_unwrap() {
         // check values are read from right place
@@ -761,6 +1144,121 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_byte_frequency_bands() {
+        let sample_rate = 48000.;
+        let fft_size = 4096;
+        // third-octave bands from ~28 Hz to ~28 kHz, 1 kHz being the center of the 16th band
+        let bands = 30;
+        let min_frequency = 1000. / 2_f32.powf(15.5 / 3.);
+        let max_frequency = min_frequency * 2_f32.powi(10);
+
+        let mut analyser = Analyser::new();
+        analyser.set_fft_size(fft_size);
+        analyser.set_smoothing_time_constant(0.);
+
+        // sine at 1 kHz
+        let signal: Vec<f32> = (0..fft_size)
+            .map(|i| (2. * PI * 1000. * i as f32 / sample_rate).sin())
+            .collect();
+        analyser.get_ring_buffer_clone().write(&signal);
+
+        let mut dst = [0; 32];
+        analyser.get_byte_frequency_bands(
+            &mut dst,
+            bands,
+            min_frequency,
+            max_frequency,
+            sample_rate,
+            0.,
+        );
+
+        let loudest = (0..bands).max_by_key(|&i| dst[i]).unwrap();
+        assert_eq!(loudest, 15);
+        assert!(dst[15] > 200);
+        // far away bands are silent, including the narrow low frequency bands
+        assert_eq!(dst[0], 0);
+        assert_eq!(dst[bands - 1], 0);
+        // excess elements are left untouched
+        assert_eq!(dst[bands..], [0, 0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_byte_frequency_bands_invalid_range() {
+        let mut analyser = Analyser::new();
+        let mut dst = [0; 8];
+        analyser.get_byte_frequency_bands(&mut dst, 8, 1000., 100., 48000., 0.);
+    }
+
+    #[test]
+    fn test_large_fft_size() {
+        let sample_rate = 48000.;
+        let fft_size = MAX_FFT_SIZE;
+        // ~0.37 Hz resolution, sine centered on bin 100
+        let freq = 100. * sample_rate / fft_size as f32;
+
+        let mut analyser = Analyser::new();
+        let ring_buffer = analyser.get_ring_buffer_clone();
+        ring_buffer.write(&[1.; RENDER_QUANTUM_SIZE]);
+
+        analyser.set_fft_size(fft_size);
+        assert_eq!(analyser.fft_size(), fft_size);
+        assert_eq!(analyser.frequency_bin_count(), fft_size / 2);
+
+        // the ring buffer has grown and kept the most recent frames
+        let ring_buffer = analyser.get_ring_buffer_clone();
+        assert!(ring_buffer.capacity() >= fft_size);
+        let mut recent = [0.; RENDER_QUANTUM_SIZE];
+        analyser.get_float_time_domain_data(&mut recent);
+        assert_float_eq!(recent[..], [1.; RENDER_QUANTUM_SIZE][..], abs_all <= 0.);
+
+        let signal: Vec<f32> = (0..fft_size)
+            .map(|i| (2. * PI * freq * i as f32 / sample_rate).sin())
+            .collect();
+        ring_buffer.write(&signal);
+
+        let mut bins = vec![0.; analyser.frequency_bin_count()];
+        analyser.get_float_frequency_data(&mut bins, 0.);
+        let peak = (0..bins.len())
+            .max_by(|&a, &b| bins[a].total_cmp(&bins[b]))
+            .unwrap();
+        assert_eq!(peak, 100);
+    }
+
+    #[test]
+    fn test_zero_padding() {
+        let sample_rate = 44100.;
+        let fft_size = 256;
+        // between bins 10 and 11 of the unpadded spectrum
+        let freq = 10.5 * sample_rate / fft_size as f32;
+
+        let mut analyser = Analyser::new();
+        analyser.set_fft_size(fft_size);
+        analyser.set_zero_padding(4);
+        assert_eq!(analyser.zero_padding(), 4);
+        assert_eq!(analyser.frequency_bin_count(), fft_size * 2);
+
+        let signal: Vec<f32> = (0..fft_size)
+            .map(|i| (2. * PI * freq * i as f32 / sample_rate).sin())
+            .collect();
+        analyser.get_ring_buffer_clone().write(&signal);
+
+        let mut bins = vec![0.; analyser.frequency_bin_count()];
+        analyser.get_float_frequency_data(&mut bins, 0.);
+        let peak = (0..bins.len())
+            .max_by(|&a, &b| bins[a].total_cmp(&bins[b]))
+            .unwrap();
+        assert_eq!(peak, 42);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_zero_padding() {
+        let mut analyser = Analyser::new();
+        analyser.set_zero_padding(3);
+    }
+
     #[test]
     fn test_get_float_frequency_data_vs_frequenc_bin_count() {
         let mut analyser = Analyser::new();
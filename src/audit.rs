@@ -0,0 +1,202 @@
+//! Audit of the real-time safety of the audio processors, enabled with the `rt-audit` feature
+//!
+//! Allocating or deallocating memory while rendering, e.g. in
+//! [`AudioWorkletProcessor::process`](crate::worklet::AudioWorkletProcessor::process), may block
+//! the render thread and cause glitches. Install the [`AuditAllocator`] as the global
+//! allocator of your application to detect these: every (de)allocation happening while a
+//! processor renders a quantum is counted, and is reported with the name of the processor and
+//! the id of its node after the quantum.
+//!
+//! By default the offending processor panics, so the node is muted and a
+//! `processorerror` event is dispatched to it, see
+//! [`AudioNode::set_onprocessorerror`](crate::node::AudioNode::set_onprocessorerror). Use
+//! [`set_audit_mode`] to only log the violations instead.
+//!
+//! ```no_run
+//! use web_audio_api::audit::AuditAllocator;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: AuditAllocator = AuditAllocator::new(std::alloc::System);
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+thread_local! {
+    /// Indicates if the current thread is running an audited `process` call
+    static ARMED: Cell<bool> = const { Cell::new(false) };
+    /// Number of (de)allocations since the audit was armed
+    static VIOLATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+static AUDIT_MODE: AtomicU8 = AtomicU8::new(AuditMode::Panic as u8);
+
+/// Reaction to an allocation in the render thread
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AuditMode {
+    /// Panic in the offending processor, which removes the node from the graph
+    #[default]
+    Panic,
+    /// Log the violation as an error and keep rendering
+    Log,
+}
+
+impl From<u8> for AuditMode {
+    fn from(i: u8) -> Self {
+        match i {
+            0 => AuditMode::Panic,
+            1 => AuditMode::Log,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Set how allocations in the render thread are reported, for all audio contexts
+pub fn set_audit_mode(mode: AuditMode) {
+    AUDIT_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// How allocations in the render thread are reported
+pub fn audit_mode() -> AuditMode {
+    AUDIT_MODE.load(Ordering::Relaxed).into()
+}
+
+/// Global allocator wrapper counting the (de)allocations of the audited `process` calls
+///
+/// All the other allocations are forwarded to the inner allocator without overhead besides a
+/// thread local lookup.
+#[derive(Debug, Default)]
+pub struct AuditAllocator<A = System> {
+    inner: A,
+}
+
+impl<A> AuditAllocator<A> {
+    /// Wrap the given allocator, typically [`System`]
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+#[inline(always)]
+fn record() {
+    // `try_with` fails while the thread local storage is being torn down, no processor runs then
+    let _ = ARMED.try_with(|armed| {
+        if armed.get() {
+            let _ = VIOLATIONS.try_with(|v| v.set(v.get() + 1));
+        }
+    });
+}
+
+// SAFETY: all calls are forwarded to the inner allocator, only thread local counters of Copy
+// types without destructors are updated besides
+unsafe impl<A: GlobalAlloc> GlobalAlloc for AuditAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record();
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record();
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record();
+        self.inner.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record();
+        self.inner.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Audit in progress on the render thread, disarmed when dropped (also when the processor panics)
+pub(crate) struct AuditGuard(());
+
+impl Drop for AuditGuard {
+    fn drop(&mut self) {
+        ARMED.with(|armed| armed.set(false));
+    }
+}
+
+/// Start counting the (de)allocations of the current thread
+pub(crate) fn arm() -> AuditGuard {
+    VIOLATIONS.with(|v| v.set(0));
+    ARMED.with(|armed| armed.set(true));
+    AuditGuard(())
+}
+
+/// Stop counting and report the (de)allocations made since the audit was armed, the label of
+/// the node is only looked up when reporting
+///
+/// # Panics
+///
+/// Panics in [`AuditMode::Panic`] if any (de)allocation was made
+pub(crate) fn check(
+    guard: AuditGuard,
+    processor: &str,
+    node_id: u64,
+    label: impl FnOnce() -> Option<Arc<String>>,
+) {
+    drop(guard);
+
+    let count = VIOLATIONS.with(Cell::get);
+    if count == 0 {
+        return;
+    }
+
+    let node = match label() {
+        Some(label) => format!("node {} '{}'", node_id, label),
+        None => format!("node {}", node_id),
+    };
+    let message = format!(
+        "Real-time safety violated by {} ({}): {} (de)allocations in process()",
+        processor, node, count
+    );
+    match audit_mode() {
+        AuditMode::Panic => panic!("{}", message),
+        AuditMode::Log => log::error!("{}", message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_armed_allocations() {
+        let allocator = AuditAllocator::new(System);
+        let layout = Layout::from_size_align(64, 8).unwrap();
+
+        // not armed, not counted
+        unsafe { allocator.dealloc(allocator.alloc(layout), layout) };
+        let guard = arm();
+        check(guard, "Processor", 1, || None);
+
+        let guard = arm();
+        unsafe { allocator.dealloc(allocator.alloc_zeroed(layout), layout) };
+        let label = || Some(Arc::new(String::from("lead")));
+        let result = std::panic::catch_unwind(|| check(guard, "Processor", 1, label));
+        let error = result.unwrap_err();
+        let message = error.downcast_ref::<String>().unwrap();
+        assert!(message.contains("Processor (node 1 'lead'): 2 (de)allocations"));
+
+        // disarmed by the check
+        unsafe { allocator.dealloc(allocator.alloc(layout), layout) };
+        assert_eq!(VIOLATIONS.with(Cell::get), 2);
+    }
+
+    #[test]
+    fn test_disarmed_on_panic() {
+        let result = std::panic::catch_unwind(|| {
+            let _guard = arm();
+            panic!("process failed");
+        });
+        assert!(result.is_err());
+        assert!(!ARMED.with(Cell::get));
+    }
+}
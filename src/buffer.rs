@@ -1,6 +1,8 @@
 //! General purpose audio signal data structures
+use std::error::Error;
 use std::sync::Arc;
 
+use crate::media_streams::ResampleQuality;
 use crate::{
     assert_valid_buffer_length, assert_valid_channel_number, assert_valid_number_of_channels,
     assert_valid_sample_rate,
@@ -213,6 +215,69 @@ impl AudioBuffer {
         channel[offset..(max_frame + offset)].copy_from_slice(&source[..max_frame]);
     }
 
+    /// Copy data from the given channel, starting at frame `start_in_channel`, to the
+    /// destination, returning an error instead of panicking
+    ///
+    /// Like the JS API, the number of frames copied is the minimum of the number of frames left
+    /// in the channel and the length of the destination, possibly zero. The remaining elements
+    /// of the destination are not modified.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the given channel number is greater than or equal to the
+    /// number of channels
+    pub fn try_copy_from_channel(
+        &self,
+        destination: &mut [f32],
+        channel_number: usize,
+        start_in_channel: usize,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.check_channel_number(channel_number)?;
+        self.copy_from_channel_with_offset(destination, channel_number, start_in_channel);
+        Ok(())
+    }
+
+    /// Copy data from the source to the given channel, starting at frame `start_in_channel`,
+    /// returning an error instead of panicking
+    ///
+    /// Like the JS API, the number of frames copied is the minimum of the number of frames left
+    /// in the channel and the length of the source, possibly zero. The remaining frames of the
+    /// channel are not modified.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the given channel number is greater than or equal to the
+    /// number of channels
+    pub fn try_copy_to_channel(
+        &mut self,
+        source: &[f32],
+        channel_number: usize,
+        start_in_channel: usize,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.check_channel_number(channel_number)?;
+        self.copy_to_channel_with_offset(source, channel_number, start_in_channel);
+        Ok(())
+    }
+
+    fn check_channel_number(
+        &self,
+        channel_number: usize,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if channel_number >= self.number_of_channels() {
+            return Err(format!(
+                "IndexSizeError - Invalid channel number {:?} (number of channels: {:?})",
+                channel_number,
+                self.number_of_channels()
+            )
+            .into());
+        }
+        Ok(())
+    }
+
     /// Return a read-only copy of the underlying data of the channel
     ///
     /// # Panics
@@ -237,6 +302,203 @@ impl AudioBuffer {
         self.channel_data_mut(channel_number).as_mut_slice()
     }
 
+    /// Remove the leading sample frames that are silent on all channels
+    ///
+    /// A sample frame is considered silent when the absolute values of all its samples are lower
+    /// than or equal to `threshold`. This is typically used to remove the pre-delay of a recorded
+    /// impulse response. If the whole buffer is silent, its length will be zero afterwards.
+    pub fn trim_leading_silence(&mut self, threshold: f32) {
+        let length = self.length();
+        let start = (0..length)
+            .find(|&i| self.channels.iter().any(|c| c.data[i].abs() > threshold))
+            .unwrap_or(length);
+
+        if start == 0 {
+            return;
+        }
+
+        self.channels.iter_mut().for_each(|channel| {
+            channel.data = Arc::from(&channel.data[start..]);
+        });
+    }
+
+    /// Scale this buffer by the equal-power normalization that the
+    /// [`ConvolverNode`](crate::node::ConvolverNode) applies when its `normalize` attribute is
+    /// set, multiplied by `gain`.
+    ///
+    /// Baking the normalization into the impulse response allows for applying a custom gain on
+    /// top of it. Make sure to disable the normalization of the `ConvolverNode` afterwards.
+    ///
+    /// - see <https://webaudio.github.io/web-audio-api/#dom-convolvernode-normalize>
+    pub fn normalize_impulse_response(&mut self, gain: f32) {
+        let scale = crate::node::normalize_buffer(self) * gain;
+
+        self.channels.iter_mut().for_each(|channel| {
+            channel.as_mut_slice().iter_mut().for_each(|s| *s *= scale);
+        });
+    }
+
+    /// Concatenate the given buffers into a new buffer
+    ///
+    /// Returns `None` if no buffers are given.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the buffers do not have the same sample rate and number of
+    /// channels
+    pub fn concat(buffers: &[&Self]) -> Option<Self> {
+        let first = buffers.first()?;
+        buffers.iter().for_each(|buffer| {
+            assert!(
+                buffer.sample_rate == first.sample_rate
+                    && buffer.number_of_channels() == first.number_of_channels(),
+                "NotSupportedError - cannot concatenate buffers of different sample rates or number of channels"
+            );
+        });
+
+        let channels = (0..first.number_of_channels())
+            .map(|channel| {
+                let data = buffers
+                    .iter()
+                    .flat_map(|buffer| buffer.channels[channel].as_slice())
+                    .copied()
+                    .collect();
+                ChannelData { data }
+            })
+            .collect();
+
+        Some(AudioBuffer::from_channels(channels, first.sample_rate))
+    }
+
+    /// Copy the sample frames from `start` (inclusive) to `end` (exclusive) into a new buffer
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `start` is greater than `end` or `end` is greater than the
+    /// length of the buffer
+    pub fn slice(&self, start: usize, end: usize) -> Self {
+        assert!(
+            start <= end && end <= self.length(),
+            "IndexSizeError - Invalid slice {}..{} of buffer of length {}",
+            start,
+            end,
+            self.length()
+        );
+
+        let channels = self
+            .channels
+            .iter()
+            .map(|channel| ChannelData {
+                data: Arc::from(&channel.as_slice()[start..end]),
+            })
+            .collect();
+
+        AudioBuffer::from_channels(channels, self.sample_rate)
+    }
+
+    /// Apply a linear fade in over the given duration in seconds, from the start of the buffer
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn fade_in(&mut self, duration: f64) {
+        let frames = self.duration_to_frames(duration);
+        self.channels.iter_mut().for_each(|channel| {
+            channel.as_mut_slice()[..frames]
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, s)| *s *= i as f32 / frames as f32);
+        });
+    }
+
+    /// Apply a linear fade out over the given duration in seconds, to the end of the buffer
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn fade_out(&mut self, duration: f64) {
+        let frames = self.duration_to_frames(duration);
+        let start = self.length() - frames;
+        self.channels.iter_mut().for_each(|channel| {
+            channel.as_mut_slice()[start..]
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, s)| *s *= (frames - 1 - i) as f32 / frames as f32);
+        });
+    }
+
+    /// Number of sample frames in the given duration, clamped to the length of the buffer
+    fn duration_to_frames(&self, duration: f64) -> usize {
+        let frames = (duration.max(0.) * self.sample_rate as f64).round() as usize;
+        frames.min(self.length())
+    }
+
+    /// Reverse the sample frames of the buffer
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn reverse(&mut self) {
+        self.channels
+            .iter_mut()
+            .for_each(|channel| channel.as_mut_slice().reverse());
+    }
+
+    /// Scale the buffer so that its peak amplitude, over all channels, equals the given level in
+    /// dBFS
+    ///
+    /// A silent buffer is left untouched.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn normalize(&mut self, db: f32) {
+        let peak = self
+            .channels
+            .iter()
+            .flat_map(|channel| channel.as_slice())
+            .fold(0_f32, |peak, s| peak.max(s.abs()));
+
+        if peak == 0. {
+            return;
+        }
+
+        let scale = 10_f32.powf(db / 20.) / peak;
+        self.channels.iter_mut().for_each(|channel| {
+            channel.as_mut_slice().iter_mut().for_each(|s| *s *= scale);
+        });
+    }
+
+    /// Add the samples of the other buffer, multiplied by `gain`, to this buffer
+    ///
+    /// The channels and sample frames that are not present in both buffers are left untouched.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the buffers do not have the same sample rate
+    pub fn mix(&mut self, other: &Self, gain: f32) {
+        assert!(
+            self.sample_rate == other.sample_rate,
+            "NotSupportedError - cannot mix buffers of different sample rates"
+        );
+
+        self.channels
+            .iter_mut()
+            .zip(other.channels.iter())
+            .for_each(|(channel, other)| {
+                channel
+                    .as_mut_slice()
+                    .iter_mut()
+                    .zip(other.as_slice())
+                    .for_each(|(s, o)| *s += o * gain);
+            });
+    }
+
+    /// Returns true if the sample data of this buffer is shared with other buffers
+    pub(crate) fn is_shared(&self) -> bool {
+        self.channels
+            .iter()
+            .any(|channel| Arc::strong_count(&channel.data) > 1)
+    }
+
     /// Create a multi-channel audiobuffer directly from `ChannelData`s.
     // @todo - remove in favor of `AudioBuffer::from`
     pub(crate) fn from_channels(channels: Vec<ChannelData>, sample_rate: f32) -> Self {
@@ -271,44 +533,23 @@ impl AudioBuffer {
         &mut self.channels[index]
     }
 
-    /// Extends an AudioBuffer with the contents of another.
+    /// Resample to the desired sample rate, with the given quality
     ///
-    /// This function will panic if the sample_rate and channel_count are not equal
-    pub(crate) fn extend(&mut self, other: &Self) {
-        assert_eq!(self.sample_rate, other.sample_rate);
-        assert_eq!(self.number_of_channels(), other.number_of_channels());
-
-        let data = self.channels_mut();
-        data.iter_mut()
-            .zip(other.channels.iter())
-            .for_each(|(channel, other_channel)| {
-                let cur_channel_data = Arc::make_mut(&mut channel.data);
-                cur_channel_data.extend(other_channel.as_slice());
-            })
-    }
-
-    /// Split an AudioBuffer in two at the given index.
-    pub(crate) fn split_off(&mut self, index: usize) -> Self {
-        let channels: Vec<_> = self
-            .channels_mut()
-            .iter_mut()
-            .map(|channel_data| Arc::make_mut(&mut channel_data.data).split_off(index))
-            .map(ChannelData::from)
-            .collect();
-
-        AudioBuffer::from_channels(channels, self.sample_rate)
-    }
-
-    /// Resample to the desired sample rate. The method performs a simple linear
-    /// interpolation an keep the first and last sample intact. The new number
-    /// of samples is always ceiled according the ratio defined by old and new
-    /// sample rates.
+    /// The new number of samples is always ceiled according the ratio defined by old and new
+    /// sample rates. With [`ResampleQuality::Linear`], the first and last sample are kept intact.
+    ///
+    /// Resampling a long buffer, especially with the windowed sinc interpolation of
+    /// [`ResampleQuality::Medium`] and [`ResampleQuality::High`], is expensive. This
+    /// method is meant to be called on the control thread or a worker thread, never in the render
+    /// thread, so you decide when the cost is paid.
+    ///
+    /// This method is not part of the Web Audio API specification.
     ///
     /// # Panics
     ///
     /// This function will panic if:
     /// - the given sample rate is zero
-    pub(crate) fn resample(&mut self, sample_rate: f32) {
+    pub fn resample(&mut self, sample_rate: f32, quality: ResampleQuality) {
         assert_valid_sample_rate(sample_rate);
 
         // if requested sample rate is very similar, do not resample
@@ -326,9 +567,26 @@ impl AudioBuffer {
         let source_sr = self.sample_rate as f64;
         let target_sr = sample_rate as f64;
         let ratio = target_sr / source_sr;
-        let source_length = self.length();
         let target_length = (self.length() as f64 * ratio).ceil() as usize;
 
+        if quality != ResampleQuality::Linear {
+            *self = crate::resampling::resample_buffer(self, sample_rate, quality, target_length);
+            return;
+        }
+
+        let resampled = self.resample_linear(target_length);
+        self.channels
+            .iter_mut()
+            .zip(resampled)
+            .for_each(|(channel_data, resampled_data)| {
+                channel_data.data = Arc::from(resampled_data);
+            });
+
+        self.sample_rate = sample_rate;
+    }
+
+    fn resample_linear(&self, target_length: usize) -> Vec<Vec<f32>> {
+        let source_length = self.length();
         let num_channels = self.number_of_channels();
         let mut resampled = Vec::<Vec<f32>>::with_capacity(num_channels);
         resampled.resize_with(num_channels, || Vec::<f32>::with_capacity(target_length));
@@ -352,23 +610,36 @@ impl AudioBuffer {
             }
         }
 
-        self.channels
-            .iter_mut()
-            .zip(resampled)
-            .for_each(|(channel_data, resampled_data)| {
-                channel_data.data = Arc::new(resampled_data);
-            });
+        resampled
+    }
+}
 
-        self.sample_rate = sample_rate;
+/// Options for decoding an [`AudioBuffer`], see
+/// [`BaseAudioContext::decode_audio_data_sync_with_options`](crate::context::BaseAudioContext::decode_audio_data_sync_with_options)
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Quality of the conversion to the sample rate of the context, or `None` to keep the sample
+    /// rate of the input and resample it later with [`AudioBuffer::resample`]
+    pub resample: Option<ResampleQuality>,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            resample: Some(ResampleQuality::Linear),
+        }
     }
 }
 
-/// Single channel audio samples, basically wraps a `Arc<Vec<f32>>`
+/// Single channel audio samples, basically wraps a `Arc<[f32]>`
 ///
-/// ChannelData has copy-on-write semantics, so it is cheap to clone.
+/// ChannelData has copy-on-write semantics, so it is cheap to clone: all clones share a single
+/// allocation until one of them is mutated.
 #[derive(Clone, PartialEq)]
 pub(crate) struct ChannelData {
-    data: Arc<Vec<f32>>,
+    data: Arc<[f32]>,
 }
 
 impl std::fmt::Debug for ChannelData {
@@ -381,15 +652,14 @@ impl std::fmt::Debug for ChannelData {
 
 impl ChannelData {
     pub fn new(length: usize) -> Self {
-        let buffer = vec![0.; length];
-        let data = Arc::new(buffer);
+        let data = std::iter::repeat(0.).take(length).collect();
 
         Self { data }
     }
 
     pub fn from(data: Vec<f32>) -> Self {
         Self {
-            data: Arc::new(data),
+            data: Arc::from(data),
         }
     }
 
@@ -408,7 +678,11 @@ impl ChannelData {
     }
 
     pub fn as_mut_slice(&mut self) -> &mut [f32] {
-        &mut Arc::make_mut(&mut self.data)[..]
+        // copy on write, `Arc::make_mut` is not available for slices on our MSRV
+        if Arc::get_mut(&mut self.data).is_none() {
+            self.data = Arc::from(&self.data[..]);
+        }
+        Arc::get_mut(&mut self.data).unwrap()
     }
 }
 
@@ -567,6 +841,33 @@ mod tests {
         assert_float_eq!(dest[..], vec![1.; 10][..], abs_all <= 0.);
     }
 
+    #[test]
+    fn test_try_copy_channel() {
+        let mut buffer = AudioBuffer::from(vec![vec![1., 2., 3., 4.]], 48000.);
+
+        // partial copy at the end of the channel
+        let mut dest = [0.; 3];
+        buffer.try_copy_from_channel(&mut dest, 0, 2).unwrap();
+        assert_float_eq!(dest, [3., 4., 0.], abs_all <= 0.);
+
+        // offset beyond the channel, nothing is copied
+        let mut dest = [9.; 2];
+        buffer.try_copy_from_channel(&mut dest, 0, 10).unwrap();
+        assert_float_eq!(dest, [9., 9.], abs_all <= 0.);
+
+        buffer.try_copy_to_channel(&[7., 8., 9.], 0, 3).unwrap();
+        assert_float_eq!(
+            buffer.get_channel_data(0),
+            &[1., 2., 3., 7.][..],
+            abs_all <= 0.
+        );
+
+        let error = buffer.try_copy_to_channel(&[1.], 1, 0).unwrap_err();
+        assert!(error.to_string().starts_with("IndexSizeError"));
+        let error = buffer.try_copy_from_channel(&mut dest, 1, 0).unwrap_err();
+        assert!(error.to_string().starts_with("IndexSizeError"));
+    }
+
     #[test]
     #[should_panic]
     fn test_invalid_copy_to_channel() {
@@ -691,9 +992,9 @@ mod tests {
             length: 5,
             sample_rate: 44100.,
         };
-        let mut b1 = AudioBuffer::new(options.clone());
+        let b1 = AudioBuffer::new(options.clone());
         let b2 = AudioBuffer::new(options);
-        b1.extend(&b2);
+        let b1 = AudioBuffer::concat(&[&b1, &b2]).unwrap();
 
         assert_eq!(b1.length(), 10);
         assert_eq!(b1.number_of_channels(), 2);
@@ -702,7 +1003,7 @@ mod tests {
         let channel_data = ChannelData::from(vec![1.; 5]);
         let b3 = AudioBuffer::from_channels(vec![channel_data; 2], 44100.);
 
-        b1.extend(&b3);
+        let b1 = AudioBuffer::concat(&[&b1, &b3]).unwrap();
 
         assert_eq!(b1.length(), 15);
         assert_eq!(b1.number_of_channels(), 2);
@@ -714,19 +1015,161 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_shared_channel_data() {
+        let sample = AudioBuffer::from(vec![vec![1.; 64]; 2], 48000.);
+        let mut clones = vec![sample.clone(); 256];
+        assert!(sample.is_shared());
+        assert!(clones.iter().all(|clone| {
+            Arc::ptr_eq(&clone.channels[0].data, &sample.channels[0].data)
+                && Arc::ptr_eq(&clone.channels[1].data, &sample.channels[1].data)
+        }));
+
+        // copy on write, only the mutated channel is copied
+        clones[0].get_channel_data_mut(0)[0] = 0.;
+        assert!(!Arc::ptr_eq(
+            &clones[0].channels[0].data,
+            &sample.channels[0].data
+        ));
+        assert!(Arc::ptr_eq(
+            &clones[0].channels[1].data,
+            &sample.channels[1].data
+        ));
+        assert_float_eq!(sample.get_channel_data(0)[0], 1., abs <= 0.);
+        assert_float_eq!(clones[0].get_channel_data(0)[0], 0., abs <= 0.);
+
+        // a buffer that is no longer shared is mutated in place
+        drop(clones);
+        let mut sample = sample;
+        let ptr = sample.get_channel_data(0).as_ptr();
+        sample.get_channel_data_mut(0)[0] = 0.5;
+        assert_eq!(sample.get_channel_data(0).as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_slice_concat() {
+        let buffer = AudioBuffer::from(vec![vec![1., 2., 3., 4., 5.]], 48000.);
+        let tail = buffer.slice(2, 5);
+        let buffer = buffer.slice(0, 2);
+        assert_float_eq!(buffer.get_channel_data(0), &[1., 2.][..], abs_all <= 0.);
+        assert_float_eq!(tail.get_channel_data(0), &[3., 4., 5.][..], abs_all <= 0.);
+
+        let joined = AudioBuffer::concat(&[&buffer, &tail]).unwrap();
+        assert_float_eq!(
+            joined.get_channel_data(0),
+            &[1., 2., 3., 4., 5.][..],
+            abs_all <= 0.
+        );
+        assert!(AudioBuffer::concat(&[]).is_none());
+    }
+
+    #[test]
+    fn test_trim_leading_silence() {
+        let mut buffer = AudioBuffer::from(
+            vec![vec![0., 0.001, 0., 0.5, 0.], vec![0., 0., -0.2, 0., 0.]],
+            48000.,
+        );
+        buffer.trim_leading_silence(0.01);
+
+        assert_eq!(buffer.length(), 3);
+        assert_float_eq!(
+            buffer.get_channel_data(0)[..],
+            [0., 0.5, 0.][..],
+            abs_all <= 0.
+        );
+        assert_float_eq!(
+            buffer.get_channel_data(1)[..],
+            [-0.2, 0., 0.][..],
+            abs_all <= 0.
+        );
+
+        // fully silent buffer
+        let mut buffer = AudioBuffer::from(vec![vec![0.; 10]], 48000.);
+        buffer.trim_leading_silence(0.);
+        assert_eq!(buffer.length(), 0);
+    }
+
+    #[test]
+    fn test_editing() {
+        let mut buffer = AudioBuffer::from(vec![vec![1., 2., 3., 4.], vec![-1.; 4]], 4.);
+
+        let slice = buffer.slice(1, 3);
+        assert_float_eq!(slice.get_channel_data(0), &[2., 3.][..], abs_all <= 0.);
+        assert_float_eq!(slice.get_channel_data(1), &[-1., -1.][..], abs_all <= 0.);
+        assert_eq!(buffer.slice(2, 2).length(), 0);
+
+        let joined = AudioBuffer::concat(&[&slice, &buffer]).unwrap();
+        assert_float_eq!(
+            joined.get_channel_data(0),
+            &[2., 3., 1., 2., 3., 4.][..],
+            abs_all <= 0.
+        );
+
+        buffer.reverse();
+        assert_float_eq!(
+            buffer.get_channel_data(0),
+            &[4., 3., 2., 1.][..],
+            abs_all <= 0.
+        );
+
+        buffer.normalize(-6.);
+        let peak = 10_f32.powf(-6. / 20.);
+        assert_float_eq!(buffer.get_channel_data(0)[0], peak, abs <= 1e-6);
+        assert_float_eq!(buffer.get_channel_data(1)[0], -peak / 4., abs <= 1e-6);
+
+        let mut buffer = AudioBuffer::from(vec![vec![1.; 4]], 4.);
+        buffer.fade_in(0.5); // 2 frames
+        buffer.fade_out(10.); // clamped to the whole buffer
+        assert_float_eq!(
+            buffer.get_channel_data(0),
+            &[0., 0.5 * 0.5, 0.25, 0.][..],
+            abs_all <= 1e-6
+        );
+
+        let mut buffer = AudioBuffer::from(vec![vec![1.; 4]], 4.);
+        let other = AudioBuffer::from(vec![vec![1., 2.], vec![5., 5.]], 4.);
+        buffer.mix(&other, 0.5);
+        assert_float_eq!(
+            buffer.get_channel_data(0),
+            &[1.5, 2., 1., 1.][..],
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_slice() {
+        let buffer = AudioBuffer::from(vec![vec![0.; 4]], 4.);
+        buffer.slice(2, 5);
+    }
+
+    #[test]
+    fn test_normalize_impulse_response() {
+        let mut buffer = AudioBuffer::from(vec![vec![0., 1., 0., -1.]], 44100.);
+        buffer.normalize_impulse_response(2.);
+
+        // rms power is sqrt(0.5), calibration gain is 0.00125 at 44100 Hz
+        let expected = 2. * 0.00125 / 0.5_f32.sqrt();
+        assert_float_eq!(
+            buffer.get_channel_data(0)[..],
+            [0., expected, 0., -expected][..],
+            abs_all <= 1e-7
+        );
+    }
+
     #[test]
     #[should_panic]
     fn test_resample_to_zero_hertz() {
         let channel = ChannelData::from(vec![1., 2., 3., 4., 5.]);
         let mut buffer = AudioBuffer::from_channels(vec![channel], 48000.);
-        buffer.resample(0.);
+        buffer.resample(0., ResampleQuality::Linear);
     }
 
     #[test]
     fn test_resample_from_empty() {
         let channel = ChannelData::from(vec![]);
         let mut buffer = AudioBuffer::from_channels(vec![channel], 48000.);
-        buffer.resample(48000.);
+        buffer.resample(48000., ResampleQuality::Linear);
 
         assert_eq!(buffer.length(), 0);
         assert_float_eq!(buffer.sample_rate, 48000., abs_all <= 0.);
@@ -736,7 +1179,7 @@ mod tests {
     fn test_upsample() {
         let channel = ChannelData::from(vec![1., 2., 3., 4., 5.]);
         let mut buffer = AudioBuffer::from_channels(vec![channel], 48000.);
-        buffer.resample(96000.); // double
+        buffer.resample(96000., ResampleQuality::Linear); // double
 
         let mut expected = [0.; 10];
         let incr = 4. / 9.; // (5 - 1) / (10 - 1)
@@ -758,7 +1201,7 @@ mod tests {
     fn test_downsample() {
         let channel = ChannelData::from(vec![1., 2., 3., 4., 5.]);
         let mut buffer = AudioBuffer::from_channels(vec![channel], 96000.);
-        buffer.resample(48000.); // half
+        buffer.resample(48000., ResampleQuality::Linear); // half
 
         assert_float_eq!(
             buffer.channel_data(0).as_slice(),
@@ -788,7 +1231,7 @@ mod tests {
             let right_chan = ChannelData::from(right);
             let mut buffer =
                 AudioBuffer::from_channels(vec![left_chan, right_chan], source_sr as f32);
-            buffer.resample(target_sr as f32);
+            buffer.resample(target_sr as f32, ResampleQuality::Linear);
 
             let mut expected_left = vec![];
             let mut expected_right = vec![];
@@ -814,4 +1257,36 @@ mod tests {
             assert_float_eq!(buffer.sample_rate, target_sr as f32, abs_all <= 0.);
         });
     }
+
+    #[test]
+    fn test_resample_high_quality() {
+        let sine = |sample_rate: f32, length: usize| {
+            (0..length)
+                .map(|i| (2. * PI * 1000. * i as f32 / sample_rate).sin())
+                .collect::<Vec<_>>()
+        };
+
+        let mut buffer = AudioBuffer::from(vec![sine(44_100., 4410)], 44_100.);
+        buffer.resample(48_000., ResampleQuality::High);
+
+        assert_eq!(buffer.length(), 4800);
+        assert_float_eq!(buffer.sample_rate(), 48_000., abs <= 0.);
+        // skip the edges, where the kernel reaches beyond the input
+        assert_float_eq!(
+            buffer.get_channel_data(0)[100..4700],
+            sine(48_000., 4800)[100..4700],
+            abs_all <= 1e-3
+        );
+
+        // a tone above the new Nyquist frequency is filtered out instead of aliased
+        let tone: Vec<_> = (0..4800)
+            .map(|i| (2. * PI * 18_000. * i as f32 / 48_000.).sin())
+            .collect();
+        let mut buffer = AudioBuffer::from(vec![tone], 48_000.);
+        buffer.resample(22_050., ResampleQuality::High);
+        let peak = buffer.get_channel_data(0)[100..2100]
+            .iter()
+            .fold(0_f32, |m, s| m.max(s.abs()));
+        assert!(peak < 0.01);
+    }
 }
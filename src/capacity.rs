@@ -1,9 +1,10 @@
 use crossbeam_channel::{Receiver, Sender};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext, OfflineAudioContext};
 use crate::events::{EventDispatch, EventHandler, EventPayload, EventType};
-use crate::Event;
+use crate::{Event, RENDER_QUANTUM_SIZE};
 
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct AudioRenderCapacityLoad {
@@ -196,6 +197,102 @@ impl AudioRenderCapacity {
     }
 }
 
+/// Options for [`estimate_render_cost`]
+#[derive(Clone, Debug)]
+pub struct RenderCostOptions {
+    /// The sample rate of the simulated context
+    pub sample_rate: f32,
+    /// The number of output channels of the simulated context
+    pub number_of_channels: usize,
+    /// The number of render quanta to measure, more quanta yield a more stable estimate
+    pub number_of_quanta: usize,
+}
+
+impl Default for RenderCostOptions {
+    fn default() -> Self {
+        Self {
+            sample_rate: 44_100.,
+            number_of_channels: 2,
+            number_of_quanta: 1000,
+        }
+    }
+}
+
+/// Estimated rendering cost of an audio graph, see [`estimate_render_cost`]
+#[derive(Clone, Debug)]
+pub struct RenderCost {
+    /// The average time spent on rendering a single render quantum
+    pub quantum_duration: Duration,
+    /// The average load value, i.e. the fraction of the real-time budget of a render quantum
+    /// consumed by the graph (see [`AudioRenderCapacity`])
+    pub load: f64,
+}
+
+/// Estimate the rendering cost of a node or subgraph before adding it to a live context
+///
+/// The graph is built by the `build` closure in a dedicated [`OfflineAudioContext`], which is
+/// then rendered as fast as possible on the current thread. The cost of rendering an empty
+/// graph is subtracted, so the result reflects the cost of the added nodes. Since the
+/// measurement is performed on the actual hardware, the result can be used to budget audio
+/// complexity per platform ahead of time.
+///
+/// The estimate does not include the overhead of the system-level audio callback, and it will
+/// vary with the load of the system. Note that source nodes only add to the cost once started.
+///
+/// # Panics
+///
+/// This function panics if the options are invalid for an [`OfflineAudioContext`], i.e. if
+/// `number_of_quanta` is zero, or if the sample rate or number of channels are out of range.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::BaseAudioContext;
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::{estimate_render_cost, RenderCostOptions};
+///
+/// let cost = estimate_render_cost(RenderCostOptions::default(), |context| {
+///     for _ in 0..16 {
+///         let mut osc = context.create_oscillator();
+///         osc.connect(&context.destination());
+///         osc.start();
+///     }
+/// });
+///
+/// println!("16 oscillators take {:.1}% of the audio budget", cost.load * 100.);
+/// ```
+pub fn estimate_render_cost<F: FnOnce(&OfflineAudioContext)>(
+    options: RenderCostOptions,
+    build: F,
+) -> RenderCost {
+    let baseline = render_duration(&options, |_| ());
+    let total = render_duration(&options, build);
+
+    let number_of_quanta = options.number_of_quanta as f64;
+    let quantum_duration =
+        Duration::from_secs_f64(total.saturating_sub(baseline).as_secs_f64() / number_of_quanta);
+    let quantum_budget = RENDER_QUANTUM_SIZE as f64 / options.sample_rate as f64;
+
+    RenderCost {
+        quantum_duration,
+        load: quantum_duration.as_secs_f64() / quantum_budget,
+    }
+}
+
+fn render_duration<F: FnOnce(&OfflineAudioContext)>(
+    options: &RenderCostOptions,
+    build: F,
+) -> Duration {
+    let length = options.number_of_quanta * RENDER_QUANTUM_SIZE;
+    let mut context =
+        OfflineAudioContext::new(options.number_of_channels, length, options.sample_rate);
+    build(&context);
+
+    let start = Instant::now();
+    let _ = context.start_rendering_sync();
+    start.elapsed()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -258,4 +355,23 @@ mod tests {
 
         assert_eq!(event.event.type_, "AudioRenderCapacityEvent");
     }
+
+    #[test]
+    fn test_estimate_render_cost() {
+        use crate::node::{AudioNode, AudioScheduledSourceNode};
+
+        let options = RenderCostOptions {
+            number_of_quanta: 100,
+            ..RenderCostOptions::default()
+        };
+
+        let cost = estimate_render_cost(options, |context| {
+            let mut osc = context.create_oscillator();
+            osc.connect(&context.destination());
+            osc.start();
+        });
+
+        assert!(cost.load >= 0.);
+        assert!(cost.load.is_finite());
+    }
 }
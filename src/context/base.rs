@@ -1,19 +1,41 @@
 //! The `BaseAudioContext` interface
 
-use crate::buffer::{AudioBuffer, AudioBufferOptions};
+use crate::buffer::{AudioBuffer, AudioBufferOptions, DecodeOptions};
 use crate::context::{
-    AudioContextRegistration, AudioContextState, AudioParamId, ConcreteBaseAudioContext,
-    DESTINATION_NODE_ID,
+    AudioContextRegistration, AudioContextState, AudioGraph, AudioParamId,
+    ConcreteBaseAudioContext, ControlQueueStats, GraphDescription, DESTINATION_NODE_ID,
+};
+use crate::decoding::{self, AudioMetadata};
+use crate::events::{
+    AudioContextStateChangeEvent, Event, EventHandler, EventKind, EventPayload, EventType,
+    ListenerHandle,
 };
-use crate::decoding::MediaDecoder;
-use crate::events::{Event, EventHandler, EventType};
 use crate::node::{AudioNode, AudioNodeOptions};
 use crate::param::AudioParamDescriptor;
 use crate::periodic_wave::{PeriodicWave, PeriodicWaveOptions};
-use crate::{node, AudioListener};
+#[cfg(feature = "async")]
+use crate::EventFuture;
+use crate::{node, AudioListener, DecodingStream, NodeProfile};
 
+use std::error::Error;
 use std::future::Future;
 
+/// A set of changes applied in the same render quantum, see [`BaseAudioContext::batch`]
+#[derive(Debug)]
+pub struct AudioTransaction {
+    current_time: f64,
+}
+
+impl AudioTransaction {
+    /// Time of the context when the transaction was opened
+    ///
+    /// Unlike [`BaseAudioContext::current_time`], this time does not change while the
+    /// transaction is open.
+    pub fn current_time(&self) -> f64 {
+        self.current_time
+    }
+}
+
 /// The interface representing an audio-processing graph built from audio modules linked together,
 /// each represented by an `AudioNode`.
 ///
@@ -27,7 +49,8 @@ pub trait BaseAudioContext {
 
     /// Decode an [`AudioBuffer`] from a given input stream.
     ///
-    /// The current implementation can decode FLAC, Opus, PCM, Vorbis, and Wav.
+    /// The current implementation can decode AAC, ALAC, FLAC, MP3, PCM, Vorbis, and Wav. Opus
+    /// requires the `opus` feature.
     ///
     /// In addition to the official spec, the input parameter can be any byte stream (not just an
     /// array). This means you can decode audio data from a file, network stream, or in memory
@@ -39,7 +62,8 @@ pub trait BaseAudioContext {
     ///
     /// # Errors
     ///
-    /// This method returns an Error in various cases (IO, mime sniffing, decoding).
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding). Unsupported
+    /// formats and codecs are reported with a [`DecodeError`](crate::DecodeError).
     ///
     /// # Usage
     ///
@@ -67,26 +91,34 @@ pub trait BaseAudioContext {
         &self,
         input: R,
     ) -> Result<AudioBuffer, Box<dyn std::error::Error + Send + Sync>> {
-        // Set up a media decoder, consume the stream in full and construct a single buffer out of it
-        let mut buffer = MediaDecoder::try_new(input)?
-            .collect::<Result<Vec<_>, _>>()?
-            .into_iter()
-            .reduce(|mut accum, item| {
-                accum.extend(&item);
-                accum
-            })
-            // if there are no samples decoded, return an empty buffer
-            .unwrap_or_else(|| AudioBuffer::from(vec![vec![]], self.sample_rate()));
-
-        // resample to desired rate (no-op if already matching)
-        buffer.resample(self.sample_rate());
+        self.decode_audio_data_sync_with_options(input, DecodeOptions::default())
+    }
 
-        Ok(buffer)
+    /// Decode an [`AudioBuffer`] from a given input stream, with the given options
+    ///
+    /// By default, the decoded buffer is converted to the sample rate of the context with a
+    /// linear interpolation, see [`Self::decode_audio_data_sync`]. The options allow for a higher
+    /// quality conversion, or to keep the sample rate of the input and convert it later with
+    /// [`AudioBuffer::resample`].
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding). Unsupported
+    /// formats and codecs are reported with a [`DecodeError`](crate::DecodeError).
+    fn decode_audio_data_sync_with_options<R: std::io::Read + Send + Sync + 'static>(
+        &self,
+        input: R,
+        options: DecodeOptions,
+    ) -> Result<AudioBuffer, Box<dyn std::error::Error + Send + Sync>> {
+        decoding::decode_audio_data(input, self.sample_rate(), options)
     }
 
     /// Decode an [`AudioBuffer`] from a given input stream.
     ///
-    /// The current implementation can decode FLAC, Opus, PCM, Vorbis, and Wav.
+    /// The current implementation can decode AAC, ALAC, FLAC, MP3, PCM, Vorbis, and Wav. Opus
+    /// requires the `opus` feature.
     ///
     /// In addition to the official spec, the input parameter can be any byte stream (not just an
     /// array). This means you can decode audio data from a file, network stream, or in memory
@@ -99,7 +131,8 @@ pub trait BaseAudioContext {
     ///
     /// # Errors
     ///
-    /// This method returns an Error in various cases (IO, mime sniffing, decoding).
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding). Unsupported
+    /// formats and codecs are reported with a [`DecodeError`](crate::DecodeError).
     // Use of `async fn` in public traits is discouraged as auto trait bounds cannot be specified,
     // hence we use `-> impl Future + ..` instead.
     fn decode_audio_data<R: std::io::Read + Send + Sync + 'static>(
@@ -108,23 +141,150 @@ pub trait BaseAudioContext {
     ) -> impl Future<Output = Result<AudioBuffer, Box<dyn std::error::Error + Send + Sync>>>
            + Send
            + 'static {
+        self.decode_audio_data_with_options(input, DecodeOptions::default())
+    }
+
+    /// Decode an [`AudioBuffer`] from a given input stream, with the given options
+    ///
+    /// See [`Self::decode_audio_data_sync_with_options`] for the options, and
+    /// [`Self::decode_audio_data`] for the blocking IO caveat.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding). Unsupported
+    /// formats and codecs are reported with a [`DecodeError`](crate::DecodeError).
+    fn decode_audio_data_with_options<R: std::io::Read + Send + Sync + 'static>(
+        &self,
+        input: R,
+        options: DecodeOptions,
+    ) -> impl Future<Output = Result<AudioBuffer, Box<dyn std::error::Error + Send + Sync>>>
+           + Send
+           + 'static {
+        let sample_rate = self.sample_rate();
+        async move { decoding::decode_audio_data(input, sample_rate, options) }
+    }
+
+    /// Decode a given input stream incrementally, to play it while it is decoding
+    ///
+    /// The returned [`DecodingStream`] holds a
+    /// [`MediaStream`](crate::media_streams::MediaStream) of the decoded audio, to be played with
+    /// a [`MediaStreamAudioSourceNode`](node::MediaStreamAudioSourceNode), and reports the
+    /// progress of the decoding. Long files start playing instantly and are not decoded in memory
+    /// as a whole.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error when the input cannot be probed (IO, mime sniffing).
+    /// Unsupported formats and codecs are reported with a [`DecodeError`](crate::DecodeError).
+    /// Later decoding errors are reported to [`DecodingStream::set_onerror`].
+    fn decode_audio_data_stream<R: std::io::Read + Send + Sync + 'static>(
+        &self,
+        input: R,
+    ) -> Result<DecodingStream, Box<dyn std::error::Error + Send + Sync>> {
+        DecodingStream::new(input)
+    }
+
+    /// Decode an [`AudioBuffer`] from a given input stream, along with its metadata
+    ///
+    /// The [`AudioMetadata`](crate::AudioMetadata) holds the tags of the input (title, artist,
+    /// ...), the duration announced by the container and the loop points of the sampler chunk of
+    /// WAV files. The input is read in memory before it is decoded. See
+    /// [`Self::decode_audio_data_sync_with_options`] for the options.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding). Unsupported
+    /// formats and codecs are reported with a [`DecodeError`](crate::DecodeError).
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext};
+    /// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+    /// use web_audio_api::DecodeOptions;
+    ///
+    /// let context = AudioContext::default();
+    /// let file = std::fs::File::open("samples/sample.wav").unwrap();
+    /// let (buffer, metadata) = context
+    ///     .decode_audio_data_with_metadata_sync(file, DecodeOptions::default())
+    ///     .unwrap();
+    ///
+    /// let mut src = context.create_buffer_source();
+    /// src.set_buffer(buffer);
+    /// if let Some(sample_loop) = metadata.loops.first() {
+    ///     src.set_loop(true);
+    ///     src.set_loop_start(sample_loop.start);
+    ///     src.set_loop_end(sample_loop.end);
+    /// }
+    /// src.connect(&context.destination());
+    /// src.start();
+    /// ```
+    fn decode_audio_data_with_metadata_sync<R: std::io::Read + Send + Sync + 'static>(
+        &self,
+        input: R,
+        options: DecodeOptions,
+    ) -> Result<(AudioBuffer, AudioMetadata), Box<dyn std::error::Error + Send + Sync>> {
+        decoding::decode_audio_data_with_metadata(input, self.sample_rate(), options)
+    }
+
+    /// Decode an [`AudioBuffer`] from a given input stream, along with its metadata
+    ///
+    /// See [`Self::decode_audio_data_with_metadata_sync`] for the metadata, and
+    /// [`Self::decode_audio_data`] for the blocking IO caveat.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding). Unsupported
+    /// formats and codecs are reported with a [`DecodeError`](crate::DecodeError).
+    fn decode_audio_data_with_metadata<R: std::io::Read + Send + Sync + 'static>(
+        &self,
+        input: R,
+        options: DecodeOptions,
+    ) -> impl Future<
+        Output = Result<(AudioBuffer, AudioMetadata), Box<dyn std::error::Error + Send + Sync>>,
+    > + Send
+           + 'static {
         let sample_rate = self.sample_rate();
+        async move { decoding::decode_audio_data_with_metadata(input, sample_rate, options) }
+    }
+
+    /// Prepare an impulse response for usage in a [`ConvolverNode`](node::ConvolverNode)
+    ///
+    /// The buffer is resampled to the sample rate of this context (as required by
+    /// [`ConvolverNode::set_buffer`](node::ConvolverNode::set_buffer)), after which its leading
+    /// silence is trimmed and the normalization is applied according to the given options.
+    ///
+    /// Resampling long impulse responses is expensive, so the work is performed on a dedicated
+    /// thread and the result is delivered via the returned future.
+    ///
+    /// # Panics
+    ///
+    /// The future will panic if the buffer could not be processed, e.g. when the impulse response
+    /// has an invalid sample rate.
+    fn prepare_impulse_response(
+        &self,
+        buffer: AudioBuffer,
+        options: node::ImpulseResponseOptions,
+    ) -> impl Future<Output = AudioBuffer> + Send + 'static {
+        let sample_rate = self.sample_rate();
+        let (sender, receiver) = futures_channel::oneshot::channel();
+
+        std::thread::spawn(move || {
+            let _ = sender.send(options.apply(buffer, sample_rate));
+        });
+
         async move {
-            // Set up a media decoder, consume the stream in full and construct a single buffer out of it
-            let mut buffer = MediaDecoder::try_new(input)?
-                .collect::<Result<Vec<_>, _>>()?
-                .into_iter()
-                .reduce(|mut accum, item| {
-                    accum.extend(&item);
-                    accum
-                })
-                // if there are no samples decoded, return an empty buffer
-                .unwrap_or_else(|| AudioBuffer::from(vec![vec![]], sample_rate));
-
-            // resample to desired rate (no-op if already matching)
-            buffer.resample(sample_rate);
-
-            Ok(buffer)
+            receiver
+                .await
+                .expect("Impulse response preparation thread has panicked")
         }
     }
 
@@ -333,6 +493,189 @@ pub trait BaseAudioContext {
         self.base().current_time()
     }
 
+    /// Apply all the changes made by the closure in the same render quantum
+    ///
+    /// The param values and automations, the connections and disconnections, the created nodes
+    /// and the other changes made while the closure runs are held back and handed to the render
+    /// thread at once when it returns, so that related parameters of several nodes never take
+    /// effect in different render quanta. Nested batches are merged into the outermost one.
+    ///
+    /// The closure receives an [`AudioTransaction`], whose time should be used to schedule the
+    /// automations of the batch relative to the same instant.
+    ///
+    /// The closure must not wait for the render thread, e.g. with
+    /// [`AudioContext::suspend_sync`](crate::context::AudioContext::suspend_sync), since the
+    /// messages it waits for are only sent when the closure returns.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext};
+    /// use web_audio_api::node::AudioNode;
+    ///
+    /// let context = AudioContext::default();
+    /// let dry = context.create_gain();
+    /// let wet = context.create_gain();
+    /// let filter = context.create_biquad_filter();
+    ///
+    /// // crossfade to the filtered signal without a gap or a bump in the level
+    /// context.batch(|tx| {
+    ///     dry.gain().set_value_at_time(0., tx.current_time());
+    ///     wet.gain().set_value_at_time(1., tx.current_time());
+    ///     filter.connect(&wet);
+    /// });
+    /// ```
+    fn batch<R, F: FnOnce(&AudioTransaction) -> R>(&self, f: F) -> R {
+        let base = self.base();
+        let transaction = AudioTransaction {
+            current_time: base.current_time(),
+        };
+        base.batch_control_msgs(|| f(&transaction))
+    }
+
+    /// Enable or disable the profiler recording the render time of each node
+    ///
+    /// The statistics are cleared when the profiler is enabled. Timing the nodes adds a small
+    /// overhead to the render thread, so the profiler is disabled by default.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    fn set_profiling(&self, enabled: bool) {
+        self.base().set_profiling(enabled);
+    }
+
+    /// Fill state of the queue of control messages (e.g. new nodes, connections and param
+    /// automations) to the render thread
+    ///
+    /// The realtime [`AudioContext`](crate::context::AudioContext) uses a lock-free queue with
+    /// a fixed number of preallocated slots, so handling a spike of control messages never
+    /// allocates or blocks in the audio callback. When all slots are taken, the control thread
+    /// waits until the render thread has handled some messages, this back-pressure is reported
+    /// in [`ControlQueueStats::blocked`] and logged once as a warning. The offline and pull
+    /// contexts are rendered on demand, their queue grows instead.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    fn control_queue_stats(&self) -> ControlQueueStats {
+        self.base().control_queue_stats()
+    }
+
+    /// Render independent branches of the audio graph in parallel, on the given number of worker
+    /// threads
+    ///
+    /// The nodes are grouped in levels: the nodes of a level only depend on the nodes of the
+    /// previous levels, so they can be rendered at the same time. For every level, the render
+    /// thread hands out the nodes to the workers and renders nodes itself too. On Linux, the
+    /// workers are pinned to a core each.
+    ///
+    /// Synchronizing the threads adds overhead to every level, so this only pays off for graphs
+    /// with expensive parallel branches, e.g. multiple convolvers or HRTF panners. Use fewer
+    /// workers than the number of cores. A count of 0 (the default) renders the whole graph on
+    /// the render thread.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    fn set_render_threads(&self, count: usize) {
+        self.base().set_render_threads(count);
+    }
+
+    /// Snapshot of the render time statistics of the nodes, most expensive first
+    ///
+    /// Only the nodes alive in the render thread that have rendered at least one quantum since
+    /// the profiler was enabled with [`set_profiling`](Self::set_profiling) are listed.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext};
+    ///
+    /// let context = AudioContext::default();
+    /// context.set_profiling(true);
+    ///
+    /// // build and play the graph
+    /// std::thread::sleep(std::time::Duration::from_secs(5));
+    ///
+    /// for node in context.profile().iter().take(5) {
+    ///     println!("{}: {:?} on average, {:?} max", node.processor, node.average, node.max);
+    /// }
+    /// ```
+    fn profile(&self) -> Vec<NodeProfile> {
+        self.base().profile()
+    }
+
+    /// Description of the current audio graph: the nodes, their channel configuration and
+    /// params, and the connections between them
+    ///
+    /// Only the nodes with a handle on the control thread are described, e.g. a source node
+    /// that was dropped while playing is left out.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext};
+    /// use web_audio_api::node::AudioNode;
+    ///
+    /// let context = AudioContext::default();
+    /// let gain = context.create_gain();
+    /// gain.connect(&context.destination());
+    ///
+    /// // visualize with e.g. `dot -Tsvg graph.dot > graph.svg`
+    /// std::fs::write("graph.dot", context.export_graph().to_dot()).unwrap();
+    /// ```
+    #[must_use]
+    fn export_graph(&self) -> GraphDescription {
+        self.base().export_graph()
+    }
+
+    /// Create and connect the nodes of the given description, e.g. a preset file or the result
+    /// of [`export_graph`](Self::export_graph)
+    ///
+    /// The supported node types are the `AudioDestinationNode`, `AudioBufferSourceNode`,
+    /// `BiquadFilterNode`, `ChannelMergerNode`, `ChannelSplitterNode`, `ConstantSourceNode`,
+    /// `DelayNode`, `DynamicsCompressorNode`, `GainNode`, `OscillatorNode` and
+    /// `StereoPannerNode`. The values of the params, the channel configuration and the labels are
+    /// restored. Other attributes, e.g. the type of a `BiquadFilterNode` or the buffer of an
+    /// `AudioBufferSourceNode`, are not part of the description and keep their default value.
+    /// Source nodes are not started.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a node type is not supported, if the params of a node do not match its
+    /// type, or if a connection refers to an unknown node, param or port. The nodes created
+    /// before the error are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a channel configuration or an automation rate of the description is not valid
+    /// for the node
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext, GraphNode};
+    /// use web_audio_api::node::AudioScheduledSourceNode;
+    ///
+    /// let context = AudioContext::default();
+    /// # let description = context.export_graph();
+    /// // e.g. deserialized from a preset file with the `serde` feature
+    /// let mut graph = context.build_graph(&description).unwrap();
+    ///
+    /// if let Some(GraphNode::Oscillator(osc)) = graph.node_by_label_mut("lead") {
+    ///     osc.start();
+    /// }
+    /// ```
+    fn build_graph(
+        &self,
+        description: &GraphDescription,
+    ) -> Result<AudioGraph, Box<dyn Error + Send + Sync>> {
+        crate::context::graph_builder::build_graph(self.base(), description)
+    }
+
     /// Create an `AudioParam`.
     ///
     /// Call this inside the `register` closure when setting up your `AudioNode`
@@ -357,13 +700,18 @@ pub trait BaseAudioContext {
 
     /// Register callback to run when the state of the AudioContext has changed
     ///
+    /// The event reports the previous and the current state of the context. Each transition is
+    /// reported once, in order.
+    ///
     /// Only a single event handler is active at any time. Calling this method multiple times will
     /// override the previous event handler.
-    fn set_onstatechange<F: FnMut(Event) + Send + 'static>(&self, mut callback: F) {
-        let callback = move |_| {
-            callback(Event {
-                type_: "statechange",
-            })
+    fn set_onstatechange<F: FnMut(AudioContextStateChangeEvent) + Send + 'static>(
+        &self,
+        mut callback: F,
+    ) {
+        let callback = move |v| match v {
+            EventPayload::StateChange(v) => callback(v),
+            _ => unreachable!(),
         };
 
         self.base().set_event_handler(
@@ -377,6 +725,69 @@ pub trait BaseAudioContext {
         self.base().clear_event_handler(EventType::StateChange);
     }
 
+    /// Add a listener to run when the state of the AudioContext has changed
+    ///
+    /// Unlike [`set_onstatechange`](Self::set_onstatechange), the listeners do not replace each
+    /// other. They run in the order they were added, before the callback of
+    /// `set_onstatechange`. Use the returned [`ListenerHandle`] to remove the listener.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    fn add_statechange_listener<F: FnMut(AudioContextStateChangeEvent) + Send + 'static>(
+        &self,
+        mut callback: F,
+    ) -> ListenerHandle {
+        let listener = move |v: &EventPayload| match v {
+            EventPayload::StateChange(v) => callback(v.clone()),
+            _ => unreachable!(),
+        };
+
+        self.base().add_listener(EventType::StateChange, listener)
+    }
+
+    /// Add a listener for the given event of the AudioContext
+    ///
+    /// Generic form of [`add_statechange_listener`](Self::add_statechange_listener), the same
+    /// listener can be added to the events of nodes and contexts, see
+    /// [`AudioScheduledSourceNode::add_event_listener`](crate::node::AudioScheduledSourceNode::add_event_listener).
+    /// The `sinkchange` event is only dispatched by an [`AudioContext`](crate::context::AudioContext).
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the event is not dispatched by contexts, e.g. [`EventKind::Ended`]
+    fn add_event_listener<F: FnMut(Event) + Send + 'static>(
+        &self,
+        kind: EventKind,
+        mut callback: F,
+    ) -> ListenerHandle {
+        let event = match kind {
+            EventKind::StateChange => EventType::StateChange,
+            EventKind::SinkChange => EventType::SinkChange,
+            _ => panic!(
+                "NotSupportedError - contexts do not dispatch {:?} events",
+                kind
+            ),
+        };
+
+        let listener = move |_: &EventPayload| {
+            callback(Event {
+                type_: kind.type_(),
+            })
+        };
+        self.base().add_listener(event, listener)
+    }
+
+    /// Future resolving with the next change of the state of the AudioContext
+    ///
+    /// The transitions happening before the creation of the future are not reported.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    #[cfg(feature = "async")]
+    fn state_change(&self) -> EventFuture<AudioContextStateChangeEvent> {
+        EventFuture::new(|sender| self.add_statechange_listener(move |event| sender.send(event)))
+    }
+
     #[cfg(test)]
     fn mock_registration(&self) -> AudioContextRegistration {
         AudioContextRegistration {
@@ -390,6 +801,8 @@ pub trait BaseAudioContext {
 mod tests {
     use super::*;
     use crate::context::OfflineAudioContext;
+    use crate::media_streams::ResampleQuality;
+    use crate::node::AudioScheduledSourceNode;
 
     use float_eq::assert_float_eq;
 
@@ -412,6 +825,29 @@ mod tests {
         assert!(left_start != right_start);
     }
 
+    #[test]
+    fn test_decode_audio_data_sync_with_options() {
+        let context = OfflineAudioContext::new(1, 1, 48000.);
+
+        let file = std::fs::File::open("samples/sample.wav").unwrap();
+        let options = DecodeOptions { resample: None };
+        let audio_buffer = context
+            .decode_audio_data_sync_with_options(file, options)
+            .unwrap();
+        assert_eq!(audio_buffer.sample_rate(), 44100.);
+        assert_eq!(audio_buffer.length(), 142_187);
+
+        let file = std::fs::File::open("samples/sample.wav").unwrap();
+        let options = DecodeOptions {
+            resample: Some(ResampleQuality::Medium),
+        };
+        let audio_buffer = context
+            .decode_audio_data_sync_with_options(file, options)
+            .unwrap();
+        assert_eq!(audio_buffer.sample_rate(), 48000.);
+        assert_eq!(audio_buffer.length(), 154_762); // ceil(142_187 * 48_000 / 44_100)
+    }
+
     #[test]
     fn test_decode_audio_data_future_send_static() {
         let context = OfflineAudioContext::new(1, 1, 44100.);
@@ -456,6 +892,149 @@ mod tests {
         assert!(context.decode_audio_data_sync(file).is_err());
     }
 
+    #[test]
+    fn test_prepare_impulse_response() {
+        use futures::executor;
+
+        let context = OfflineAudioContext::new(1, 1, 44100.);
+        let ir = AudioBuffer::from(vec![vec![0., 0., 1., 0.5, 0.25, 0.]], 22050.);
+        let options = node::ImpulseResponseOptions {
+            trim_threshold: Some(0.),
+            gain: 0.5,
+            ..node::ImpulseResponseOptions::default()
+        };
+
+        let future = context.prepare_impulse_response(ir, options);
+        let ir = executor::block_on(future);
+
+        assert_float_eq!(ir.sample_rate(), 44100., abs <= 0.);
+        // resampled length is 12, minus 3 frames of leading silence
+        assert_eq!(ir.length(), 9);
+        assert_float_eq!(ir.get_channel_data(0)[0], 0.5 * 4. / 11., abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_batch() {
+        let mut context = OfflineAudioContext::new(1, 128, 44100.);
+
+        let (_src, _gain) = context.batch(|tx| {
+            assert_float_eq!(tx.current_time(), 0., abs <= 0.);
+
+            let mut src = context.create_constant_source();
+            let gain = context.create_gain();
+            src.connect(&gain);
+            gain.connect(&context.destination());
+
+            // nested batches are merged
+            context.batch(|tx| {
+                src.offset().set_value_at_time(2., tx.current_time());
+                gain.gain().set_value(0.25);
+            });
+            src.start();
+
+            (src, gain)
+        });
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0)[..],
+            [0.5; 128][..],
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    fn test_profile() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 128 * 4, sample_rate);
+
+        let mut src = context.create_constant_source();
+        let gain = context.create_gain();
+        src.connect(&gain);
+        gain.connect(&context.destination());
+        src.start();
+
+        assert_eq!(gain.label(), None);
+        gain.set_label(String::from("master"));
+        assert_eq!(gain.label().as_deref(), Some("master"));
+
+        context.set_profiling(true);
+        assert!(context.profile().is_empty());
+
+        context.suspend_sync(128. * 3. / f64::from(sample_rate), move |context| {
+            let profile = context.profile();
+            let names: Vec<_> = profile.iter().map(|p| p.processor).collect();
+            assert!(names.iter().any(|n| n.ends_with("GainRenderer")));
+            assert!(names.iter().any(|n| n.ends_with("ConstantSourceRenderer")));
+            assert!(names.iter().any(|n| n.ends_with("DestinationRenderer")));
+
+            let labels: Vec<_> = profile.iter().filter_map(|p| p.label.as_deref()).collect();
+            assert_eq!(labels, ["master"]);
+
+            assert!(profile.iter().all(|p| p.calls == 3 && p.max <= p.total));
+            assert!(profile.windows(2).all(|w| w[0].total >= w[1].total));
+        });
+
+        let _ = context.start_rendering_sync();
+        drop((src, gain));
+    }
+
+    #[test]
+    fn test_control_queue_stats() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let before = context.control_queue_stats();
+        assert_eq!(before.capacity, None);
+
+        // the offline context handles the messages when rendering starts
+        let gain = context.create_gain();
+        gain.connect(&context.destination());
+        let after = context.control_queue_stats();
+        assert!(after.pending > before.pending);
+        assert_eq!(after.blocked, 0);
+    }
+
+    #[test]
+    fn test_render_threads() {
+        let render = |threads| {
+            let context = OfflineAudioContext::new(2, 128 * 8, 48_000.);
+            context.set_render_threads(threads);
+
+            // parallel branches feeding a feedback delay
+            let delay = context.create_delay(1.);
+            delay.delay_time().set_value(0.005);
+            let feedback = context.create_gain();
+            feedback.gain().set_value(0.5);
+            delay.connect(&feedback);
+            feedback.connect(&delay);
+            delay.connect(&context.destination());
+
+            for i in 0..8 {
+                let mut osc = context.create_oscillator();
+                osc.frequency().set_value(110. * (i + 1) as f32);
+                let lfo = context.create_constant_source();
+                let gain = context.create_gain();
+                gain.gain().set_value(0.1);
+                lfo.connect(gain.gain());
+                osc.connect(&gain);
+                gain.connect(&delay);
+                gain.connect(&context.destination());
+                osc.start();
+            }
+
+            context.start_rendering_sync()
+        };
+
+        let expected = render(0);
+        let result = render(3);
+        for channel in 0..2 {
+            assert_float_eq!(
+                result.get_channel_data(channel),
+                expected.get_channel_data(channel),
+                abs_all <= 1e-5
+            );
+        }
+    }
+
     #[test]
     fn test_create_buffer() {
         let number_of_channels = 3;
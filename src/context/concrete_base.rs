@@ -1,22 +1,44 @@
 //! The `ConcreteBaseAudioContext` type
 
+use crate::context::graph_description::NodeRegistry;
 use crate::context::{
-    AudioContextRegistration, AudioContextState, AudioNodeId, BaseAudioContext,
+    AudioContextRegistration, AudioContextState, AudioNodeId, BaseAudioContext, GraphDescription,
     DESTINATION_NODE_ID, LISTENER_NODE_ID, LISTENER_PARAM_IDS,
 };
-use crate::events::{EventDispatch, EventHandler, EventLoop, EventType};
+use crate::events::{
+    EventDispatch, EventHandler, EventLoop, EventPayload, EventType, ListenerHandle,
+};
 use crate::message::ControlMessage;
-use crate::node::{AudioDestinationNode, AudioNode, AudioNodeOptions, ChannelConfig};
+use crate::node::{
+    AudioDestinationNode, AudioNode, AudioNodeOptions, ChannelConfig, CycleError, DestinationGuard,
+};
 use crate::param::AudioParam;
-use crate::render::AudioProcessor;
+use crate::profiler::{NodeProfile, Profiler};
+use crate::render::{AudioProcessor, RenderWorkers};
 use crate::spatial::AudioListenerParams;
 
-use crate::AudioListener;
+use crate::{AtomicF64, AudioListener};
 
-use crossbeam_channel::{SendError, Sender};
+use crossbeam_channel::{SendError, Sender, TrySendError};
+use std::any::Any;
 use std::collections::HashSet;
-use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
-use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard, TryLockError};
+
+/// Fill state of the queue of control messages to the render thread, see
+/// [`BaseAudioContext::control_queue_stats`]
+///
+/// This type is not part of the Web Audio API specification.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ControlQueueStats {
+    /// Number of messages waiting to be handled by the render thread
+    pub pending: usize,
+    /// Number of preallocated message slots, `None` if the queue grows on demand
+    pub capacity: Option<usize>,
+    /// Number of times the control thread had to wait for a free slot
+    pub blocked: u64,
+}
 
 /// This struct assigns new [`AudioNodeId`]s for [`AudioNode`]s
 ///
@@ -46,6 +68,81 @@ impl AudioNodeIdProvider {
     }
 }
 
+/// Notification to the idle watcher thread of an `AudioContext`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IdleEvent {
+    /// The destination renderer has been silent for the idle timeout
+    Idle,
+    /// A source started to produce sound, or the context was resumed
+    Active,
+}
+
+/// Activity of the audio graph, used to suspend an idle `AudioContext`
+///
+/// Shared by the control thread, the destination renderer and the idle watcher thread. The
+/// destination renderer detects the idle graph, the watcher thread only acts on the events.
+#[derive(Debug)]
+pub(crate) struct IdleMonitor {
+    /// seconds without activity before the graph is idle, infinite when idle suspend is disabled
+    pub timeout: AtomicF64,
+    /// latest start time of the scheduled source nodes
+    pub last_start: AtomicF64,
+    /// set when a source starts sounding, the destination renderer restarts the idle timer
+    pub active: AtomicBool,
+    /// events of the idle watcher thread, `None` when no watcher is running
+    pub events: Mutex<Option<Sender<IdleEvent>>>,
+}
+
+impl Default for IdleMonitor {
+    fn default() -> Self {
+        Self {
+            timeout: AtomicF64::new(f64::INFINITY),
+            last_start: AtomicF64::new(0.),
+            active: AtomicBool::new(false),
+            events: Mutex::new(None),
+        }
+    }
+}
+
+impl IdleMonitor {
+    /// Restart the idle timer and resume the context if it was suspended because it was idle
+    pub fn notify_active(&self) {
+        self.active.store(true, Ordering::Relaxed);
+        if let Some(events) = self.events.lock().unwrap().as_ref() {
+            let _ = events.try_send(IdleEvent::Active);
+        }
+    }
+
+    /// Tell the watcher thread that the graph is idle, without blocking the render thread
+    ///
+    /// Returns `false` when the event could not be delivered yet and should be retried.
+    pub fn notify_idle(&self) -> bool {
+        match self.events.try_lock() {
+            Ok(events) => match events.as_ref() {
+                Some(events) => {
+                    !matches!(events.try_send(IdleEvent::Idle), Err(TrySendError::Full(_)))
+                }
+                None => true,
+            },
+            Err(TryLockError::WouldBlock) => false,
+            Err(TryLockError::Poisoned(_)) => true,
+        }
+    }
+
+    /// Stop the watcher thread, it exits when its event channel disconnects
+    pub fn stop(&self) {
+        self.events.lock().unwrap().take();
+    }
+}
+
+/// Insert of the main bus, see [`AudioDestinationNode::insert_effect`]
+#[derive(Debug)]
+struct MainBusInsert {
+    id: AudioNodeId,
+    /// The control handle of the node was dropped while it was inserted
+    handle_dropped: bool,
+}
+
 /// The struct that corresponds to the Javascript `BaseAudioContext` object.
 ///
 /// This object is returned from the `base()` method on
@@ -94,8 +191,12 @@ struct ConcreteBaseAudioContextInner {
     destination_channel_config: ChannelConfig,
     /// message channel from control to render thread
     render_channel: RwLock<Sender<ControlMessage>>,
+    /// number of control messages that waited for a free slot of the message channel
+    render_channel_blocked: AtomicU64,
     /// control messages that cannot be sent immediately
     queued_messages: Mutex<Vec<ControlMessage>>,
+    /// control messages collected by an open batch, sent together when it closes
+    batched_messages: Mutex<Option<Vec<ControlMessage>>>,
     /// number of frames played
     frames_played: Arc<AtomicU64>,
     /// control msg to add the AudioListener, to be sent when the first panner is created
@@ -112,6 +213,16 @@ struct ConcreteBaseAudioContextInner {
     event_send: Sender<EventDispatch>,
     /// Current audio graph connections (from node, output port, to node, input port)
     connections: Mutex<HashSet<(AudioNodeId, usize, AudioNodeId, usize)>>,
+    /// NaN/Inf guard of the destination node, shared with the RenderThread
+    destination_guard: Arc<DestinationGuard>,
+    /// Activity of the audio graph, shared with the RenderThread
+    idle_monitor: Arc<IdleMonitor>,
+    /// Render time statistics of the nodes
+    profiler: Profiler,
+    /// Nodes with a handle on the control thread, to describe the audio graph
+    nodes: NodeRegistry,
+    /// Nodes processing the input of the destination, in order
+    main_bus: Mutex<Vec<MainBusInsert>>,
 }
 
 impl BaseAudioContext for ConcreteBaseAudioContext {
@@ -140,7 +251,9 @@ impl ConcreteBaseAudioContext {
             sample_rate,
             max_channel_count,
             render_channel: RwLock::new(render_channel),
+            render_channel_blocked: AtomicU64::new(0),
             queued_messages: Mutex::new(Vec::new()),
+            batched_messages: Mutex::new(None),
             audio_node_id_provider,
             destination_channel_config: AudioNodeOptions::default().into(),
             frames_played,
@@ -151,6 +264,11 @@ impl ConcreteBaseAudioContext {
             event_loop,
             event_send,
             connections: Mutex::new(HashSet::new()),
+            destination_guard: Arc::new(DestinationGuard::default()),
+            idle_monitor: Arc::new(IdleMonitor::default()),
+            profiler: Profiler::default(),
+            nodes: NodeRegistry::default(),
+            main_bus: Mutex::new(Vec::new()),
         };
         let base = Self {
             inner: Arc::new(base_inner),
@@ -221,6 +339,30 @@ impl ConcreteBaseAudioContext {
         base
     }
 
+    /// NaN/Inf guard of the destination node
+    pub(crate) fn destination_guard(&self) -> &Arc<DestinationGuard> {
+        &self.inner.destination_guard
+    }
+
+    /// Activity of the audio graph
+    pub(crate) fn idle_monitor(&self) -> &Arc<IdleMonitor> {
+        &self.inner.idle_monitor
+    }
+
+    /// Register the start of a source node at the given time, resuming the context if it was
+    /// suspended because it was idle
+    ///
+    /// Every node that starts producing sound calls this, so an idle suspended context wakes up
+    /// for all of them.
+    pub(crate) fn notify_source_start(&self, when: f64) {
+        let monitor = &self.inner.idle_monitor;
+        let when = when.max(self.current_time());
+        if when > monitor.last_start.load(Ordering::Relaxed) {
+            monitor.last_start.store(when, Ordering::Relaxed);
+        }
+        monitor.notify_active();
+    }
+
     pub(crate) fn address(&self) -> usize {
         Arc::as_ptr(&self.inner) as usize
     }
@@ -235,6 +377,8 @@ impl ConcreteBaseAudioContext {
     ) -> T {
         // create a unique id for this node
         let id = self.inner.audio_node_id_provider.get();
+        // the id may be reused from a dropped node, whose callbacks must not run for this node
+        self.inner.event_loop.clear_node(id);
         let registration = AudioContextRegistration {
             id,
             context: self.clone(),
@@ -242,15 +386,25 @@ impl ConcreteBaseAudioContext {
 
         // create the node and its renderer
         let (node, render) = (f)(registration);
+        let stats = self.inner.profiler.add_node(id, render.name());
+        self.inner.nodes.add_node(
+            id,
+            std::any::type_name::<T>(),
+            node.number_of_inputs(),
+            node.number_of_outputs(),
+            node.channel_config().clone(),
+        );
 
         // pass the renderer to the audio graph
         let message = ControlMessage::RegisterNode {
             id,
             reclaim_id: llq::Node::new(id),
+            garbage: crate::render::graph::garbage_slot(),
             node: render,
             inputs: node.number_of_inputs(),
             outputs: node.number_of_outputs(),
             channel_config: node.channel_config().inner(),
+            stats,
         };
 
         // if this is the AudioListener or its params, do not add it to the graph just yet
@@ -269,16 +423,81 @@ impl ConcreteBaseAudioContext {
     /// Send a control message to the render thread
     ///
     /// When the render thread is closed or crashed, the message is discarded and a log warning is
-    /// emitted.
+    /// emitted. When all slots of the message channel are taken, the control thread waits for
+    /// the render thread to handle the pending messages, so the render thread never allocates or
+    /// blocks for them.
     pub(crate) fn send_control_msg(&self, msg: ControlMessage) {
         if self.state() != AudioContextState::Closed {
-            let result = self.inner.render_channel.read().unwrap().send(msg);
+            if let Some(batch) = self.inner.batched_messages.lock().unwrap().as_mut() {
+                batch.push(msg);
+                return;
+            }
+
+            let render_channel = self.inner.render_channel.read().unwrap();
+            let result = match render_channel.try_send(msg) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(msg)) => {
+                    let blocked = self
+                        .inner
+                        .render_channel_blocked
+                        .fetch_add(1, Ordering::Relaxed);
+                    if blocked == 0 {
+                        log::warn!("Control message queue is full - waiting for the render thread");
+                    }
+                    render_channel.send(msg).map_err(|e| e.into_inner())
+                }
+                Err(TrySendError::Disconnected(msg)) => Err(msg),
+            };
             if result.is_err() {
                 log::warn!("Discarding control message - render thread is closed");
             }
         }
     }
 
+    /// Fill state of the message channel to the render thread
+    pub(crate) fn control_queue_stats(&self) -> ControlQueueStats {
+        let render_channel = self.inner.render_channel.read().unwrap();
+        ControlQueueStats {
+            pending: render_channel.len(),
+            capacity: render_channel.capacity(),
+            blocked: self.inner.render_channel_blocked.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Run the given closure and send all the control messages it emits at once, so the render
+    /// thread handles them in the same render quantum
+    ///
+    /// Nested calls are merged into the outermost batch. The messages are also sent when the
+    /// closure panics.
+    pub(crate) fn batch_control_msgs<R>(&self, f: impl FnOnce() -> R) -> R {
+        struct SendOnDrop<'a>(&'a ConcreteBaseAudioContext);
+
+        impl Drop for SendOnDrop<'_> {
+            fn drop(&mut self) {
+                let batch = match self.0.inner.batched_messages.lock() {
+                    Ok(mut batch) => batch.take(),
+                    Err(_) => return,
+                };
+                if let Some(msgs) = batch.filter(|msgs| !msgs.is_empty()) {
+                    let msgs = llq::Node::new(Box::new(msgs) as Box<dyn Any + Send>);
+                    self.0.send_control_msg(ControlMessage::Batch { msgs });
+                }
+            }
+        }
+
+        {
+            let mut batch = self.inner.batched_messages.lock().unwrap();
+            if batch.is_some() {
+                drop(batch);
+                return f();
+            }
+            *batch = Some(Vec::new());
+        }
+
+        let _send_on_drop = SendOnDrop(self);
+        f()
+    }
+
     pub(crate) fn send_event(&self, msg: EventDispatch) -> Result<(), SendError<EventDispatch>> {
         self.inner.event_send.send(msg)
     }
@@ -294,11 +513,20 @@ impl ConcreteBaseAudioContext {
             return;
         }
 
-        // Inform render thread that the control thread AudioNode no longer has any handles
-        let message = ControlMessage::ControlHandleDropped { id };
-        self.send_control_msg(message);
+        // The inserts of the main bus are kept alive until they are removed
+        let mut main_bus = self.inner.main_bus.lock().unwrap();
+        match main_bus.iter_mut().find(|insert| insert.id == id) {
+            Some(insert) => insert.handle_dropped = true,
+            None => {
+                // Inform render thread that the control thread AudioNode no longer has any handles
+                let message = ControlMessage::ControlHandleDropped { id };
+                self.send_control_msg(message);
+            }
+        }
+        drop(main_bus);
 
-        // Clear the connection administration for this node, the node id may be recycled later
+        // Clear the administration of this node, the node id may be recycled later
+        self.inner.nodes.remove_node(id);
         self.inner
             .connections
             .lock()
@@ -310,10 +538,89 @@ impl ConcreteBaseAudioContext {
     #[doc(hidden)]
     pub fn mark_cycle_breaker(&self, reg: &AudioContextRegistration) {
         let id = reg.id();
+        self.inner.nodes.set_cycle_breaker(id);
         let message = ControlMessage::MarkCycleBreaker { id };
         self.send_control_msg(message);
     }
 
+    /// Check that a connection from `from` to `to` does not close a cycle without a `DelayNode`,
+    /// which would be muted
+    ///
+    /// Only the nodes with a handle on the control thread are considered.
+    pub(crate) fn check_cycle(&self, from: AudioNodeId, to: AudioNodeId) -> Result<(), CycleError> {
+        let connections = self.inner.connections.lock().unwrap().clone();
+        let nodes = &self.inner.nodes;
+
+        match nodes.find_cycle(connections.into_iter(), from, to) {
+            None => Ok(()),
+            Some(cycle) => Err(CycleError {
+                nodes: cycle
+                    .into_iter()
+                    .map(|id| nodes.describe_node(id, self.node_label(id)))
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Enable or disable the recording of the render time of the nodes, the statistics are
+    /// cleared when enabled
+    pub(crate) fn set_profiling(&self, enabled: bool) {
+        if enabled {
+            self.inner.profiler.reset();
+        }
+        self.send_control_msg(ControlMessage::SetProfiling { enabled });
+    }
+
+    /// Replace the worker threads rendering independent nodes in parallel, none if zero
+    pub(crate) fn set_render_threads(&self, count: usize) {
+        let workers = (count > 0).then(|| RenderWorkers::new(count));
+        let message = ControlMessage::SetRenderWorkers {
+            workers: llq::Node::new(Box::new(workers)),
+        };
+        self.send_control_msg(message);
+    }
+
+    /// Render time statistics of the nodes, most expensive first
+    pub(crate) fn profile(&self) -> Vec<NodeProfile> {
+        self.inner.profiler.snapshot()
+    }
+
+    /// Label of the given node
+    pub(crate) fn node_label(&self, id: AudioNodeId) -> Option<String> {
+        self.inner.profiler.label(id)
+    }
+
+    /// Assign a label to the given node, shared with the render thread
+    pub(crate) fn set_node_label(&self, id: AudioNodeId, label: String) {
+        self.inner.profiler.set_label(id, label);
+    }
+
+    /// Description of the nodes with a handle on the control thread and their connections
+    pub(crate) fn export_graph(&self) -> GraphDescription {
+        let hidden = |id: AudioNodeId| id == LISTENER_NODE_ID || LISTENER_PARAM_IDS.contains(&id.0);
+        let connections = self.inner.connections.lock().unwrap().clone();
+
+        self.inner
+            .nodes
+            .describe(connections.into_iter(), hidden, |id| self.node_label(id))
+    }
+
+    /// The params of the given node, in order of creation
+    pub(crate) fn audio_params(&self, id: AudioNodeId) -> Vec<AudioParam> {
+        self.inner.nodes.params_of(id)
+    }
+
+    /// The audio param with the given id in the graph description
+    #[cfg(feature = "inspect")]
+    pub(crate) fn audio_param(&self, id: AudioNodeId) -> Option<AudioParam> {
+        self.inner.nodes.param(id)
+    }
+
+    /// Describe the node as an internal part of the given node in the graph description
+    pub(crate) fn mark_part_of(&self, reg: &AudioContextRegistration, owner: AudioNodeId) {
+        self.inner.nodes.set_part_of(reg.id(), owner);
+    }
+
     /// `ChannelConfig` of the `AudioDestinationNode`
     pub(super) fn destination_channel_config(&self) -> ChannelConfig {
         self.inner.destination_channel_config.clone()
@@ -356,7 +663,7 @@ impl ConcreteBaseAudioContext {
         let current_state = self.state();
         if current_state != state {
             self.inner.state.store(state as u8, Ordering::Release);
-            let _ = self.send_event(EventDispatch::state_change(state));
+            let _ = self.send_event(EventDispatch::state_change(current_state, state));
         }
     }
 
@@ -417,7 +724,8 @@ impl ConcreteBaseAudioContext {
 
     /// Schedule a connection of an `AudioParam` to the `AudioNode` it belongs to
     ///
-    /// It is not performed immediately as the `AudioNode` is not registered at this point.
+    /// It is not performed immediately as the `AudioNode` is not registered at this point. The
+    /// param is recorded as a param of the `AudioNode` in the graph description.
     pub(super) fn queue_audio_param_connect(&self, param: &AudioParam, audio_node: AudioNodeId) {
         // no need to store these type of connections in self.inner.connections
 
@@ -428,6 +736,10 @@ impl ConcreteBaseAudioContext {
             input: usize::MAX, // audio params connect to the 'hidden' input port
         };
         self.inner.queued_messages.lock().unwrap().push(message);
+
+        self.inner
+            .nodes
+            .set_param(param.registration().id(), audio_node, param.downgrade());
     }
 
     /// Disconnects outputs of the audio node, possibly filtered by output node, input, output.
@@ -467,6 +779,116 @@ impl ConcreteBaseAudioContext {
         }
     }
 
+    /// Disconnect the given connection if it exists, unlike [`Self::disconnect`] this does not
+    /// panic when it was already removed through the node handles
+    pub(crate) fn disconnect_if_connected(
+        &self,
+        from: AudioNodeId,
+        output: usize,
+        to: AudioNodeId,
+        input: usize,
+    ) {
+        let connection = (from, output, to, input);
+        if self.inner.connections.lock().unwrap().contains(&connection) {
+            self.disconnect(from, Some(output), Some(to), Some(input));
+        }
+    }
+
+    /// Append the node to the inserts of the main bus, processing the input of the destination
+    pub(crate) fn insert_main_bus(&self, id: AudioNodeId) {
+        let mut main_bus = self.inner.main_bus.lock().unwrap();
+        let connected =
+            self.inner
+                .connections
+                .lock()
+                .unwrap()
+                .contains(&(id, 0, DESTINATION_NODE_ID, 0));
+
+        let error = if main_bus.iter().any(|insert| insert.id == id) {
+            Some("InvalidStateError - node is already inserted in the main bus")
+        } else if connected {
+            Some("InvalidStateError - an insert of the main bus cannot be connected to the destination")
+        } else {
+            None
+        };
+
+        // make sure to drop the MutexGuard before the panic to avoid poisoning
+        if let Some(error) = error {
+            drop(main_bus);
+            panic!("{}", error);
+        }
+
+        main_bus.push(MainBusInsert {
+            id,
+            handle_dropped: false,
+        });
+
+        // the render thread routes the signal of the insert to the next one, if any
+        self.batch_control_msgs(|| {
+            self.send_control_msg(ControlMessage::ConnectNode {
+                from: id,
+                to: DESTINATION_NODE_ID,
+                output: 0,
+                input: 0,
+            });
+            self.send_main_bus(&main_bus);
+        });
+    }
+
+    /// Remove the node from the inserts of the main bus
+    pub(crate) fn remove_main_bus(&self, id: AudioNodeId) {
+        let mut main_bus = self.inner.main_bus.lock().unwrap();
+        let Some(index) = main_bus.iter().position(|insert| insert.id == id) else {
+            // make sure to drop the MutexGuard before the panic to avoid poisoning
+            drop(main_bus);
+            panic!("InvalidAccessError - node is not inserted in the main bus");
+        };
+
+        main_bus.remove(index);
+        self.batch_control_msgs(|| {
+            self.send_main_bus(&main_bus);
+            self.disconnect_main_bus_insert(id, false);
+        });
+    }
+
+    /// Remove all the inserts of the main bus, the ones without a control handle are dropped
+    pub(crate) fn clear_main_bus(&self) {
+        let mut main_bus = self.inner.main_bus.lock().unwrap();
+        let inserts = std::mem::take(&mut *main_bus);
+
+        self.batch_control_msgs(|| {
+            self.send_main_bus(&main_bus);
+            inserts.into_iter().for_each(|insert| {
+                self.disconnect_main_bus_insert(insert.id, insert.handle_dropped);
+            });
+        });
+    }
+
+    /// The number of nodes inserted in the main bus
+    pub(crate) fn main_bus_len(&self) -> usize {
+        self.inner.main_bus.lock().unwrap().len()
+    }
+
+    fn send_main_bus(&self, main_bus: &[MainBusInsert]) {
+        let inserts: Vec<_> = main_bus.iter().map(|insert| insert.id).collect();
+        let message = ControlMessage::SetMainBus {
+            inserts: llq::Node::new(Box::new(inserts)),
+        };
+        self.send_control_msg(message);
+    }
+
+    fn disconnect_main_bus_insert(&self, id: AudioNodeId, handle_dropped: bool) {
+        self.send_control_msg(ControlMessage::DisconnectNode {
+            from: id,
+            to: DESTINATION_NODE_ID,
+            output: 0,
+            input: 0,
+        });
+        if handle_dropped {
+            self.send_control_msg(ControlMessage::ControlHandleDropped { id });
+        }
+    }
+
     /// Connect the `AudioListener` to a `PannerNode`
     pub(crate) fn connect_listener_to_panner(&self, panner: AudioNodeId) {
         self.connect(LISTENER_NODE_ID, panner, 0, usize::MAX);
@@ -505,12 +927,23 @@ impl ConcreteBaseAudioContext {
     pub(crate) fn clear_event_handler(&self, event: EventType) {
         self.inner.event_loop.clear_handler(event);
     }
+
+    pub(crate) fn add_listener(
+        &self,
+        event: EventType,
+        listener: impl FnMut(&EventPayload) + Send + 'static,
+    ) -> ListenerHandle {
+        self.inner.event_loop.add_listener(event, listener)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use float_eq::assert_float_eq;
+
     use super::*;
     use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
 
     #[test]
     fn test_provide_node_id() {
@@ -549,6 +982,200 @@ mod tests {
         assert!(context.base().inner.connections.lock().unwrap().is_empty());
     }
 
+    #[test]
+    fn test_disconnect_overloads() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let splitter = context.create_channel_splitter(2);
+        let merger = context.create_channel_merger(2);
+        let gain = context.create_gain();
+
+        let connections = || {
+            let mut connections: Vec<_> = context
+                .base()
+                .inner
+                .connections
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|&(_, output, to, input)| (output, to, input))
+                .collect();
+            connections.sort_by_key(|&(output, to, input)| (output, to.0, input));
+            connections
+        };
+        let connect_all = || {
+            splitter.connect_from_output_to_input(&merger, 0, 0);
+            splitter.connect_from_output_to_input(&merger, 0, 1);
+            splitter.connect_from_output_to_input(&merger, 1, 1);
+            splitter.connect_from_output_to_input(gain.gain(), 1, 0);
+        };
+        let merger_id = merger.registration().id();
+        let param_id = gain.gain().registration().id();
+
+        // by output
+        connect_all();
+        splitter.disconnect_output(0);
+        assert_eq!(connections(), [(1, merger_id, 1), (1, param_id, 0)]);
+        splitter.disconnect();
+
+        // by destination node
+        connect_all();
+        splitter.disconnect_dest(&merger);
+        assert_eq!(connections(), [(1, param_id, 0)]);
+        splitter.disconnect();
+
+        // by destination node and output
+        connect_all();
+        splitter.disconnect_dest_from_output(&merger, 0);
+        assert_eq!(connections(), [(1, merger_id, 1), (1, param_id, 0)]);
+        splitter.disconnect();
+
+        // by destination node, output and input
+        connect_all();
+        splitter.disconnect_dest_from_output_to_input(&merger, 0, 1);
+        assert_eq!(
+            connections(),
+            [(0, merger_id, 0), (1, merger_id, 1), (1, param_id, 0)]
+        );
+        splitter.disconnect();
+
+        // by destination param
+        connect_all();
+        splitter.disconnect_dest(gain.gain());
+        assert_eq!(
+            connections(),
+            [(0, merger_id, 0), (0, merger_id, 1), (1, merger_id, 1)]
+        );
+        splitter.disconnect();
+
+        // by destination param and output
+        connect_all();
+        splitter.disconnect_dest_from_output(gain.gain(), 1);
+        assert_eq!(
+            connections(),
+            [(0, merger_id, 0), (0, merger_id, 1), (1, merger_id, 1)]
+        );
+        splitter.disconnect();
+        assert!(connections().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_disconnect_dest_from_other_output() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let splitter = context.create_channel_splitter(2);
+        let gain = context.create_gain();
+
+        splitter.connect_from_output_to_input(&gain, 0, 0);
+        splitter.disconnect_dest_from_output(&gain, 1);
+    }
+
+    #[test]
+    fn test_try_connect_cycle() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let gain1 = context.create_gain();
+        let gain2 = context.create_gain();
+        gain2.set_label(String::from("feedback"));
+        let gain3 = context.create_gain();
+
+        gain1.connect(&gain2);
+        gain2.connect(&gain3);
+
+        let error = gain3.try_connect(&gain1).unwrap_err();
+        let id = |node: &dyn AudioNode| node.registration().id().0;
+        assert_eq!(
+            error.nodes,
+            [
+                format!("GainNode {}", id(&gain1)),
+                format!("GainNode {} \"feedback\"", id(&gain2)),
+                format!("GainNode {}", id(&gain3)),
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "NotSupportedError - the connection closes a cycle without DelayNode, which is muted: \
+                GainNode {} -> GainNode {} \"feedback\" -> GainNode {} -> GainNode {}",
+                id(&gain1),
+                id(&gain2),
+                id(&gain3),
+                id(&gain1)
+            )
+        );
+        // the nodes are not connected
+        assert_eq!(context.base().inner.connections.lock().unwrap().len(), 2);
+
+        // through an AudioParam, or to itself
+        assert!(gain3.try_connect(gain1.gain()).is_err());
+        let error = gain1.try_connect(&gain1).unwrap_err();
+        assert_eq!(error.nodes.len(), 1);
+
+        // no cycle
+        assert!(gain1.try_connect(&gain3).is_ok());
+    }
+
+    #[test]
+    fn test_try_connect_cycle_with_delay() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let gain = context.create_gain();
+        let delay = context.create_delay(1.);
+
+        gain.connect(&delay);
+        assert!(delay.try_connect(&gain).is_ok());
+        assert!(gain.try_connect(&delay).is_ok());
+        assert!(delay.try_connect(delay.delay_time()).is_ok());
+
+        // another path without the delay
+        let other = context.create_gain();
+        gain.connect(&other);
+        assert!(other.try_connect(&gain).is_err());
+    }
+
+    #[test]
+    fn test_audio_params_by_name() {
+        let mut context = OfflineAudioContext::new(1, 128, 48000.);
+
+        let osc = context.create_oscillator();
+        let names: Vec<_> = osc
+            .audio_params()
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect();
+        assert_eq!(names, ["frequency", "detune"]);
+
+        // the param of the reader of the delay is found on the node
+        let delay = context.create_delay(1.);
+        assert_eq!(delay.audio_params().len(), 1);
+        assert!(delay.audio_param("delayTime").is_some());
+        assert!(delay.audio_param("frequency").is_none());
+
+        // modulate the gain of a node of unknown type
+        let target: Box<dyn AudioNode> = Box::new(context.create_gain());
+        target.audio_param("gain").unwrap().set_value(0.);
+        target.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.offset().set_value(0.5);
+        src.connect(&*target);
+        let param = src.connect_param(&*target, "gain");
+        assert_eq!(param.name(), "gain");
+        src.start();
+
+        let output = context.start_rendering_sync();
+        output
+            .get_channel_data(0)
+            .iter()
+            .for_each(|&v| assert_float_eq!(v, 0.25, abs <= 0.));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_connect_param_unknown_name() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let src = context.create_constant_source();
+        let gain = context.create_gain();
+        src.connect_param(&gain, "frequency");
+    }
+
     #[test]
     #[should_panic]
     fn test_disconnect_not_existing() {
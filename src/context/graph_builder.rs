@@ -0,0 +1,518 @@
+//! Construction of an audio graph from its description
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+use crate::context::{
+    BaseAudioContext, ConcreteBaseAudioContext, ConnectionDescription, GraphDescription,
+    NodeDescription,
+};
+use crate::node::*;
+use crate::param::AudioParam;
+
+/// A node created by [`BaseAudioContext::build_graph`](super::BaseAudioContext::build_graph)
+///
+/// Match on the variants to access the concrete node, e.g. to start an [`OscillatorNode`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum GraphNode {
+    /// `AudioDestinationNode`
+    Destination(AudioDestinationNode),
+    /// `AudioBufferSourceNode`
+    AudioBufferSource(AudioBufferSourceNode),
+    /// `BiquadFilterNode`
+    BiquadFilter(BiquadFilterNode),
+    /// `ChannelMergerNode`
+    ChannelMerger(ChannelMergerNode),
+    /// `ChannelSplitterNode`
+    ChannelSplitter(ChannelSplitterNode),
+    /// `ConstantSourceNode`
+    ConstantSource(ConstantSourceNode),
+    /// `DelayNode`
+    Delay(DelayNode),
+    /// `DynamicsCompressorNode`
+    DynamicsCompressor(DynamicsCompressorNode),
+    /// `GainNode`
+    Gain(GainNode),
+    /// `OscillatorNode`
+    Oscillator(OscillatorNode),
+    /// `StereoPannerNode`
+    StereoPanner(StereoPannerNode),
+}
+
+impl GraphNode {
+    /// The node as an [`AudioNode`], to connect it or change its channel configuration
+    pub fn as_audio_node(&self) -> &dyn AudioNode {
+        match self {
+            Self::Destination(n) => n,
+            Self::AudioBufferSource(n) => n,
+            Self::BiquadFilter(n) => n,
+            Self::ChannelMerger(n) => n,
+            Self::ChannelSplitter(n) => n,
+            Self::ConstantSource(n) => n,
+            Self::Delay(n) => n,
+            Self::DynamicsCompressor(n) => n,
+            Self::Gain(n) => n,
+            Self::Oscillator(n) => n,
+            Self::StereoPanner(n) => n,
+        }
+    }
+
+    /// The audio params of the node, in the order of the [`GraphDescription`]
+    fn params(&self) -> Vec<&AudioParam> {
+        match self {
+            Self::Destination(_) | Self::ChannelMerger(_) | Self::ChannelSplitter(_) => vec![],
+            Self::AudioBufferSource(n) => vec![n.detune(), n.playback_rate()],
+            Self::BiquadFilter(n) => vec![n.q(), n.detune(), n.frequency(), n.gain()],
+            Self::ConstantSource(n) => vec![n.offset()],
+            Self::Delay(n) => vec![n.delay_time()],
+            Self::DynamicsCompressor(n) => {
+                vec![n.attack(), n.knee(), n.ratio(), n.release(), n.threshold()]
+            }
+            Self::Gain(n) => vec![n.gain()],
+            Self::Oscillator(n) => vec![n.frequency(), n.detune()],
+            Self::StereoPanner(n) => vec![n.pan()],
+        }
+    }
+}
+
+/// The nodes created by [`BaseAudioContext::build_graph`](super::BaseAudioContext::build_graph)
+///
+/// The nodes are identified by their id in the [`GraphDescription`]. Dropping the `AudioGraph`
+/// drops the nodes, like any other node handle.
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Debug, Default)]
+pub struct AudioGraph {
+    nodes: Vec<(u64, GraphNode)>,
+    /// The description the graph was built or last updated with
+    description: GraphDescription,
+}
+
+impl AudioGraph {
+    /// The node with the given id in the description
+    pub fn node(&self, id: u64) -> Option<&GraphNode> {
+        self.nodes.iter().find(|(i, _)| *i == id).map(|(_, n)| n)
+    }
+
+    /// Mutable access to the node with the given id in the description
+    pub fn node_mut(&mut self, id: u64) -> Option<&mut GraphNode> {
+        self.nodes
+            .iter_mut()
+            .find(|(i, _)| *i == id)
+            .map(|(_, n)| n)
+    }
+
+    /// The first node with the given label
+    pub fn node_by_label(&self, label: &str) -> Option<&GraphNode> {
+        self.nodes
+            .iter()
+            .find(|(_, n)| n.as_audio_node().label().as_deref() == Some(label))
+            .map(|(_, n)| n)
+    }
+
+    /// Mutable access to the first node with the given label
+    pub fn node_by_label_mut(&mut self, label: &str) -> Option<&mut GraphNode> {
+        self.nodes
+            .iter_mut()
+            .find(|(_, n)| n.as_audio_node().label().as_deref() == Some(label))
+            .map(|(_, n)| n)
+    }
+
+    /// Iterate over the nodes and their id in the description
+    pub fn nodes(&self) -> impl Iterator<Item = (u64, &GraphNode)> {
+        self.nodes.iter().map(|(i, n)| (*i, n))
+    }
+
+    /// Apply the changes of the given description, while the context keeps rendering
+    ///
+    /// The description is compared with the one the graph was built or last updated with:
+    /// - the nodes with a new id, or with a new type for their id, are created
+    /// - the nodes that are no longer described are disconnected and dropped
+    /// - the changed param values, labels and channel configurations are applied
+    /// - the connections that are no longer described are disconnected, the new ones connected
+    ///
+    /// Returns the ids of the created nodes, they are not started.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`build_graph`](super::BaseAudioContext::build_graph), the graph is
+    /// left unchanged in that case.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a channel configuration or an automation rate of the description is not valid
+    /// for the node
+    pub fn update<C: BaseAudioContext>(
+        &mut self,
+        context: &C,
+        description: &GraphDescription,
+    ) -> Result<Vec<u64>, Box<dyn Error + Send + Sync>> {
+        let previous = &self.description;
+        let previous_node = |id| previous.nodes.iter().find(|n| n.id == id);
+
+        // create the new nodes first, so nothing changes when the description is invalid
+        let mut ids = HashSet::new();
+        let mut kept = HashSet::new();
+        let mut created = Vec::new();
+        for desc in &description.nodes {
+            if !ids.insert(desc.id) {
+                return Err(format!("InvalidStateError - Duplicate node id: {}", desc.id).into());
+            }
+            if previous_node(desc.id).is_some_and(|p| p.node_type == desc.node_type) {
+                kept.insert(desc.id);
+            } else {
+                created.push((desc.id, create_node(context.base(), desc)?));
+            }
+        }
+
+        let params = param_owners(description);
+        let node = |id| match created.iter().find(|(i, _)| *i == id) {
+            Some((_, node)) => Some(node),
+            None => kept.contains(&id).then(|| self.node(id)).flatten(),
+        };
+        for connection in &description.connections {
+            resolve_connection(node, &params, connection)?;
+        }
+
+        // a connection is kept when both its nodes are kept
+        let previous_params = param_owners(previous);
+        let owner = |id| previous_params.get(&id).map_or(id, |&(owner, _)| owner);
+        let unchanged = |c: &&ConnectionDescription| {
+            kept.contains(&c.from)
+                && kept.contains(&owner(c.to))
+                && previous.connections.contains(c)
+                && description.connections.contains(c)
+        };
+
+        for connection in previous.connections.iter().filter(|c| !unchanged(c)) {
+            // the other connections are removed with their source node below
+            if kept.contains(&connection.from) {
+                let (from, to, input) =
+                    resolve_connection(|id| self.node(id), &previous_params, connection)?;
+                from.context().disconnect_if_connected(
+                    from.registration().id(),
+                    connection.output,
+                    to.registration().id(),
+                    input,
+                );
+            }
+        }
+
+        let created_ids = created.iter().map(|(id, _)| *id).collect();
+        let mut removed = std::mem::take(&mut self.nodes);
+        for desc in &description.nodes {
+            let node = match created.iter().position(|(id, _)| *id == desc.id) {
+                Some(index) => {
+                    let (_, node) = created.swap_remove(index);
+                    configure_node(&node, desc, None);
+                    node
+                }
+                None => {
+                    let index = removed.iter().position(|(id, _)| *id == desc.id).unwrap();
+                    let (_, node) = removed.swap_remove(index);
+                    configure_node(&node, desc, previous_node(desc.id));
+                    node
+                }
+            };
+            self.nodes.push((desc.id, node));
+        }
+        for (_, node) in removed {
+            node.as_audio_node().disconnect();
+        }
+
+        for connection in description.connections.iter().filter(|c| !unchanged(c)) {
+            let (from, to, input) = resolve_connection(|id| self.node(id), &params, connection)?;
+            from.connect_from_output_to_input(to, connection.output, input);
+        }
+
+        self.description = description.clone();
+        Ok(created_ids)
+    }
+}
+
+/// The node of the description, its params are not set yet
+fn create_node(
+    context: &ConcreteBaseAudioContext,
+    desc: &NodeDescription,
+) -> Result<GraphNode, Box<dyn Error + Send + Sync>> {
+    let node = match desc.node_type.as_str() {
+        "AudioDestinationNode" => GraphNode::Destination(context.destination()),
+        "AudioBufferSourceNode" => GraphNode::AudioBufferSource(context.create_buffer_source()),
+        "BiquadFilterNode" => GraphNode::BiquadFilter(context.create_biquad_filter()),
+        "ChannelMergerNode" => {
+            GraphNode::ChannelMerger(context.create_channel_merger(desc.number_of_inputs))
+        }
+        "ChannelSplitterNode" => {
+            GraphNode::ChannelSplitter(context.create_channel_splitter(desc.number_of_outputs))
+        }
+        "ConstantSourceNode" => GraphNode::ConstantSource(context.create_constant_source()),
+        "DelayNode" => {
+            let max_delay_time = desc.params.first().map_or(1., |p| p.max_value);
+            GraphNode::Delay(context.create_delay(f64::from(max_delay_time)))
+        }
+        "DynamicsCompressorNode" => {
+            GraphNode::DynamicsCompressor(context.create_dynamics_compressor())
+        }
+        "GainNode" => GraphNode::Gain(context.create_gain()),
+        "OscillatorNode" => GraphNode::Oscillator(context.create_oscillator()),
+        "StereoPannerNode" => GraphNode::StereoPanner(context.create_stereo_panner()),
+        other => {
+            return Err(format!("NotSupportedError - Unsupported node type: {:?}", other).into())
+        }
+    };
+
+    let number_of_params = node.params().len();
+    if number_of_params != desc.params.len() {
+        return Err(format!(
+            "InvalidStateError - {} has {} params, the description has {}",
+            desc.node_type,
+            number_of_params,
+            desc.params.len()
+        )
+        .into());
+    }
+
+    Ok(node)
+}
+
+/// Apply the param values, label and channel configuration of the description that differ from
+/// the previous description of the node
+fn configure_node(node: &GraphNode, desc: &NodeDescription, previous: Option<&NodeDescription>) {
+    for (index, (param, param_desc)) in node.params().into_iter().zip(&desc.params).enumerate() {
+        let previous = previous.and_then(|p| p.params.get(index));
+        if param.automation_rate() != param_desc.automation_rate {
+            param.set_automation_rate(param_desc.automation_rate);
+        }
+        if previous.map_or(true, |p| p.value != param_desc.value) {
+            param.set_value(param_desc.value);
+        }
+    }
+
+    let audio_node = node.as_audio_node();
+    if let Some(label) = &desc.label {
+        if previous.map_or(true, |p| p.label.as_ref() != Some(label)) {
+            audio_node.set_label(label.clone());
+        }
+    }
+
+    // the channel configuration of the destination is determined by the output device
+    if !matches!(node, GraphNode::Destination(_)) {
+        if audio_node.channel_count_mode() != desc.channel_count_mode {
+            audio_node.set_channel_count_mode(desc.channel_count_mode);
+        }
+        if audio_node.channel_interpretation() != desc.channel_interpretation {
+            audio_node.set_channel_interpretation(desc.channel_interpretation);
+        }
+        if audio_node.channel_count() != desc.channel_count {
+            audio_node.set_channel_count(desc.channel_count);
+        }
+    }
+}
+
+/// The owner node and the index of the params of the description, which can be the destination
+/// of a connection
+fn param_owners(description: &GraphDescription) -> HashMap<u64, (u64, usize)> {
+    description
+        .nodes
+        .iter()
+        .flat_map(|node| {
+            node.params
+                .iter()
+                .enumerate()
+                .map(|(index, param)| (param.id, (node.id, index)))
+        })
+        .collect()
+}
+
+/// The source, destination and destination input of the connection, a param has a single input
+fn resolve_connection<'a>(
+    node: impl Fn(u64) -> Option<&'a GraphNode>,
+    params: &HashMap<u64, (u64, usize)>,
+    connection: &ConnectionDescription,
+) -> Result<(&'a dyn AudioNode, &'a dyn AudioNode, usize), Box<dyn Error + Send + Sync>> {
+    let unknown = |id| format!("InvalidAccessError - Unknown node or param id: {}", id);
+
+    let from = node(connection.from)
+        .ok_or_else(|| unknown(connection.from))?
+        .as_audio_node();
+    let (to, input): (&dyn AudioNode, usize) = match node(connection.to) {
+        Some(node) => (node.as_audio_node(), connection.input),
+        None => {
+            let &(owner, index) = params
+                .get(&connection.to)
+                .ok_or_else(|| unknown(connection.to))?;
+            let owner = node(owner).ok_or_else(|| unknown(owner))?;
+            (owner.params()[index], 0)
+        }
+    };
+
+    if connection.output >= from.number_of_outputs() || input >= to.number_of_inputs() {
+        return Err(format!(
+            "IndexSizeError - Invalid ports of connection from {} to {}: {} -> {}",
+            connection.from, connection.to, connection.output, connection.input
+        )
+        .into());
+    }
+
+    Ok((from, to, input))
+}
+
+pub(super) fn build_graph(
+    context: &ConcreteBaseAudioContext,
+    description: &GraphDescription,
+) -> Result<AudioGraph, Box<dyn Error + Send + Sync>> {
+    let mut graph = AudioGraph::default();
+    graph.update(context, description)?;
+    Ok(graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{NodeDescription, OfflineAudioContext};
+
+    fn summary(node: &NodeDescription) -> (String, Option<String>, usize, Vec<f32>) {
+        let values = node.params.iter().map(|p| p.value).collect();
+        (
+            node.node_type.clone(),
+            node.label.clone(),
+            node.channel_count,
+            values,
+        )
+    }
+
+    #[test]
+    fn test_build_graph_roundtrip() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+
+        let osc = context.create_oscillator();
+        osc.set_label(String::from("lead"));
+        osc.frequency().set_value(220.);
+        let gain = context.create_gain();
+        gain.gain().set_value(0.25);
+        gain.set_channel_count(1);
+        let delay = context.create_delay(2.);
+        delay.delay_time().set_value(0.1);
+        let lfo = context.create_constant_source();
+
+        osc.connect(&gain);
+        gain.connect(&delay);
+        delay.connect(&context.destination());
+        lfo.connect(gain.gain());
+
+        let description = context.export_graph();
+        let types: Vec<_> = description
+            .nodes
+            .iter()
+            .map(|n| n.node_type.as_str())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "AudioDestinationNode",
+                "OscillatorNode",
+                "GainNode",
+                "DelayNode",
+                "ConstantSourceNode"
+            ]
+        );
+        assert_eq!(description.connections.len(), 4);
+
+        let other = OfflineAudioContext::new(1, 128, 48_000.);
+        let mut graph = other.build_graph(&description).unwrap();
+        let rebuilt = other.export_graph();
+
+        let expected: Vec<_> = description.nodes.iter().map(summary).collect();
+        let result: Vec<_> = rebuilt.nodes.iter().map(summary).collect();
+        assert_eq!(result, expected);
+        assert_eq!(rebuilt.connections.len(), 4);
+        assert_eq!(rebuilt.nodes[3].params[0].max_value, 2.);
+
+        match graph.node_by_label_mut("lead") {
+            Some(GraphNode::Oscillator(osc)) => osc.start(),
+            _ => panic!("lead oscillator not found"),
+        }
+        let gain_id = description.nodes[2].id;
+        assert!(matches!(graph.node(gain_id), Some(GraphNode::Gain(_))));
+        assert_eq!(graph.nodes().count(), 5);
+    }
+
+    #[test]
+    fn test_build_graph_errors() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let gain = context.create_gain();
+        gain.connect(&context.destination());
+        let description = context.export_graph();
+
+        let mut unsupported = description.clone();
+        unsupported.nodes[1].node_type = String::from("FooNode");
+        let error = context.build_graph(&unsupported).unwrap_err();
+        assert!(error.to_string().starts_with("NotSupportedError"));
+
+        let mut unknown = description.clone();
+        unknown.connections[0].to = 1234;
+        let error = context.build_graph(&unknown).unwrap_err();
+        assert!(error.to_string().starts_with("InvalidAccessError"));
+
+        let mut invalid_port = description;
+        invalid_port.connections[0].output = 1;
+        let error = context.build_graph(&invalid_port).unwrap_err();
+        assert!(error.to_string().starts_with("IndexSizeError"));
+    }
+
+    #[test]
+    fn test_update_graph() {
+        let source = OfflineAudioContext::new(1, 128, 48_000.);
+        let osc = source.create_oscillator();
+        let gain = source.create_gain();
+        osc.connect(&gain);
+        gain.connect(&source.destination());
+        let first = source.export_graph();
+        let (osc_id, gain_id) = (first.nodes[1].id, first.nodes[2].id);
+
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let mut graph = context.build_graph(&first).unwrap();
+
+        // replace the oscillator by a constant source, and change the gain
+        let mut second = first.clone();
+        second.nodes[1].node_type = String::from("ConstantSourceNode");
+        second.nodes[1].params.truncate(1);
+        second.nodes[2].params[0].value = 0.5;
+        let created = graph.update(&context, &second).unwrap();
+        assert_eq!(created, [osc_id]);
+        assert!(matches!(
+            graph.node(osc_id),
+            Some(GraphNode::ConstantSource(_))
+        ));
+        match graph.node(gain_id) {
+            Some(GraphNode::Gain(gain)) => assert_eq!(gain.gain().value(), 0.5),
+            _ => panic!("gain node not found"),
+        }
+        let exported = context.export_graph();
+        assert_eq!(exported.nodes.len(), 3);
+        assert_eq!(exported.connections.len(), 2);
+
+        // an invalid description leaves the graph unchanged
+        let mut invalid = second.clone();
+        invalid.nodes.remove(2);
+        let error = graph.update(&context, &invalid).unwrap_err();
+        assert!(error.to_string().starts_with("InvalidAccessError"));
+        assert_eq!(graph.nodes().count(), 3);
+
+        // remove the gain
+        let mut third = second;
+        third.nodes.remove(2);
+        third.connections = vec![ConnectionDescription {
+            from: osc_id,
+            output: 0,
+            to: third.nodes[0].id,
+            input: 0,
+        }];
+        assert!(graph.update(&context, &third).unwrap().is_empty());
+        assert!(graph.node(gain_id).is_none());
+        let exported = context.export_graph();
+        assert_eq!(exported.nodes.len(), 2);
+        assert_eq!(exported.connections.len(), 1);
+    }
+}
@@ -0,0 +1,413 @@
+//! Description of the topology of the audio graph
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use crate::context::AudioNodeId;
+use crate::node::{ChannelConfig, ChannelCountMode, ChannelInterpretation};
+use crate::param::{AudioParam, AutomationRate, WeakAudioParam};
+
+/// Description of the audio graph of a context, see
+/// [`BaseAudioContext::export_graph`](super::BaseAudioContext::export_graph)
+///
+/// With the `serde` feature enabled, the description can be serialized, e.g. to JSON, to persist
+/// or visualize the graph. Use [`GraphDescription::to_dot`] to render it with Graphviz.
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphDescription {
+    /// The nodes of the graph, in order of creation
+    pub nodes: Vec<NodeDescription>,
+    /// The connections between the nodes, and from the nodes to the params
+    pub connections: Vec<ConnectionDescription>,
+}
+
+/// Description of an audio node, see [`GraphDescription`]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeDescription {
+    /// Identifier of the node, unique among the live nodes of the context
+    pub id: u64,
+    /// Type of the node, e.g. `GainNode`
+    pub node_type: String,
+    /// Label of the node, see [`AudioNode::set_label`](crate::node::AudioNode::set_label)
+    pub label: Option<String>,
+    /// Number of inputs of the node
+    pub number_of_inputs: usize,
+    /// Number of outputs of the node
+    pub number_of_outputs: usize,
+    /// Channel count of the node
+    pub channel_count: usize,
+    /// Channel count mode of the node
+    pub channel_count_mode: ChannelCountMode,
+    /// Channel interpretation of the node
+    pub channel_interpretation: ChannelInterpretation,
+    /// The audio params of the node, in order of creation
+    pub params: Vec<ParamDescription>,
+}
+
+/// Description of an audio param, see [`GraphDescription`]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParamDescription {
+    /// Identifier of the param, it can be the destination of a connection
+    pub id: u64,
+    /// Value of the param at the last rendered quantum
+    pub value: f32,
+    /// Default value of the param
+    pub default_value: f32,
+    /// Minimum value of the param
+    pub min_value: f32,
+    /// Maximum value of the param
+    pub max_value: f32,
+    /// Automation rate of the param
+    pub automation_rate: AutomationRate,
+}
+
+/// Description of a connection, see [`GraphDescription`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConnectionDescription {
+    /// Identifier of the source node
+    pub from: u64,
+    /// Output of the source node
+    pub output: usize,
+    /// Identifier of the destination node or param
+    pub to: u64,
+    /// Input of the destination node, 0 for a param
+    pub input: usize,
+}
+
+impl GraphDescription {
+    /// Render the graph in the Graphviz DOT language
+    ///
+    /// Connections to an audio param are drawn as dashed edges to the node owning the param.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+
+        for node in &self.nodes {
+            let mut label = format!("{} #{}", node.node_type, node.id);
+            if let Some(name) = &node.label {
+                let _ = write!(label, "\\n{}", name.replace('"', "\\\""));
+            }
+            let _ = writeln!(dot, "    n{} [label=\"{}\"];", node.id, label);
+        }
+
+        for connection in &self.connections {
+            let param = self.nodes.iter().find_map(|node| {
+                node.params
+                    .iter()
+                    .position(|p| p.id == connection.to)
+                    .map(|index| (node.id, index))
+            });
+            let _ = match param {
+                Some((owner, index)) => writeln!(
+                    dot,
+                    "    n{} -> n{} [label=\"{} -> param {}\", style=dashed];",
+                    connection.from, owner, connection.output, index
+                ),
+                None => writeln!(
+                    dot,
+                    "    n{} -> n{} [label=\"{} -> {}\"];",
+                    connection.from, connection.to, connection.output, connection.input
+                ),
+            };
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// A connection (from node, output port, to node, input port)
+pub(crate) type Connection = (AudioNodeId, usize, AudioNodeId, usize);
+
+/// A node known to the control thread
+#[derive(Debug)]
+struct RegisteredNode {
+    node_type: &'static str,
+    number_of_inputs: usize,
+    number_of_outputs: usize,
+    channel_config: ChannelConfig,
+    /// The node owning this param and the state of the param, if this node is an audio param
+    param: Option<(AudioNodeId, WeakAudioParam)>,
+    /// The node this node is an internal part of, e.g. the reader of a `DelayNode`
+    part_of: Option<AudioNodeId>,
+    /// Whether the node breaks the cycles it is part of, i.e. the writer of a `DelayNode`
+    cycle_breaker: bool,
+}
+
+/// Administration of the nodes that have a handle on the control thread, to describe the graph
+#[derive(Debug, Default)]
+pub(crate) struct NodeRegistry {
+    nodes: Mutex<BTreeMap<u64, RegisteredNode>>,
+}
+
+impl NodeRegistry {
+    pub fn add_node(
+        &self,
+        id: AudioNodeId,
+        node_type: &'static str,
+        number_of_inputs: usize,
+        number_of_outputs: usize,
+        channel_config: ChannelConfig,
+    ) {
+        // strip the module path and the generic arguments
+        let node_type = node_type.split('<').next().unwrap();
+        let node_type = node_type.rsplit("::").next().unwrap();
+
+        let node = RegisteredNode {
+            node_type,
+            number_of_inputs,
+            number_of_outputs,
+            channel_config,
+            param: None,
+            part_of: None,
+            cycle_breaker: false,
+        };
+        self.nodes.lock().unwrap().insert(id.0, node);
+    }
+
+    /// Mark the node as the audio param of the given owner node
+    pub fn set_param(&self, id: AudioNodeId, owner: AudioNodeId, param: WeakAudioParam) {
+        if let Some(node) = self.nodes.lock().unwrap().get_mut(&id.0) {
+            node.param = Some((owner, param));
+        }
+    }
+
+    /// Mark the node as an internal part of the given node, they are described as a single node
+    pub fn set_part_of(&self, id: AudioNodeId, owner: AudioNodeId) {
+        if let Some(node) = self.nodes.lock().unwrap().get_mut(&id.0) {
+            node.part_of = Some(owner);
+        }
+    }
+
+    /// Mark the node as able to break the cycles it is part of
+    pub fn set_cycle_breaker(&self, id: AudioNodeId) {
+        if let Some(node) = self.nodes.lock().unwrap().get_mut(&id.0) {
+            node.cycle_breaker = true;
+        }
+    }
+
+    pub fn remove_node(&self, id: AudioNodeId) {
+        self.nodes.lock().unwrap().remove(&id.0);
+    }
+
+    /// The params of the given node with a live handle, in order of creation
+    pub fn params_of(&self, owner: AudioNodeId) -> Vec<AudioParam> {
+        let registry = self.nodes.lock().unwrap();
+        let part_of = |id: AudioNodeId| registry.get(&id.0).and_then(|node| node.part_of);
+
+        registry
+            .values()
+            .filter_map(|node| match &node.param {
+                Some((id, param)) if *id == owner || part_of(*id) == Some(owner) => param.upgrade(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The audio param with the given id, if it has a live handle
+    #[cfg(feature = "inspect")]
+    pub fn param(&self, id: AudioNodeId) -> Option<AudioParam> {
+        let registry = self.nodes.lock().unwrap();
+        let (_, param) = registry.get(&id.0)?.param.as_ref()?;
+        param.upgrade()
+    }
+
+    /// Find the cycle without cycle breaker that a connection from `from` to `to` would close
+    ///
+    /// Returns the nodes of the cycle, from `to` to `from`, following the given connections and
+    /// the connections of the params to their node.
+    pub fn find_cycle(
+        &self,
+        connections: impl Iterator<Item = Connection>,
+        from: AudioNodeId,
+        to: AudioNodeId,
+    ) -> Option<Vec<AudioNodeId>> {
+        let registry = self.nodes.lock().unwrap();
+        let cycle_breaker =
+            |id: AudioNodeId| registry.get(&id.0).is_some_and(|node| node.cycle_breaker);
+
+        if cycle_breaker(from) || cycle_breaker(to) {
+            return None;
+        }
+
+        let mut edges: HashMap<AudioNodeId, Vec<AudioNodeId>> = HashMap::new();
+        connections.for_each(|(from, _output, to, _input)| edges.entry(from).or_default().push(to));
+        registry.iter().for_each(|(&id, node)| {
+            if let Some((owner, _)) = &node.param {
+                edges.entry(AudioNodeId(id)).or_default().push(*owner);
+            }
+        });
+
+        // depth first search from `to`, keeping track of the node each node is reached from
+        let mut reached_from = HashMap::from([(to, to)]);
+        let mut stack = vec![to];
+        while let Some(id) = stack.pop() {
+            if id == from {
+                let mut cycle = vec![from];
+                let mut current = from;
+                while current != to {
+                    current = reached_from[&current];
+                    cycle.push(current);
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+
+            for &next in edges.get(&id).into_iter().flatten() {
+                if !cycle_breaker(next) && !reached_from.contains_key(&next) {
+                    reached_from.insert(next, id);
+                    stack.push(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Short description of the node for error messages, e.g. `GainNode 3 "master"`
+    pub fn describe_node(&self, id: AudioNodeId, label: Option<String>) -> String {
+        let registry = self.nodes.lock().unwrap();
+        let node_type = registry
+            .get(&id.0)
+            .map_or("AudioNode", |node| node.node_type);
+        match label {
+            Some(label) => format!("{node_type} {} {label:?}", id.0),
+            None => format!("{node_type} {}", id.0),
+        }
+    }
+
+    /// Describe the registered nodes, except the given hidden ones, their params and the given
+    /// connections between them
+    pub fn describe(
+        &self,
+        connections: impl Iterator<Item = Connection>,
+        hidden: impl Fn(AudioNodeId) -> bool,
+        label: impl Fn(AudioNodeId) -> Option<String>,
+    ) -> GraphDescription {
+        let registry = self.nodes.lock().unwrap();
+        let part_of = |id: AudioNodeId| registry.get(&id.0).and_then(|node| node.part_of);
+        let resolve = |id: AudioNodeId| part_of(id).unwrap_or(id).0;
+
+        let nodes = registry
+            .iter()
+            .filter(|(&id, node)| {
+                node.param.is_none() && node.part_of.is_none() && !hidden(AudioNodeId(id))
+            })
+            .map(|(&id, node)| {
+                let params = registry
+                    .iter()
+                    .filter_map(|(&param_id, param)| match &param.param {
+                        Some((owner, inner)) if resolve(*owner) == id => {
+                            Some(inner.raw_parts().description(param_id))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+
+                NodeDescription {
+                    id,
+                    node_type: node.node_type.to_string(),
+                    label: label(AudioNodeId(id)),
+                    number_of_inputs: node.number_of_inputs,
+                    number_of_outputs: node.number_of_outputs,
+                    channel_count: node.channel_config.count(),
+                    channel_count_mode: node.channel_config.count_mode(),
+                    channel_interpretation: node.channel_config.interpretation(),
+                    params,
+                }
+            })
+            .collect();
+
+        let mut connections: Vec<_> = connections
+            .filter(|&(from, _output, to, _input)| !hidden(from) && !hidden(to))
+            // skip the connections between the parts of a node
+            .filter(|&(from, _output, to, _input)| part_of(to) != Some(from))
+            .map(|(from, output, to, input)| ConnectionDescription {
+                from: resolve(from),
+                output,
+                to: resolve(to),
+                input,
+            })
+            .collect();
+        connections.sort_by_key(|c| (c.from, c.output, c.to, c.input));
+
+        GraphDescription { nodes, connections }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+
+    #[test]
+    fn test_export_graph() {
+        let context = OfflineAudioContext::new(2, 128, 48_000.);
+
+        let mut osc = context.create_oscillator();
+        let lfo = context.create_oscillator();
+        let gain = context.create_gain();
+        gain.set_label(String::from("master"));
+        gain.set_channel_count(1);
+        osc.connect(&gain);
+        lfo.connect(gain.gain());
+        gain.connect(&context.destination());
+        osc.start();
+
+        let graph = context.export_graph();
+        let types: Vec<_> = graph.nodes.iter().map(|n| n.node_type.as_str()).collect();
+        assert_eq!(
+            types,
+            [
+                "AudioDestinationNode",
+                "OscillatorNode",
+                "OscillatorNode",
+                "GainNode"
+            ]
+        );
+
+        let dest = &graph.nodes[0];
+        assert_eq!(dest.channel_count, 2);
+        assert!(dest.params.is_empty());
+
+        let osc_description = &graph.nodes[1];
+        assert_eq!(osc_description.params.len(), 2); // frequency and detune
+        assert_eq!(osc_description.params[0].default_value, 440.);
+
+        let gain_description = &graph.nodes[3];
+        assert_eq!(gain_description.label.as_deref(), Some("master"));
+        assert_eq!(gain_description.channel_count, 1);
+        assert_eq!(gain_description.number_of_inputs, 1);
+        let gain_param = &gain_description.params[0];
+        assert_eq!(gain_param.default_value, 1.);
+
+        let edges: Vec<_> = graph.connections.iter().map(|c| (c.from, c.to)).collect();
+        assert_eq!(
+            edges,
+            [
+                (osc_description.id, gain_description.id),
+                (graph.nodes[2].id, gain_param.id),
+                (gain_description.id, dest.id),
+            ]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains(&format!(
+            "n{} [label=\"GainNode #{}\\nmaster\"];",
+            gain_description.id, gain_description.id
+        )));
+        assert!(dot.contains("-> param 0\", style=dashed];"));
+
+        // dropped nodes are removed from the description
+        drop((osc, lfo));
+        let graph = context.export_graph();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.connections.len(), 1);
+    }
+}
@@ -0,0 +1,231 @@
+//! Live reloading of a graph description file
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::context::{
+    AudioGraph, BaseAudioContext, ConcreteBaseAudioContext, GraphDescription, GraphNode,
+};
+use crate::node::AudioScheduledSourceNode;
+
+/// Parser of the watched file
+type Parser = dyn Fn(&[u8]) -> Result<GraphDescription, Box<dyn Error + Send + Sync>> + Send + Sync;
+
+/// Watch a graph description file, e.g. a patch edited by a sound designer, and apply its
+/// changes to the context while it keeps playing
+///
+/// The file is checked for changes with [`GraphWatcher::poll`], and the changes are applied with
+/// [`AudioGraph::update`]: only the nodes, params and connections that differ from the previous
+/// version of the file are touched. The `OscillatorNode`s and `ConstantSourceNode`s are started
+/// when they are created.
+///
+/// The format of the file is up to the application, e.g. JSON parsed with `serde_json` and the
+/// `serde` feature.
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, GraphDescription, GraphWatcher};
+/// # fn parse(_: &[u8]) -> Result<GraphDescription, std::io::Error> { unimplemented!() }
+///
+/// let context = AudioContext::default();
+/// // e.g. `serde_json::from_slice::<GraphDescription>`
+/// let mut watcher = GraphWatcher::new(&context, "patch.json", parse).unwrap();
+///
+/// loop {
+///     if let Err(e) = watcher.poll() {
+///         eprintln!("cannot reload the patch: {e}");
+///     }
+///     std::thread::sleep(std::time::Duration::from_millis(200));
+/// }
+/// ```
+///
+/// This type is not part of the Web Audio API specification.
+pub struct GraphWatcher {
+    context: ConcreteBaseAudioContext,
+    path: PathBuf,
+    parse: Box<Parser>,
+    /// Modification time of the file at the last reload
+    modified: Option<SystemTime>,
+    graph: AudioGraph,
+}
+
+impl std::fmt::Debug for GraphWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GraphWatcher")
+            .field("path", &self.path)
+            .field("modified", &self.modified)
+            .field("graph", &self.graph)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GraphWatcher {
+    /// Build the graph of the file at the given path, to be watched for changes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or if the graph cannot be built,
+    /// see [`BaseAudioContext::build_graph`].
+    pub fn new<C, P, F, E>(
+        context: &C,
+        path: P,
+        parse: F,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>>
+    where
+        C: BaseAudioContext,
+        P: Into<PathBuf>,
+        F: Fn(&[u8]) -> Result<GraphDescription, E> + Send + Sync + 'static,
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        let mut watcher = Self {
+            context: context.base().clone(),
+            path: path.into(),
+            parse: Box::new(move |bytes| parse(bytes).map_err(Into::into)),
+            modified: None,
+            graph: AudioGraph::default(),
+        };
+        watcher.reload()?;
+
+        Ok(watcher)
+    }
+
+    /// Apply the changes of the file if it was modified since the last reload
+    ///
+    /// Returns whether the file was reloaded. Call this regularly from the control thread, e.g.
+    /// a few times per second.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or if the changes cannot be
+    /// applied. The graph is left unchanged, and the file is reloaded at its next modification.
+    pub fn poll(&mut self) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let modified = std::fs::metadata(&self.path)?.modified()?;
+        if self.modified == Some(modified) {
+            return Ok(false);
+        }
+
+        self.reload()?;
+        Ok(true)
+    }
+
+    fn reload(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // do not retry a broken file until it is saved again
+        self.modified = Some(std::fs::metadata(&self.path)?.modified()?);
+        let bytes = std::fs::read(&self.path)?;
+        let description = (self.parse)(&bytes)?;
+
+        for id in self.graph.update(&self.context, &description)? {
+            match self.graph.node_mut(id) {
+                Some(GraphNode::ConstantSource(node)) => node.start(),
+                Some(GraphNode::Oscillator(node)) => node.start(),
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The nodes of the graph, e.g. to set the buffer of an `AudioBufferSourceNode`
+    pub fn graph(&self) -> &AudioGraph {
+        &self.graph
+    }
+
+    /// Mutable access to the nodes of the graph
+    pub fn graph_mut(&mut self) -> &mut AudioGraph {
+        &mut self.graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioNode;
+
+    /// Types of the nodes of the connections in the description
+    fn edges(description: &GraphDescription) -> Vec<(&str, &str)> {
+        let node_type = |id| {
+            description
+                .nodes
+                .iter()
+                .find(|n| n.id == id || n.params.iter().any(|p| p.id == id))
+                .map_or("", |n| n.node_type.as_str())
+        };
+        let mut edges: Vec<_> = description
+            .connections
+            .iter()
+            .map(|c| (node_type(c.from), node_type(c.to)))
+            .collect();
+        edges.sort_unstable();
+        edges
+    }
+
+    #[test]
+    fn test_graph_watcher() {
+        // two versions of a patch
+        let source = OfflineAudioContext::new(1, 128, 48_000.);
+        let osc = source.create_oscillator();
+        let gain = source.create_gain();
+        osc.connect(&gain);
+        gain.connect(&source.destination());
+        let first = source.export_graph();
+
+        drop(osc);
+        let constant = source.create_constant_source();
+        constant.connect(&gain);
+        gain.gain().set_value(0.5);
+        gain.disconnect();
+        let delay = source.create_delay(1.);
+        gain.connect(&delay);
+        delay.connect(&source.destination());
+        let second = source.export_graph();
+        let gain_id = second
+            .nodes
+            .iter()
+            .find(|n| n.node_type == "GainNode")
+            .unwrap()
+            .id;
+
+        // the file holds the index of the version
+        let path = std::env::temp_dir().join("web-audio-api-graph-watcher.txt");
+        let start = SystemTime::now();
+        let save = |contents: &str, seconds: u64| {
+            std::fs::write(&path, contents).unwrap();
+            let file = File::options().write(true).open(&path).unwrap();
+            file.set_modified(start + Duration::from_secs(seconds))
+                .unwrap();
+        };
+        let versions = [first.clone(), second.clone()];
+        let parse = move |bytes: &[u8]| match bytes {
+            b"0" => Ok(versions[0].clone()),
+            b"1" => Ok(versions[1].clone()),
+            _ => Err("invalid patch"),
+        };
+
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        save("0", 0);
+        let mut watcher = GraphWatcher::new(&context, &path, parse).unwrap();
+        assert_eq!(edges(&context.export_graph()), edges(&first));
+        assert!(!watcher.poll().unwrap());
+
+        save("1", 1);
+        assert!(watcher.poll().unwrap());
+        assert_eq!(edges(&context.export_graph()), edges(&second));
+        assert_eq!(context.export_graph().nodes.len(), 4);
+        match watcher.graph().node(gain_id) {
+            Some(GraphNode::Gain(gain)) => assert_eq!(gain.gain().value(), 0.5),
+            _ => panic!("gain node not found"),
+        }
+
+        // a broken file is not reloaded until it is saved again
+        save("x", 2);
+        assert!(watcher.poll().is_err());
+        assert!(!watcher.poll().unwrap());
+        assert_eq!(edges(&context.export_graph()), edges(&second));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
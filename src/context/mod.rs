@@ -1,4 +1,5 @@
-//! The `BaseAudioContext` interface and the `AudioContext` and `OfflineAudioContext` types
+//! The `BaseAudioContext` interface and the `AudioContext`, `OfflineAudioContext` and
+//! `PullAudioContext` types
 
 use std::{any::Any, ops::Range};
 
@@ -8,12 +9,24 @@ pub use base::*;
 mod concrete_base;
 pub use concrete_base::*;
 
+mod graph_builder;
+pub use graph_builder::*;
+
+mod graph_description;
+pub use graph_description::*;
+
+mod graph_watcher;
+pub use graph_watcher::*;
+
 mod offline;
 pub use offline::*;
 
 mod online;
 pub use online::*;
 
+mod pull;
+pub use pull::*;
+
 // magic node values
 /// Destination node id is always at index 0
 pub(crate) const DESTINATION_NODE_ID: AudioNodeId = AudioNodeId(0);
@@ -60,7 +73,11 @@ impl From<&AudioParamId> for AudioNodeId {
 }
 
 /// Describes the current state of the `AudioContext`
+///
+/// More states may be added in a minor release, so matching on this enum requires a wildcard
+/// arm.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum AudioContextState {
     /// This context is currently suspended (context time is not proceeding,
     /// audio hardware may be powered down/released).
@@ -70,6 +87,10 @@ pub enum AudioContextState {
     /// This context has been released, and can no longer be used to process audio.
     /// All system audio resources have been released.
     Closed,
+    /// The audio device reported an error, e.g. it has been unplugged or claimed by another
+    /// application. Context time is not proceeding until the device renders again, see
+    /// [`AudioContextInterruptionPolicy`].
+    Interrupted,
 }
 
 impl From<u8> for AudioContextState {
@@ -78,6 +99,7 @@ impl From<u8> for AudioContextState {
             0 => Self::Suspended,
             1 => Self::Running,
             2 => Self::Closed,
+            3 => Self::Interrupted,
             _ => unreachable!(),
         }
     }
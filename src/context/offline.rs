@@ -1,5 +1,6 @@
 //! The `OfflineAudioContext` type
 
+use std::io::Write;
 use std::sync::atomic::{AtomicU64, AtomicU8};
 use std::sync::{Arc, Mutex};
 
@@ -21,6 +22,132 @@ use futures_util::SinkExt as _;
 pub(crate) type OfflineAudioContextCallback =
     dyn FnOnce(&mut OfflineAudioContext) + Send + Sync + 'static;
 
+/// Sample format of the WAV files written by [`OfflineAudioContext::render_to_writer`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum WavFormat {
+    /// 16-bit signed integer PCM
+    Int16,
+    /// 24-bit signed integer PCM
+    Int24,
+    /// 32-bit IEEE float, lossless
+    #[default]
+    Float32,
+}
+
+impl WavFormat {
+    fn bytes_per_sample(self) -> usize {
+        match self {
+            Self::Int16 => 2,
+            Self::Int24 => 3,
+            Self::Float32 => 4,
+        }
+    }
+}
+
+/// Streaming WAV encoder, the length of the file must be known upfront
+struct WavEncoder<W: Write> {
+    writer: W,
+    format: WavFormat,
+    /// encoded bytes of the current quantum, reused across quanta
+    buffer: Vec<u8>,
+    data_len: usize,
+}
+
+impl<W: Write> WavEncoder<W> {
+    fn new(
+        mut writer: W,
+        format: WavFormat,
+        number_of_channels: usize,
+        length: usize,
+        sample_rate: f32,
+    ) -> std::io::Result<Self> {
+        let bytes_per_sample = format.bytes_per_sample();
+        let block_align = number_of_channels * bytes_per_sample;
+
+        // the data chunk is padded to an even size
+        let data_len = length * block_align;
+        let padded_data_len = data_len + data_len % 2;
+
+        // non PCM formats require the cbSize field and a fact chunk
+        let (format_tag, fmt_len, fact_len) = match format {
+            WavFormat::Int16 | WavFormat::Int24 => (1_u16, 16, 0),
+            WavFormat::Float32 => (3_u16, 18, 12),
+        };
+        let riff_len = 4 + 8 + fmt_len + fact_len + 8 + padded_data_len;
+        let riff_len = u32::try_from(riff_len).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "rendered audio is too long for a WAV file",
+            )
+        })?;
+
+        let sample_rate = sample_rate.round() as u32;
+        let mut header = vec![];
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&riff_len.to_le_bytes());
+        header.extend_from_slice(b"WAVE");
+        header.extend_from_slice(b"fmt ");
+        header.extend_from_slice(&(fmt_len as u32).to_le_bytes());
+        header.extend_from_slice(&format_tag.to_le_bytes());
+        header.extend_from_slice(&(number_of_channels as u16).to_le_bytes());
+        header.extend_from_slice(&sample_rate.to_le_bytes());
+        header.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+        header.extend_from_slice(&(block_align as u16).to_le_bytes());
+        header.extend_from_slice(&(8 * bytes_per_sample as u16).to_le_bytes());
+        if fact_len > 0 {
+            header.extend_from_slice(&0_u16.to_le_bytes());
+            header.extend_from_slice(b"fact");
+            header.extend_from_slice(&4_u32.to_le_bytes());
+            header.extend_from_slice(&(length as u32).to_le_bytes());
+        }
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&(data_len as u32).to_le_bytes());
+        writer.write_all(&header)?;
+
+        Ok(Self {
+            writer,
+            format,
+            buffer: Vec::with_capacity(RENDER_QUANTUM_SIZE * block_align),
+            data_len,
+        })
+    }
+
+    /// Encode and write the interleaved frames of a quantum
+    fn write_quantum(&mut self, channels: &[Vec<f32>]) -> std::io::Result<()> {
+        self.buffer.clear();
+
+        for i in 0..channels[0].len() {
+            for channel in channels {
+                let value = channel[i];
+                match self.format {
+                    WavFormat::Int16 => {
+                        let value = (value * 32_768.).round().clamp(-32_768., 32_767.) as i16;
+                        self.buffer.extend_from_slice(&value.to_le_bytes());
+                    }
+                    WavFormat::Int24 => {
+                        let value =
+                            (value * 8_388_608.).round().clamp(-8_388_608., 8_388_607.) as i32;
+                        self.buffer.extend_from_slice(&value.to_le_bytes()[..3]);
+                    }
+                    WavFormat::Float32 => {
+                        self.buffer.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+            }
+        }
+
+        self.writer.write_all(&self.buffer)
+    }
+
+    /// Write the padding byte if needed and flush the writer
+    fn finish(mut self) -> std::io::Result<()> {
+        if self.data_len % 2 == 1 {
+            self.writer.write_all(&[0])?;
+        }
+        self.writer.flush()
+    }
+}
+
 /// The `OfflineAudioContext` doesn't render the audio to the device hardware; instead, it generates
 /// it, as fast as it can, and outputs the result to an `AudioBuffer`.
 // the naming comes from the web audio specification
@@ -182,6 +309,71 @@ impl OfflineAudioContext {
         result
     }
 
+    /// Given the current connections and scheduled changes, renders the audio as a WAV file
+    /// into the provided writer
+    ///
+    /// Each render quantum is encoded and written as soon as it is rendered, so the memory usage
+    /// does not depend on the length of the context, e.g. to bounce long sessions to disk. The
+    /// writer is not buffered by this method, use a [`BufWriter`](std::io::BufWriter) for
+    /// unbuffered sinks such as files. As no `AudioBuffer` is rendered, the `complete` event is
+    /// not dispatched.
+    ///
+    /// This function will block the current thread until the rendering is done. It will only
+    /// adhere to scheduled suspensions via [`Self::suspend_sync`] and will ignore those provided
+    /// via [`Self::suspend`].
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rendered audio is too long for a WAV file (4 GB), or if writing
+    /// fails, in which case the rendering is stopped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this method or one of the `start_rendering` methods has already been called
+    pub fn render_to_writer<W: Write>(
+        &mut self,
+        writer: W,
+        format: WavFormat,
+    ) -> std::io::Result<()> {
+        let renderer = self
+            .renderer
+            .lock()
+            .unwrap()
+            .take()
+            .expect("InvalidStateError - Cannot call `startRendering` twice");
+
+        let OfflineAudioContextRenderer {
+            renderer,
+            suspend_callbacks,
+            event_loop,
+            ..
+        } = renderer;
+
+        let mut encoder = WavEncoder::new(
+            writer,
+            format,
+            self.base.max_channel_count(),
+            self.length,
+            self.base.sample_rate(),
+        )?;
+
+        self.base.set_state(AudioContextState::Running);
+
+        let result = renderer.render_quanta_sync(self, suspend_callbacks, &event_loop, |quantum| {
+            encoder.write_quantum(quantum)
+        });
+
+        self.base.set_state(AudioContextState::Closed);
+
+        // spin the event loop once more to handle the statechange events
+        event_loop.handle_pending_events();
+
+        result?;
+        encoder.finish()
+    }
+
     /// Given the current connections and scheduled changes, starts rendering audio.
     ///
     /// Rendering is purely CPU bound and contains no `await` points, so calling this method will
@@ -456,6 +648,84 @@ mod tests {
         assert_eq!(context.state(), AudioContextState::Closed);
     }
 
+    #[test]
+    fn test_render_to_writer() {
+        let mut context = OfflineAudioContext::new(2, 300, 44_100.);
+        let mut src = context.create_constant_source();
+        src.offset().set_value(0.5);
+        src.connect(&context.destination());
+        src.start();
+
+        let mut wav = vec![];
+        context
+            .render_to_writer(&mut wav, WavFormat::Int16)
+            .unwrap();
+        assert_eq!(context.state(), AudioContextState::Closed);
+
+        assert_eq!(wav.len(), 44 + 300 * 2 * 2);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[4..8], &(wav.len() as u32 - 8).to_le_bytes());
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(&wav[20..22], &1_u16.to_le_bytes()); // PCM
+        assert_eq!(&wav[22..24], &2_u16.to_le_bytes()); // channels
+        assert_eq!(&wav[24..28], &44_100_u32.to_le_bytes());
+        assert_eq!(&wav[34..36], &16_u16.to_le_bytes()); // bits per sample
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(&wav[40..44], &(300_u32 * 2 * 2).to_le_bytes());
+        wav[44..].chunks(2).for_each(|sample| {
+            assert_eq!(sample, &16_384_i16.to_le_bytes());
+        });
+    }
+
+    #[test]
+    fn test_render_to_writer_float() {
+        let mut context = OfflineAudioContext::new(1, 129, 48_000.);
+        let mut src = context.create_constant_source();
+        src.offset().set_value(0.25);
+        src.connect(&context.destination());
+        src.start();
+
+        let mut wav = vec![];
+        context
+            .render_to_writer(&mut wav, WavFormat::Float32)
+            .unwrap();
+
+        // fmt chunk with cbSize, fact chunk
+        assert_eq!(wav.len(), 58 + 129 * 4);
+        assert_eq!(&wav[20..22], &3_u16.to_le_bytes()); // IEEE float
+        assert_eq!(&wav[38..42], b"fact");
+        assert_eq!(&wav[46..50], &129_u32.to_le_bytes());
+        assert_eq!(&wav[50..54], b"data");
+        wav[58..].chunks(4).for_each(|sample| {
+            assert_eq!(sample, &0.25_f32.to_le_bytes());
+        });
+    }
+
+    #[test]
+    fn test_render_to_writer_error() {
+        struct FailingWriter(usize);
+
+        impl Write for FailingWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                if self.0 == 0 {
+                    return Err(std::io::ErrorKind::WriteZero.into());
+                }
+                self.0 -= 1;
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        // header and first quantum succeed
+        let mut context = OfflineAudioContext::new(1, 1280, 44_100.);
+        let result = context.render_to_writer(FailingWriter(2), WavFormat::Int24);
+        assert!(result.is_err());
+        assert_eq!(context.state(), AudioContextState::Closed);
+    }
+
     #[test]
     #[should_panic]
     fn render_twice_panics() {
@@ -587,6 +857,36 @@ mod tests {
         assert!(changed.load(Ordering::Relaxed));
     }
 
+    #[test]
+    fn test_statechange_listeners() {
+        let mut context = OfflineAudioContext::new(2, 555, 44_100.);
+
+        let states = Arc::new(std::sync::Mutex::new(vec![]));
+        let states_clone = Arc::clone(&states);
+        context.set_onstatechange(move |event| {
+            states_clone.lock().unwrap().push(("handler", event.state));
+        });
+        let states_clone = Arc::clone(&states);
+        let _ = context.add_statechange_listener(move |event| {
+            states_clone.lock().unwrap().push(("listener", event.state));
+        });
+        let states_clone = Arc::clone(&states);
+        context
+            .add_statechange_listener(move |event| {
+                states_clone.lock().unwrap().push(("removed", event.state));
+            })
+            .remove();
+
+        let _ = context.start_rendering_sync();
+
+        // the listeners run before the handler
+        let states = states.lock().unwrap();
+        assert!(!states.is_empty());
+        assert!(states.chunks(2).all(|pair| pair[0].0 == "listener"
+            && pair[1].0 == "handler"
+            && pair[0].1 == pair[1].1));
+    }
+
     #[test]
     fn test_onstatechange_async() {
         use futures::executor;
@@ -1,20 +1,44 @@
 //! The `AudioContext` type and constructor options
 use std::error::Error;
-use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
 
-use crate::context::{AudioContextState, BaseAudioContext, ConcreteBaseAudioContext};
+use crate::context::{AudioContextState, BaseAudioContext, ConcreteBaseAudioContext, IdleEvent};
 use crate::events::{EventDispatch, EventHandler, EventLoop, EventPayload, EventType};
 use crate::io::{self, AudioBackendManager, ControlThreadInit, NoneBackend, RenderThreadInit};
 use crate::media_devices::{enumerate_devices_sync, MediaDeviceInfoKind};
-use crate::media_streams::{MediaStream, MediaStreamTrack};
+use crate::media_streams::{MediaStream, MediaStreamTrack, ResampleQuality};
 use crate::message::{ControlMessage, OneshotNotify};
-use crate::node::{self, AudioNodeOptions};
+use crate::node::{self, AudioNodeOptions, SpeakerLayout};
 use crate::render::graph::Graph;
 use crate::MediaElement;
-use crate::{AudioRenderCapacity, Event};
+use crate::{assert_valid_time_value, AudioRenderCapacity, Event, ListenerHandle};
 
 use futures_channel::oneshot;
 
+/// Duration in seconds of the fade out applied by [`AudioContext::render_for`]
+const RENDER_FOR_FADE_OUT: f64 = 0.02;
+
+/// Number of frames that [`AudioContext::prime`] waits for the output stream to render
+const PRIME_FRAMES: usize = 4 * crate::RENDER_QUANTUM_SIZE;
+
+/// Maximum time [`AudioContext::prime`] waits for the output stream to spin up
+const PRIME_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Number of pending events of the idle watcher, the render thread retries when it is full
+const IDLE_EVENT_CAPACITY: usize = 8;
+
+/// Interval at which the device watcher checks the state of the audio device
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Default number of seconds an interrupted device is given to recover, see
+/// [`AudioContextOptions::device_fallback`]
+const DEFAULT_DEVICE_FALLBACK: f64 = 1.;
+
+/// Maximum time to wait for the render thread of an interrupted device to hand over the graph
+const GRAPH_RECOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Check if the provided sink_id is available for playback
 ///
 /// It should be "", "none" or a valid output `sinkId` returned from [`enumerate_devices_sync`]
@@ -69,6 +93,25 @@ impl Default for AudioContextRenderSizeCategory {
     }
 }
 
+/// Behavior of an [`AudioContext`] whose audio device has been interrupted, see
+/// [`AudioContextState::Interrupted`]
+///
+/// This is not part of the Web Audio API specification.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AudioContextInterruptionPolicy {
+    /// Resume rendering as soon as the audio device renders again. This is the default.
+    AutoResume,
+    /// Play silence and stay interrupted until [`AudioContext::resume_sync`] is called, e.g. to
+    /// let the user confirm the playback of a call after a headset was disconnected
+    Manual,
+}
+
+impl Default for AudioContextInterruptionPolicy {
+    fn default() -> Self {
+        Self::AutoResume
+    }
+}
+
 /// Specify the playback configuration for the [`AudioContext`] constructor.
 ///
 /// All fields are optional and will default to the value best suited for interactive playback on
@@ -84,13 +127,18 @@ impl Default for AudioContextRenderSizeCategory {
 ///     sample_rate: Some(44100.),
 ///     ..AudioContextOptions::default()
 /// };
-#[derive(Clone, Debug, Default)]
+/// ```
+#[derive(Clone, Debug)]
 pub struct AudioContextOptions {
     /// Identify the type of playback, which affects tradeoffs between audio output latency and
     /// power consumption.
     pub latency_hint: AudioContextLatencyCategory,
 
     /// Sample rate of the audio context and audio output hardware. Use `None` for a default value.
+    ///
+    /// When the audio output device does not support the requested sample rate, the output of
+    /// the context is resampled to the rate of the device, see
+    /// [`AudioContext::device_sample_rate`].
     pub sample_rate: Option<f32>,
 
     /// The audio output device
@@ -101,6 +149,45 @@ pub struct AudioContextOptions {
 
     /// Option to request a default, optimized or specific render quantum size. It is a hint that might not be honored.
     pub render_size_hint: AudioContextRenderSizeCategory,
+
+    /// Suspend the audio stream after the given number of seconds without audible output, to
+    /// save battery in applications with sporadic sounds. The context resumes when a node starts
+    /// producing sound: a scheduled source is started, an `EnvelopeNode` is triggered, or a media
+    /// element, media stream or network stream source is created or played. Use `None` (the
+    /// default) to keep the stream running.
+    ///
+    /// This is not part of the Web Audio API specification.
+    pub idle_suspend: Option<f64>,
+
+    /// Behavior of the context after an interruption of the audio device
+    ///
+    /// This is not part of the Web Audio API specification.
+    pub interruption_policy: AudioContextInterruptionPolicy,
+
+    /// Move the audio graph to the default output device when the current device has been
+    /// interrupted for the given number of seconds, e.g. because it was unplugged. The graph and
+    /// the context time carry on, and a `sinkchange` event is dispatched after the migration.
+    /// The context resumes on the new device regardless of the
+    /// [`interruption_policy`](Self::interruption_policy).
+    ///
+    /// Defaults to one second, use `None` to stay interrupted instead.
+    ///
+    /// This is not part of the Web Audio API specification.
+    pub device_fallback: Option<f64>,
+}
+
+impl Default for AudioContextOptions {
+    fn default() -> Self {
+        Self {
+            latency_hint: AudioContextLatencyCategory::default(),
+            sample_rate: None,
+            sink_id: String::new(),
+            render_size_hint: AudioContextRenderSizeCategory::default(),
+            idle_suspend: None,
+            interruption_policy: AudioContextInterruptionPolicy::default(),
+            device_fallback: Some(DEFAULT_DEVICE_FALLBACK),
+        }
+    }
 }
 
 /// This interface represents an audio graph whose `AudioDestinationNode` is routed to a real-time
@@ -110,12 +197,14 @@ pub struct AudioContextOptions {
 pub struct AudioContext {
     /// represents the underlying `BaseAudioContext`
     base: ConcreteBaseAudioContext,
-    /// audio backend (play/pause functionality)
-    backend_manager: Mutex<Box<dyn AudioBackendManager>>,
+    /// audio backend (play/pause functionality), shared with the idle watcher thread
+    backend_manager: Arc<Mutex<Box<dyn AudioBackendManager>>>,
     /// Provider for rendering performance metrics
     render_capacity: AudioRenderCapacity,
     /// Initializer for the render thread (when restart is required)
     render_thread_init: RenderThreadInit,
+    /// Behavior after an interruption of the audio device, kept when the sink changes
+    interruption_policy: AudioContextInterruptionPolicy,
 }
 
 impl std::fmt::Debug for AudioContext {
@@ -131,10 +220,13 @@ impl std::fmt::Debug for AudioContext {
 
 impl Drop for AudioContext {
     fn drop(&mut self) {
+        self.base.idle_monitor().stop();
+
         // Continue playing the stream if the AudioContext goes out of scope
         if self.state() == AudioContextState::Running {
             let tombstone = Box::new(NoneBackend::void());
-            let original = std::mem::replace(self.backend_manager.get_mut().unwrap(), tombstone);
+            let mut backend_manager = self.backend_manager.lock().unwrap();
+            let original = std::mem::replace(&mut *backend_manager, tombstone);
             Box::leak(original);
         }
     }
@@ -186,6 +278,15 @@ impl AudioContext {
             "NotFoundError - Invalid sinkId: {:?}",
             options.sink_id
         );
+        if let Some(idle_suspend) = options.idle_suspend {
+            assert_valid_time_value(idle_suspend);
+        }
+        let idle_suspend = options.idle_suspend;
+        if let Some(device_fallback) = options.device_fallback {
+            assert_valid_time_value(device_fallback);
+        }
+        let device_fallback = options.device_fallback;
+        let interruption_policy = options.interruption_policy;
 
         // Set up the audio output thread
         let (control_thread_init, render_thread_init) = io::thread_init();
@@ -231,11 +332,29 @@ impl AudioContext {
         // construction.
         event_loop.run_in_thread();
 
+        let backend_manager = Arc::new(Mutex::new(backend));
+        if let Some(timeout) = idle_suspend {
+            base.idle_monitor()
+                .timeout
+                .store(timeout, Ordering::Relaxed);
+            spawn_idle_watcher(base.clone(), Arc::downgrade(&backend_manager));
+        }
+        if let Some(timeout) = device_fallback {
+            spawn_device_watcher(
+                base.clone(),
+                Arc::downgrade(&backend_manager),
+                render_thread_init.clone(),
+                interruption_policy,
+                timeout,
+            );
+        }
+
         Self {
             base,
-            backend_manager: Mutex::new(backend),
+            backend_manager,
             render_capacity,
             render_thread_init,
+            interruption_policy,
         }
     }
 
@@ -260,6 +379,31 @@ impl AudioContext {
         self.backend_manager.lock().unwrap().output_latency()
     }
 
+    /// The sample rate of the current audio output device
+    ///
+    /// This differs from the sample rate of the context when the device does not support it, e.g.
+    /// after [`set_sink_id_sync`](Self::set_sink_id_sync) moved the context to a device running at
+    /// another rate. The graph keeps running at the sample rate of the context and its output is
+    /// resampled to the rate of the device.
+    ///
+    /// This is not part of the Web Audio API specification.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn device_sample_rate(&self) -> f32 {
+        self.backend_manager.lock().unwrap().device_sample_rate()
+    }
+
+    /// The speaker layout of the current audio output device
+    ///
+    /// This is derived from the maximum channel count of the destination. Render to the layout by
+    /// setting the channel count of the destination accordingly.
+    ///
+    /// This is not part of the Web Audio API specification.
+    #[must_use]
+    pub fn speaker_layout(&self) -> SpeakerLayout {
+        SpeakerLayout::from_number_of_channels(self.destination().max_channel_count())
+    }
+
     /// Identifier or the information of the current audio output device.
     ///
     /// The initial value is `""`, which means the default audio output device.
@@ -281,6 +425,9 @@ impl AudioContext {
     /// Supplying `"none"` for the `sink_id` will process the audio graph without playing through an
     /// audio output device.
     ///
+    /// The context keeps its sample rate. When the new device runs at another rate, the output is
+    /// resampled to the rate of the device, see [`device_sample_rate`](Self::device_sample_rate).
+    ///
     /// This function operates synchronously and might block the current thread. An async version
     /// is currently not implemented.
     #[allow(clippy::needless_collect, clippy::missing_panics_doc)]
@@ -295,83 +442,13 @@ impl AudioContext {
             Err(format!("NotFoundError: invalid sinkId {sink_id}"))?;
         };
 
-        log::debug!("SinkChange: locking backend manager");
-        let mut backend_manager_guard = self.backend_manager.lock().unwrap();
-        let original_state = self.state();
-        if original_state == AudioContextState::Closed {
-            log::debug!("SinkChange: context is closed");
-            return Ok(());
-        }
-
-        // Acquire exclusive lock on ctrl msg sender
-        log::debug!("SinkChange: locking message channel");
-        let ctrl_msg_send = self.base.lock_control_msg_sender();
-
-        // Flush out the ctrl msg receiver, cache
-        let mut pending_msgs: Vec<_> = self.render_thread_init.ctrl_msg_recv.try_iter().collect();
-
-        // Acquire the active audio graph from the current render thread, shutting it down
-        let graph = if matches!(pending_msgs.first(), Some(ControlMessage::Startup { .. })) {
-            // Handle the edge case where the previous backend was suspended for its entire lifetime.
-            // In this case, the `Startup` control message was never processed.
-            log::debug!("SinkChange: recover unstarted graph");
-
-            let msg = pending_msgs.remove(0);
-            match msg {
-                ControlMessage::Startup { graph } => graph,
-                _ => unreachable!(),
-            }
-        } else {
-            // Acquire the audio graph from the current render thread, shutting it down
-            log::debug!("SinkChange: recover graph from render thread");
-
-            let (graph_send, graph_recv) = crossbeam_channel::bounded(1);
-            let message = ControlMessage::CloseAndRecycle { sender: graph_send };
-            ctrl_msg_send.send(message).unwrap();
-            if original_state == AudioContextState::Suspended {
-                // We must wake up the render thread to be able to handle the shutdown.
-                // No new audio will be produced because it will receive the shutdown command first.
-                backend_manager_guard.resume();
-            }
-            graph_recv.recv().unwrap()
-        };
-
-        log::debug!("SinkChange: closing audio stream");
-        backend_manager_guard.close();
-
-        // hotswap the backend
-        let options = AudioContextOptions {
-            sample_rate: Some(self.sample_rate()),
-            latency_hint: AudioContextLatencyCategory::default(), // todo reuse existing setting
+        switch_sink(
+            &self.base,
+            &self.backend_manager,
+            &self.render_thread_init,
+            self.interruption_policy,
             sink_id,
-            render_size_hint: AudioContextRenderSizeCategory::default(), // todo reuse existing setting
-        };
-        log::debug!("SinkChange: starting audio stream");
-        *backend_manager_guard = io::build_output(options, self.render_thread_init.clone());
-
-        // if the previous backend state was suspend, suspend the new one before shipping the graph
-        if original_state == AudioContextState::Suspended {
-            log::debug!("SinkChange: suspending audio stream");
-            backend_manager_guard.suspend();
-        }
-
-        // send the audio graph to the new render thread
-        let message = ControlMessage::Startup { graph };
-        ctrl_msg_send.send(message).unwrap();
-
-        // flush the cached msgs
-        pending_msgs
-            .into_iter()
-            .for_each(|m| self.base().send_control_msg(m));
-
-        // explicitly release the lock to prevent concurrent render threads
-        drop(backend_manager_guard);
-
-        // trigger event when all the work is done
-        let _ = self.base.send_event(EventDispatch::sink_change());
-
-        log::debug!("SinkChange: done");
-        Ok(())
+        )
     }
 
     /// Register callback to run when the audio sink has changed
@@ -396,6 +473,26 @@ impl AudioContext {
         self.base().clear_event_handler(EventType::SinkChange);
     }
 
+    /// Add a listener to run when the audio sink has changed
+    ///
+    /// Unlike [`set_onsinkchange`](Self::set_onsinkchange), the listeners do not replace each
+    /// other. They run in the order they were added, before the callback of `set_onsinkchange`.
+    /// Use the returned [`ListenerHandle`] to remove the listener.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn add_sinkchange_listener<F: FnMut(Event) + Send + 'static>(
+        &self,
+        mut callback: F,
+    ) -> ListenerHandle {
+        let listener = move |_: &EventPayload| {
+            callback(Event {
+                type_: "sinkchange",
+            })
+        };
+
+        self.base().add_listener(EventType::SinkChange, listener)
+    }
+
     #[allow(clippy::missing_panics_doc)]
     #[doc(hidden)] // Method signature might change in the future
     pub fn run_diagnostics<F: Fn(String) + Send + 'static>(&self, callback: F) {
@@ -444,6 +541,11 @@ impl AudioContext {
         // Don't lock the backend manager because we can't hold is across the await point
         log::debug!("Suspend called");
 
+        if self.state() == AudioContextState::Interrupted {
+            suspend_interrupted(&self.base, &self.backend_manager);
+            return;
+        }
+
         if self.state() != AudioContextState::Running {
             log::debug!("Suspend no-op - context is not running");
             return;
@@ -470,6 +572,9 @@ impl AudioContext {
     /// Resumes the progression of time in an audio context that has previously been
     /// suspended/paused.
     ///
+    /// An interrupted context is resumed as well, which waits until its audio device renders
+    /// again.
+    ///
     /// # Panics
     ///
     /// Will panic if:
@@ -479,12 +584,18 @@ impl AudioContext {
     pub async fn resume(&self) {
         let (sender, receiver) = oneshot::channel();
 
+        // restart the idle timer, the graph would be suspended again right away otherwise
+        self.base.idle_monitor().notify_active();
+
         {
             // Lock the backend manager mutex to avoid concurrent calls
             log::debug!("Resume called, locking backend manager");
             let backend_manager_guard = self.backend_manager.lock().unwrap();
 
-            if self.state() != AudioContextState::Suspended {
+            if !matches!(
+                self.state(),
+                AudioContextState::Suspended | AudioContextState::Interrupted
+            ) {
                 log::debug!("Resume no-op - context is not suspended");
                 return;
             }
@@ -564,31 +675,8 @@ impl AudioContext {
     /// * The audio device is not available
     /// * For a `BackendSpecificError`
     pub fn suspend_sync(&self) {
-        // Lock the backend manager mutex to avoid concurrent calls
-        log::debug!("Suspend_sync called, locking backend manager");
-        let backend_manager_guard = self.backend_manager.lock().unwrap();
-
-        if self.state() != AudioContextState::Running {
-            log::debug!("Suspend_sync no-op - context is not running");
-            return;
-        }
-
-        // Pause rendering via a control message
-        let (sender, receiver) = crossbeam_channel::bounded(0);
-        let notify = OneshotNotify::Sync(sender);
-        self.base
-            .send_control_msg(ControlMessage::Suspend { notify });
-
-        // Wait for the render thread to have processed the suspend message.
-        // The AudioContextState will be updated by the render thread.
-        log::debug!("Suspending audio graph, waiting for signal..");
-        receiver.recv().ok();
-
-        // Then ask the audio host to suspend the stream
-        log::debug!("Suspended audio graph. Suspending audio stream..");
-        backend_manager_guard.suspend();
-
-        log::debug!("Suspended audio stream");
+        log::debug!("Suspend_sync called");
+        suspend_backend_sync(&self.base, &self.backend_manager);
     }
 
     /// Resumes the progression of time in an audio context that has previously been
@@ -597,6 +685,9 @@ impl AudioContext {
     /// This function operates synchronously and blocks the current thread until the audio thread
     /// has started processing again.
     ///
+    /// An interrupted context is resumed as well, which waits until its audio device renders
+    /// again.
+    ///
     /// # Panics
     ///
     /// Will panic if:
@@ -604,29 +695,10 @@ impl AudioContext {
     /// * The audio device is not available
     /// * For a `BackendSpecificError`
     pub fn resume_sync(&self) {
-        // Lock the backend manager mutex to avoid concurrent calls
-        log::debug!("Resume_sync called, locking backend manager");
-        let backend_manager_guard = self.backend_manager.lock().unwrap();
-
-        if self.state() != AudioContextState::Suspended {
-            log::debug!("Resume no-op - context is not suspended");
-            return;
-        }
-
-        // Ask the audio host to resume the stream
-        backend_manager_guard.resume();
-
-        // Then, ask to resume rendering via a control message
-        log::debug!("Resumed audio stream, waking audio graph");
-        let (sender, receiver) = crossbeam_channel::bounded(0);
-        let notify = OneshotNotify::Sync(sender);
-        self.base
-            .send_control_msg(ControlMessage::Resume { notify });
-
-        // Wait for the render thread to have processed the resume message
-        // The AudioContextState will be updated by the render thread.
-        receiver.recv().ok();
-        log::debug!("Resumed audio graph");
+        log::debug!("Resume_sync called");
+        // restart the idle timer, the graph would be suspended again right away otherwise
+        self.base.idle_monitor().notify_active();
+        resume_backend_sync(&self.base, &self.backend_manager);
     }
 
     /// Closes the `AudioContext`, releasing the system resources being used.
@@ -675,6 +747,188 @@ impl AudioContext {
         log::debug!("Closed audio stream");
     }
 
+    /// Warms up the `AudioContext`, so the first sound after a user interaction is not delayed
+    ///
+    /// This computes the lookup tables, HRTF impulse responses and FFT plans that are otherwise
+    /// lazily initialized when the first nodes are created, and blocks until the output stream
+    /// is rendering. Call this e.g. while the application is loading.
+    ///
+    /// This is not part of the Web Audio API specification.
+    pub fn prime(&self) {
+        log::debug!("Prime called");
+
+        // lookup tables and plans shared by all nodes
+        node::precomputed_sine_table();
+        node::load_hrtf_processor(self.sample_rate() as u32);
+        crate::analysis::prime_fft_plans();
+
+        // wait for the output stream to spin up, the render thread may still be booting
+        let booting = self.state() == AudioContextState::Suspended && self.current_time() == 0.;
+        if self.state() == AudioContextState::Running || booting {
+            let start = std::time::Instant::now();
+            let target = self.current_time() + PRIME_FRAMES as f64 / self.sample_rate() as f64;
+            while start.elapsed() < PRIME_TIMEOUT
+                && self.state() != AudioContextState::Closed
+                && (self.state() != AudioContextState::Running || self.current_time() < target)
+            {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        log::debug!("Primed audio context");
+    }
+
+    /// Closes the `AudioContext` at the given time, after fading out its output.
+    ///
+    /// The output is linearly faded out during the `fade_out` seconds preceding `when`. The
+    /// returned future resolves when the context is closed, so an application that plays a cue
+    /// and exits does not need to guess how long to sleep.
+    ///
+    /// A call to [`close`](Self::close) or [`suspend`](Self::suspend) before `when`, including
+    /// the suspension of an idle graph (see [`AudioContextOptions::idle_suspend`]), supersedes
+    /// the scheduled close: the returned future resolves right away and the context is not
+    /// closed.
+    ///
+    /// This is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `when` or `fade_out` is negative or not finite
+    pub async fn close_at(&self, when: f64, fade_out: f64) {
+        assert_valid_time_value(when);
+        assert_valid_time_value(fade_out);
+
+        // Don't lock the backend manager because we can't hold is across the await point
+        log::debug!("Close_at called");
+
+        if self.state() != AudioContextState::Running {
+            // time is not progressing, close right away
+            return self.close().await;
+        }
+
+        // Schedule the end of rendering via a control message
+        let (sender, receiver) = oneshot::channel();
+        let notify = OneshotNotify::Async(sender);
+        self.base.send_control_msg(ControlMessage::CloseAt {
+            when,
+            fade_out,
+            notify,
+        });
+
+        // Wait for the render thread to reach the scheduled time.
+        // The AudioContextState will be updated by the render thread.
+        log::debug!("Scheduled close of audio graph, waiting for signal..");
+        if receiver.await.is_err() {
+            log::debug!("Close_at superseded by another close call");
+            return;
+        }
+
+        // Then ask the audio host to close the stream
+        log::debug!("Closed audio graph. Closing audio stream..");
+        self.backend_manager.lock().unwrap().close();
+
+        // Stop the AudioRenderCapacity collection thread
+        self.render_capacity.stop();
+
+        log::debug!("Closed audio stream");
+    }
+
+    /// Closes the `AudioContext` at the given time, after fading out its output.
+    ///
+    /// This function operates synchronously and blocks the current thread until the context is
+    /// closed, or until the scheduled close is superseded. See [`close_at`](Self::close_at) for
+    /// details.
+    ///
+    /// This is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `when` or `fade_out` is negative or not finite
+    pub fn close_at_sync(&self, when: f64, fade_out: f64) {
+        assert_valid_time_value(when);
+        assert_valid_time_value(fade_out);
+
+        log::debug!("Close_at_sync called");
+
+        if self.state() != AudioContextState::Running {
+            // time is not progressing, close right away
+            return self.close_sync();
+        }
+
+        // Schedule the end of rendering via a control message
+        let (sender, receiver) = crossbeam_channel::bounded(0);
+        let notify = OneshotNotify::Sync(sender);
+        self.base.send_control_msg(ControlMessage::CloseAt {
+            when,
+            fade_out,
+            notify,
+        });
+
+        // Wait for the render thread to reach the scheduled time.
+        // The AudioContextState will be updated by the render thread.
+        log::debug!("Scheduled close of audio graph, waiting for signal..");
+        if receiver.recv().is_err() {
+            log::debug!("Close_at_sync superseded by another close call");
+            return;
+        }
+
+        // Then ask the audio host to close the stream
+        log::debug!("Closed audio graph. Closing audio stream..");
+        self.backend_manager.lock().unwrap().close();
+
+        // Stop the AudioRenderCapacity collection thread
+        self.render_capacity.stop();
+
+        log::debug!("Closed audio stream");
+    }
+
+    /// Renders for the given duration in seconds from now, then fades out and closes the
+    /// `AudioContext`.
+    ///
+    /// A suspension of the context before the end supersedes the close, see
+    /// [`close_at`](Self::close_at).
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext};
+    /// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+    ///
+    /// let context = AudioContext::default();
+    /// let mut osc = context.create_oscillator();
+    /// osc.connect(&context.destination());
+    /// osc.start();
+    ///
+    /// // play the cue and exit
+    /// context.render_for_sync(2.);
+    /// ```
+    ///
+    /// This is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `duration` is negative or not finite
+    pub async fn render_for(&self, duration: f64) {
+        assert_valid_time_value(duration);
+        let when = self.current_time() + duration;
+        self.close_at(when, RENDER_FOR_FADE_OUT.min(duration)).await
+    }
+
+    /// Renders for the given duration in seconds from now, then fades out and closes the
+    /// `AudioContext`.
+    ///
+    /// This function operates synchronously and blocks the current thread until the context is
+    /// closed.
+    ///
+    /// This is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `duration` is negative or not finite
+    pub fn render_for_sync(&self, duration: f64) {
+        assert_valid_time_value(duration);
+        let when = self.current_time() + duration;
+        self.close_at_sync(when, RENDER_FOR_FADE_OUT.min(duration))
+    }
+
     /// Creates a [`MediaStreamAudioSourceNode`](node::MediaStreamAudioSourceNode) from a
     /// [`MediaStream`]
     #[must_use]
@@ -684,6 +938,7 @@ impl AudioContext {
     ) -> node::MediaStreamAudioSourceNode {
         let opts = node::MediaStreamAudioSourceOptions {
             media_stream: media,
+            resample_quality: ResampleQuality::default(),
         };
         node::MediaStreamAudioSourceNode::new(self, opts)
     }
@@ -695,6 +950,23 @@ impl AudioContext {
         node::MediaStreamAudioDestinationNode::new(self, opts)
     }
 
+    /// Creates an [`AuxiliaryDestinationNode`](node::AuxiliaryDestinationNode) playing its input
+    /// on the given audio output device
+    ///
+    /// This is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic when an invalid `sinkId` is provided
+    #[must_use]
+    pub fn create_auxiliary_destination(&self, sink_id: String) -> node::AuxiliaryDestinationNode {
+        let opts = node::AuxiliaryDestinationOptions {
+            sink_id,
+            ..node::AuxiliaryDestinationOptions::default()
+        };
+        node::AuxiliaryDestinationNode::new(self, opts)
+    }
+
     /// Creates a [`MediaStreamTrackAudioSourceNode`](node::MediaStreamTrackAudioSourceNode) from a
     /// [`MediaStreamTrack`]
     #[must_use]
@@ -704,6 +976,7 @@ impl AudioContext {
     ) -> node::MediaStreamTrackAudioSourceNode {
         let opts = node::MediaStreamTrackAudioSourceOptions {
             media_stream_track: media,
+            resample_quality: ResampleQuality::default(),
         };
         node::MediaStreamTrackAudioSourceNode::new(self, opts)
     }
@@ -720,10 +993,313 @@ impl AudioContext {
     }
 }
 
+/// Move the audio graph to a new output stream on the given sink, then dispatch the `sinkchange`
+/// event
+fn switch_sink(
+    base: &ConcreteBaseAudioContext,
+    backend_manager: &Mutex<Box<dyn AudioBackendManager>>,
+    render_thread_init: &RenderThreadInit,
+    interruption_policy: AudioContextInterruptionPolicy,
+    sink_id: String,
+) -> Result<(), Box<dyn Error>> {
+    log::debug!("SinkChange: locking backend manager");
+    let mut backend_manager_guard = backend_manager.lock().unwrap();
+    let original_state = base.state();
+    if original_state == AudioContextState::Closed {
+        log::debug!("SinkChange: context is closed");
+        return Ok(());
+    }
+
+    // Acquire exclusive lock on ctrl msg sender
+    log::debug!("SinkChange: locking message channel");
+    let ctrl_msg_send = base.lock_control_msg_sender();
+
+    // Flush out the ctrl msg receiver, cache
+    let mut pending_msgs: Vec<_> = render_thread_init.ctrl_msg_recv.try_iter().collect();
+
+    // Acquire the active audio graph from the current render thread, shutting it down
+    let mut stream_closed = false;
+    let graph = if matches!(pending_msgs.first(), Some(ControlMessage::Startup { .. })) {
+        // Handle the edge case where the previous backend was suspended for its entire lifetime.
+        // In this case, the `Startup` control message was never processed.
+        log::debug!("SinkChange: recover unstarted graph");
+
+        let msg = pending_msgs.remove(0);
+        match msg {
+            ControlMessage::Startup { graph } => graph,
+            _ => unreachable!(),
+        }
+    } else if original_state == AudioContextState::Interrupted {
+        // The render thread of an unavailable device may never run again. It hands over the
+        // graph when it is dropped along with the audio stream.
+        log::debug!("SinkChange: recover graph from interrupted render thread");
+
+        backend_manager_guard.close();
+        stream_closed = true;
+        render_thread_init
+            .graph_recv
+            .recv_timeout(GRAPH_RECOVERY_TIMEOUT)
+            .map_err(|_| "InvalidStateError: the audio graph of the interrupted device was lost")?
+    } else {
+        // Acquire the audio graph from the current render thread, shutting it down
+        log::debug!("SinkChange: recover graph from render thread");
+
+        let (graph_send, graph_recv) = crossbeam_channel::bounded(1);
+        let message = ControlMessage::CloseAndRecycle { sender: graph_send };
+        ctrl_msg_send.send(message).unwrap();
+        if original_state == AudioContextState::Suspended {
+            // We must wake up the render thread to be able to handle the shutdown.
+            // No new audio will be produced because it will receive the shutdown command first.
+            backend_manager_guard.resume();
+        }
+        graph_recv.recv().unwrap()
+    };
+
+    if !stream_closed {
+        log::debug!("SinkChange: closing audio stream");
+        backend_manager_guard.close();
+    }
+
+    // the graph of an interrupted device runs again on the new device
+    if original_state == AudioContextState::Interrupted {
+        base.set_state(AudioContextState::Running);
+    }
+
+    // hotswap the backend
+    let options = AudioContextOptions {
+        sample_rate: Some(base.sample_rate()),
+        latency_hint: AudioContextLatencyCategory::default(), // todo reuse existing setting
+        sink_id,
+        render_size_hint: AudioContextRenderSizeCategory::default(), // todo reuse existing setting
+        idle_suspend: None, // handled by the idle watcher, not the backend
+        interruption_policy,
+        device_fallback: None, // handled by the device watcher, not the backend
+    };
+    log::debug!("SinkChange: starting audio stream");
+    *backend_manager_guard = io::build_output(options, render_thread_init.clone());
+
+    // if the previous backend state was suspend, suspend the new one before shipping the graph
+    if original_state == AudioContextState::Suspended {
+        log::debug!("SinkChange: suspending audio stream");
+        backend_manager_guard.suspend();
+    }
+
+    // send the audio graph to the new render thread
+    let message = ControlMessage::Startup { graph };
+    ctrl_msg_send.send(message).unwrap();
+
+    // flush the cached msgs, the channel is still locked
+    pending_msgs
+        .into_iter()
+        .for_each(|m| ctrl_msg_send.send(m).unwrap());
+
+    // explicitly release the locks to prevent concurrent render threads
+    drop(ctrl_msg_send);
+    drop(backend_manager_guard);
+
+    // trigger event when all the work is done
+    let _ = base.send_event(EventDispatch::sink_change());
+
+    log::debug!("SinkChange: done");
+    Ok(())
+}
+
+/// Pause rendering and suspend the audio stream, blocking until the render thread has stopped
+fn suspend_backend_sync(
+    base: &ConcreteBaseAudioContext,
+    backend_manager: &Mutex<Box<dyn AudioBackendManager>>,
+) {
+    // Lock the backend manager mutex to avoid concurrent calls
+    log::debug!("Suspend: locking backend manager");
+    let backend_manager_guard = backend_manager.lock().unwrap();
+
+    if base.state() == AudioContextState::Interrupted {
+        drop(backend_manager_guard);
+        suspend_interrupted(base, backend_manager);
+        return;
+    }
+
+    if base.state() != AudioContextState::Running {
+        log::debug!("Suspend no-op - context is not running");
+        return;
+    }
+
+    // Pause rendering via a control message
+    let (sender, receiver) = crossbeam_channel::bounded(0);
+    let notify = OneshotNotify::Sync(sender);
+    base.send_control_msg(ControlMessage::Suspend { notify });
+
+    // Wait for the render thread to have processed the suspend message.
+    // The AudioContextState will be updated by the render thread.
+    log::debug!("Suspending audio graph, waiting for signal..");
+    receiver.recv().ok();
+
+    // Then ask the audio host to suspend the stream
+    log::debug!("Suspended audio graph. Suspending audio stream..");
+    backend_manager_guard.suspend();
+
+    log::debug!("Suspended audio stream");
+}
+
+/// Suspend a context whose audio device has been interrupted
+///
+/// The render thread may not run until the device recovers, so the state is changed manually and
+/// the render thread picks up the suspension once the device renders again. The stream itself is
+/// left alone, pausing a stream of an unavailable device fails.
+fn suspend_interrupted(
+    base: &ConcreteBaseAudioContext,
+    backend_manager: &Mutex<Box<dyn AudioBackendManager>>,
+) {
+    // Lock the backend manager mutex to avoid concurrent calls
+    let _backend_manager_guard = backend_manager.lock().unwrap();
+
+    // nobody waits for the render thread to handle the message
+    let (sender, _) = crossbeam_channel::bounded(0);
+    let notify = OneshotNotify::Sync(sender);
+    base.send_control_msg(ControlMessage::Suspend { notify });
+    base.set_state(AudioContextState::Suspended);
+
+    log::debug!("Suspended interrupted audio graph");
+}
+
+/// Resume the audio stream and rendering, blocking until the render thread has started
+fn resume_backend_sync(
+    base: &ConcreteBaseAudioContext,
+    backend_manager: &Mutex<Box<dyn AudioBackendManager>>,
+) {
+    // Lock the backend manager mutex to avoid concurrent calls
+    log::debug!("Resume: locking backend manager");
+    let backend_manager_guard = backend_manager.lock().unwrap();
+
+    if !matches!(
+        base.state(),
+        AudioContextState::Suspended | AudioContextState::Interrupted
+    ) {
+        log::debug!("Resume no-op - context is not suspended");
+        return;
+    }
+
+    // Ask the audio host to resume the stream
+    backend_manager_guard.resume();
+
+    // Then, ask to resume rendering via a control message
+    log::debug!("Resumed audio stream, waking audio graph");
+    let (sender, receiver) = crossbeam_channel::bounded(0);
+    let notify = OneshotNotify::Sync(sender);
+    base.send_control_msg(ControlMessage::Resume { notify });
+
+    // Wait for the render thread to have processed the resume message
+    // The AudioContextState will be updated by the render thread.
+    receiver.recv().ok();
+    log::debug!("Resumed audio graph");
+}
+
+/// Suspend the context when the destination renderer reports an idle graph, and resume it when
+/// a node starts producing sound
+///
+/// The thread blocks on the events and exits when the `AudioContext` is dropped.
+fn spawn_idle_watcher(
+    base: ConcreteBaseAudioContext,
+    backend_manager: Weak<Mutex<Box<dyn AudioBackendManager>>>,
+) {
+    let (event_send, event_recv) = crossbeam_channel::bounded(IDLE_EVENT_CAPACITY);
+    *base.idle_monitor().events.lock().unwrap() = Some(event_send);
+
+    std::thread::spawn(move || {
+        // only resume the context when it was suspended by this watcher
+        let mut idle_suspended = false;
+
+        for event in event_recv {
+            let Some(backend_manager) = backend_manager.upgrade() else {
+                break;
+            };
+
+            match (event, base.state()) {
+                (_, AudioContextState::Closed) => break,
+                (IdleEvent::Idle, AudioContextState::Running) => {
+                    log::debug!("Idle watcher: no activity, suspending");
+                    suspend_backend_sync(&base, &backend_manager);
+                    idle_suspended = true;
+                }
+                (IdleEvent::Active, AudioContextState::Suspended) if idle_suspended => {
+                    log::debug!("Idle watcher: source started, resuming");
+                    idle_suspended = false;
+                    resume_backend_sync(&base, &backend_manager);
+                }
+                // the context was resumed by the user
+                (IdleEvent::Active, AudioContextState::Running) => idle_suspended = false,
+                _ => (),
+            }
+        }
+
+        log::debug!("Idle watcher has been stopped");
+    });
+}
+
+/// Move the graph to the default output device after the current device has been interrupted for
+/// `timeout` seconds
+fn spawn_device_watcher(
+    base: ConcreteBaseAudioContext,
+    backend_manager: Weak<Mutex<Box<dyn AudioBackendManager>>>,
+    render_thread_init: RenderThreadInit,
+    interruption_policy: AudioContextInterruptionPolicy,
+    timeout: f64,
+) {
+    std::thread::spawn(move || {
+        let mut interrupted_since = None;
+
+        loop {
+            std::thread::sleep(DEVICE_POLL_INTERVAL);
+
+            // stop watching when the AudioContext has been dropped
+            let Some(backend_manager) = backend_manager.upgrade() else {
+                break;
+            };
+
+            match base.state() {
+                AudioContextState::Closed => break,
+                AudioContextState::Interrupted => {
+                    let since = *interrupted_since.get_or_insert_with(Instant::now);
+                    if since.elapsed().as_secs_f64() < timeout || !is_output_available() {
+                        continue;
+                    }
+
+                    log::info!("Device watcher: output device lost, moving to the default device");
+                    interrupted_since = None;
+                    let result = switch_sink(
+                        &base,
+                        &backend_manager,
+                        &render_thread_init,
+                        interruption_policy,
+                        String::new(),
+                    );
+                    if let Err(e) = result {
+                        log::error!("Device watcher: {e}");
+                        break;
+                    }
+                }
+                _ => interrupted_since = None,
+            }
+        }
+
+        log::debug!("Device watcher has been stopped");
+    });
+}
+
+/// Check if any audio output device is available
+fn is_output_available() -> bool {
+    enumerate_devices_sync()
+        .into_iter()
+        .any(|d| d.kind() == MediaDeviceInfoKind::AudioOutput)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+    use crate::render::InterruptionHandle;
     use futures::executor;
+    use std::sync::atomic::AtomicBool;
 
     #[test]
     fn test_suspend_resume_close() {
@@ -782,6 +1358,242 @@ mod tests {
         require_send_sync(context.suspend());
         require_send_sync(context.resume());
         require_send_sync(context.close());
+        require_send_sync(context.close_at(1., 0.));
+        require_send_sync(context.render_for(1.));
+    }
+
+    #[test]
+    fn test_idle_suspend() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            idle_suspend: Some(0.05),
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+
+        let (state_send, state_recv) = crossbeam_channel::unbounded();
+        context.set_onstatechange(move |_| {
+            let _ = state_send.send(());
+        });
+        let wait_for = |state| {
+            while context.state() != state {
+                state_recv
+                    .recv_timeout(Duration::from_secs(5))
+                    .unwrap_or_else(|_| panic!("context did not become {state:?}"));
+            }
+        };
+
+        // an oscillator is playing, but it is muted by the envelope
+        let vca = context.create_gain();
+        vca.gain().set_value(0.);
+        vca.connect(&context.destination());
+        let mut osc = context.create_oscillator();
+        osc.connect(&vca);
+        osc.start();
+        let envelope = node::EnvelopeNode::new(&context, node::EnvelopeOptions::default());
+        envelope.connect(vca.gain());
+        wait_for(AudioContextState::Suspended);
+
+        // triggering the envelope resumes the context
+        envelope.trigger_attack_at(context.current_time());
+        wait_for(AudioContextState::Running);
+
+        // the context is suspended again after the release
+        envelope.trigger_release_at(context.current_time());
+        wait_for(AudioContextState::Suspended);
+
+        // starting a source resumes the context
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.start();
+        wait_for(AudioContextState::Running);
+
+        context.close_sync();
+    }
+
+    /// Interrupt the running context as an error of its audio device would, and collect the
+    /// following transitions
+    fn interrupt(
+        context: &AudioContext,
+    ) -> Arc<Mutex<Vec<(AudioContextState, AudioContextState)>>> {
+        context.prime();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        let transitions = Arc::new(Mutex::new(Vec::new()));
+        let transitions_clone = Arc::clone(&transitions);
+        context.set_onstatechange(move |event| {
+            transitions_clone
+                .lock()
+                .unwrap()
+                .push((event.previous_state, event.state));
+        });
+
+        let init = &context.render_thread_init;
+        InterruptionHandle::new(Arc::clone(&init.state), init.event_send.clone()).interrupt();
+
+        transitions
+    }
+
+    #[test]
+    fn test_interruption_auto_resume() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+        let transitions = interrupt(&context);
+
+        // the device keeps rendering
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(context.state(), AudioContextState::Running);
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            [
+                (AudioContextState::Running, AudioContextState::Interrupted),
+                (AudioContextState::Interrupted, AudioContextState::Running),
+            ]
+        );
+
+        context.close_sync();
+    }
+
+    #[test]
+    fn test_interruption_manual() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            interruption_policy: AudioContextInterruptionPolicy::Manual,
+            device_fallback: None,
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+        let transitions = interrupt(&context);
+
+        // no progression of time until resumed
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert_eq!(context.state(), AudioContextState::Interrupted);
+        let time = context.current_time();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert_eq!(context.current_time(), time);
+
+        context.resume_sync();
+        assert_eq!(context.state(), AudioContextState::Running);
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(context.current_time() > time);
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            [
+                (AudioContextState::Running, AudioContextState::Interrupted),
+                (AudioContextState::Interrupted, AudioContextState::Running),
+            ]
+        );
+
+        context.close_sync();
+    }
+
+    #[test]
+    fn test_recover_graph_of_interrupted_device() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            interruption_policy: AudioContextInterruptionPolicy::Manual,
+            device_fallback: None,
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.start();
+
+        let transitions = interrupt(&context);
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let time = context.current_time();
+
+        let sink_changed = Arc::new(AtomicBool::new(false));
+        let sink_changed_clone = Arc::clone(&sink_changed);
+        context.set_onsinkchange(move |_| sink_changed_clone.store(true, Ordering::Relaxed));
+
+        // the graph moves to a new stream and the context time carries on
+        switch_sink(
+            &context.base,
+            &context.backend_manager,
+            &context.render_thread_init,
+            context.interruption_policy,
+            "none".into(),
+        )
+        .unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(context.state(), AudioContextState::Running);
+        assert!(context.current_time() > time);
+        assert!(context.current_time() < time + 1.);
+        assert!(sink_changed.load(Ordering::Relaxed));
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            [
+                (AudioContextState::Running, AudioContextState::Interrupted),
+                (AudioContextState::Interrupted, AudioContextState::Running),
+            ]
+        );
+
+        // the nodes are still alive
+        src.stop();
+        context.close_sync();
+    }
+
+    #[test]
+    fn test_prime() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+
+        context.prime();
+        assert_eq!(context.state(), AudioContextState::Running);
+        assert!(context.current_time() > 0.);
+    }
+
+    #[test]
+    fn test_render_for() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+
+        context.render_for_sync(0.05);
+        assert_eq!(context.state(), AudioContextState::Closed);
+        assert!(context.current_time() >= 0.05);
+
+        // no-op on a closed context
+        context.render_for_sync(0.05);
+        assert_eq!(context.state(), AudioContextState::Closed);
+    }
+
+    #[test]
+    fn test_close_at_superseded_by_suspend() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+        while context.state() != AudioContextState::Running {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        std::thread::scope(|s| {
+            let closing = s.spawn(|| context.close_at_sync(10., 0.));
+            std::thread::sleep(Duration::from_millis(100));
+
+            // time stops, the scheduled close is abandoned instead of blocking forever
+            context.suspend_sync();
+            closing.join().unwrap();
+        });
+        assert_eq!(context.state(), AudioContextState::Suspended);
+
+        context.close_sync();
+        assert_eq!(context.state(), AudioContextState::Closed);
     }
 
     #[test]
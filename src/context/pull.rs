@@ -0,0 +1,255 @@
+//! The `PullAudioContext` type
+
+use std::sync::atomic::{AtomicU64, AtomicU8};
+use std::sync::Arc;
+
+use crate::context::{AudioContextState, BaseAudioContext, ConcreteBaseAudioContext};
+use crate::events::EventLoop;
+use crate::render::RenderThread;
+use crate::{assert_valid_number_of_channels, assert_valid_sample_rate, RENDER_QUANTUM_SIZE};
+
+/// The `PullAudioContext` renders the audio graph on demand, block by block, with no fixed
+/// length
+///
+/// Unlike the [`OfflineAudioContext`](super::OfflineAudioContext), which renders a predefined
+/// length as fast as possible, and the [`AudioContext`](super::AudioContext), which is driven by
+/// the audio hardware, the rendering is driven by the clock of a host application, e.g. a plugin
+/// wrapper or the mixer of a game engine, which pulls the audio when it needs it.
+///
+/// Nodes can be created and controlled from any thread, the changes are applied at the start of
+/// the next rendered block. The events (e.g. `ended`) are dispatched on the thread pulling the
+/// audio, right after each rendered block.
+///
+/// This type is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, PullAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let mut context = PullAudioContext::new(2, 48_000.);
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&context.destination());
+/// osc.start();
+///
+/// // e.g. in the audio callback of the host, with interleaved stereo frames
+/// let mut block = vec![0.; 2 * 512];
+/// context.render(&mut block);
+///
+/// // or one render quantum at a time
+/// let quantum: &[f32] = context.render_next_quantum();
+/// assert_eq!(quantum.len(), 2 * 128);
+/// ```
+pub struct PullAudioContext {
+    /// represents the underlying `BaseAudioContext`
+    base: ConcreteBaseAudioContext,
+    /// actual renderer of the audio graph, driven by the caller
+    renderer: RenderThread,
+    /// event loop to run after each rendered block
+    event_loop: EventLoop,
+    /// interleaved frames of the last rendered quantum
+    quantum: Vec<f32>,
+}
+
+impl std::fmt::Debug for PullAudioContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PullAudioContext")
+            .field("base", &self.base())
+            .finish_non_exhaustive()
+    }
+}
+
+impl BaseAudioContext for PullAudioContext {
+    fn base(&self) -> &ConcreteBaseAudioContext {
+        &self.base
+    }
+}
+
+impl PullAudioContext {
+    /// Creates a `PullAudioContext` instance
+    ///
+    /// # Arguments
+    ///
+    /// * `number_of_channels` - number of interleaved output channels to render
+    /// * `sample_rate` - output sample rate
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of channels or the sample rate is invalid
+    #[must_use]
+    pub fn new(number_of_channels: usize, sample_rate: f32) -> Self {
+        assert_valid_number_of_channels(number_of_channels);
+        assert_valid_sample_rate(sample_rate);
+
+        // communication channel to the renderer,
+        // unbounded is fine because it does not need to be realtime safe
+        let (sender, receiver) = crossbeam_channel::unbounded();
+
+        let (node_id_producer, node_id_consumer) = llq::Queue::new().split();
+        let graph = crate::render::graph::Graph::new(node_id_producer);
+        let message = crate::message::ControlMessage::Startup { graph };
+        sender.send(message).unwrap();
+
+        // track number of frames - synced from the renderer to the control side
+        let frames_played = Arc::new(AtomicU64::new(0));
+        let frames_played_clone = Arc::clone(&frames_played);
+        let state = Arc::new(AtomicU8::new(AudioContextState::Suspended as u8));
+        let state_clone = Arc::clone(&state);
+
+        // Communication channel for events from the renderer to the control side.
+        // Use an unbounded channel because we do not require real-time safety.
+        let (event_send, event_recv) = crossbeam_channel::unbounded();
+        let event_loop = EventLoop::new(event_recv);
+
+        // the renderer may run in the realtime callback of the host, so deallocations are
+        // offloaded to the garbage collector thread
+        let mut renderer = RenderThread::new(
+            sample_rate,
+            number_of_channels,
+            receiver,
+            state_clone,
+            frames_played_clone,
+            event_send.clone(),
+        );
+        renderer.spawn_garbage_collector_thread();
+
+        let base = ConcreteBaseAudioContext::new(
+            sample_rate,
+            number_of_channels,
+            state,
+            frames_played,
+            sender,
+            event_send,
+            event_loop.clone(),
+            true,
+            node_id_consumer,
+        );
+
+        Self {
+            base,
+            renderer,
+            event_loop,
+            quantum: vec![0.; RENDER_QUANTUM_SIZE * number_of_channels],
+        }
+    }
+
+    /// Number of interleaved output channels
+    #[must_use]
+    pub fn number_of_channels(&self) -> usize {
+        self.base.max_channel_count()
+    }
+
+    /// Render the next frames of the audio graph into the provided buffer of interleaved frames
+    ///
+    /// The buffer can be of any length, frames rendered beyond its end are kept for the next
+    /// call so that the timeline of the graph is continuous.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer length is not a multiple of the number of channels
+    pub fn render(&mut self, buffer: &mut [f32]) {
+        assert_eq!(
+            buffer.len() % self.number_of_channels(),
+            0,
+            "IndexSizeError - Invalid buffer length: {:?} is not a multiple of {:?} channels",
+            buffer.len(),
+            self.number_of_channels(),
+        );
+
+        self.renderer.render(buffer);
+
+        // dispatch the events of the rendered frames
+        self.event_loop.handle_pending_events();
+    }
+
+    /// Render the next render quantum of the audio graph, returns its interleaved frames
+    ///
+    /// The returned slice holds 128 frames and is overwritten by the next call.
+    pub fn render_next_quantum(&mut self) -> &[f32] {
+        let mut quantum = std::mem::take(&mut self.quantum);
+        self.render(&mut quantum);
+        self.quantum = quantum;
+
+        &self.quantum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+
+    #[test]
+    fn test_render_next_quantum() {
+        let mut context = PullAudioContext::new(2, 48_000.);
+        assert_eq!(context.state(), AudioContextState::Suspended);
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.start_at(RENDER_QUANTUM_SIZE as f64 / 48_000.);
+
+        let quantum = context.render_next_quantum();
+        assert_float_eq!(quantum, &[0.; 2 * RENDER_QUANTUM_SIZE][..], abs_all <= 0.);
+        assert_eq!(context.state(), AudioContextState::Running);
+
+        let quantum = context.render_next_quantum();
+        assert_float_eq!(quantum, &[1.; 2 * RENDER_QUANTUM_SIZE][..], abs_all <= 0.);
+        assert_float_eq!(
+            context.current_time(),
+            2. * RENDER_QUANTUM_SIZE as f64 / 48_000.,
+            abs <= 0.
+        );
+    }
+
+    #[test]
+    fn test_render_arbitrary_blocks() {
+        let mut context = PullAudioContext::new(1, 48_000.);
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.start_at(200. / 48_000.);
+
+        // the timeline is continuous across blocks not aligned with the render quantum
+        let mut output = vec![];
+        let mut block = [0.; 100];
+        for _ in 0..3 {
+            context.render(&mut block);
+            output.extend_from_slice(&block);
+        }
+
+        assert_float_eq!(&output[..200], &[0.; 200][..], abs_all <= 0.);
+        assert_float_eq!(&output[200..], &[1.; 100][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_ended_event() {
+        let mut context = PullAudioContext::new(1, 48_000.);
+
+        let ended = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let ended_clone = Arc::clone(&ended);
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.set_onended(move |_| {
+            ended_clone.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+        src.start();
+        src.stop_at(RENDER_QUANTUM_SIZE as f64 / 48_000.);
+
+        context.render_next_quantum();
+        context.render_next_quantum();
+        assert!(ended.load(std::sync::atomic::Ordering::Relaxed));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_buffer_length() {
+        let mut context = PullAudioContext::new(2, 48_000.);
+        let mut block = [0.; 3];
+        context.render(&mut block);
+    }
+}
@@ -1,17 +1,239 @@
 use std::error::Error;
-use std::io::{Read, Seek, SeekFrom};
+use std::fmt;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::sync::OnceLock;
 
-use crate::buffer::{AudioBuffer, ChannelData};
+use crate::buffer::{AudioBuffer, ChannelData, DecodeOptions};
 
 use symphonia::core::audio::AudioBufferRef;
 use symphonia::core::audio::Signal;
-use symphonia::core::codecs::{Decoder, DecoderOptions, FinalizeResult};
+use symphonia::core::codecs::{CodecRegistry, CodecType, Decoder, DecoderOptions, FinalizeResult};
+use symphonia::core::codecs::{
+    CODEC_TYPE_AAC, CODEC_TYPE_ALAC, CODEC_TYPE_FLAC, CODEC_TYPE_MP3, CODEC_TYPE_NULL,
+    CODEC_TYPE_OPUS, CODEC_TYPE_VORBIS,
+};
 use symphonia::core::conv::FromSample;
 use symphonia::core::errors::Error as SymphoniaError;
 use symphonia::core::formats::{FormatOptions, FormatReader};
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, Tag};
 use symphonia::core::probe::Hint;
 
+/// Codecs with their name and the feature flag enabling them
+const CODECS: &[(CodecType, &str, &str)] = &[
+    (CODEC_TYPE_AAC, "AAC", "aac"),
+    (CODEC_TYPE_ALAC, "ALAC", "alac"),
+    (CODEC_TYPE_FLAC, "FLAC", "flac"),
+    (CODEC_TYPE_MP3, "MP3", "mp3"),
+    (CODEC_TYPE_OPUS, "Opus", "opus"),
+    (CODEC_TYPE_VORBIS, "Vorbis", "ogg"),
+];
+
+/// Reason why an input could not be decoded
+///
+/// The decoding methods of [`BaseAudioContext`](crate::context::BaseAudioContext) return a boxed
+/// error, which can be downcast to this type to tell unsupported inputs apart from corrupted
+/// files and IO errors.
+///
+/// This type is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, OfflineAudioContext};
+/// use web_audio_api::DecodeError;
+///
+/// let context = OfflineAudioContext::new(2, 44_100, 44_100.);
+/// let file = std::fs::File::open("samples/podcast.opus").unwrap();
+///
+/// match context.decode_audio_data_sync(file) {
+///     Ok(buffer) => println!("decoded {} frames", buffer.length()),
+///     Err(e) => match e.downcast_ref::<DecodeError>() {
+///         Some(DecodeError::UnsupportedCodec {
+///             feature: Some(feature),
+///             ..
+///         }) => println!("enable the {feature} feature"),
+///         _ => println!("cannot decode: {e}"),
+///     },
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The container format of the input is not recognized, or its feature flag is not enabled
+    UnsupportedFormat,
+    /// The input contains no audio track
+    NoAudioTrack,
+    /// The codec of the audio track is not supported
+    UnsupportedCodec {
+        /// Name of the codec
+        codec: String,
+        /// Feature flag enabling the codec, if any
+        feature: Option<&'static str>,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat => write!(f, "EncodingError - unsupported container format"),
+            Self::NoAudioTrack => write!(f, "EncodingError - no audio track"),
+            Self::UnsupportedCodec {
+                codec,
+                feature: Some(feature),
+            } => write!(
+                f,
+                "EncodingError - the {codec} codec requires the `{feature}` feature"
+            ),
+            Self::UnsupportedCodec {
+                codec,
+                feature: None,
+            } => write!(f, "EncodingError - unsupported codec {codec}"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+impl DecodeError {
+    fn unsupported_codec(codec: CodecType) -> Self {
+        match CODECS.iter().find(|(c, _, _)| *c == codec) {
+            Some(&(_, name, feature)) => Self::UnsupportedCodec {
+                codec: name.to_string(),
+                feature: Some(feature),
+            },
+            None => Self::UnsupportedCodec {
+                codec: codec.to_string(),
+                feature: None,
+            },
+        }
+    }
+}
+
+/// Information on a decoded input, see
+/// [`BaseAudioContext::decode_audio_data_with_metadata_sync`](crate::context::BaseAudioContext::decode_audio_data_with_metadata_sync)
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct AudioMetadata {
+    /// Title of the track
+    pub title: Option<String>,
+    /// Artist of the track
+    pub artist: Option<String>,
+    /// Album of the track
+    pub album: Option<String>,
+    /// All tags of the input, by key, including the ones above
+    pub tags: Vec<(String, String)>,
+    /// Duration announced by the container, in seconds
+    ///
+    /// This is an estimate, the length of the decoded buffer is authoritative.
+    pub duration: Option<f64>,
+    /// Sample rate of the input, before its conversion to the sample rate of the context
+    pub sample_rate: Option<f32>,
+    /// MIDI note at which the sample plays at its original pitch, from the sampler chunk of WAV
+    /// files
+    pub root_key: Option<u8>,
+    /// Loops of the sampler chunk of WAV files
+    pub loops: Vec<SampleLoop>,
+}
+
+impl AudioMetadata {
+    fn from_tags(tags: &[Tag]) -> Self {
+        let mut metadata = Self::default();
+
+        tags.iter().for_each(|tag| {
+            let value = tag.value.to_string();
+            let field = match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => Some(&mut metadata.title),
+                Some(StandardTagKey::Artist) => Some(&mut metadata.artist),
+                Some(StandardTagKey::Album) => Some(&mut metadata.album),
+                _ => None,
+            };
+            if let Some(field) = field {
+                field.get_or_insert_with(|| value.clone());
+            }
+            metadata.tags.push((tag.key.clone(), value));
+        });
+
+        metadata
+    }
+}
+
+/// Loop of a sample, to be played with
+/// [`AudioBufferSourceNode::set_loop_start`](crate::node::AudioBufferSourceNode::set_loop_start)
+/// and [`AudioBufferSourceNode::set_loop_end`](crate::node::AudioBufferSourceNode::set_loop_end)
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampleLoop {
+    /// Start of the loop, in seconds
+    pub start: f64,
+    /// End of the loop, in seconds
+    pub end: f64,
+    /// Number of times the loop is played, 0 meaning infinitely
+    pub play_count: u32,
+}
+
+/// Sampler chunk (`smpl`) of a WAV file, with its loop points in frames
+#[derive(Debug, PartialEq)]
+struct SamplerChunk {
+    root_key: u8,
+    /// Start, inclusive end and play count of the loops
+    loops: Vec<(u32, u32, u32)>,
+}
+
+impl SamplerChunk {
+    /// Find the sampler chunk of a RIFF WAVE file
+    fn find(bytes: &[u8]) -> Option<Self> {
+        if bytes.get(..4)? != b"RIFF" || bytes.get(8..12)? != b"WAVE" {
+            return None;
+        }
+
+        let mut position = 12;
+        while let Some(header) = bytes.get(position..position + 8) {
+            let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+            let body = bytes.get(position + 8..)?;
+            if &header[..4] == b"smpl" {
+                return Self::parse(body.get(..size)?);
+            }
+            // chunks are padded to an even size
+            position += 8 + size + size % 2;
+        }
+
+        None
+    }
+
+    fn parse(body: &[u8]) -> Option<Self> {
+        let read = |offset: usize| {
+            body.get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        let root_key = u8::try_from(read(12)?).ok()?;
+        let loops = (0..read(28)? as usize)
+            .map_while(|i| {
+                let offset = 36 + 24 * i;
+                Some((read(offset + 8)?, read(offset + 12)?, read(offset + 20)?))
+            })
+            .collect();
+
+        Some(Self { root_key, loops })
+    }
+}
+
+/// Codecs of the enabled features
+fn codecs() -> &'static CodecRegistry {
+    static INSTANCE: OnceLock<CodecRegistry> = OnceLock::new();
+    INSTANCE.get_or_init(|| {
+        let mut registry = CodecRegistry::new();
+        symphonia::default::register_enabled_codecs(&mut registry);
+        // symphonia demuxes Ogg Opus streams but does not decode them
+        #[cfg(feature = "opus")]
+        registry.register_all::<opus_decoder::OpusDecoder>();
+        registry
+    })
+}
+
 /// Wrapper for `Read` implementers to be used in Symphonia decoding
 ///
 /// Symphonia requires its input to impl `Seek` - but allows non-seekable sources. Hence we
@@ -49,12 +271,14 @@ impl<R: Read + Send + Sync> symphonia::core::io::MediaSource for MediaInput<R> {
 
 /// Media stream decoder (OGG, WAV, FLAC, ..)
 ///
-/// The current implementation can decode FLAC, Opus, PCM, Vorbis, and Wav.
+/// The current implementation can decode AAC, ALAC, FLAC, MP3, Opus, PCM, Vorbis, and Wav,
+/// depending on the enabled features.
 pub(crate) struct MediaDecoder {
     format: Box<dyn FormatReader>,
     decoder: Box<dyn Decoder>,
     track_index: usize,
     packet_count: usize,
+    metadata: AudioMetadata,
 }
 
 impl MediaDecoder {
@@ -62,7 +286,8 @@ impl MediaDecoder {
     ///
     /// # Errors
     ///
-    /// This method returns an Error in various cases (IO, mime sniffing, decoding).
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding). Unsupported
+    /// inputs are reported with a [`DecodeError`].
     pub fn try_new<R: std::io::Read + Send + Sync + 'static>(
         input: R,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
@@ -77,7 +302,12 @@ impl MediaDecoder {
         let hint = Hint::new();
 
         // TODO: Allow to customize some options.
-        let format_opts: FormatOptions = Default::default();
+        let format_opts = FormatOptions {
+            // Trim the encoder delay and padding of MP3 and AAC streams, so consecutive files
+            // play back without gaps
+            enable_gapless: true,
+            ..Default::default()
+        };
         let metadata_opts: MetadataOptions = Default::default();
         let decoder_opts = DecoderOptions {
             // Opt-in to verify the decoded data against the checksums in the container.
@@ -85,31 +315,87 @@ impl MediaDecoder {
         };
 
         // Probe the media source stream for a format.
-        let probed =
-            symphonia::default::get_probe().format(&hint, stream, &format_opts, &metadata_opts)?;
+        let mut probed = symphonia::default::get_probe()
+            .format(&hint, stream, &format_opts, &metadata_opts)
+            .map_err(|err| -> Box<dyn Error + Send + Sync> {
+                match err {
+                    SymphoniaError::Unsupported(_) => Box::new(DecodeError::UnsupportedFormat),
+                    // short inputs run out before a format is recognized
+                    SymphoniaError::IoError(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                        Box::new(DecodeError::UnsupportedFormat)
+                    }
+                    err => Box::new(err),
+                }
+            })?;
+
+        // Collect the tags found while probing and in the container
+        let mut tags = Vec::new();
+        if let Some(metadata) = probed.metadata.get() {
+            if let Some(revision) = metadata.current() {
+                tags.extend_from_slice(revision.tags());
+            }
+        }
 
         // Get the format reader yielded by the probe operation.
-        let format = probed.format;
+        let mut format = probed.format;
+        if let Some(revision) = format.metadata().current() {
+            tags.extend_from_slice(revision.tags());
+        }
 
         // Get the default track.
-        let track = format.default_track().ok_or(SymphoniaError::Unsupported(
-            "no default media track available",
-        ))?;
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or(DecodeError::NoAudioTrack)?;
         let track_index = format
             .tracks()
             .iter()
             .position(|t| t.id == track.id)
             .unwrap();
         // Create a (stateful) decoder for the track.
-        let decoder = symphonia::default::get_codecs().make(&track.codec_params, &decoder_opts)?;
+        let decoder = codecs().make(&track.codec_params, &decoder_opts).map_err(
+            |err| -> Box<dyn Error + Send + Sync> {
+                match err {
+                    SymphoniaError::Unsupported(_) => {
+                        Box::new(DecodeError::unsupported_codec(track.codec_params.codec))
+                    }
+                    err => Box::new(err),
+                }
+            },
+        )?;
+
+        let params = &format.tracks()[track_index].codec_params;
+        let metadata = AudioMetadata {
+            duration: params
+                .n_frames
+                .zip(params.sample_rate)
+                .map(|(frames, sample_rate)| frames as f64 / f64::from(sample_rate)),
+            sample_rate: params.sample_rate.map(|sample_rate| sample_rate as f32),
+            ..AudioMetadata::from_tags(&tags)
+        };
 
         Ok(Self {
             format,
             decoder,
             track_index,
             packet_count: 0,
+            metadata,
         })
     }
+
+    /// Tags and duration of the input, known before decoding
+    pub fn metadata(&self) -> &AudioMetadata {
+        &self.metadata
+    }
+
+    /// Number of channels of the decoded track, if announced by the container
+    pub fn number_of_channels(&self) -> Option<usize> {
+        self.format.tracks()[self.track_index]
+            .codec_params
+            .channels
+            .map(|channels| channels.count())
+    }
 }
 
 impl Iterator for MediaDecoder {
@@ -121,6 +407,7 @@ impl Iterator for MediaDecoder {
             decoder,
             track_index,
             packet_count,
+            ..
         } = self;
 
         // Get the track.
@@ -166,6 +453,18 @@ impl Iterator for MediaDecoder {
             match decoder.decode(&packet) {
                 Ok(input) => {
                     let output = convert_buf(input);
+
+                    // Drop the frames of the encoder delay and padding
+                    let length = output.length();
+                    let start = (packet.trim_start as usize).min(length);
+                    let end = length.saturating_sub(packet.trim_end as usize).max(start);
+                    if start == end {
+                        continue;
+                    }
+                    if end - start < length {
+                        return Some(Ok(output.slice(start, end)));
+                    }
+
                     return Some(Ok(output));
                 }
                 Err(SymphoniaError::DecodeError(err)) => {
@@ -185,6 +484,69 @@ impl Iterator for MediaDecoder {
     }
 }
 
+/// Decode the input in full into a single buffer, converted to the sample rate of the context
+/// according to the options
+pub(crate) fn decode_audio_data<R: Read + Send + Sync + 'static>(
+    input: R,
+    sample_rate: f32,
+    options: DecodeOptions,
+) -> Result<AudioBuffer, Box<dyn Error + Send + Sync>> {
+    decode_all(MediaDecoder::try_new(input)?, sample_rate, options)
+}
+
+/// Decode the input in full like [`decode_audio_data`], along with its metadata
+pub(crate) fn decode_audio_data_with_metadata<R: Read + Send + Sync + 'static>(
+    mut input: R,
+    sample_rate: f32,
+    options: DecodeOptions,
+) -> Result<(AudioBuffer, AudioMetadata), Box<dyn Error + Send + Sync>> {
+    // the sampler chunk of WAV files usually follows the audio data, which is the last part of
+    // the input read by the decoder, so the input is read in memory first
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes)?;
+    let sampler = SamplerChunk::find(&bytes);
+
+    let decoder = MediaDecoder::try_new(Cursor::new(bytes))?;
+    let mut metadata = decoder.metadata().clone();
+
+    if let (Some(sampler), Some(rate)) = (sampler, metadata.sample_rate) {
+        let rate = f64::from(rate);
+        metadata.root_key = Some(sampler.root_key);
+        metadata.loops = sampler
+            .loops
+            .into_iter()
+            .map(|(start, end, play_count)| SampleLoop {
+                start: f64::from(start) / rate,
+                end: (f64::from(end) + 1.) / rate,
+                play_count,
+            })
+            .collect();
+    }
+
+    let buffer = decode_all(decoder, sample_rate, options)?;
+    Ok((buffer, metadata))
+}
+
+/// Consume the decoder in full and construct a single buffer out of it
+fn decode_all(
+    decoder: MediaDecoder,
+    sample_rate: f32,
+    options: DecodeOptions,
+) -> Result<AudioBuffer, Box<dyn Error + Send + Sync>> {
+    let buffers = decoder.collect::<Result<Vec<_>, _>>()?;
+    let buffers: Vec<_> = buffers.iter().collect();
+    let mut buffer = AudioBuffer::concat(&buffers)
+        // if there are no samples decoded, return an empty buffer
+        .unwrap_or_else(|| AudioBuffer::from(vec![vec![]], sample_rate));
+
+    // resample to desired rate (no-op if already matching)
+    if let Some(quality) = options.resample {
+        buffer.resample(sample_rate, quality);
+    }
+
+    Ok(buffer)
+}
+
 /// Convert a Symphonia AudioBufferRef to our own AudioBuffer
 fn convert_buf(input: AudioBufferRef<'_>) -> AudioBuffer {
     let channels = 0..input.spec().channels.count();
@@ -231,6 +593,128 @@ fn convert_buf(input: AudioBufferRef<'_>) -> AudioBuffer {
     AudioBuffer::from_channels(channels, sample_rate)
 }
 
+#[cfg(feature = "opus")]
+mod opus_decoder {
+    use std::sync::{Mutex, PoisonError};
+
+    use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Channels, Signal, SignalSpec};
+    use symphonia::core::codecs::{
+        CodecDescriptor, CodecParameters, Decoder, DecoderOptions, FinalizeResult, CODEC_TYPE_OPUS,
+    };
+    use symphonia::core::errors::{Error, Result};
+    use symphonia::core::formats::Packet;
+    use symphonia::core::support_codec;
+
+    /// Opus streams are always decoded at 48 kHz
+    const SAMPLE_RATE: u32 = 48_000;
+
+    /// Maximum number of frames of a packet, 120 ms
+    const MAX_FRAMES: usize = 5760;
+
+    /// Opus decoder backed by libopus, for mono and stereo streams
+    pub(super) struct OpusDecoder {
+        params: CodecParameters,
+        // the libopus decoder is not Sync
+        decoder: Mutex<opus::Decoder>,
+        number_of_channels: usize,
+        /// Frames left to discard at the start of the stream
+        pre_skip: usize,
+        interleaved: Vec<f32>,
+        buffer: AudioBuffer<f32>,
+    }
+
+    impl Decoder for OpusDecoder {
+        fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+            // identification header of the stream, see RFC 7845
+            let header = params
+                .extra_data
+                .as_deref()
+                .filter(|header| header.len() >= 19 && header.starts_with(b"OpusHead"))
+                .ok_or(Error::DecodeError("opus: missing identification header"))?;
+            if header[18] != 0 {
+                return Err(Error::Unsupported("opus: multichannel streams"));
+            }
+
+            let number_of_channels = usize::from(header[9]);
+            let (channels, layout) = match number_of_channels {
+                1 => (opus::Channels::Mono, Channels::FRONT_LEFT),
+                2 => (
+                    opus::Channels::Stereo,
+                    Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+                ),
+                _ => return Err(Error::DecodeError("opus: invalid number of channels")),
+            };
+            let decoder = opus::Decoder::new(SAMPLE_RATE, channels)
+                .map_err(|_| Error::DecodeError("opus: invalid stream"))?;
+
+            Ok(Self {
+                params: params.clone(),
+                decoder: Mutex::new(decoder),
+                number_of_channels,
+                // the demuxer trims the pre-skip itself when it reports it as the stream delay
+                pre_skip: match params.delay {
+                    Some(_) => 0,
+                    None => usize::from(u16::from_le_bytes([header[10], header[11]])),
+                },
+                interleaved: vec![0.; MAX_FRAMES * number_of_channels],
+                buffer: AudioBuffer::new(MAX_FRAMES as u64, SignalSpec::new(SAMPLE_RATE, layout)),
+            })
+        }
+
+        fn supported_codecs() -> &'static [CodecDescriptor] {
+            &[support_codec!(CODEC_TYPE_OPUS, "opus", "Opus")]
+        }
+
+        fn reset(&mut self) {
+            let decoder = self
+                .decoder
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner);
+            let _ = decoder.reset_state();
+        }
+
+        fn codec_params(&self) -> &CodecParameters {
+            &self.params
+        }
+
+        fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+            let decoder = self
+                .decoder
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner);
+            let frames = decoder
+                .decode_float(packet.buf(), &mut self.interleaved, false)
+                .map_err(|_| Error::DecodeError("opus: invalid packet"))?;
+
+            // the first frames of the stream are the priming of the decoder
+            let skip = self.pre_skip.min(frames);
+            self.pre_skip -= skip;
+
+            self.buffer.clear();
+            self.buffer.render_reserved(Some(frames - skip));
+            for c in 0..self.number_of_channels {
+                self.buffer
+                    .chan_mut(c)
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(i, s)| {
+                        *s = self.interleaved[(skip + i) * self.number_of_channels + c]
+                    });
+            }
+
+            Ok(self.buffer.as_audio_buffer_ref())
+        }
+
+        fn finalize(&mut self) -> FinalizeResult {
+            FinalizeResult { verify_ok: None }
+        }
+
+        fn last_decoded(&self) -> AudioBufferRef<'_> {
+            self.buffer.as_audio_buffer_ref()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -243,4 +727,81 @@ mod tests {
 
         assert!(media.is_err()); // the input was not a valid MIME type
     }
+
+    #[test]
+    fn test_decode_error() {
+        let input = Cursor::new(vec![0; 32]);
+        let error = MediaDecoder::try_new(input).err().unwrap();
+        assert_eq!(
+            error.downcast_ref::<DecodeError>(),
+            Some(&DecodeError::UnsupportedFormat)
+        );
+
+        let error = DecodeError::unsupported_codec(CODEC_TYPE_OPUS);
+        assert_eq!(
+            error.to_string(),
+            "EncodingError - the Opus codec requires the `opus` feature"
+        );
+    }
+
+    #[cfg(feature = "wav")]
+    #[test]
+    fn test_decode_metadata() {
+        // one second of silence at 22050 Hz
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 22050,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+        (0..22050).for_each(|_| writer.write_sample(0_i16).unwrap());
+        writer.finalize().unwrap();
+        let mut bytes = bytes.into_inner();
+
+        // append a sampler chunk, root key 60 and a single loop over frames [100, 200]
+        let mut chunk = vec![0_u32; 9 + 6];
+        chunk[3] = 60;
+        chunk[7] = 1;
+        chunk[9 + 2] = 100;
+        chunk[9 + 3] = 200;
+        bytes.extend_from_slice(b"smpl");
+        bytes.extend_from_slice(&(4 * chunk.len() as u32).to_le_bytes());
+        chunk
+            .iter()
+            .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()));
+        let riff_size = (bytes.len() - 8) as u32;
+        bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let (buffer, metadata) =
+            decode_audio_data_with_metadata(Cursor::new(bytes), 44_100., DecodeOptions::default())
+                .unwrap();
+        assert_eq!(buffer.length(), 44_100);
+        assert_eq!(metadata.sample_rate, Some(22050.));
+        assert_eq!(metadata.duration, Some(1.));
+        assert_eq!(metadata.root_key, Some(60));
+        assert_eq!(
+            metadata.loops,
+            vec![SampleLoop {
+                start: 100. / 22050.,
+                end: 201. / 22050.,
+                play_count: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_sampler_chunk() {
+        assert_eq!(SamplerChunk::find(b"RIFF\0\0\0\0WAVE"), None);
+        assert_eq!(SamplerChunk::find(b"RIFF\0\0\0\0WAVEsmpl\xff\0\0\0"), None);
+    }
+
+    #[cfg(feature = "m4a")]
+    #[test]
+    fn test_decode_aac() {
+        let file = std::fs::File::open("samples/sample-aac.m4a").unwrap();
+        let buffer = decode_audio_data(file, 44_100., DecodeOptions::default()).unwrap();
+        assert!(buffer.length() > 0);
+    }
 }
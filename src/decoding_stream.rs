@@ -0,0 +1,305 @@
+use std::error::Error;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+
+use crate::decoding::{AudioMetadata, MediaDecoder};
+use crate::media_streams::{MediaStream, MediaStreamTrack};
+use crate::{AtomicF64, AudioBuffer, ErrorEvent, Event, FallibleBuffer, RENDER_QUANTUM_SIZE};
+
+/// Number of decoded packets kept ahead of the playback, a few seconds for common codecs
+const READ_AHEAD: usize = 256;
+
+/// Interval between two progress events, in seconds of decoded audio
+const PROGRESS_INTERVAL: f64 = 1.;
+
+type ProgressEventCallback = Box<dyn FnMut(DecodingProgressEvent) + Send + 'static>;
+type EventCallback = Box<dyn FnOnce(Event) + Send + 'static>;
+type ErrorEventCallback = Box<dyn FnOnce(ErrorEvent) + Send + 'static>;
+
+struct DecodingStreamInner {
+    metadata: AudioMetadata,
+    buffered: AtomicF64,
+    finished: AtomicBool,
+    progress_callback: Mutex<Option<ProgressEventCallback>>,
+    ended_callback: Mutex<Option<EventCallback>>,
+    error_callback: Mutex<Option<ErrorEventCallback>>,
+}
+
+impl DecodingStreamInner {
+    fn progress(&self) {
+        if let Some(f) = self.progress_callback.lock().unwrap().as_mut() {
+            (f)(DecodingProgressEvent {
+                buffered: self.buffered.load(Ordering::Relaxed),
+                duration: self.metadata.duration,
+                event: Event { type_: "progress" },
+            })
+        }
+    }
+
+    fn end(&self) {
+        self.finished.store(true, Ordering::Relaxed);
+        self.progress();
+
+        if let Some(f) = self.ended_callback.lock().unwrap().take() {
+            (f)(Event { type_: "ended" })
+        }
+    }
+
+    fn handle_error(&self, error: Box<dyn Error + Send + Sync>) {
+        if let Some(f) = self.error_callback.lock().unwrap().take() {
+            (f)(ErrorEvent {
+                message: error.to_string(),
+                error: Box::new(error),
+                event: Event {
+                    type_: "ErrorEvent",
+                },
+            })
+        }
+    }
+}
+
+/// Audio input decoded incrementally, played as a [`MediaStream`] while it is decoding
+///
+/// Created with
+/// [`BaseAudioContext::decode_audio_data_stream`](crate::context::BaseAudioContext::decode_audio_data_stream).
+/// The input is decoded on a dedicated thread, a few seconds ahead of the playback, so long files
+/// start playing instantly and are never decoded in memory as a whole. The stream plays silence
+/// when the decoding falls behind, e.g. when the input is read from a slow network.
+///
+/// Progress events report the decoded duration, the ended event is dispatched when the input is
+/// decoded in full. The callbacks run on the decoding thread.
+///
+/// This type is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::AudioNode;
+///
+/// let context = AudioContext::default();
+/// let file = std::fs::File::open("samples/major-scale.ogg").unwrap();
+/// let decoding = context.decode_audio_data_stream(file).unwrap();
+///
+/// decoding.set_onprogress(|event| {
+///     println!("decoded {:.1} of {:?} seconds", event.buffered, event.duration);
+/// });
+///
+/// let src = context.create_media_stream_source(decoding.stream());
+/// src.connect(&context.destination());
+/// ```
+pub struct DecodingStream {
+    stream: MediaStream,
+    inner: Arc<DecodingStreamInner>,
+}
+
+impl std::fmt::Debug for DecodingStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecodingStream")
+            .field("stream", &self.stream)
+            .field("metadata", &self.inner.metadata)
+            .field("buffered", &self.buffered())
+            .field("finished", &self.finished())
+            .finish_non_exhaustive()
+    }
+}
+
+impl DecodingStream {
+    /// Probe the input and start decoding it on a dedicated thread
+    pub(crate) fn new<R: Read + Send + Sync + 'static>(
+        input: R,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let decoder = MediaDecoder::try_new(input)?;
+
+        let metadata = decoder.metadata().clone();
+        let silence = {
+            let number_of_channels = decoder.number_of_channels().unwrap_or(2);
+            let sample_rate = metadata.sample_rate.unwrap_or(48_000.);
+            AudioBuffer::from(
+                vec![vec![0.; RENDER_QUANTUM_SIZE]; number_of_channels],
+                sample_rate,
+            )
+        };
+
+        let inner = Arc::new(DecodingStreamInner {
+            metadata,
+            buffered: AtomicF64::new(0.),
+            finished: AtomicBool::new(false),
+            progress_callback: Mutex::new(None),
+            ended_callback: Mutex::new(None),
+            error_callback: Mutex::new(None),
+        });
+
+        let (sender, receiver) = crossbeam_channel::bounded(READ_AHEAD);
+        {
+            let inner = Arc::clone(&inner);
+            std::thread::Builder::new()
+                .name("decoding-stream".into())
+                .spawn(move || decode(decoder, &sender, &inner))?;
+        }
+
+        let track = MediaStreamTrack::from_iter(DecodedBuffers { receiver, silence });
+        let stream = MediaStream::from_tracks(vec![track]);
+
+        Ok(Self { stream, inner })
+    }
+
+    /// The decoded audio, to be played with a
+    /// [`MediaStreamAudioSourceNode`](crate::node::MediaStreamAudioSourceNode)
+    pub fn stream(&self) -> &MediaStream {
+        &self.stream
+    }
+
+    /// Tags and duration of the input, see [`AudioMetadata`]
+    ///
+    /// The loop points of the sampler chunk of WAV files are not available, as the chunk usually
+    /// follows the audio data.
+    pub fn metadata(&self) -> &AudioMetadata {
+        &self.inner.metadata
+    }
+
+    /// Duration of the decoded audio, in seconds
+    pub fn buffered(&self) -> f64 {
+        self.inner.buffered.load(Ordering::Relaxed)
+    }
+
+    /// True when the input has been decoded in full
+    pub fn finished(&self) -> bool {
+        self.inner.finished.load(Ordering::Relaxed)
+    }
+
+    #[allow(clippy::missing_panics_doc)]
+    pub fn set_onprogress<F: FnMut(DecodingProgressEvent) + Send + 'static>(&self, callback: F) {
+        *self.inner.progress_callback.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    #[allow(clippy::missing_panics_doc)]
+    pub fn clear_onprogress(&self) {
+        *self.inner.progress_callback.lock().unwrap() = None;
+    }
+
+    #[allow(clippy::missing_panics_doc)]
+    pub fn set_onended<F: FnOnce(Event) + Send + 'static>(&self, callback: F) {
+        *self.inner.ended_callback.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    #[allow(clippy::missing_panics_doc)]
+    pub fn clear_onended(&self) {
+        *self.inner.ended_callback.lock().unwrap() = None;
+    }
+
+    #[allow(clippy::missing_panics_doc)]
+    pub fn set_onerror<F: FnOnce(ErrorEvent) + Send + 'static>(&self, callback: F) {
+        *self.inner.error_callback.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    #[allow(clippy::missing_panics_doc)]
+    pub fn clear_onerror(&self) {
+        *self.inner.error_callback.lock().unwrap() = None;
+    }
+}
+
+/// Decode the input ahead of the playback, until it is decoded in full or the stream is dropped
+fn decode(decoder: MediaDecoder, sender: &Sender<FallibleBuffer>, inner: &DecodingStreamInner) {
+    let mut reported = 0.;
+
+    for item in decoder {
+        match item {
+            Ok(buffer) => {
+                let buffered = inner.buffered.load(Ordering::Relaxed) + buffer.duration();
+                inner.buffered.store(buffered, Ordering::Relaxed);
+
+                // blocks while the read ahead is full
+                if sender.send(Ok(buffer)).is_err() {
+                    return; // the stream has been dropped
+                }
+
+                if buffered - reported >= PROGRESS_INTERVAL {
+                    reported = buffered;
+                    inner.progress();
+                }
+            }
+            Err(error) => {
+                let _ = sender.send(Err(error.to_string().into()));
+                inner.handle_error(error);
+                return;
+            }
+        }
+    }
+
+    inner.end();
+}
+
+/// Buffers of the decoding thread, polled on the render thread
+struct DecodedBuffers {
+    receiver: Receiver<FallibleBuffer>,
+    /// Played while the decoding thread falls behind
+    silence: AudioBuffer,
+}
+
+impl Iterator for DecodedBuffers {
+    type Item = FallibleBuffer;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.receiver.try_recv() {
+            Ok(item) => Some(item),
+            Err(TryRecvError::Empty) => {
+                log::debug!("DecodingStream buffer underrun");
+                Some(Ok(self.silence.clone()))
+            }
+            Err(TryRecvError::Disconnected) => None,
+        }
+    }
+}
+
+/// Interface for the `progress` event of a [`DecodingStream`]
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct DecodingProgressEvent {
+    /// Duration of the decoded audio, in seconds
+    pub buffered: f64,
+    /// Duration announced by the container, in seconds
+    pub duration: Option<f64>,
+    /// Inherits from this base Event
+    pub event: Event,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_decoding_stream() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+        (0..2 * 3 * 44100).for_each(|_| writer.write_sample(1000_i16).unwrap());
+        writer.finalize().unwrap();
+        bytes.set_position(0);
+
+        let decoding = DecodingStream::new(bytes).unwrap();
+        assert_float_eq!(decoding.metadata().duration.unwrap(), 3., abs <= 0.);
+
+        // the stream is consumed while decoding, it ends when the input is decoded in full
+        let length: usize = decoding.stream().get_tracks()[0]
+            .iter()
+            .map(|buffer| buffer.unwrap().length())
+            .sum();
+
+        assert!(decoding.finished());
+        assert!(length >= 3 * 44100);
+        assert_float_eq!(decoding.buffered(), 3., abs <= 1e-9);
+    }
+}
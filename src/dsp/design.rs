@@ -0,0 +1,1083 @@
+//! Filter design
+//!
+//! IIR designs (Butterworth, Chebyshev, elliptic, shelving and the RBJ cookbook filters) produce
+//! [`IirCoefficients`], which convert into [`IIRFilterOptions`]. FIR designs (windowed-sinc and
+//! Parks-McClellan) produce taps for [`FirFilterOptions`](crate::node::FirFilterOptions).
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use web_audio_api::context::{AudioContext, BaseAudioContext};
+//! use web_audio_api::dsp::design::{self, FilterResponse, FirResponse, Window};
+//! use web_audio_api::node::{AudioNode, FirFilterNode, FirFilterOptions, IIRFilterNode};
+//!
+//! let context = AudioContext::default();
+//! let sample_rate = context.sample_rate();
+//!
+//! let coefs = design::butterworth(FilterResponse::Lowpass, 4, 1000., sample_rate);
+//! let iir = IIRFilterNode::new(&context, coefs.into());
+//!
+//! let taps = design::windowed_sinc(FirResponse::Highpass(80.), 511, Window::Hann, sample_rate);
+//! let fir = FirFilterNode::new(&context, FirFilterOptions { taps, ..Default::default() });
+//!
+//! iir.connect(&fir);
+//! fir.connect(&context.destination());
+//! ```
+
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use num_complex::Complex;
+
+use crate::node::{calculate_coefs, AudioNodeOptions, BiquadFilterType, IIRFilterOptions};
+
+/// Filters designed from analog prototypes are limited by the `IIRFilterNode` to 20 coefficients
+const MAX_ORDER: usize = 19;
+
+/// Density of the frequency grid of the Parks-McClellan algorithm
+const GRID_DENSITY: usize = 16;
+
+/// Maximum number of iterations of the Parks-McClellan algorithm
+const MAX_ITERATIONS: usize = 40;
+
+/// Zeroth order modified Bessel function of the first kind
+pub(crate) fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.;
+    let mut term = 1.;
+    let mut k = 1.;
+
+    while term > sum * 1e-12 {
+        term *= (x / (2. * k)).powi(2);
+        sum += term;
+        k += 1.;
+    }
+
+    sum
+}
+
+#[track_caller]
+#[inline(always)]
+fn assert_valid_order(order: usize) {
+    assert!(
+        (1..=MAX_ORDER).contains(&order),
+        "NotSupportedError - filter order should be in the range [1, {}], got {}",
+        MAX_ORDER,
+        order
+    );
+}
+
+#[track_caller]
+#[inline(always)]
+fn assert_valid_frequency(frequency: f32, sample_rate: f32) {
+    let nyquist = sample_rate / 2.;
+    assert!(
+        frequency > 0. && frequency < nyquist,
+        "NotSupportedError - frequency should be in the range ]0, {}[, got {}",
+        nyquist,
+        frequency
+    );
+}
+
+/// Coefficients of an IIR filter, normalized so that `feedback[0]` is 1
+#[derive(Clone, Debug, PartialEq)]
+pub struct IirCoefficients {
+    pub feedforward: Vec<f64>,
+    pub feedback: Vec<f64>,
+}
+
+impl IirCoefficients {
+    /// Magnitude response of the filter at the given frequency
+    pub fn magnitude(&self, frequency: f32, sample_rate: f32) -> f64 {
+        let w = 2. * PI * f64::from(frequency) / f64::from(sample_rate);
+        // z^-1
+        let z = Complex::from_polar(1., -w);
+        let eval = |coefs: &[f64]| {
+            coefs
+                .iter()
+                .rev()
+                .fold(Complex::new(0., 0.), |acc, &c| acc * z + c)
+        };
+
+        (eval(&self.feedforward) / eval(&self.feedback)).norm()
+    }
+}
+
+impl From<IirCoefficients> for IIRFilterOptions {
+    fn from(coefs: IirCoefficients) -> Self {
+        Self {
+            audio_node_options: AudioNodeOptions::default(),
+            feedforward: coefs.feedforward,
+            feedback: coefs.feedback,
+        }
+    }
+}
+
+/// Response of the filters designed from analog prototypes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterResponse {
+    Lowpass,
+    Highpass,
+}
+
+/// Butterworth filter, maximally flat in the passband
+///
+/// The response is 3dB down at `frequency`.
+///
+/// # Panics
+///
+/// This function panics if `order` is outside the [1, 19] range, or if `frequency` is outside
+/// the ]0, nyquist[ range.
+pub fn butterworth(
+    response: FilterResponse,
+    order: usize,
+    frequency: f32,
+    sample_rate: f32,
+) -> IirCoefficients {
+    assert_valid_order(order);
+    assert_valid_frequency(frequency, sample_rate);
+
+    let n = order as f64;
+    let poles = (0..order)
+        .map(|k| Complex::from_polar(1., PI * (2. * k as f64 + n + 1.) / (2. * n)))
+        .collect();
+
+    digital(vec![], poles, 1., response, frequency, sample_rate)
+}
+
+/// Chebyshev type I filter, with equiripple passband and steeper transition than a Butterworth
+/// filter of the same order
+///
+/// `ripple` is the peak-to-peak passband ripple in dB. The response leaves the ripple band at
+/// `frequency`.
+///
+/// # Panics
+///
+/// This function panics if `order` is outside the [1, 19] range, if `frequency` is outside
+/// the ]0, nyquist[ range or if `ripple` is not strictly positive.
+pub fn chebyshev(
+    response: FilterResponse,
+    order: usize,
+    ripple: f32,
+    frequency: f32,
+    sample_rate: f32,
+) -> IirCoefficients {
+    assert_valid_order(order);
+    assert_valid_frequency(frequency, sample_rate);
+    assert!(
+        ripple > 0.,
+        "NotSupportedError - ripple should be strictly positive, got {}",
+        ripple
+    );
+
+    let n = order as f64;
+    let eps = (10_f64.powf(f64::from(ripple) / 10.) - 1.).sqrt();
+    let mu = (1. / eps).asinh() / n;
+
+    let poles = (0..order)
+        .map(|k| {
+            let theta = PI * (2. * k as f64 + 1.) / (2. * n);
+            Complex::new(-mu.sinh() * theta.sin(), mu.cosh() * theta.cos())
+        })
+        .collect();
+
+    digital(
+        vec![],
+        poles,
+        passband_gain(order, eps),
+        response,
+        frequency,
+        sample_rate,
+    )
+}
+
+/// Elliptic (Cauer) filter, with equiripple passband and stopband and the steepest transition
+/// for a given order
+///
+/// `ripple` is the peak-to-peak passband ripple in dB, `attenuation` the minimum stopband
+/// attenuation in dB. The response leaves the ripple band at `frequency`.
+///
+/// # Panics
+///
+/// This function panics if `order` is outside the [1, 19] range, if `frequency` is outside
+/// the ]0, nyquist[ range or if `ripple` is not in the ]0, `attenuation`[ range.
+pub fn elliptic(
+    response: FilterResponse,
+    order: usize,
+    ripple: f32,
+    attenuation: f32,
+    frequency: f32,
+    sample_rate: f32,
+) -> IirCoefficients {
+    assert_valid_order(order);
+    assert_valid_frequency(frequency, sample_rate);
+    assert!(
+        ripple > 0. && ripple < attenuation,
+        "NotSupportedError - ripple ({}) should be in the range ]0, attenuation ({})[",
+        ripple,
+        attenuation
+    );
+
+    // see S. J. Orfanidis, "Lecture Notes on Elliptic Filter Design", 2006
+    let n = order as f64;
+    let ep = (10_f64.powf(f64::from(ripple) / 10.) - 1.).sqrt();
+    let es = (10_f64.powf(f64::from(attenuation) / 10.) - 1.).sqrt();
+    let k1 = ep / es;
+    let k = ellipdeg(order, k1);
+
+    let j = Complex::new(0., 1.);
+    let v0 = (-j * asne(j / ep, k1) / n).re;
+
+    let mut zeros = vec![];
+    let mut poles = vec![];
+    for i in 1..=order / 2 {
+        let u = (2. * i as f64 - 1.) / n;
+
+        let zeta = cde(Complex::from(u), k).re;
+        let zero = j / (k * zeta);
+        zeros.extend([zero, zero.conj()]);
+
+        let pole = j * cde(Complex::new(u, -v0), k);
+        poles.extend([pole, pole.conj()]);
+    }
+    if order % 2 == 1 {
+        poles.push(j * sne(Complex::new(0., v0), k));
+    }
+
+    digital(
+        zeros,
+        poles,
+        passband_gain(order, ep),
+        response,
+        frequency,
+        sample_rate,
+    )
+}
+
+/// Gain at DC of the equiripple lowpass prototypes, odd orders start at the top of the ripple
+/// band, even orders at the bottom
+fn passband_gain(order: usize, eps: f64) -> f64 {
+    if order % 2 == 1 {
+        1.
+    } else {
+        1. / (1. + eps * eps).sqrt()
+    }
+}
+
+/// Descending Landen sequence of elliptic moduli
+fn landen(k: f64) -> Vec<f64> {
+    let mut moduli = vec![];
+    let mut k = k;
+
+    while k > 1e-15 && moduli.len() < 16 {
+        k = (k / (1. + (1. - k * k).sqrt())).powi(2);
+        moduli.push(k);
+    }
+
+    moduli
+}
+
+/// Jacobi elliptic function cd(uK, k)
+fn cde(u: Complex<f64>, k: f64) -> Complex<f64> {
+    landen(k).iter().rev().fold((u * FRAC_PI_2).cos(), |w, &v| {
+        (1. + v) * w / (1. + v * w * w)
+    })
+}
+
+/// Jacobi elliptic function sn(uK, k)
+fn sne(u: Complex<f64>, k: f64) -> Complex<f64> {
+    landen(k).iter().rev().fold((u * FRAC_PI_2).sin(), |w, &v| {
+        (1. + v) * w / (1. + v * w * w)
+    })
+}
+
+/// Inverse of `cde`, principal value only which suffices for the prototype design
+fn acde(w: Complex<f64>, k: f64) -> Complex<f64> {
+    let mut w = w;
+    let mut previous = k;
+
+    for v in landen(k) {
+        w = w / (1. + (1. - w * w * previous * previous).sqrt()) * 2. / (1. + v);
+        previous = v;
+    }
+
+    w.acos() * 2. / PI
+}
+
+/// Inverse of `sne`
+fn asne(w: Complex<f64>, k: f64) -> Complex<f64> {
+    1. - acde(w, k)
+}
+
+/// Solve the degree equation for the selectivity modulus, given the order and the
+/// discrimination modulus
+fn ellipdeg(order: usize, k1: f64) -> f64 {
+    let kc1 = (1. - k1 * k1).sqrt();
+    let product: f64 = (1..=order / 2)
+        .map(|i| sne(Complex::from((2. * i as f64 - 1.) / order as f64), kc1).re)
+        .product();
+    let kc = kc1.powi(order as i32) * product.powi(4);
+
+    (1. - kc * kc).sqrt()
+}
+
+/// Turn a normalized analog lowpass prototype into a digital filter using the bilinear transform
+///
+/// `gain` is the target gain in the passband, at DC for lowpass filters and at nyquist for
+/// highpass filters.
+fn digital(
+    zeros: Vec<Complex<f64>>,
+    poles: Vec<Complex<f64>>,
+    gain: f64,
+    response: FilterResponse,
+    frequency: f32,
+    sample_rate: f32,
+) -> IirCoefficients {
+    // prewarp the cutoff frequency for the bilinear transform s = (z - 1) / (z + 1)
+    let wc = (PI * f64::from(frequency) / f64::from(sample_rate)).tan();
+
+    let transform = |r: Complex<f64>| match response {
+        FilterResponse::Lowpass => r * wc,
+        FilterResponse::Highpass => wc / r,
+    };
+    // zeros at infinity map to nyquist for lowpass filters, and to DC for highpass filters
+    let extra_zero = match response {
+        FilterResponse::Lowpass => -1.,
+        FilterResponse::Highpass => 1.,
+    };
+    let bilinear = |r: Complex<f64>| (1. + r) / (1. - r);
+
+    let mut digital_zeros: Vec<_> = zeros.iter().map(|&z| bilinear(transform(z))).collect();
+    digital_zeros.resize(poles.len(), Complex::from(extra_zero));
+    let digital_poles: Vec<_> = poles.iter().map(|&p| bilinear(transform(p))).collect();
+
+    let mut coefs = IirCoefficients {
+        feedforward: polynomial(&digital_zeros),
+        feedback: polynomial(&digital_poles),
+    };
+
+    // normalize the passband gain
+    let reference = match response {
+        FilterResponse::Lowpass => 0.,
+        FilterResponse::Highpass => sample_rate / 2.,
+    };
+    let scale = gain / coefs.magnitude(reference, sample_rate);
+    coefs.feedforward.iter_mut().for_each(|b| *b *= scale);
+
+    coefs
+}
+
+/// Coefficients of the monic polynomial with the given roots, highest degree first
+///
+/// The roots are expected to come in complex conjugate pairs, so the coefficients are real.
+fn polynomial(roots: &[Complex<f64>]) -> Vec<f64> {
+    let mut coefs = vec![Complex::new(1., 0.)];
+
+    for &root in roots {
+        coefs.push(Complex::new(0., 0.));
+        for i in (1..coefs.len()).rev() {
+            let previous = coefs[i - 1];
+            coefs[i] -= root * previous;
+        }
+    }
+
+    coefs.iter().map(|c| c.re).collect()
+}
+
+/// Cookbook biquad filter, as used by the `BiquadFilterNode`
+///
+/// `q` and `gain` are interpreted as the `Q` and `gain` parameters of the
+/// [`BiquadFilterNode`](crate::node::BiquadFilterNode), i.e. `q` is in dB for the lowpass and
+/// highpass types.
+///
+/// # Panics
+///
+/// This function panics if `frequency` is outside the ]0, nyquist[ range.
+pub fn rbj(
+    type_: BiquadFilterType,
+    frequency: f32,
+    q: f32,
+    gain: f32,
+    sample_rate: f32,
+) -> IirCoefficients {
+    assert_valid_frequency(frequency, sample_rate);
+
+    let coefs = calculate_coefs(
+        type_,
+        f64::from(sample_rate),
+        f64::from(frequency),
+        f64::from(gain),
+        f64::from(q),
+    );
+
+    IirCoefficients {
+        feedforward: vec![coefs.b0, coefs.b1, coefs.b2],
+        feedback: vec![1., coefs.a1, coefs.a2],
+    }
+}
+
+/// Type of first order shelving filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shelf {
+    Low,
+    High,
+}
+
+/// First order shelving filter, with a gentler slope than the second order shelves of the
+/// [`rbj`] designs
+///
+/// `gain` is the boost (or cut, if negative) of the shelf in dB, the response reaches half of
+/// that gain at `frequency`.
+///
+/// # Panics
+///
+/// This function panics if `frequency` is outside the ]0, nyquist[ range.
+pub fn shelf(type_: Shelf, frequency: f32, gain: f32, sample_rate: f32) -> IirCoefficients {
+    assert_valid_frequency(frequency, sample_rate);
+
+    let wc = (PI * f64::from(frequency) / f64::from(sample_rate)).tan();
+    let g = 10_f64.powf(f64::from(gain) / 20.);
+
+    // analog prototype (b1 * s + b0) / (a1 * s + a0)
+    let (b1, b0, a1, a0) = match type_ {
+        Shelf::Low => (1., wc * g.sqrt(), 1., wc / g.sqrt()),
+        Shelf::High => (g, wc * g.sqrt(), 1., wc * g.sqrt()),
+    };
+
+    // bilinear transform s = (z - 1) / (z + 1)
+    let norm = a0 + a1;
+    IirCoefficients {
+        feedforward: vec![(b0 + b1) / norm, (b0 - b1) / norm],
+        feedback: vec![1., (a0 - a1) / norm],
+    }
+}
+
+/// Window functions for the [`windowed_sinc`] design
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+    /// Kaiser window, larger `beta` values trade a wider transition for more stopband
+    /// attenuation
+    Kaiser {
+        beta: f64,
+    },
+}
+
+impl Window {
+    /// Value of the symmetric window of the given length at index `n`
+    fn evaluate(&self, n: usize, length: usize) -> f64 {
+        if length == 1 {
+            return 1.;
+        }
+
+        let x = n as f64 / (length - 1) as f64;
+        match self {
+            Window::Rectangular => 1.,
+            Window::Hann => 0.5 - 0.5 * (2. * PI * x).cos(),
+            Window::Hamming => 0.54 - 0.46 * (2. * PI * x).cos(),
+            Window::Blackman => 0.42 - 0.5 * (2. * PI * x).cos() + 0.08 * (4. * PI * x).cos(),
+            Window::Kaiser { beta } => {
+                let ratio = 2. * x - 1.;
+                bessel_i0(beta * (1. - ratio * ratio).sqrt()) / bessel_i0(*beta)
+            }
+        }
+    }
+}
+
+/// Response of the [`windowed_sinc`] designs, with cutoff frequencies in Hz
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FirResponse {
+    Lowpass(f32),
+    Highpass(f32),
+    Bandpass(f32, f32),
+    Bandstop(f32, f32),
+}
+
+/// Linear phase FIR filter designed with the window method
+///
+/// The response is 6dB down at the cutoff frequencies.
+///
+/// # Panics
+///
+/// This function panics if:
+/// - any cutoff frequency is outside the ]0, nyquist[ range
+/// - the band edges are not increasing
+/// - `number_of_taps` is even for the highpass and bandstop responses, which require a non zero
+///   gain at nyquist
+pub fn windowed_sinc(
+    response: FirResponse,
+    number_of_taps: usize,
+    window: Window,
+    sample_rate: f32,
+) -> Vec<f32> {
+    let (low, high) = match response {
+        FirResponse::Lowpass(f) | FirResponse::Highpass(f) => (f, f),
+        FirResponse::Bandpass(low, high) | FirResponse::Bandstop(low, high) => (low, high),
+    };
+    assert_valid_frequency(low, sample_rate);
+    assert_valid_frequency(high, sample_rate);
+    assert!(
+        low <= high,
+        "NotSupportedError - band edges should be increasing, got {} and {}",
+        low,
+        high
+    );
+    if matches!(
+        response,
+        FirResponse::Highpass(_) | FirResponse::Bandstop(..)
+    ) {
+        assert!(
+            number_of_taps % 2 == 1,
+            "NotSupportedError - number_of_taps should be odd for {:?}, got {}",
+            response,
+            number_of_taps
+        );
+    }
+
+    let center = (number_of_taps - 1) as f64 / 2.;
+
+    // windowed sinc normalized to unity gain at DC
+    let lowpass = |cutoff: f32| -> Vec<f64> {
+        let fc = f64::from(cutoff) / f64::from(sample_rate);
+        let mut taps: Vec<f64> = (0..number_of_taps)
+            .map(|n| {
+                let x = n as f64 - center;
+                let sinc = if x == 0. {
+                    2. * fc
+                } else {
+                    (2. * PI * fc * x).sin() / (PI * x)
+                };
+                sinc * window.evaluate(n, number_of_taps)
+            })
+            .collect();
+        let sum: f64 = taps.iter().sum();
+        taps.iter_mut().for_each(|t| *t /= sum);
+        taps
+    };
+
+    // spectral inversion, only valid for odd lengths
+    let invert = |mut taps: Vec<f64>| {
+        taps.iter_mut().for_each(|t| *t = -*t);
+        taps[number_of_taps / 2] += 1.;
+        taps
+    };
+
+    let bandpass = || -> Vec<f64> {
+        lowpass(high)
+            .iter()
+            .zip(lowpass(low))
+            .map(|(h, l)| h - l)
+            .collect()
+    };
+
+    let taps = match response {
+        FirResponse::Lowpass(f) => lowpass(f),
+        FirResponse::Highpass(f) => invert(lowpass(f)),
+        FirResponse::Bandpass(..) => bandpass(),
+        FirResponse::Bandstop(..) => invert(bandpass()),
+    };
+
+    taps.iter().map(|&t| t as f32).collect()
+}
+
+/// Optimal (equiripple) linear phase FIR filter designed with the Parks-McClellan algorithm
+///
+/// `bands` are pairs of band edges in Hz, in increasing order and within [0, nyquist]. Each band
+/// has a constant `desired` gain and a `weight` for its approximation error, e.g. a weight of
+/// 10 in the stopband of a lowpass filter yields 10 times less ripple there than in the passband.
+///
+/// # Panics
+///
+/// This function panics if:
+/// - `number_of_taps` is even or smaller than 3
+/// - `bands` is empty or the band edges are not increasing or outside [0, nyquist]
+/// - `desired` and `weights` do not have one entry per band, or a weight is not strictly positive
+pub fn parks_mcclellan(
+    number_of_taps: usize,
+    bands: &[[f32; 2]],
+    desired: &[f32],
+    weights: &[f32],
+    sample_rate: f32,
+) -> Vec<f32> {
+    assert!(
+        number_of_taps >= 3 && number_of_taps % 2 == 1,
+        "NotSupportedError - number_of_taps should be odd and at least 3, got {}",
+        number_of_taps
+    );
+    assert!(
+        !bands.is_empty() && bands.len() == desired.len() && bands.len() == weights.len(),
+        "NotSupportedError - bands, desired and weights should have the same non zero length"
+    );
+    let edges: Vec<f32> = bands.iter().flatten().copied().collect();
+    assert!(
+        edges.windows(2).all(|w| w[0] <= w[1])
+            && bands.iter().all(|b| b[0] < b[1])
+            && edges[0] >= 0.
+            && edges[edges.len() - 1] <= sample_rate / 2.,
+        "NotSupportedError - band edges should be increasing and within [0, nyquist], got {:?}",
+        bands
+    );
+    assert!(
+        weights.iter().all(|&w| w > 0.),
+        "NotSupportedError - weights should be strictly positive, got {:?}",
+        weights
+    );
+
+    // the frequency response of a symmetric filter of odd length is the cosine polynomial
+    // A(w) = sum(a_k cos(k w)) for k in 0..=l
+    let l = (number_of_taps - 1) / 2;
+    let number_of_extrema = l + 2;
+
+    // dense grid of normalized frequencies in [0, 0.5], per band
+    let step = 0.5 / (GRID_DENSITY * (l + 1)) as f64;
+    let mut grid = Grid::default();
+    for ((band, &d), &w) in bands.iter().zip(desired).zip(weights) {
+        let low = f64::from(band[0]) / f64::from(sample_rate);
+        let high = f64::from(band[1]) / f64::from(sample_rate);
+        let count = ((high - low) / step).ceil().max(1.) as usize;
+
+        let start = grid.x.len();
+        for i in 0..=count {
+            let f = low + (high - low) * i as f64 / count as f64;
+            grid.x.push((2. * PI * f).cos());
+            grid.desired.push(f64::from(d));
+            grid.weight.push(f64::from(w));
+        }
+        grid.bands.push((start, grid.x.len() - 1));
+    }
+    assert!(
+        grid.x.len() > number_of_extrema,
+        "NotSupportedError - bands are too narrow for {} taps",
+        number_of_taps
+    );
+
+    // initial guess of the extremal frequencies, evenly spread over the grid
+    let mut extrema: Vec<usize> = (0..number_of_extrema)
+        .map(|i| i * (grid.x.len() - 1) / (number_of_extrema - 1))
+        .collect();
+    let mut interpolant = Interpolant::new(&extrema, &grid);
+
+    for _ in 0..MAX_ITERATIONS {
+        let error: Vec<f64> = (0..grid.x.len())
+            .map(|i| grid.weight[i] * (grid.desired[i] - interpolant.evaluate(grid.x[i])))
+            .collect();
+
+        let candidates = grid.extrema(&error, number_of_extrema);
+        if candidates.len() != number_of_extrema {
+            break;
+        }
+
+        let max_error = candidates
+            .iter()
+            .fold(0., |max: f64, &i| max.max(error[i].abs()));
+        let converged = max_error - interpolant.delta.abs() <= 1e-6 * max_error;
+
+        extrema = candidates;
+        interpolant = Interpolant::new(&extrema, &grid);
+
+        if converged {
+            break;
+        }
+    }
+
+    // sample A(w) on the DFT frequencies and invert the DFT to get the taps
+    let n = number_of_taps as f64;
+    let a: Vec<f64> = (0..=l)
+        .map(|m| interpolant.evaluate((2. * PI * m as f64 / n).cos()))
+        .collect();
+
+    (0..number_of_taps)
+        .map(|k| {
+            let t = k as f64 - l as f64;
+            let sum: f64 = a[1..]
+                .iter()
+                .enumerate()
+                .map(|(m, &a)| 2. * a * (2. * PI * (m + 1) as f64 * t / n).cos())
+                .sum();
+            ((a[0] + sum) / n) as f32
+        })
+        .collect()
+}
+
+/// Frequency grid of the Parks-McClellan algorithm, in the `x = cos(w)` domain
+#[derive(Default)]
+struct Grid {
+    x: Vec<f64>,
+    desired: Vec<f64>,
+    weight: Vec<f64>,
+    /// first and last grid index of each band
+    bands: Vec<(usize, usize)>,
+}
+
+impl Grid {
+    /// Find the alternating extrema of the weighted error
+    fn extrema(&self, error: &[f64], count: usize) -> Vec<usize> {
+        let mut extrema: Vec<usize> = vec![];
+
+        for &(start, end) in &self.bands {
+            for i in start..=end {
+                let e = error[i];
+                let left = if i > start { Some(error[i - 1]) } else { None };
+                let right = if i < end { Some(error[i + 1]) } else { None };
+                let is_extremum = (e > 0.
+                    && left.map_or(true, |l| e >= l)
+                    && right.map_or(true, |r| e > r))
+                    || (e < 0. && left.map_or(true, |l| e <= l) && right.map_or(true, |r| e < r));
+                if !is_extremum {
+                    continue;
+                }
+
+                // keep the largest of consecutive extrema with the same sign
+                match extrema.last_mut() {
+                    Some(last) if error[*last].signum() == e.signum() => {
+                        if e.abs() > error[*last].abs() {
+                            *last = i;
+                        }
+                    }
+                    _ => extrema.push(i),
+                }
+            }
+        }
+
+        // drop superfluous extrema, preserving the alternation
+        while extrema.len() > count {
+            if extrema.len() == count + 1 {
+                let first = error[extrema[0]].abs();
+                let last = error[extrema[extrema.len() - 1]].abs();
+                if first < last {
+                    extrema.remove(0);
+                } else {
+                    extrema.pop();
+                }
+                continue;
+            }
+
+            let (smallest, _) = extrema
+                .iter()
+                .enumerate()
+                .min_by(|a, b| error[*a.1].abs().total_cmp(&error[*b.1].abs()))
+                .unwrap();
+            extrema.remove(smallest);
+
+            // the neighbours now have the same sign, keep the largest
+            if smallest > 0 && smallest < extrema.len() {
+                let (a, b) = (extrema[smallest - 1], extrema[smallest]);
+                if error[a].abs() < error[b].abs() {
+                    extrema.remove(smallest - 1);
+                } else {
+                    extrema.remove(smallest);
+                }
+            }
+        }
+
+        extrema
+    }
+}
+
+/// Polynomial interpolating the desired response on the extremal frequencies, with alternating
+/// error `delta`, in barycentric form
+struct Interpolant {
+    delta: f64,
+    x: Vec<f64>,
+    values: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+impl Interpolant {
+    fn new(extrema: &[usize], grid: &Grid) -> Self {
+        let x: Vec<f64> = extrema.iter().map(|&i| grid.x[i]).collect();
+
+        let alternation = |i: usize| if i % 2 == 0 { 1. } else { -1. };
+
+        let weights = barycentric_weights(&x);
+        let (numerator, denominator) = extrema.iter().zip(&weights).enumerate().fold(
+            (0., 0.),
+            |(num, den), (i, (&index, &w))| {
+                (
+                    num + w * grid.desired[index],
+                    den + w * alternation(i) / grid.weight[index],
+                )
+            },
+        );
+        let delta = numerator / denominator;
+
+        // interpolate through all but the last extremal frequency
+        let n = x.len() - 1;
+        let values = extrema[..n]
+            .iter()
+            .enumerate()
+            .map(|(i, &index)| grid.desired[index] - alternation(i) * delta / grid.weight[index])
+            .collect();
+        let x = x[..n].to_vec();
+        let weights = barycentric_weights(&x);
+
+        Self {
+            delta,
+            x,
+            values,
+            weights,
+        }
+    }
+
+    fn evaluate(&self, x: f64) -> f64 {
+        let mut numerator = 0.;
+        let mut denominator = 0.;
+
+        for ((&xi, &value), &weight) in self.x.iter().zip(&self.values).zip(&self.weights) {
+            let dx = x - xi;
+            if dx.abs() < 1e-14 {
+                return value;
+            }
+            let c = weight / dx;
+            numerator += c * value;
+            denominator += c;
+        }
+
+        numerator / denominator
+    }
+}
+
+/// Barycentric weights `1 / prod(x_i - x_j)`, up to a common scale factor
+///
+/// The products are computed in the log domain as they easily over- or underflow.
+fn barycentric_weights(x: &[f64]) -> Vec<f64> {
+    let logs: Vec<(f64, f64)> = x
+        .iter()
+        .enumerate()
+        .map(|(i, &xi)| {
+            x.iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .fold((0., 1.), |(log, sign), (_, &xj)| {
+                    let d = xi - xj;
+                    (log - d.abs().ln(), sign * d.signum())
+                })
+        })
+        .collect();
+
+    let max = logs
+        .iter()
+        .fold(f64::NEG_INFINITY, |max, &(log, _)| max.max(log));
+    logs.iter()
+        .map(|&(log, sign)| sign * (log - max).exp())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.;
+
+    // magnitude response of FIR taps at the given frequency
+    fn fir_magnitude(taps: &[f32], frequency: f32) -> f64 {
+        IirCoefficients {
+            feedforward: taps.iter().map(|&t| f64::from(t)).collect(),
+            feedback: vec![1.],
+        }
+        .magnitude(frequency, SAMPLE_RATE)
+    }
+
+    fn assert_symmetric(taps: &[f32]) {
+        let reversed: Vec<f32> = taps.iter().rev().copied().collect();
+        assert_float_eq!(taps[..], reversed[..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_butterworth() {
+        let lowpass = butterworth(FilterResponse::Lowpass, 4, 1000., SAMPLE_RATE);
+        assert_eq!(lowpass.feedforward.len(), 5);
+        assert_eq!(lowpass.feedback.len(), 5);
+        assert_float_eq!(lowpass.feedback[0], 1., abs <= 1e-12);
+        assert_float_eq!(lowpass.magnitude(0., SAMPLE_RATE), 1., abs <= 1e-9);
+        assert_float_eq!(
+            lowpass.magnitude(1000., SAMPLE_RATE),
+            std::f64::consts::FRAC_1_SQRT_2,
+            abs <= 1e-6
+        );
+        // 24dB per octave
+        assert!(lowpass.magnitude(4000., SAMPLE_RATE) < 10_f64.powf(-46. / 20.));
+
+        let highpass = butterworth(FilterResponse::Highpass, 3, 1000., SAMPLE_RATE);
+        assert_float_eq!(highpass.magnitude(24_000., SAMPLE_RATE), 1., abs <= 1e-9);
+        assert_float_eq!(highpass.magnitude(0., SAMPLE_RATE), 0., abs <= 1e-9);
+        assert_float_eq!(
+            highpass.magnitude(1000., SAMPLE_RATE),
+            std::f64::consts::FRAC_1_SQRT_2,
+            abs <= 1e-6
+        );
+    }
+
+    #[test]
+    fn test_butterworth_matches_rbj() {
+        let butterworth = butterworth(FilterResponse::Lowpass, 2, 1000., SAMPLE_RATE);
+        // Q = 1 / sqrt(2), i.e. -3.0103dB
+        let rbj = rbj(BiquadFilterType::Lowpass, 1000., -3.0103, 0., SAMPLE_RATE);
+
+        assert_float_eq!(
+            butterworth.feedforward[..],
+            rbj.feedforward[..],
+            abs_all <= 1e-5
+        );
+        assert_float_eq!(butterworth.feedback[..], rbj.feedback[..], abs_all <= 1e-5);
+    }
+
+    #[test]
+    fn test_chebyshev() {
+        let ripple = 10_f64.powf(-1. / 20.);
+
+        for order in [3, 4] {
+            let coefs = chebyshev(FilterResponse::Lowpass, order, 1., 1000., SAMPLE_RATE);
+            assert_float_eq!(coefs.magnitude(1000., SAMPLE_RATE), ripple, abs <= 1e-6);
+
+            // within the ripple band in the passband
+            for f in [0., 200., 500., 800.] {
+                let m = coefs.magnitude(f, SAMPLE_RATE);
+                assert!(m <= 1. + 1e-9 && m >= ripple - 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_elliptic() {
+        let ripple = 10_f64.powf(-1. / 20.);
+        let attenuation = 10_f64.powf(-40. / 20.);
+
+        for order in [3, 4] {
+            let coefs = elliptic(FilterResponse::Lowpass, order, 1., 40., 1000., SAMPLE_RATE);
+            assert_float_eq!(coefs.magnitude(1000., SAMPLE_RATE), ripple, abs <= 1e-6);
+
+            for f in [0., 200., 500., 800.] {
+                let m = coefs.magnitude(f, SAMPLE_RATE);
+                assert!(m <= 1. + 1e-9 && m >= ripple - 1e-9);
+            }
+            for f in [4000., 6000., 10_000., 20_000.] {
+                assert!(coefs.magnitude(f, SAMPLE_RATE) <= attenuation + 1e-6);
+            }
+        }
+
+        let highpass = elliptic(FilterResponse::Highpass, 5, 0.5, 60., 1000., SAMPLE_RATE);
+        assert_float_eq!(highpass.magnitude(24_000., SAMPLE_RATE), 1., abs <= 1e-9);
+        assert!(highpass.magnitude(100., SAMPLE_RATE) <= 1e-3 + 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_order() {
+        let _ = butterworth(FilterResponse::Lowpass, 20, 1000., SAMPLE_RATE);
+    }
+
+    #[test]
+    fn test_shelf() {
+        let low = shelf(Shelf::Low, 1000., 12., SAMPLE_RATE);
+        assert_float_eq!(
+            low.magnitude(0., SAMPLE_RATE),
+            10_f64.powf(12. / 20.),
+            abs <= 1e-9
+        );
+        assert_float_eq!(
+            low.magnitude(1000., SAMPLE_RATE),
+            10_f64.powf(6. / 20.),
+            abs <= 1e-6
+        );
+        assert_float_eq!(low.magnitude(24_000., SAMPLE_RATE), 1., abs <= 1e-9);
+
+        let high = shelf(Shelf::High, 1000., -12., SAMPLE_RATE);
+        assert_float_eq!(high.magnitude(0., SAMPLE_RATE), 1., abs <= 1e-9);
+        assert_float_eq!(
+            high.magnitude(1000., SAMPLE_RATE),
+            10_f64.powf(-6. / 20.),
+            abs <= 1e-6
+        );
+        assert_float_eq!(
+            high.magnitude(24_000., SAMPLE_RATE),
+            10_f64.powf(-12. / 20.),
+            abs <= 1e-9
+        );
+    }
+
+    #[test]
+    fn test_windowed_sinc() {
+        let lowpass = windowed_sinc(
+            FirResponse::Lowpass(4800.),
+            101,
+            Window::Blackman,
+            SAMPLE_RATE,
+        );
+        assert_symmetric(&lowpass);
+        assert_float_eq!(fir_magnitude(&lowpass, 0.), 1., abs <= 1e-6);
+        assert_float_eq!(fir_magnitude(&lowpass, 4800.), 0.5, abs <= 1e-2);
+        assert!(fir_magnitude(&lowpass, 10_000.) < 1e-3);
+
+        let highpass = windowed_sinc(
+            FirResponse::Highpass(4800.),
+            101,
+            Window::Kaiser { beta: 8. },
+            SAMPLE_RATE,
+        );
+        assert_symmetric(&highpass);
+        assert_float_eq!(fir_magnitude(&highpass, 0.), 0., abs <= 1e-6);
+        assert_float_eq!(fir_magnitude(&highpass, 15_000.), 1., abs <= 1e-3);
+
+        let bandpass = windowed_sinc(
+            FirResponse::Bandpass(4000., 8000.),
+            201,
+            Window::Blackman,
+            SAMPLE_RATE,
+        );
+        assert_float_eq!(fir_magnitude(&bandpass, 6000.), 1., abs <= 1e-3);
+        assert!(fir_magnitude(&bandpass, 1000.) < 1e-3);
+
+        let bandstop = windowed_sinc(
+            FirResponse::Bandstop(4000., 8000.),
+            201,
+            Window::Hamming,
+            SAMPLE_RATE,
+        );
+        assert!(fir_magnitude(&bandstop, 6000.) < 1e-2);
+        assert_float_eq!(fir_magnitude(&bandstop, 1000.), 1., abs <= 1e-2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_windowed_sinc_even_highpass() {
+        let _ = windowed_sinc(FirResponse::Highpass(4800.), 100, Window::Hann, SAMPLE_RATE);
+    }
+
+    #[test]
+    fn test_parks_mcclellan() {
+        let taps = parks_mcclellan(
+            63,
+            &[[0., 4800.], [7200., 24_000.]],
+            &[1., 0.],
+            &[1., 1.],
+            SAMPLE_RATE,
+        );
+        assert_eq!(taps.len(), 63);
+        assert_symmetric(&taps);
+
+        for f in [0., 1000., 3000., 4800.] {
+            assert_float_eq!(fir_magnitude(&taps, f), 1., abs <= 1e-2);
+        }
+        for f in [7200., 10_000., 16_000., 24_000.] {
+            assert!(fir_magnitude(&taps, f) < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_parks_mcclellan_weights() {
+        let bands = [[0., 4800.], [7200., 24_000.]];
+        let stopband_error = |weights: &[f32]| {
+            let taps = parks_mcclellan(31, &bands, &[1., 0.], weights, SAMPLE_RATE);
+            [7200., 9000., 12_000., 20_000.]
+                .iter()
+                .fold(0., |max: f64, &f| max.max(fir_magnitude(&taps, f)))
+        };
+
+        // more weight in the stopband yields more attenuation
+        assert!(stopband_error(&[1., 10.]) < stopband_error(&[1., 1.]) / 2.);
+    }
+}
@@ -0,0 +1,8 @@
+//! Digital signal processing utilities
+//!
+//! These helpers are not part of the Web Audio API specification.
+
+pub mod design;
+
+mod oversampler;
+pub use oversampler::*;
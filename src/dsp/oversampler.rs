@@ -0,0 +1,237 @@
+use std::f64::consts::PI;
+
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::design::bessel_i0;
+
+/// Number of taps per polyphase branch of the oversampling filters
+const TAPS_PER_PHASE: usize = 32;
+
+/// Cutoff frequency of the oversampling filters, relative to the base sample rate
+const CUTOFF: f64 = 0.45;
+
+/// Shape parameter of the Kaiser window, yields roughly 80dB of stopband attenuation
+const KAISER_BETA: f64 = 8.;
+
+/// Polyphase FIR up- and downsampler, operating on render quanta
+///
+/// Each call to [`Oversampler::process`] upsamples a render quantum by `factor`, hands the
+/// oversampled signal to a callback and downsamples the result back in place. Running
+/// nonlinear processing at the higher rate keeps the harmonics it generates from folding back
+/// into the audible band.
+///
+/// Both directions share the same linear phase lowpass prototype filter, of length
+/// `factor * (TAPS_PER_PHASE - 1) + 1` so the combined delay is an integer number of frames,
+/// see [`Oversampler::LATENCY`].
+///
+/// # Usage
+///
+/// ```
+/// use web_audio_api::dsp::Oversampler;
+///
+/// let mut oversampler = Oversampler::new(4);
+/// oversampler.set_number_of_channels(1);
+///
+/// // a render quantum is 128 frames
+/// let mut channel = [0.5; 128];
+/// oversampler.process(0, &mut channel, |oversampled| {
+///     assert_eq!(oversampled.len(), 4 * 128);
+///     oversampled.iter_mut().for_each(|s| *s = s.tanh());
+/// });
+/// ```
+#[derive(Debug)]
+pub struct Oversampler {
+    factor: usize,
+    /// prototype filter, normalized to unity gain at DC
+    coefs: Vec<f32>,
+    /// prototype filter split in `factor` branches for upsampling, scaled by `factor`
+    phases: Vec<[f32; TAPS_PER_PHASE]>,
+    /// per channel history of the input signal, followed by the current render quantum
+    up_buffers: Vec<Vec<f32>>,
+    /// per channel history of the oversampled signal, followed by the current render quantum
+    down_buffers: Vec<Vec<f32>>,
+}
+
+impl Oversampler {
+    /// Delay in frames (at the base rate) introduced by the up- and downsampling filters
+    ///
+    /// The delay does not depend on the oversampling factor.
+    pub const LATENCY: usize = TAPS_PER_PHASE - 1;
+
+    /// Create a new `Oversampler` for a single channel
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is less than 2
+    #[must_use]
+    pub fn new(factor: usize) -> Self {
+        assert!(
+            factor >= 2,
+            "RangeError - oversampling factor should be at least 2, got {}",
+            factor
+        );
+
+        let length = factor * (TAPS_PER_PHASE - 1) + 1;
+        let center = (length - 1) as f64 / 2.;
+        let cutoff = CUTOFF / factor as f64; // relative to the oversampled rate
+
+        let mut coefs: Vec<f32> = (0..length)
+            .map(|i| {
+                let x = i as f64 - center;
+                let sinc = if x == 0. {
+                    2. * cutoff
+                } else {
+                    (2. * PI * cutoff * x).sin() / (PI * x)
+                };
+                let ratio = x / center;
+                let window =
+                    bessel_i0(KAISER_BETA * (1. - ratio * ratio).sqrt()) / bessel_i0(KAISER_BETA);
+                (sinc * window) as f32
+            })
+            .collect();
+
+        let sum: f32 = coefs.iter().sum();
+        coefs.iter_mut().for_each(|c| *c /= sum);
+
+        let phases = (0..factor)
+            .map(|phase| {
+                let mut branch = [0.; TAPS_PER_PHASE];
+                branch.iter_mut().enumerate().for_each(|(k, c)| {
+                    // the prototype is zero padded up to `factor * TAPS_PER_PHASE` taps
+                    *c = coefs.get(k * factor + phase).copied().unwrap_or(0.) * factor as f32;
+                });
+                branch
+            })
+            .collect();
+
+        let mut oversampler = Self {
+            factor,
+            coefs,
+            phases,
+            up_buffers: vec![],
+            down_buffers: vec![],
+        };
+        oversampler.set_number_of_channels(1);
+
+        oversampler
+    }
+
+    /// The oversampling factor
+    #[must_use]
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Number of channels the filter states are kept for
+    #[must_use]
+    pub fn number_of_channels(&self) -> usize {
+        self.up_buffers.len()
+    }
+
+    /// Update the number of channels, the states of the retained channels are preserved
+    ///
+    /// This method allocates when the number of channels grows.
+    pub fn set_number_of_channels(&mut self, number_of_channels: usize) {
+        let up_length = TAPS_PER_PHASE - 1 + RENDER_QUANTUM_SIZE;
+        let down_length = self.coefs.len() - 1 + RENDER_QUANTUM_SIZE * self.factor;
+
+        self.up_buffers
+            .resize_with(number_of_channels, || vec![0.; up_length]);
+        self.down_buffers
+            .resize_with(number_of_channels, || vec![0.; down_length]);
+    }
+
+    /// Clear the filter states of all channels
+    pub fn reset(&mut self) {
+        self.up_buffers.iter_mut().for_each(|b| b.fill(0.));
+        self.down_buffers.iter_mut().for_each(|b| b.fill(0.));
+    }
+
+    /// Upsample the channel, apply `f` on the oversampled signal and downsample again in place
+    ///
+    /// The oversampled signal passed to `f` holds `factor * RENDER_QUANTUM_SIZE` frames.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not below [`Oversampler::number_of_channels`] or if `channel` is
+    /// not exactly one render quantum long.
+    pub fn process<F: FnOnce(&mut [f32])>(&mut self, index: usize, channel: &mut [f32], f: F) {
+        let factor = self.factor;
+
+        // upsampling
+        let up_buffer = &mut self.up_buffers[index];
+        up_buffer.copy_within(RENDER_QUANTUM_SIZE.., 0);
+        up_buffer[TAPS_PER_PHASE - 1..].copy_from_slice(channel);
+
+        let down_history = self.coefs.len() - 1;
+        let down_buffer = &mut self.down_buffers[index];
+        down_buffer.copy_within(RENDER_QUANTUM_SIZE * factor.., 0);
+
+        let oversampled = &mut down_buffer[down_history..];
+        for n in 0..RENDER_QUANTUM_SIZE {
+            // most recent input sample first
+            let window = &up_buffer[n..n + TAPS_PER_PHASE];
+            for (phase, coefs) in self.phases.iter().enumerate() {
+                oversampled[n * factor + phase] = coefs
+                    .iter()
+                    .zip(window.iter().rev())
+                    .map(|(c, s)| c * s)
+                    .sum();
+            }
+        }
+
+        f(oversampled);
+
+        // downsampling, only compute the retained samples
+        channel.iter_mut().enumerate().for_each(|(n, o)| {
+            let end = down_history + n * factor + 1;
+            let window = &down_buffer[end - self.coefs.len()..end];
+            *o = self
+                .coefs
+                .iter()
+                .zip(window.iter().rev())
+                .map(|(c, s)| c * s)
+                .sum();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_identity_is_delay() {
+        for factor in [2, 4, 8] {
+            let mut oversampler = Oversampler::new(factor);
+
+            let mut impulse = [0.; RENDER_QUANTUM_SIZE];
+            impulse[0] = 1.;
+            oversampler.process(0, &mut impulse, |_| {});
+
+            // the response peaks at the latency and is symmetric around it
+            let peak = impulse
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .unwrap()
+                .0;
+            assert_eq!(peak, Oversampler::LATENCY);
+            for k in 1..Oversampler::LATENCY {
+                assert_float_eq!(
+                    impulse[Oversampler::LATENCY - k],
+                    impulse[Oversampler::LATENCY + k],
+                    abs <= 1e-6
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_factor() {
+        let _ = Oversampler::new(1);
+    }
+}
@@ -0,0 +1,141 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::ListenerHandle;
+
+/// State shared by the future and its listener, which runs on the event thread
+struct Slot<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// Future resolving with the next dispatch of an event
+///
+/// Created with
+/// [`AudioScheduledSourceNode::ended`](crate::node::AudioScheduledSourceNode::ended) or
+/// [`BaseAudioContext::state_change`](crate::context::BaseAudioContext::state_change). The
+/// listener of the event is added when the future is created, so no event is missed before the
+/// future is polled for the first time. It is removed when the future resolves or is dropped.
+///
+/// The future does not depend on a specific runtime: it is woken from the thread dispatching the
+/// events, and can be awaited on any executor.
+///
+/// This type is not part of the Web Audio API specification.
+pub struct EventFuture<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+    listener: Option<ListenerHandle>,
+}
+
+impl<T> std::fmt::Debug for EventFuture<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventFuture")
+            .field("listener", &self.listener)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Send + 'static> EventFuture<T> {
+    /// Create the future, `register` adds a listener resolving it with the provided sender
+    pub(crate) fn new(register: impl FnOnce(EventSender<T>) -> ListenerHandle) -> Self {
+        let slot = Arc::new(Mutex::new(Slot {
+            value: None,
+            waker: None,
+        }));
+        let listener = register(EventSender(Arc::clone(&slot)));
+
+        Self {
+            slot,
+            listener: Some(listener),
+        }
+    }
+}
+
+impl<T> Future for EventFuture<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let this = self.get_mut();
+
+        let mut slot = this.slot.lock().unwrap();
+        let Some(value) = slot.value.take() else {
+            slot.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        };
+        drop(slot);
+
+        if let Some(listener) = this.listener.take() {
+            listener.remove();
+        }
+
+        Poll::Ready(value)
+    }
+}
+
+impl<T> Drop for EventFuture<T> {
+    fn drop(&mut self) {
+        if let Some(listener) = self.listener.take() {
+            listener.remove();
+        }
+    }
+}
+
+/// Resolves the [`EventFuture`] from the listener of the event
+pub(crate) struct EventSender<T>(Arc<Mutex<Slot<T>>>);
+
+impl<T> EventSender<T> {
+    /// Store the value and wake the task awaiting the future, only the first value is kept
+    pub(crate) fn send(&self, value: T) {
+        let mut slot = self.0.lock().unwrap();
+        if slot.value.is_some() {
+            return;
+        }
+        slot.value = Some(value);
+        let waker = slot.waker.take();
+        drop(slot); // release the Mutex before waking the task
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor;
+    use futures::FutureExt;
+
+    use crate::context::{AudioContextState, BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+
+    #[test]
+    fn test_ended() {
+        let mut context = OfflineAudioContext::new(1, 128, 48_000.);
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.start();
+
+        let mut ended = src.ended();
+        assert!((&mut ended).now_or_never().is_none());
+
+        let _ = context.start_rendering_sync();
+        let event = executor::block_on(ended);
+        assert_eq!(event.type_, "ended");
+    }
+
+    #[test]
+    fn test_state_change() {
+        let mut context = OfflineAudioContext::new(1, 128, 48_000.);
+
+        let state_change = context.state_change();
+        let dropped = context.state_change();
+        drop(dropped);
+
+        let _ = context.start_rendering_sync();
+        let event = executor::block_on(state_change);
+        assert_eq!(event.previous_state, AudioContextState::Suspended);
+        assert_eq!(event.state, AudioContextState::Running);
+    }
+}
@@ -6,7 +6,8 @@ use std::any::Any;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::ops::ControlFlow;
-use std::sync::{Arc, Mutex};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, Weak};
 
 use crossbeam_channel::Receiver;
 
@@ -17,7 +18,7 @@ pub struct Event {
     pub type_: &'static str,
 }
 
-#[derive(Hash, Eq, PartialEq, Debug)]
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
 pub(crate) enum EventType {
     Ended(AudioNodeId),
     SinkChange,
@@ -28,6 +29,49 @@ pub(crate) enum EventType {
     Message(AudioNodeId),
     Complete,
     AudioProcessing(AudioNodeId),
+    Onset(AudioNodeId),
+    Spectrogram(AudioNodeId),
+}
+
+impl EventType {
+    /// The events dispatched for the node with the given id
+    fn node_events(id: AudioNodeId) -> [Self; 6] {
+        [
+            Self::Ended(id),
+            Self::ProcessorError(id),
+            Self::Message(id),
+            Self::AudioProcessing(id),
+            Self::Onset(id),
+            Self::Spectrogram(id),
+        ]
+    }
+}
+
+/// The events a listener can be added for, see
+/// [`AudioScheduledSourceNode::add_event_listener`](crate::node::AudioScheduledSourceNode::add_event_listener)
+/// and [`BaseAudioContext::add_event_listener`](crate::context::BaseAudioContext::add_event_listener)
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EventKind {
+    /// The `ended` event of a source node
+    Ended,
+    /// The `statechange` event of a context
+    StateChange,
+    /// The `sinkchange` event of an [`AudioContext`](crate::context::AudioContext)
+    SinkChange,
+}
+
+impl EventKind {
+    /// The type of the dispatched [`Event`]
+    pub(crate) fn type_(self) -> &'static str {
+        match self {
+            Self::Ended => "ended",
+            Self::StateChange => "statechange",
+            Self::SinkChange => "sinkchange",
+        }
+    }
 }
 
 /// The Error Event interface
@@ -68,6 +112,65 @@ impl Drop for AudioProcessingEvent {
     }
 }
 
+/// The OnsetEvent interface, dispatched by the
+/// [`OnsetDetectorNode`](crate::node::OnsetDetectorNode)
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct OnsetEvent {
+    /// Estimated time of the onset, in the same time coordinate system as the AudioContext's
+    /// currentTime
+    pub time: f64,
+    /// Spectral flux of the onset divided by the adaptive threshold, i.e. how clearly it stands
+    /// out from the recent activity (always greater than 1)
+    pub strength: f32,
+    /// Inherits from this base Event
+    pub event: Event,
+}
+
+/// The SpectrogramEvent interface, dispatched for each analysis frame of an
+/// [`AnalyserNode`](crate::node::AnalyserNode) subscribed with
+/// [`subscribe_spectrogram`](crate::node::AnalyserNode::subscribe_spectrogram)
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct SpectrogramEvent {
+    /// Time of the end of the analysis frame, in the same time coordinate system as the
+    /// AudioContext's currentTime
+    pub time: f64,
+    /// Magnitudes of the frequency bins of the frame, in dB
+    pub magnitudes: Vec<f32>,
+    /// Number of frames dropped since the previous event, because the control thread did not
+    /// keep up with the render thread
+    pub dropped_frames: usize,
+    /// Inherits from this base Event
+    pub event: Event,
+    pub(crate) registration: Option<(ConcreteBaseAudioContext, AudioNodeId)>,
+}
+
+impl Drop for SpectrogramEvent {
+    fn drop(&mut self) {
+        // ship the buffer back to the render thread so it can be reused for the next frames
+        if let Some((context, id)) = self.registration.take() {
+            let wrapped = crate::message::ControlMessage::NodeMessage {
+                id,
+                msg: llq::Node::new(Box::new(std::mem::take(&mut self.magnitudes))),
+            };
+            context.send_control_msg(wrapped);
+        }
+    }
+}
+
+/// The Event interface of the `statechange` event of an audio context, reporting the transition
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct AudioContextStateChangeEvent {
+    /// The state of the context before the transition
+    pub previous_state: AudioContextState,
+    /// The current state of the context
+    pub state: AudioContextState,
+    /// Inherits from this base Event
+    pub event: Event,
+}
+
 /// The OfflineAudioCompletionEvent Event interface
 #[non_exhaustive]
 #[derive(Debug)]
@@ -85,9 +188,11 @@ pub(crate) enum EventPayload {
     ProcessorError(ErrorEvent),
     Diagnostics(Vec<u8>),
     Message(Box<dyn Any + Send + 'static>),
-    AudioContextState(AudioContextState),
+    StateChange(AudioContextStateChangeEvent),
     Complete(AudioBuffer),
     AudioProcessing(AudioProcessingEvent),
+    Onset(OnsetEvent),
+    Spectrogram(SpectrogramEvent),
 }
 
 #[derive(Debug)]
@@ -111,10 +216,16 @@ impl EventDispatch {
         }
     }
 
-    pub fn state_change(state: AudioContextState) -> Self {
+    pub fn state_change(previous_state: AudioContextState, state: AudioContextState) -> Self {
         EventDispatch {
             type_: EventType::StateChange,
-            payload: EventPayload::AudioContextState(state),
+            payload: EventPayload::StateChange(AudioContextStateChangeEvent {
+                previous_state,
+                state,
+                event: Event {
+                    type_: "statechange",
+                },
+            }),
         }
     }
 
@@ -159,6 +270,24 @@ impl EventDispatch {
             payload: EventPayload::AudioProcessing(value),
         }
     }
+
+    pub fn onset(id: AudioNodeId, value: OnsetEvent) -> Self {
+        EventDispatch {
+            type_: EventType::Onset(id),
+            payload: EventPayload::Onset(value),
+        }
+    }
+
+    pub fn spectrogram(id: AudioNodeId, value: SpectrogramEvent) -> Self {
+        EventDispatch {
+            type_: EventType::Spectrogram(id),
+            payload: EventPayload::Spectrogram(value),
+        }
+    }
+
+    pub fn into_payload(self) -> EventPayload {
+        self.payload
+    }
 }
 
 pub(crate) enum EventHandler {
@@ -166,10 +295,68 @@ pub(crate) enum EventHandler {
     Multiple(Box<dyn FnMut(EventPayload) + Send + 'static>),
 }
 
+type EventListener = Arc<Mutex<dyn FnMut(&EventPayload) + Send + 'static>>;
+
+/// Listeners of the events, run before the single event handler of the same event
+#[derive(Default)]
+struct EventListeners {
+    next_id: u64,
+    listeners: HashMap<EventType, Vec<(u64, EventListener)>>,
+}
+
+impl EventListeners {
+    fn contains(&self, event: EventType, id: u64) -> bool {
+        self.listeners
+            .get(&event)
+            .is_some_and(|listeners| listeners.iter().any(|(i, _)| *i == id))
+    }
+
+    fn remove(&mut self, event: EventType, id: u64) {
+        if let Some(listeners) = self.listeners.get_mut(&event) {
+            listeners.retain(|(i, _)| *i != id);
+            if listeners.is_empty() {
+                self.listeners.remove(&event);
+            }
+        }
+    }
+}
+
+/// Handle of an event listener, to remove it from its node or context
+///
+/// Unlike the `set_on...` methods, which replace the previous event handler, any number of
+/// listeners can be added to the same event.
+///
+/// Dropping the handle does nothing: the listener stays in place until it is removed with
+/// [`remove`](Self::remove), until the source node has ended, or until the context is dropped.
+/// Use `let _ = ...` to keep a listener for the lifetime of its node or context.
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Debug)]
+#[must_use = "dropping the handle does not remove the listener, use `let _ =` to keep it"]
+pub struct ListenerHandle {
+    listeners: Weak<Mutex<EventListeners>>,
+    event: EventType,
+    id: u64,
+}
+
+impl ListenerHandle {
+    /// Remove the listener, it will not run for the subsequent events
+    ///
+    /// Has no effect when the listener has already been released, e.g. after the `ended` event
+    /// of a source node or when the context is dropped.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn remove(self) {
+        if let Some(listeners) = self.listeners.upgrade() {
+            listeners.lock().unwrap().remove(self.event, self.id);
+        }
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct EventLoop {
     event_recv: Receiver<EventDispatch>,
     event_handlers: Arc<Mutex<HashMap<EventType, EventHandler>>>,
+    event_listeners: Arc<Mutex<EventListeners>>,
 }
 
 impl EventLoop {
@@ -177,17 +364,17 @@ impl EventLoop {
         Self {
             event_recv,
             event_handlers: Default::default(),
+            event_listeners: Default::default(),
         }
     }
 
-    fn handle_event(&self, mut event: EventDispatch) -> ControlFlow<()> {
+    fn handle_event(&self, event: EventDispatch) -> ControlFlow<()> {
         // Terminate the event loop when the audio context is closing
         let mut result = ControlFlow::Continue(());
         if matches!(
-            event.payload,
-            EventPayload::AudioContextState(AudioContextState::Closed)
+            &event.payload,
+            EventPayload::StateChange(e) if e.state == AudioContextState::Closed
         ) {
-            event.payload = EventPayload::None; // the statechange handler takes no argument
             result = ControlFlow::Break(());
         }
 
@@ -198,19 +385,25 @@ impl EventLoop {
             panic!("Rethrowing exception during tests: {:?}", e);
         }
 
+        self.run_listeners(&event);
+
         let mut event_handler_lock = self.event_handlers.lock().unwrap();
         let callback_option = event_handler_lock.remove(&event.type_);
         drop(event_handler_lock); // release Mutex while running callback
 
         if let Some(callback) = callback_option {
             match callback {
-                EventHandler::Once(f) => (f)(event.payload),
+                EventHandler::Once(f) => {
+                    run_callback(event.type_, || (f)(event.payload));
+                }
                 EventHandler::Multiple(mut f) => {
-                    (f)(event.payload);
-                    self.event_handlers
-                        .lock()
-                        .unwrap()
-                        .insert(event.type_, EventHandler::Multiple(f));
+                    // a callback that panicked may be in an inconsistent state, drop it
+                    if run_callback(event.type_, || (f)(event.payload)) {
+                        self.event_handlers
+                            .lock()
+                            .unwrap()
+                            .insert(event.type_, EventHandler::Multiple(f));
+                    }
                 }
             };
         }
@@ -218,6 +411,44 @@ impl EventLoop {
         result
     }
 
+    fn run_listeners(&self, event: &EventDispatch) {
+        // release the Mutex while running the listeners, they may add or remove listeners
+        let listeners = match self
+            .event_listeners
+            .lock()
+            .unwrap()
+            .listeners
+            .get(&event.type_)
+        {
+            Some(listeners) => listeners.clone(),
+            None => return,
+        };
+
+        for (id, listener) in listeners {
+            // skip the listeners removed by the previous ones
+            if !self
+                .event_listeners
+                .lock()
+                .unwrap()
+                .contains(event.type_, id)
+            {
+                continue;
+            }
+            if !run_callback(event.type_, || (listener.lock().unwrap())(&event.payload)) {
+                self.event_listeners.lock().unwrap().remove(event.type_, id);
+            }
+        }
+
+        // a source node ends only once, release its listeners
+        if matches!(event.type_, EventType::Ended(_)) {
+            self.event_listeners
+                .lock()
+                .unwrap()
+                .listeners
+                .remove(&event.type_);
+        }
+    }
+
     #[inline(always)]
     pub fn handle_pending_events(&self) -> bool {
         let mut events_were_handled = false;
@@ -255,4 +486,116 @@ impl EventLoop {
     pub fn clear_handler(&self, event: EventType) {
         self.event_handlers.lock().unwrap().remove(&event);
     }
+
+    /// Release the handlers and listeners of a node, before its id is reused by a new node
+    pub fn clear_node(&self, id: AudioNodeId) {
+        let events = EventType::node_events(id);
+
+        let mut handlers = self.event_handlers.lock().unwrap();
+        for event in &events {
+            handlers.remove(event);
+        }
+        drop(handlers);
+
+        let mut listeners = self.event_listeners.lock().unwrap();
+        for event in &events {
+            listeners.listeners.remove(event);
+        }
+    }
+
+    pub fn add_listener(
+        &self,
+        event: EventType,
+        listener: impl FnMut(&EventPayload) + Send + 'static,
+    ) -> ListenerHandle {
+        let listener: EventListener = Arc::new(Mutex::new(listener));
+
+        let mut listeners = self.event_listeners.lock().unwrap();
+        let id = listeners.next_id;
+        listeners.next_id += 1;
+        listeners
+            .listeners
+            .entry(event)
+            .or_default()
+            .push((id, listener));
+
+        ListenerHandle {
+            listeners: Arc::downgrade(&self.event_listeners),
+            event,
+            id,
+        }
+    }
+}
+
+/// Run a callback of the user, a panic is logged instead of terminating the event loop
+///
+/// Returns false when the callback panicked.
+fn run_callback(event: EventType, callback: impl FnOnce()) -> bool {
+    match panic::catch_unwind(AssertUnwindSafe(callback)) {
+        Ok(()) => true,
+        Err(error) => {
+            // In unit tests, we rethrow panics to avoid missing failed assertions
+            if cfg!(test) {
+                panic::resume_unwind(error);
+            }
+
+            log::error!(
+                "Panic occurred in the callback of the {:?} event: '{}'. Removing the callback.",
+                event,
+                panic_message(&*error)
+            );
+            false
+        }
+    }
+}
+
+/// Message of a panic, from the object with which the panic was invoked
+pub(crate) fn panic_message(error: &(dyn Any + Send)) -> String {
+    if let Some(v) = error.downcast_ref::<String>() {
+        v.to_string()
+    } else if let Some(v) = error.downcast_ref::<&str>() {
+        v.to_string()
+    } else {
+        std::any::type_name::<Box<dyn Any + Send>>().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_clear_node() {
+        let (event_send, event_recv) = crossbeam_channel::unbounded();
+        let event_loop = EventLoop::new(event_recv);
+
+        let ended = Arc::new(AtomicUsize::new(0));
+        for (id, value) in [(3, 1), (4, 2)] {
+            let ended = Arc::clone(&ended);
+            let _ = event_loop.add_listener(EventType::Ended(AudioNodeId(id)), move |_| {
+                ended.fetch_add(value, Ordering::Relaxed);
+            });
+        }
+        let ended_clone = Arc::clone(&ended);
+        event_loop.set_handler(
+            EventType::Ended(AudioNodeId(3)),
+            EventHandler::Once(Box::new(move |_| {
+                ended_clone.fetch_add(4, Ordering::Relaxed);
+            })),
+        );
+
+        // the id 3 is reused, its listeners and handler must not run for the new node
+        event_loop.clear_node(AudioNodeId(3));
+
+        event_send
+            .send(EventDispatch::ended(AudioNodeId(3)))
+            .unwrap();
+        event_send
+            .send(EventDispatch::ended(AudioNodeId(4)))
+            .unwrap();
+        event_loop.handle_pending_events();
+        assert_eq!(ended.load(Ordering::Relaxed), 2);
+    }
 }
@@ -0,0 +1,718 @@
+//! Loading of measured head-related transfer functions
+//!
+//! The `HRTF` panning model of the [`PannerNode`](crate::node::PannerNode) renders binaural
+//! audio with a set of head-related impulse responses. Besides the bundled dataset, the impulse
+//! responses of a listener can be loaded from a SOFA file (AES69) and converted to an
+//! [`HrtfDataset`].
+//!
+//! This is not part of the Web Audio API specification.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use web_audio_api::context::{AudioContext, BaseAudioContext};
+//! use web_audio_api::hrtf::SofaDatabase;
+//! use web_audio_api::node::{PanningModelType, PannerOptions};
+//!
+//! let file = std::fs::File::open("subject.sofa").unwrap();
+//! let dataset = SofaDatabase::from_reader(file).unwrap().into_dataset().unwrap();
+//!
+//! let context = AudioContext::default();
+//! let panner = context.create_panner_with(PannerOptions {
+//!     panning_model: PanningModelType::HRTF,
+//!     hrtf_dataset: dataset,
+//!     ..PannerOptions::default()
+//! });
+//! ```
+
+use std::error::Error;
+use std::io::Read;
+
+pub use crate::node::HrtfDataset;
+
+/// netCDF external data types
+const NC_BYTE: u32 = 1;
+const NC_CHAR: u32 = 2;
+const NC_SHORT: u32 = 3;
+const NC_INT: u32 = 4;
+const NC_FLOAT: u32 = 5;
+const NC_DOUBLE: u32 = 6;
+
+/// netCDF header list tags
+const NC_DIMENSION: u32 = 0x0A;
+const NC_VARIABLE: u32 = 0x0B;
+const NC_ATTRIBUTE: u32 = 0x0C;
+
+/// Maximum angle (in radians) between two measurement directions that are merged
+const DUPLICATE_DIRECTION_EPSILON: f64 = 1e-4;
+
+/// Head-related impulse responses of a listener, read from a SOFA file
+///
+/// The SOFA file must follow the `SimpleFreeFieldHRIR` convention (two receivers, one impulse
+/// response per source position) and be stored in the netCDF classic format. Files stored in
+/// the HDF5 based netCDF-4 format can be converted with `nccopy -k classic`.
+///
+/// Measurements at the same direction but different distances are merged, the first one is
+/// kept.
+#[derive(Clone)]
+pub struct SofaDatabase {
+    sample_rate: f32,
+    /// unit vectors of the measurement directions, x pointing right, y front and z up
+    directions: Vec<[f32; 3]>,
+    left: Vec<Vec<f32>>,
+    right: Vec<Vec<f32>>,
+}
+
+impl std::fmt::Debug for SofaDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SofaDatabase")
+            .field("sample_rate", &self.sample_rate)
+            .field("len", &self.len())
+            .field("ir_length", &self.ir_length())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SofaDatabase {
+    /// Read a SOFA file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is not a netCDF classic file, or does not contain the
+    /// impulse responses, sample rate and source positions of the `SimpleFreeFieldHRIR`
+    /// convention.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let file = NetCdf::parse(&data)?;
+        Self::from_netcdf(&file)
+    }
+
+    /// The sample rate of the impulse responses
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// The number of measurement directions
+    pub fn len(&self) -> usize {
+        self.directions.len()
+    }
+
+    /// Returns `true` if the database contains no measurements
+    pub fn is_empty(&self) -> bool {
+        self.directions.is_empty()
+    }
+
+    /// The length in samples of the impulse responses
+    pub fn ir_length(&self) -> usize {
+        self.left.first().map_or(0, Vec::len)
+    }
+
+    /// Convert the measurements to an [`HrtfDataset`] for the `HRTF` panning model
+    ///
+    /// The measurement directions are triangulated, so they should cover the whole sphere
+    /// around the listener. Positions outside of the measured area are rendered with the
+    /// impulse responses of the closest measurements.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directions cannot be triangulated, e.g. when all measurements
+    /// lie in the horizontal plane.
+    pub fn into_dataset(self) -> Result<HrtfDataset, Box<dyn Error + Send + Sync>> {
+        let faces = convex_hull(&self.directions)?;
+        HrtfDataset::from_bytes(self.to_hrir_sphere(&faces))
+    }
+
+    fn from_netcdf(file: &NetCdf<'_>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let ir = file.variable("Data.IR")?;
+        let [measurements, receivers, length] = file.shape(ir)?[..] else {
+            return Err("InvalidStateError - Data.IR must have 3 dimensions".into());
+        };
+        if receivers != 2 {
+            return Err(format!(
+                "NotSupportedError - expected 2 receivers in Data.IR, found {receivers}"
+            )
+            .into());
+        }
+        if measurements == 0 || length == 0 {
+            return Err("InvalidStateError - Data.IR is empty".into());
+        }
+        let ir = file.values(ir)?;
+
+        let sample_rate = file.values(file.variable("Data.SamplingRate")?)?;
+        let sample_rate = match sample_rate.first() {
+            Some(&value) if value > 0. => value as f32,
+            _ => return Err("InvalidStateError - invalid Data.SamplingRate".into()),
+        };
+
+        let positions = file.variable("SourcePosition")?;
+        let spherical = match file.text_attribute(positions, "Type") {
+            Some(kind) if kind.eq_ignore_ascii_case("spherical") => true,
+            Some(kind) if kind.eq_ignore_ascii_case("cartesian") => false,
+            kind => {
+                return Err(format!(
+                    "NotSupportedError - unsupported SourcePosition type: {kind:?}"
+                )
+                .into())
+            }
+        };
+        let positions = file.values(positions)?;
+        // a single position is shared by all measurements
+        let position = |m: usize| {
+            let offset = if positions.len() >= 3 * measurements {
+                3 * m
+            } else {
+                0
+            };
+            positions.get(offset..offset + 3)
+        };
+
+        // the broadband delays (in samples) are prepended to the impulse responses
+        let delays = match file.variable("Data.Delay") {
+            Ok(delay) => file.values(delay)?,
+            Err(_) => vec![0.],
+        };
+        let delay = |m: usize, r: usize| {
+            let index = if delays.len() >= 2 * measurements {
+                2 * m + r
+            } else {
+                r.min(delays.len().saturating_sub(1))
+            };
+            delays.get(index).map_or(0, |&d| d.max(0.).round() as usize)
+        };
+        let max_delay = (0..measurements)
+            .flat_map(|m| [delay(m, 0), delay(m, 1)])
+            .max()
+            .unwrap_or_default();
+
+        let mut database = Self {
+            sample_rate,
+            directions: Vec::with_capacity(measurements),
+            left: Vec::with_capacity(measurements),
+            right: Vec::with_capacity(measurements),
+        };
+
+        for m in 0..measurements {
+            let Some(&[a, b, c]) = position(m) else {
+                return Err("InvalidStateError - missing SourcePosition values".into());
+            };
+            let Some(direction) = to_direction(a, b, c, spherical) else {
+                continue;
+            };
+            if database.directions.iter().any(|d| {
+                let dot: f64 = d
+                    .iter()
+                    .zip(direction)
+                    .map(|(&x, y)| f64::from(x) * y)
+                    .sum();
+                dot > DUPLICATE_DIRECTION_EPSILON.cos()
+            }) {
+                continue;
+            }
+
+            let response = |r: usize| {
+                let start = (m * receivers + r) * length;
+                let mut response = vec![0.; delay(m, r)];
+                response.extend(ir[start..start + length].iter().map(|&v| v as f32));
+                response.resize(length + max_delay, 0.);
+                response
+            };
+
+            database.directions.push(direction.map(|v| v as f32));
+            database.left.push(response(0));
+            database.right.push(response(1));
+        }
+
+        Ok(database)
+    }
+
+    /// Serialize to the HRIR sphere format of the `hrtf` crate
+    fn to_hrir_sphere(&self, faces: &[[usize; 3]]) -> Vec<u8> {
+        let len = self.ir_length();
+        let mut out = Vec::with_capacity(20 + faces.len() * 12 + self.len() * (12 + 8 * len));
+
+        out.extend_from_slice(b"HRIR");
+        out.extend_from_slice(&(self.sample_rate.round() as u32).to_le_bytes());
+        out.extend_from_slice(&(len as u32).to_le_bytes());
+        out.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(faces.len() as u32 * 3).to_le_bytes());
+
+        faces
+            .iter()
+            .flatten()
+            .for_each(|&i| out.extend_from_slice(&(i as u32).to_le_bytes()));
+
+        for ((direction, left), right) in self.directions.iter().zip(&self.left).zip(&self.right) {
+            direction
+                .iter()
+                .chain(left)
+                .chain(right)
+                .for_each(|v| out.extend_from_slice(&v.to_le_bytes()));
+        }
+
+        out
+    }
+}
+
+/// Convert a SOFA source position to a unit vector with x pointing right, y front and z up
+///
+/// SOFA coordinates have x pointing front, y left and z up, spherical coordinates are given
+/// in degrees with the azimuth counter-clockwise from the front.
+fn to_direction(a: f64, b: f64, c: f64, spherical: bool) -> Option<[f64; 3]> {
+    let (front, left, up) = if spherical {
+        let (azimuth, elevation) = (a.to_radians(), b.to_radians());
+        (
+            azimuth.cos() * elevation.cos(),
+            azimuth.sin() * elevation.cos(),
+            elevation.sin(),
+        )
+    } else {
+        (a, b, c)
+    };
+
+    let norm = (front * front + left * left + up * up).sqrt();
+    if !norm.is_finite() || norm == 0. {
+        return None;
+    }
+
+    Some([-left / norm, front / norm, up / norm])
+}
+
+/// Triangulate the given directions by computing their convex hull
+///
+/// The faces are oriented counter-clockwise when seen from outside.
+fn convex_hull(directions: &[[f32; 3]]) -> Result<Vec<[usize; 3]>, Box<dyn Error + Send + Sync>> {
+    // Measurement grids have many points on the same circle, which makes the hull degenerate.
+    // Slightly perturb the points (deterministically) to get a proper triangulation.
+    let mut rng = crate::SeededRng::new(0);
+    let points: Vec<[f64; 3]> = directions
+        .iter()
+        .map(|d| d.map(|v| f64::from(v) + 1e-7 * rng.next_f64()))
+        .collect();
+
+    let sub = |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    let dot = |a: [f64; 3], b: [f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let cross = |a: [f64; 3], b: [f64; 3]| {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+    let normal =
+        |[a, b, c]: [usize; 3]| cross(sub(points[b], points[a]), sub(points[c], points[a]));
+    // signed distance of point p above the plane of the face
+    let height = |face: [usize; 3], p: usize| {
+        let n = normal(face);
+        dot(n, sub(points[p], points[face[0]])) / dot(n, n).sqrt()
+    };
+    let eps = 1e-9;
+
+    // initial tetrahedron, its points must be well apart for the hull to cover the sphere
+    let farthest = |key: &dyn Fn(usize) -> f64| {
+        (0..points.len())
+            .max_by(|&i, &j| key(i).total_cmp(&key(j)))
+            .filter(|&i| key(i) > 1e-3)
+            .ok_or("InvalidStateError - the measurement directions do not span a sphere")
+    };
+    let p0 = 0;
+    let p1 = farthest(&|i| dot(sub(points[i], points[p0]), sub(points[i], points[p0])))?;
+    let p2 = farthest(&|i| {
+        let n = cross(sub(points[p1], points[p0]), sub(points[i], points[p0]));
+        dot(n, n)
+    })?;
+    let p3 = farthest(&|i| height([p0, p1, p2], i).abs())?;
+
+    let mut faces = if height([p0, p1, p2], p3) > 0. {
+        vec![[p0, p2, p1], [p0, p1, p3], [p1, p2, p3], [p2, p0, p3]]
+    } else {
+        vec![[p0, p1, p2], [p0, p3, p1], [p1, p3, p2], [p2, p3, p0]]
+    };
+
+    let mut horizon = Vec::new();
+    for p in 0..points.len() {
+        if [p0, p1, p2, p3].contains(&p) {
+            continue;
+        }
+
+        let (visible, hidden): (Vec<_>, Vec<_>) = std::mem::take(&mut faces)
+            .into_iter()
+            .partition(|&face| height(face, p) > eps);
+        if visible.is_empty() {
+            faces = hidden;
+            continue;
+        }
+
+        // edges of the visible region that are not shared by two visible faces
+        horizon.clear();
+        for &[a, b, c] in &visible {
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                let shared = visible
+                    .iter()
+                    .any(|face| (0..3).any(|i| face[i] == to && face[(i + 1) % 3] == from));
+                if !shared {
+                    horizon.push((from, to));
+                }
+            }
+        }
+
+        faces = hidden;
+        faces.extend(horizon.iter().map(|&(from, to)| [from, to, p]));
+    }
+
+    Ok(faces)
+}
+
+/// Header and contents of a netCDF classic (CDF-1 and CDF-2) file
+///
+/// See <https://docs.unidata.ucar.edu/netcdf-c/current/file_format_specifications.html>
+struct NetCdf<'a> {
+    data: &'a [u8],
+    dimensions: Vec<usize>,
+    variables: Vec<Variable>,
+}
+
+struct Variable {
+    name: String,
+    dimensions: Vec<usize>,
+    attributes: Vec<(String, u32, Vec<u8>)>,
+    nc_type: u32,
+    begin: usize,
+}
+
+/// Big-endian reader over the header of a netCDF file
+struct Cursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error + Send + Sync>> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or("InvalidStateError - unexpected end of the netCDF header")?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Read values of the given size, padded to a multiple of 4 bytes
+    fn padded(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error + Send + Sync>> {
+        let bytes = self.bytes(len)?;
+        self.bytes((4 - len % 4) % 4)?;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Box<dyn Error + Send + Sync>> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        Ok(u64::from_be_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn len(&mut self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn name(&mut self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let len = self.len()?;
+        Ok(String::from_utf8_lossy(self.padded(len)?).into_owned())
+    }
+
+    /// Read the tag and number of elements of a list, an absent list has zero elements
+    fn list(&mut self, tag: u32) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        match (self.u32()?, self.len()?) {
+            (0, 0) => Ok(0),
+            (t, len) if t == tag => Ok(len),
+            _ => Err("InvalidStateError - malformed netCDF header".into()),
+        }
+    }
+
+    fn attributes(&mut self) -> Result<Vec<(String, u32, Vec<u8>)>, Box<dyn Error + Send + Sync>> {
+        (0..self.list(NC_ATTRIBUTE)?)
+            .map(|_| {
+                let name = self.name()?;
+                let nc_type = self.u32()?;
+                let len = self.len()? * type_size(nc_type)?;
+                Ok((name, nc_type, self.padded(len)?.to_vec()))
+            })
+            .collect()
+    }
+}
+
+fn type_size(nc_type: u32) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    match nc_type {
+        NC_BYTE | NC_CHAR => Ok(1),
+        NC_SHORT => Ok(2),
+        NC_INT | NC_FLOAT => Ok(4),
+        NC_DOUBLE => Ok(8),
+        _ => Err(format!("InvalidStateError - invalid netCDF type {nc_type}").into()),
+    }
+}
+
+impl<'a> NetCdf<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let offset_size = match data.get(..4) {
+            Some(b"CDF\x01") => 4,
+            Some(b"CDF\x02") => 8,
+            Some(b"\x89HDF") => {
+                return Err(
+                    "NotSupportedError - SOFA files in the netCDF-4 (HDF5) format are \
+                    not supported, convert them to the netCDF classic format"
+                        .into(),
+                )
+            }
+            _ => return Err("InvalidStateError - not a netCDF file".into()),
+        };
+
+        let mut cursor = Cursor { data, position: 4 };
+        let _num_records = cursor.u32()?;
+
+        let dimensions = (0..cursor.list(NC_DIMENSION)?)
+            .map(|_| {
+                cursor.name()?;
+                cursor.len()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // global attributes
+        cursor.attributes()?;
+
+        let variables = (0..cursor.list(NC_VARIABLE)?)
+            .map(|_| {
+                let name = cursor.name()?;
+                let dimensions = (0..cursor.len()?)
+                    .map(|_| cursor.len())
+                    .collect::<Result<Vec<_>, _>>()?;
+                let attributes = cursor.attributes()?;
+                let nc_type = cursor.u32()?;
+                let _size = cursor.u32()?;
+                let begin = if offset_size == 4 {
+                    u64::from(cursor.u32()?)
+                } else {
+                    cursor.u64()?
+                };
+
+                Ok(Variable {
+                    name,
+                    dimensions,
+                    attributes,
+                    nc_type,
+                    begin: usize::try_from(begin)?,
+                })
+            })
+            .collect::<Result<Vec<_>, Box<dyn Error + Send + Sync>>>()?;
+
+        Ok(Self {
+            data,
+            dimensions,
+            variables,
+        })
+    }
+
+    fn variable(&self, name: &str) -> Result<&Variable, Box<dyn Error + Send + Sync>> {
+        self.variables
+            .iter()
+            .find(|v| v.name == name)
+            .ok_or_else(|| format!("InvalidStateError - missing SOFA variable {name}").into())
+    }
+
+    fn shape(&self, variable: &Variable) -> Result<Vec<usize>, Box<dyn Error + Send + Sync>> {
+        variable
+            .dimensions
+            .iter()
+            .map(|&id| match self.dimensions.get(id) {
+                // the unlimited dimension has length zero in the header
+                Some(0) => Err(format!(
+                    "NotSupportedError - record variable {} is not supported",
+                    variable.name
+                )
+                .into()),
+                Some(&len) => Ok(len),
+                None => Err("InvalidStateError - invalid netCDF dimension".into()),
+            })
+            .collect()
+    }
+
+    fn text_attribute(&self, variable: &Variable, name: &str) -> Option<String> {
+        variable
+            .attributes
+            .iter()
+            .find(|(n, nc_type, _)| n == name && *nc_type == NC_CHAR)
+            .map(|(_, _, value)| {
+                String::from_utf8_lossy(value)
+                    .trim_end_matches('\0')
+                    .trim()
+                    .to_string()
+            })
+    }
+
+    /// Read all values of a numeric variable
+    fn values(&self, variable: &Variable) -> Result<Vec<f64>, Box<dyn Error + Send + Sync>> {
+        let len: usize = self.shape(variable)?.iter().product();
+        let size = type_size(variable.nc_type)?;
+        let bytes = len
+            .checked_mul(size)
+            .and_then(|n| n.checked_add(variable.begin))
+            .and_then(|end| self.data.get(variable.begin..end))
+            .ok_or_else(|| {
+                format!(
+                    "InvalidStateError - unexpected end of the data of {}",
+                    variable.name
+                )
+            })?;
+
+        if variable.nc_type == NC_CHAR {
+            return Err(format!("InvalidStateError - {} is not numeric", variable.name).into());
+        }
+
+        let values = bytes.chunks_exact(size).map(|b| match variable.nc_type {
+            NC_BYTE => f64::from(b[0] as i8),
+            NC_SHORT => f64::from(i16::from_be_bytes([b[0], b[1]])),
+            NC_INT => f64::from(i32::from_be_bytes(b.try_into().unwrap())),
+            NC_FLOAT => f64::from(f32::from_be_bytes(b.try_into().unwrap())),
+            NC_DOUBLE => f64::from_be_bytes(b.try_into().unwrap()),
+            _ => f64::NAN,
+        });
+
+        Ok(values.collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal CDF-1 writer for SimpleFreeFieldHRIR files with double precision variables
+    fn sofa_file(positions: &[[f64; 3]], length: usize) -> Vec<u8> {
+        fn name(out: &mut Vec<u8>, name: &str) {
+            out.extend_from_slice(&(name.len() as u32).to_be_bytes());
+            out.extend_from_slice(name.as_bytes());
+            out.resize(out.len().next_multiple_of(4), 0);
+        }
+
+        let m = positions.len();
+        let ir: Vec<f64> = (0..m)
+            .flat_map(|i| (0..2 * length).map(move |n| (i * 100 + n) as f64))
+            .collect();
+        let positions: Vec<f64> = positions.iter().flatten().copied().collect();
+
+        // name, dimension ids, type attribute, values
+        let variables: [(&str, Vec<u32>, Option<&str>, Vec<f64>); 3] = [
+            ("Data.IR", vec![0, 1, 2], None, ir),
+            ("Data.SamplingRate", vec![3], None, vec![44_100.]),
+            ("SourcePosition", vec![0, 4], Some("spherical"), positions),
+        ];
+
+        let mut header = b"CDF\x01".to_vec();
+        header.extend_from_slice(&0_u32.to_be_bytes());
+        header.extend_from_slice(&NC_DIMENSION.to_be_bytes());
+        header.extend_from_slice(&5_u32.to_be_bytes());
+        for (dim, len) in [("M", m), ("R", 2), ("N", length), ("I", 1), ("C", 3)] {
+            name(&mut header, dim);
+            header.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+        header.extend_from_slice(&[0; 8]);
+        header.extend_from_slice(&NC_VARIABLE.to_be_bytes());
+        header.extend_from_slice(&(variables.len() as u32).to_be_bytes());
+
+        // the begin offsets are patched once the header length is known
+        let mut begin_offsets = vec![];
+        for (var, dims, kind, values) in &variables {
+            name(&mut header, var);
+            header.extend_from_slice(&(dims.len() as u32).to_be_bytes());
+            dims.iter()
+                .for_each(|d| header.extend_from_slice(&d.to_be_bytes()));
+            match kind {
+                Some(kind) => {
+                    header.extend_from_slice(&NC_ATTRIBUTE.to_be_bytes());
+                    header.extend_from_slice(&1_u32.to_be_bytes());
+                    name(&mut header, "Type");
+                    header.extend_from_slice(&NC_CHAR.to_be_bytes());
+                    name(&mut header, kind);
+                }
+                None => header.extend_from_slice(&[0; 8]),
+            }
+            header.extend_from_slice(&NC_DOUBLE.to_be_bytes());
+            header.extend_from_slice(&(values.len() as u32 * 8).to_be_bytes());
+            begin_offsets.push(header.len());
+            header.extend_from_slice(&[0; 4]);
+        }
+
+        let mut out = header;
+        for ((_, _, _, values), offset) in variables.iter().zip(begin_offsets) {
+            let begin = (out.len() as u32).to_be_bytes();
+            out[offset..offset + 4].copy_from_slice(&begin);
+            values
+                .iter()
+                .for_each(|v| out.extend_from_slice(&v.to_be_bytes()));
+        }
+
+        out
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        assert!(SofaDatabase::from_reader(&b"not a sofa file"[..]).is_err());
+        let error = SofaDatabase::from_reader(&b"\x89HDF\r\n\x1a\n"[..]).unwrap_err();
+        assert!(error.to_string().starts_with("NotSupportedError"));
+    }
+
+    #[test]
+    fn test_from_reader() {
+        // octahedron, with a duplicate direction at another distance
+        let positions = [
+            [0., 0., 1.],
+            [90., 0., 1.],
+            [180., 0., 1.],
+            [270., 0., 1.],
+            [0., 90., 1.],
+            [0., -90., 1.],
+            [90., 0., 2.],
+        ];
+        let file = sofa_file(&positions, 4);
+        let database = SofaDatabase::from_reader(&file[..]).unwrap();
+
+        assert_eq!(database.sample_rate(), 44_100.);
+        assert_eq!(database.len(), 6);
+        assert_eq!(database.ir_length(), 4);
+        assert_eq!(database.left[1], [100., 101., 102., 103.]);
+        assert_eq!(database.right[1], [104., 105., 106., 107.]);
+
+        // the source at 90 degrees azimuth is on the left
+        let [x, y, z] = database.directions[1];
+        assert!((x + 1.).abs() < 1e-6 && y.abs() < 1e-6 && z.abs() < 1e-6);
+
+        // closed triangulation, oriented outward
+        let faces = convex_hull(&database.directions).unwrap();
+        assert_eq!(faces.len(), 2 * database.len() - 4);
+        for [a, b, c] in faces {
+            let [a, b, c] = [a, b, c].map(|i| database.directions[i]);
+            let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let n = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            let centroid: Vec<f32> = (0..3).map(|i| a[i] + b[i] + c[i]).collect();
+            assert!(n.iter().zip(centroid).map(|(n, c)| n * c).sum::<f32>() > 0.);
+        }
+
+        let dataset = database.into_dataset().unwrap();
+        assert_ne!(dataset, HrtfDataset::default());
+    }
+
+    #[test]
+    fn test_degenerate_directions() {
+        // horizontal plane only
+        let positions = [[0., 0., 1.], [90., 0., 1.], [180., 0., 1.], [270., 0., 1.]];
+        let file = sofa_file(&positions, 4);
+        let database = SofaDatabase::from_reader(&file[..]).unwrap();
+        assert!(database.into_dataset().is_err());
+    }
+}
@@ -0,0 +1,332 @@
+//! Remote inspection of a running context over WebSocket
+//!
+//! This module is only available with the `inspect` feature.
+//!
+//! An [`InspectServer`] lets an external tool, e.g. a GUI on another machine, inspect and tweak
+//! a headless context. Every text message is a JSON request with an `id`, a `method` and its
+//! `params`, and is answered with the same `id` and either a `result` or an `error`:
+//!
+//! - `graph`: the [`GraphDescription`](crate::context::GraphDescription) of the context
+//! - `profile`: the [`NodeProfile`]s of the nodes, with the times in seconds
+//! - `set_profiling` with the `enabled` boolean: enable or disable the profiling
+//! - `state`: the state, current time and sample rate of the context, and the fill state of its
+//!   control queue
+//! - `set_param` with the `id` and `value` of a param: set the value of the param
+//!
+//! The param ids are the ids of the [`ParamDescription`](crate::context::ParamDescription)s of
+//! the graph.
+//!
+//! ```text
+//! > {"id": 1, "method": "set_param", "params": {"id": 12, "value": 0.5}}
+//! < {"id": 1, "result": null}
+//! ```
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use web_audio_api::context::AudioContext;
+//! use web_audio_api::inspect::InspectServer;
+//!
+//! let context = AudioContext::default();
+//! let server = InspectServer::bind(&context, "0.0.0.0:9001").unwrap();
+//! println!("inspect at ws://{}", server.local_addr());
+//! ```
+//!
+//! The server has no authentication: only bind it to a trusted network.
+
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tungstenite::{Message, WebSocket};
+
+use crate::context::{AudioContextState, AudioNodeId, BaseAudioContext, ConcreteBaseAudioContext};
+use crate::NodeProfile;
+
+/// Interval at which the connections check whether the server is dropped
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// WebSocket server to inspect and tweak a running context, see the [module docs](self)
+///
+/// Every connection is handled on its own thread. Dropping the server closes the connections.
+pub struct InspectServer {
+    addr: SocketAddr,
+    closed: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for InspectServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InspectServer")
+            .field("addr", &self.addr)
+            .finish_non_exhaustive()
+    }
+}
+
+impl InspectServer {
+    /// Listen for connections at the given address
+    ///
+    /// Use port 0 to let the system pick a free port, see [`local_addr`](Self::local_addr).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be bound.
+    pub fn bind<C: BaseAudioContext, A: ToSocketAddrs>(context: &C, addr: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let closed = Arc::new(AtomicBool::new(false));
+
+        let context = context.base().clone();
+        let closed_clone = Arc::clone(&closed);
+        let thread = std::thread::Builder::new()
+            .name("inspect server".into())
+            .spawn(move || accept(&listener, &context, &closed_clone))?;
+
+        Ok(Self {
+            addr,
+            closed,
+            thread: Some(thread),
+        })
+    }
+
+    /// The address the server listens at
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for InspectServer {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Release);
+
+        // wake the accepting thread with a connection of our own
+        let mut addr = self.addr;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr.ip() {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            });
+        }
+        if TcpStream::connect(addr).is_ok() {
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+fn accept(listener: &TcpListener, context: &ConcreteBaseAudioContext, closed: &Arc<AtomicBool>) {
+    for stream in listener.incoming() {
+        if closed.load(Ordering::Acquire) {
+            break;
+        }
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn!("inspect server: cannot accept connection: {e}");
+                continue;
+            }
+        };
+
+        let context = context.clone();
+        let closed = Arc::clone(closed);
+        let spawned = std::thread::Builder::new()
+            .name("inspect connection".into())
+            .spawn(move || {
+                if let Err(e) = serve(stream, &context, &closed) {
+                    log::debug!("inspect server: connection closed: {e}");
+                }
+            });
+        if let Err(e) = spawned {
+            log::warn!("inspect server: cannot spawn connection thread: {e}");
+        }
+    }
+}
+
+fn serve(
+    stream: TcpStream,
+    context: &ConcreteBaseAudioContext,
+    closed: &AtomicBool,
+) -> Result<(), tungstenite::Error> {
+    let mut socket: WebSocket<TcpStream> = tungstenite::accept(stream).map_err(|e| match e {
+        tungstenite::HandshakeError::Failure(e) => e,
+        tungstenite::HandshakeError::Interrupted(_) => {
+            tungstenite::Error::Io(ErrorKind::WouldBlock.into())
+        }
+    })?;
+    socket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+
+    loop {
+        if closed.load(Ordering::Acquire) {
+            return socket.close(None);
+        }
+
+        let message = match socket.read() {
+            Ok(message) => message,
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                continue
+            }
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        if let Message::Text(text) = message {
+            let response = respond(context, &text);
+            socket.send(Message::Text(response.to_string()))?;
+        }
+    }
+}
+
+/// Handle the JSON request and build the response
+fn respond(context: &ConcreteBaseAudioContext, text: &str) -> Value {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => return json!({ "id": null, "error": format!("SyntaxError - {e}") }),
+    };
+
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    match handle(context, &request) {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(error) => json!({ "id": id, "error": error }),
+    }
+}
+
+fn handle(context: &ConcreteBaseAudioContext, request: &Value) -> Result<Value, String> {
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .ok_or("TypeError - Missing method")?;
+    let params = request.get("params").unwrap_or(&Value::Null);
+
+    match method {
+        "graph" => serde_json::to_value(context.export_graph()).map_err(|e| e.to_string()),
+        "profile" => Ok(context.profile().iter().map(profile).collect()),
+        "set_profiling" => {
+            let enabled = params
+                .get("enabled")
+                .and_then(Value::as_bool)
+                .ok_or("TypeError - Missing boolean enabled")?;
+            context.set_profiling(enabled);
+            Ok(Value::Null)
+        }
+        "state" => {
+            let queue = context.control_queue_stats();
+            Ok(json!({
+                "state": state(context.state()),
+                "current_time": context.current_time(),
+                "sample_rate": context.sample_rate(),
+                "control_queue": {
+                    "pending": queue.pending,
+                    "capacity": queue.capacity,
+                    "blocked": queue.blocked,
+                },
+            }))
+        }
+        "set_param" => {
+            let id = params
+                .get("id")
+                .and_then(Value::as_u64)
+                .ok_or("TypeError - Missing param id")?;
+            let value = params
+                .get("value")
+                .and_then(Value::as_f64)
+                .map(|v| v as f32)
+                .filter(|v| v.is_finite())
+                .ok_or("TypeError - Missing finite value")?;
+            let param = context
+                .audio_param(AudioNodeId(id))
+                .ok_or_else(|| format!("NotFoundError - No AudioParam with id {id}"))?;
+            param.set_value(value);
+            Ok(Value::Null)
+        }
+        _ => Err(format!("NotSupportedError - Unknown method {method}")),
+    }
+}
+
+fn profile(profile: &NodeProfile) -> Value {
+    json!({
+        "label": profile.label,
+        "processor": profile.processor,
+        "calls": profile.calls,
+        "total": profile.total.as_secs_f64(),
+        "average": profile.average.as_secs_f64(),
+        "max": profile.max.as_secs_f64(),
+    })
+}
+
+fn state(state: AudioContextState) -> &'static str {
+    match state {
+        AudioContextState::Suspended => "suspended",
+        AudioContextState::Running => "running",
+        AudioContextState::Closed => "closed",
+        AudioContextState::Interrupted => "interrupted",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tungstenite::stream::MaybeTlsStream;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioNode;
+
+    use super::*;
+
+    fn request(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>, request: Value) -> Value {
+        socket.send(Message::Text(request.to_string())).unwrap();
+        loop {
+            if let Message::Text(text) = socket.read().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_inspect_server() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let gain = context.create_gain();
+        gain.connect(&context.destination());
+
+        let server = InspectServer::bind(&context, "127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", server.local_addr());
+        let (mut socket, _) = tungstenite::connect(url).unwrap();
+
+        let response = request(&mut socket, json!({ "id": 1, "method": "graph" }));
+        assert_eq!(response["id"], 1);
+        let nodes = response["result"]["nodes"].as_array().unwrap();
+        assert_eq!(nodes[1]["node_type"], "GainNode");
+        let param_id = nodes[1]["params"][0]["id"].as_u64().unwrap();
+
+        let response = request(
+            &mut socket,
+            json!({ "id": 2, "method": "set_param", "params": { "id": param_id, "value": 0.5 } }),
+        );
+        assert_eq!(response, json!({ "id": 2, "result": null }));
+        assert_eq!(gain.gain().value(), 0.5);
+
+        let response = request(&mut socket, json!({ "id": 3, "method": "state" }));
+        assert_eq!(response["result"]["state"], "suspended");
+        assert_eq!(response["result"]["sample_rate"], 48_000.);
+
+        let response = request(
+            &mut socket,
+            json!({ "id": 4, "method": "set_param", "params": { "id": 1234, "value": 0.5 } }),
+        );
+        let error = response["error"].as_str().unwrap();
+        assert!(error.starts_with("NotFoundError"));
+
+        let response = request(&mut socket, json!({ "id": 5, "method": "foo" }));
+        let error = response["error"].as_str().unwrap();
+        assert!(error.starts_with("NotSupportedError"));
+
+        // the connection is closed with the server
+        drop(server);
+        while socket.read().is_ok() {}
+    }
+}
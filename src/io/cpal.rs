@@ -1,4 +1,5 @@
 //! Audio IO management API
+use std::error::Error;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -6,7 +7,7 @@ use std::sync::Mutex;
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
     BuildStreamError, Device, OutputCallbackInfo, SampleFormat, Stream, StreamConfig,
-    SupportedBufferSize,
+    SupportedBufferSize, SupportedStreamConfig,
 };
 use crossbeam_channel::Receiver;
 
@@ -111,6 +112,7 @@ pub(crate) struct CpalBackend {
     stream: ThreadSafeClosableStream,
     output_latency: Arc<AtomicF64>,
     sample_rate: f32,
+    device_sample_rate: f32,
     number_of_channels: usize,
     sink_id: String,
 }
@@ -130,6 +132,8 @@ impl AudioBackendManager for CpalBackend {
             ctrl_msg_recv,
             load_value_send,
             event_send,
+            graph_send,
+            graph_recv: _,
         } = render_thread_init;
 
         let device = if options.sink_id.is_empty() {
@@ -195,6 +199,7 @@ impl AudioBackendManager for CpalBackend {
         // sample rate is not supported by the hardware, it will fallback to the
         // default device sample rate
         let mut sample_rate = preferred_config.sample_rate.0 as f32;
+        let mut device_sample_rate = sample_rate;
 
         // shared atomic to report output latency to the control thread
         let output_latency = Arc::new(AtomicF64::new(0.));
@@ -208,6 +213,8 @@ impl AudioBackendManager for CpalBackend {
             event_send.clone(),
         );
         renderer.set_load_value_sender(load_value_send.clone());
+        renderer.set_interruption_policy(options.interruption_policy);
+        renderer.set_graph_recycler(graph_send.clone());
         renderer.spawn_garbage_collector_thread();
 
         log::debug!(
@@ -234,8 +241,10 @@ impl AudioBackendManager for CpalBackend {
                 let mut supported_config: StreamConfig = default_device_config.clone().into();
                 // make sure number of channels is clamped to MAX_CHANNELS
                 supported_config.channels = number_of_channels as u16;
-                // fallback to device default sample rate
-                sample_rate = supported_config.sample_rate.0 as f32;
+                // fallback to device default sample rate, a requested sample rate is kept for
+                // the graph and its output is resampled to the rate of the device
+                device_sample_rate = supported_config.sample_rate.0 as f32;
+                sample_rate = options.sample_rate.unwrap_or(device_sample_rate);
 
                 log::debug!(
                     "Attempt output stream with fallback config: {:?}",
@@ -251,6 +260,9 @@ impl AudioBackendManager for CpalBackend {
                     event_send,
                 );
                 renderer.set_load_value_sender(load_value_send);
+                renderer.set_interruption_policy(options.interruption_policy);
+                renderer.set_graph_recycler(graph_send);
+                renderer.set_output_sample_rate(device_sample_rate);
                 renderer.spawn_garbage_collector_thread();
 
                 let spawned = spawn_output_stream(
@@ -275,6 +287,7 @@ impl AudioBackendManager for CpalBackend {
             stream: ThreadSafeClosableStream::new(stream),
             output_latency,
             sample_rate,
+            device_sample_rate,
             number_of_channels,
             sink_id: options.sink_id,
         }
@@ -311,6 +324,101 @@ impl AudioBackendManager for CpalBackend {
             .default_input_config()
             .expect("InvalidStateError - error while querying device input config");
 
+        Self::open_input(&device, supported, options, number_of_channels)
+    }
+
+    fn resume(&self) -> bool {
+        self.stream.resume()
+    }
+
+    fn suspend(&self) -> bool {
+        self.stream.suspend()
+    }
+
+    fn close(&self) {
+        self.stream.close()
+    }
+
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    fn device_sample_rate(&self) -> f32 {
+        self.device_sample_rate
+    }
+
+    fn number_of_channels(&self) -> usize {
+        self.number_of_channels
+    }
+
+    fn output_latency(&self) -> f64 {
+        self.output_latency.load(Ordering::Relaxed)
+    }
+
+    fn sink_id(&self) -> &str {
+        self.sink_id.as_str()
+    }
+
+    fn enumerate_devices_sync() -> Vec<MediaDeviceInfo>
+    where
+        Self: Sized,
+    {
+        let host = get_host();
+
+        let input_devices = host.input_devices().unwrap().map(|d| {
+            let num_channels = d.default_input_config().unwrap().channels();
+            (d, MediaDeviceInfoKind::AudioInput, num_channels)
+        });
+
+        let output_devices = host.output_devices().unwrap().map(|d| {
+            let num_channels = d.default_output_config().unwrap().channels();
+            (d, MediaDeviceInfoKind::AudioOutput, num_channels)
+        });
+
+        // cf. https://github.com/orottier/web-audio-api-rs/issues/356
+        let mut list = Vec::<MediaDeviceInfo>::new();
+
+        for (device, kind, num_channels) in input_devices.chain(output_devices) {
+            let mut index = 0;
+
+            loop {
+                let device_id = crate::media_devices::DeviceId::as_string(
+                    kind,
+                    "cpal".to_string(),
+                    device.name().unwrap(),
+                    num_channels,
+                    index,
+                );
+
+                if !list.iter().any(|d| d.device_id() == device_id) {
+                    let device = MediaDeviceInfo::new(
+                        device_id,
+                        None,
+                        kind,
+                        device.name().unwrap(),
+                        Box::new(device),
+                    );
+
+                    list.push(device);
+                    break;
+                } else {
+                    index += 1;
+                }
+            }
+        }
+
+        list
+    }
+}
+
+impl CpalBackend {
+    /// Set up an input stream on the given device and configuration
+    fn open_input(
+        device: &Device,
+        supported: SupportedStreamConfig,
+        options: AudioContextOptions,
+        number_of_channels: Option<u32>,
+    ) -> (Self, Receiver<AudioBuffer>) {
         // clone the config, we may need to fall back on it later
         let mut preferred: StreamConfig = supported.clone().into();
 
@@ -396,6 +504,7 @@ impl AudioBackendManager for CpalBackend {
             stream: ThreadSafeClosableStream::new(stream),
             output_latency: Arc::new(AtomicF64::new(0.)),
             sample_rate,
+            device_sample_rate: sample_rate,
             number_of_channels,
             sink_id: options.sink_id,
         };
@@ -403,83 +512,59 @@ impl AudioBackendManager for CpalBackend {
         (backend, receiver)
     }
 
-    fn resume(&self) -> bool {
-        self.stream.resume()
-    }
-
-    fn suspend(&self) -> bool {
-        self.stream.suspend()
-    }
-
-    fn close(&self) {
-        self.stream.close()
-    }
-
-    fn sample_rate(&self) -> f32 {
-        self.sample_rate
-    }
-
-    fn number_of_channels(&self) -> usize {
-        self.number_of_channels
-    }
-
-    fn output_latency(&self) -> f64 {
-        self.output_latency.load(Ordering::Relaxed)
-    }
-
-    fn sink_id(&self) -> &str {
-        self.sink_id.as_str()
-    }
-
-    fn enumerate_devices_sync() -> Vec<MediaDeviceInfo>
-    where
-        Self: Sized,
-    {
+    /// Set up a stream capturing the audio played on the given output device, or the default
+    /// output device if `sink_id` is empty
+    ///
+    /// WASAPI captures the output device itself. Other hosts expose the played audio as an input
+    /// device, e.g. the monitor sources of PulseAudio and PipeWire, which is selected instead.
+    pub(crate) fn build_loopback(
+        sink_id: &str,
+    ) -> Result<(Self, Receiver<AudioBuffer>), Box<dyn Error + Send + Sync>> {
         let host = get_host();
+        log::info!("Audio Loopback Host: cpal {:?}", host.id());
 
-        let input_devices = host.input_devices().unwrap().map(|d| {
-            let num_channels = d.default_input_config().unwrap().channels();
-            (d, MediaDeviceInfoKind::AudioInput, num_channels)
-        });
-
-        let output_devices = host.output_devices().unwrap().map(|d| {
-            let num_channels = d.default_output_config().unwrap().channels();
-            (d, MediaDeviceInfoKind::AudioOutput, num_channels)
-        });
-
-        // cf. https://github.com/orottier/web-audio-api-rs/issues/356
-        let mut list = Vec::<MediaDeviceInfo>::new();
-
-        for (device, kind, num_channels) in input_devices.chain(output_devices) {
-            let mut index = 0;
-
-            loop {
-                let device_id = crate::media_devices::DeviceId::as_string(
-                    kind,
-                    "cpal".to_string(),
-                    device.name().unwrap(),
-                    num_channels,
-                    index,
-                );
+        let output = if sink_id.is_empty() {
+            host.default_output_device()
+        } else {
+            Self::enumerate_devices_sync()
+                .into_iter()
+                .find(|e| e.kind() == MediaDeviceInfoKind::AudioOutput && e.device_id() == sink_id)
+                .map(|e| *e.device().downcast::<cpal::Device>().unwrap())
+        }
+        .ok_or("NotFoundError - no output device available")?;
 
-                if !list.iter().any(|d| d.device_id() == device_id) {
-                    let device = MediaDeviceInfo::new(
-                        device_id,
-                        None,
-                        kind,
-                        device.name().unwrap(),
-                        Box::new(device),
-                    );
+        let options = AudioContextOptions {
+            sink_id: sink_id.to_string(),
+            ..AudioContextOptions::default()
+        };
 
-                    list.push(device);
-                    break;
-                } else {
-                    index += 1;
-                }
-            }
+        #[cfg(target_os = "windows")]
+        if host.id() == cpal::HostId::Wasapi {
+            // WASAPI records the output device with an input stream in loopback mode
+            let supported = output.default_output_config()?;
+            log::info!("Loopback device: {:?}", output.name());
+            return Ok(Self::open_input(&output, supported, options, None));
         }
 
-        list
+        // look for the monitor of the output device, or any monitor
+        let output_name = output.name()?;
+        let monitors: Vec<_> = host
+            .input_devices()?
+            .filter(|d| {
+                d.name()
+                    .is_ok_and(|name| name.to_lowercase().contains("monitor"))
+            })
+            .collect();
+        let device = monitors
+            .iter()
+            .find(|d| d.name().is_ok_and(|name| name.contains(&output_name)))
+            .or_else(|| monitors.first())
+            .cloned()
+            .ok_or("NotSupportedError - no loopback or monitor input available for this host")?;
+
+        log::info!("Loopback device: {:?}", device.name());
+        let supported = device.default_input_config()?;
+        Ok(Self::open_input(&device, supported, options, None))
     }
 }
 
@@ -507,7 +592,11 @@ fn spawn_output_stream(
     mut render: RenderThread,
     output_latency: Arc<AtomicF64>,
 ) -> Result<Stream, BuildStreamError> {
-    let err_fn = |err| log::error!("an error occurred on the output audio stream: {}", err);
+    let interruption = render.interruption_handle();
+    let err_fn = move |err| {
+        log::error!("an error occurred on the output audio stream: {}", err);
+        interruption.interrupt();
+    };
 
     match sample_format {
         SampleFormat::F32 => device.build_output_stream(
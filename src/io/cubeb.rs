@@ -106,6 +106,7 @@ fn init_output_backend<const N: usize>(
     mut renderer: RenderThread,
 ) -> ThreadSafeClosableStream {
     let mut builder = cubeb::StreamBuilder::<[f32; N]>::new();
+    let interruption = renderer.interruption_handle();
 
     match device {
         None => builder.default_output(&params),
@@ -128,8 +129,12 @@ fn init_output_backend<const N: usize>(
 
             output.len() as isize
         })
-        .state_callback(|state| {
-            println!("stream state changed: {state:?}");
+        .state_callback(move |state| {
+            log::debug!("stream state changed: {state:?}");
+            if matches!(state, cubeb::State::Error) {
+                log::error!("an error occurred on the output audio stream");
+                interruption.interrupt();
+            }
         });
 
     let stream = builder
@@ -158,6 +163,8 @@ impl AudioBackendManager for CubebBackend {
             ctrl_msg_recv,
             load_value_send,
             event_send,
+            graph_send,
+            graph_recv: _,
         } = render_thread_init;
 
         // Set up cubeb context
@@ -175,8 +182,9 @@ impl AudioBackendManager for CubebBackend {
             .unwrap_or(2);
 
         // clamp the requested stream number of channels to MAX_CHANNELS even if
-        // the soundcard can provide more channels
-        let number_of_channels = number_of_channels.min(MAX_CHANNELS);
+        // the soundcard can provide more channels, the cubeb backend below supports
+        // at most 32 channels regardless of the `max-channels-*` features
+        let number_of_channels = number_of_channels.min(MAX_CHANNELS).min(32);
 
         let layout = match number_of_channels {
             1 => cubeb::ChannelLayout::MONO,
@@ -194,6 +202,8 @@ impl AudioBackendManager for CubebBackend {
             event_send,
         );
         renderer.set_load_value_sender(load_value_send);
+        renderer.set_interruption_policy(options.interruption_policy);
+        renderer.set_graph_recycler(graph_send);
         renderer.spawn_garbage_collector_thread();
 
         let params = cubeb::StreamParamsBuilder::new()
@@ -11,6 +11,8 @@ pub(crate) struct MicrophoneStream {
     number_of_channels: usize,
     sample_rate: f32,
     stream: Box<dyn AudioBackendManager>,
+    /// discard the frames captured before the first poll
+    discard_pending: bool,
 }
 
 impl MicrophoneStream {
@@ -23,8 +25,14 @@ impl MicrophoneStream {
             number_of_channels: backend.number_of_channels(),
             sample_rate: backend.sample_rate(),
             stream: backend,
+            discard_pending: false,
         }
     }
+
+    /// Start the stream at its first poll instead of at the opening of the device
+    pub(crate) fn discard_until_first_poll(&mut self) {
+        self.discard_pending = true;
+    }
 }
 
 impl Drop for MicrophoneStream {
@@ -38,6 +46,11 @@ impl Iterator for MicrophoneStream {
     type Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.discard_pending {
+            self.discard_pending = false;
+            while self.receiver.try_recv().is_ok() {}
+        }
+
         let next = match self.receiver.try_recv() {
             Ok(buffer) => {
                 // new frame was ready
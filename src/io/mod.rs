@@ -1,5 +1,6 @@
 //! Audio input/output interfaces
 
+use std::error::Error;
 use std::sync::atomic::{AtomicU64, AtomicU8};
 use std::sync::Arc;
 
@@ -8,9 +9,10 @@ use crossbeam_channel::{Receiver, Sender};
 use crate::buffer::AudioBuffer;
 use crate::context::{AudioContextLatencyCategory, AudioContextOptions, AudioContextState};
 use crate::events::EventDispatch;
-use crate::media_devices::MediaDeviceInfo;
+use crate::media_devices::{AutoGainControlOptions, MediaDeviceInfo};
 use crate::media_streams::{MediaStream, MediaStreamTrack};
 use crate::message::ControlMessage;
+use crate::render::graph::Graph;
 use crate::{AudioRenderCapacityLoad, RENDER_QUANTUM_SIZE};
 
 mod none;
@@ -25,6 +27,23 @@ mod cubeb;
 #[cfg(any(feature = "cubeb", feature = "cpal"))]
 mod microphone;
 
+#[cfg(feature = "voice-processing")]
+pub(crate) mod voice;
+
+/// Speech processing of a microphone track, requested by its constraints
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct VoiceSettings {
+    pub echo_cancellation: bool,
+    pub noise_suppression: bool,
+    pub auto_gain_control: Option<AutoGainControlOptions>,
+}
+
+impl VoiceSettings {
+    fn is_enabled(&self) -> bool {
+        self.echo_cancellation || self.noise_suppression || self.auto_gain_control.is_some()
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ControlThreadInit {
     pub state: Arc<AtomicU8>,
@@ -42,6 +61,9 @@ pub(crate) struct RenderThreadInit {
     pub ctrl_msg_recv: Receiver<ControlMessage>,
     pub load_value_send: Sender<AudioRenderCapacityLoad>,
     pub event_send: Sender<EventDispatch>,
+    /// The graph of a render thread dropped while its device is interrupted
+    pub graph_send: Sender<Graph>,
+    pub graph_recv: Receiver<Graph>,
 }
 
 pub(crate) fn thread_init() -> (ControlThreadInit, RenderThreadInit) {
@@ -66,6 +88,10 @@ pub(crate) fn thread_init() -> (ControlThreadInit, RenderThreadInit) {
     // will be sent per render quantum. Excess events are dropped when the capacity is reached.
     let (event_send, event_recv) = crossbeam_channel::bounded(256);
 
+    // Communication channel to recover the audio graph of an interrupted audio device, when its
+    // render thread is dropped along with the audio stream.
+    let (graph_send, graph_recv) = crossbeam_channel::bounded(1);
+
     let control_thread_init = ControlThreadInit {
         state: Arc::clone(&state),
         frames_played: Arc::clone(&frames_played),
@@ -81,6 +107,8 @@ pub(crate) fn thread_init() -> (ControlThreadInit, RenderThreadInit) {
         ctrl_msg_recv,
         load_value_send,
         event_send,
+        graph_send,
+        graph_recv,
     };
 
     (control_thread_init, render_thread_init)
@@ -116,7 +144,57 @@ pub(crate) fn build_output(
 pub(crate) fn build_input(
     options: AudioContextOptions,
     number_of_channels: Option<u32>,
+    voice: VoiceSettings,
+) -> MediaStream {
+    let track = build_input_track(options, number_of_channels, voice, false);
+    MediaStream::from_tracks(vec![track])
+}
+
+/// Set up several input streams, one track per device
+///
+/// The tracks discard the frames captured before their first poll, so that tracks polled in the
+/// same render quantum start in sync.
+pub(crate) fn build_inputs(
+    inputs: Vec<(AudioContextOptions, Option<u32>, VoiceSettings)>,
 ) -> MediaStream {
+    let tracks = inputs
+        .into_iter()
+        .map(|(options, number_of_channels, voice)| {
+            build_input_track(options, number_of_channels, voice, true)
+        })
+        .collect();
+    MediaStream::from_tracks(tracks)
+}
+
+/// Set up a stream capturing the audio played on an output device (loopback)
+pub(crate) fn build_loopback(sink_id: &str) -> Result<MediaStream, Box<dyn Error + Send + Sync>> {
+    #[cfg(feature = "cubeb")]
+    {
+        let _ = sink_id;
+        Err("NotSupportedError - loopback capture is not supported by the cubeb backend".into())
+    }
+
+    #[cfg(all(not(feature = "cubeb"), feature = "cpal"))]
+    {
+        let (backend, receiver) = cpal::CpalBackend::build_loopback(sink_id)?;
+        let media_iter = microphone::MicrophoneStream::new(receiver, Box::new(backend));
+        let track = MediaStreamTrack::from_iter(media_iter);
+        Ok(MediaStream::from_tracks(vec![track]))
+    }
+
+    #[cfg(all(not(feature = "cubeb"), not(feature = "cpal")))]
+    {
+        let _ = sink_id;
+        Err("No audio backend available, enable the 'cpal' or 'cubeb' feature".into())
+    }
+}
+
+fn build_input_track(
+    options: AudioContextOptions,
+    number_of_channels: Option<u32>,
+    voice: VoiceSettings,
+    synchronized: bool,
+) -> MediaStreamTrack {
     #[cfg(all(not(feature = "cubeb"), not(feature = "cpal")))]
     {
         panic!("No audio backend available, enable the 'cpal' or 'cubeb' feature")
@@ -136,9 +214,23 @@ pub(crate) fn build_input(
             }
         };
 
-        let media_iter = microphone::MicrophoneStream::new(receiver, Box::new(backend));
-        let track = MediaStreamTrack::from_iter(media_iter);
-        MediaStream::from_tracks(vec![track])
+        let mut media_iter = microphone::MicrophoneStream::new(receiver, Box::new(backend));
+        if synchronized {
+            media_iter.discard_until_first_poll();
+        }
+
+        #[cfg(feature = "voice-processing")]
+        if voice.is_enabled() {
+            return MediaStreamTrack::from_iter(voice::VoiceProcessing::new(media_iter, voice));
+        }
+        #[cfg(not(feature = "voice-processing"))]
+        if voice.is_enabled() {
+            log::warn!(
+                "Echo cancellation, noise suppression and gain control require the voice-processing feature"
+            );
+        }
+
+        MediaStreamTrack::from_iter(media_iter)
     }
 }
 
@@ -174,6 +266,12 @@ pub(crate) trait AudioBackendManager: Send + Sync + 'static {
     /// Sample rate of the stream
     fn sample_rate(&self) -> f32;
 
+    /// Sample rate of the audio device, differs from the sample rate of the stream when its
+    /// output is resampled
+    fn device_sample_rate(&self) -> f32 {
+        self.sample_rate()
+    }
+
     /// Number of channels of the stream
     fn number_of_channels(&self) -> usize;
 
@@ -86,6 +86,8 @@ impl AudioBackendManager for NoneBackend {
             ctrl_msg_recv,
             load_value_send,
             event_send,
+            graph_send,
+            graph_recv: _,
         } = render_thread_init;
 
         let mut render_thread = RenderThread::new(
@@ -97,6 +99,8 @@ impl AudioBackendManager for NoneBackend {
             event_send,
         );
         render_thread.set_load_value_sender(load_value_send);
+        render_thread.set_interruption_policy(options.interruption_policy);
+        render_thread.set_graph_recycler(graph_send);
         render_thread.spawn_garbage_collector_thread();
 
         // Use a bounded channel for real-time safety. A maximum of 32 control messages (resume,
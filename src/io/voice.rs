@@ -0,0 +1,666 @@
+//! Speech processing of the microphone input: echo cancellation, noise suppression and automatic
+//! gain control
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crossbeam_channel::{Receiver, Sender};
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealToComplex};
+
+use crate::analysis::fft_planner;
+use crate::buffer::AudioBuffer;
+use crate::media_devices::AutoGainControlOptions;
+use crate::render::AudioRenderQuantum;
+use crate::{FallibleBuffer, RENDER_QUANTUM_SIZE};
+
+use super::VoiceSettings;
+
+/// Number of frames processed at once
+const BLOCK_SIZE: usize = 256;
+
+/// Size of the FFTs, two blocks
+const FFT_SIZE: usize = 2 * BLOCK_SIZE;
+
+/// Number of bins of the spectra
+const BINS: usize = BLOCK_SIZE + 1;
+
+/// Number of partitions of the echo canceller, an echo path of 4096 frames (85 ms at 48 kHz)
+const PARTITIONS: usize = 16;
+
+/// Step size of the adaptive filter of the echo canceller
+const STEP_SIZE: f32 = 0.5;
+
+/// Regularization of the normalized step size, for a silent far end
+const REGULARIZATION: f32 = 1e-3;
+
+/// Maximum number of frames of the far end signal waiting to be cancelled
+const MAX_REFERENCE_LEAD: usize = 8 * BLOCK_SIZE;
+
+/// Number of blocks of the sub-windows of the minimum tracking of the noise, about 0.5 s
+const NOISE_WINDOW: usize = 100;
+
+/// Smoothing of the power spectrum of the noise suppressor
+const POWER_SMOOTHING: f32 = 0.8;
+
+/// Overestimation of the noise, compensating the bias of the minimum tracking
+const NOISE_BIAS: f32 = 2.;
+
+/// Weight of the previous block in the decision-directed estimate of the SNR
+const SNR_SMOOTHING: f32 = 0.98;
+
+/// Minimum gain of the noise suppressor, -20 dB, limiting the musical noise
+const GAIN_FLOOR: f32 = 0.1;
+
+/// Level below which the automatic gain control holds its gain, in dBFS, to not amplify silence
+const GAIN_CONTROL_GATE: f32 = -60.;
+
+/// Maximum peak level of the automatic gain control output
+const GAIN_CONTROL_CEILING: f32 = 0.99;
+
+/// Far end signals rendered by the audio contexts, the reference of the echo cancellers
+#[derive(Default)]
+struct EchoReference {
+    /// Number of listening echo cancellers, checked by the render threads without locking
+    count: AtomicUsize,
+    next_id: AtomicUsize,
+    listeners: Mutex<Vec<(usize, Sender<ReferenceBlock>)>>,
+}
+
+/// Mono mix of a render quantum and its sample rate
+type ReferenceBlock = (Arc<[f32]>, f32);
+
+fn echo_reference() -> &'static EchoReference {
+    static INSTANCE: OnceLock<EchoReference> = OnceLock::new();
+    INSTANCE.get_or_init(EchoReference::default)
+}
+
+/// Send the output of a context to the echo cancellers, mixed down to mono
+///
+/// This is a no-op unless an echo canceller is active. The render thread never waits for the
+/// listeners, the quantum is skipped instead.
+pub(crate) fn push_echo_reference(quantum: &AudioRenderQuantum, sample_rate: f32) {
+    let reference = echo_reference();
+    if reference.count.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let Ok(listeners) = reference.listeners.try_lock() else {
+        return;
+    };
+
+    let channels = quantum.channels();
+    let scale = 1. / channels.len() as f32;
+    let mono: Arc<[f32]> = (0..RENDER_QUANTUM_SIZE)
+        .map(|i| channels.iter().map(|c| c[i]).sum::<f32>() * scale)
+        .collect();
+
+    listeners.iter().for_each(|(_, sender)| {
+        let _ = sender.try_send((Arc::clone(&mono), sample_rate));
+    });
+}
+
+/// Registration of an echo canceller with the far end signals
+struct ReferenceListener {
+    id: usize,
+    receiver: Receiver<ReferenceBlock>,
+}
+
+impl ReferenceListener {
+    fn new() -> Self {
+        let reference = echo_reference();
+        let id = reference.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = crossbeam_channel::bounded(256);
+        reference.listeners.lock().unwrap().push((id, sender));
+        reference.count.fetch_add(1, Ordering::Relaxed);
+
+        Self { id, receiver }
+    }
+}
+
+impl Drop for ReferenceListener {
+    fn drop(&mut self) {
+        let reference = echo_reference();
+        reference.count.fetch_sub(1, Ordering::Relaxed);
+        reference
+            .listeners
+            .lock()
+            .unwrap()
+            .retain(|(id, _)| *id != self.id);
+    }
+}
+
+/// Forward and inverse FFTs of `FFT_SIZE` frames
+struct Fft {
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    time: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+}
+
+impl Fft {
+    fn new() -> Self {
+        let mut planner = fft_planner().lock().unwrap();
+        let forward = planner.plan_fft_forward(FFT_SIZE);
+        let inverse = planner.plan_fft_inverse(FFT_SIZE);
+
+        Self {
+            time: forward.make_input_vec(),
+            spectrum: inverse.make_input_vec(),
+            forward,
+            inverse,
+        }
+    }
+
+    fn forward(&mut self, input: &[f32], output: &mut [Complex<f32>]) {
+        self.time.copy_from_slice(input);
+        self.forward.process(&mut self.time, output).unwrap();
+    }
+
+    /// Inverse transform, normalized so that it undoes the forward transform
+    fn inverse(&mut self, input: &[Complex<f32>], output: &mut [f32]) {
+        self.spectrum.copy_from_slice(input);
+        // the imaginary parts of the DC and Nyquist bins of a real signal are zero
+        self.spectrum[0].im = 0.;
+        self.spectrum[BINS - 1].im = 0.;
+        self.inverse.process(&mut self.spectrum, output).unwrap();
+        output.iter_mut().for_each(|v| *v /= FFT_SIZE as f32);
+    }
+}
+
+/// Acoustic echo canceller, a partitioned block frequency domain adaptive filter removing the far
+/// end signal from each channel of the microphone
+struct EchoCanceller {
+    listener: ReferenceListener,
+    sample_rate: f32,
+    /// Far end frames received and not cancelled yet
+    reference: VecDeque<f32>,
+    /// Previous and current block of the far end signal
+    frame: Vec<f32>,
+    /// Spectra of the last `PARTITIONS` frames of the far end signal, the newest at `head`
+    spectra: Vec<Vec<Complex<f32>>>,
+    head: usize,
+    /// Smoothed power spectrum of the far end signal
+    power: Vec<f32>,
+    /// Partitions of the filter, per channel
+    filters: Vec<Vec<Vec<Complex<f32>>>>,
+    time: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+}
+
+impl EchoCanceller {
+    fn new(number_of_channels: usize, sample_rate: f32) -> Self {
+        let spectrum = vec![Complex::default(); BINS];
+        Self {
+            listener: ReferenceListener::new(),
+            sample_rate,
+            reference: VecDeque::new(),
+            frame: vec![0.; FFT_SIZE],
+            spectra: vec![spectrum.clone(); PARTITIONS],
+            head: 0,
+            power: vec![0.; BINS],
+            filters: vec![vec![spectrum.clone(); PARTITIONS]; number_of_channels],
+            time: vec![0.; FFT_SIZE],
+            spectrum,
+        }
+    }
+
+    /// Collect the far end frames rendered since the previous block
+    fn receive(&mut self) {
+        while let Ok((block, sample_rate)) = self.listener.receiver.try_recv() {
+            if sample_rate == self.sample_rate {
+                self.reference.extend(block.iter());
+            } else {
+                log::debug!("EchoCanceller: ignoring far end signal of another sample rate");
+            }
+        }
+
+        // keep up with the far end after the microphone has stalled
+        if self.reference.len() > MAX_REFERENCE_LEAD {
+            let excess = self.reference.len() - BLOCK_SIZE;
+            self.reference.drain(..excess);
+        }
+    }
+
+    /// Remove the echo from a block of each channel
+    fn process(&mut self, fft: &mut Fft, blocks: &mut [Vec<f32>]) {
+        self.receive();
+
+        // the far end is silent when no context is rendering
+        self.frame.copy_within(BLOCK_SIZE.., 0);
+        self.frame[BLOCK_SIZE..]
+            .iter_mut()
+            .for_each(|v| *v = self.reference.pop_front().unwrap_or(0.));
+
+        self.head = (self.head + PARTITIONS - 1) % PARTITIONS;
+        fft.forward(&self.frame, &mut self.spectra[self.head]);
+        self.power
+            .iter_mut()
+            .zip(&self.spectra[self.head])
+            .for_each(|(p, x)| *p = 0.9 * *p + 0.1 * x.norm_sqr());
+
+        for (filter, block) in self.filters.iter_mut().zip(blocks.iter_mut()) {
+            // subtract the estimated echo
+            self.spectrum.fill(Complex::default());
+            for (p, partition) in filter.iter().enumerate() {
+                let x = &self.spectra[(self.head + p) % PARTITIONS];
+                self.spectrum
+                    .iter_mut()
+                    .zip(partition)
+                    .zip(x)
+                    .for_each(|((y, w), x)| *y += w * x);
+            }
+            fft.inverse(&self.spectrum, &mut self.time);
+            block
+                .iter_mut()
+                .zip(&self.time[BLOCK_SIZE..])
+                .for_each(|(d, y)| *d -= y);
+
+            // adapt the filter to the normalized error
+            self.time[..BLOCK_SIZE].fill(0.);
+            self.time[BLOCK_SIZE..].copy_from_slice(block);
+            fft.forward(&self.time, &mut self.spectrum);
+            self.spectrum
+                .iter_mut()
+                .zip(&self.power)
+                .for_each(|(e, p)| *e *= STEP_SIZE / (PARTITIONS as f32 * p + REGULARIZATION));
+
+            for (p, partition) in filter.iter_mut().enumerate() {
+                let x = &self.spectra[(self.head + p) % PARTITIONS];
+                partition
+                    .iter_mut()
+                    .zip(x)
+                    .zip(&self.spectrum)
+                    .for_each(|((w, x), e)| *w += x.conj() * e);
+
+                // constrain the partition to a block of taps, preventing circular convolution
+                fft.inverse(partition, &mut self.time);
+                self.time[BLOCK_SIZE..].fill(0.);
+                fft.forward(&self.time, partition);
+            }
+        }
+    }
+}
+
+/// Noise suppressor of a single channel, a spectral gain based on the minimum statistics of the
+/// power spectrum and a decision-directed estimate of the SNR
+struct NoiseSuppressor {
+    /// Previous and current block of the input
+    frame: Vec<f32>,
+    /// Second half of the previous output frame
+    overlap: Vec<f32>,
+    /// Smoothed power spectrum
+    power: Vec<f32>,
+    /// Minimum of the power spectrum in the current and previous sub-windows
+    minimum: Vec<f32>,
+    previous_minimum: Vec<f32>,
+    noise: Vec<f32>,
+    /// Power spectrum of the clean signal of the previous block
+    clean: Vec<f32>,
+    /// Number of blocks processed in the current sub-window
+    count: usize,
+    initialized: bool,
+    time: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+}
+
+impl NoiseSuppressor {
+    fn new() -> Self {
+        Self {
+            frame: vec![0.; FFT_SIZE],
+            overlap: vec![0.; BLOCK_SIZE],
+            power: vec![0.; BINS],
+            minimum: vec![0.; BINS],
+            previous_minimum: vec![0.; BINS],
+            noise: vec![0.; BINS],
+            clean: vec![0.; BINS],
+            count: 0,
+            initialized: false,
+            time: vec![0.; FFT_SIZE],
+            spectrum: vec![Complex::default(); BINS],
+        }
+    }
+
+    /// Suppress the noise of a block, the output is delayed by a block
+    fn process(&mut self, fft: &mut Fft, window: &[f32], block: &mut [f32]) {
+        self.frame.copy_within(BLOCK_SIZE.., 0);
+        self.frame[BLOCK_SIZE..].copy_from_slice(block);
+        self.time
+            .iter_mut()
+            .zip(&self.frame)
+            .zip(window)
+            .for_each(|((t, x), w)| *t = x * w);
+        fft.forward(&self.time, &mut self.spectrum);
+
+        self.count += 1;
+        let new_window = self.count == NOISE_WINDOW;
+        if new_window {
+            self.count = 0;
+        }
+
+        for k in 0..BINS {
+            let power = self.spectrum[k].norm_sqr();
+            if self.initialized {
+                self.power[k] = POWER_SMOOTHING * self.power[k] + (1. - POWER_SMOOTHING) * power;
+                self.minimum[k] = self.minimum[k].min(self.power[k]);
+                self.noise[k] = self.noise[k].min(self.power[k]);
+            } else {
+                self.power[k] = power;
+                self.minimum[k] = power;
+                self.previous_minimum[k] = power;
+                self.noise[k] = power;
+            }
+
+            // the noise is the minimum over the last two sub-windows, so it follows rising noise
+            if new_window {
+                self.noise[k] = self.minimum[k].min(self.previous_minimum[k]);
+                self.previous_minimum[k] = self.minimum[k];
+                self.minimum[k] = self.power[k];
+            }
+
+            let noise = (NOISE_BIAS * self.noise[k]).max(f32::MIN_POSITIVE);
+            let posterior = power / noise;
+            let prior = SNR_SMOOTHING * self.clean[k] / noise
+                + (1. - SNR_SMOOTHING) * (posterior - 1.).max(0.);
+            let gain = (prior / (1. + prior)).max(GAIN_FLOOR);
+
+            self.clean[k] = gain * gain * power;
+            self.spectrum[k] *= gain;
+        }
+        self.initialized = true;
+
+        // overlap-add of the windowed frames
+        fft.inverse(&self.spectrum, &mut self.time);
+        block.iter_mut().enumerate().for_each(|(i, v)| {
+            *v = self.overlap[i] + self.time[i] * window[i];
+            self.overlap[i] = self.time[BLOCK_SIZE + i] * window[BLOCK_SIZE + i];
+        });
+    }
+}
+
+/// Automatic gain control, bringing the level of all channels to a target level
+struct GainControl {
+    options: AutoGainControlOptions,
+    /// Maximum change of the gain per block, in dB
+    step: f32,
+    /// Current gain, in dB
+    gain: f32,
+}
+
+impl GainControl {
+    fn new(options: AutoGainControlOptions, sample_rate: f32) -> Self {
+        Self {
+            options,
+            step: options.compression_speed * BLOCK_SIZE as f32 / sample_rate,
+            gain: 0.,
+        }
+    }
+
+    fn process(&mut self, blocks: &mut [Vec<f32>]) {
+        let frames = (blocks.len() * BLOCK_SIZE) as f32;
+        let mean_square = blocks.iter().flatten().map(|s| s * s).sum::<f32>() / frames;
+        let level = 10. * (mean_square + 1e-12).log10();
+        let previous = 10_f32.powf(self.gain / 20.);
+
+        // move towards the target level, unless the input is silent
+        if level > GAIN_CONTROL_GATE {
+            let desired = (self.options.target_level - level).min(self.options.max_gain);
+            self.gain += (desired - self.gain).clamp(-self.step, self.step);
+        }
+
+        // never clip, reduce the gain at once instead
+        let peak = blocks.iter().flatten().fold(0_f32, |m, s| m.max(s.abs()));
+        if peak * 10_f32.powf(self.gain / 20.) > GAIN_CONTROL_CEILING {
+            self.gain = 20. * (GAIN_CONTROL_CEILING / peak).log10();
+        }
+        let gain = 10_f32.powf(self.gain / 20.);
+
+        // ramp from the previous gain to prevent zipper noise
+        blocks.iter_mut().for_each(|block| {
+            block.iter_mut().enumerate().for_each(|(i, s)| {
+                let t = (i + 1) as f32 / BLOCK_SIZE as f32;
+                *s *= previous + t * (gain - previous);
+                *s = s.clamp(-GAIN_CONTROL_CEILING, GAIN_CONTROL_CEILING);
+            })
+        });
+    }
+}
+
+/// Microphone stream with echo cancellation, noise suppression and/or automatic gain control
+///
+/// The frames are processed in blocks, the output is delayed by `2 * BLOCK_SIZE` frames.
+pub(crate) struct VoiceProcessing<I> {
+    input: I,
+    settings: VoiceSettings,
+    fft: Fft,
+    /// Square root of a periodic Hann window, for the overlap-add of the noise suppressor
+    window: Vec<f32>,
+    sample_rate: f32,
+    echo_canceller: Option<EchoCanceller>,
+    noise_suppressors: Vec<NoiseSuppressor>,
+    gain_control: Option<GainControl>,
+    /// Frames of the input not processed yet, per channel
+    pending: Vec<Vec<f32>>,
+    /// Frames processed and not emitted yet, per channel
+    processed: Vec<VecDeque<f32>>,
+}
+
+impl<I: Iterator<Item = FallibleBuffer>> VoiceProcessing<I> {
+    pub fn new(input: I, settings: VoiceSettings) -> Self {
+        let window = (0..FFT_SIZE)
+            .map(|i| (std::f32::consts::PI * i as f32 / FFT_SIZE as f32).sin())
+            .collect();
+
+        Self {
+            input,
+            settings,
+            fft: Fft::new(),
+            window,
+            sample_rate: 0.,
+            echo_canceller: None,
+            noise_suppressors: Vec::new(),
+            gain_control: None,
+            pending: Vec::new(),
+            processed: Vec::new(),
+        }
+    }
+
+    /// Set up the processing for the given input format
+    fn reset(&mut self, number_of_channels: usize, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.echo_canceller = self
+            .settings
+            .echo_cancellation
+            .then(|| EchoCanceller::new(number_of_channels, sample_rate));
+        self.noise_suppressors = if self.settings.noise_suppression {
+            (0..number_of_channels)
+                .map(|_| NoiseSuppressor::new())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.gain_control = self
+            .settings
+            .auto_gain_control
+            .map(|options| GainControl::new(options, sample_rate));
+        self.pending = vec![Vec::new(); number_of_channels];
+        // a block of silence so that a full buffer can be emitted before its last block is
+        // processed
+        self.processed = vec![std::iter::repeat(0.).take(BLOCK_SIZE).collect(); number_of_channels];
+    }
+}
+
+impl<I: Iterator<Item = FallibleBuffer>> Iterator for VoiceProcessing<I> {
+    type Item = FallibleBuffer;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let buffer = match self.input.next()? {
+            Ok(buffer) => buffer,
+            Err(e) => return Some(Err(e)),
+        };
+
+        if buffer.number_of_channels() != self.pending.len()
+            || buffer.sample_rate() != self.sample_rate
+        {
+            self.reset(buffer.number_of_channels(), buffer.sample_rate());
+        }
+
+        self.pending
+            .iter_mut()
+            .zip(buffer.channels())
+            .for_each(|(pending, channel)| pending.extend_from_slice(channel.as_slice()));
+
+        while self.pending[0].len() >= BLOCK_SIZE {
+            let mut blocks: Vec<Vec<f32>> = self
+                .pending
+                .iter_mut()
+                .map(|pending| pending.drain(..BLOCK_SIZE).collect())
+                .collect();
+
+            if let Some(echo_canceller) = self.echo_canceller.as_mut() {
+                echo_canceller.process(&mut self.fft, &mut blocks);
+            }
+            self.noise_suppressors
+                .iter_mut()
+                .zip(blocks.iter_mut())
+                .for_each(|(suppressor, block)| {
+                    suppressor.process(&mut self.fft, &self.window, block)
+                });
+            if let Some(gain_control) = self.gain_control.as_mut() {
+                gain_control.process(&mut blocks);
+            }
+
+            self.processed
+                .iter_mut()
+                .zip(blocks)
+                .for_each(|(processed, block)| processed.extend(block));
+        }
+
+        let length = buffer.length();
+        let channels = self
+            .processed
+            .iter_mut()
+            .map(|processed| processed.drain(..length).collect())
+            .collect();
+
+        Some(Ok(AudioBuffer::from(channels, self.sample_rate)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SeededRng;
+
+    use super::*;
+
+    fn energy(samples: &[f32]) -> f32 {
+        samples.iter().map(|s| s * s).sum()
+    }
+
+    #[test]
+    fn test_buffer_lengths() {
+        let settings = VoiceSettings {
+            echo_cancellation: true,
+            noise_suppression: true,
+            auto_gain_control: Some(AutoGainControlOptions::default()),
+        };
+        let buffers: [FallibleBuffer; 4] = [100, 441, 1, 512]
+            .map(|length| Ok(AudioBuffer::from(vec![vec![0.5; length]; 2], 44_100.)));
+        let processing = VoiceProcessing::new(buffers.into_iter(), settings);
+
+        let lengths: Vec<_> = processing
+            .map(|buffer| {
+                let buffer = buffer.unwrap();
+                assert_eq!(buffer.number_of_channels(), 2);
+                buffer.length()
+            })
+            .collect();
+        assert_eq!(lengths, [100, 441, 1, 512]);
+    }
+
+    #[test]
+    fn test_noise_suppression() {
+        let mut rng = SeededRng::new(1);
+        let mut fft = Fft::new();
+        let window: Vec<f32> = (0..FFT_SIZE)
+            .map(|i| (std::f32::consts::PI * i as f32 / FFT_SIZE as f32).sin())
+            .collect();
+        let mut suppressor = NoiseSuppressor::new();
+
+        let mut input = 0.;
+        let mut output = 0.;
+        for i in 0..400 {
+            let mut block: Vec<f32> = (0..BLOCK_SIZE).map(|_| 0.1 * rng.next_bipolar()).collect();
+            let block_energy = energy(&block);
+            suppressor.process(&mut fft, &window, &mut block);
+            if i >= 300 {
+                input += block_energy;
+                output += energy(&block);
+            }
+        }
+
+        // stationary noise is attenuated by more than 6 dB
+        assert!(output < 0.25 * input);
+    }
+
+    #[test]
+    fn test_echo_cancellation() {
+        let mut rng = SeededRng::new(1);
+        let mut fft = Fft::new();
+        let mut canceller = EchoCanceller::new(1, 48_000.);
+
+        // the echo is the far end signal, attenuated and delayed by 100 frames
+        let far_end: Vec<f32> = (0..1000 * BLOCK_SIZE).map(|_| rng.next_bipolar()).collect();
+        let echo: Vec<f32> = std::iter::repeat(0.)
+            .take(100)
+            .chain(far_end.iter().map(|s| 0.5 * s))
+            .collect();
+
+        let mut input = 0.;
+        let mut output = 0.;
+        for i in 0..1000 {
+            let range = i * BLOCK_SIZE..(i + 1) * BLOCK_SIZE;
+            canceller.reference.extend(&far_end[range.clone()]);
+            let mut blocks = vec![echo[range].to_vec()];
+            let block_energy = energy(&blocks[0]);
+            canceller.process(&mut fft, &mut blocks);
+            if i >= 900 {
+                input += block_energy;
+                output += energy(&blocks[0]);
+            }
+        }
+
+        // the echo is attenuated by more than 20 dB
+        assert!(output < 0.01 * input);
+    }
+
+    #[test]
+    fn test_gain_control() {
+        let options = AutoGainControlOptions::default();
+        let mut gain_control = GainControl::new(options, 48_000.);
+
+        let sine = |amplitude: f32, i: usize| -> Vec<Vec<f32>> {
+            let block = (0..BLOCK_SIZE)
+                .map(|j| amplitude * (0.05 * (i * BLOCK_SIZE + j) as f32).sin())
+                .collect();
+            vec![block]
+        };
+        let level = |blocks: &[Vec<f32>]| {
+            let mean_square = blocks[0].iter().map(|s| s * s).sum::<f32>() / BLOCK_SIZE as f32;
+            10. * mean_square.log10()
+        };
+
+        // a quiet input is raised to the target level
+        let mut blocks = vec![];
+        for i in 0..2000 {
+            blocks = sine(0.01, i);
+            gain_control.process(&mut blocks);
+        }
+        assert!((level(&blocks) - options.target_level).abs() < 1.);
+
+        // a sudden loud input does not clip
+        let mut blocks = sine(1., 0);
+        gain_control.process(&mut blocks);
+        assert!(blocks[0].iter().all(|s| s.abs() <= GAIN_CONTROL_CEILING));
+    }
+}
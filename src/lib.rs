@@ -14,8 +14,20 @@ use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 pub(crate) const RENDER_QUANTUM_SIZE: usize = 128;
 
 /// Maximum number of channels for audio processing
+///
+/// The specification requires at least 32 channels. Enable the `max-channels-64` or
+/// `max-channels-128` feature to address all channels of larger interfaces, e.g. Dante or MADI.
+#[cfg(not(any(feature = "max-channels-64", feature = "max-channels-128")))]
 pub const MAX_CHANNELS: usize = 32;
 
+/// Maximum number of channels for audio processing, raised by the `max-channels-64` feature
+#[cfg(all(feature = "max-channels-64", not(feature = "max-channels-128")))]
+pub const MAX_CHANNELS: usize = 64;
+
+/// Maximum number of channels for audio processing, raised by the `max-channels-128` feature
+#[cfg(feature = "max-channels-128")]
+pub const MAX_CHANNELS: usize = 128;
+
 mod buffer;
 pub use buffer::*;
 
@@ -24,6 +36,8 @@ pub use capacity::*;
 
 pub mod context;
 
+pub mod dsp;
+
 pub mod media_devices;
 pub mod media_recorder;
 pub mod media_streams;
@@ -33,15 +47,28 @@ pub mod node;
 mod events;
 pub use events::*;
 
+#[cfg(feature = "async")]
+mod event_future;
+#[cfg(feature = "async")]
+pub use event_future::EventFuture;
+
+pub mod hrtf;
+
 mod message_port;
 pub use message_port::MessagePort;
 
+pub mod offline_render;
+pub use offline_render::{render_graph, render_graph_to_file, OfflineRenderOptions};
+
 mod param;
 pub use param::*;
 
 mod periodic_wave;
 pub use periodic_wave::*;
 
+mod profiler;
+pub use profiler::NodeProfile;
+
 mod render;
 
 mod spatial;
@@ -50,14 +77,49 @@ pub use spatial::AudioListener;
 mod io;
 
 mod analysis;
+#[cfg(feature = "rt-audit")]
+pub mod audit;
 mod message;
 
 mod decoding;
+pub use decoding::{AudioMetadata, DecodeError, SampleLoop};
+
+mod decoding_stream;
+pub use decoding_stream::{DecodingProgressEvent, DecodingStream};
 
 mod media_element;
 pub use media_element::MediaElement;
 
+#[cfg(feature = "inspect")]
+pub mod inspect;
+
+#[cfg(feature = "midi")]
+pub mod midi;
+
+mod random;
+pub use random::*;
+
 mod resampling;
+
+mod scheduler;
+pub use scheduler::*;
+
+mod sound_bank;
+pub use sound_bank::*;
+
+mod streaming_buffer;
+pub use streaming_buffer::StreamingAudioBuffer;
+
+pub mod stress;
+
+mod tone_match;
+pub use tone_match::*;
+
+pub mod units;
+
+mod voice_manager;
+pub use voice_manager::*;
+
 pub mod worklet;
 
 #[repr(transparent)]
@@ -157,8 +219,8 @@ pub(crate) fn assert_valid_sample_rate(sample_rate: f32) {
 /// # Panics
 ///
 /// This function will panic if:
-/// - the given number of channels is outside the [1, 32] range,
-///   32 being defined by the MAX_CHANNELS constant.
+/// - the given number of channels is outside the [1, MAX_CHANNELS] range,
+///   MAX_CHANNELS being 32 unless raised by a feature flag.
 ///
 #[track_caller]
 #[inline(always)]
@@ -281,13 +343,13 @@ mod tests {
     #[test]
     #[should_panic]
     fn test_invalid_number_of_channels_max() {
-        assert_valid_number_of_channels(33);
+        assert_valid_number_of_channels(MAX_CHANNELS + 1);
     }
 
     #[test]
     fn test_valid_number_of_channels() {
         assert_valid_number_of_channels(1);
-        assert_valid_number_of_channels(32);
+        assert_valid_number_of_channels(MAX_CHANNELS);
     }
 
     #[test]
@@ -5,9 +5,11 @@
 //! <https://developer.mozilla.org/en-US/docs/Web/API/MediaDevices>
 
 use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
 use std::hash::{Hash, Hasher};
 
 use crate::context::{AudioContextLatencyCategory, AudioContextOptions};
+use crate::io::VoiceSettings;
 use crate::media_streams::MediaStream;
 
 /// List the available media output devices, such as speakers, headsets, loopbacks, etc
@@ -145,9 +147,19 @@ pub struct MediaTrackConstraints {
     // ConstrainDOMString resizeMode;
     pub sample_rate: Option<f32>,
     // ConstrainULong sampleSize;
-    // ConstrainBoolean echoCancellation;
-    // ConstrainBoolean autoGainControl;
-    // ConstrainBoolean noiseSuppression;
+    /// Remove the audio played by the audio contexts from the input, requires the
+    /// `voice-processing` feature
+    pub echo_cancellation: Option<bool>,
+    /// Bring the input to a constant level, see `auto_gain_control_options`, requires the
+    /// `voice-processing` feature
+    pub auto_gain_control: Option<bool>,
+    /// Settings of the automatic gain control
+    ///
+    /// This is not part of the MediaDevices API.
+    pub auto_gain_control_options: AutoGainControlOptions,
+    /// Suppress the stationary background noise of the input, requires the `voice-processing`
+    /// feature
+    pub noise_suppression: Option<bool>,
     pub latency: Option<f64>,
     pub channel_count: Option<u32>, // TODO model as ConstrainULong;
     pub device_id: Option<String>,
@@ -167,6 +179,46 @@ impl From<MediaTrackConstraints> for AudioContextOptions {
             sample_rate: value.sample_rate,
             sink_id,
             render_size_hint: Default::default(),
+            idle_suspend: None,
+            interruption_policy: Default::default(),
+            device_fallback: None,
+        }
+    }
+}
+
+/// Settings of the automatic gain control of a microphone input, see
+/// [`MediaTrackConstraints::auto_gain_control`]
+///
+/// This is not part of the MediaDevices API.
+#[derive(Clone, Copy, Debug)]
+pub struct AutoGainControlOptions {
+    /// Level the input is brought to, in dBFS
+    pub target_level: f32,
+    /// Maximum gain applied to quiet inputs, in dB
+    pub max_gain: f32,
+    /// Maximum change of the gain, in dB per second
+    pub compression_speed: f32,
+}
+
+impl Default for AutoGainControlOptions {
+    fn default() -> Self {
+        Self {
+            target_level: -18.,
+            max_gain: 30.,
+            compression_speed: 6.,
+        }
+    }
+}
+
+impl From<&MediaTrackConstraints> for VoiceSettings {
+    fn from(value: &MediaTrackConstraints) -> Self {
+        VoiceSettings {
+            echo_cancellation: value.echo_cancellation.unwrap_or(false),
+            noise_suppression: value.noise_suppression.unwrap_or(false),
+            auto_gain_control: value
+                .auto_gain_control
+                .unwrap_or(false)
+                .then_some(value.auto_gain_control_options),
         }
     }
 }
@@ -219,9 +271,15 @@ fn is_valid_device_id(device_id: &str) -> bool {
 /// std::thread::sleep(std::time::Duration::from_secs(4));
 /// ```
 pub fn get_user_media_sync(constraints: MediaStreamConstraints) -> MediaStream {
-    let (channel_count, mut options) = match constraints {
-        MediaStreamConstraints::Audio => (None, AudioContextOptions::default()),
-        MediaStreamConstraints::AudioWithConstraints(cs) => (cs.channel_count, cs.into()),
+    let (channel_count, voice, mut options) = match constraints {
+        MediaStreamConstraints::Audio => (
+            None,
+            VoiceSettings::default(),
+            AudioContextOptions::default(),
+        ),
+        MediaStreamConstraints::AudioWithConstraints(cs) => {
+            (cs.channel_count, (&cs).into(), cs.into())
+        }
     };
 
     if !is_valid_device_id(&options.sink_id) {
@@ -229,5 +287,108 @@ pub fn get_user_media_sync(constraints: MediaStreamConstraints) -> MediaStream {
         options.sink_id = String::from("");
     }
 
-    crate::io::build_input(options, channel_count)
+    crate::io::build_input(options, channel_count, voice)
+}
+
+/// Open several media inputs at once, e.g. the microphones of a multi-mic recording
+///
+/// This produces a [`MediaStream`] with one track per item of `constraints`, in the same order.
+/// Play each track with a
+/// [`MediaStreamTrackAudioSourceNode`](crate::node::MediaStreamTrackAudioSourceNode) and use
+/// [`MediaStreamTrack::set_gain`](crate::media_streams::MediaStreamTrack::set_gain) and
+/// [`MediaStreamTrack::set_enabled`](crate::media_streams::MediaStreamTrack::set_enabled) to
+/// balance and mute them.
+///
+/// The tracks start at their first render instead of the opening of the devices, so the
+/// tracks whose source nodes are created together share the same timeline.
+///
+/// This is not part of the MediaDevices API.
+///
+/// # Example
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::media_devices::{self, enumerate_devices_sync, MediaDeviceInfoKind};
+/// use web_audio_api::media_devices::MediaTrackConstraints;
+/// use web_audio_api::node::AudioNode;
+///
+/// let constraints = enumerate_devices_sync()
+///     .into_iter()
+///     .filter(|device| device.kind() == MediaDeviceInfoKind::AudioInput)
+///     .map(|device| {
+///         let mut constraints = MediaTrackConstraints::default();
+///         constraints.device_id = Some(device.device_id().to_string());
+///         constraints
+///     })
+///     .collect();
+/// let mics = media_devices::get_user_media_multi_sync(constraints);
+///
+/// let context = AudioContext::default();
+/// for track in mics.get_tracks() {
+///     let source = context.create_media_stream_track_source(track);
+///     source.connect(&context.destination());
+/// }
+///
+/// // lower the first microphone by 6dB
+/// mics.get_tracks()[0].set_gain(0.5);
+/// ```
+pub fn get_user_media_multi_sync(constraints: Vec<MediaTrackConstraints>) -> MediaStream {
+    let inputs = constraints
+        .into_iter()
+        .map(|constraints| {
+            let channel_count = constraints.channel_count;
+            let voice = (&constraints).into();
+            let mut options: AudioContextOptions = constraints.into();
+
+            if !is_valid_device_id(&options.sink_id) {
+                log::error!("NotFoundError: invalid deviceId {:?}", options.sink_id);
+                options.sink_id = String::from("");
+            }
+
+            (options, channel_count, voice)
+        })
+        .collect();
+
+    crate::io::build_inputs(inputs)
+}
+
+/// Capture the audio played on an output device (loopback), e.g. to analyze or visualize what
+/// the machine is playing
+///
+/// Use `""` for the default output device, or the `deviceId` of an output device returned from
+/// [`enumerate_devices_sync`]. The stream has a single track and behaves like a microphone
+/// stream obtained with [`get_user_media_sync`].
+///
+/// Support depends on the host: WASAPI captures the output device itself, PulseAudio and
+/// PipeWire expose the played audio as a monitor input device, which is selected instead. On
+/// macOS, system audio can only be captured via a loopback driver that appears as a regular input
+/// device, ScreenCaptureKit is not available to the audio backends.
+///
+/// This is not part of the MediaDevices API.
+///
+/// # Errors
+///
+/// This function returns an error if the output device is not found, or the host offers no way to
+/// capture it.
+///
+/// # Example
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, AudioContextOptions, BaseAudioContext};
+/// use web_audio_api::media_devices;
+/// use web_audio_api::node::AudioNode;
+///
+/// // do not play the captured audio, this would feed back into the capture
+/// let context = AudioContext::new(AudioContextOptions {
+///     sink_id: "none".into(),
+///     ..AudioContextOptions::default()
+/// });
+/// let loopback = media_devices::get_loopback_media_sync("").unwrap();
+///
+/// let source = context.create_media_stream_source(&loopback);
+/// let analyser = context.create_analyser();
+/// source.connect(&analyser);
+/// ```
+pub fn get_loopback_media_sync(sink_id: &str) -> Result<MediaStream, Box<dyn Error + Send + Sync>> {
+    crate::io::build_loopback(sink_id)
 }
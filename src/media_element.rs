@@ -6,6 +6,7 @@ use std::sync::Arc;
 use creek::{ReadDiskStream, SeekMode, SymphoniaDecoder};
 use crossbeam_channel::{Receiver, Sender};
 
+use crate::context::IdleMonitor;
 use crate::{AtomicF64, AudioBuffer, RENDER_QUANTUM_SIZE};
 
 /// Real time safe audio stream
@@ -52,6 +53,8 @@ pub struct MediaElement {
     loop_: Arc<AtomicBool>,
     paused: Arc<AtomicBool>,
     playback_rate: Arc<AtomicF64>,
+    /// Activity of the context of the source node, playing resumes an idle suspended context
+    idle_monitor: Option<Arc<IdleMonitor>>,
 }
 
 impl std::fmt::Debug for MediaElement {
@@ -116,11 +119,15 @@ impl MediaElement {
             loop_,
             paused,
             playback_rate,
+            idle_monitor: None,
         })
     }
 
-    pub(crate) fn take_stream(&mut self) -> Option<RTSStream> {
-        self.stream.take()
+    /// Hand the stream to the source node of the given context
+    pub(crate) fn take_stream(&mut self, idle_monitor: Arc<IdleMonitor>) -> Option<RTSStream> {
+        let stream = self.stream.take()?;
+        self.idle_monitor = Some(idle_monitor);
+        Some(stream)
     }
 
     pub fn current_time(&self) -> f64 {
@@ -140,6 +147,9 @@ impl MediaElement {
     }
 
     pub fn play(&self) {
+        if let Some(idle_monitor) = &self.idle_monitor {
+            idle_monitor.notify_active();
+        }
         let _ = self.sender.send(MediaElementAction::Play);
     }
 
@@ -9,7 +9,7 @@
 //!
 //! <https://developer.mozilla.org/en-US/docs/Web/API/Media_Capture_and_Streams_API>
 
-use crate::{AudioBuffer, FallibleBuffer};
+use crate::{AtomicF32, AudioBuffer, FallibleBuffer};
 use arc_swap::ArcSwap;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
@@ -26,6 +26,26 @@ pub enum MediaStreamTrackState {
     Ended,
 }
 
+/// Quality of the sample rate conversion of a media stream played in a context running at
+/// another sample rate
+///
+/// This is not part of the Media Capture and Streams API.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ResampleQuality {
+    /// Linear interpolation, cheapest but aliases and dulls the high frequencies
+    Linear,
+    /// Windowed sinc interpolation over 16 input frames
+    Medium,
+    /// Windowed sinc interpolation over 64 input frames
+    High,
+}
+
+impl Default for ResampleQuality {
+    fn default() -> Self {
+        Self::High
+    }
+}
+
 /// Single media track within a [`MediaStream`]
 #[derive(Clone)]
 pub struct MediaStreamTrack {
@@ -36,6 +56,8 @@ impl std::fmt::Debug for MediaStreamTrack {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("MediaStreamTrack")
             .field("ended", &self.inner.ended)
+            .field("enabled", &self.inner.enabled)
+            .field("gain", &self.inner.gain)
             .finish_non_exhaustive()
     }
 }
@@ -44,6 +66,8 @@ struct MediaStreamTrackInner {
     data: ArcSwap<FallibleBuffer>,
     position: AtomicU64,
     ended: AtomicBool,
+    enabled: AtomicBool,
+    gain: AtomicF32,
     provider: Mutex<Box<dyn Iterator<Item = FallibleBuffer> + Send + Sync + 'static>>,
 }
 
@@ -58,6 +82,8 @@ impl MediaStreamTrack {
             data: ArcSwap::from_pointee(initial),
             position: AtomicU64::new(0),
             ended: AtomicBool::new(false),
+            enabled: AtomicBool::new(true),
+            gain: AtomicF32::new(1.),
             provider: Mutex::new(Box::new(iter.into_iter())),
         };
         MediaStreamTrack {
@@ -73,6 +99,34 @@ impl MediaStreamTrack {
         }
     }
 
+    /// Whether the track renders its media, a disabled track renders silence
+    pub fn enabled(&self) -> bool {
+        self.inner.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable (mute) the track
+    ///
+    /// The setting is shared by all clones of the track.
+    pub fn set_enabled(&self, value: bool) {
+        self.inner.enabled.store(value, Ordering::Relaxed);
+    }
+
+    /// Gain applied to the media of the track
+    ///
+    /// This is not part of the Media Capture and Streams API.
+    pub fn gain(&self) -> f32 {
+        self.inner.gain.load(Ordering::Relaxed)
+    }
+
+    /// Set the gain applied to the media of the track, e.g. to balance several microphones
+    ///
+    /// The setting is shared by all clones of the track.
+    ///
+    /// This is not part of the Media Capture and Streams API.
+    pub fn set_gain(&self, value: f32) {
+        self.inner.gain.store(value, Ordering::Relaxed);
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = FallibleBuffer> {
         MediaStreamTrackIter {
             track: Arc::clone(&self.inner),
@@ -80,6 +134,53 @@ impl MediaStreamTrack {
         }
     }
 
+    /// Create a new track rewriting the audio frames of this track, similar to the insertable
+    /// streams of WebRTC
+    ///
+    /// The `transform` receives every frame of this track, after its gain has been applied, and
+    /// returns the frame of the new track, e.g. to apply a custom noise suppression before the
+    /// track is played by a
+    /// [`MediaStreamTrackAudioSourceNode`](crate::node::MediaStreamTrackAudioSourceNode). Errors
+    /// are passed on unaltered.
+    ///
+    /// The frames are pulled from this track when the new track is played, so the `transform`
+    /// runs on the render thread of the playing context and should be quick.
+    ///
+    /// This is not part of the Media Capture and Streams API.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext};
+    /// use web_audio_api::media_devices::{self, MediaStreamConstraints};
+    /// use web_audio_api::node::AudioNode;
+    ///
+    /// let context = AudioContext::default();
+    /// let mic = media_devices::get_user_media_sync(MediaStreamConstraints::Audio);
+    ///
+    /// // a crude noise gate
+    /// let gated = mic.get_tracks()[0].transformed(|mut frame| {
+    ///     for channel in 0..frame.number_of_channels() {
+    ///         frame
+    ///             .get_channel_data_mut(channel)
+    ///             .iter_mut()
+    ///             .filter(|s| s.abs() < 0.01)
+    ///             .for_each(|s| *s = 0.);
+    ///     }
+    ///     frame
+    /// });
+    ///
+    /// let source = context.create_media_stream_track_source(&gated);
+    /// source.connect(&context.destination());
+    /// ```
+    pub fn transformed<F>(&self, mut transform: F) -> MediaStreamTrack
+    where
+        F: FnMut(AudioBuffer) -> AudioBuffer + Send + Sync + 'static,
+    {
+        let iter = self.iter().map(move |frame| frame.map(&mut transform));
+        MediaStreamTrack::from_iter(iter)
+    }
+
     #[allow(clippy::missing_panics_doc)]
     pub fn close(&self) {
         // TODO, close should only close this instance but should leave clones unaltered.
@@ -116,8 +217,22 @@ impl Iterator for MediaStreamTrackIter {
         }
 
         self.position = stream_position;
+
+        let gain = if self.track.enabled.load(Ordering::Relaxed) {
+            self.track.gain.load(Ordering::Relaxed)
+        } else {
+            0.
+        };
+
         Some(match &self.track.data.load().as_ref() {
-            Ok(buf) => Ok(buf.clone()),
+            Ok(buf) if gain == 1. => Ok(buf.clone()),
+            Ok(buf) => {
+                let mut buf = buf.clone();
+                buf.channels_mut().iter_mut().for_each(|channel| {
+                    channel.as_mut_slice().iter_mut().for_each(|s| *s *= gain);
+                });
+                Ok(buf)
+            }
             Err(e) => Err(e.to_string().into()),
         })
     }
@@ -248,4 +363,68 @@ mod tests {
         track.close();
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn test_enabled_and_gain() {
+        let buffers = vec![
+            Ok(AudioBuffer::from(vec![vec![1.]], 48000.)),
+            Ok(AudioBuffer::from(vec![vec![1.]], 48000.)),
+            Ok(AudioBuffer::from(vec![vec![1.]], 48000.)),
+        ];
+        let track = MediaStreamTrack::from_iter(buffers);
+        let mut iter = track.iter();
+
+        track.set_gain(0.5);
+        assert_float_eq!(
+            iter.next().unwrap().unwrap().get_channel_data(0)[..],
+            [0.5][..],
+            abs_all <= 0.
+        );
+
+        track.set_enabled(false);
+        assert!(!track.enabled());
+        assert_float_eq!(
+            iter.next().unwrap().unwrap().get_channel_data(0)[..],
+            [0.][..],
+            abs_all <= 0.
+        );
+
+        track.set_enabled(true);
+        track.set_gain(1.);
+        assert_float_eq!(
+            iter.next().unwrap().unwrap().get_channel_data(0)[..],
+            [1.][..],
+            abs_all <= 0.
+        );
+    }
+    #[test]
+    fn test_transformed() {
+        let buffers = vec![
+            Ok(AudioBuffer::from(vec![vec![1.]], 48000.)),
+            Err("boom".into()),
+            Ok(AudioBuffer::from(vec![vec![2.]], 48000.)),
+        ];
+        let track = MediaStreamTrack::from_iter(buffers);
+        track.set_gain(0.5);
+
+        let transformed = track.transformed(|mut frame| {
+            frame.get_channel_data_mut(0)[0] += 1.;
+            frame
+        });
+        let mut iter = transformed.iter();
+
+        assert_float_eq!(
+            iter.next().unwrap().unwrap().get_channel_data(0)[..],
+            [1.5][..],
+            abs_all <= 0.
+        );
+        assert!(iter.next().unwrap().is_err());
+        assert_float_eq!(
+            iter.next().unwrap().unwrap().get_channel_data(0)[..],
+            [2.][..],
+            abs_all <= 0.
+        );
+        assert!(iter.next().is_none());
+        assert_eq!(track.ready_state(), MediaStreamTrackState::Ended);
+    }
 }
@@ -1,9 +1,11 @@
 //! Message passing from control to render node
 
 use std::any::Any;
+use std::sync::Arc;
 
 use crate::context::AudioNodeId;
 use crate::node::{ChannelConfigInner, ChannelCountMode, ChannelInterpretation};
+use crate::profiler::ProcessorStats;
 use crate::render::graph::Graph;
 use crate::render::AudioProcessor;
 
@@ -13,10 +15,13 @@ pub(crate) enum ControlMessage {
     RegisterNode {
         id: AudioNodeId,
         reclaim_id: llq::Node<AudioNodeId>,
+        /// Slot to ship the node to the garbage collector when it is dropped
+        garbage: llq::Node<Box<dyn Any + Send>>,
         node: Box<dyn AudioProcessor>,
         inputs: usize,
         outputs: usize,
         channel_config: ChannelConfigInner,
+        stats: Arc<ProcessorStats>,
     },
 
     /// Connect a node to another in the audio graph
@@ -41,6 +46,25 @@ pub(crate) enum ControlMessage {
     /// Mark node as a cycle breaker (DelayNode only)
     MarkCycleBreaker { id: AudioNodeId },
 
+    /// Enable or disable the recording of the render time of the nodes
+    SetProfiling { enabled: bool },
+
+    /// Replace the worker threads rendering the graph in parallel
+    ///
+    /// The payload is an `Option<RenderWorkers>`, boxed so the previous workers can be shut down
+    /// by the garbage collector thread.
+    SetRenderWorkers {
+        workers: llq::Node<Box<dyn Any + Send>>,
+    },
+
+    /// Replace the inserts of the main bus, processing the input of the destination in order
+    ///
+    /// The payload is a `Vec<AudioNodeId>`, boxed so the previous inserts are deallocated by the
+    /// garbage collector thread.
+    SetMainBus {
+        inserts: llq::Node<Box<dyn Any + Send>>,
+    },
+
     /// Shut down and recycle the audio graph
     CloseAndRecycle {
         sender: crossbeam_channel::Sender<Graph>,
@@ -58,12 +82,27 @@ pub(crate) enum ControlMessage {
     /// Stop audio processing
     Close { notify: OneshotNotify },
 
+    /// Fade out the output and stop audio processing at the given time
+    CloseAt {
+        when: f64,
+        fade_out: f64,
+        notify: OneshotNotify,
+    },
+
     /// Generic message to be handled by AudioProcessor
     NodeMessage {
         id: AudioNodeId,
         msg: llq::Node<Box<dyn Any + Send>>,
     },
 
+    /// Control messages to be handled together, in the same render quantum
+    ///
+    /// The payload is a `Vec<ControlMessage>`, boxed so the emptied vector can be deallocated by
+    /// the garbage collector thread.
+    Batch {
+        msgs: llq::Node<Box<dyn Any + Send>>,
+    },
+
     /// Request a diagnostic report of the audio graph
     RunDiagnostics { buffer: Vec<u8> },
 
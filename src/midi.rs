@@ -0,0 +1,329 @@
+//! Input from MIDI devices, loosely following the Web MIDI API
+//!
+//! This module is only available with the `midi` feature.
+//!
+//! <https://webaudio.github.io/web-midi-api/>
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use web_audio_api::context::{AudioContext, BaseAudioContext};
+//! use web_audio_api::midi::{self, MidiClock, MidiInput};
+//! use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+//!
+//! let context = AudioContext::default();
+//!
+//! let info = &midi::enumerate_inputs().unwrap()[0];
+//! let input = MidiInput::open(info.id()).unwrap();
+//!
+//! // schedule the events 20 ms in the future to render them sample-accurately
+//! let mut clock = MidiClock::new(&context, 0.02);
+//!
+//! while let Ok(message) = input.recv() {
+//!     if let Some((_channel, note, _velocity)) = message.note_on() {
+//!         let when = clock.context_time(&message);
+//!         let mut osc = context.create_oscillator();
+//!         osc.frequency()
+//!             .set_value(440. * 2_f32.powf((note as f32 - 69.) / 12.));
+//!         osc.connect(&context.destination());
+//!         osc.start_at(when);
+//!         osc.stop_at(when + 0.2);
+//!     }
+//! }
+//! ```
+
+use std::error::Error;
+use std::time::Instant;
+
+use crossbeam_channel::{Receiver, RecvError};
+use midir::{MidiInputConnection, MidiInputPort};
+
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+
+/// Name of the client registered with the MIDI system
+const CLIENT_NAME: &str = "web-audio-api";
+
+/// Information about a MIDI input port
+#[derive(Clone, Debug)]
+pub struct MidiInputInfo {
+    id: String,
+    name: String,
+}
+
+impl MidiInputInfo {
+    /// Identifier of the port, to be used with [`MidiInput::open`]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Human readable name of the port
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// List the available MIDI input ports
+pub fn enumerate_inputs() -> Result<Vec<MidiInputInfo>, Box<dyn Error>> {
+    let midi_in = midir::MidiInput::new(CLIENT_NAME)?;
+
+    let inputs = midi_in
+        .ports()
+        .iter()
+        .map(|port| MidiInputInfo {
+            id: port.id(),
+            name: midi_in.port_name(port).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(inputs)
+}
+
+/// Message received from a MIDI input
+#[derive(Clone, Debug)]
+pub struct MidiMessage {
+    data: Vec<u8>,
+    timestamp: u64,
+    received: Instant,
+}
+
+impl MidiMessage {
+    /// The raw bytes of the message, starting with the status byte
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The timestamp in microseconds provided by the MIDI system
+    ///
+    /// Its origin depends on the platform, use a [`MidiClock`] to convert the message to the
+    /// time of an audio context.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// The channel (0 - 15), note number and velocity (in the range `[0, 1]`) of a note on
+    /// message
+    ///
+    /// A note on message with zero velocity is a note off, and returns `None`.
+    pub fn note_on(&self) -> Option<(u8, u8, f32)> {
+        match self.data[..] {
+            [status, note, velocity] if status & 0xF0 == 0x90 && velocity > 0 => {
+                Some((status & 0x0F, note, f32::from(velocity) / 127.))
+            }
+            _ => None,
+        }
+    }
+
+    /// The channel (0 - 15) and note number of a note off message
+    pub fn note_off(&self) -> Option<(u8, u8)> {
+        match self.data[..] {
+            [status, note, _] if status & 0xF0 == 0x80 => Some((status & 0x0F, note)),
+            [status, note, 0] if status & 0xF0 == 0x90 => Some((status & 0x0F, note)),
+            _ => None,
+        }
+    }
+
+    /// The channel (0 - 15), controller number and value (in the range `[0, 1]`) of a control
+    /// change message
+    pub fn control_change(&self) -> Option<(u8, u8, f32)> {
+        match self.data[..] {
+            [status, controller, value] if status & 0xF0 == 0xB0 => {
+                Some((status & 0x0F, controller, f32::from(value) / 127.))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Connection to a MIDI input port
+///
+/// Messages are queued as they arrive and delivered on the thread that reads them, typically
+/// the control thread. The port is closed when the `MidiInput` is dropped.
+pub struct MidiInput {
+    info: MidiInputInfo,
+    receiver: Receiver<MidiMessage>,
+    // keep the connection alive
+    _connection: MidiInputConnection<()>,
+}
+
+impl std::fmt::Debug for MidiInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MidiInput")
+            .field("info", &self.info)
+            .field("pending", &self.receiver.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl MidiInput {
+    /// Open the MIDI input port with the given [`id`](MidiInputInfo::id)
+    pub fn open(id: &str) -> Result<Self, Box<dyn Error>> {
+        let midi_in = midir::MidiInput::new(CLIENT_NAME)?;
+        let port: MidiInputPort = midi_in
+            .find_port_by_id(id.to_string())
+            .ok_or_else(|| format!("NotFoundError - Invalid MIDI input id: {id:?}"))?;
+        let info = MidiInputInfo {
+            id: port.id(),
+            name: midi_in.port_name(&port)?,
+        };
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let connection = midi_in
+            .connect(
+                &port,
+                CLIENT_NAME,
+                move |timestamp, data, _| {
+                    let message = MidiMessage {
+                        data: data.to_vec(),
+                        timestamp,
+                        received: Instant::now(),
+                    };
+                    let _ = sender.send(message);
+                },
+                (),
+            )
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            info,
+            receiver,
+            _connection: connection,
+        })
+    }
+
+    /// Information about the port of this input
+    pub fn info(&self) -> &MidiInputInfo {
+        &self.info
+    }
+
+    /// Wait for the next message
+    pub fn recv(&self) -> Result<MidiMessage, RecvError> {
+        self.receiver.recv()
+    }
+
+    /// Take the next message if one is pending, without blocking
+    pub fn try_recv(&self) -> Option<MidiMessage> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Iterate over the pending messages, without blocking
+    pub fn pending(&self) -> impl Iterator<Item = MidiMessage> + '_ {
+        self.receiver.try_iter()
+    }
+}
+
+/// Converts the arrival time of MIDI messages to the time of an audio context
+///
+/// The current time of a context progresses per rendered block, so it is a coarse measure of
+/// the time of arrival of a message. The clock keeps track of the offset between the wall clock
+/// and the context time, and adds a fixed `latency` so the converted times are in the future of
+/// the render thread and the events are rendered with a stable delay.
+#[derive(Debug)]
+pub struct MidiClock {
+    context: ConcreteBaseAudioContext,
+    latency: f64,
+    origin: Instant,
+    offset: Option<f64>,
+}
+
+impl MidiClock {
+    /// Create a new clock for the given context, with the latency in seconds added to the
+    /// converted times
+    pub fn new<C: BaseAudioContext>(context: &C, latency: f64) -> Self {
+        Self {
+            context: context.base().clone(),
+            latency,
+            origin: Instant::now(),
+            offset: None,
+        }
+    }
+
+    /// The latency in seconds added to the converted times
+    pub fn latency(&self) -> f64 {
+        self.latency
+    }
+
+    /// The time in the audio context at which the given message should be rendered
+    pub fn context_time(&mut self, message: &MidiMessage) -> f64 {
+        self.update();
+        let received = message
+            .received
+            .saturating_duration_since(self.origin)
+            .as_secs_f64();
+        received + self.offset.unwrap_or_default() + self.latency
+    }
+
+    /// Discard the measured offset, e.g. after the context was suspended
+    pub fn resync(&mut self) {
+        self.origin = Instant::now();
+        self.offset = None;
+    }
+
+    fn update(&mut self) {
+        // the current time lags behind the wall clock by up to one block, so the largest
+        // observed offset is the most accurate
+        let offset = self.context.current_time() - self.origin.elapsed().as_secs_f64();
+        self.offset = Some(self.offset.map_or(offset, |o| o.max(offset)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+
+    use super::*;
+
+    fn message(data: Vec<u8>, received: Instant) -> MidiMessage {
+        MidiMessage {
+            data,
+            timestamp: 0,
+            received,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let now = Instant::now();
+
+        let msg = message(vec![0x91, 60, 127], now);
+        assert_eq!(msg.note_on(), Some((1, 60, 1.)));
+        assert_eq!(msg.note_off(), None);
+
+        let msg = message(vec![0x91, 60, 0], now);
+        assert_eq!(msg.note_on(), None);
+        assert_eq!(msg.note_off(), Some((1, 60)));
+
+        let msg = message(vec![0x80, 64, 10], now);
+        assert_eq!(msg.note_off(), Some((0, 64)));
+
+        let msg = message(vec![0xB2, 7, 0], now);
+        assert_eq!(msg.control_change(), Some((2, 7, 0.)));
+        assert_eq!(msg.note_on(), None);
+
+        let msg = message(vec![0xF8], now);
+        assert_eq!(msg.note_on(), None);
+        assert_eq!(msg.note_off(), None);
+        assert_eq!(msg.control_change(), None);
+    }
+
+    #[test]
+    fn test_clock() {
+        // the time of an offline context does not progress before rendering
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let mut clock = MidiClock::new(&context, 0.02);
+
+        let first = message(vec![0x90, 60, 100], Instant::now());
+        let second = message(
+            vec![0x90, 60, 100],
+            first.received + Duration::from_millis(10),
+        );
+
+        let t1 = clock.context_time(&first);
+        let t2 = clock.context_time(&second);
+        assert_float_eq!(t2 - t1, 0.01, abs <= 1e-6);
+        assert_float_eq!(t1, 0.02, abs <= 1e-3);
+    }
+}
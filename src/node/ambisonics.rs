@@ -0,0 +1,917 @@
+use hrtf::{HrtfContext, HrtfProcessor, Vec3};
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+
+use super::{
+    load_hrtf_dataset_processor, AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode,
+    ChannelInterpretation, HrtfDataset,
+};
+
+/// Highest supported ambisonic order
+pub const MAX_AMBISONIC_ORDER: usize = 3;
+
+/// Number of channels of the highest supported ambisonic order
+const MAX_AMBISONIC_CHANNELS: usize = (MAX_AMBISONIC_ORDER + 1) * (MAX_AMBISONIC_ORDER + 1);
+
+/// Assert that the given ambisonic order is supported
+///
+/// # Panics
+///
+/// This function panics if the order is zero or greater than [`MAX_AMBISONIC_ORDER`]
+#[track_caller]
+#[inline(always)]
+fn assert_valid_order(order: usize) {
+    assert!(
+        order > 0 && order <= MAX_AMBISONIC_ORDER,
+        "NotSupportedError - ambisonic order {order} is outside range [1, {MAX_AMBISONIC_ORDER}]"
+    );
+}
+
+/// Number of channels of a signal of the given ambisonic order
+fn number_of_ambisonic_channels(order: usize) -> usize {
+    (order + 1) * (order + 1)
+}
+
+/// Compute the real spherical harmonics up to the given order, in ACN ordering with SN3D
+/// normalization, for a direction given in degrees
+///
+/// The azimuth is counter-clockwise from the front, the elevation is positive upwards.
+fn spherical_harmonics(order: usize, azimuth: f32, elevation: f32, out: &mut [f32]) {
+    let azimuth = f64::from(azimuth).to_radians();
+    let elevation = f64::from(elevation).to_radians();
+    let (x, cos_elevation) = (elevation.sin(), elevation.cos());
+
+    // associated Legendre polynomials P_l^m(sin(elevation)), without Condon-Shortley phase
+    let mut legendre = [[0.; MAX_AMBISONIC_ORDER + 1]; MAX_AMBISONIC_ORDER + 1];
+    let mut diagonal = 1.;
+    for m in 0..=order {
+        legendre[m][m] = diagonal;
+        diagonal *= (2 * m + 1) as f64 * cos_elevation;
+        if m < order {
+            legendre[m + 1][m] = x * (2 * m + 1) as f64 * legendre[m][m];
+        }
+        for l in m + 2..=order {
+            legendre[l][m] = ((2 * l - 1) as f64 * x * legendre[l - 1][m]
+                - (l + m - 1) as f64 * legendre[l - 2][m])
+                / (l - m) as f64;
+        }
+    }
+
+    let factorial = |n: usize| (1..=n).product::<usize>() as f64;
+    for l in 0..=order {
+        for m in 0..=l {
+            let weight = if m == 0 { 1. } else { 2. };
+            let norm = (weight * factorial(l - m) / factorial(l + m)).sqrt();
+            let value = norm * legendre[l][m];
+            let acn = l * l + l;
+            out[acn + m] = (value * (m as f64 * azimuth).cos()) as f32;
+            if m > 0 {
+                out[acn - m] = (value * (m as f64 * azimuth).sin()) as f32;
+            }
+        }
+    }
+}
+
+/// Sampling decoder matrix from SN3D ambisonics to the given directions, `[direction][acn]`
+fn decoder_matrix(order: usize, directions: &[[f32; 2]]) -> Vec<[f32; MAX_AMBISONIC_CHANNELS]> {
+    let scale = 1. / directions.len() as f32;
+
+    directions
+        .iter()
+        .map(|&[azimuth, elevation]| {
+            let mut row = [0.; MAX_AMBISONIC_CHANNELS];
+            spherical_harmonics(order, azimuth, elevation, &mut row);
+            // convert SN3D to N3D, so the decoder is the transpose of the encoder
+            for l in 0..=order {
+                let gain = (2 * l + 1) as f32 * scale;
+                row[l * l..(l + 1) * (l + 1)]
+                    .iter_mut()
+                    .for_each(|v| *v *= gain);
+            }
+            row
+        })
+        .collect()
+}
+
+/// Directions of the virtual speakers used for binaural decoding, as `[azimuth, elevation]`
+///
+/// The layouts are regular polyhedra (spherical designs) so the decoding is uniform over the
+/// sphere: an octahedron for first order, an icosahedron for second order and the vertices of
+/// both an icosahedron and a dodecahedron for third order.
+fn virtual_speakers(order: usize) -> Vec<[f32; 2]> {
+    let phi = (1. + 5_f32.sqrt()) / 2.;
+    let cyclic = |[a, b, c]: [f32; 3]| [[a, b, c], [b, c, a], [c, a, b]];
+    let signs = |[a, b, c]: [f32; 3]| {
+        let mut points = vec![];
+        for sa in [1., -1.] {
+            for sb in [1., -1.] {
+                for sc in [1., -1.] {
+                    let point = [sa * a, sb * b, sc * c];
+                    if !points.contains(&point) {
+                        points.push(point);
+                    }
+                }
+            }
+        }
+        points
+    };
+
+    let octahedron = cyclic([1., 0., 0.]).into_iter().flat_map(signs);
+    let icosahedron = cyclic([0., 1., phi]).into_iter().flat_map(signs);
+    let dodecahedron = signs([1., 1., 1.])
+        .into_iter()
+        .chain(cyclic([0., 1. / phi, phi]).into_iter().flat_map(signs));
+
+    let points: Vec<[f32; 3]> = match order {
+        1 => octahedron.collect(),
+        2 => icosahedron.collect(),
+        _ => icosahedron.chain(dodecahedron).collect(),
+    };
+
+    // x front, y left, z up
+    points
+        .into_iter()
+        .map(|[x, y, z]| {
+            let azimuth = y.atan2(x).to_degrees();
+            let elevation = z.atan2(x.hypot(y)).to_degrees();
+            [azimuth, elevation]
+        })
+        .collect()
+}
+
+/// Options for constructing an [`AmbisonicEncoderNode`]
+#[derive(Clone, Debug)]
+pub struct AmbisonicEncoderOptions {
+    /// The ambisonic order of the output, in the range `[1, 3]`
+    pub order: usize,
+    /// Initial azimuth of the source in degrees, counter-clockwise from the front
+    pub azimuth: f32,
+    /// Initial elevation of the source in degrees, positive upwards
+    pub elevation: f32,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for AmbisonicEncoderOptions {
+    fn default() -> Self {
+        Self {
+            order: 1,
+            azimuth: 0.,
+            elevation: 0.,
+            audio_node_options: AudioNodeOptions {
+                channel_count: 1,
+                channel_count_mode: ChannelCountMode::Explicit,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// Assert that the channel count is valid for the AmbisonicEncoderNode
+///
+/// # Panics
+///
+/// This function panics if given count is not 1
+#[track_caller]
+#[inline(always)]
+fn assert_valid_encoder_channel_count(count: usize) {
+    assert!(
+        count == 1,
+        "NotSupportedError - AmbisonicEncoderNode channel count must be one"
+    );
+}
+
+/// Assert that the channel count mode is valid for the AmbisonicEncoderNode
+///
+/// # Panics
+///
+/// This function panics if given count mode is [`ChannelCountMode::Max`]
+#[track_caller]
+#[inline(always)]
+fn assert_valid_encoder_channel_count_mode(mode: ChannelCountMode) {
+    assert_ne!(
+        mode,
+        ChannelCountMode::Max,
+        "NotSupportedError - AmbisonicEncoderNode channel count mode cannot be set to max",
+    );
+}
+
+/// `AmbisonicEncoderNode` encodes a mono input into an ambisonic sound field
+///
+/// The output has `(order + 1)²` channels in the AmbiX format, i.e. ACN channel ordering and
+/// SN3D normalization. Several encoded sources can be summed into a single sound field, which
+/// can be transformed and is rendered by an [`AmbisonicDecoderNode`].
+///
+/// The direction of the source is given by the `azimuth` (counter-clockwise from the front) and
+/// `elevation` (positive upwards) params, in degrees.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{
+///     AmbisonicDecoderNode, AmbisonicDecoderOptions, AmbisonicEncoderNode,
+///     AmbisonicEncoderOptions, AudioNode, AudioScheduledSourceNode,
+/// };
+///
+/// let context = AudioContext::default();
+///
+/// let decoder = AmbisonicDecoderNode::new(&context, AmbisonicDecoderOptions::default());
+/// decoder.connect(&context.destination());
+///
+/// let encoder = AmbisonicEncoderNode::new(&context, AmbisonicEncoderOptions::default());
+/// encoder.connect(&decoder);
+///
+/// // circle around the listener
+/// let now = context.current_time();
+/// encoder.azimuth().set_value_at_time(0., now);
+/// encoder.azimuth().linear_ramp_to_value_at_time(360., now + 4.);
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&encoder);
+/// osc.start();
+/// ```
+#[derive(Debug)]
+pub struct AmbisonicEncoderNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    order: usize,
+    azimuth: AudioParam,
+    elevation: AudioParam,
+}
+
+impl AudioNode for AmbisonicEncoderNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_encoder_channel_count_mode(mode);
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_encoder_channel_count(count);
+        self.channel_config.set_count(count, self.registration());
+    }
+}
+
+impl AmbisonicEncoderNode {
+    /// Create a new `AmbisonicEncoderNode`
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// * `options.order` is zero or greater than [`MAX_AMBISONIC_ORDER`]
+    /// * `options.audio_node_options.channel_count` is not 1
+    /// * `options.audio_node_options.channel_count_mode` is `ChannelCountMode::Max`
+    pub fn new<C: BaseAudioContext>(context: &C, options: AmbisonicEncoderOptions) -> Self {
+        assert_valid_order(options.order);
+        assert_valid_encoder_channel_count(options.audio_node_options.channel_count);
+        assert_valid_encoder_channel_count_mode(options.audio_node_options.channel_count_mode);
+
+        context.base().register(move |registration| {
+            let azimuth_descriptor = AudioParamDescriptor {
+                name: String::from("azimuth"),
+                min_value: f32::MIN,
+                max_value: f32::MAX,
+                default_value: 0.,
+                automation_rate: AutomationRate::A,
+            };
+            let (azimuth_param, azimuth_proc) =
+                context.create_audio_param(azimuth_descriptor, &registration);
+            azimuth_param.set_value(options.azimuth);
+
+            let elevation_descriptor = AudioParamDescriptor {
+                name: String::from("elevation"),
+                min_value: -90.,
+                max_value: 90.,
+                default_value: 0.,
+                automation_rate: AutomationRate::A,
+            };
+            let (elevation_param, elevation_proc) =
+                context.create_audio_param(elevation_descriptor, &registration);
+            elevation_param.set_value(options.elevation);
+
+            let render = AmbisonicEncoderRenderer {
+                order: options.order,
+                azimuth: azimuth_proc,
+                elevation: elevation_proc,
+                gains: Box::new([[0.; MAX_AMBISONIC_CHANNELS]; RENDER_QUANTUM_SIZE]),
+            };
+
+            let node = Self {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                order: options.order,
+                azimuth: azimuth_param,
+                elevation: elevation_param,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The ambisonic order of the output
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// A-rate [`AudioParam`] representing the azimuth of the source in degrees,
+    /// counter-clockwise from the front
+    #[must_use]
+    pub fn azimuth(&self) -> &AudioParam {
+        &self.azimuth
+    }
+
+    /// A-rate [`AudioParam`] representing the elevation of the source in degrees, in the
+    /// [-90, 90] range
+    #[must_use]
+    pub fn elevation(&self) -> &AudioParam {
+        &self.elevation
+    }
+}
+
+struct AmbisonicEncoderRenderer {
+    order: usize,
+    azimuth: AudioParamId,
+    elevation: AudioParamId,
+    /// gains per frame and channel, when the params are automated
+    gains: Box<[[f32; MAX_AMBISONIC_CHANNELS]; RENDER_QUANTUM_SIZE]>,
+}
+
+impl AudioProcessor for AmbisonicEncoderRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        let number_of_channels = number_of_ambisonic_channels(self.order);
+        *output = input.clone();
+        output.set_number_of_channels(number_of_channels);
+
+        let azimuth = params.get(&self.azimuth);
+        let elevation = params.get(&self.elevation);
+
+        if azimuth.len() == 1 && elevation.len() == 1 {
+            let mut gains = [0.; MAX_AMBISONIC_CHANNELS];
+            spherical_harmonics(self.order, azimuth[0], elevation[0], &mut gains);
+
+            // the input was copied to channel 0, which has unit gain
+            let source = output.channel_data(0).clone();
+            for (channel, &gain) in gains.iter().enumerate().take(number_of_channels).skip(1) {
+                output
+                    .channel_data_mut(channel)
+                    .iter_mut()
+                    .zip(source.iter())
+                    .for_each(|(o, i)| *o = gain * i);
+            }
+        } else {
+            let azimuth = azimuth.iter().cycle();
+            let elevation = elevation.iter().cycle();
+            self.gains.iter_mut().zip(azimuth.zip(elevation)).for_each(
+                |(gains, (&azimuth, &elevation))| {
+                    spherical_harmonics(self.order, azimuth, elevation, gains);
+                },
+            );
+
+            let source = output.channel_data(0).clone();
+            for channel in 1..number_of_channels {
+                output
+                    .channel_data_mut(channel)
+                    .iter_mut()
+                    .zip(source.iter())
+                    .zip(self.gains.iter())
+                    .for_each(|((o, i), gains)| *o = gains[channel] * i);
+            }
+        }
+
+        false
+    }
+}
+
+/// Rendering of the sound field by an [`AmbisonicDecoderNode`]
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum AmbisonicDecoderOutput {
+    /// Stereo output for headphones, rendered with head-related transfer functions
+    #[default]
+    Binaural,
+    /// One output channel per speaker, given as `[azimuth, elevation]` in degrees
+    ///
+    /// The azimuth is counter-clockwise from the front, the elevation is positive upwards. The
+    /// decoder assumes the speakers are evenly spread around the listener.
+    Speakers(Vec<[f32; 2]>),
+}
+
+/// Options for constructing an [`AmbisonicDecoderNode`]
+///
+/// The channel count of the node is the number of channels of the ambisonic order, with the
+/// `explicit` channel count mode and `discrete` channel interpretation.
+#[derive(Clone, Debug)]
+pub struct AmbisonicDecoderOptions {
+    /// The ambisonic order of the input, in the range `[1, 3]`
+    pub order: usize,
+    /// The rendering of the sound field
+    pub output: AmbisonicDecoderOutput,
+    /// The HRTF dataset used for binaural rendering
+    pub hrtf_dataset: HrtfDataset,
+}
+
+impl Default for AmbisonicDecoderOptions {
+    fn default() -> Self {
+        Self {
+            order: 1,
+            output: AmbisonicDecoderOutput::default(),
+            hrtf_dataset: HrtfDataset::default(),
+        }
+    }
+}
+
+/// `AmbisonicDecoderNode` renders an ambisonic sound field to headphones or a speaker array
+///
+/// The input is expected in the AmbiX format (ACN channel ordering and SN3D normalization), as
+/// produced by the [`AmbisonicEncoderNode`]. The sound field is decoded with a sampling decoder
+/// to the given speakers, or to a regular array of virtual speakers which are rendered with
+/// head-related transfer functions for binaural output.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{
+///     AmbisonicDecoderNode, AmbisonicDecoderOptions, AmbisonicDecoderOutput, AudioNode,
+/// };
+///
+/// let context = AudioContext::default();
+///
+/// // quadraphonic speaker array
+/// let options = AmbisonicDecoderOptions {
+///     output: AmbisonicDecoderOutput::Speakers(vec![
+///         [45., 0.],
+///         [-45., 0.],
+///         [135., 0.],
+///         [-135., 0.],
+///     ]),
+///     ..AmbisonicDecoderOptions::default()
+/// };
+/// let decoder = AmbisonicDecoderNode::new(&context, options);
+/// decoder.connect(&context.destination());
+/// ```
+#[derive(Debug)]
+pub struct AmbisonicDecoderNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    order: usize,
+    output: AmbisonicDecoderOutput,
+}
+
+/// Assert that the channel count is valid for the AmbisonicDecoderNode
+///
+/// # Panics
+///
+/// This function panics if given count does not match the ambisonic order
+#[track_caller]
+#[inline(always)]
+fn assert_valid_decoder_channel_count(count: usize, order: usize) {
+    assert!(
+        count == number_of_ambisonic_channels(order),
+        "InvalidStateError - channel count of AmbisonicDecoderNode must match its order"
+    );
+}
+
+/// Assert that the channel count mode is valid for the AmbisonicDecoderNode
+///
+/// # Panics
+///
+/// This function panics if given count mode is not [`ChannelCountMode::Explicit`]
+#[track_caller]
+#[inline(always)]
+fn assert_valid_decoder_channel_count_mode(mode: ChannelCountMode) {
+    assert!(
+        mode == ChannelCountMode::Explicit,
+        "InvalidStateError - channel count mode of AmbisonicDecoderNode must be set to Explicit"
+    );
+}
+
+/// Assert that the channel interpretation is valid for the AmbisonicDecoderNode
+///
+/// # Panics
+///
+/// This function panics if given interpretation is not [`ChannelInterpretation::Discrete`]
+#[track_caller]
+#[inline(always)]
+fn assert_valid_decoder_channel_interpretation(interpretation: ChannelInterpretation) {
+    assert!(
+        interpretation == ChannelInterpretation::Discrete,
+        "InvalidStateError - channel interpretation of AmbisonicDecoderNode must be set to Discrete"
+    );
+}
+
+impl AudioNode for AmbisonicDecoderNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_decoder_channel_count(count, self.order);
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_decoder_channel_count_mode(mode);
+    }
+
+    fn set_channel_interpretation(&self, interpretation: ChannelInterpretation) {
+        assert_valid_decoder_channel_interpretation(interpretation);
+    }
+}
+
+impl AmbisonicDecoderNode {
+    /// Create a new `AmbisonicDecoderNode`
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// * `options.order` is zero or greater than [`MAX_AMBISONIC_ORDER`]
+    /// * the number of speakers is zero or greater than [`MAX_CHANNELS`]
+    pub fn new<C: BaseAudioContext>(context: &C, options: AmbisonicDecoderOptions) -> Self {
+        let AmbisonicDecoderOptions {
+            order,
+            output,
+            hrtf_dataset,
+        } = options;
+        assert_valid_order(order);
+
+        let renderer = match &output {
+            AmbisonicDecoderOutput::Binaural => {
+                let sample_rate = context.sample_rate() as u32;
+                let (processor, len) = load_hrtf_dataset_processor(&hrtf_dataset, sample_rate);
+                let speakers = virtual_speakers(order);
+                let decoder = decoder_matrix(order, &speakers);
+                let virtual_speakers = speakers
+                    .iter()
+                    .map(|&[azimuth, elevation]| {
+                        let (azimuth, elevation) = (azimuth.to_radians(), elevation.to_radians());
+                        // the hrtf crate has x pointing right, y up and z front
+                        let direction = Vec3 {
+                            x: -azimuth.sin() * elevation.cos(),
+                            y: elevation.sin(),
+                            z: azimuth.cos() * elevation.cos(),
+                        };
+                        VirtualSpeaker {
+                            direction,
+                            prev_left_samples: vec![],
+                            prev_right_samples: vec![],
+                        }
+                    })
+                    .collect();
+
+                AmbisonicDecoderRenderer {
+                    order,
+                    decoder,
+                    binaural: Some(BinauralState {
+                        processor,
+                        tail_time: len,
+                        virtual_speakers,
+                        feed: [0.; RENDER_QUANTUM_SIZE],
+                        output_interleaved: vec![(0., 0.); RENDER_QUANTUM_SIZE],
+                    }),
+                    tail_time_counter: 0,
+                }
+            }
+            AmbisonicDecoderOutput::Speakers(speakers) => {
+                assert!(
+                    !speakers.is_empty() && speakers.len() <= MAX_CHANNELS,
+                    "NotSupportedError - number of speakers {} is outside range [1, {}]",
+                    speakers.len(),
+                    MAX_CHANNELS
+                );
+
+                AmbisonicDecoderRenderer {
+                    order,
+                    decoder: decoder_matrix(order, speakers),
+                    binaural: None,
+                    tail_time_counter: 0,
+                }
+            }
+        };
+
+        context.base().register(move |registration| {
+            let audio_node_options = AudioNodeOptions {
+                channel_count: number_of_ambisonic_channels(order),
+                channel_count_mode: ChannelCountMode::Explicit,
+                channel_interpretation: ChannelInterpretation::Discrete,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                order,
+                output,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// The ambisonic order of the input
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// The rendering of the sound field
+    pub fn output(&self) -> &AmbisonicDecoderOutput {
+        &self.output
+    }
+}
+
+struct VirtualSpeaker {
+    direction: Vec3,
+    prev_left_samples: Vec<f32>,
+    prev_right_samples: Vec<f32>,
+}
+
+struct BinauralState {
+    processor: HrtfProcessor,
+    /// length of the impulse responses
+    tail_time: usize,
+    virtual_speakers: Vec<VirtualSpeaker>,
+    /// signal of the virtual speaker being rendered
+    feed: [f32; RENDER_QUANTUM_SIZE],
+    output_interleaved: Vec<(f32, f32)>,
+}
+
+struct AmbisonicDecoderRenderer {
+    order: usize,
+    /// decoder gains per speaker and ambisonic channel
+    decoder: Vec<[f32; MAX_AMBISONIC_CHANNELS]>,
+    binaural: Option<BinauralState>,
+    tail_time_counter: usize,
+}
+
+impl AmbisonicDecoderRenderer {
+    /// Decode the input for the given speaker
+    fn decode(&self, input: &AudioRenderQuantum, speaker: usize, out: &mut [f32]) {
+        out.fill(0.);
+        let number_of_channels =
+            number_of_ambisonic_channels(self.order).min(input.number_of_channels());
+        let gains = &self.decoder[speaker];
+
+        for (channel, &gain) in gains.iter().enumerate().take(number_of_channels) {
+            out.iter_mut()
+                .zip(input.channel_data(channel).iter())
+                .for_each(|(o, i)| *o += gain * i);
+        }
+    }
+}
+
+impl AudioProcessor for AmbisonicDecoderRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() {
+            let tail_time = self.binaural.as_ref().map_or(0, |b| b.tail_time);
+            if self.tail_time_counter >= tail_time {
+                output.make_silent();
+                return false;
+            }
+            self.tail_time_counter += RENDER_QUANTUM_SIZE;
+        } else {
+            self.tail_time_counter = 0;
+        }
+
+        // for borrow reasons, take the binaural state out of self
+        let Some(mut binaural) = self.binaural.take() else {
+            output.set_number_of_channels(self.decoder.len());
+            for speaker in 0..self.decoder.len() {
+                self.decode(input, speaker, output.channel_data_mut(speaker));
+            }
+            return false;
+        };
+
+        binaural.output_interleaved.fill((0., 0.));
+        for (speaker, virtual_speaker) in binaural.virtual_speakers.iter_mut().enumerate() {
+            self.decode(input, speaker, &mut binaural.feed);
+
+            // the output of all virtual speakers is accumulated
+            let context = HrtfContext {
+                source: &binaural.feed,
+                output: &mut binaural.output_interleaved,
+                new_sample_vector: virtual_speaker.direction,
+                prev_sample_vector: virtual_speaker.direction,
+                prev_left_samples: &mut virtual_speaker.prev_left_samples,
+                prev_right_samples: &mut virtual_speaker.prev_right_samples,
+                new_distance_gain: 1.,
+                prev_distance_gain: 1.,
+            };
+            binaural.processor.process_samples(context);
+        }
+
+        output.set_number_of_channels(2);
+        let [left, right] = output.stereo_mut();
+        binaural
+            .output_interleaved
+            .iter()
+            .zip(left.iter_mut())
+            .zip(right.iter_mut())
+            .for_each(|((&(l, r), left), right)| {
+                *left = l;
+                *right = r;
+            });
+
+        self.binaural = Some(binaural);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_spherical_harmonics() {
+        let mut out = [0.; MAX_AMBISONIC_CHANNELS];
+
+        // W, Y, Z, X for a source on the left
+        spherical_harmonics(1, 90., 0., &mut out);
+        assert_float_eq!(out[..4], [1., 1., 0., 0.][..], abs_all <= 1e-6);
+
+        // the SN3D components of each order have unit energy
+        spherical_harmonics(3, 37., 21., &mut out);
+        for l in 0..=3 {
+            let energy: f32 = out[l * l..(l + 1) * (l + 1)].iter().map(|v| v * v).sum();
+            assert_float_eq!(energy, 1., abs <= 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_virtual_speakers() {
+        assert_eq!(virtual_speakers(1).len(), 6);
+        assert_eq!(virtual_speakers(2).len(), 12);
+        assert_eq!(virtual_speakers(3).len(), 32);
+
+        // a sampling decoder on a spherical design preserves the amplitude
+        for order in 1..=MAX_AMBISONIC_ORDER {
+            let decoder = decoder_matrix(order, &virtual_speakers(order));
+            let mut encoded = [0.; MAX_AMBISONIC_CHANNELS];
+            spherical_harmonics(order, 12., 34., &mut encoded);
+            let sum: f32 = decoder
+                .iter()
+                .map(|row| row.iter().zip(encoded).map(|(d, e)| d * e).sum::<f32>())
+                .sum();
+            assert_float_eq!(sum, 1., abs <= 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_encoder() {
+        let mut context = OfflineAudioContext::new(4, 128, 48_000.);
+
+        let options = AmbisonicEncoderOptions {
+            azimuth: 90.,
+            ..AmbisonicEncoderOptions::default()
+        };
+        let encoder = AmbisonicEncoderNode::new(&context, options);
+        encoder.connect(&context.destination());
+        assert_eq!(encoder.order(), 1);
+
+        let mut src = context.create_constant_source();
+        src.connect(&encoder);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        for (channel, expected) in [1., 1., 0., 0.].into_iter().enumerate() {
+            assert_float_eq!(
+                output.get_channel_data(channel)[..],
+                [expected; 128][..],
+                abs_all <= 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn test_decoder_speakers() {
+        let mut context = OfflineAudioContext::new(4, 128, 48_000.);
+
+        let options = AmbisonicDecoderOptions {
+            output: AmbisonicDecoderOutput::Speakers(vec![
+                [0., 0.],
+                [90., 0.],
+                [180., 0.],
+                [-90., 0.],
+            ]),
+            ..AmbisonicDecoderOptions::default()
+        };
+        let decoder = AmbisonicDecoderNode::new(&context, options);
+        decoder.connect(&context.destination());
+        assert_eq!(decoder.channel_count(), 4);
+
+        let encoder = AmbisonicEncoderNode::new(&context, AmbisonicEncoderOptions::default());
+        encoder.connect(&decoder);
+
+        let mut src = context.create_constant_source();
+        src.connect(&encoder);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        for (channel, expected) in [1., 0.25, -0.5, 0.25].into_iter().enumerate() {
+            assert_float_eq!(
+                output.get_channel_data(channel)[..],
+                [expected; 128][..],
+                abs_all <= 1e-6
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_decoder_invalid_channel_count() {
+        let context = OfflineAudioContext::new(2, 128, 48_000.);
+        let decoder = AmbisonicDecoderNode::new(&context, AmbisonicDecoderOptions::default());
+        decoder.set_channel_count(2);
+    }
+
+    #[test]
+    fn test_decoder_binaural() {
+        let length = 4096;
+        let mut context = OfflineAudioContext::new(2, length, 44_100.);
+
+        let decoder = AmbisonicDecoderNode::new(&context, AmbisonicDecoderOptions::default());
+        decoder.connect(&context.destination());
+
+        // source on the left
+        let options = AmbisonicEncoderOptions {
+            azimuth: 90.,
+            ..AmbisonicEncoderOptions::default()
+        };
+        let encoder = AmbisonicEncoderNode::new(&context, options);
+        encoder.connect(&decoder);
+
+        let mut src = context.create_oscillator();
+        src.connect(&encoder);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        let energy = |channel: usize| -> f32 {
+            output.get_channel_data(channel).iter().map(|v| v * v).sum()
+        };
+        assert!(energy(0) > 2. * energy(1));
+    }
+}
@@ -1,14 +1,37 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use realfft::{num_complex::Complex, RealToComplex};
+
+pub use crate::analysis::WindowFunction;
 use crate::analysis::{
-    Analyser, AnalyserRingBuffer, DEFAULT_FFT_SIZE, DEFAULT_MAX_DECIBELS, DEFAULT_MIN_DECIBELS,
-    DEFAULT_SMOOTHING_TIME_CONSTANT,
+    fft_planner, Analyser, AnalyserRingBuffer, DEFAULT_FFT_SIZE, DEFAULT_MAX_DECIBELS,
+    DEFAULT_MIN_DECIBELS, DEFAULT_SMOOTHING_TIME_CONSTANT,
 };
 use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::events::{EventHandler, EventPayload, EventType, SpectrogramEvent};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelInterpretation};
 
+/// Duration in seconds of the spectrogram frames that can be pending on the control thread
+/// before frames are dropped
+const SPECTROGRAM_BUFFERED_DURATION: f32 = 0.2;
+/// Minimum number of recycled spectrogram buffers
+const MIN_SPECTROGRAM_BUFFERS: usize = 4;
+
+#[track_caller]
+#[inline(always)]
+fn assert_valid_hop_size(hop_size: usize) {
+    assert!(
+        hop_size > 0,
+        "IndexSizeError - Invalid hop size: {:?} should be strictly positive",
+        hop_size
+    );
+}
+
 /// Options for constructing an [`AnalyserNode`]
 // dictionary AnalyserOptions : AudioNodeOptions {
 //   unsigned long fftSize = 2048;
@@ -22,6 +45,10 @@ pub struct AnalyserOptions {
     pub max_decibels: f64,
     pub min_decibels: f64,
     pub smoothing_time_constant: f64,
+    /// Window applied before the FFT, the Blackman window of the specification by default
+    pub window_function: WindowFunction,
+    /// Zero-padding factor of the FFT, 1 (no padding) by default
+    pub zero_padding: usize,
     pub audio_node_options: AudioNodeOptions,
 }
 
@@ -32,6 +59,8 @@ impl Default for AnalyserOptions {
             max_decibels: DEFAULT_MAX_DECIBELS,
             min_decibels: DEFAULT_MIN_DECIBELS,
             smoothing_time_constant: DEFAULT_SMOOTHING_TIME_CONSTANT,
+            window_function: WindowFunction::default(),
+            zero_padding: 1,
             audio_node_options: AudioNodeOptions::default(),
         }
     }
@@ -116,9 +145,12 @@ impl AnalyserNode {
             analyser.set_fft_size(fft_size);
             analyser.set_smoothing_time_constant(smoothing_time_constant);
             analyser.set_decibels(min_decibels, max_decibels);
+            analyser.set_window_function(options.window_function);
+            analyser.set_zero_padding(options.zero_padding);
 
             let render = AnalyserRenderer {
                 ring_buffer: analyser.get_ring_buffer_clone(),
+                spectrogram: None,
             };
 
             let node = AnalyserNode {
@@ -142,11 +174,42 @@ impl AnalyserNode {
 
     /// Set FFT size
     ///
+    /// Sizes above 32768, which allow for a finer frequency resolution e.g. for room
+    /// measurements, are not part of the Web Audio API specification. The buffers of the node
+    /// are grown on demand for these sizes.
+    ///
     /// # Panics
     ///
-    /// This function panics if fft_size is not a power of two or not in the range [32, 32768]
+    /// This function panics if fft_size is not a power of two or not in the range [32, 131072]
     pub fn set_fft_size(&mut self, fft_size: usize) {
+        let capacity = self.analyser.get_ring_buffer_clone().capacity();
         self.analyser.set_fft_size(fft_size);
+
+        let ring_buffer = self.analyser.get_ring_buffer_clone();
+        if ring_buffer.capacity() != capacity {
+            self.registration.post_message(ring_buffer);
+        }
+    }
+
+    /// Zero-padding factor of the FFT
+    pub fn zero_padding(&self) -> usize {
+        self.analyser.zero_padding()
+    }
+
+    /// Set the zero-padding factor of the FFT
+    ///
+    /// The windowed time domain data is padded with zeros up to `fft_size * zero_padding`
+    /// frames before the FFT, which interpolates the frequency data: the
+    /// [`frequency_bin_count`](Self::frequency_bin_count) is multiplied by the factor, while the
+    /// actual resolution is still given by the fft size.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the factor is not a power of two in the range [1, 8]
+    pub fn set_zero_padding(&mut self, zero_padding: usize) {
+        self.analyser.set_zero_padding(zero_padding);
     }
 
     /// Time averaging parameter with the last analysis frame.
@@ -209,7 +272,22 @@ impl AnalyserNode {
         self.analyser.set_decibels(self.min_decibels(), value);
     }
 
-    /// Number of bins in the FFT results, is half the FFT size
+    /// The window function applied to the time domain data before the FFT
+    pub fn window_function(&self) -> WindowFunction {
+        self.analyser.window_function()
+    }
+
+    /// Set the window function applied to the time domain data before the FFT
+    ///
+    /// Use the correction factors of the [`WindowFunction`] to derive calibrated amplitude or
+    /// energy measurements from the frequency data.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn set_window_function(&mut self, value: WindowFunction) {
+        self.analyser.set_window_function(value);
+    }
+
+    /// Number of bins in the FFT results, is half the FFT size (times the zero-padding factor)
     ///
     /// # Panics
     ///
@@ -256,10 +334,236 @@ impl AnalyserNode {
         let current_time = self.registration.context().current_time();
         self.analyser.get_byte_frequency_data(buffer, current_time);
     }
+
+    /// Copy the current frequency data, aggregated into `bands` log-spaced frequency bands
+    /// between `min_hz` and `max_hz`, and scaled between min_decibels and max_decibels, into
+    /// the provided buffer
+    ///
+    /// Each band holds the mean power of the FFT bins it contains, or the magnitude
+    /// interpolated at its center frequency when it is narrower than a bin. E.g. 30 bands
+    /// spanning 10 octaves give third-octave bands. If the buffer is shorter than `bands`, the
+    /// excess bands are dropped.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `bands` is zero, or if `min_hz` is not strictly positive or not
+    /// smaller than `max_hz`
+    pub fn get_byte_frequency_bands(
+        &mut self,
+        buffer: &mut [u8],
+        bands: usize,
+        min_hz: f32,
+        max_hz: f32,
+    ) {
+        let context = self.registration.context();
+        let current_time = context.current_time();
+        let sample_rate = context.sample_rate();
+        self.analyser.get_byte_frequency_bands(
+            buffer,
+            bands,
+            min_hz,
+            max_hz,
+            sample_rate,
+            current_time,
+        );
+    }
+
+    /// Register callback to run for each successive frame of frequency data, computed every
+    /// `hop_size` sample-frames
+    ///
+    /// Unlike with the polling methods, e.g.
+    /// [`get_float_frequency_data`](Self::get_float_frequency_data), no frame is missed or
+    /// received twice. Each [`SpectrogramEvent`] holds the magnitudes in
+    /// dB of the [`frequency_bin_count`](Self::frequency_bin_count) bins of a frame, without
+    /// time averaging. The frames are computed on the render thread with the fft size, window
+    /// function and zero-padding factor of the node at the time of the subscription.
+    ///
+    /// The buffers holding the magnitudes are preallocated and shipped back to the render thread
+    /// when the event goes out of scope, so be sure not to store them somewhere. If the control
+    /// thread does not keep up, frames are dropped and counted in
+    /// [`SpectrogramEvent::dropped_frames`].
+    ///
+    /// Only a single subscription is active at any time. Calling this method multiple times will
+    /// override the previous subscription.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the hop size is zero
+    pub fn subscribe_spectrogram<F: FnMut(SpectrogramEvent) + Send + 'static>(
+        &self,
+        hop_size: usize,
+        mut callback: F,
+    ) {
+        assert_valid_hop_size(hop_size);
+
+        let base = self.registration().context().clone();
+        let id = self.registration().id();
+
+        let callback = move |v| {
+            let mut payload = match v {
+                EventPayload::Spectrogram(v) => v,
+                _ => unreachable!(),
+            };
+            payload.registration = Some((base.clone(), id));
+            callback(payload);
+        };
+
+        self.context().set_event_handler(
+            EventType::Spectrogram(self.registration().id()),
+            EventHandler::Multiple(Box::new(callback)),
+        );
+
+        let spectrogram = Spectrogram::new(
+            self.fft_size(),
+            self.zero_padding(),
+            self.window_function(),
+            hop_size,
+            self.context().sample_rate(),
+        );
+        self.registration.post_message(Some(spectrogram));
+    }
+
+    /// Stop the spectrogram frames and unset the callback registered with
+    /// [`subscribe_spectrogram`](Self::subscribe_spectrogram)
+    pub fn unsubscribe_spectrogram(&self) {
+        self.registration.post_message(None::<Spectrogram>);
+        self.context()
+            .clear_event_handler(EventType::Spectrogram(self.registration().id()));
+    }
+}
+
+/// Render side state of a spectrogram subscription
+struct Spectrogram {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    normalize_factor: f32,
+    /// windowed and zero-padded input of the FFT
+    frame: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    ring_buffer: Vec<f32>,
+    write_index: usize,
+    hop_size: usize,
+    samples_until_hop: usize,
+    /// recycled buffers of `frequency_bin_count` values
+    buffers: Vec<Vec<f32>>,
+    frequency_bin_count: usize,
+    dropped_frames: usize,
+}
+
+impl Spectrogram {
+    fn new(
+        fft_size: usize,
+        zero_padding: usize,
+        window_function: WindowFunction,
+        hop_size: usize,
+        sample_rate: f32,
+    ) -> Self {
+        let padded_size = fft_size * zero_padding;
+        let fft = fft_planner().lock().unwrap().plan_fft_forward(padded_size);
+        let frequency_bin_count = padded_size / 2;
+
+        let buffered_frames = SPECTROGRAM_BUFFERED_DURATION * sample_rate / hop_size as f32;
+        let count = (buffered_frames.ceil() as usize).max(MIN_SPECTROGRAM_BUFFERS);
+        let mut buffers = Vec::with_capacity(count);
+        buffers.extend((0..count).map(|_| vec![0.; frequency_bin_count]));
+
+        Self {
+            frame: fft.make_input_vec(),
+            spectrum: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            fft,
+            window: window_function.generate(fft_size).collect(),
+            normalize_factor: 1. / fft_size as f32,
+            ring_buffer: vec![0.; fft_size],
+            write_index: 0,
+            hop_size,
+            samples_until_hop: hop_size,
+            buffers,
+            frequency_bin_count,
+            dropped_frames: 0,
+        }
+    }
+
+    /// Compute the magnitudes in dB of the frame ending at the current write index
+    fn compute(&mut self, magnitudes: &mut [f32]) {
+        // unroll the ring buffer, oldest sample first, and zero-pad
+        let fft_size = self.ring_buffer.len();
+        let (input, padding) = self.frame.split_at_mut(fft_size);
+        let (newest, oldest) = self.ring_buffer.split_at(self.write_index);
+        let (head, tail) = input.split_at_mut(oldest.len());
+        head.copy_from_slice(oldest);
+        tail.copy_from_slice(newest);
+        padding.fill(0.);
+
+        input
+            .iter_mut()
+            .zip(self.window.iter())
+            .for_each(|(s, w)| *s *= *w);
+
+        self.fft
+            .process_with_scratch(&mut self.frame, &mut self.spectrum, &mut self.scratch)
+            .unwrap();
+
+        // same scaling as the frequency data of the analyser, the Nyquist bin is ignored
+        let normalize_factor = self.normalize_factor;
+        magnitudes
+            .iter_mut()
+            .zip(self.spectrum.iter())
+            .for_each(|(m, c)| *m = 20. * (c.norm() * normalize_factor).log10());
+    }
+
+    fn process(&mut self, input: &[f32], scope: &AudioWorkletGlobalScope) {
+        let sample_rate = f64::from(scope.sample_rate);
+
+        for (index, &sample) in input.iter().enumerate() {
+            self.ring_buffer[self.write_index] = sample;
+            self.write_index = (self.write_index + 1) % self.ring_buffer.len();
+
+            self.samples_until_hop -= 1;
+            if self.samples_until_hop > 0 {
+                continue;
+            }
+            self.samples_until_hop = self.hop_size;
+
+            let mut magnitudes = match self.buffers.pop() {
+                Some(buffer) => buffer,
+                None => {
+                    // all buffers are pending on the control thread
+                    self.dropped_frames += 1;
+                    continue;
+                }
+            };
+            self.compute(&mut magnitudes);
+
+            let time = scope.current_time + (index + 1) as f64 / sample_rate;
+            match scope.send_spectrogram_event(time, magnitudes, self.dropped_frames) {
+                Ok(()) => self.dropped_frames = 0,
+                Err(buffer) => {
+                    self.buffers.push(buffer);
+                    self.dropped_frames += 1;
+                }
+            }
+        }
+    }
+
+    /// Take back a buffer shipped to the control thread
+    fn recycle(&mut self, buffer: &mut Vec<f32>) {
+        // buffers of a previous subscription are left to the garbage collector
+        if buffer.len() == self.frequency_bin_count && self.buffers.len() < self.buffers.capacity()
+        {
+            self.buffers.push(std::mem::take(buffer));
+        }
+    }
 }
 
 struct AnalyserRenderer {
     ring_buffer: AnalyserRingBuffer,
+    spectrogram: Option<Spectrogram>,
 }
 
 impl AudioProcessor for AnalyserRenderer {
@@ -268,7 +572,7 @@ impl AudioProcessor for AnalyserRenderer {
         inputs: &[AudioRenderQuantum],
         outputs: &mut [AudioRenderQuantum],
         _params: AudioParamValues<'_>,
-        _scope: &AudioWorkletGlobalScope,
+        scope: &AudioWorkletGlobalScope,
     ) -> bool {
         // single input/output node
         let input = &inputs[0];
@@ -285,13 +589,44 @@ impl AudioProcessor for AnalyserRenderer {
         let data = mono.channel_data(0).as_ref();
         self.ring_buffer.write(data);
 
+        if let Some(spectrogram) = self.spectrogram.as_mut() {
+            spectrogram.process(data, scope);
+        }
+
         // no tail-time
         false
     }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(ring_buffer) = msg.downcast_mut::<AnalyserRingBuffer>() {
+            // the ring buffer has grown, copy the frames rendered since the node read the old
+            // one, and swap so the old one is deallocated outside of the render thread
+            ring_buffer.copy_from(&self.ring_buffer);
+            std::mem::swap(&mut self.ring_buffer, ring_buffer);
+            return;
+        }
+
+        if let Some(spectrogram) = msg.downcast_mut::<Option<Spectrogram>>() {
+            // the previous subscription is deallocated outside of the render thread
+            std::mem::swap(&mut self.spectrogram, spectrogram);
+            return;
+        }
+
+        if let Some(buffer) = msg.downcast_mut::<Vec<f32>>() {
+            if let Some(spectrogram) = self.spectrogram.as_mut() {
+                spectrogram.recycle(buffer);
+            }
+            return;
+        }
+
+        log::warn!("AnalyserRenderer: Dropping incoming message {msg:?}");
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Mutex;
+
     use super::*;
     use crate::context::{
         AudioContext, AudioContextOptions, BaseAudioContext, OfflineAudioContext,
@@ -341,4 +676,56 @@ mod tests {
         };
         let _ = AnalyserNode::new(&context, options);
     }
+
+    #[test]
+    fn test_spectrogram() {
+        let sample_rate = 44_100.;
+        let mut context = OfflineAudioContext::new(1, 44_100, sample_rate);
+
+        let options = AnalyserOptions {
+            fft_size: 1024,
+            ..AnalyserOptions::default()
+        };
+        let analyser = AnalyserNode::new(&context, options);
+        analyser.connect(&context.destination());
+
+        // peak in the 20th bin
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(20. * sample_rate / 1024.);
+        osc.connect(&analyser);
+        osc.start();
+
+        let frames = Arc::new(Mutex::new(vec![]));
+        let frames_clone = Arc::clone(&frames);
+        analyser.subscribe_spectrogram(512, move |event| {
+            assert_eq!(event.magnitudes.len(), 512);
+            assert_eq!(event.dropped_frames, 0);
+            let peak = event
+                .magnitudes
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(i, _)| i)
+                .unwrap();
+            frames_clone.lock().unwrap().push((event.time, peak));
+        });
+
+        let _ = context.start_rendering_sync();
+
+        let frames = frames.lock().unwrap();
+        assert_eq!(frames.len(), 44_100 / 512);
+        for (index, &(time, peak)) in frames.iter().enumerate() {
+            let expected = ((index + 1) * 512) as f64 / f64::from(sample_rate);
+            assert_float_eq!(time, expected, abs <= 1e-9);
+            assert_eq!(peak, 20);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_spectrogram_invalid_hop_size() {
+        let context = OfflineAudioContext::new(1, 128, 44_100.);
+        let analyser = context.create_analyser();
+        analyser.subscribe_spectrogram(0, |_| {});
+    }
 }
@@ -8,7 +8,8 @@ use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::{assert_valid_time_value, AtomicF64, RENDER_QUANTUM_SIZE};
+use crate::streaming_buffer::BufferStream;
+use crate::{assert_valid_time_value, AtomicF64, StreamingAudioBuffer, RENDER_QUANTUM_SIZE};
 
 use super::{AudioNode, AudioScheduledSourceNode, ChannelConfig};
 
@@ -112,6 +113,7 @@ pub struct AudioBufferSourceNode {
     playback_rate: AudioParam, // has constraints, no a-rate
     buffer_time: Arc<AtomicF64>,
     buffer: Option<AudioBuffer>,
+    streaming_buffer: Option<StreamingAudioBuffer>,
     loop_state: LoopState,
     start_stop_count: u8,
 }
@@ -177,7 +179,7 @@ impl AudioBufferSourceNode {
             // these parameters can't be changed to a-rate
             // @see - <https://webaudio.github.io/web-audio-api/#audioparam-automation-rate-constraints>
             let detune_param_options = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("detune"),
                 min_value: f32::MIN,
                 max_value: f32::MAX,
                 default_value: 0.,
@@ -189,7 +191,7 @@ impl AudioBufferSourceNode {
             d_param.set_value(detune);
 
             let playback_rate_param_options = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("playbackRate"),
                 min_value: f32::MIN,
                 max_value: f32::MAX,
                 default_value: 1.,
@@ -212,6 +214,7 @@ impl AudioBufferSourceNode {
                 duration: f64::MAX,
                 offset: 0.,
                 buffer: None,
+                stream: None,
                 detune: d_proc,
                 playback_rate: pr_proc,
                 loop_state,
@@ -225,6 +228,7 @@ impl AudioBufferSourceNode {
                 playback_rate: pr_param,
                 buffer_time: Arc::clone(&renderer.render_state.buffer_time),
                 buffer: None,
+                streaming_buffer: None,
                 loop_state,
                 start_stop_count: 0,
             };
@@ -266,6 +270,7 @@ impl AudioBufferSourceNode {
         self.start_stop_count += 1;
         let control = ControlMessage::StartWithOffsetAndDuration(start, offset, duration);
         self.registration.post_message(control);
+        self.registration.context().notify_source_start(start);
     }
 
     /// Current buffer value (nullable)
@@ -283,7 +288,7 @@ impl AudioBufferSourceNode {
         let clone = audio_buffer.clone();
 
         assert!(
-            self.buffer.is_none(),
+            self.buffer.is_none() && self.streaming_buffer.is_none(),
             "InvalidStateError - cannot assign buffer twice",
         );
         self.buffer = Some(audio_buffer);
@@ -291,6 +296,43 @@ impl AudioBufferSourceNode {
         self.registration.post_message(clone);
     }
 
+    /// Current streaming buffer value (nullable)
+    pub fn streaming_buffer(&self) -> Option<&StreamingAudioBuffer> {
+        self.streaming_buffer.as_ref()
+    }
+
+    /// Provide a [`StreamingAudioBuffer`] as the source of data to be played back, instead of an
+    /// [`AudioBuffer`]
+    ///
+    /// The file is opened and the start of it is loaded before this method returns. See
+    /// [`StreamingAudioBuffer`] for the limitations of streamed playback.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error if the file cannot be opened
+    ///
+    /// # Panics
+    ///
+    /// Panics if a buffer has already been given to the source (though `new`, `set_buffer` or
+    /// `set_streaming_buffer`)
+    pub fn set_streaming_buffer(
+        &mut self,
+        buffer: StreamingAudioBuffer,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        assert!(
+            self.buffer.is_none() && self.streaming_buffer.is_none(),
+            "InvalidStateError - cannot assign buffer twice",
+        );
+
+        let stream = buffer.open()?;
+        self.streaming_buffer = Some(buffer);
+        self.registration.post_message(Some(stream));
+
+        Ok(())
+    }
+
     /// K-rate [`AudioParam`] that defines the speed at which the [`AudioBuffer`]
     /// will be played, e.g.:
     /// - `0.5` will play the file at half speed
@@ -381,6 +423,7 @@ struct AudioBufferSourceRenderer {
     offset: f64,
     duration: f64,
     buffer: Option<AudioBuffer>,
+    stream: Option<BufferStream>,
     detune: AudioParamId,
     playback_rate: AudioParamId,
     loop_state: LoopState,
@@ -421,6 +464,91 @@ impl AudioBufferSourceRenderer {
             }
         }
     }
+
+    /// Render a quantum of the [`StreamingAudioBuffer`], the start time of the source is known to
+    /// lie before the end of the quantum
+    fn process_stream(
+        &mut self,
+        output: &mut AudioRenderQuantum,
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        let stream = self.stream.as_mut().unwrap();
+
+        let sample_rate = scope.sample_rate as f64;
+        let dt = 1. / sample_rate;
+        let block_time = scope.current_time;
+        let next_block_time = block_time + dt * RENDER_QUANTUM_SIZE as f64;
+
+        let detune = params.get(&self.detune)[0];
+        let playback_rate = params.get(&self.playback_rate)[0];
+        let computed_playback_rate = (playback_rate * (detune / 1200.).exp2()) as f64;
+        let stream_sample_rate = stream.sample_rate() as f64;
+        // number of frames of the file per output sample, negative rates pause the playback
+        let step = (computed_playback_rate * stream_sample_rate / sample_rate).max(0.);
+
+        if !self.render_state.started {
+            self.start_time = self.start_time.max(block_time);
+            if self.offset > 0. {
+                stream.seek((self.offset * stream_sample_rate) as usize);
+            }
+            self.render_state.started = true;
+        }
+
+        // the active part of the quantum, between start time, stop time and duration, allowing for
+        // floating point errors in the computation of times that lie on a sample
+        let sample_index = |time: f64| ((time - block_time) * sample_rate - 1e-6).ceil().max(0.);
+        let first = sample_index(self.start_time) as usize;
+        let mut last = sample_index(self.stop_time) as usize;
+        if step > 0. {
+            let remaining = self.duration - self.render_state.buffer_time_elapsed;
+            let frames = (remaining * stream_sample_rate / step).ceil().max(0.) as usize;
+            last = last.min(first.saturating_add(frames));
+        }
+        let last = last.min(RENDER_QUANTUM_SIZE);
+
+        let LoopState {
+            is_looping,
+            start: loop_start,
+            end: loop_end,
+        } = self.loop_state;
+        let loop_frames = is_looping.then(|| {
+            let start = (loop_start.max(0.) * stream_sample_rate) as usize;
+            let end = if loop_end > 0. {
+                (loop_end * stream_sample_rate) as usize
+            } else {
+                usize::MAX
+            };
+            (start, end)
+        });
+        stream.set_loop(loop_frames);
+
+        output.make_silent();
+        output.set_number_of_channels(stream.number_of_channels());
+        if first < last {
+            stream.render(output.channels_mut(), first..last, step);
+            self.render_state.buffer_time_elapsed +=
+                (last - first) as f64 * step / stream_sample_rate;
+        }
+
+        self.render_state
+            .buffer_time
+            .store(stream.position(), Ordering::Relaxed);
+
+        if let Some(message) = stream.take_error() {
+            scope.report_error_message(message);
+        }
+
+        if next_block_time >= self.stop_time
+            || self.render_state.buffer_time_elapsed >= self.duration
+            || stream.has_ended()
+        {
+            self.render_state.ended = true;
+            scope.send_ended_event();
+        }
+
+        true
+    }
 }
 
 impl AudioProcessor for AudioBufferSourceRenderer {
@@ -452,6 +580,10 @@ impl AudioProcessor for AudioBufferSourceRenderer {
             return self.start_time != f64::MAX;
         }
 
+        if self.stream.is_some() {
+            return self.process_stream(output, params, scope);
+        }
+
         // If the buffer has not been set wait for it.
         let buffer = match &self.buffer {
             None => {
@@ -836,6 +968,12 @@ impl AudioProcessor for AudioBufferSourceRenderer {
             return;
         };
 
+        if let Some(stream) = msg.downcast_mut::<Option<BufferStream>>() {
+            // Avoid deallocation in the render thread by swapping the streams.
+            std::mem::swap(&mut self.stream, stream);
+            return;
+        };
+
         log::warn!("AudioBufferSourceRenderer: Dropping incoming message {msg:?}");
     }
 
@@ -937,6 +1075,49 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_playing_streaming_buffer() {
+        let length = RENDER_QUANTUM_SIZE * 10;
+        let mut context = OfflineAudioContext::new(2, length, 44_100.);
+
+        let file = std::fs::File::open("samples/sample-44100.wav").unwrap();
+        let expected = context.decode_audio_data_sync(file).unwrap();
+
+        let buffer = StreamingAudioBuffer::new("samples/sample-44100.wav").unwrap();
+        assert_eq!(buffer.number_of_channels(), 2);
+        assert_eq!(buffer.length(), expected.length());
+        assert_float_eq!(buffer.sample_rate(), 44_100., abs <= 0.);
+
+        let mut src = context.create_buffer_source();
+        src.set_streaming_buffer(buffer).unwrap();
+        src.connect(&context.destination());
+        // start in the middle of the first quantum
+        src.start_at(64. / 44_100.);
+
+        let res = context.start_rendering_sync();
+
+        for channel in 0..2 {
+            let result = res.get_channel_data(channel);
+            assert_float_eq!(result[..64], [0.; 64][..], abs_all <= 0.);
+            assert_float_eq!(
+                result[64..],
+                expected.get_channel_data(channel)[..length - 64],
+                abs_all <= 1e-6
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_streaming_buffer_after_buffer() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(context.create_buffer(1, 1, 44_100.));
+
+        let buffer = StreamingAudioBuffer::new("samples/sample-44100.wav").unwrap();
+        let _ = src.set_streaming_buffer(buffer);
+    }
+
     // slow track
     #[test]
     fn test_sub_quantum_start_1() {
@@ -1,11 +1,17 @@
+use std::error::Error;
+use std::fmt;
 use std::sync::{Arc, Mutex};
 
 use crate::context::{AudioContextRegistration, ConcreteBaseAudioContext};
 use crate::events::{ErrorEvent, EventHandler, EventPayload, EventType};
 use crate::message::ControlMessage;
+use crate::param::AudioParam;
+
+use super::{Bus, BusSend, ConnectionHandle};
 
 /// How channels must be matched between the node's inputs and outputs.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ChannelCountMode {
     /// `computedNumberOfChannels` is the maximum of the number of channels of all connections to an
     /// input. In this mode channelCount is ignored.
@@ -32,6 +38,7 @@ impl From<u32> for ChannelCountMode {
 
 /// The meaning of the channels, defining how audio up-mixing and down-mixing will happen.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ChannelInterpretation {
     Speakers,
     Discrete,
@@ -49,6 +56,65 @@ impl From<u32> for ChannelInterpretation {
     }
 }
 
+/// Speaker layout of a number of channels
+///
+/// The layouts define the meaning of the channels for the [`ChannelInterpretation::Speakers`]
+/// up-mixing and down-mixing rules. The 7.1 layout is not part of the Web Audio API
+/// specification, it extends the 5.1 layout with back channels.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SpeakerLayout {
+    /// Channels `M`
+    Mono,
+    /// Channels `L, R`
+    Stereo,
+    /// Channels `L, R, SL, SR`
+    Quad,
+    /// Channels `L, R, C, LFE, SL, SR`
+    Surround51,
+    /// Channels `L, R, C, LFE, SL, SR, BL, BR`
+    Surround71,
+    /// Any other number of channels, which are mixed discretely
+    Discrete(usize),
+}
+
+impl SpeakerLayout {
+    /// The speaker layout for the given number of channels
+    pub fn from_number_of_channels(number_of_channels: usize) -> Self {
+        match number_of_channels {
+            1 => Self::Mono,
+            2 => Self::Stereo,
+            4 => Self::Quad,
+            6 => Self::Surround51,
+            8 => Self::Surround71,
+            n => Self::Discrete(n),
+        }
+    }
+
+    /// The number of channels of this layout
+    pub fn number_of_channels(&self) -> usize {
+        match self {
+            Self::Mono => 1,
+            Self::Stereo => 2,
+            Self::Quad => 4,
+            Self::Surround51 => 6,
+            Self::Surround71 => 8,
+            Self::Discrete(n) => *n,
+        }
+    }
+
+    /// The labels of the speakers in channel order, empty for discrete layouts
+    pub fn channel_labels(&self) -> &'static [&'static str] {
+        match self {
+            Self::Mono => &["M"],
+            Self::Stereo => &["L", "R"],
+            Self::Quad => &["L", "R", "SL", "SR"],
+            Self::Surround51 => &["L", "R", "C", "LFE", "SL", "SR"],
+            Self::Surround71 => &["L", "R", "C", "LFE", "SL", "SR", "BL", "BR"],
+            Self::Discrete(_) => &[],
+        }
+    }
+}
+
 /// Options that can be used in constructing all AudioNodes.
 #[derive(Clone, Debug)]
 pub struct AudioNodeOptions {
@@ -213,6 +279,40 @@ impl From<AudioNodeOptions> for ChannelConfig {
     }
 }
 
+/// Error of [`AudioNode::try_connect`] when the connection would close a cycle without a
+/// [`DelayNode`](super::DelayNode)
+///
+/// Such a cycle is muted by the render thread, as specified. A cycle going through a `DelayNode`
+/// is allowed.
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CycleError {
+    /// The nodes forming the cycle, starting with the destination of the connection, e.g.
+    /// `GainNode 3 "master"` (type, id and label)
+    pub nodes: Vec<String>,
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "NotSupportedError - the connection closes a cycle without DelayNode, which is muted: "
+        )?;
+        self.nodes
+            .iter()
+            .chain(self.nodes.first())
+            .enumerate()
+            .try_for_each(|(i, node)| match i {
+                0 => write!(f, "{node}"),
+                _ => write!(f, " -> {node}"),
+            })
+    }
+}
+
+impl Error for CycleError {}
+
 /// This interface represents audio sources, the audio destination, and intermediate processing
 /// modules.
 ///
@@ -288,6 +388,97 @@ pub trait AudioNode {
         dest
     }
 
+    /// Connect the output of this AudioNode to the input of another node, unless it closes a
+    /// cycle without [`DelayNode`](super::DelayNode)
+    ///
+    /// Such a cycle would be muted. Only the nodes with a handle are considered: a cycle going
+    /// through a node whose handle is dropped is not detected. The check walks the connections of
+    /// the whole graph, [`connect`](Self::connect) does not perform it.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CycleError`] naming the nodes of the cycle, the nodes are not connected.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the AudioContext of the source and destination does not match
+    fn try_connect<'a>(&self, dest: &'a dyn AudioNode) -> Result<&'a dyn AudioNode, CycleError> {
+        self.try_connect_from_output_to_input(dest, 0, 0)
+    }
+
+    /// Connect a specific output of this AudioNode to a specific input of another node, unless it
+    /// closes a cycle without [`DelayNode`](super::DelayNode), see
+    /// [`try_connect`](Self::try_connect)
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CycleError`] naming the nodes of the cycle, the nodes are not connected.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the AudioContext of the source and destination does not match
+    /// - if the input port is out of bounds for the destination node
+    /// - if the output port is out of bounds for the source node
+    fn try_connect_from_output_to_input<'a>(
+        &self,
+        dest: &'a dyn AudioNode,
+        output: usize,
+        input: usize,
+    ) -> Result<&'a dyn AudioNode, CycleError> {
+        assert!(
+            self.context() == dest.context(),
+            "InvalidAccessError - Attempting to connect nodes from different contexts",
+        );
+
+        self.context()
+            .check_cycle(self.registration().id(), dest.registration().id())?;
+
+        Ok(self.connect_from_output_to_input(dest, output, input))
+    }
+
+    /// Connect the output of this AudioNode to the input of another node, through a gain
+    ///
+    /// The returned [`ConnectionHandle`] changes the gain of this connection only, or removes it,
+    /// e.g. for the cross points of a matrix mixer.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the AudioContext of the source and destination does not match
+    /// - the source node does not have an output or the destination node does not have an input
+    fn connect_with_gain(&self, dest: &dyn AudioNode, gain: f32) -> ConnectionHandle {
+        ConnectionHandle::new(self, dest, gain)
+    }
+
+    /// Connect the output of this AudioNode to the [`AudioParam`] of another node with the given
+    /// name, see [`AudioParam::name`]
+    ///
+    /// This resolves the param at runtime, e.g. for a modulation matrix that does not know the
+    /// type of the nodes it connects. Returns the param.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the AudioContext of the source and destination does not match
+    /// - the destination node does not have an AudioParam with the given name
+    fn connect_param(&self, dest: &dyn AudioNode, name: &str) -> AudioParam {
+        let param = dest
+            .audio_param(name)
+            .unwrap_or_else(|| panic!("NotFoundError - no AudioParam named {name:?}"));
+        self.connect(&param);
+        param
+    }
+
     /// Disconnects all outgoing connections from the AudioNode.
     fn disconnect(&self) {
         self.context()
@@ -296,6 +487,9 @@ pub trait AudioNode {
 
     /// Disconnects all outputs of the AudioNode that go to a specific destination AudioNode.
     ///
+    /// The destination can be an [`AudioParam`](crate::AudioParam) too, which is the
+    /// `disconnect(destinationParam)` overload of the specification.
+    ///
     /// # Panics
     ///
     /// This function will panic when
@@ -334,6 +528,9 @@ pub trait AudioNode {
 
     /// Disconnects a specific output of the AudioNode to a specific destination AudioNode
     ///
+    /// The destination can be an [`AudioParam`](crate::AudioParam) too, which is the
+    /// `disconnect(destinationParam, output)` overload of the specification.
+    ///
     /// # Panics
     ///
     /// This function will panic when
@@ -401,6 +598,44 @@ pub trait AudioNode {
         );
     }
 
+    /// Send the output of this AudioNode to a [`Bus`], at the given level
+    ///
+    /// The send runs in parallel to the other connections of the node. Use the returned
+    /// [`BusSend`] to adjust the level, the send is removed when it is dropped.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the AudioContext of the node and the bus does not match
+    /// - the node does not have an output
+    #[must_use]
+    fn send_to(&self, bus: &Bus, level: f32) -> BusSend {
+        BusSend::new(self, bus, level)
+    }
+
+    /// The [`AudioParam`]s of this AudioNode, in order of creation
+    ///
+    /// Use [`AudioParam::name`] to tell them apart, e.g. to list the modulation targets of nodes
+    /// whose type is not known. The params of the nodes of this library are named after their
+    /// attribute in the specification, e.g. `"frequency"` or `"Q"`.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    fn audio_params(&self) -> Vec<AudioParam> {
+        self.context().audio_params(self.registration().id())
+    }
+
+    /// The [`AudioParam`] of this AudioNode with the given name, see
+    /// [`audio_params`](Self::audio_params)
+    ///
+    /// This method is not part of the Web Audio API specification.
+    fn audio_param(&self, name: &str) -> Option<AudioParam> {
+        self.audio_params()
+            .into_iter()
+            .find(|param| param.name() == name)
+    }
+
     /// The number of inputs feeding into the AudioNode. For source nodes, this will be 0.
     fn number_of_inputs(&self) -> usize;
 
@@ -440,6 +675,26 @@ pub trait AudioNode {
         self.channel_config().set_count(v, self.registration())
     }
 
+    /// The label assigned to this node with [`set_label`](Self::set_label), if any
+    ///
+    /// This method is not part of the Web Audio API specification.
+    fn label(&self) -> Option<String> {
+        self.context().node_label(self.registration().id())
+    }
+
+    /// Assign a label to this node, to identify it while debugging large graphs
+    ///
+    /// The label is included in the [profile](crate::context::BaseAudioContext::profile) of the
+    /// context, in the diagnostics of the render thread and in the messages of the panics
+    /// occurring in the audio processor. Calling this method multiple times will override the
+    /// previous label.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    fn set_label(&self, label: String) {
+        self.context()
+            .set_node_label(self.registration().id(), label)
+    }
+
     /// Register callback to run when an unhandled exception occurs in the audio processor.
     ///
     /// Note that once a unhandled exception is thrown, the processor will output silence throughout its lifetime.
@@ -0,0 +1,383 @@
+use std::error::Error;
+
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+
+use crate::buffer::AudioBuffer;
+use crate::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, AudioContextRegistration,
+    BaseAudioContext,
+};
+use crate::media_streams::MediaStreamTrack;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
+
+/// Number of frames buffered between the two devices, absorbing the jitter of their callbacks
+const TARGET_FILL: f64 = (8 * RENDER_QUANTUM_SIZE) as f64;
+
+/// Maximum deviation of the playback rate from 1 to compensate for the clock drift, 0.5 %
+const MAX_CORRECTION: f64 = 0.005;
+
+/// Correction of the playback rate for a buffer that is off target by `TARGET_FILL` frames
+const CORRECTION_GAIN: f64 = 0.01;
+
+/// Smoothing factor of the fill level, to filter out the jitter of the callbacks
+const FILL_SMOOTHING: f64 = 0.01;
+
+/// Number of render quanta that can be in flight between the render threads
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Options for constructing an [`AuxiliaryDestinationNode`]
+#[derive(Clone, Debug)]
+pub struct AuxiliaryDestinationOptions {
+    /// The audio output device, see [`AudioContextOptions::sink_id`]
+    pub sink_id: String,
+    /// Latency category of the audio output device
+    pub latency_hint: AudioContextLatencyCategory,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for AuxiliaryDestinationOptions {
+    fn default() -> Self {
+        Self {
+            sink_id: String::new(),
+            latency_hint: AudioContextLatencyCategory::default(),
+            audio_node_options: AudioNodeOptions {
+                channel_count: 2,
+                channel_count_mode: ChannelCountMode::Explicit,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// Secondary output of an [`AudioContext`], bound to another audio output device
+///
+/// The input of the node is played on its own output device, e.g. a cue mix on headphones next
+/// to the main mix on the speakers of the context. The audio is rendered by the graph of the
+/// context, so it shares the clock of the context. The output device runs on its own clock, the
+/// drift between both devices is compensated by slightly adjusting the playback rate of the
+/// auxiliary output.
+///
+/// The output device is released when the node is dropped.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Panics
+///
+/// The constructor panics when an invalid `sinkId` is provided, like the [`AudioContext`]
+/// constructor.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::media_devices::{enumerate_devices_sync, MediaDeviceInfoKind};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// // play a cue mix on the last output device
+/// let sink_id = enumerate_devices_sync()
+///     .into_iter()
+///     .filter(|d| d.kind() == MediaDeviceInfoKind::AudioOutput)
+///     .last()
+///     .unwrap()
+///     .device_id()
+///     .to_string();
+/// let cue = context.create_auxiliary_destination(sink_id);
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&context.destination());
+/// osc.connect(&cue);
+/// osc.start();
+/// ```
+#[derive(Debug)]
+pub struct AuxiliaryDestinationNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    /// Context playing the stream of the node on the output device
+    output: AudioContext,
+}
+
+impl AudioNode for AuxiliaryDestinationNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        0
+    }
+}
+
+impl AuxiliaryDestinationNode {
+    /// Create a new `AuxiliaryDestinationNode`, opening the given output device
+    pub fn new<C: BaseAudioContext>(context: &C, options: AuxiliaryDestinationOptions) -> Self {
+        let AuxiliaryDestinationOptions {
+            sink_id,
+            latency_hint,
+            audio_node_options,
+        } = options;
+
+        let sample_rate = context.sample_rate();
+        let output = AudioContext::new(AudioContextOptions {
+            sink_id,
+            latency_hint,
+            // the device may not support the sample rate of the context, in which case the
+            // stream is resampled by the source node below
+            sample_rate: Some(sample_rate),
+            ..AudioContextOptions::default()
+        });
+
+        let (sender, receiver) = crossbeam_channel::bounded(CHANNEL_CAPACITY);
+        let stream = AuxiliaryStream::new(receiver, audio_node_options.channel_count, sample_rate);
+        let track = MediaStreamTrack::from_iter(stream);
+        let source = output.create_media_stream_track_source(&track);
+        source.connect(&output.destination());
+
+        context.base().register(move |registration| {
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                output,
+            };
+            let render = AuxiliaryDestinationRenderer { sender };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The audio output device of the node
+    pub fn sink_id(&self) -> String {
+        self.output.sink_id()
+    }
+
+    /// Latency of the output, in seconds, including the buffering between both devices
+    pub fn output_latency(&self) -> f64 {
+        self.output.output_latency() + TARGET_FILL / f64::from(self.context().sample_rate())
+    }
+}
+
+impl Drop for AuxiliaryDestinationNode {
+    fn drop(&mut self) {
+        self.output.close_sync();
+    }
+}
+
+struct AuxiliaryDestinationRenderer {
+    sender: Sender<AudioBuffer>,
+}
+
+impl AudioProcessor for AuxiliaryDestinationRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        _outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input node, no output
+        let input = &inputs[0];
+
+        // convert AudioRenderQuantum to AudioBuffer, silent quanta are sent too so the output
+        // keeps following the clock of the context
+        let samples: Vec<_> = input.channels().iter().map(|c| c.to_vec()).collect();
+        let buffer = AudioBuffer::from(samples, scope.sample_rate);
+
+        if self.sender.try_send(buffer).is_err() {
+            log::debug!("AuxiliaryDestination buffer dropped");
+        }
+
+        false
+    }
+}
+
+/// Stream of the rendered quanta, played back at a slightly adjusted rate to keep the buffer
+/// between both devices at its target fill level
+struct AuxiliaryStream {
+    receiver: Receiver<AudioBuffer>,
+    sample_rate: f32,
+    /// Frames received and not played yet, per channel
+    fifo: Vec<Vec<f32>>,
+    /// Position of the playhead relative to the first frame of the fifo
+    position: f64,
+    /// Smoothed number of frames ahead of the playhead
+    fill: f64,
+    /// Number of frames of the fifo per output frame
+    ratio: f64,
+    /// Wait for the fifo to reach its target fill level before playing
+    buffering: bool,
+}
+
+impl AuxiliaryStream {
+    fn new(receiver: Receiver<AudioBuffer>, number_of_channels: usize, sample_rate: f32) -> Self {
+        Self {
+            receiver,
+            sample_rate,
+            fifo: vec![Vec::new(); number_of_channels],
+            position: 0.,
+            fill: TARGET_FILL,
+            ratio: 1.,
+            buffering: true,
+        }
+    }
+
+    fn available(&self) -> f64 {
+        self.fifo[0].len() as f64 - self.position
+    }
+
+    fn silence(&self) -> AudioBuffer {
+        let samples = vec![vec![0.; RENDER_QUANTUM_SIZE]; self.fifo.len()];
+        AudioBuffer::from(samples, self.sample_rate)
+    }
+
+    /// Append the received quanta to the fifo, returns false if the node has been dropped
+    fn receive(&mut self) -> bool {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(buffer) => {
+                    // a silent quantum may have a single channel
+                    self.fifo.iter_mut().enumerate().for_each(|(i, fifo)| {
+                        match buffer.channels().get(i) {
+                            Some(channel) => fifo.extend_from_slice(channel.as_slice()),
+                            None => fifo.resize(fifo.len() + buffer.length(), 0.),
+                        }
+                    });
+                }
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    /// Drop the frames exceeding the target fill level, after the output device has stalled
+    fn skip_excess(&mut self) {
+        let excess = (self.available() - TARGET_FILL).max(0.) as usize;
+        self.fifo.iter_mut().for_each(|fifo| {
+            fifo.drain(..excess);
+        });
+        self.fill = TARGET_FILL;
+    }
+}
+
+impl Iterator for AuxiliaryStream {
+    type Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the stream ends when the node is dropped and the fifo has run dry
+        let connected = self.receive();
+
+        if self.available() > 4. * TARGET_FILL {
+            log::debug!("AuxiliaryDestination output stalled, skipping frames");
+            self.skip_excess();
+        }
+
+        if self.buffering {
+            if self.available() < TARGET_FILL {
+                return connected.then(|| Ok(self.silence()));
+            }
+            self.buffering = false;
+            self.skip_excess();
+        }
+
+        // adjust the playback rate to the smoothed fill level of the fifo
+        self.fill += FILL_SMOOTHING * (self.available() - self.fill);
+        let correction = (self.fill - TARGET_FILL) / TARGET_FILL * CORRECTION_GAIN;
+        self.ratio = 1. + correction.clamp(-MAX_CORRECTION, MAX_CORRECTION);
+
+        let count = RENDER_QUANTUM_SIZE as f64;
+        let end = self.position + count * self.ratio;
+        if end.floor() as usize + 1 >= self.fifo[0].len() {
+            log::debug!("AuxiliaryDestination buffer underrun");
+            self.buffering = true;
+            return connected.then(|| Ok(self.silence()));
+        }
+
+        let samples = self
+            .fifo
+            .iter()
+            .map(|fifo| {
+                (0..RENDER_QUANTUM_SIZE)
+                    .map(|i| {
+                        let position = self.position + i as f64 * self.ratio;
+                        let index = position.floor();
+                        let k = (position - index) as f32;
+                        let index = index as usize;
+                        (1. - k).mul_add(fifo[index], k * fifo[index + 1])
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let consumed = end.floor() as usize;
+        self.fifo.iter_mut().for_each(|fifo| {
+            fifo.drain(..consumed);
+        });
+        self.position = end - consumed as f64;
+
+        Some(Ok(AudioBuffer::from(samples, self.sample_rate)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    fn quantum(value: f32) -> AudioBuffer {
+        AudioBuffer::from(vec![vec![value; RENDER_QUANTUM_SIZE]; 2], 48_000.)
+    }
+
+    #[test]
+    fn test_buffering() {
+        let (sender, receiver) = crossbeam_channel::bounded(CHANNEL_CAPACITY);
+        let mut stream = AuxiliaryStream::new(receiver, 2, 48_000.);
+
+        // silence until the target fill level is reached
+        sender.send(quantum(1.)).unwrap();
+        let buffer = stream.next().unwrap().unwrap();
+        assert_eq!(buffer.number_of_channels(), 2);
+        assert_float_eq!(buffer.get_channel_data(0)[..], [0.; 128][..], abs_all <= 0.);
+
+        let quanta = TARGET_FILL as usize / RENDER_QUANTUM_SIZE;
+        (1..quanta).for_each(|_| sender.send(quantum(1.)).unwrap());
+        let buffer = stream.next().unwrap().unwrap();
+        assert_float_eq!(buffer.get_channel_data(1)[..], [1.; 128][..], abs_all <= 0.);
+
+        // the stream ends when the node is dropped and the fifo has been played
+        drop(sender);
+        assert!(stream.take_while(Result::is_ok).count() < quanta);
+    }
+
+    #[test]
+    fn test_drift_compensation() {
+        let (sender, receiver) = crossbeam_channel::bounded(CHANNEL_CAPACITY);
+        let mut stream = AuxiliaryStream::new(receiver, 2, 48_000.);
+
+        // the context renders 0.2 % faster than the output device plays
+        let mut pending = 0.;
+        for _ in 0..5000 {
+            pending += 1.002;
+            while pending >= 1. {
+                sender.send(quantum(1.)).unwrap();
+                pending -= 1.;
+            }
+            stream.next().unwrap().unwrap();
+        }
+
+        assert!(stream.ratio > 1.001 && stream.ratio < 1.003);
+        assert!(stream.available() > TARGET_FILL && stream.available() < 2. * TARGET_FILL);
+    }
+}
@@ -20,17 +20,17 @@ fn get_computed_freq(freq: f32, detune: f32, sample_rate: f32) -> f32 {
 
 /// Biquad filter coefficients normalized against a0
 #[derive(Clone, Copy, Debug, Default)]
-struct Coefficients {
-    b0: f64,
-    b1: f64,
-    b2: f64,
-    a1: f64,
-    a2: f64,
+pub(crate) struct Coefficients {
+    pub b0: f64,
+    pub b1: f64,
+    pub b2: f64,
+    pub a1: f64,
+    pub a2: f64,
 }
 
 // allow non snake to better the variable names in the spec
 #[allow(non_snake_case)]
-fn calculate_coefs(
+pub(crate) fn calculate_coefs(
     filter_type: BiquadFilterType,
     sample_rate: f64,
     f0: f64,
@@ -355,7 +355,7 @@ impl BiquadFilterNode {
             } = options;
 
             let q_param_options = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("Q"),
                 min_value: f32::MIN,
                 max_value: f32::MAX,
                 default_value: 1.,
@@ -365,7 +365,7 @@ impl BiquadFilterNode {
             q_param.set_value(q);
 
             let detune_param_options = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("detune"),
                 min_value: -153_600.,
                 max_value: 153_600.,
                 default_value: 0.,
@@ -375,7 +375,7 @@ impl BiquadFilterNode {
             d_param.set_value(detune);
 
             let freq_options = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("frequency"),
                 min_value: 0.,
                 max_value: sample_rate / 2.,
                 default_value: 350.,
@@ -385,7 +385,7 @@ impl BiquadFilterNode {
             f_param.set_value(frequency);
 
             let gain_options = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("gain"),
                 min_value: f32::MIN,
                 max_value: 40. * f32::MAX.log10(),
                 default_value: 0.,
@@ -474,6 +474,66 @@ impl BiquadFilterNode {
         frequency_hz: &[f32],
         mag_response: &mut [f32],
         phase_response: &mut [f32],
+    ) {
+        let frequency = self.frequency().value();
+        let detune = self.detune().value();
+        let gain = self.gain().value();
+        let q = self.q().value();
+
+        self.frequency_response(
+            [frequency, detune, gain, q],
+            frequency_hz,
+            mag_response,
+            phase_response,
+        );
+    }
+
+    /// Returns the frequency response for the specified frequencies, at the given context time
+    ///
+    /// Contrary to [`BiquadFilterNode::get_frequency_response`], which uses the current values of
+    /// the `AudioParam`s, the scheduled automation of `frequency`, `detune`, `Q` and `gain` is
+    /// evaluated at `time`. This allows e.g. EQ user interfaces to draw the response that will
+    /// actually be in effect. See [`AudioParam::value_at_time`].
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - context time at which the frequency response should be calculated
+    /// * `frequency_hz` - frequencies for which frequency response of the filter should be calculated
+    /// * `mag_response` - magnitude of the frequency response of the filter
+    /// * `phase_response` - phase of the frequency response of the filter
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if arguments' lengths don't match
+    ///
+    pub fn get_frequency_response_at_time(
+        &self,
+        time: f64,
+        frequency_hz: &[f32],
+        mag_response: &mut [f32],
+        phase_response: &mut [f32],
+    ) {
+        let frequency = self.frequency().value_at_time(time);
+        let detune = self.detune().value_at_time(time);
+        let gain = self.gain().value_at_time(time);
+        let q = self.q().value_at_time(time);
+
+        self.frequency_response(
+            [frequency, detune, gain, q],
+            frequency_hz,
+            mag_response,
+            phase_response,
+        );
+    }
+
+    fn frequency_response(
+        &self,
+        [frequency, detune, gain, q]: [f32; 4],
+        frequency_hz: &[f32],
+        mag_response: &mut [f32],
+        phase_response: &mut [f32],
     ) {
         assert!(
             frequency_hz.len() == mag_response.len() && mag_response.len() == phase_response.len(),
@@ -484,10 +544,6 @@ impl BiquadFilterNode {
         let n_quist = sample_rate / 2.;
 
         let type_ = self.type_();
-        let frequency = self.frequency().value();
-        let detune = self.detune().value();
-        let gain = self.gain().value();
-        let q = self.q().value();
 
         // get coefs
         let computed_freq = get_computed_freq(frequency, detune, sample_rate);
@@ -793,6 +849,49 @@ mod tests {
         biquad.get_frequency_response(&frequency_hz, &mut mag_response, &mut phase_response);
     }
 
+    #[test]
+    fn test_frequency_response_at_time() {
+        let context = OfflineAudioContext::new(1, 128, 44_100.);
+
+        let biquad = context.create_biquad_filter();
+        biquad.frequency().set_value_at_time(1000., 0.);
+        biquad.frequency().linear_ramp_to_value_at_time(2000., 1.);
+        biquad.q().set_value_at_time(5., 0.25);
+
+        let reference = context.create_biquad_filter();
+        reference.frequency().set_value(1500.);
+        reference.q().set_value(5.);
+
+        let frequency_hz = [100., 1000., 1500., 2000., 10000.];
+        let mut mag_response = [0.; 5];
+        let mut phase_response = [0.; 5];
+        let mut expected_mag_response = [0.; 5];
+        let mut expected_phase_response = [0.; 5];
+
+        biquad.get_frequency_response_at_time(
+            0.5,
+            &frequency_hz,
+            &mut mag_response,
+            &mut phase_response,
+        );
+        reference.get_frequency_response(
+            &frequency_hz,
+            &mut expected_mag_response,
+            &mut expected_phase_response,
+        );
+
+        assert_float_eq!(mag_response[..], expected_mag_response[..], abs_all <= 1e-6);
+        assert_float_eq!(
+            phase_response[..],
+            expected_phase_response[..],
+            abs_all <= 1e-6
+        );
+
+        // the current response is not affected by the automation
+        biquad.get_frequency_response(&frequency_hz, &mut mag_response, &mut phase_response);
+        assert!(mag_response[2] < expected_mag_response[2]);
+    }
+
     // @note: expected values retrieved from chrome and firefox, both being coherent
     #[test]
     #[allow(clippy::excessive_precision)]
@@ -0,0 +1,357 @@
+use crate::context::{AudioNodeId, BaseAudioContext};
+use crate::param::AudioParam;
+
+use super::{AudioNode, ConnectionHandle, GainNode, GainOptions, GainStageNode, GainStageOptions};
+
+/// Options for constructing a [`Bus`]
+#[derive(Clone, Debug)]
+pub struct BusOptions {
+    /// Initial gain of the fader
+    pub gain: f32,
+}
+
+impl Default for BusOptions {
+    fn default() -> Self {
+        Self { gain: 1. }
+    }
+}
+
+/// `Bus` sums the signals sent to it, processes them with a chain of effects and measures the
+/// level of the result, like a group or an aux channel of a mixing desk
+///
+/// Nodes are routed to the bus with [`AudioNode::send_to`], which returns a [`BusSend`] to
+/// adjust the level of the send. The effects are inserted pre-fader, the peak level is measured
+/// post-fader.
+///
+/// The chain is made of regular nodes: dropping the `Bus` keeps it in the graph as long as it is
+/// connected, like any other node.
+///
+/// This type is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, Bus, BusOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // a reverb return shared by several sources
+/// let mut reverb = Bus::new(&context, BusOptions::default());
+/// let convolver = context.create_convolver();
+/// reverb.insert_effect(&convolver);
+/// reverb.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&context.destination());
+/// let send = osc.send_to(&reverb, 0.3);
+/// osc.start();
+///
+/// // more reverb
+/// send.set_level(0.6);
+/// ```
+#[derive(Debug)]
+pub struct Bus {
+    input: GainNode,
+    fader: GainNode,
+    meter: GainStageNode,
+    effects: Vec<AudioNodeId>,
+}
+
+impl Bus {
+    /// Create a new `Bus` without effects
+    pub fn new<C: BaseAudioContext>(context: &C, options: BusOptions) -> Self {
+        let input = GainNode::new(context, GainOptions::default());
+        let fader = GainNode::new(
+            context,
+            GainOptions {
+                gain: options.gain,
+                ..GainOptions::default()
+            },
+        );
+        let meter = GainStageNode::new(context, GainStageOptions::default());
+
+        input.connect(&fader);
+        fader.connect(&meter);
+
+        Self {
+            input,
+            fader,
+            meter,
+            effects: vec![],
+        }
+    }
+
+    /// The node summing the sends of the bus
+    ///
+    /// Connect to it directly to send a signal at unity gain.
+    pub fn input(&self) -> &dyn AudioNode {
+        &self.input
+    }
+
+    /// A-rate [`AudioParam`] representing the gain of the fader
+    pub fn gain(&self) -> &AudioParam {
+        self.fader.gain()
+    }
+
+    /// Connect the output of the bus to the input of another node
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when the node belongs to another context
+    pub fn connect<'a>(&self, dest: &'a dyn AudioNode) -> &'a dyn AudioNode {
+        self.meter.connect(dest)
+    }
+
+    /// Disconnect the output of the bus from all the nodes it is connected to
+    pub fn disconnect(&self) {
+        self.meter.disconnect()
+    }
+
+    /// Append the node to the effects of the bus
+    ///
+    /// The first input and output of the node are connected in the chain, its other
+    /// connections are left untouched. The node keeps processing the bus when its handle is
+    /// dropped, until it is removed.
+    ///
+    /// # Panics
+    ///
+    /// Will panic when the node belongs to another context, does not have an input and an output
+    /// or is already inserted
+    pub fn insert_effect(&mut self, node: &dyn AudioNode) {
+        let context = self.input.context();
+        assert!(
+            context == node.context(),
+            "InvalidAccessError - Attempting to insert a node from a different context",
+        );
+        assert!(
+            node.number_of_inputs() > 0 && node.number_of_outputs() > 0,
+            "InvalidAccessError - an effect of a bus needs an input and an output",
+        );
+
+        let id = node.registration().id();
+        assert!(
+            !self.effects.contains(&id) && !self.is_internal(id),
+            "InvalidStateError - node is already inserted in the bus",
+        );
+
+        let last = self.effects.last().copied().unwrap_or(self.input_id());
+        let fader = self.fader_id();
+
+        context.batch_control_msgs(|| {
+            context.disconnect(last, Some(0), Some(fader), Some(0));
+            context.connect(last, id, 0, 0);
+            context.connect(id, fader, 0, 0);
+        });
+
+        self.effects.push(id);
+    }
+
+    /// Remove the node from the effects of the bus, see [`insert_effect`](Self::insert_effect)
+    ///
+    /// # Panics
+    ///
+    /// Will panic when the node is not inserted in the bus
+    pub fn remove_effect(&mut self, node: &dyn AudioNode) {
+        let id = node.registration().id();
+        let index = self
+            .effects
+            .iter()
+            .position(|&effect| effect == id)
+            .expect("InvalidStateError - node is not inserted in the bus");
+
+        let previous = match index {
+            0 => self.input_id(),
+            i => self.effects[i - 1],
+        };
+        let next = self
+            .effects
+            .get(index + 1)
+            .copied()
+            .unwrap_or(self.fader_id());
+
+        let context = self.input.context();
+        context.batch_control_msgs(|| {
+            context.disconnect(previous, Some(0), Some(id), Some(0));
+            context.disconnect(id, Some(0), Some(next), Some(0));
+            context.connect(previous, next, 0, 0);
+        });
+
+        self.effects.remove(index);
+    }
+
+    /// Remove all the effects from the bus, see [`insert_effect`](Self::insert_effect)
+    pub fn clear_effects(&mut self) {
+        let Some(&last) = self.effects.last() else {
+            return;
+        };
+
+        let input = self.input_id();
+        let fader = self.fader_id();
+
+        let context = self.input.context();
+        context.batch_control_msgs(|| {
+            let mut previous = input;
+            for &id in &self.effects {
+                context.disconnect(previous, Some(0), Some(id), Some(0));
+                previous = id;
+            }
+            context.disconnect(last, Some(0), Some(fader), Some(0));
+            context.connect(input, fader, 0, 0);
+        });
+
+        self.effects.clear();
+    }
+
+    /// The number of effects inserted in the bus
+    #[must_use]
+    pub fn number_of_effects(&self) -> usize {
+        self.effects.len()
+    }
+
+    /// Highest absolute sample value of the output since the last reset
+    #[must_use]
+    pub fn peak(&self) -> f32 {
+        self.meter.output_peak()
+    }
+
+    /// Restart the peak measurement
+    pub fn reset_peak(&self) {
+        self.meter.reset_peaks();
+    }
+
+    fn input_id(&self) -> AudioNodeId {
+        self.input.registration().id()
+    }
+
+    fn fader_id(&self) -> AudioNodeId {
+        self.fader.registration().id()
+    }
+
+    fn is_internal(&self, id: AudioNodeId) -> bool {
+        id == self.input_id() || id == self.fader_id() || id == self.meter.registration().id()
+    }
+}
+
+/// Handle of a send to a [`Bus`], see [`AudioNode::send_to`]
+///
+/// The send is removed from the graph when the handle is dropped.
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Debug)]
+pub struct BusSend {
+    connection: ConnectionHandle,
+}
+
+impl BusSend {
+    pub(crate) fn new<N: AudioNode + ?Sized>(source: &N, bus: &Bus, level: f32) -> Self {
+        let connection = ConnectionHandle::new(source, bus.input(), level);
+        Self { connection }
+    }
+
+    /// A-rate [`AudioParam`] representing the level of the send
+    pub fn level(&self) -> &AudioParam {
+        self.connection.gain()
+    }
+
+    /// Set the level of the send, see [`level`](Self::level) to automate it
+    pub fn set_level(&self, level: f32) {
+        self.connection.set_gain(level);
+    }
+}
+
+impl Drop for BusSend {
+    fn drop(&mut self) {
+        self.connection.disconnect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_sends() {
+        let mut context = OfflineAudioContext::new(1, 128, 48_000.);
+
+        let bus = Bus::new(&context, BusOptions { gain: 0.5 });
+        bus.connect(&context.destination());
+
+        let mut src1 = context.create_constant_source();
+        src1.start();
+        let send1 = src1.send_to(&bus, 0.5);
+
+        let mut src2 = context.create_constant_source();
+        src2.start();
+        let send2 = src2.send_to(&bus, 1.);
+        send2.set_level(0.25);
+        assert_float_eq!(send2.level().value(), 0.25, abs <= 0.);
+
+        // dropped sends are removed
+        let mut src3 = context.create_constant_source();
+        src3.start();
+        drop(src3.send_to(&bus, 1.));
+
+        let output = context.start_rendering_sync();
+        output
+            .get_channel_data(0)
+            .iter()
+            .for_each(|&v| assert_float_eq!(v, 0.375, abs <= 1e-6));
+        assert_float_eq!(bus.peak(), 0.375, abs <= 1e-6);
+
+        drop(send1);
+        bus.reset_peak();
+        assert_float_eq!(bus.peak(), 0., abs <= 0.);
+    }
+
+    #[test]
+    fn test_effects() {
+        let mut context = OfflineAudioContext::new(1, 128, 48_000.);
+
+        let mut bus = Bus::new(&context, BusOptions::default());
+        bus.connect(&context.destination());
+
+        let double = context.create_gain();
+        double.gain().set_value(2.);
+        let mut clip = context.create_wave_shaper();
+        clip.set_curve(vec![-1., 0., 1.]);
+        let unused = context.create_wave_shaper();
+
+        // clip then double
+        bus.insert_effect(&clip);
+        bus.insert_effect(&unused);
+        bus.insert_effect(&double);
+        bus.remove_effect(&unused);
+        assert_eq!(bus.number_of_effects(), 2);
+
+        let mut src = context.create_constant_source();
+        src.offset().set_value(0.75);
+        src.connect(bus.input());
+        src.start();
+
+        let output = context.start_rendering_sync();
+        output
+            .get_channel_data(0)
+            .iter()
+            .for_each(|&v| assert_float_eq!(v, 1.5, abs <= 1e-6));
+
+        bus.clear_effects();
+        assert_eq!(bus.number_of_effects(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_twice() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+
+        let mut bus = Bus::new(&context, BusOptions::default());
+        let gain = context.create_gain();
+        bus.insert_effect(&gain);
+        bus.insert_effect(&gain);
+    }
+}
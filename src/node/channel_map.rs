@@ -0,0 +1,252 @@
+use std::any::Any;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::MAX_CHANNELS;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
+
+/// Assert that the given channel map is valid for a ChannelMapNode
+///
+/// # Panics
+///
+/// This function will panic if:
+/// - the number of output channels is outside the [1, MAX_CHANNELS] range,
+/// - an input channel index is not smaller than MAX_CHANNELS,
+///
+/// MAX_CHANNELS being 32 unless raised by a feature flag.
+#[track_caller]
+#[inline(always)]
+fn assert_valid_channel_map(channel_map: &[Option<usize>]) {
+    assert!(
+        !channel_map.is_empty() && channel_map.len() <= MAX_CHANNELS,
+        "IndexSizeError - Invalid number of channels: {:?} is outside range [1, {:?}]",
+        channel_map.len(),
+        MAX_CHANNELS
+    );
+
+    if let Some(index) = channel_map.iter().flatten().find(|&&i| i >= MAX_CHANNELS) {
+        panic!(
+            "IndexSizeError - Invalid input channel: {:?} is outside range [0, {:?}[",
+            index, MAX_CHANNELS
+        );
+    }
+}
+
+/// Options for constructing a [`ChannelMapNode`]
+#[derive(Clone, Debug)]
+pub struct ChannelMapOptions {
+    /// For each output channel, the index of the input channel routed to it, `None` for silence
+    pub channel_map: Vec<Option<usize>>,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for ChannelMapOptions {
+    fn default() -> Self {
+        Self {
+            channel_map: vec![Some(0), Some(1)],
+            audio_node_options: AudioNodeOptions {
+                channel_count: 2,
+                channel_count_mode: ChannelCountMode::Max,
+                channel_interpretation: ChannelInterpretation::Discrete,
+            },
+        }
+    }
+}
+
+/// `ChannelMapNode` routes the channels of its input to arbitrary output channels
+///
+/// Each output channel copies the input channel given by the channel map, or is silent. This
+/// allows to send a signal to specific speakers of a multichannel output (see
+/// [`SpeakerLayout`](super::SpeakerLayout)), to reorder the channels of a file with another
+/// channel ordering, or to duplicate and drop channels, without the up-mixing and down-mixing
+/// rules of the speakers interpretation.
+///
+/// Input channels that are missing from the actual input are rendered as silence.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, ChannelMapNode, ChannelMapOptions};
+///
+/// let context = AudioContext::default();
+/// context.destination().set_channel_count(6);
+///
+/// // send a stereo signal to the surround speakers of a 5.1 layout
+/// let options = ChannelMapOptions {
+///     channel_map: vec![None, None, None, None, Some(0), Some(1)],
+///     ..ChannelMapOptions::default()
+/// };
+/// let map = ChannelMapNode::new(&context, options);
+/// map.connect(&context.destination());
+/// ```
+#[derive(Debug)]
+pub struct ChannelMapNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    channel_map: Vec<Option<usize>>,
+}
+
+impl AudioNode for ChannelMapNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl ChannelMapNode {
+    /// Create a new `ChannelMapNode`
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the channel map is empty, has more than [`MAX_CHANNELS`] entries, or
+    /// refers to an input channel index not smaller than [`MAX_CHANNELS`]
+    pub fn new<C: BaseAudioContext>(context: &C, options: ChannelMapOptions) -> Self {
+        assert_valid_channel_map(&options.channel_map);
+
+        context.base().register(move |registration| {
+            let render = ChannelMapRenderer {
+                channel_map: options.channel_map.clone(),
+            };
+
+            let node = Self {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                channel_map: options.channel_map,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// For each output channel, the index of the input channel routed to it
+    pub fn channel_map(&self) -> &[Option<usize>] {
+        &self.channel_map
+    }
+
+    /// Update the channel map
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the channel map is empty, has more than [`MAX_CHANNELS`] entries, or
+    /// refers to an input channel index not smaller than [`MAX_CHANNELS`]
+    pub fn set_channel_map(&mut self, channel_map: Vec<Option<usize>>) {
+        assert_valid_channel_map(&channel_map);
+        self.channel_map.clone_from(&channel_map);
+        self.registration.post_message(channel_map);
+    }
+}
+
+struct ChannelMapRenderer {
+    channel_map: Vec<Option<usize>>,
+}
+
+impl AudioProcessor for ChannelMapRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        let silence = input.channel_data(0).silence();
+        output.set_number_of_channels(self.channel_map.len());
+
+        for (channel, source) in self.channel_map.iter().enumerate() {
+            *output.channel_data_mut(channel) = match source {
+                Some(index) if *index < input.number_of_channels() => {
+                    input.channel_data(*index).clone()
+                }
+                _ => silence.clone(),
+            };
+        }
+
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(channel_map) = msg.downcast_mut::<Vec<Option<usize>>>() {
+            // swap so the old map is deallocated outside of the render thread
+            std::mem::swap(&mut self.channel_map, channel_map);
+            return;
+        }
+
+        log::warn!("ChannelMapRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::RENDER_QUANTUM_SIZE;
+
+    use super::*;
+
+    #[test]
+    fn test_channel_map() {
+        let mut context = OfflineAudioContext::new(4, RENDER_QUANTUM_SIZE, 48_000.);
+
+        let options = ChannelMapOptions {
+            channel_map: vec![Some(1), None, Some(0), Some(5)],
+            ..ChannelMapOptions::default()
+        };
+        let map = ChannelMapNode::new(&context, options);
+        map.connect(&context.destination());
+        assert_eq!(map.channel_map(), &[Some(1), None, Some(0), Some(5)]);
+
+        let mut buffer = context.create_buffer(2, RENDER_QUANTUM_SIZE, 48_000.);
+        buffer.copy_to_channel(&[1.; RENDER_QUANTUM_SIZE], 0);
+        buffer.copy_to_channel(&[2.; RENDER_QUANTUM_SIZE], 1);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&map);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        for (channel, expected) in [2., 0., 1., 0.].into_iter().enumerate() {
+            assert_float_eq!(
+                output.get_channel_data(channel)[..],
+                [expected; RENDER_QUANTUM_SIZE][..],
+                abs_all <= 0.
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_map() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48_000.);
+        let options = ChannelMapOptions {
+            channel_map: vec![Some(MAX_CHANNELS)],
+            ..ChannelMapOptions::default()
+        };
+        let _ = ChannelMapNode::new(&context, options);
+    }
+}
@@ -15,8 +15,8 @@ const DEFAULT_NUMBER_OF_OUTPUTS: usize = 6;
 /// # Panics
 ///
 /// This function will panic if:
-/// - the given number of channels is outside the [1, 32] range,
-///   32 being defined by the MAX_CHANNELS constant.
+/// - the given number of channels is outside the [1, MAX_CHANNELS] range,
+///   MAX_CHANNELS being 32 unless raised by a feature flag.
 ///
 #[track_caller]
 #[inline(always)]
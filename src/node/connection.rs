@@ -0,0 +1,112 @@
+use crate::param::AudioParam;
+
+use super::{AudioNode, GainNode, GainOptions};
+
+/// Handle of a connection with its own gain, see [`AudioNode::connect_with_gain`]
+///
+/// The connection is made of a hidden [`GainNode`] between the nodes. Like the handle of a node,
+/// dropping the handle leaves the connection in place.
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Debug)]
+pub struct ConnectionHandle {
+    gain: GainNode,
+}
+
+impl ConnectionHandle {
+    pub(crate) fn new<N: AudioNode + ?Sized>(source: &N, dest: &dyn AudioNode, gain: f32) -> Self {
+        assert!(
+            source.context() == dest.context(),
+            "InvalidAccessError - Attempting to connect nodes from different contexts",
+        );
+        assert!(
+            source.number_of_outputs() > 0,
+            "IndexSizeError - output port 0 is out of bounds",
+        );
+        assert!(
+            dest.number_of_inputs() > 0,
+            "IndexSizeError - input port 0 is out of bounds",
+        );
+
+        let options = GainOptions {
+            gain,
+            ..GainOptions::default()
+        };
+        let node = GainNode::new(source.context(), options);
+        source.connect(&node);
+        node.connect(dest);
+
+        Self { gain: node }
+    }
+
+    /// A-rate [`AudioParam`] representing the gain of the connection
+    pub fn gain(&self) -> &AudioParam {
+        self.gain.gain()
+    }
+
+    /// Set the gain of the connection, see [`gain`](Self::gain) to automate it
+    pub fn set_gain(&self, gain: f32) {
+        self.gain.gain().set_value(gain);
+    }
+
+    /// Remove the connection, leaving the other connections of the nodes untouched
+    ///
+    /// Has no effect when the connection is already removed.
+    pub fn disconnect(&self) {
+        // without outgoing connections, the gain node is dropped in the render thread together
+        // with its handle
+        self.gain.disconnect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioScheduledSourceNode, ChannelMergerNode, ChannelMergerOptions};
+
+    use super::*;
+
+    #[test]
+    fn test_matrix() {
+        let mut context = OfflineAudioContext::new(2, 128, 48_000.);
+
+        let options = ChannelMergerOptions {
+            number_of_inputs: 2,
+            ..ChannelMergerOptions::default()
+        };
+        let merger = ChannelMergerNode::new(&context, options);
+        merger.connect(&context.destination());
+
+        let left = context.create_gain();
+        left.connect_from_output_to_input(&merger, 0, 0);
+        let right = context.create_gain();
+        right.connect_from_output_to_input(&merger, 0, 1);
+
+        let mut src = context.create_constant_source();
+        src.start();
+        src.connect(&left);
+        let to_left = src.connect_with_gain(&left, 0.5);
+        let to_right = src.connect_with_gain(&right, 1.);
+        to_right.set_gain(0.25);
+        assert_float_eq!(to_right.gain().value(), 0.25, abs <= 0.);
+
+        // only the edge of the handle is removed
+        let removed = src.connect_with_gain(&right, 1.);
+        removed.disconnect();
+        removed.disconnect();
+
+        drop(to_left);
+
+        let output = context.start_rendering_sync();
+        output
+            .get_channel_data(0)
+            .iter()
+            .for_each(|&v| assert_float_eq!(v, 1.5, abs <= 1e-6));
+        output
+            .get_channel_data(1)
+            .iter()
+            .for_each(|&v| assert_float_eq!(v, 0.25, abs <= 1e-6));
+    }
+}
@@ -112,6 +112,7 @@ impl AudioScheduledSourceNode for ConstantSourceNode {
 
         self.start_stop_count += 1;
         self.registration.post_message(Schedule::Start(when));
+        self.registration.context().notify_source_start(when);
     }
 
     fn stop(&mut self) {
@@ -137,7 +138,7 @@ impl ConstantSourceNode {
             let ConstantSourceOptions { offset } = options;
 
             let param_options = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("offset"),
                 min_value: f32::MIN,
                 max_value: f32::MAX,
                 default_value: 1.,
@@ -4,6 +4,7 @@ use fft_convolver::FFTConvolver;
 
 use crate::buffer::AudioBuffer;
 use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::media_streams::ResampleQuality;
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
@@ -13,7 +14,7 @@ use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, Channe
 
 /// Scale buffer by an equal-power normalization
 // see - <https://webaudio.github.io/web-audio-api/#dom-convolvernode-normalize>
-fn normalize_buffer(buffer: &AudioBuffer) -> f32 {
+pub(crate) fn normalize_buffer(buffer: &AudioBuffer) -> f32 {
     let gain_calibration = 0.00125;
     let gain_calibration_sample_rate = 44100.;
     let min_power = 0.000125;
@@ -81,6 +82,54 @@ impl Default for ConvolverOptions {
     }
 }
 
+/// Options for preparing an impulse response with
+/// [`BaseAudioContext::prepare_impulse_response`]
+#[derive(Clone, Debug)]
+pub struct ImpulseResponseOptions {
+    /// Trim the leading sample frames of which all samples are below this threshold (absolute
+    /// value). `None` disables trimming.
+    pub trim_threshold: Option<f32>,
+    /// Bake the equal-power normalization of the `ConvolverNode` into the buffer.
+    ///
+    /// When set, the `ConvolverNode` should be created with `disable_normalization` to prevent
+    /// the normalization from being applied twice.
+    pub normalize: bool,
+    /// Gain applied to the buffer, after normalization
+    pub gain: f32,
+}
+
+impl Default for ImpulseResponseOptions {
+    fn default() -> Self {
+        Self {
+            trim_threshold: None,
+            normalize: false,
+            gain: 1.,
+        }
+    }
+}
+
+impl ImpulseResponseOptions {
+    /// Resample, trim and normalize the given impulse response
+    pub(crate) fn apply(&self, mut buffer: AudioBuffer, sample_rate: f32) -> AudioBuffer {
+        buffer.resample(sample_rate, ResampleQuality::Linear);
+
+        if let Some(threshold) = self.trim_threshold {
+            buffer.trim_leading_silence(threshold);
+        }
+
+        if self.normalize {
+            buffer.normalize_impulse_response(self.gain);
+        } else {
+            buffer
+                .channels_mut()
+                .iter_mut()
+                .for_each(|c| c.as_mut_slice().iter_mut().for_each(|s| *s *= self.gain));
+        }
+
+        buffer
+    }
+}
+
 /// Assert that the channel count is valid for the ConvolverNode
 /// see <https://webaudio.github.io/web-audio-api/#audionode-channelcount-constraints>
 ///
@@ -120,8 +169,12 @@ fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
 /// - specification: <https://webaudio.github.io/web-audio-api/#ConvolverNode>
 /// - see also: [`BaseAudioContext::create_convolver`]
 ///
-/// The current implementation only handles mono-to-mono convolutions. The provided impulse
-/// response buffer and the input signal will be downmixed appropriately.
+/// The impulse response buffer can have 1, 2 or 4 channels. A 4-channel response is interpreted
+/// as a true-stereo response, with the channels respectively containing the L→L, L→R, R→L and
+/// R→R paths. The channel configurations are matrixed as described in
+/// <https://webaudio.github.io/web-audio-api/#Convolution-channel-configurations>: a mono input
+/// is treated as a stereo input with identical channels, and the output is always stereo unless
+/// both the input and the response are mono.
 ///
 /// # Usage
 ///
@@ -574,6 +627,22 @@ mod tests {
         assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 1E-6);
     }
 
+    #[test]
+    fn test_normalize_true_stereo_compensation() {
+        let sample_rate = 44100.;
+        let channel = vec![0.5, -0.25, 0.125, 0.];
+
+        let stereo = AudioBuffer::from(vec![channel.clone(); 2], sample_rate);
+        let true_stereo = AudioBuffer::from(vec![channel; 4], sample_rate);
+
+        // same RMS power, but the true-stereo response sums two paths per output channel
+        assert_float_eq!(
+            normalize_buffer(&true_stereo),
+            0.5 * normalize_buffer(&stereo),
+            abs <= 1E-6
+        );
+    }
+
     fn test_convolve(signal: &[f32], impulse_resp: Option<Vec<f32>>, length: usize) -> AudioBuffer {
         let sample_rate = 44100.;
         let mut context = OfflineAudioContext::new(1, length, sample_rate);
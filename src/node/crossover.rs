@@ -0,0 +1,447 @@
+use std::f64::consts::{PI, SQRT_2};
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Assert that the crossover frequencies are valid for the given sample rate
+///
+/// # Panics
+///
+/// This function panics if:
+/// - no frequencies are given, or more than 31 (one output per band, 32 being defined by the
+///   MAX_CHANNELS constant)
+/// - the frequencies are not strictly increasing
+/// - any frequency is outside the ]0, nyquist[ range
+#[track_caller]
+#[inline(always)]
+fn assert_valid_frequencies(frequencies: &[f32], sample_rate: f32) {
+    assert!(
+        !frequencies.is_empty() && frequencies.len() < MAX_CHANNELS,
+        "NotSupportedError - CrossoverNode requires 1 to {} crossover frequencies, got {}",
+        MAX_CHANNELS - 1,
+        frequencies.len()
+    );
+
+    assert!(
+        frequencies.windows(2).all(|w| w[0] < w[1]),
+        "NotSupportedError - CrossoverNode frequencies must be strictly increasing, got {:?}",
+        frequencies
+    );
+
+    let nyquist = sample_rate / 2.;
+    assert!(
+        frequencies.iter().all(|&f| f > 0. && f < nyquist),
+        "NotSupportedError - CrossoverNode frequencies must be in the range ]0, {}[, got {:?}",
+        nyquist,
+        frequencies
+    );
+}
+
+/// Options for constructing a [`CrossoverNode`]
+#[derive(Clone, Debug)]
+pub struct CrossoverOptions {
+    /// The crossover frequencies in Hz, in increasing order. The node splits the input in
+    /// `frequencies.len() + 1` bands.
+    pub frequencies: Vec<f32>,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for CrossoverOptions {
+    fn default() -> Self {
+        Self {
+            frequencies: vec![200., 2000.],
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `CrossoverNode` splits its input into frequency bands, each band is sent to its own output.
+///
+/// The bands are separated by 4th order Linkwitz-Riley filters. The lower bands are compensated
+/// with allpass filters for the phase shift of the higher crossovers, so the sum of all outputs
+/// has a flat magnitude response. This makes the node suitable for multiband processing, e.g.
+/// multiband compression, where the processed bands are summed again afterwards.
+///
+/// Output 0 carries the lowest band, the last output carries the highest band.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{CrossoverNode, CrossoverOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let options = CrossoverOptions {
+///     frequencies: vec![250., 4000.],
+///     ..CrossoverOptions::default()
+/// };
+/// let crossover = CrossoverNode::new(&context, options);
+///
+/// // compress each band separately and sum them again
+/// for band in 0..crossover.number_of_outputs() {
+///     let compressor = context.create_dynamics_compressor();
+///     crossover.connect_from_output_to_input(&compressor, band, 0);
+///     compressor.connect(&context.destination());
+/// }
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&crossover);
+/// osc.start();
+/// ```
+#[derive(Debug)]
+pub struct CrossoverNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    frequencies: Vec<f32>,
+}
+
+impl AudioNode for CrossoverNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        self.frequencies.len() + 1
+    }
+}
+
+impl CrossoverNode {
+    /// Create a new `CrossoverNode`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the crossover frequencies are not strictly increasing, or if
+    /// any of them is outside the ]0, nyquist[ range.
+    pub fn new<C: BaseAudioContext>(context: &C, options: CrossoverOptions) -> Self {
+        let CrossoverOptions {
+            frequencies,
+            audio_node_options,
+        } = options;
+
+        assert_valid_frequencies(&frequencies, context.sample_rate());
+
+        context.base().register(move |registration| {
+            let render = CrossoverRenderer::new(&frequencies, context.sample_rate());
+
+            let node = CrossoverNode {
+                registration,
+                channel_config: audio_node_options.into(),
+                frequencies,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The crossover frequencies in Hz
+    pub fn frequencies(&self) -> &[f32] {
+        &self.frequencies
+    }
+}
+
+/// Second order section in transposed direct form II
+#[derive(Clone, Debug)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum BiquadKind {
+    Lowpass,
+    Highpass,
+    Allpass,
+}
+
+impl Biquad {
+    // Butterworth sections (Q = 1/sqrt(2)), see
+    // https://webaudio.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html
+    fn new(kind: BiquadKind, frequency: f32, sample_rate: f32) -> Self {
+        let w0 = 2. * PI * frequency as f64 / sample_rate as f64;
+        let (sin_w0, cos_w0) = w0.sin_cos();
+        let alpha = sin_w0 / SQRT_2; // sin(w0) / (2 * Q)
+
+        let (b0, b1, b2) = match kind {
+            BiquadKind::Lowpass => ((1. - cos_w0) / 2., 1. - cos_w0, (1. - cos_w0) / 2.),
+            BiquadKind::Highpass => ((1. + cos_w0) / 2., -(1. + cos_w0), (1. + cos_w0) / 2.),
+            BiquadKind::Allpass => (1. - alpha, -2. * cos_w0, 1. + alpha),
+        };
+        let a0 = 1. + alpha;
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: -2. * cos_w0 / a0,
+            a2: (1. - alpha) / a0,
+            z1: 0.,
+            z2: 0.,
+        }
+    }
+
+    #[inline(always)]
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+
+    fn is_active(&self) -> bool {
+        self.z1.is_normal() || self.z2.is_normal()
+    }
+}
+
+/// Filter state of a single channel
+#[derive(Clone, Debug)]
+struct CrossoverChannel {
+    // 4th order Linkwitz-Riley filters per crossover, as two cascaded Butterworth sections
+    lowpass: Vec<[Biquad; 2]>,
+    highpass: Vec<[Biquad; 2]>,
+    // phase compensation of band `k` for all crossovers above `k`
+    allpass: Vec<Vec<Biquad>>,
+}
+
+impl CrossoverChannel {
+    fn new(frequencies: &[f32], sample_rate: f32) -> Self {
+        let section = |kind, frequency| {
+            let biquad = Biquad::new(kind, frequency, sample_rate);
+            [biquad.clone(), biquad]
+        };
+
+        let lowpass = frequencies
+            .iter()
+            .map(|&f| section(BiquadKind::Lowpass, f))
+            .collect();
+        let highpass = frequencies
+            .iter()
+            .map(|&f| section(BiquadKind::Highpass, f))
+            .collect();
+        let allpass = (0..frequencies.len())
+            .map(|band| {
+                frequencies[band + 1..]
+                    .iter()
+                    .map(|&f| Biquad::new(BiquadKind::Allpass, f, sample_rate))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            lowpass,
+            highpass,
+            allpass,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.lowpass
+            .iter()
+            .chain(self.highpass.iter())
+            .flatten()
+            .chain(self.allpass.iter().flatten())
+            .any(Biquad::is_active)
+    }
+}
+
+struct CrossoverRenderer {
+    // pristine filter state, used when the number of channels grows
+    template: CrossoverChannel,
+    channels: Vec<CrossoverChannel>,
+}
+
+impl CrossoverRenderer {
+    fn new(frequencies: &[f32], sample_rate: f32) -> Self {
+        let template = CrossoverChannel::new(frequencies, sample_rate);
+        // allocate for stereo upfront
+        let channels = vec![template.clone(); 2];
+
+        Self { template, channels }
+    }
+}
+
+impl AudioProcessor for CrossoverRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input node
+        let input = &inputs[0];
+        let number_of_crossovers = outputs.len() - 1;
+
+        // handle tail time
+        if input.is_silent() && !self.channels.iter().any(CrossoverChannel::is_active) {
+            outputs.iter_mut().for_each(AudioRenderQuantum::make_silent);
+            return false;
+        }
+
+        // eventually resize state according to input number of channels
+        // if in tail time, we should continue with previous number of channels
+        let number_of_channels = if input.is_silent() {
+            self.channels.len()
+        } else {
+            input.number_of_channels()
+        };
+        if number_of_channels != self.channels.len() {
+            self.channels
+                .resize(number_of_channels, self.template.clone());
+        }
+
+        outputs
+            .iter_mut()
+            .for_each(|output| output.set_number_of_channels(number_of_channels));
+
+        for (index, state) in self.channels.iter_mut().enumerate() {
+            let mut rest = [0.; RENDER_QUANTUM_SIZE];
+            if index < input.number_of_channels() {
+                rest.iter_mut()
+                    .zip(input.channel_data(index).iter())
+                    .for_each(|(r, &i)| *r = i as f64);
+            }
+
+            for band in 0..number_of_crossovers {
+                let [lp1, lp2] = &mut state.lowpass[band];
+                let [hp1, hp2] = &mut state.highpass[band];
+                let allpass = &mut state.allpass[band];
+
+                let output = outputs[band].channel_data_mut(index);
+                output.iter_mut().zip(rest.iter_mut()).for_each(|(o, r)| {
+                    let mut low = lp2.process(lp1.process(*r));
+                    for ap in allpass.iter_mut() {
+                        low = ap.process(low);
+                    }
+                    *o = low as f32;
+                    *r = hp2.process(hp1.process(*r));
+                });
+            }
+
+            let output = outputs[number_of_crossovers].channel_data_mut(index);
+            output
+                .iter_mut()
+                .zip(rest.iter())
+                .for_each(|(o, &r)| *o = r as f32);
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.;
+    const LENGTH: usize = RENDER_QUANTUM_SIZE * 100;
+
+    // render a sine through the crossover, summing the given outputs
+    fn render_bands(frequencies: Vec<f32>, sine_frequency: f32, bands: &[usize]) -> f32 {
+        let context = OfflineAudioContext::new(1, LENGTH, SAMPLE_RATE);
+
+        let options = CrossoverOptions {
+            frequencies,
+            ..CrossoverOptions::default()
+        };
+        let crossover = CrossoverNode::new(&context, options);
+        for &band in bands {
+            crossover.connect_from_output_to_input(&context.destination(), band, 0);
+        }
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(sine_frequency);
+        osc.connect(&crossover);
+        osc.start();
+
+        let output = context.start_rendering_sync();
+        // peak amplitude in steady state
+        output.get_channel_data(0)[LENGTH / 2..]
+            .iter()
+            .fold(0., |max, s| s.abs().max(max))
+    }
+
+    #[test]
+    fn test_number_of_outputs() {
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let crossover = CrossoverNode::new(&context, CrossoverOptions::default());
+        assert_eq!(crossover.number_of_outputs(), 3);
+        assert_eq!(crossover.frequencies(), &[200., 2000.]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_frequencies_not_increasing() {
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let options = CrossoverOptions {
+            frequencies: vec![2000., 200.],
+            ..CrossoverOptions::default()
+        };
+        let _ = CrossoverNode::new(&context, options);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_frequency_above_nyquist() {
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let options = CrossoverOptions {
+            frequencies: vec![30_000.],
+            ..CrossoverOptions::default()
+        };
+        let _ = CrossoverNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_sum_is_flat() {
+        let frequencies = vec![200., 1000., 5000.];
+        let bands = [0, 1, 2, 3];
+
+        for sine_frequency in [50., 200., 500., 1000., 3000., 5000., 10000.] {
+            let amplitude = render_bands(frequencies.clone(), sine_frequency, &bands);
+            assert_float_eq!(amplitude, 1., abs <= 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_band_separation() {
+        let frequencies = vec![500.];
+
+        // crossover point, both bands are -6dB
+        let low = render_bands(frequencies.clone(), 500., &[0]);
+        assert_float_eq!(low, 0.5, abs <= 1e-2);
+        let high = render_bands(frequencies.clone(), 500., &[1]);
+        assert_float_eq!(high, 0.5, abs <= 1e-2);
+
+        // two octaves away, the other band is attenuated by 48dB
+        let low = render_bands(frequencies.clone(), 125., &[1]);
+        assert!(low < 0.01);
+        let high = render_bands(frequencies, 2000., &[0]);
+        assert!(high < 0.01);
+    }
+}
@@ -3,12 +3,38 @@ use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::RENDER_QUANTUM_SIZE;
+use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelInterpretation};
 
-use std::cell::{Cell, RefCell, RefMut};
-use std::rc::Rc;
+use std::any::Any;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+
+/// Interpolation of the signal between sample frames for fractional delay times
+///
+/// This type is not part of the Web Audio API specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayInterpolation {
+    /// Use the closest sample frame, the cheapest but the delay time is quantized
+    Nearest,
+    /// Linear interpolation between the two closest frames, attenuates high frequencies for
+    /// fractional delays
+    Linear,
+    /// Cubic Lagrange interpolation on the four closest frames, flatter frequency response
+    /// than linear interpolation for modulated delays (chorus, flanger, vibrato)
+    Cubic,
+    /// First order allpass interpolation, a flat magnitude response but the phase response
+    /// depends on the history of the signal. Best suited for fixed or slowly varying delays,
+    /// e.g. in feedback loops of physical models.
+    Allpass,
+}
+
+impl Default for DelayInterpolation {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
 
 /// Options for constructing a [`DelayNode`]
 // dictionary DelayOptions : AudioNodeOptions {
@@ -19,6 +45,10 @@ use std::rc::Rc;
 pub struct DelayOptions {
     pub max_delay_time: f64,
     pub delay_time: f64,
+    /// Interpolation for fractional delay times - default to `Linear`
+    ///
+    /// This option is not part of the Web Audio API specification.
+    pub interpolation: DelayInterpolation,
     pub audio_node_options: AudioNodeOptions,
 }
 
@@ -27,6 +57,7 @@ impl Default for DelayOptions {
         Self {
             max_delay_time: 1.,
             delay_time: 0.,
+            interpolation: DelayInterpolation::default(),
             audio_node_options: AudioNodeOptions::default(),
         }
     }
@@ -37,6 +68,8 @@ struct PlaybackInfo {
     prev_block_index: usize,
     prev_frame_index: usize,
     k: f32,
+    /// the frame after the next one is available for cubic interpolation
+    lookahead: bool,
 }
 
 /// Node that delays the incoming audio signal by a certain amount
@@ -102,6 +135,7 @@ pub struct DelayNode {
     writer_registration: AudioContextRegistration,
     delay_time: AudioParam,
     channel_config: ChannelConfig,
+    interpolation: DelayInterpolation,
 }
 
 impl AudioNode for DelayNode {
@@ -295,28 +329,30 @@ impl DelayNode {
         // Allocate large enough ring buffer to store all delayed samples.
         // We add one extra slot in the ring buffer so that reader never reads the
         // same entry in history as the writer, even if `delay_time == max_delay_time`
-        // of if `max_delay_time < quantum duration`
+        // of if `max_delay_time < quantum duration`, and another one for the frame
+        // before the delayed position required by the cubic interpolation
         let max_delay_time = options.max_delay_time;
+        let interpolation = options.interpolation;
         let num_quanta =
             (max_delay_time * sample_rate / RENDER_QUANTUM_SIZE as f64).ceil() as usize;
-        let ring_buffer = Vec::with_capacity(num_quanta + 1);
+        let ring_buffer = Vec::with_capacity(num_quanta + 2);
 
-        let shared_ring_buffer = Rc::new(RefCell::new(ring_buffer));
-        let shared_ring_buffer_clone = Rc::clone(&shared_ring_buffer);
+        let shared_ring_buffer = Arc::new(Mutex::new(ring_buffer));
+        let shared_ring_buffer_clone = Arc::clone(&shared_ring_buffer);
 
-        // shared value set by the writer when it is dropped
-        let last_written_index = Rc::new(Cell::<Option<usize>>::new(None));
-        let last_written_index_clone = Rc::clone(&last_written_index);
+        // shared value set by the writer when it is dropped, `NOT_WRITTEN` until then
+        let last_written_index = Arc::new(AtomicUsize::new(NOT_WRITTEN));
+        let last_written_index_clone = Arc::clone(&last_written_index);
 
         // shared value for reader/writer to determine who was rendered first,
         // this will indicate if the delay node acts as a cycle breaker
-        let latest_frame_written = Rc::new(Cell::new(u64::MAX));
-        let latest_frame_written_clone = Rc::clone(&latest_frame_written);
+        let latest_frame_written = Arc::new(AtomicU64::new(u64::MAX));
+        let latest_frame_written_clone = Arc::clone(&latest_frame_written);
 
         let node = context.base().register(move |writer_registration| {
             let node = context.base().register(move |reader_registration| {
                 let param_opts = AudioParamDescriptor {
-                    name: String::new(),
+                    name: String::from("delayTime"),
                     min_value: 0.,
                     max_value: max_delay_time as f32,
                     default_value: 0.,
@@ -334,6 +370,8 @@ impl DelayNode {
                     in_cycle: false,
                     last_written_index_checked: None,
                     latest_frame_written: latest_frame_written_clone,
+                    interpolation,
+                    allpass_states: [0.; MAX_CHANNELS],
                 };
 
                 let node = DelayNode {
@@ -341,6 +379,7 @@ impl DelayNode {
                     writer_registration,
                     channel_config: options.audio_node_options.into(),
                     delay_time: param,
+                    interpolation,
                 };
 
                 (node, Box::new(reader_render))
@@ -349,6 +388,7 @@ impl DelayNode {
             let writer_render = DelayWriter {
                 ring_buffer: shared_ring_buffer,
                 index: 0,
+                capacity: num_quanta + 2,
                 last_written_index,
                 latest_frame_written,
             };
@@ -363,6 +403,10 @@ impl DelayNode {
         // by the graph and the minimum delay clamped to one render quantum
         context.base().mark_cycle_breaker(&node.writer_registration);
         context.base().connect(writer_id, reader_id, 0, 0);
+        // describe the writer and the reader as a single node
+        context
+            .base()
+            .mark_part_of(&node.reader_registration, writer_id);
 
         node
     }
@@ -371,23 +415,55 @@ impl DelayNode {
     pub fn delay_time(&self) -> &AudioParam {
         &self.delay_time
     }
+
+    /// The interpolation applied for fractional delay times
+    ///
+    /// This method is not part of the Web Audio API specification.
+    #[must_use]
+    pub fn interpolation(&self) -> DelayInterpolation {
+        self.interpolation
+    }
+
+    /// Update the interpolation applied for fractional delay times
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn set_interpolation(&mut self, interpolation: DelayInterpolation) {
+        self.interpolation = interpolation;
+        self.reader_registration.post_message(interpolation);
+    }
+}
+
+/// Value of the shared `last_written_index` while the writer is alive
+const NOT_WRITTEN: usize = usize::MAX;
+
+/// Lock the ring buffer shared by the writer and the reader
+///
+/// The graph never renders them at the same time: either the writer is connected to the reader,
+/// or both are rendered serially when the connection is broken. So the lock never blocks, and
+/// failing to take it is a bug of the render graph.
+fn lock_ring_buffer(
+    ring_buffer: &Mutex<Vec<AudioRenderQuantum>>,
+) -> MutexGuard<'_, Vec<AudioRenderQuantum>> {
+    match ring_buffer.try_lock() {
+        Ok(guard) => guard,
+        // a panic of the other end has been reported already
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => {
+            panic!("DelayNode writer and reader rendered at the same time")
+        }
+    }
 }
 
 struct DelayWriter {
-    ring_buffer: Rc<RefCell<Vec<AudioRenderQuantum>>>,
+    ring_buffer: Arc<Mutex<Vec<AudioRenderQuantum>>>,
     index: usize,
-    latest_frame_written: Rc<Cell<u64>>,
-    last_written_index: Rc<Cell<Option<usize>>>,
+    capacity: usize,
+    latest_frame_written: Arc<AtomicU64>,
+    last_written_index: Arc<AtomicUsize>,
 }
 
-// SAFETY:
-// AudioRenderQuantums are not Send but we promise the `ring_buffer` Vec is
-// empty before we ship it to the render thread.
-#[allow(clippy::non_send_fields_in_send_ty)]
-unsafe impl Send for DelayWriter {}
-
 trait RingBufferChecker {
-    fn ring_buffer_mut(&self) -> RefMut<'_, Vec<AudioRenderQuantum>>;
+    fn ring_buffer_mut(&self) -> MutexGuard<'_, Vec<AudioRenderQuantum>>;
 
     // This step guarantees the ring buffer is filled with silence buffers,
     // This allow to simplify the code in both Writer and Reader as we know
@@ -408,20 +484,22 @@ trait RingBufferChecker {
 
 impl Drop for DelayWriter {
     fn drop(&mut self) {
+        // the writer may be dropped off the render thread, so it does not lock the ring buffer
         let last_written_index = if self.index == 0 {
-            self.ring_buffer.borrow().capacity() - 1
+            self.capacity - 1
         } else {
             self.index - 1
         };
 
-        self.last_written_index.set(Some(last_written_index));
+        self.last_written_index
+            .store(last_written_index, Ordering::Relaxed);
     }
 }
 
 impl RingBufferChecker for DelayWriter {
     #[inline(always)]
-    fn ring_buffer_mut(&self) -> RefMut<'_, Vec<AudioRenderQuantum>> {
-        self.ring_buffer.borrow_mut()
+    fn ring_buffer_mut(&self) -> MutexGuard<'_, Vec<AudioRenderQuantum>> {
+        lock_ring_buffer(&self.ring_buffer)
     }
 }
 
@@ -445,12 +523,13 @@ impl AudioProcessor for DelayWriter {
         self.check_ring_buffer_up_down_mix(&input);
 
         // populate ring buffer
-        let mut buffer = self.ring_buffer.borrow_mut();
+        let mut buffer = self.ring_buffer_mut();
         buffer[self.index] = input;
 
         // increment cursor and last written frame
         self.index = (self.index + 1) % buffer.capacity();
-        self.latest_frame_written.set(scope.current_frame);
+        self.latest_frame_written
+            .store(scope.current_frame, Ordering::Relaxed);
 
         // The writer end does not produce output,
         // clear the buffer so that it can be reused
@@ -490,25 +569,22 @@ impl DelayWriter {
 
 struct DelayReader {
     delay_time: AudioParamId,
-    ring_buffer: Rc<RefCell<Vec<AudioRenderQuantum>>>,
+    ring_buffer: Arc<Mutex<Vec<AudioRenderQuantum>>>,
     index: usize,
-    latest_frame_written: Rc<Cell<u64>>,
+    latest_frame_written: Arc<AtomicU64>,
     in_cycle: bool,
-    last_written_index: Rc<Cell<Option<usize>>>,
+    last_written_index: Arc<AtomicUsize>,
     // local copy of shared `last_written_index` so as to avoid render ordering issues
     last_written_index_checked: Option<usize>,
+    interpolation: DelayInterpolation,
+    // per channel previous output of the allpass interpolator
+    allpass_states: [f32; MAX_CHANNELS],
 }
 
-// SAFETY:
-// AudioRenderQuantums are not Send but we promise the `ring_buffer` Vec is
-// empty before we ship it to the render thread.
-#[allow(clippy::non_send_fields_in_send_ty)]
-unsafe impl Send for DelayReader {}
-
 impl RingBufferChecker for DelayReader {
     #[inline(always)]
-    fn ring_buffer_mut(&self) -> RefMut<'_, Vec<AudioRenderQuantum>> {
-        self.ring_buffer.borrow_mut()
+    fn ring_buffer_mut(&self) -> MutexGuard<'_, Vec<AudioRenderQuantum>> {
+        lock_ring_buffer(&self.ring_buffer)
     }
 }
 
@@ -526,7 +602,7 @@ impl AudioProcessor for DelayReader {
         // and Reader as the order of processing between them is not guaranteed.
         self.check_ring_buffer_size(output);
 
-        let ring_buffer = self.ring_buffer.borrow();
+        let ring_buffer = self.ring_buffer_mut();
 
         // we need to rely on ring buffer to know the actual number of output channels
         let number_of_channels = ring_buffer[0].number_of_channels();
@@ -534,7 +610,7 @@ impl AudioProcessor for DelayReader {
 
         if !self.in_cycle {
             // check the latest written frame by the delay writer
-            let latest_frame_written = self.latest_frame_written.get();
+            let latest_frame_written = self.latest_frame_written.load(Ordering::Relaxed);
             // if the delay writer has not rendered before us, the cycle breaker has been applied
             self.in_cycle = latest_frame_written != scope.current_frame;
             // once we store in_cycle = true, we do not want to go back to false
@@ -566,6 +642,7 @@ impl AudioProcessor for DelayReader {
                     prev_block_index,
                     prev_frame_index,
                     k,
+                    lookahead,
                 } = playback_infos[i - 1];
 
                 let mut prev_block_index = prev_block_index;
@@ -580,6 +657,7 @@ impl AudioProcessor for DelayReader {
                     prev_block_index,
                     prev_frame_index,
                     k,
+                    lookahead,
                 };
             }
         } else {
@@ -607,10 +685,14 @@ impl AudioProcessor for DelayReader {
         let mut is_actively_processing = false;
 
         // render channels aligned
+        let interpolation = self.interpolation;
+        let ring_length = ring_buffer.len();
+
         for (channel_number, output_channel) in output.channels_mut().iter_mut().enumerate() {
             // store channel data locally and update pointer only when needed
             let mut block_index = playback_infos[0].prev_block_index;
             let mut channel_data = ring_buffer[block_index].channel_data(channel_number);
+            let mut allpass_state = self.allpass_states[channel_number];
 
             output_channel
                 .iter_mut()
@@ -620,6 +702,7 @@ impl AudioProcessor for DelayReader {
                         prev_block_index,
                         prev_frame_index,
                         k,
+                        lookahead,
                     } = *infos;
 
                     // find next sample address
@@ -627,7 +710,7 @@ impl AudioProcessor for DelayReader {
                     let mut next_frame_index = prev_frame_index + 1;
 
                     if next_frame_index >= RENDER_QUANTUM_SIZE {
-                        next_block_index = (next_block_index + 1) % ring_buffer.len();
+                        next_block_index = (next_block_index + 1) % ring_length;
                         next_frame_index = 0;
                     }
 
@@ -649,7 +732,48 @@ impl AudioProcessor for DelayReader {
 
                     let next_sample = channel_data[next_frame_index];
 
-                    let value = (1. - k).mul_add(prev_sample, k * next_sample);
+                    let value = match interpolation {
+                        DelayInterpolation::Nearest => {
+                            if k < 0.5 {
+                                prev_sample
+                            } else {
+                                next_sample
+                            }
+                        }
+                        DelayInterpolation::Cubic if lookahead => {
+                            // outer frames of the 4 points neighborhood
+                            let before_sample = if prev_frame_index == 0 {
+                                let index = (prev_block_index + ring_length - 1) % ring_length;
+                                ring_buffer[index].channel_data(channel_number)
+                                    [RENDER_QUANTUM_SIZE - 1]
+                            } else {
+                                ring_buffer[prev_block_index].channel_data(channel_number)
+                                    [prev_frame_index - 1]
+                            };
+                            let after_sample = if next_frame_index == RENDER_QUANTUM_SIZE - 1 {
+                                let index = (next_block_index + 1) % ring_length;
+                                ring_buffer[index].channel_data(channel_number)[0]
+                            } else {
+                                channel_data[next_frame_index + 1]
+                            };
+
+                            lagrange_interpolation(
+                                [before_sample, prev_sample, next_sample, after_sample],
+                                k,
+                            )
+                        }
+                        // the delay is too short to access the frame after next, fall back to
+                        // linear interpolation
+                        DelayInterpolation::Cubic | DelayInterpolation::Linear => {
+                            (1. - k).mul_add(prev_sample, k * next_sample)
+                        }
+                        DelayInterpolation::Allpass => {
+                            // fractional part of the delay relative to `next_sample`
+                            let eta = k / (2. - k);
+                            allpass_state = eta.mul_add(next_sample - allpass_state, prev_sample);
+                            allpass_state
+                        }
+                    };
 
                     if value.is_normal() {
                         is_actively_processing = true;
@@ -657,6 +781,8 @@ impl AudioProcessor for DelayReader {
 
                     *o = value;
                 });
+
+            self.allpass_states[channel_number] = allpass_state;
         }
 
         if !is_actively_processing {
@@ -671,7 +797,8 @@ impl AudioProcessor for DelayReader {
         // we need this local copy because if the writer has been processed
         // before the reader, the direct check against `self.last_written_index`
         // would be true earlier than we want
-        let last_written_index = self.last_written_index.get();
+        let last_written_index = Some(self.last_written_index.load(Ordering::Relaxed))
+            .filter(|&index| index != NOT_WRITTEN);
 
         if last_written_index.is_some() && self.last_written_index_checked.is_none() {
             self.last_written_index_checked = last_written_index;
@@ -681,6 +808,28 @@ impl AudioProcessor for DelayReader {
 
         true
     }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(&interpolation) = msg.downcast_ref::<DelayInterpolation>() {
+            self.interpolation = interpolation;
+            return;
+        }
+
+        log::warn!("DelayReader: Dropping incoming message {msg:?}");
+    }
+}
+
+/// Cubic Lagrange interpolation at `k` in [0, 1[ between the two middle points of `samples`
+#[inline(always)]
+pub(crate) fn lagrange_interpolation(samples: [f32; 4], k: f32) -> f32 {
+    let [xm1, x0, x1, x2] = samples;
+
+    let c_m1 = -k * (k - 1.) * (k - 2.) / 6.;
+    let c_0 = (k + 1.) * (k - 1.) * (k - 2.) / 2.;
+    let c_1 = -(k + 1.) * k * (k - 2.) / 2.;
+    let c_2 = (k + 1.) * k * (k - 1.) / 6.;
+
+    c_m1 * xm1 + c_0 * x0 + c_1 * x1 + c_2 * x2
 }
 
 impl DelayReader {
@@ -735,10 +884,20 @@ impl DelayReader {
         // as position is negative k will be what we expect
         let k = (position - position_floored) as f32;
 
+        // the frame after next must have been written already: in a cycle the writer
+        // renders after the reader so the current block is not available
+        let min_lookahead_samples = if in_cycle {
+            RENDER_QUANTUM_SIZE as f64 + 2.
+        } else {
+            2.
+        };
+        let lookahead = num_samples >= min_lookahead_samples;
+
         PlaybackInfo {
             prev_block_index: prev_block_index as usize,
             prev_frame_index: prev_frame_index as usize,
             k,
+            lookahead,
         }
     }
 }
@@ -1203,4 +1362,72 @@ mod tests {
 
         assert_float_eq!(channel[..], expected[..], abs_all <= 1e-5);
     }
+
+    fn render_delayed_sine(delay_frames: f32, interpolation: DelayInterpolation) -> Vec<f32> {
+        let sample_rate = 48_000.;
+        let length = 8 * 128;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let mut delay = context.create_delay(1.);
+        delay.set_interpolation(interpolation);
+        assert_eq!(delay.interpolation(), interpolation);
+        delay.delay_time.set_value(delay_frames / sample_rate);
+        delay.connect(&context.destination());
+
+        let mut buffer = context.create_buffer(1, length, sample_rate);
+        let sine: Vec<f32> = (0..length)
+            .map(|i| (2. * std::f32::consts::PI * 1000. * i as f32 / sample_rate).sin())
+            .collect();
+        buffer.copy_to_channel(&sine, 0);
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&delay);
+        src.start();
+
+        let result = context.start_rendering_sync();
+        result.get_channel_data(0).to_vec()
+    }
+
+    // maximum error of the delayed sine, after the transients have settled
+    fn delayed_sine_error(output: &[f32], delay_frames: f32) -> f32 {
+        output
+            .iter()
+            .enumerate()
+            .skip(512)
+            .map(|(i, o)| {
+                let t = (i as f32 - delay_frames) / 48_000.;
+                (o - (2. * std::f32::consts::PI * 1000. * t).sin()).abs()
+            })
+            .fold(0., f32::max)
+    }
+
+    #[test]
+    fn test_interpolation_nearest() {
+        let output = render_delayed_sine(200.3, DelayInterpolation::Nearest);
+        assert!(delayed_sine_error(&output, 200.) < 1e-4);
+
+        let output = render_delayed_sine(200.7, DelayInterpolation::Nearest);
+        assert!(delayed_sine_error(&output, 201.) < 1e-4);
+    }
+
+    #[test]
+    fn test_interpolation_cubic() {
+        let linear = render_delayed_sine(200.5, DelayInterpolation::Linear);
+        let cubic = render_delayed_sine(200.5, DelayInterpolation::Cubic);
+
+        // linear interpolation attenuates the signal at half sample delays
+        assert!(delayed_sine_error(&linear, 200.5) > 1e-3);
+        assert!(delayed_sine_error(&cubic, 200.5) < 1e-4);
+    }
+
+    #[test]
+    fn test_interpolation_allpass() {
+        let output = render_delayed_sine(200.5, DelayInterpolation::Allpass);
+        assert!(delayed_sine_error(&output, 200.5) < 1e-3);
+
+        // integer delays are exact
+        let output = render_delayed_sine(200., DelayInterpolation::Allpass);
+        assert!(delayed_sine_error(&output, 200.) < 1e-5);
+    }
 }
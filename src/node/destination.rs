@@ -1,9 +1,17 @@
-use crate::context::{AudioContextRegistration, BaseAudioContext};
+use std::any::Any;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext, IdleMonitor};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
+use crate::MAX_CHANNELS;
 
-use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
+use super::{
+    scrub_non_finite, AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode,
+    ChannelInterpretation, DestinationGuard,
+};
 
 /// The AudioDestinationNode interface represents the terminal node of an audio
 /// graph in a given context. usually the speakers of your device, or the node that
@@ -110,7 +118,13 @@ impl AudioDestinationNode {
                 registration,
                 channel_config,
             };
-            let proc = DestinationRenderer {};
+            let proc = DestinationRenderer {
+                guard: Arc::clone(context.base().destination_guard()),
+                idle_monitor: Arc::clone(context.base().idle_monitor()),
+                last_active: 0.,
+                idle_notified: false,
+                channel_map: Vec::new(),
+            };
 
             (node, Box::new(proc))
         })
@@ -135,9 +149,200 @@ impl AudioDestinationNode {
     pub fn max_channel_count(&self) -> usize {
         self.registration.context().base().max_channel_count()
     }
+
+    /// Route the channels of the destination to specific channels of the device
+    ///
+    /// For each channel of the device, the map holds the index of the destination channel routed
+    /// to it, or `None` to keep it silent. Device channels beyond the end of the map are silent
+    /// too, and an empty map restores the default one-to-one routing. This allows to send e.g. a
+    /// stereo mix to channels 9 and 10 of a multichannel interface, see also the
+    /// [`MAX_CHANNELS`](crate::MAX_CHANNELS) constant to address interfaces with more than 32
+    /// channels.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the map has more entries than [`max_channel_count`](Self::max_channel_count)
+    /// or refers to a destination channel index not smaller than [`MAX_CHANNELS`]
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext};
+    ///
+    /// let context = AudioContext::default();
+    ///
+    /// // play the stereo output on the third and fourth channel of the device
+    /// context
+    ///     .destination()
+    ///     .set_output_channel_map(vec![None, None, Some(0), Some(1)]);
+    /// ```
+    pub fn set_output_channel_map(&self, channel_map: Vec<Option<usize>>) {
+        assert!(
+            channel_map.len() <= self.max_channel_count(),
+            "IndexSizeError - Invalid number of channels: {:?} is greater than maxChannelCount ({:?})",
+            channel_map.len(),
+            self.max_channel_count()
+        );
+
+        if let Some(index) = channel_map.iter().flatten().find(|&&i| i >= MAX_CHANNELS) {
+            panic!(
+                "IndexSizeError - Invalid destination channel: {:?} is outside range [0, {:?}[",
+                index, MAX_CHANNELS
+            );
+        }
+
+        self.registration.post_message(channel_map);
+    }
+
+    /// Insert the node in the main bus, processing the whole output of the context
+    ///
+    /// All the connections to the destination, made before or after this call, are routed
+    /// through the inserts of the main bus, in the order of insertion: the first insert receives
+    /// the sum of the connections and the last insert feeds the destination. This allows to add
+    /// e.g. a limiter or a meter to the output of an application, without changing how its nodes
+    /// are connected. The insert should not be connected to the destination itself, its first
+    /// output is routed by the main bus.
+    ///
+    /// The node stays inserted when its handle is dropped, until
+    /// [`clear_effects`](Self::clear_effects) is called.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic when the node belongs to another context, does not have an input and an output,
+    /// is already inserted or is connected to the destination
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext};
+    /// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, LimiterNode};
+    ///
+    /// let context = AudioContext::default();
+    ///
+    /// // limit the output of the context, however its nodes are connected
+    /// let limiter = LimiterNode::new(&context, Default::default());
+    /// context.destination().insert_effect(&limiter);
+    ///
+    /// let mut osc = context.create_oscillator();
+    /// osc.connect(&context.destination());
+    /// osc.start();
+    /// ```
+    pub fn insert_effect(&self, node: &dyn AudioNode) {
+        assert!(
+            self.context() == node.context(),
+            "InvalidAccessError - Attempting to insert a node from a different context",
+        );
+        assert!(
+            node.number_of_inputs() > 0 && node.number_of_outputs() > 0,
+            "InvalidAccessError - an insert of the main bus needs an input and an output",
+        );
+        assert!(
+            node.registration().id() != self.registration().id(),
+            "InvalidAccessError - the destination cannot be inserted in the main bus",
+        );
+
+        self.context().insert_main_bus(node.registration().id());
+    }
+
+    /// Remove the node from the main bus, see [`insert_effect`](Self::insert_effect)
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic when the node is not inserted in the main bus
+    pub fn remove_effect(&self, node: &dyn AudioNode) {
+        self.context().remove_main_bus(node.registration().id());
+    }
+
+    /// Remove all the inserts from the main bus, see [`insert_effect`](Self::insert_effect)
+    ///
+    /// The inserts whose handle has been dropped are released.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn clear_effects(&self) {
+        self.context().clear_main_bus();
+    }
+
+    /// The number of nodes inserted in the main bus, see [`insert_effect`](Self::insert_effect)
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn number_of_effects(&self) -> usize {
+        self.context().main_bus_len()
+    }
+
+    /// Replace NaN and infinite samples reaching the output with silence
+    ///
+    /// This prevents a single misbehaving processor from sending garbage or full-scale noise to
+    /// the speakers. Use a [`GuardNode`](super::GuardNode) to guard individual sources.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn set_guard(&self, enabled: bool) {
+        self.registration
+            .context()
+            .destination_guard()
+            .enabled
+            .store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether NaN and infinite samples are replaced with silence, see
+    /// [`AudioDestinationNode::set_guard`]
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn guard(&self) -> bool {
+        self.registration
+            .context()
+            .destination_guard()
+            .enabled
+            .load(Ordering::Relaxed)
+    }
+
+    /// The number of NaN and infinite samples replaced with silence so far
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn non_finite_count(&self) -> u64 {
+        self.registration
+            .context()
+            .destination_guard()
+            .count
+            .load(Ordering::Relaxed)
+    }
+}
+
+struct DestinationRenderer {
+    guard: Arc<DestinationGuard>,
+    idle_monitor: Arc<IdleMonitor>,
+    /// Context time of the last audible render quantum, or of the last started source
+    last_active: f64,
+    /// The idle watcher has been told that the graph is idle
+    idle_notified: bool,
+    /// For each device channel, the destination channel routed to it, empty for the default
+    channel_map: Vec<Option<usize>>,
 }
 
-struct DestinationRenderer {}
+impl DestinationRenderer {
+    /// Notify the idle watcher once the output has been silent for the idle timeout, without any
+    /// source starting in the meantime
+    fn detect_idle(&mut self, output: &AudioRenderQuantum, current_time: f64) {
+        let monitor = &self.idle_monitor;
+        if monitor.active.swap(false, Ordering::Relaxed) || !output.is_silent() {
+            self.last_active = current_time;
+        }
+
+        let last_active = self
+            .last_active
+            .max(monitor.last_start.load(Ordering::Relaxed));
+        if current_time - last_active < monitor.timeout.load(Ordering::Relaxed) {
+            self.idle_notified = false;
+        } else if !self.idle_notified {
+            self.idle_notified = monitor.notify_idle();
+        }
+    }
+}
 
 impl AudioProcessor for DestinationRenderer {
     fn process(
@@ -145,7 +350,7 @@ impl AudioProcessor for DestinationRenderer {
         inputs: &[AudioRenderQuantum],
         outputs: &mut [AudioRenderQuantum],
         _params: AudioParamValues<'_>,
-        _scope: &AudioWorkletGlobalScope,
+        scope: &AudioWorkletGlobalScope,
     ) -> bool {
         // single input/output node
         let input = &inputs[0];
@@ -154,10 +359,192 @@ impl AudioProcessor for DestinationRenderer {
         // just move input to output
         *output = input.clone();
 
+        self.detect_idle(output, scope.current_time);
+
+        // a silent output is padded with silence for all device channels anyway
+        if !self.channel_map.is_empty() && !output.is_silent() {
+            let silence = input.channel_data(0).silence();
+            output.set_number_of_channels(self.channel_map.len());
+
+            for (channel, source) in self.channel_map.iter().enumerate() {
+                *output.channel_data_mut(channel) = match source {
+                    Some(index) if *index < input.number_of_channels() => {
+                        input.channel_data(*index).clone()
+                    }
+                    _ => silence.clone(),
+                };
+            }
+        }
+
+        if self.guard.enabled.load(Ordering::Relaxed) && !output.is_silent() {
+            let count = scrub_non_finite(output);
+            if count > 0 {
+                self.guard.count.fetch_add(count, Ordering::Relaxed);
+            }
+        }
+
         true
     }
 
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(channel_map) = msg.downcast_mut::<Vec<Option<usize>>>() {
+            // swap so the old map is deallocated outside of the render thread
+            std::mem::swap(&mut self.channel_map, channel_map);
+            return;
+        }
+
+        log::warn!("DestinationRenderer: Dropping incoming message {msg:?}");
+    }
+
     fn has_side_effects(&self) -> bool {
         true // speaker output
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::RENDER_QUANTUM_SIZE;
+
+    use super::*;
+
+    #[test]
+    fn test_output_channel_map() {
+        let mut context = OfflineAudioContext::new(4, RENDER_QUANTUM_SIZE, 48_000.);
+        let destination = context.destination();
+        destination.set_output_channel_map(vec![None, Some(1), Some(0)]);
+
+        let mut buffer = context.create_buffer(2, RENDER_QUANTUM_SIZE, 48_000.);
+        buffer.copy_to_channel(&[1.; RENDER_QUANTUM_SIZE], 0);
+        buffer.copy_to_channel(&[2.; RENDER_QUANTUM_SIZE], 1);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&destination);
+        src.start();
+
+        // the stereo input is up-mixed to [1., 2., 0., 0.] before it is routed
+        let output = context.start_rendering_sync();
+        for (channel, expected) in [0., 2., 1., 0.].into_iter().enumerate() {
+            assert_float_eq!(
+                output.get_channel_data(channel)[..],
+                [expected; RENDER_QUANTUM_SIZE][..],
+                abs_all <= 0.
+            );
+        }
+    }
+
+    /// Render a constant signal of 0.75 through the given inserts of the main bus
+    fn render_main_bus(setup: impl FnOnce(&OfflineAudioContext)) -> f32 {
+        let mut context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48_000.);
+
+        let mut src = context.create_constant_source();
+        src.offset().set_value(0.75);
+        src.connect(&context.destination());
+        src.start();
+
+        setup(&context);
+
+        let output = context.start_rendering_sync();
+        output.get_channel_data(0)[0]
+    }
+
+    #[test]
+    fn test_main_bus_order() {
+        let output = render_main_bus(|context| {
+            let gain = context.create_gain();
+            gain.gain().set_value(2.);
+            let mut clip = context.create_wave_shaper();
+            clip.set_curve(vec![-1., 0., 1.]);
+
+            let destination = context.destination();
+            destination.insert_effect(&gain);
+            destination.insert_effect(&clip);
+            assert_eq!(destination.number_of_effects(), 2);
+        });
+        // amplified, then clipped
+        assert_float_eq!(output, 1., abs <= 1e-6);
+
+        let output = render_main_bus(|context| {
+            let gain = context.create_gain();
+            gain.gain().set_value(2.);
+            let mut clip = context.create_wave_shaper();
+            clip.set_curve(vec![-1., 0., 1.]);
+
+            let destination = context.destination();
+            destination.insert_effect(&clip);
+            destination.insert_effect(&gain);
+        });
+        // clipped, then amplified
+        assert_float_eq!(output, 1.5, abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_main_bus_later_connections() {
+        let output = render_main_bus(|context| {
+            let gain = context.create_gain();
+            gain.gain().set_value(0.5);
+            context.destination().insert_effect(&gain);
+
+            // connections made after the insertion are routed through the main bus too
+            let mut src = context.create_constant_source();
+            src.offset().set_value(0.25);
+            src.connect(&context.destination());
+            src.start();
+        });
+        assert_float_eq!(output, 0.5, abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_main_bus_remove() {
+        let output = render_main_bus(|context| {
+            let gain = context.create_gain();
+            gain.gain().set_value(0.5);
+            let destination = context.destination();
+            destination.insert_effect(&gain);
+            destination.remove_effect(&gain);
+            assert_eq!(destination.number_of_effects(), 0);
+        });
+        assert_float_eq!(output, 0.75, abs <= 0.);
+    }
+
+    #[test]
+    fn test_main_bus_dropped_handle() {
+        let output = render_main_bus(|context| {
+            let gain = context.create_gain();
+            gain.gain().set_value(0.5);
+            context.destination().insert_effect(&gain);
+            // the insert stays alive
+        });
+        assert_float_eq!(output, 0.375, abs <= 1e-6);
+
+        let output = render_main_bus(|context| {
+            let gain = context.create_gain();
+            gain.gain().set_value(0.5);
+            context.destination().insert_effect(&gain);
+            drop(gain);
+            context.destination().clear_effects();
+        });
+        assert_float_eq!(output, 0.75, abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_main_bus_connected_insert() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48_000.);
+        let gain = context.create_gain();
+        gain.connect(&context.destination());
+        context.destination().insert_effect(&gain);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_output_channel_map_too_long() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 48_000.);
+        context
+            .destination()
+            .set_output_channel_map(vec![Some(0), Some(1), None]);
+    }
+}
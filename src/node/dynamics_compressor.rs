@@ -187,7 +187,7 @@ impl DynamicsCompressorNode {
             // attack, knee, ratio, release and threshold have automation rate constraints
             // https://webaudio.github.io/web-audio-api/#audioparam-automation-rate-constraints
             let attack_param_opts = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("attack"),
                 min_value: 0.,
                 max_value: 1.,
                 default_value: 0.003,
@@ -199,7 +199,7 @@ impl DynamicsCompressorNode {
             attack_param.set_value(options.attack);
 
             let knee_param_opts = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("knee"),
                 min_value: 0.,
                 max_value: 40.,
                 default_value: 30.,
@@ -211,7 +211,7 @@ impl DynamicsCompressorNode {
             knee_param.set_value(options.knee);
 
             let ratio_param_opts = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("ratio"),
                 min_value: 1.,
                 max_value: 20.,
                 default_value: 12.,
@@ -223,7 +223,7 @@ impl DynamicsCompressorNode {
             ratio_param.set_value(options.ratio);
 
             let release_param_opts = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("release"),
                 min_value: 0.,
                 max_value: 1.,
                 default_value: 0.25,
@@ -235,7 +235,7 @@ impl DynamicsCompressorNode {
             release_param.set_value(options.release);
 
             let threshold_param_opts = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("threshold"),
                 min_value: -100.,
                 max_value: 0.,
                 default_value: -24.,
@@ -318,12 +318,6 @@ struct DynamicsCompressorRenderer {
     prev_detector_value: f32,
 }
 
-// SAFETY:
-// AudioRenderQuantums are not Send but we promise the `ring_buffer` Vec is
-// empty before we ship it to the render thread.
-#[allow(clippy::non_send_fields_in_send_ty)]
-unsafe impl Send for DynamicsCompressorRenderer {}
-
 // https://webaudio.github.io/web-audio-api/#DynamicsCompressorOptions-processing
 // see also https://www.eecs.qmul.ac.uk/~josh/documents/2012/GiannoulisMassbergReiss-dynamicrangecompression-JAES2012.pdf
 // follow Fig. 7 (c) diagram in paper
@@ -0,0 +1,431 @@
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Level below which the signal in the feedback loop is considered silent
+const SILENCE_THRESHOLD: f32 = 1e-5;
+
+/// Options for constructing an [`EchoNode`]
+#[derive(Clone, Debug)]
+pub struct EchoOptions {
+    /// Maximum delay time in seconds, the length of the delay line
+    pub max_delay_time: f64,
+    /// Time in seconds between the echoes
+    pub delay_time: f32,
+    /// Gain applied to the signal fed back into the delay line
+    pub feedback: f32,
+    /// Cutoff frequency in Hz of the lowpass filter in the feedback loop
+    pub damping: f32,
+    /// Gain of the echoes in the output
+    pub wet: f32,
+    /// Gain of the input signal in the output
+    pub dry: f32,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for EchoOptions {
+    fn default() -> Self {
+        Self {
+            max_delay_time: 1.,
+            delay_time: 0.25,
+            feedback: 0.5,
+            damping: 5000.,
+            wet: 0.5,
+            dry: 1.,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `EchoNode` is a feedback delay with a damping filter in the loop and a wet/dry mix
+///
+/// The node implements the common echo patch of a [`DelayNode`](super::DelayNode), a lowpass
+/// [`BiquadFilterNode`](super::BiquadFilterNode) and feedback, wet and dry
+/// [`GainNode`](super::GainNode)s in a single node. Since the feedback loop runs inside the
+/// node, the delay time is not limited to one render quantum as with cycles in the audio graph,
+/// and the tail of the echoes is rendered after the input has stopped.
+///
+/// The first echo is not filtered, each repetition is darkened by the one-pole `damping` filter.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{EchoNode, EchoOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let options = EchoOptions {
+///     delay_time: 0.375,
+///     feedback: 0.6,
+///     damping: 3000.,
+///     ..EchoOptions::default()
+/// };
+/// let echo = EchoNode::new(&context, options);
+/// echo.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&echo);
+/// osc.start();
+/// osc.stop_at(0.1);
+/// ```
+#[derive(Debug)]
+pub struct EchoNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    delay_time: AudioParam,
+    feedback: AudioParam,
+    damping: AudioParam,
+    wet: AudioParam,
+    dry: AudioParam,
+}
+
+impl AudioNode for EchoNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl EchoNode {
+    /// Create a new `EchoNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics when the max delay value is smaller than zero or langer than three minutes.
+    pub fn new<C: BaseAudioContext>(context: &C, options: EchoOptions) -> Self {
+        assert!(
+            options.max_delay_time > 0. && options.max_delay_time < 180.,
+            "NotSupportedError - maxDelayTime MUST be greater than zero and less than three minutes",
+        );
+
+        let sample_rate = context.sample_rate();
+        let max_delay_time = options.max_delay_time;
+        // extra slots for the interpolation of the read position
+        let buffer_length = (max_delay_time * f64::from(sample_rate)).ceil() as usize + 2;
+
+        context.base().register(move |registration| {
+            let create_param = |name: &str, default_value, min_value, max_value, value| {
+                let descriptor = AudioParamDescriptor {
+                    name: name.to_string(),
+                    min_value,
+                    max_value,
+                    default_value,
+                    automation_rate: AutomationRate::A,
+                };
+                let (param, proc) = context.create_audio_param(descriptor, &registration);
+                param.set_value(value);
+                (param, proc)
+            };
+
+            let (delay_time_param, delay_time_proc) = create_param(
+                "delayTime",
+                0.25,
+                0.,
+                max_delay_time as f32,
+                options.delay_time,
+            );
+            let (feedback_param, feedback_proc) =
+                create_param("feedback", 0.5, -1., 1., options.feedback);
+            let (damping_param, damping_proc) =
+                create_param("damping", 5000., 0., sample_rate / 2., options.damping);
+            let (wet_param, wet_proc) = create_param("wet", 0.5, f32::MIN, f32::MAX, options.wet);
+            let (dry_param, dry_proc) = create_param("dry", 1., f32::MIN, f32::MAX, options.dry);
+
+            let render = EchoRenderer {
+                delay_time: delay_time_proc,
+                feedback: feedback_proc,
+                damping: damping_proc,
+                wet: wet_proc,
+                dry: dry_proc,
+                buffer_length,
+                delay_lines: vec![],
+                lowpass_states: vec![],
+                write_index: 0,
+                silent_frames: buffer_length,
+            };
+
+            let node = EchoNode {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                delay_time: delay_time_param,
+                feedback: feedback_param,
+                damping: damping_param,
+                wet: wet_param,
+                dry: dry_param,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// A-rate [`AudioParam`] representing the time in seconds between the echoes
+    ///
+    /// The minimum delay is one sample frame.
+    pub fn delay_time(&self) -> &AudioParam {
+        &self.delay_time
+    }
+
+    /// A-rate [`AudioParam`] representing the gain applied to the signal fed back into the
+    /// delay line, in the [-1, 1] range
+    pub fn feedback(&self) -> &AudioParam {
+        &self.feedback
+    }
+
+    /// A-rate [`AudioParam`] representing the cutoff frequency in Hz of the lowpass filter in
+    /// the feedback loop
+    ///
+    /// The filter is bypassed when the cutoff is set to the Nyquist frequency.
+    pub fn damping(&self) -> &AudioParam {
+        &self.damping
+    }
+
+    /// A-rate [`AudioParam`] representing the gain of the echoes in the output
+    pub fn wet(&self) -> &AudioParam {
+        &self.wet
+    }
+
+    /// A-rate [`AudioParam`] representing the gain of the input signal in the output
+    pub fn dry(&self) -> &AudioParam {
+        &self.dry
+    }
+}
+
+struct EchoRenderer {
+    delay_time: AudioParamId,
+    feedback: AudioParamId,
+    damping: AudioParamId,
+    wet: AudioParamId,
+    dry: AudioParamId,
+    buffer_length: usize,
+    /// per channel ring buffer of the feedback loop
+    delay_lines: Vec<Vec<f32>>,
+    /// per channel state of the damping filter
+    lowpass_states: Vec<f32>,
+    /// position of the first frame of the render quantum in the ring buffers
+    write_index: usize,
+    /// number of frames written in the delay lines since the loop was last non silent
+    silent_frames: usize,
+}
+
+impl AudioProcessor for EchoRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let tail_done = self.silent_frames >= self.buffer_length;
+        if input.is_silent() && tail_done {
+            output.make_silent();
+            return false;
+        }
+
+        *output = input.clone();
+        if input.is_silent() {
+            // render the tail with the previous number of channels
+            output.set_number_of_channels(self.delay_lines.len());
+        } else if output.number_of_channels() != self.delay_lines.len() {
+            let number_of_channels = output.number_of_channels();
+            let buffer_length = self.buffer_length;
+            self.delay_lines
+                .resize_with(number_of_channels, || vec![0.; buffer_length]);
+            self.lowpass_states.resize(number_of_channels, 0.);
+        }
+
+        let sample_rate = scope.sample_rate;
+        let nyquist = sample_rate / 2.;
+        let max_delay = (self.buffer_length - 2) as f32;
+        let delay_time = params.get(&self.delay_time);
+        let feedback = params.get(&self.feedback);
+        let damping = params.get(&self.damping);
+        let wet = params.get(&self.wet);
+        let dry = params.get(&self.dry);
+
+        // a-rate params hold either a single value or one value per frame
+        let value_at = |values: &[f32], i: usize| values[i.min(values.len() - 1)];
+
+        let buffer_length = self.buffer_length;
+        let mut loud = false;
+
+        output
+            .channels_mut()
+            .iter_mut()
+            .zip(self.delay_lines.iter_mut())
+            .zip(self.lowpass_states.iter_mut())
+            .for_each(|((channel, delay_line), lowpass)| {
+                for (i, o) in channel.iter_mut().enumerate() {
+                    let write_index = (self.write_index + i) % buffer_length;
+
+                    let delay = (value_at(&delay_time[..], i) * sample_rate).clamp(1., max_delay);
+                    let position = write_index as f32 + buffer_length as f32 - delay;
+                    let index = position.floor();
+                    let frac = position - index;
+                    let index = index as usize % buffer_length;
+                    let next = (index + 1) % buffer_length;
+                    let delayed = delay_line[index] * (1. - frac) + delay_line[next] * frac;
+
+                    // one-pole lowpass in the feedback loop
+                    let cutoff = value_at(&damping[..], i);
+                    let coef = if cutoff >= nyquist {
+                        1.
+                    } else {
+                        1. - (-2. * std::f32::consts::PI * cutoff / sample_rate).exp()
+                    };
+                    *lowpass += coef * (delayed - *lowpass);
+
+                    let x = *o;
+                    let written = x + value_at(&feedback[..], i) * *lowpass;
+                    delay_line[write_index] = written;
+                    loud |= written.abs() > SILENCE_THRESHOLD;
+
+                    *o = value_at(&dry[..], i) * x + value_at(&wet[..], i) * delayed;
+                }
+            });
+
+        self.write_index = (self.write_index + RENDER_QUANTUM_SIZE) % buffer_length;
+        self.silent_frames = if loud {
+            0
+        } else {
+            self.silent_frames.saturating_add(RENDER_QUANTUM_SIZE)
+        };
+
+        // keep alive until the delay lines have been flushed
+        self.silent_frames < self.buffer_length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.;
+
+    fn render_impulse(length: usize, options: EchoOptions) -> Vec<f32> {
+        let mut context = OfflineAudioContext::new(1, length, SAMPLE_RATE);
+
+        let echo = EchoNode::new(&context, options);
+        echo.connect(&context.destination());
+
+        let mut buffer = context.create_buffer(1, 1, SAMPLE_RATE);
+        buffer.copy_to_channel(&[1.], 0);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&echo);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        output.get_channel_data(0).to_vec()
+    }
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let echo = EchoNode::new(&context, EchoOptions::default());
+
+        assert_float_eq!(echo.delay_time().value(), 0.25, abs <= 0.);
+        assert_float_eq!(echo.delay_time().max_value(), 1., abs <= 0.);
+        assert_float_eq!(echo.feedback().value(), 0.5, abs <= 0.);
+        assert_float_eq!(echo.damping().value(), 5000., abs <= 0.);
+        assert_float_eq!(echo.wet().value(), 0.5, abs <= 0.);
+        assert_float_eq!(echo.dry().value(), 1., abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_max_delay_time() {
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let options = EchoOptions {
+            max_delay_time: 0.,
+            ..EchoOptions::default()
+        };
+        let _ = EchoNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_echoes() {
+        // delay shorter than a render quantum, without damping
+        let delay = 100;
+        let options = EchoOptions {
+            delay_time: delay as f32 / SAMPLE_RATE,
+            feedback: 0.5,
+            damping: SAMPLE_RATE / 2.,
+            wet: 1.,
+            dry: 1.,
+            ..EchoOptions::default()
+        };
+        let output = render_impulse(4 * delay, options);
+
+        let mut expected = vec![0.; 4 * delay];
+        expected[0] = 1.;
+        expected[delay] = 1.;
+        expected[2 * delay] = 0.5;
+        expected[3 * delay] = 0.25;
+        assert_float_eq!(output[..], expected[..], abs_all <= 1e-3);
+    }
+
+    #[test]
+    fn test_damping() {
+        let delay = 100;
+        let options = EchoOptions {
+            delay_time: delay as f32 / SAMPLE_RATE,
+            feedback: 1.,
+            damping: 1000.,
+            wet: 1.,
+            dry: 0.,
+            ..EchoOptions::default()
+        };
+        let output = render_impulse(3 * delay, options);
+
+        // the first echo is not filtered, the second one is smeared by the lowpass
+        assert_float_eq!(output[delay], 1., abs <= 1e-4);
+        let coef = 1. - (-2. * std::f32::consts::PI * 1000. / SAMPLE_RATE).exp();
+        assert_float_eq!(output[2 * delay], coef, abs <= 1e-4);
+        assert_float_eq!(output[2 * delay + 1], coef * (1. - coef), abs <= 1e-4);
+    }
+
+    #[test]
+    fn test_tail_after_input_stopped() {
+        // the echoes outlive the input by several render quanta
+        let delay = 3 * RENDER_QUANTUM_SIZE;
+        let options = EchoOptions {
+            delay_time: delay as f32 / SAMPLE_RATE,
+            damping: SAMPLE_RATE / 2.,
+            wet: 1.,
+            ..EchoOptions::default()
+        };
+        let output = render_impulse(3 * delay, options);
+
+        assert_float_eq!(output[delay], 1., abs <= 1e-3);
+        assert_float_eq!(output[2 * delay], 0.5, abs <= 1e-3);
+    }
+}
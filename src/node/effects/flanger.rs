@@ -0,0 +1,228 @@
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::node::{AudioNode, AudioNodeOptions, ChannelConfig};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+
+use super::modulated_delay::{ModulatedDelayParams, ModulatedDelayRenderer, Sweep};
+
+/// Maximum of the `delay_time` param of the [`FlangerNode`]
+const MAX_DELAY_TIME: f32 = 0.02;
+
+/// Options for constructing a [`FlangerNode`]
+#[derive(Clone, Debug)]
+pub struct FlangerOptions {
+    /// Frequency in Hz of the modulation
+    pub rate: f32,
+    /// Amount of modulation, in the [0, 1] range
+    pub depth: f32,
+    /// Average delay time in seconds
+    pub delay_time: f32,
+    /// Gain of the delayed signal fed back into the delay line
+    pub feedback: f32,
+    /// Balance between the input (0) and the effect (1)
+    pub mix: f32,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for FlangerOptions {
+    fn default() -> Self {
+        Self {
+            rate: 0.25,
+            depth: 0.8,
+            delay_time: 0.003,
+            feedback: 0.5,
+            mix: 0.5,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `FlangerNode` mixes the signal with a copy delayed by a few milliseconds, sweeping a comb
+/// filter over the spectrum
+///
+/// The delay time is modulated proportionally: at full `depth` it sweeps from zero up to twice
+/// `delay_time`. The `feedback` accentuates the resonances of the comb filter, negative values
+/// emphasize the odd harmonics.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::effects::{FlangerNode, FlangerOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let flanger = FlangerNode::new(&context, FlangerOptions::default());
+/// flanger.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&flanger);
+/// osc.start();
+/// ```
+#[derive(Debug)]
+pub struct FlangerNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    rate: AudioParam,
+    depth: AudioParam,
+    delay_time: AudioParam,
+    feedback: AudioParam,
+    mix: AudioParam,
+}
+
+impl AudioNode for FlangerNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl FlangerNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: FlangerOptions) -> Self {
+        let sample_rate = context.sample_rate();
+
+        context.base().register(move |registration| {
+            let create_param = |name: &str, default_value, min_value, max_value, value| {
+                let descriptor = AudioParamDescriptor {
+                    name: name.to_string(),
+                    min_value,
+                    max_value,
+                    default_value,
+                    automation_rate: AutomationRate::A,
+                };
+                let (param, proc) = context.create_audio_param(descriptor, &registration);
+                param.set_value(value);
+                (param, proc)
+            };
+
+            let (rate_param, rate_proc) = create_param("rate", 0.25, 0., 20., options.rate);
+            let (depth_param, depth_proc) = create_param("depth", 0.8, 0., 1., options.depth);
+            let (delay_time_param, delay_time_proc) =
+                create_param("delayTime", 0.003, 0., MAX_DELAY_TIME, options.delay_time);
+            let (feedback_param, feedback_proc) =
+                create_param("feedback", 0.5, -0.95, 0.95, options.feedback);
+            let (mix_param, mix_proc) = create_param("mix", 0.5, 0., 1., options.mix);
+
+            let param_ids = ModulatedDelayParams {
+                rate: rate_proc,
+                depth: depth_proc,
+                delay_time: delay_time_proc,
+                feedback: feedback_proc,
+                mix: mix_proc,
+            };
+            let sweep = Sweep::Proportional;
+            let max_delay_time = 2. * MAX_DELAY_TIME;
+            let render = ModulatedDelayRenderer::new(param_ids, sweep, max_delay_time, sample_rate);
+
+            let node = FlangerNode {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                rate: rate_param,
+                depth: depth_param,
+                delay_time: delay_time_param,
+                feedback: feedback_param,
+                mix: mix_param,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// A-rate [`AudioParam`] representing the frequency in Hz of the modulation
+    pub fn rate(&self) -> &AudioParam {
+        &self.rate
+    }
+
+    /// A-rate [`AudioParam`] representing the amount of modulation, in the [0, 1] range
+    pub fn depth(&self) -> &AudioParam {
+        &self.depth
+    }
+
+    /// A-rate [`AudioParam`] representing the average delay time in seconds
+    pub fn delay_time(&self) -> &AudioParam {
+        &self.delay_time
+    }
+
+    /// A-rate [`AudioParam`] representing the gain of the delayed signal fed back into the
+    /// delay line
+    pub fn feedback(&self) -> &AudioParam {
+        &self.feedback
+    }
+
+    /// A-rate [`AudioParam`] representing the balance between the input (0) and the effect (1)
+    pub fn mix(&self) -> &AudioParam {
+        &self.mix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(1, 1, 48_000.);
+        let flanger = FlangerNode::new(&context, FlangerOptions::default());
+
+        assert_float_eq!(flanger.rate().value(), 0.25, abs <= 0.);
+        assert_float_eq!(flanger.depth().value(), 0.8, abs <= 0.);
+        assert_float_eq!(flanger.delay_time().value(), 0.003, abs <= 0.);
+        assert_float_eq!(flanger.feedback().value(), 0.5, abs <= 0.);
+        assert_float_eq!(flanger.mix().value(), 0.5, abs <= 0.);
+    }
+
+    #[test]
+    fn test_feedback() {
+        // without modulation the flanger is a feedback comb filter
+        let sample_rate = 48_000.;
+        let delay = 48;
+        let mut context = OfflineAudioContext::new(1, 4 * delay, sample_rate);
+
+        let options = FlangerOptions {
+            depth: 0.,
+            delay_time: delay as f32 / sample_rate,
+            feedback: -0.5,
+            mix: 1.,
+            ..FlangerOptions::default()
+        };
+        let flanger = FlangerNode::new(&context, options);
+        flanger.connect(&context.destination());
+
+        let mut buffer = context.create_buffer(1, 1, sample_rate);
+        buffer.copy_to_channel(&[1.], 0);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&flanger);
+        src.start();
+
+        let output = context.start_rendering_sync();
+
+        let mut expected = vec![0.; 4 * delay];
+        expected[delay] = 1.;
+        expected[2 * delay] = -0.5;
+        expected[3 * delay] = 0.25;
+        assert_float_eq!(
+            output.get_channel_data(0)[..],
+            expected[..],
+            abs_all <= 1e-3
+        );
+    }
+}
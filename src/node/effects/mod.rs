@@ -0,0 +1,63 @@
+//! Modulation effects
+//!
+//! Chorus, flanger and phaser effects with their low frequency oscillator built in, so that a
+//! single node replaces the usual graph of an [`OscillatorNode`](super::OscillatorNode)
+//! modulating the `delayTime` of a [`DelayNode`](super::DelayNode) through a
+//! [`GainNode`](super::GainNode) for each voice.
+//!
+//! The effects process each channel independently, with the phase of the modulation offset by
+//! a quarter period between consecutive channels for a wider stereo image.
+//!
+//! These nodes are not part of the Web Audio API specification.
+
+use std::f64::consts::PI;
+
+use crate::RENDER_QUANTUM_SIZE;
+
+mod chorus;
+pub use chorus::*;
+
+mod flanger;
+pub use flanger::*;
+
+mod modulated_delay;
+
+mod phaser;
+pub use phaser::*;
+
+/// Level below which the signal in the effect is considered silent
+const SILENCE_THRESHOLD: f32 = 1e-5;
+
+/// a-rate params hold either a single value or one value per frame
+#[inline(always)]
+fn value_at(values: &[f32], i: usize) -> f32 {
+    values[i.min(values.len() - 1)]
+}
+
+/// Sine low frequency oscillator shared by the channels of an effect
+#[derive(Debug, Default)]
+struct Lfo {
+    /// phase in cycles, in the [0, 1[ range
+    phase: f64,
+}
+
+impl Lfo {
+    /// Compute the phase of each frame of the render quantum and advance the oscillator
+    fn render(&mut self, rate: &[f32], sample_rate: f32) -> [f64; RENDER_QUANTUM_SIZE] {
+        let mut phases = [0.; RENDER_QUANTUM_SIZE];
+
+        phases.iter_mut().enumerate().for_each(|(i, p)| {
+            *p = self.phase;
+            self.phase += f64::from(value_at(rate, i)) / f64::from(sample_rate);
+            self.phase -= self.phase.floor();
+        });
+
+        phases
+    }
+
+    /// Value of the oscillator in the [-1, 1] range for the given channel
+    #[inline(always)]
+    fn value(phase: f64, channel_number: usize) -> f32 {
+        (2. * PI * (phase + 0.25 * channel_number as f64)).sin() as f32
+    }
+}
@@ -0,0 +1,164 @@
+use crate::context::AudioParamId;
+use crate::node::lagrange_interpolation;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{value_at, Lfo, SILENCE_THRESHOLD};
+
+/// Minimum delay in frames, the interpolation needs two frames read before the write position
+const MIN_DELAY: f32 = 2.;
+
+/// Modulation of the delay time by the oscillator
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Sweep {
+    /// `delay_time + depth * range * lfo`
+    Offset { range: f32 },
+    /// `delay_time * (1 + depth * lfo)`
+    Proportional,
+}
+
+impl Sweep {
+    #[inline(always)]
+    fn delay(self, delay_time: f32, depth: f32, lfo: f32) -> f32 {
+        match self {
+            Self::Offset { range } => delay_time + depth * range * lfo,
+            Self::Proportional => delay_time * (1. + depth * lfo),
+        }
+    }
+}
+
+/// The ids of the params of a modulated delay effect
+pub(super) struct ModulatedDelayParams {
+    pub rate: AudioParamId,
+    pub depth: AudioParamId,
+    pub delay_time: AudioParamId,
+    pub feedback: AudioParamId,
+    pub mix: AudioParamId,
+}
+
+/// Renderer of the chorus and flanger effects, a delay line read with cubic interpolation at a
+/// position modulated by an [`Lfo`]
+pub(super) struct ModulatedDelayRenderer {
+    param_ids: ModulatedDelayParams,
+    sweep: Sweep,
+    buffer_length: usize,
+    /// per channel ring buffer
+    delay_lines: Vec<Vec<f32>>,
+    /// position of the first frame of the render quantum in the ring buffers
+    write_index: usize,
+    lfo: Lfo,
+    /// number of frames written in the delay lines since the signal was last non silent
+    silent_frames: usize,
+}
+
+impl ModulatedDelayRenderer {
+    pub fn new(
+        param_ids: ModulatedDelayParams,
+        sweep: Sweep,
+        max_delay_time: f32,
+        sample_rate: f32,
+    ) -> Self {
+        // extra slots for the 4 points of the interpolation
+        let buffer_length = (max_delay_time * sample_rate).ceil() as usize + 4;
+
+        Self {
+            param_ids,
+            sweep,
+            buffer_length,
+            delay_lines: vec![],
+            write_index: 0,
+            lfo: Lfo::default(),
+            silent_frames: buffer_length,
+        }
+    }
+}
+
+impl AudioProcessor for ModulatedDelayRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let tail_done = self.silent_frames >= self.buffer_length;
+        if input.is_silent() && tail_done {
+            output.make_silent();
+            return false;
+        }
+
+        *output = input.clone();
+        if input.is_silent() {
+            // render the tail with the previous number of channels
+            output.set_number_of_channels(self.delay_lines.len());
+        } else if output.number_of_channels() != self.delay_lines.len() {
+            let number_of_channels = output.number_of_channels();
+            let buffer_length = self.buffer_length;
+            self.delay_lines
+                .resize_with(number_of_channels, || vec![0.; buffer_length]);
+        }
+
+        let sample_rate = scope.sample_rate;
+        let rate = params.get(&self.param_ids.rate);
+        let depth = params.get(&self.param_ids.depth);
+        let delay_time = params.get(&self.param_ids.delay_time);
+        let feedback = params.get(&self.param_ids.feedback);
+        let mix = params.get(&self.param_ids.mix);
+
+        let phases = self.lfo.render(&rate[..], sample_rate);
+        let sweep = self.sweep;
+        let buffer_length = self.buffer_length;
+        let max_delay = (buffer_length - 4) as f32;
+        let write_index = self.write_index;
+        let mut loud = false;
+
+        output
+            .channels_mut()
+            .iter_mut()
+            .zip(self.delay_lines.iter_mut())
+            .enumerate()
+            .for_each(|(channel_number, (channel, delay_line))| {
+                for (i, o) in channel.iter_mut().enumerate() {
+                    let index = (write_index + i) % buffer_length;
+
+                    let lfo = Lfo::value(phases[i], channel_number);
+                    let delay =
+                        sweep.delay(value_at(&delay_time[..], i), value_at(&depth[..], i), lfo);
+                    let delay = (delay * sample_rate).clamp(MIN_DELAY, max_delay);
+
+                    let position = index as f32 + buffer_length as f32 - delay;
+                    let floor = position.floor();
+                    let k = position - floor;
+                    let floor = floor as usize;
+                    let samples = [0, 1, 2, 3].map(|offset| {
+                        delay_line[(floor + buffer_length + offset - 1) % buffer_length]
+                    });
+                    let delayed = lagrange_interpolation(samples, k);
+
+                    let x = *o;
+                    let written = x + value_at(&feedback[..], i) * delayed;
+                    delay_line[index] = written;
+                    loud |= written.abs() > SILENCE_THRESHOLD;
+
+                    let wet = value_at(&mix[..], i);
+                    *o = (1. - wet) * x + wet * delayed;
+                }
+            });
+
+        self.write_index = (write_index + RENDER_QUANTUM_SIZE) % buffer_length;
+        self.silent_frames = if loud {
+            0
+        } else {
+            self.silent_frames.saturating_add(RENDER_QUANTUM_SIZE)
+        };
+
+        // keep alive until the delay lines have been flushed
+        self.silent_frames < self.buffer_length
+    }
+}
@@ -0,0 +1,374 @@
+use std::f32::consts::PI;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::node::{AudioNode, AudioNodeOptions, ChannelConfig};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+
+use super::{value_at, Lfo, SILENCE_THRESHOLD};
+
+/// Maximum number of allpass stages of the [`PhaserNode`]
+const MAX_STAGES: usize = 12;
+
+/// Sweep range in octaves on each side of `frequency` at full depth
+const SWEEP_OCTAVES: f32 = 2.;
+
+/// Assert that the number of stages of the phaser is valid
+///
+/// # Panics
+///
+/// This function panics if the number of stages is odd, zero or larger than 12
+#[track_caller]
+#[inline(always)]
+fn assert_valid_stages(stages: usize) {
+    assert!(
+        stages > 0 && stages % 2 == 0 && stages <= MAX_STAGES,
+        "NotSupportedError - PhaserNode stages must be an even number between 2 and {}, got {}",
+        MAX_STAGES,
+        stages
+    );
+}
+
+/// Options for constructing a [`PhaserNode`]
+#[derive(Clone, Debug)]
+pub struct PhaserOptions {
+    /// Number of allpass filters in the chain, must be even. Each pair of stages adds a notch
+    /// to the response.
+    pub stages: usize,
+    /// Frequency in Hz of the modulation
+    pub rate: f32,
+    /// Amount of modulation, in the [0, 1] range
+    pub depth: f32,
+    /// Center frequency in Hz of the allpass filters
+    pub frequency: f32,
+    /// Gain of the output of the allpass chain fed back into its input
+    pub feedback: f32,
+    /// Balance between the input (0) and the effect (1), the notches are the deepest at 0.5
+    pub mix: f32,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for PhaserOptions {
+    fn default() -> Self {
+        Self {
+            stages: 4,
+            rate: 0.5,
+            depth: 1.,
+            frequency: 1000.,
+            feedback: 0.,
+            mix: 0.5,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `PhaserNode` sweeps notches over the spectrum by mixing the signal with the output of a
+/// modulated chain of allpass filters
+///
+/// At full `depth`, the center frequency of the allpass filters sweeps two octaves on each side
+/// of `frequency`.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::effects::{PhaserNode, PhaserOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let options = PhaserOptions {
+///     stages: 6,
+///     feedback: 0.5,
+///     ..PhaserOptions::default()
+/// };
+/// let phaser = PhaserNode::new(&context, options);
+/// phaser.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&phaser);
+/// osc.start();
+/// ```
+#[derive(Debug)]
+pub struct PhaserNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    stages: usize,
+    rate: AudioParam,
+    depth: AudioParam,
+    frequency: AudioParam,
+    feedback: AudioParam,
+    mix: AudioParam,
+}
+
+impl AudioNode for PhaserNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl PhaserNode {
+    /// Create a new `PhaserNode`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of stages is odd, zero or larger than 12
+    pub fn new<C: BaseAudioContext>(context: &C, options: PhaserOptions) -> Self {
+        assert_valid_stages(options.stages);
+        let nyquist = context.sample_rate() / 2.;
+
+        context.base().register(move |registration| {
+            let create_param = |name: &str, default_value, min_value, max_value, value| {
+                let descriptor = AudioParamDescriptor {
+                    name: name.to_string(),
+                    min_value,
+                    max_value,
+                    default_value,
+                    automation_rate: AutomationRate::A,
+                };
+                let (param, proc) = context.create_audio_param(descriptor, &registration);
+                param.set_value(value);
+                (param, proc)
+            };
+
+            let (rate_param, rate_proc) = create_param("rate", 0.5, 0., 20., options.rate);
+            let (depth_param, depth_proc) = create_param("depth", 1., 0., 1., options.depth);
+            let (frequency_param, frequency_proc) =
+                create_param("frequency", 1000., 0., nyquist, options.frequency);
+            let (feedback_param, feedback_proc) =
+                create_param("feedback", 0., -0.95, 0.95, options.feedback);
+            let (mix_param, mix_proc) = create_param("mix", 0.5, 0., 1., options.mix);
+
+            let render = PhaserRenderer {
+                rate: rate_proc,
+                depth: depth_proc,
+                frequency: frequency_proc,
+                feedback: feedback_proc,
+                mix: mix_proc,
+                stages: options.stages,
+                states: vec![],
+                lfo: Lfo::default(),
+                tail_pending: false,
+            };
+
+            let node = PhaserNode {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                stages: options.stages,
+                rate: rate_param,
+                depth: depth_param,
+                frequency: frequency_param,
+                feedback: feedback_param,
+                mix: mix_param,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The number of allpass filters in the chain
+    #[must_use]
+    pub fn stages(&self) -> usize {
+        self.stages
+    }
+
+    /// A-rate [`AudioParam`] representing the frequency in Hz of the modulation
+    pub fn rate(&self) -> &AudioParam {
+        &self.rate
+    }
+
+    /// A-rate [`AudioParam`] representing the amount of modulation, in the [0, 1] range
+    pub fn depth(&self) -> &AudioParam {
+        &self.depth
+    }
+
+    /// A-rate [`AudioParam`] representing the center frequency in Hz of the allpass filters
+    pub fn frequency(&self) -> &AudioParam {
+        &self.frequency
+    }
+
+    /// A-rate [`AudioParam`] representing the gain of the output of the allpass chain fed back
+    /// into its input
+    pub fn feedback(&self) -> &AudioParam {
+        &self.feedback
+    }
+
+    /// A-rate [`AudioParam`] representing the balance between the input (0) and the effect (1)
+    pub fn mix(&self) -> &AudioParam {
+        &self.mix
+    }
+}
+
+/// State of the allpass chain of a channel
+#[derive(Clone, Copy, Default)]
+struct ChannelState {
+    allpasses: [f32; MAX_STAGES],
+    /// last output of the chain, for the feedback
+    output: f32,
+}
+
+struct PhaserRenderer {
+    rate: AudioParamId,
+    depth: AudioParamId,
+    frequency: AudioParamId,
+    feedback: AudioParamId,
+    mix: AudioParamId,
+    stages: usize,
+    states: Vec<ChannelState>,
+    lfo: Lfo,
+    /// the allpass chain still rings
+    tail_pending: bool,
+}
+
+impl AudioProcessor for PhaserRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() && !self.tail_pending {
+            output.make_silent();
+            return false;
+        }
+
+        *output = input.clone();
+        if input.is_silent() {
+            // render the tail with the previous number of channels
+            output.set_number_of_channels(self.states.len());
+        } else if output.number_of_channels() != self.states.len() {
+            self.states
+                .resize(output.number_of_channels(), ChannelState::default());
+        }
+
+        let sample_rate = scope.sample_rate;
+        let rate = params.get(&self.rate);
+        let depth = params.get(&self.depth);
+        let frequency = params.get(&self.frequency);
+        let feedback = params.get(&self.feedback);
+        let mix = params.get(&self.mix);
+
+        let phases = self.lfo.render(&rate[..], sample_rate);
+        let stages = self.stages;
+        let max_frequency = 0.45 * sample_rate;
+        let mut loud = false;
+
+        output
+            .channels_mut()
+            .iter_mut()
+            .zip(self.states.iter_mut())
+            .enumerate()
+            .for_each(|(channel_number, (channel, state))| {
+                for (i, o) in channel.iter_mut().enumerate() {
+                    let lfo = Lfo::value(phases[i], channel_number);
+                    let octaves = SWEEP_OCTAVES * value_at(&depth[..], i) * lfo;
+                    let center = (value_at(&frequency[..], i) * 2_f32.powf(octaves))
+                        .clamp(10., max_frequency);
+
+                    let t = (PI * center / sample_rate).tan();
+                    let a = (t - 1.) / (t + 1.);
+
+                    let x = *o;
+                    let mut y = x + value_at(&feedback[..], i) * state.output;
+                    state.allpasses[..stages].iter_mut().for_each(|s| {
+                        let v = a.mul_add(y, *s);
+                        *s = a.mul_add(-v, y);
+                        y = v;
+                    });
+                    state.output = y;
+                    loud |= y.abs() > SILENCE_THRESHOLD;
+
+                    let wet = value_at(&mix[..], i);
+                    *o = (1. - wet) * x + wet * y;
+                }
+            });
+
+        self.tail_pending = loud;
+        loud
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(1, 1, 48_000.);
+        let phaser = PhaserNode::new(&context, PhaserOptions::default());
+
+        assert_eq!(phaser.stages(), 4);
+        assert_float_eq!(phaser.rate().value(), 0.5, abs <= 0.);
+        assert_float_eq!(phaser.depth().value(), 1., abs <= 0.);
+        assert_float_eq!(phaser.frequency().value(), 1000., abs <= 0.);
+        assert_float_eq!(phaser.feedback().value(), 0., abs <= 0.);
+        assert_float_eq!(phaser.mix().value(), 0.5, abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_odd_stages() {
+        let context = OfflineAudioContext::new(1, 1, 48_000.);
+        let options = PhaserOptions {
+            stages: 3,
+            ..PhaserOptions::default()
+        };
+        let _ = PhaserNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_notches() {
+        // the 4 stages shift the phase by 180 degrees at 414.7Hz and 2398Hz, and by 360 degrees
+        // at the center frequency
+        let sample_rate = 48_000.;
+        let length = 4096;
+
+        for (frequency, expected) in [(414.7, 0.), (2398., 0.), (1000., 1.)] {
+            let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+            let options = PhaserOptions {
+                depth: 0.,
+                ..PhaserOptions::default()
+            };
+            let phaser = PhaserNode::new(&context, options);
+            phaser.connect(&context.destination());
+
+            let mut osc = context.create_oscillator();
+            osc.frequency().set_value(frequency);
+            osc.connect(&phaser);
+            osc.start();
+
+            let output = context.start_rendering_sync();
+            let peak = output.get_channel_data(0)[length / 2..]
+                .iter()
+                .fold(0_f32, |max, s| max.max(s.abs()));
+            assert_float_eq!(peak, expected, abs <= 1e-2);
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use std::any::Any;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{assert_valid_time_value, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, ChannelConfig};
+
+/// Options for constructing an [`EnvelopeNode`]
+// @note - Does not extend AudioNodeOptions because AudioNodeOptions are
+// useless for source nodes, because they instruct how to upmix the inputs.
+#[derive(Clone, Debug)]
+pub struct EnvelopeOptions {
+    /// Time in seconds to rise from zero to one
+    pub attack: f32,
+    /// Time in seconds to fall from one to the sustain level
+    pub decay: f32,
+    /// Level held until the release
+    pub sustain: f32,
+    /// Time in seconds to fall from the sustain level to zero
+    pub release: f32,
+}
+
+impl Default for EnvelopeOptions {
+    fn default() -> Self {
+        Self {
+            attack: 0.01, // seconds
+            decay: 0.1,   // seconds
+            sustain: 0.7,
+            release: 0.3, // seconds
+        }
+    }
+}
+
+/// Instructions to start or end a note
+#[derive(Debug, Copy, Clone)]
+enum Trigger {
+    Attack(f64),
+    Release(f64),
+}
+
+impl Trigger {
+    fn time(self) -> f64 {
+        match self {
+            Self::Attack(time) | Self::Release(time) => time,
+        }
+    }
+}
+
+/// `EnvelopeNode` is a source of ADSR envelopes
+///
+/// The output is a mono control signal in the [0, 1] range. After a call to
+/// [`trigger_attack_at`](Self::trigger_attack_at), it rises linearly to one in `attack`
+/// seconds, falls linearly to the `sustain` level in `decay` seconds and holds it. After a call
+/// to [`trigger_release_at`](Self::trigger_release_at), it falls linearly to zero in `release`
+/// seconds. A new attack starts from the current level, so retriggering a note does not click.
+///
+/// Connect the output to an [`AudioParam`], typically the `gain` of a [`GainNode`](super::GainNode)
+/// whose value is set to zero, to shape the notes of a synthesizer voice.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{EnvelopeNode, EnvelopeOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let vca = context.create_gain();
+/// vca.gain().set_value(0.);
+/// vca.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&vca);
+/// osc.start();
+///
+/// let envelope = EnvelopeNode::new(&context, EnvelopeOptions::default());
+/// envelope.connect(vca.gain());
+///
+/// // play a note of half a second
+/// let now = context.current_time();
+/// envelope.trigger_attack_at(now);
+/// envelope.trigger_release_at(now + 0.5);
+/// ```
+#[derive(Debug)]
+pub struct EnvelopeNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    attack: AudioParam,
+    decay: AudioParam,
+    sustain: AudioParam,
+    release: AudioParam,
+}
+
+impl AudioNode for EnvelopeNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl EnvelopeNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: EnvelopeOptions) -> Self {
+        context.base().register(move |registration| {
+            let create_param = |name: &str, default_value, max_value, value| {
+                let descriptor = AudioParamDescriptor {
+                    name: name.to_string(),
+                    min_value: 0.,
+                    max_value,
+                    default_value,
+                    automation_rate: AutomationRate::K,
+                };
+                let (mut param, proc) = context.create_audio_param(descriptor, &registration);
+                param.set_automation_rate_constrained(true);
+                param.set_value(value);
+                (param, proc)
+            };
+
+            let (attack_param, attack_proc) = create_param("attack", 0.01, 60., options.attack);
+            let (decay_param, decay_proc) = create_param("decay", 0.1, 60., options.decay);
+            let (sustain_param, sustain_proc) = create_param("sustain", 0.7, 1., options.sustain);
+            let (release_param, release_proc) = create_param("release", 0.3, 60., options.release);
+
+            let render = EnvelopeRenderer {
+                attack: attack_proc,
+                decay: decay_proc,
+                sustain: sustain_proc,
+                release: release_proc,
+                triggers: Vec::new(),
+                stage: Stage::Idle,
+                level: 0.,
+                release_rate: 0.,
+            };
+
+            let node = EnvelopeNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+                attack: attack_param,
+                decay: decay_param,
+                sustain: sustain_param,
+                release: release_param,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// K-rate [`AudioParam`] representing the time in seconds to rise from zero to one
+    #[must_use]
+    pub fn attack(&self) -> &AudioParam {
+        &self.attack
+    }
+
+    /// K-rate [`AudioParam`] representing the time in seconds to fall from one to the
+    /// sustain level
+    #[must_use]
+    pub fn decay(&self) -> &AudioParam {
+        &self.decay
+    }
+
+    /// K-rate [`AudioParam`] representing the level held until the release
+    #[must_use]
+    pub fn sustain(&self) -> &AudioParam {
+        &self.sustain
+    }
+
+    /// K-rate [`AudioParam`] representing the time in seconds to fall from the sustain level
+    /// to zero
+    #[must_use]
+    pub fn release(&self) -> &AudioParam {
+        &self.release
+    }
+
+    /// Schedule the start of a note at the given time
+    ///
+    /// # Panics
+    ///
+    /// Panics if `when` is negative
+    pub fn trigger_attack_at(&self, when: f64) {
+        assert_valid_time_value(when);
+        self.registration.context().notify_source_start(when);
+        self.registration.post_message(Trigger::Attack(when));
+    }
+
+    /// Schedule the end of a note at the given time
+    ///
+    /// # Panics
+    ///
+    /// Panics if `when` is negative
+    pub fn trigger_release_at(&self, when: f64) {
+        assert_valid_time_value(when);
+        self.registration.post_message(Trigger::Release(when));
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Stage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+struct EnvelopeRenderer {
+    attack: AudioParamId,
+    decay: AudioParamId,
+    sustain: AudioParamId,
+    release: AudioParamId,
+    /// scheduled triggers, sorted by time
+    triggers: Vec<Trigger>,
+    stage: Stage,
+    level: f32,
+    /// decrease of the level per second during the release
+    release_rate: f32,
+}
+
+impl AudioProcessor for EnvelopeRenderer {
+    fn process(
+        &mut self,
+        _inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single output node
+        let output = &mut outputs[0];
+
+        let dt = 1. / scope.sample_rate as f64;
+        let next_block_time = scope.current_time + dt * RENDER_QUANTUM_SIZE as f64;
+
+        let idle_block = self.stage == Stage::Idle
+            && self
+                .triggers
+                .first()
+                .map_or(true, |trigger| trigger.time() >= next_block_time);
+        if idle_block {
+            output.make_silent();
+            return !self.triggers.is_empty();
+        }
+
+        let attack = params.get(&self.attack)[0];
+        let decay = params.get(&self.decay)[0];
+        let sustain = params.get(&self.sustain)[0];
+        let release = params.get(&self.release)[0];
+        let dt_f32 = dt as f32;
+
+        output.force_mono();
+        let mut current_time = scope.current_time;
+
+        output.channel_data_mut(0).iter_mut().for_each(|o| {
+            while let Some(&trigger) = self.triggers.first() {
+                if trigger.time() > current_time {
+                    break;
+                }
+                self.triggers.remove(0);
+
+                match trigger {
+                    Trigger::Attack(_) => self.stage = Stage::Attack,
+                    Trigger::Release(_) if self.stage != Stage::Idle => {
+                        self.stage = Stage::Release;
+                        self.release_rate = self.level / release.max(dt_f32);
+                    }
+                    Trigger::Release(_) => (),
+                }
+            }
+
+            *o = self.level;
+
+            match self.stage {
+                Stage::Idle => (),
+                Stage::Attack => {
+                    self.level += dt_f32 / attack.max(dt_f32);
+                    if self.level >= 1. {
+                        self.level = 1.;
+                        self.stage = Stage::Decay;
+                    }
+                }
+                Stage::Decay => {
+                    self.level -= (1. - sustain) * dt_f32 / decay.max(dt_f32);
+                    if self.level <= sustain {
+                        self.level = sustain;
+                        self.stage = Stage::Sustain;
+                    }
+                }
+                Stage::Sustain => self.level = sustain,
+                Stage::Release => {
+                    self.level -= self.release_rate * dt_f32;
+                    if self.level <= 0. {
+                        self.level = 0.;
+                        self.stage = Stage::Idle;
+                    }
+                }
+            }
+
+            current_time += dt;
+        });
+
+        true
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(&trigger) = msg.downcast_ref::<Trigger>() {
+            // keep the triggers sorted, in order of scheduling for equal times
+            let index = self
+                .triggers
+                .partition_point(|other| other.time() <= trigger.time());
+            self.triggers.insert(index, trigger);
+            return;
+        }
+
+        log::warn!("EnvelopeRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(1, 1, 48_000.);
+        let envelope = EnvelopeNode::new(&context, EnvelopeOptions::default());
+
+        assert_eq!(envelope.number_of_inputs(), 0);
+        assert_float_eq!(envelope.attack().value(), 0.01, abs <= 0.);
+        assert_float_eq!(envelope.decay().value(), 0.1, abs <= 0.);
+        assert_float_eq!(envelope.sustain().value(), 0.7, abs <= 0.);
+        assert_float_eq!(envelope.release().value(), 0.3, abs <= 0.);
+    }
+
+    #[test]
+    fn test_adsr() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 4800, sample_rate);
+
+        let options = EnvelopeOptions {
+            attack: 0.01,
+            decay: 0.01,
+            sustain: 0.5,
+            release: 0.01,
+        };
+        let envelope = EnvelopeNode::new(&context, options);
+        envelope.connect(&context.destination());
+        envelope.trigger_attack_at(0.);
+        envelope.trigger_release_at(0.04);
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+
+        // attack
+        assert_float_eq!(output[0], 0., abs <= 1e-6);
+        assert_float_eq!(output[240], 0.5, abs <= 3e-3);
+        assert_float_eq!(output[480], 1., abs <= 3e-3);
+        // decay
+        assert_float_eq!(output[720], 0.75, abs <= 3e-3);
+        // sustain
+        assert_float_eq!(output[1000], 0.5, abs <= 3e-3);
+        assert_float_eq!(output[1920], 0.5, abs <= 3e-3);
+        // release
+        assert_float_eq!(output[2160], 0.25, abs <= 3e-3);
+        assert_float_eq!(output[2400], 0., abs <= 3e-3);
+        assert_float_eq!(output[4799], 0., abs <= 0.);
+    }
+
+    #[test]
+    fn test_retrigger_from_current_level() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 4800, sample_rate);
+
+        let options = EnvelopeOptions {
+            attack: 0.01,
+            sustain: 1.,
+            release: 0.02,
+            ..EnvelopeOptions::default()
+        };
+        let envelope = EnvelopeNode::new(&context, options);
+        envelope.connect(&context.destination());
+        envelope.trigger_attack_at(0.);
+        envelope.trigger_release_at(0.02);
+        envelope.trigger_attack_at(0.03);
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+
+        // half way through the release, the new attack rises from 0.5
+        assert_float_eq!(output[1440], 0.5, abs <= 3e-3);
+        assert_float_eq!(output[1560], 0.75, abs <= 3e-3);
+        assert_float_eq!(output[1680], 1., abs <= 3e-3);
+    }
+}
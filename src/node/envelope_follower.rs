@@ -0,0 +1,283 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::AtomicF32;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Level below which the envelope is considered silent
+const SILENCE_THRESHOLD: f32 = 1e-5;
+
+/// Options for constructing an [`EnvelopeFollowerNode`]
+#[derive(Clone, Debug)]
+pub struct EnvelopeFollowerOptions {
+    /// Time in seconds for the envelope to rise
+    pub attack: f32,
+    /// Time in seconds for the envelope to fall
+    pub release: f32,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for EnvelopeFollowerOptions {
+    fn default() -> Self {
+        Self {
+            attack: 0.01, // seconds
+            release: 0.1, // seconds
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `EnvelopeFollowerNode` tracks the amplitude of its input
+///
+/// The envelope is the peak level of the input across all channels, smoothed by the `attack`
+/// and `release` times. It is rendered as a mono signal on the output, which can be connected
+/// to an [`AudioParam`] to modulate it, and can be read from the control thread with
+/// [`value`](Self::value), e.g. to drive a level meter.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{EnvelopeFollowerNode, EnvelopeFollowerOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let mut drums = context.create_buffer_source();
+/// let follower = EnvelopeFollowerNode::new(&context, EnvelopeFollowerOptions::default());
+/// drums.connect(&follower);
+///
+/// // duck the pad with the drums
+/// let mut pad = context.create_oscillator();
+/// let gain = context.create_gain();
+/// pad.connect(&gain);
+/// gain.connect(&context.destination());
+///
+/// let inverter = context.create_gain();
+/// inverter.gain().set_value(-1.);
+/// follower.connect(&inverter);
+/// inverter.connect(gain.gain());
+///
+/// pad.start();
+/// drums.start();
+///
+/// loop {
+///     println!("drums level: {}", follower.value());
+///     std::thread::sleep(std::time::Duration::from_millis(50));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct EnvelopeFollowerNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    attack: AudioParam,
+    release: AudioParam,
+    value: Arc<AtomicF32>,
+}
+
+impl AudioNode for EnvelopeFollowerNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl EnvelopeFollowerNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: EnvelopeFollowerOptions) -> Self {
+        context.base().register(move |registration| {
+            let create_param = |name: &str, default_value, value| {
+                let descriptor = AudioParamDescriptor {
+                    name: name.to_string(),
+                    min_value: 0.,
+                    max_value: 10.,
+                    default_value,
+                    automation_rate: AutomationRate::K,
+                };
+                let (mut param, proc) = context.create_audio_param(descriptor, &registration);
+                param.set_automation_rate_constrained(true);
+                param.set_value(value);
+                (param, proc)
+            };
+
+            let (attack_param, attack_proc) = create_param("attack", 0.01, options.attack);
+            let (release_param, release_proc) = create_param("release", 0.1, options.release);
+
+            let value = Arc::new(AtomicF32::new(0.));
+
+            let render = EnvelopeFollowerRenderer {
+                attack: attack_proc,
+                release: release_proc,
+                envelope: 0.,
+                value: Arc::clone(&value),
+            };
+
+            let node = EnvelopeFollowerNode {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                attack: attack_param,
+                release: release_param,
+                value,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// K-rate [`AudioParam`] representing the time in seconds for the envelope to rise
+    #[must_use]
+    pub fn attack(&self) -> &AudioParam {
+        &self.attack
+    }
+
+    /// K-rate [`AudioParam`] representing the time in seconds for the envelope to fall
+    #[must_use]
+    pub fn release(&self) -> &AudioParam {
+        &self.release
+    }
+
+    /// The value of the envelope at the end of the last rendered block
+    #[must_use]
+    pub fn value(&self) -> f32 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+struct EnvelopeFollowerRenderer {
+    attack: AudioParamId,
+    release: AudioParamId,
+    envelope: f32,
+    value: Arc<AtomicF32>,
+}
+
+impl AudioProcessor for EnvelopeFollowerRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() && self.envelope < SILENCE_THRESHOLD {
+            if self.envelope != 0. {
+                self.envelope = 0.;
+                self.value.store(0., Ordering::Relaxed);
+            }
+            output.make_silent();
+            return false;
+        }
+
+        let sample_rate = scope.sample_rate;
+        let attack = params.get(&self.attack)[0];
+        let release = params.get(&self.release)[0];
+
+        let attack_tau = (-1. / (attack * sample_rate)).exp();
+        let release_tau = (-1. / (release * sample_rate)).exp();
+
+        output.set_number_of_channels(1);
+        let mut envelope = self.envelope;
+
+        output
+            .channel_data_mut(0)
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, o)| {
+                let level = input
+                    .channels()
+                    .iter()
+                    .fold(0_f32, |max, channel| max.max(channel[i].abs()));
+
+                let tau = if level > envelope {
+                    attack_tau
+                } else {
+                    release_tau
+                };
+                envelope = level + tau * (envelope - level);
+                *o = envelope;
+            });
+
+        self.envelope = envelope;
+        self.value.store(envelope, Ordering::Relaxed);
+
+        // keep rendering the release
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(1, 1, 48_000.);
+        let follower = EnvelopeFollowerNode::new(&context, EnvelopeFollowerOptions::default());
+
+        assert_float_eq!(follower.attack().value(), 0.01, abs <= 0.);
+        assert_float_eq!(follower.release().value(), 0.1, abs <= 0.);
+        assert_float_eq!(follower.value(), 0., abs <= 0.);
+    }
+
+    #[test]
+    fn test_attack_and_release() {
+        let sample_rate = 48_000.;
+        let length = 4800;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let options = EnvelopeFollowerOptions {
+            attack: 0.001,
+            release: 0.005,
+            ..EnvelopeFollowerOptions::default()
+        };
+        let follower = EnvelopeFollowerNode::new(&context, options);
+        follower.connect(&context.destination());
+
+        // stereo input, the envelope is the peak of the channels
+        let mut buffer = context.create_buffer(2, length / 2, sample_rate);
+        buffer.copy_to_channel(&vec![-0.5; length / 2], 1);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&follower);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+
+        // one time constant
+        assert_float_eq!(output[47], 0.5 * (1. - (-1_f32).exp()), abs <= 1e-2);
+        // settled
+        assert_float_eq!(output[length / 2 - 1], 0.5, abs <= 1e-3);
+        // released by one time constant
+        assert_float_eq!(output[length / 2 + 239], 0.5 * (-1_f32).exp(), abs <= 1e-2);
+        // back to silence
+        assert_float_eq!(output[length - 1], 0., abs <= 1e-3);
+        assert!(follower.value() < 1e-3);
+    }
+}
@@ -0,0 +1,439 @@
+use std::f32::consts::PI;
+
+use fft_convolver::FFTConvolver;
+use realfft::RealFftPlanner;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Assert that the FIR taps are valid
+///
+/// # Panics
+///
+/// This function panics if no taps are given, or if any tap is not finite
+#[track_caller]
+#[inline(always)]
+fn assert_valid_taps(taps: &[f32]) {
+    assert!(
+        !taps.is_empty(),
+        "NotSupportedError - FirFilterNode requires at least one tap"
+    );
+
+    assert!(
+        taps.iter().all(|t| t.is_finite()),
+        "InvalidStateError - FirFilterNode taps must be finite"
+    );
+}
+
+/// Options for constructing a [`FirFilterNode`]
+#[derive(Clone, Debug)]
+pub struct FirFilterOptions {
+    /// The impulse response of the filter
+    pub taps: Vec<f32>,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for FirFilterOptions {
+    fn default() -> Self {
+        Self {
+            taps: vec![1.],
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+impl FirFilterOptions {
+    /// Design linear phase taps following a magnitude response
+    ///
+    /// The magnitude response is given as linear gains at the given frequencies (in Hz), and
+    /// linearly interpolated in between. Below the first and above the last frequency the
+    /// response is held constant. The filter is designed with the frequency sampling method and
+    /// a Blackman window, so the resolution of the response is about `4 * sample_rate /
+    /// number_of_taps`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - `frequencies` and `magnitudes` are empty or have different lengths
+    /// - `frequencies` are not strictly increasing
+    /// - `number_of_taps` is even, a linear phase filter with an integer latency requires an odd
+    ///   number of taps
+    pub fn from_magnitude_response(
+        frequencies: &[f32],
+        magnitudes: &[f32],
+        number_of_taps: usize,
+        sample_rate: f32,
+    ) -> Self {
+        assert!(
+            !frequencies.is_empty() && frequencies.len() == magnitudes.len(),
+            "NotSupportedError - frequencies and magnitudes must have the same non zero length"
+        );
+        assert!(
+            frequencies.windows(2).all(|w| w[0] < w[1]),
+            "NotSupportedError - frequencies must be strictly increasing, got {:?}",
+            frequencies
+        );
+        assert!(
+            number_of_taps % 2 == 1,
+            "NotSupportedError - number_of_taps must be odd, got {}",
+            number_of_taps
+        );
+
+        // oversample the frequency grid to limit time aliasing
+        let fft_size = (number_of_taps * 4).next_power_of_two();
+        let mut planner = RealFftPlanner::<f32>::new();
+        let c2r = planner.plan_fft_inverse(fft_size);
+        let mut spectrum = c2r.make_input_vec();
+        let mut impulse = c2r.make_output_vec();
+
+        // zero phase spectrum
+        spectrum.iter_mut().enumerate().for_each(|(k, c)| {
+            let frequency = k as f32 * sample_rate / fft_size as f32;
+            c.re = interpolate(frequencies, magnitudes, frequency);
+            c.im = 0.;
+        });
+        c2r.process(&mut spectrum, &mut impulse).unwrap();
+
+        // center the zero phase impulse and window it
+        let half = number_of_taps / 2;
+        let taps = (0..number_of_taps)
+            .map(|n| {
+                let window = if number_of_taps == 1 {
+                    1.
+                } else {
+                    let x = 2. * PI * n as f32 / (number_of_taps - 1) as f32;
+                    0.42 - 0.5 * x.cos() + 0.08 * (2. * x).cos()
+                };
+                impulse[(n + fft_size - half) % fft_size] / fft_size as f32 * window
+            })
+            .collect();
+
+        Self {
+            taps,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// Linear interpolation of the magnitude response, held constant outside the frequency range
+fn interpolate(frequencies: &[f32], magnitudes: &[f32], frequency: f32) -> f32 {
+    match frequencies.iter().position(|&f| f > frequency) {
+        Some(0) => magnitudes[0],
+        None => magnitudes[magnitudes.len() - 1],
+        Some(i) => {
+            let t = (frequency - frequencies[i - 1]) / (frequencies[i] - frequencies[i - 1]);
+            magnitudes[i - 1] + t * (magnitudes[i] - magnitudes[i - 1])
+        }
+    }
+}
+
+/// `FirFilterNode` is a finite impulse response filter
+///
+/// The filter convolves its input with the given taps using uniformly partitioned FFT
+/// convolution, so long filters remain affordable. Symmetric taps yield a linear phase filter,
+/// e.g. for mastering-style equalization without the phase distortion of the
+/// [`BiquadFilterNode`](super::BiquadFilterNode) and [`IIRFilterNode`](super::IIRFilterNode).
+/// Such filters delay the signal by half their length, see [`FirFilterNode::latency`].
+///
+/// The taps can be designed from a magnitude response with
+/// [`FirFilterOptions::from_magnitude_response`].
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{FirFilterNode, FirFilterOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // gentle high frequency shelf
+/// let options = FirFilterOptions::from_magnitude_response(
+///     &[0., 2000., 8000.],
+///     &[1., 1., 0.5],
+///     1023,
+///     context.sample_rate(),
+/// );
+/// let filter = FirFilterNode::new(&context, options);
+/// filter.connect(&context.destination());
+/// println!("latency: {}s", filter.latency());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&filter);
+/// osc.start();
+/// ```
+#[derive(Debug)]
+pub struct FirFilterNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    taps: Vec<f32>,
+}
+
+impl AudioNode for FirFilterNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl FirFilterNode {
+    /// Create a new `FirFilterNode`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if no taps are given, or if any tap is not finite.
+    pub fn new<C: BaseAudioContext>(context: &C, options: FirFilterOptions) -> Self {
+        let FirFilterOptions {
+            taps,
+            audio_node_options,
+        } = options;
+
+        assert_valid_taps(&taps);
+
+        context.base().register(move |registration| {
+            let render = FirFilterRenderer::new(taps.clone());
+
+            let node = FirFilterNode {
+                registration,
+                channel_config: audio_node_options.into(),
+                taps,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The taps of the filter
+    pub fn taps(&self) -> &[f32] {
+        &self.taps
+    }
+
+    /// The latency in seconds of the filter, assuming linear phase (symmetric) taps
+    ///
+    /// A linear phase filter delays the signal by half its length.
+    #[must_use]
+    pub fn latency(&self) -> f64 {
+        (self.taps.len() - 1) as f64 / 2. / self.registration.context().sample_rate() as f64
+    }
+}
+
+pub(crate) struct FirFilterRenderer {
+    taps: Vec<f32>,
+    convolvers: Vec<FFTConvolver<f32>>,
+    tail_count: usize,
+}
+
+impl FirFilterRenderer {
+    pub(crate) fn new(taps: Vec<f32>) -> Self {
+        let mut renderer = Self {
+            taps,
+            convolvers: Vec::new(),
+            tail_count: 0,
+        };
+        // allocate for stereo upfront
+        renderer.resize(2);
+
+        renderer
+    }
+
+    fn resize(&mut self, number_of_channels: usize) {
+        self.convolvers.truncate(number_of_channels);
+
+        // @note - same partition size as the ConvolverNode
+        let partition_size = RENDER_QUANTUM_SIZE * 8;
+        for _ in self.convolvers.len()..number_of_channels {
+            let mut convolver = FFTConvolver::<f32>::default();
+            convolver
+                .init(partition_size, &self.taps)
+                .expect("Unable to initialize convolution engine");
+            self.convolvers.push(convolver);
+        }
+    }
+}
+
+impl AudioProcessor for FirFilterRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // handle tail time
+        if input.is_silent() {
+            if self.tail_count >= self.taps.len() {
+                output.make_silent();
+                return false;
+            }
+            self.tail_count += RENDER_QUANTUM_SIZE;
+        } else {
+            self.tail_count = 0;
+        }
+
+        // eventually resize state according to input number of channels
+        // if in tail time, we should continue with previous number of channels
+        let number_of_channels = if input.is_silent() {
+            self.convolvers.len()
+        } else {
+            input.number_of_channels()
+        };
+        if number_of_channels != self.convolvers.len() {
+            self.resize(number_of_channels);
+        }
+
+        output.set_number_of_channels(number_of_channels);
+
+        for (index, convolver) in self.convolvers.iter_mut().enumerate() {
+            let i = if input.is_silent() {
+                &input.channel_data(0)[..]
+            } else {
+                &input.channel_data(index)[..]
+            };
+            let o = &mut output.channel_data_mut(index)[..];
+            let _ = convolver.process(i, o);
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.;
+
+    fn render(signal: &[f32], taps: Vec<f32>, length: usize) -> Vec<f32> {
+        let mut context = OfflineAudioContext::new(1, length, SAMPLE_RATE);
+
+        let options = FirFilterOptions {
+            taps,
+            ..FirFilterOptions::default()
+        };
+        let filter = FirFilterNode::new(&context, options);
+        filter.connect(&context.destination());
+
+        let mut buffer = context.create_buffer(1, signal.len(), SAMPLE_RATE);
+        buffer.copy_to_channel(signal, 0);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&filter);
+        src.start();
+
+        context.start_rendering_sync().get_channel_data(0).to_vec()
+    }
+
+    // magnitude response of the taps at the given frequency
+    fn magnitude(taps: &[f32], frequency: f32) -> f32 {
+        let w = 2. * PI * frequency / SAMPLE_RATE;
+        let (re, im) = taps.iter().enumerate().fold((0., 0.), |(re, im), (n, &t)| {
+            let (sin, cos) = (w * n as f32).sin_cos();
+            (re + t * cos, im - t * sin)
+        });
+        (re * re + im * im).sqrt()
+    }
+
+    #[test]
+    fn test_identity() {
+        let signal = [1., 0.5, -0.25, 0.];
+        let result = render(&signal, vec![1.], RENDER_QUANTUM_SIZE);
+        assert_float_eq!(result[..4], signal[..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_delay_and_tail() {
+        let length = RENDER_QUANTUM_SIZE * 4;
+        let mut taps = vec![0.; 300];
+        taps[2] = 0.5;
+        taps[299] = 1.;
+
+        let result = render(&[1.], taps, length);
+
+        let mut expected = vec![0.; length];
+        expected[2] = 0.5;
+        expected[299] = 1.;
+        assert_float_eq!(result[..], expected[..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_latency() {
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let options = FirFilterOptions {
+            taps: vec![0.25, 0.5, 0.25],
+            ..FirFilterOptions::default()
+        };
+        let filter = FirFilterNode::new(&context, options);
+        assert_float_eq!(filter.latency(), 1. / SAMPLE_RATE as f64, abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_taps() {
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let options = FirFilterOptions {
+            taps: vec![],
+            ..FirFilterOptions::default()
+        };
+        let _ = FirFilterNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_from_magnitude_response_flat() {
+        let options = FirFilterOptions::from_magnitude_response(&[0.], &[1.], 63, SAMPLE_RATE);
+        let mut expected = vec![0.; 63];
+        expected[31] = 1.;
+        assert_float_eq!(options.taps[..], expected[..], abs_all <= 1e-5);
+    }
+
+    #[test]
+    fn test_from_magnitude_response_lowpass() {
+        let taps = FirFilterOptions::from_magnitude_response(
+            &[0., 2000., 4000.],
+            &[1., 1., 0.],
+            255,
+            SAMPLE_RATE,
+        )
+        .taps;
+
+        // linear phase
+        let reversed: Vec<f32> = taps.iter().rev().copied().collect();
+        assert_float_eq!(taps[..], reversed[..], abs_all <= 1e-6);
+
+        assert_float_eq!(magnitude(&taps, 500.), 1., abs <= 1e-2);
+        assert_float_eq!(magnitude(&taps, 3000.), 0.5, abs <= 5e-2);
+        assert!(magnitude(&taps, 10_000.) < 1e-2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_magnitude_response_even_taps() {
+        let _ = FirFilterOptions::from_magnitude_response(&[0.], &[1.], 64, SAMPLE_RATE);
+    }
+}
@@ -55,7 +55,7 @@ impl GainNode {
     pub fn new<C: BaseAudioContext>(context: &C, options: GainOptions) -> Self {
         context.base().register(move |registration| {
             let param_opts = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("gain"),
                 min_value: f32::MIN,
                 max_value: f32::MAX,
                 default_value: 1.,
@@ -0,0 +1,417 @@
+use std::any::Any;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Options for constructing a [`GainStageNode`]
+#[derive(Clone, Debug)]
+pub struct GainStageOptions {
+    /// Initial gain trim in dB
+    pub trim: f32,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for GainStageOptions {
+    fn default() -> Self {
+        Self {
+            trim: 0.,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `GainStageNode` applies a gain trim in dB and measures the peak level of the signal going
+/// through it
+///
+/// Insert it at the points of the graph where the level should be watched, e.g. in front of
+/// each effect of a chain, and read the peaks after some rendering. See [`GainStaging`] to
+/// derive the trims from the measured peaks.
+///
+/// This node is not part of the Web Audio API specification.
+#[derive(Debug)]
+pub struct GainStageNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    trim: AudioParam,
+    input_peak: Arc<AtomicF32>,
+    output_peak: Arc<AtomicF32>,
+}
+
+impl AudioNode for GainStageNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl GainStageNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: GainStageOptions) -> Self {
+        context.base().register(move |registration| {
+            let descriptor = AudioParamDescriptor {
+                name: String::from("trim"),
+                min_value: f32::MIN,
+                max_value: f32::MAX,
+                default_value: 0.,
+                automation_rate: AutomationRate::A,
+            };
+            let (param, proc) = context.create_audio_param(descriptor, &registration);
+            param.set_value(options.trim);
+
+            let input_peak = Arc::new(AtomicF32::new(0.));
+            let output_peak = Arc::new(AtomicF32::new(0.));
+
+            let render = GainStageRenderer {
+                trim: proc,
+                input_peak: Arc::clone(&input_peak),
+                output_peak: Arc::clone(&output_peak),
+                peaks: [0.; 2],
+            };
+
+            let node = GainStageNode {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                trim: param,
+                input_peak,
+                output_peak,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// A-rate [`AudioParam`] representing the gain trim in dB
+    #[must_use]
+    pub fn trim(&self) -> &AudioParam {
+        &self.trim
+    }
+
+    /// Highest absolute sample value received since the last reset
+    #[must_use]
+    pub fn input_peak(&self) -> f32 {
+        self.input_peak.load(Ordering::Relaxed)
+    }
+
+    /// Highest absolute sample value sent, after the trim, since the last reset
+    #[must_use]
+    pub fn output_peak(&self) -> f32 {
+        self.output_peak.load(Ordering::Relaxed)
+    }
+
+    /// Restart the peak measurements
+    pub fn reset_peaks(&self) {
+        self.input_peak.store(0., Ordering::Relaxed);
+        self.output_peak.store(0., Ordering::Relaxed);
+        self.registration.post_message(ResetPeaks);
+    }
+}
+
+/// Message sent to the renderer to restart the peak measurements
+#[derive(Debug)]
+struct ResetPeaks;
+
+struct GainStageRenderer {
+    trim: AudioParamId,
+    input_peak: Arc<AtomicF32>,
+    output_peak: Arc<AtomicF32>,
+    /// input and output peaks since the last reset
+    peaks: [f32; 2],
+}
+
+impl AudioProcessor for GainStageRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        *output = input.clone();
+
+        if input.is_silent() {
+            return false;
+        }
+
+        let trim = params.get(&self.trim);
+        let [mut input_peak, mut output_peak] = self.peaks;
+
+        if trim.len() == 1 {
+            let gain = 10_f32.powf(trim[0] / 20.);
+            output.channels_mut().iter_mut().for_each(|channel| {
+                channel.iter_mut().for_each(|o| {
+                    input_peak = input_peak.max(o.abs());
+                    *o *= gain;
+                    output_peak = output_peak.max(o.abs());
+                });
+            });
+        } else {
+            let mut gains = [0.; RENDER_QUANTUM_SIZE];
+            gains
+                .iter_mut()
+                .zip(trim.iter())
+                .for_each(|(g, t)| *g = 10_f32.powf(t / 20.));
+            output.channels_mut().iter_mut().for_each(|channel| {
+                channel.iter_mut().zip(gains.iter()).for_each(|(o, g)| {
+                    input_peak = input_peak.max(o.abs());
+                    *o *= g;
+                    output_peak = output_peak.max(o.abs());
+                });
+            });
+        }
+
+        self.peaks = [input_peak, output_peak];
+        self.input_peak.store(input_peak, Ordering::Relaxed);
+        self.output_peak.store(output_peak, Ordering::Relaxed);
+
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if msg.downcast_ref::<ResetPeaks>().is_some() {
+            self.peaks = [0.; 2];
+            self.input_peak.store(0., Ordering::Relaxed);
+            self.output_peak.store(0., Ordering::Relaxed);
+            return;
+        }
+
+        log::warn!("GainStageRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+/// Options for constructing a [`GainStaging`] assistant
+#[derive(Clone, Debug)]
+pub struct GainStagingOptions {
+    /// Distance in dB between the peak level of each stage and full scale
+    pub headroom: f32,
+    /// Largest trim in dB, boost or cut, applied to a stage
+    pub max_trim: f32,
+}
+
+impl Default for GainStagingOptions {
+    fn default() -> Self {
+        Self {
+            headroom: 6.,
+            max_trim: 24.,
+        }
+    }
+}
+
+/// Assistant computing the gain trims that keep a chain of processing stages within a target
+/// headroom
+///
+/// Create the stages in signal order, from upstream to downstream, and insert them in the
+/// graph. Then render a representative excerpt (the calibration pass) and apply the trims.
+/// Stages that stayed silent during the calibration keep their trim.
+///
+/// The trims of the downstream stages account for the change of the upstream trims, assuming
+/// the stages form a serial chain and the processing in between is linear. Run another
+/// calibration pass to refine the trims otherwise, e.g. around compressors or distortions.
+///
+/// This is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{GainStaging, GainStagingOptions};
+///
+/// let context = AudioContext::default();
+/// let mut staging = GainStaging::new(GainStagingOptions::default());
+///
+/// let mut osc = context.create_oscillator();
+/// let input_stage = staging.create_stage(&context);
+/// osc.connect(input_stage);
+///
+/// let filter = context.create_biquad_filter();
+/// input_stage.connect(&filter);
+/// let output_stage = staging.create_stage(&context);
+/// filter.connect(output_stage);
+/// output_stage.connect(&context.destination());
+/// osc.start();
+///
+/// // calibration pass
+/// staging.start_calibration();
+/// std::thread::sleep(std::time::Duration::from_secs(2));
+/// staging.apply();
+/// ```
+#[derive(Debug)]
+pub struct GainStaging {
+    headroom: f32,
+    max_trim: f32,
+    stages: Vec<GainStageNode>,
+}
+
+impl GainStaging {
+    #[must_use]
+    pub fn new(options: GainStagingOptions) -> Self {
+        Self {
+            headroom: options.headroom,
+            max_trim: options.max_trim,
+            stages: vec![],
+        }
+    }
+
+    /// Create a new stage, downstream of the existing ones
+    pub fn create_stage<C: BaseAudioContext>(&mut self, context: &C) -> &GainStageNode {
+        let stage = GainStageNode::new(context, GainStageOptions::default());
+        self.stages.push(stage);
+        self.stages.last().unwrap()
+    }
+
+    /// The stages, in signal order
+    #[must_use]
+    pub fn stages(&self) -> &[GainStageNode] {
+        &self.stages
+    }
+
+    /// Restart the peak measurements of all stages
+    pub fn start_calibration(&self) {
+        self.stages.iter().for_each(GainStageNode::reset_peaks);
+    }
+
+    /// Trims in dB bringing the peak of each stage to the target headroom, given the peaks
+    /// measured since the calibration started
+    #[must_use]
+    pub fn suggested_trims(&self) -> Vec<f32> {
+        let target = -self.headroom;
+        // change in dB of the level at the input of the stage, due to the upstream trims
+        let mut upstream_change = 0.;
+
+        self.stages
+            .iter()
+            .map(|stage| {
+                let trim = stage.trim().value();
+                let peak = stage.input_peak();
+                if peak <= 0. {
+                    return trim;
+                }
+
+                let input_level = 20. * peak.log10() + upstream_change;
+                let suggested = (target - input_level).clamp(-self.max_trim, self.max_trim);
+                upstream_change += suggested - trim;
+
+                suggested
+            })
+            .collect()
+    }
+
+    /// Set the trims of the stages to the suggested values
+    pub fn apply(&self) {
+        self.stages
+            .iter()
+            .zip(self.suggested_trims())
+            .for_each(|(stage, trim)| {
+                stage.trim().set_value(trim);
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_trim_and_peaks() {
+        let mut context = OfflineAudioContext::new(1, 128, 48_000.);
+
+        let options = GainStageOptions {
+            trim: -6.,
+            ..GainStageOptions::default()
+        };
+        let stage = GainStageNode::new(&context, options);
+        stage.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.offset().set_value(0.5);
+        src.connect(&stage);
+        src.start();
+
+        let output = context.start_rendering_sync();
+
+        let gain = 10_f32.powf(-6. / 20.);
+        assert_float_eq!(output.get_channel_data(0)[0], 0.5 * gain, abs <= 1e-6);
+        assert_float_eq!(stage.input_peak(), 0.5, abs <= 1e-6);
+        assert_float_eq!(stage.output_peak(), 0.5 * gain, abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_suggested_trims() {
+        // a source peaking at -6dB, followed by a +12dB boost
+        let mut context = OfflineAudioContext::new(1, 128, 48_000.);
+        let mut staging = GainStaging::new(GainStagingOptions::default());
+
+        let mut src = context.create_constant_source();
+        src.offset().set_value(0.5);
+        let boost = context.create_gain();
+        boost.gain().set_value(4.);
+
+        let input_stage = staging.create_stage(&context);
+        src.connect(input_stage);
+        input_stage.connect(&boost);
+        let output_stage = staging.create_stage(&context);
+        boost.connect(output_stage);
+        output_stage.connect(&context.destination());
+        src.start();
+
+        staging.start_calibration();
+        let _ = context.start_rendering_sync();
+
+        // the input is already at -6dB, the boost then needs a -12dB cut
+        let trims = staging.suggested_trims();
+        assert_float_eq!(trims[0], 0., abs <= 0.1);
+        assert_float_eq!(trims[1], -12., abs <= 0.1);
+    }
+
+    #[test]
+    fn test_upstream_change() {
+        let mut context = OfflineAudioContext::new(1, 128, 48_000.);
+        let mut staging = GainStaging::new(GainStagingOptions::default());
+
+        let mut src = context.create_constant_source();
+        src.offset().set_value(0.125);
+
+        staging.create_stage(&context);
+        staging.create_stage(&context);
+        let stages = staging.stages();
+        src.connect(&stages[0]);
+        stages[0].connect(&stages[1]);
+        stages[1].connect(&context.destination());
+        src.start();
+
+        staging.start_calibration();
+        let _ = context.start_rendering_sync();
+
+        // the first stage boosts by 12dB, the second one is then at the target
+        let trims = staging.suggested_trims();
+        assert_float_eq!(trims[0], 12., abs <= 0.1);
+        assert_float_eq!(trims[1], 0., abs <= 0.1);
+    }
+}
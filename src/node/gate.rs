@@ -0,0 +1,350 @@
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Options for constructing a [`GateNode`]
+#[derive(Clone, Debug)]
+pub struct GateOptions {
+    /// Level in dBFS above which the gate opens
+    pub threshold: f32,
+    /// Time in seconds for the gate to open
+    pub attack: f32,
+    /// Time in seconds the gate stays open after the signal has dropped below the threshold
+    pub hold: f32,
+    /// Time in seconds for the gate to close
+    pub release: f32,
+    /// Attenuation in dB applied when the gate is closed
+    pub range: f32,
+    /// Use a second input as sidechain to key the gate
+    pub sidechain: bool,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for GateOptions {
+    fn default() -> Self {
+        Self {
+            threshold: -40., // dB
+            attack: 0.001,   // seconds
+            hold: 0.01,      // seconds
+            release: 0.1,    // seconds
+            range: -80.,     // dB
+            sidechain: false,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `GateNode` attenuates the signal when its level drops below a threshold.
+///
+/// It can be used as a noise gate, e.g. to clean up microphone input captured via
+/// [`AudioContext::create_media_stream_source`](crate::context::AudioContext::create_media_stream_source),
+/// or as a downward expander by using a moderate `range`.
+///
+/// When constructed with the `sidechain` option, the node has a second input from which the
+/// level is detected instead of the main input. The gate stays closed as long as nothing is
+/// connected to the sidechain input.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::media_devices;
+/// use web_audio_api::media_devices::MediaStreamConstraints;
+/// use web_audio_api::node::{AudioNode, GateNode, GateOptions};
+///
+/// let context = AudioContext::default();
+/// let mic = media_devices::get_user_media_sync(MediaStreamConstraints::Audio);
+/// let stream_source = context.create_media_stream_source(&mic);
+///
+/// let options = GateOptions {
+///     threshold: -50.,
+///     ..GateOptions::default()
+/// };
+/// let gate = GateNode::new(&context, options);
+/// stream_source.connect(&gate);
+/// gate.connect(&context.destination());
+/// ```
+#[derive(Debug)]
+pub struct GateNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    threshold: AudioParam,
+    attack: AudioParam,
+    hold: AudioParam,
+    release: AudioParam,
+    range: AudioParam,
+    sidechain: bool,
+}
+
+impl AudioNode for GateNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        if self.sidechain {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl GateNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: GateOptions) -> Self {
+        context.base().register(move |registration| {
+            let create_param = |name: &str, default_value, min_value, max_value, value| {
+                let descriptor = AudioParamDescriptor {
+                    name: name.to_string(),
+                    min_value,
+                    max_value,
+                    default_value,
+                    automation_rate: AutomationRate::K,
+                };
+                let (mut param, proc) = context.create_audio_param(descriptor, &registration);
+                param.set_automation_rate_constrained(true);
+                param.set_value(value);
+                (param, proc)
+            };
+
+            let (threshold_param, threshold_proc) =
+                create_param("threshold", -40., -100., 0., options.threshold);
+            let (attack_param, attack_proc) = create_param("attack", 0.001, 0., 1., options.attack);
+            let (hold_param, hold_proc) = create_param("hold", 0.01, 0., 1., options.hold);
+            let (release_param, release_proc) =
+                create_param("release", 0.1, 0., 1., options.release);
+            let (range_param, range_proc) = create_param("range", -80., -100., 0., options.range);
+
+            let render = GateRenderer {
+                threshold: threshold_proc,
+                attack: attack_proc,
+                hold: hold_proc,
+                release: release_proc,
+                range: range_proc,
+                sidechain: options.sidechain,
+                hold_counter: 0,
+                gain: 0.,
+            };
+
+            let node = GateNode {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                threshold: threshold_param,
+                attack: attack_param,
+                hold: hold_param,
+                release: release_param,
+                range: range_param,
+                sidechain: options.sidechain,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The level in dBFS above which the gate opens
+    pub fn threshold(&self) -> &AudioParam {
+        &self.threshold
+    }
+
+    /// The time in seconds for the gate to open
+    pub fn attack(&self) -> &AudioParam {
+        &self.attack
+    }
+
+    /// The time in seconds the gate stays open after the level has dropped below the threshold
+    pub fn hold(&self) -> &AudioParam {
+        &self.hold
+    }
+
+    /// The time in seconds for the gate to close
+    pub fn release(&self) -> &AudioParam {
+        &self.release
+    }
+
+    /// The attenuation in dB applied when the gate is closed
+    pub fn range(&self) -> &AudioParam {
+        &self.range
+    }
+}
+
+struct GateRenderer {
+    threshold: AudioParamId,
+    attack: AudioParamId,
+    hold: AudioParamId,
+    release: AudioParamId,
+    range: AudioParamId,
+    sidechain: bool,
+    hold_counter: usize,
+    gain: f32,
+}
+
+impl AudioProcessor for GateRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let key = if self.sidechain { &inputs[1] } else { input };
+        let output = &mut outputs[0];
+
+        let sample_rate = scope.sample_rate;
+        let threshold = 10_f32.powf(params.get(&self.threshold)[0] / 20.);
+        let attack = params.get(&self.attack)[0];
+        let hold = params.get(&self.hold)[0];
+        let release = params.get(&self.release)[0];
+        let range = 10_f32.powf(params.get(&self.range)[0] / 20.);
+
+        let attack_tau = (-1. / (attack * sample_rate)).exp();
+        let release_tau = (-1. / (release * sample_rate)).exp();
+        let hold_samples = (hold * sample_rate) as usize;
+
+        let mut gains = [0.; RENDER_QUANTUM_SIZE];
+
+        for (i, gain) in gains.iter_mut().enumerate() {
+            let level = key
+                .channels()
+                .iter()
+                .fold(0_f32, |max, channel| max.max(channel[i].abs()));
+
+            let target = if level >= threshold {
+                self.hold_counter = hold_samples;
+                1.
+            } else if self.hold_counter > 0 {
+                self.hold_counter -= 1;
+                1.
+            } else {
+                range
+            };
+
+            let tau = if target > self.gain {
+                attack_tau
+            } else {
+                release_tau
+            };
+            self.gain = target + tau * (self.gain - target);
+            *gain = self.gain;
+        }
+
+        // nothing to gate
+        if input.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        *output = input.clone();
+        output.channels_mut().iter_mut().for_each(|channel| {
+            channel
+                .iter_mut()
+                .zip(gains.iter())
+                .for_each(|(o, g)| *o *= g);
+        });
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    fn render_gate(level: f32, key_level: Option<f32>, options: GateOptions) -> Vec<f32> {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+
+        let gate = GateNode::new(&context, options);
+        gate.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.offset().set_value(level);
+        src.connect(&gate);
+        src.start();
+
+        if let Some(key_level) = key_level {
+            let mut key = context.create_constant_source();
+            key.offset().set_value(key_level);
+            key.connect_from_output_to_input(&gate, 0, 1);
+            key.start();
+        }
+
+        let output = context.start_rendering_sync();
+        output.get_channel_data(0).to_vec()
+    }
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(1, 1, 44_100.);
+        let gate = GateNode::new(&context, GateOptions::default());
+
+        assert_eq!(gate.number_of_inputs(), 1);
+        assert_float_eq!(gate.threshold().value(), -40., abs <= 0.);
+        assert_float_eq!(gate.attack().value(), 0.001, abs <= 0.);
+        assert_float_eq!(gate.hold().value(), 0.01, abs <= 0.);
+        assert_float_eq!(gate.release().value(), 0.1, abs <= 0.);
+        assert_float_eq!(gate.range().value(), -80., abs <= 0.);
+    }
+
+    #[test]
+    fn test_open_and_closed() {
+        let options = GateOptions {
+            threshold: -20.,
+            attack: 0.,
+            release: 0.,
+            range: -20.,
+            ..GateOptions::default()
+        };
+
+        // above threshold, the signal passes through
+        let output = render_gate(0.5, None, options.clone());
+        assert_float_eq!(output[..], [0.5; RENDER_QUANTUM_SIZE][..], abs_all <= 1e-6);
+
+        // below threshold, the signal is attenuated by range
+        let output = render_gate(0.05, None, options);
+        assert_float_eq!(
+            output[..],
+            [0.005; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 1e-6
+        );
+    }
+
+    #[test]
+    fn test_sidechain() {
+        let options = GateOptions {
+            threshold: -20.,
+            attack: 0.,
+            release: 0.,
+            range: -20.,
+            sidechain: true,
+            ..GateOptions::default()
+        };
+
+        // quiet key closes the gate on a loud signal
+        let output = render_gate(0.5, Some(0.01), options.clone());
+        assert_float_eq!(output[..], [0.05; RENDER_QUANTUM_SIZE][..], abs_all <= 1e-6);
+
+        // loud key opens the gate on a quiet signal
+        let output = render_gate(0.05, Some(0.5), options);
+        assert_float_eq!(output[..], [0.05; RENDER_QUANTUM_SIZE][..], abs_all <= 1e-6);
+    }
+}
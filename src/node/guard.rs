@@ -0,0 +1,238 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Replace NaN and infinite samples with silence, returns the number of replaced samples
+pub(crate) fn scrub_non_finite(quantum: &mut AudioRenderQuantum) -> u64 {
+    // do not trigger a copy of the channels when there is nothing to scrub
+    let dirty = quantum
+        .channels()
+        .iter()
+        .any(|channel| channel.iter().any(|s| !s.is_finite()));
+    if !dirty {
+        return 0;
+    }
+
+    let mut count = 0;
+    quantum.channels_mut().iter_mut().for_each(|channel| {
+        channel.iter_mut().filter(|s| !s.is_finite()).for_each(|s| {
+            *s = 0.;
+            count += 1;
+        });
+    });
+
+    count
+}
+
+/// Guard state of the [`AudioDestinationNode`](super::AudioDestinationNode), shared between the
+/// control and render thread
+#[derive(Debug, Default)]
+pub(crate) struct DestinationGuard {
+    pub enabled: AtomicBool,
+    pub count: AtomicU64,
+}
+
+/// Options for constructing a [`GuardNode`]
+#[derive(Clone, Debug, Default)]
+pub struct GuardOptions {
+    pub audio_node_options: AudioNodeOptions,
+}
+
+/// `GuardNode` replaces NaN and infinite samples with silence
+///
+/// A single NaN sample emitted by a misbehaving processor poisons every node downstream (e.g.
+/// filters will output NaN forever), turning the whole mix into garbage or full-scale noise. Put
+/// a `GuardNode` after sources or custom processors that are not fully trusted to contain the
+/// damage. The number of replaced samples is counted, so placing a guard after each source
+/// reveals which one is misbehaving.
+///
+/// Valid samples pass through unaltered. The context destination can be guarded as well, see
+/// [`AudioDestinationNode::set_guard`](super::AudioDestinationNode::set_guard).
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, GuardNode, GuardOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let guard = GuardNode::new(&context, GuardOptions::default());
+/// guard.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&guard);
+/// osc.start();
+///
+/// std::thread::sleep(std::time::Duration::from_secs(1));
+/// assert_eq!(guard.non_finite_count(), 0);
+/// ```
+#[derive(Debug)]
+pub struct GuardNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    count: Arc<AtomicU64>,
+}
+
+impl AudioNode for GuardNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl GuardNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: GuardOptions) -> Self {
+        context.base().register(move |registration| {
+            let count = Arc::new(AtomicU64::new(0));
+
+            let render = GuardRenderer {
+                count: Arc::clone(&count),
+            };
+
+            let node = GuardNode {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                count,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The number of NaN and infinite samples replaced with silence so far
+    pub fn non_finite_count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Reset the count of replaced samples to zero
+    pub fn reset_non_finite_count(&self) {
+        self.count.store(0, Ordering::Relaxed);
+    }
+}
+
+struct GuardRenderer {
+    count: Arc<AtomicU64>,
+}
+
+impl AudioProcessor for GuardRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        *output = input.clone();
+
+        if !output.is_silent() {
+            let count = scrub_non_finite(output);
+            if count > 0 {
+                self.count.fetch_add(count, Ordering::Relaxed);
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::RENDER_QUANTUM_SIZE;
+
+    use super::*;
+
+    fn render_with_poison(guard_destination: bool) -> (Vec<f32>, u64, u64) {
+        let mut context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+
+        let mut data = [0.5; RENDER_QUANTUM_SIZE];
+        data[1] = f32::NAN;
+        data[2] = f32::INFINITY;
+        data[3] = f32::NEG_INFINITY;
+
+        let mut buffer = context.create_buffer(1, RENDER_QUANTUM_SIZE, 44_100.);
+        buffer.copy_to_channel(&data, 0);
+
+        let destination = context.destination();
+        destination.set_guard(guard_destination);
+
+        let guard = GuardNode::new(&context, GuardOptions::default());
+        guard.connect(&destination);
+
+        let mut poisoned = context.create_buffer_source();
+        poisoned.set_buffer(buffer.clone());
+        poisoned.connect(&guard);
+        poisoned.start();
+
+        // unguarded source, only caught by the destination guard
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&destination);
+        src.start();
+
+        let output = context.start_rendering_sync();
+
+        (
+            output.get_channel_data(0).to_vec(),
+            guard.non_finite_count(),
+            destination.non_finite_count(),
+        )
+    }
+
+    #[test]
+    fn test_scrub() {
+        let (output, guard_count, destination_count) = render_with_poison(true);
+
+        let mut expected = [1.; RENDER_QUANTUM_SIZE];
+        expected[1..4].copy_from_slice(&[0.; 3]);
+        assert_float_eq!(output[..], expected[..], abs_all <= 0.);
+
+        assert_eq!(guard_count, 3);
+        assert_eq!(destination_count, 3);
+    }
+
+    #[test]
+    fn test_destination_unguarded() {
+        let (output, guard_count, destination_count) = render_with_poison(false);
+
+        assert!(output[1].is_nan());
+        assert_eq!(guard_count, 3);
+        assert_eq!(destination_count, 0);
+    }
+
+    #[test]
+    fn test_reset_count() {
+        let context = OfflineAudioContext::new(1, 1, 44_100.);
+        let guard = GuardNode::new(&context, GuardOptions::default());
+        guard.count.store(3, Ordering::Relaxed);
+        guard.reset_non_finite_count();
+        assert_eq!(guard.non_finite_count(), 0);
+    }
+}
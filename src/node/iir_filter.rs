@@ -1,4 +1,6 @@
 //! The IIR filter control and renderer parts
+use std::any::Any;
+
 use arrayvec::ArrayVec;
 use num_complex::Complex;
 
@@ -90,7 +92,9 @@ pub struct IIRFilterOptions {
 /// your application require such filters and/or automation is not needed, then IIR
 /// filters may be appropriate. In short, use this if you know what you are doing!
 ///
-/// Note that once created, the coefficients of the IIR filter cannot be changed.
+/// Note that once created, the coefficients of the IIR filter cannot be changed per
+/// specification. As an extension, they can be updated with
+/// [`set_coefficients`](IIRFilterNode::set_coefficients).
 ///
 /// - MDN documentation: <https://developer.mozilla.org/en-US/docs/Web/API/IIRFilterNode>
 /// - specification: <https://webaudio.github.io/web-audio-api/#IIRFilterNode>
@@ -206,6 +210,34 @@ impl IIRFilterNode {
         })
     }
 
+    /// Update the coefficients of the filter
+    ///
+    /// This allows adaptive filtering (e.g. de-essing) without replacing the node. The state of
+    /// the filter is preserved, and the output crossfades from the previous to the new filter
+    /// during the next render quantum. Large changes of the response or of the filter order can
+    /// still produce audible transients.
+    ///
+    /// This method is not part of the Web Audio API specification, which defines the
+    /// coefficients as immutable.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - coefs length is 0 and greater than 20
+    /// - feedforward coefs are all zeros
+    /// - feedback first coef is zero
+    ///
+    pub fn set_coefficients(&mut self, feedforward: Vec<f64>, feedback: Vec<f64>) {
+        assert_valid_feedforward_coefs(&feedforward);
+        assert_valid_feedback_coefs(&feedback);
+
+        let norm_coeffs = normalize_coefs(feedforward.clone(), feedback.clone());
+        self.registration.post_message(IirCoefsMessage(norm_coeffs));
+
+        self.feedforward = feedforward;
+        self.feedback = feedback;
+    }
+
     /// Returns the frequency response for the specified frequencies
     ///
     /// # Arguments
@@ -265,12 +297,65 @@ impl IIRFilterNode {
     }
 }
 
+/// Normalized filter coefficients -- `(b[n], a[n])`
+type NormalizedCoefs = ArrayVec<(f64, f64), MAX_IIR_COEFFS_LEN>;
+
+/// Filter state of a single channel, sized for the maximum filter order
+type ChannelState = [f64; MAX_IIR_COEFFS_LEN];
+
+/// Normalize the coefficients against `feedback[0]`
+fn normalize_coefs(mut feedforward: Vec<f64>, mut feedback: Vec<f64>) -> NormalizedCoefs {
+    // make sure feedback and feedforward have same length, fill with 0. to match
+    match (feedforward.len(), feedback.len()) {
+        (feedforward_len, feedback_len) if feedforward_len > feedback_len => {
+            feedforward = feedforward
+                .into_iter()
+                .chain(std::iter::repeat(0.))
+                .take(feedback_len)
+                .collect();
+        }
+        (feedforward_len, feedback_len) if feedforward_len < feedback_len => {
+            feedback = feedback
+                .into_iter()
+                .chain(std::iter::repeat(0.))
+                .take(feedforward_len)
+                .collect();
+        }
+        _ => (),
+    };
+
+    let a0 = feedback[0];
+    feedforward
+        .into_iter()
+        .zip(feedback)
+        .map(|(b, a)| (b / a0, a / a0))
+        .collect()
+}
+
+/// Process a single sample through the filter, in transposed direct form II
+#[inline(always)]
+fn process_sample(coefs: &[(f64, f64)], state: &mut ChannelState, input: f64) -> f64 {
+    let output = coefs[0].0.mul_add(input, state[0]);
+
+    // update states for next call
+    for (i, (b, a)) in coefs.iter().skip(1).enumerate() {
+        state[i] = b * input - a * output + state[i + 1];
+    }
+
+    output
+}
+
+/// Message to the renderer containing updated coefficients
+struct IirCoefsMessage(NormalizedCoefs);
+
 /// Renderer associated with the `IirFilterNode`
 struct IirFilterRenderer {
     /// Normalized filter's coeffs -- `(b[n], a[n])`
-    norm_coeffs: Vec<(f64, f64)>,
+    norm_coeffs: NormalizedCoefs,
     /// filter's states
-    states: ArrayVec<Vec<f64>, MAX_CHANNELS>,
+    states: ArrayVec<ChannelState, MAX_CHANNELS>,
+    /// previous filter, faded out during the render quantum following a coefficients update
+    previous: Option<(NormalizedCoefs, ArrayVec<ChannelState, MAX_CHANNELS>)>,
 }
 
 impl IirFilterRenderer {
@@ -279,46 +364,36 @@ impl IirFilterRenderer {
     /// # Arguments
     ///
     /// * `config` - renderer config
-    fn new(mut feedforward: Vec<f64>, mut feedback: Vec<f64>) -> Self {
-        // make sure feedback and feedforward have same length, fill with 0. to match
-        match (feedforward.len(), feedback.len()) {
-            (feedforward_len, feedback_len) if feedforward_len > feedback_len => {
-                feedforward = feedforward
-                    .into_iter()
-                    .chain(std::iter::repeat(0.))
-                    .take(feedback_len)
-                    .collect();
-            }
-            (feedforward_len, feedback_len) if feedforward_len < feedback_len => {
-                feedback = feedback
-                    .into_iter()
-                    .chain(std::iter::repeat(0.))
-                    .take(feedforward_len)
-                    .collect();
-            }
-            _ => (),
-        };
-
-        let a0 = feedback[0];
-        let mut norm_coeffs: Vec<(f64, f64)> = feedforward.into_iter().zip(feedback).collect();
-
-        norm_coeffs.iter_mut().for_each(|(b, a)| {
-            *b /= a0;
-            *a /= a0;
-        });
-
-        let coeffs_len = norm_coeffs.len();
+    fn new(feedforward: Vec<f64>, feedback: Vec<f64>) -> Self {
+        let norm_coeffs = normalize_coefs(feedforward, feedback);
 
         // eagerly assume stereo input, will adjust during rendering if needed
         let mut states = ArrayVec::new();
-        states.push(vec![0.; coeffs_len]);
-        states.push(vec![0.; coeffs_len]);
+        states.push([0.; MAX_IIR_COEFFS_LEN]);
+        states.push([0.; MAX_IIR_COEFFS_LEN]);
 
         Self {
             norm_coeffs,
             states,
+            previous: None,
         }
     }
+
+    fn set_coefs(&mut self, norm_coeffs: NormalizedCoefs) {
+        // keep the filter that is currently heard for the crossfade, if coefficients are
+        // updated several times during the same render quantum
+        if self.previous.is_none() {
+            self.previous = Some((self.norm_coeffs.clone(), self.states.clone()));
+        }
+
+        // preserve the filter state, only clear the state beyond the new filter order
+        let len = norm_coeffs.len();
+        self.states
+            .iter_mut()
+            .for_each(|state| state[len - 1..].fill(0.));
+
+        self.norm_coeffs = norm_coeffs;
+    }
 }
 
 impl AudioProcessor for IirFilterRenderer {
@@ -334,7 +409,7 @@ impl AudioProcessor for IirFilterRenderer {
         let output = &mut outputs[0];
 
         // handle tail time
-        if input.is_silent() {
+        if input.is_silent() && self.previous.is_none() {
             let mut ended = true;
 
             // if all values in states are 0., we have nothing left to process
@@ -363,7 +438,7 @@ impl AudioProcessor for IirFilterRenderer {
             if num_channels != self.states.len() {
                 self.states.truncate(num_channels);
                 for _ in self.states.len()..num_channels {
-                    self.states.push(vec![0.; self.norm_coeffs.len()]);
+                    self.states.push([0.; MAX_IIR_COEFFS_LEN]);
                 }
             }
 
@@ -383,16 +458,7 @@ impl AudioProcessor for IirFilterRenderer {
             let channel_state = &mut self.states[channel_number];
 
             for (&i, o) in input_channel.iter().zip(output_channel.iter_mut()) {
-                let input = f64::from(i);
-                let b0 = self.norm_coeffs[0].0;
-                let last_state = channel_state[0];
-                let output = b0.mul_add(input, last_state);
-
-                // update states for next call
-                for (i, (b, a)) in self.norm_coeffs.iter().skip(1).enumerate() {
-                    let state = channel_state[i + 1];
-                    channel_state[i] = b * input - a * output + state;
-                }
+                let output = process_sample(&self.norm_coeffs, channel_state, f64::from(i));
 
                 #[cfg(debug_assertions)]
                 if output.is_nan() || output.is_infinite() {
@@ -401,10 +467,40 @@ impl AudioProcessor for IirFilterRenderer {
 
                 *o = output as f32;
             }
+
+            // crossfade from the previous filter after a coefficients update
+            if let Some((coefs, states)) = &self.previous {
+                let mut state = states
+                    .get(channel_number)
+                    .copied()
+                    .unwrap_or([0.; MAX_IIR_COEFFS_LEN]);
+                let len = output_channel.len() as f64;
+
+                input_channel
+                    .iter()
+                    .zip(output_channel.iter_mut())
+                    .enumerate()
+                    .for_each(|(n, (&i, o))| {
+                        let previous = process_sample(coefs, &mut state, f64::from(i));
+                        let t = (n + 1) as f64 / len;
+                        *o = (previous * (1. - t) + f64::from(*o) * t) as f32;
+                    });
+            }
         }
 
+        self.previous = None;
+
         true
     }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(msg) = msg.downcast_ref::<IirCoefsMessage>() {
+            self.set_coefs(msg.0.clone());
+            return;
+        }
+
+        log::warn!("IirFilterRenderer: Dropping incoming message {msg:?}");
+    }
 }
 
 #[cfg(test)]
@@ -414,7 +510,7 @@ mod tests {
 
     use crate::context::{BaseAudioContext, OfflineAudioContext};
     use crate::node::{AudioNode, AudioScheduledSourceNode, BiquadFilterType};
-    use crate::AudioBuffer;
+    use crate::{AudioBuffer, RENDER_QUANTUM_SIZE};
 
     use super::*;
 
@@ -926,4 +1022,72 @@ mod tests {
         mags.iter().for_each(|v| assert!(v.is_nan()));
         phases.iter().for_each(|v| assert!(v.is_nan()));
     }
+
+    #[test]
+    fn test_set_coefficients_crossfade() {
+        let sample_rate = 44_100.;
+        let mut context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 3, sample_rate);
+
+        let mut iir = context.create_iir_filter(vec![1.], vec![1.]);
+        iir.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&iir);
+        src.start();
+
+        let quantum = RENDER_QUANTUM_SIZE as f64 / sample_rate as f64;
+        context.suspend_sync(quantum, move |_| {
+            iir.set_coefficients(vec![1.], vec![2.]);
+            assert_eq!(iir.feedback, vec![2.]);
+        });
+
+        let output = context.start_rendering_sync();
+        let channel = output.get_channel_data(0);
+
+        let mut expected = vec![1.; RENDER_QUANTUM_SIZE];
+        expected.extend((0..RENDER_QUANTUM_SIZE).map(|n| {
+            let t = (n + 1) as f32 / RENDER_QUANTUM_SIZE as f32;
+            1. - t + 0.5 * t
+        }));
+        expected.extend([0.5; RENDER_QUANTUM_SIZE]);
+
+        assert_float_eq!(channel[..], expected[..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_set_coefficients_preserves_state() {
+        let sample_rate = 44_100.;
+        let length = RENDER_QUANTUM_SIZE * 100;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let lowpass = |frequency| {
+            crate::dsp::design::butterworth(
+                crate::dsp::design::FilterResponse::Lowpass,
+                2,
+                frequency,
+                sample_rate,
+            )
+        };
+
+        let coefs = lowpass(1000.);
+        let mut iir = context.create_iir_filter(coefs.feedforward, coefs.feedback);
+        iir.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&iir);
+        src.start();
+
+        let switch = RENDER_QUANTUM_SIZE * 50;
+        context.suspend_sync(switch as f64 / sample_rate as f64, move |_| {
+            let coefs = lowpass(1100.);
+            iir.set_coefficients(coefs.feedforward, coefs.feedback);
+        });
+
+        let output = context.start_rendering_sync();
+
+        // the filter has settled, and stays settled after the update
+        output.get_channel_data(0)[switch / 2..]
+            .iter()
+            .for_each(|&s| assert_float_eq!(s, 1., abs <= 1e-2));
+    }
 }
@@ -0,0 +1,522 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Number of taps of the interpolation filter used for true-peak detection
+const TRUE_PEAK_TAPS: usize = 16;
+
+/// Options for constructing a [`LimiterNode`]
+#[derive(Clone, Debug)]
+pub struct LimiterOptions {
+    /// Maximum output level in dBFS
+    pub ceiling: f32,
+    /// Time in seconds for the gain reduction to recover
+    pub release: f32,
+    /// Lookahead time in seconds, in the range [0, 1]
+    pub lookahead: f64,
+    /// Detect inter-sample peaks using 4x oversampling
+    pub true_peak: bool,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for LimiterOptions {
+    fn default() -> Self {
+        Self {
+            ceiling: -1.,     // dBFS
+            release: 0.05,    // seconds
+            lookahead: 0.005, // seconds
+            true_peak: false,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `LimiterNode` is a brickwall limiter, preventing the signal from exceeding a given ceiling.
+///
+/// The input signal is delayed by the lookahead time so the gain reduction can be applied
+/// smoothly before a peak arrives at the output. The gain reduction is shared across all
+/// channels, preserving the stereo image. When `true_peak` is set, the peak detection is
+/// performed on a 4x oversampled signal so inter-sample peaks (which may clip after
+/// digital-to-analog conversion or lossy encoding) are caught as well.
+///
+/// The node introduces a latency which can be queried with [`LimiterNode::latency`].
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, LimiterNode, LimiterOptions};
+///
+/// let context = AudioContext::default();
+/// let file = File::open("samples/sample.wav").unwrap();
+/// let buffer = context.decode_audio_data_sync(file).unwrap();
+///
+/// let options = LimiterOptions {
+///     ceiling: -0.3,
+///     true_peak: true,
+///     ..LimiterOptions::default()
+/// };
+/// let limiter = LimiterNode::new(&context, options);
+/// limiter.connect(&context.destination());
+///
+/// let mut src = context.create_buffer_source();
+/// src.set_buffer(buffer);
+/// src.connect(&limiter);
+/// src.start();
+/// ```
+#[derive(Debug)]
+pub struct LimiterNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    ceiling: AudioParam,
+    release: AudioParam,
+    latency: f64,
+    true_peak: bool,
+    reduction: Arc<AtomicF32>,
+}
+
+impl AudioNode for LimiterNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl LimiterNode {
+    /// Create a new `LimiterNode`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the lookahead time is outside the [0, 1] range
+    pub fn new<C: BaseAudioContext>(context: &C, options: LimiterOptions) -> Self {
+        assert!(
+            (0. ..=1.).contains(&options.lookahead),
+            "RangeError - LimiterNode lookahead must be in the range [0, 1], got {:?}",
+            options.lookahead
+        );
+
+        context.base().register(move |registration| {
+            let ceiling_param_opts = AudioParamDescriptor {
+                name: String::from("ceiling"),
+                min_value: -60.,
+                max_value: 0.,
+                default_value: -1.,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut ceiling_param, ceiling_proc) =
+                context.create_audio_param(ceiling_param_opts, &registration);
+            ceiling_param.set_automation_rate_constrained(true);
+            ceiling_param.set_value(options.ceiling);
+
+            let release_param_opts = AudioParamDescriptor {
+                name: String::from("release"),
+                min_value: 0.,
+                max_value: 1.,
+                default_value: 0.05,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut release_param, release_proc) =
+                context.create_audio_param(release_param_opts, &registration);
+            release_param.set_automation_rate_constrained(true);
+            release_param.set_value(options.release);
+
+            let sample_rate = context.sample_rate();
+            let lookahead = ((options.lookahead * sample_rate as f64).round() as usize).max(1);
+            let detector_delay = if options.true_peak {
+                TRUE_PEAK_TAPS / 2
+            } else {
+                0
+            };
+            let latency = lookahead - 1 + detector_delay;
+
+            let reduction = Arc::new(AtomicF32::new(0.));
+
+            let render = LimiterRenderer {
+                ceiling: ceiling_proc,
+                release: release_proc,
+                reduction: Arc::clone(&reduction),
+                true_peak: options.true_peak.then(TruePeakDetector::new),
+                lookahead,
+                latency,
+                frame: 0,
+                hold: VecDeque::with_capacity(lookahead + 1),
+                envelope: 1.,
+                average_buffer: vec![1.; lookahead],
+                average_sum: lookahead as f64,
+                average_index: 0,
+                delay_lines: Vec::new(),
+                delay_index: 0,
+                silent_frames: usize::MAX,
+            };
+
+            let node = LimiterNode {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                ceiling: ceiling_param,
+                release: release_param,
+                latency: latency as f64 / sample_rate as f64,
+                true_peak: options.true_peak,
+                reduction,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The maximum output level in dBFS
+    pub fn ceiling(&self) -> &AudioParam {
+        &self.ceiling
+    }
+
+    /// The time in seconds for the gain reduction to recover
+    pub fn release(&self) -> &AudioParam {
+        &self.release
+    }
+
+    /// The latency in seconds introduced by the lookahead (and true-peak detection)
+    pub fn latency(&self) -> f64 {
+        self.latency
+    }
+
+    /// Whether inter-sample peaks are detected
+    pub fn true_peak(&self) -> bool {
+        self.true_peak
+    }
+
+    /// The current gain reduction in dB (zero or negative)
+    pub fn reduction(&self) -> f32 {
+        self.reduction.load(Ordering::Relaxed)
+    }
+}
+
+/// Estimates inter-sample peaks by interpolating three points between each pair of samples with
+/// a windowed-sinc filter
+struct TruePeakDetector {
+    coefs: [[f32; TRUE_PEAK_TAPS]; 3],
+    history: Vec<[f32; TRUE_PEAK_TAPS]>,
+}
+
+impl TruePeakDetector {
+    fn new() -> Self {
+        let half = (TRUE_PEAK_TAPS / 2) as f32;
+        let mut coefs = [[0.; TRUE_PEAK_TAPS]; 3];
+
+        coefs.iter_mut().enumerate().for_each(|(phase, coefs)| {
+            let fraction = (phase + 1) as f32 / 4.;
+
+            coefs.iter_mut().enumerate().for_each(|(k, coef)| {
+                // distance to the interpolated point, the point lies between taps `half - 1`
+                // and `half`
+                let x = fraction + half - 1. - k as f32;
+                let sinc = if x == 0. { 1. } else { (PI * x).sin() / (PI * x) };
+                let window = 0.5 * (1. + (PI * x / half).cos());
+                *coef = sinc * window;
+            });
+
+            // normalize to unity gain at DC
+            let sum: f32 = coefs.iter().sum();
+            coefs.iter_mut().for_each(|c| *c /= sum);
+        });
+
+        Self {
+            coefs,
+            history: Vec::new(),
+        }
+    }
+
+    /// Push a sample for the given channel and return the peak level around the sample that was
+    /// pushed `TRUE_PEAK_TAPS / 2` frames ago
+    fn process(&mut self, channel: usize, sample: f32) -> f32 {
+        let history = &mut self.history[channel];
+        history.copy_within(1.., 0);
+        history[TRUE_PEAK_TAPS - 1] = sample;
+
+        let mut peak = history[TRUE_PEAK_TAPS / 2 - 1].abs();
+        for coefs in &self.coefs {
+            let value: f32 = coefs.iter().zip(history.iter()).map(|(c, s)| c * s).sum();
+            peak = peak.max(value.abs());
+        }
+
+        peak
+    }
+}
+
+struct LimiterRenderer {
+    ceiling: AudioParamId,
+    release: AudioParamId,
+    reduction: Arc<AtomicF32>,
+    true_peak: Option<TruePeakDetector>,
+    // lookahead window in frames
+    lookahead: usize,
+    // delay of the signal path in frames
+    latency: usize,
+    frame: u64,
+    // monotonic queue of (frame, gain) to compute the minimum gain over the lookahead window
+    hold: VecDeque<(u64, f32)>,
+    // held gain with release smoothing
+    envelope: f32,
+    // moving average of the envelope over the lookahead window
+    average_buffer: Vec<f32>,
+    average_sum: f64,
+    average_index: usize,
+    delay_lines: Vec<Vec<f32>>,
+    delay_index: usize,
+    silent_frames: usize,
+}
+
+impl AudioProcessor for LimiterRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() {
+            // no signal is left in the delay lines, we have reached the tail time
+            if self.silent_frames >= self.latency {
+                output.make_silent();
+                return false;
+            }
+            self.silent_frames = self.silent_frames.saturating_add(RENDER_QUANTUM_SIZE);
+        } else {
+            self.silent_frames = 0;
+        }
+
+        let number_of_channels = if input.is_silent() {
+            self.delay_lines.len().max(1)
+        } else {
+            input.number_of_channels()
+        };
+
+        if self.delay_lines.len() != number_of_channels {
+            self.delay_lines
+                .resize(number_of_channels, vec![0.; self.latency + 1]);
+            if let Some(true_peak) = self.true_peak.as_mut() {
+                true_peak
+                    .history
+                    .resize(number_of_channels, [0.; TRUE_PEAK_TAPS]);
+            }
+        }
+
+        *output = input.clone();
+        output.set_number_of_channels(number_of_channels);
+
+        let ceiling = 10_f32.powf(params.get(&self.ceiling)[0] / 20.);
+        let release = params.get(&self.release)[0];
+        let release_coef = (-1. / (release * scope.sample_rate)).exp();
+
+        // compute the gain to apply for each frame of the delayed signal
+        let mut gains = [1.; RENDER_QUANTUM_SIZE];
+        let mut min_gain = 1_f32;
+
+        for (i, gain) in gains.iter_mut().enumerate() {
+            let mut peak = 0_f32;
+            for (c, channel) in output.channels().iter().enumerate() {
+                let level = match self.true_peak.as_mut() {
+                    Some(true_peak) => true_peak.process(c, channel[i]),
+                    None => channel[i].abs(),
+                };
+                peak = peak.max(level);
+            }
+
+            let target = if peak > ceiling { ceiling / peak } else { 1. };
+
+            // hold the minimum gain over the lookahead window
+            while self.hold.back().is_some_and(|&(_, g)| g >= target) {
+                self.hold.pop_back();
+            }
+            self.hold.push_back((self.frame, target));
+            while self
+                .hold
+                .front()
+                .is_some_and(|&(f, _)| f + self.lookahead as u64 <= self.frame)
+            {
+                self.hold.pop_front();
+            }
+            let held = self.hold.front().unwrap().1;
+            self.frame += 1;
+
+            // instant attack, smooth release
+            self.envelope = if held < self.envelope {
+                held
+            } else {
+                held + release_coef * (self.envelope - held)
+            };
+
+            // smooth the attack by averaging over the lookahead window, the average cannot
+            // exceed the held gain at the time the peak reaches the output
+            let index = self.average_index;
+            self.average_sum += (self.envelope - self.average_buffer[index]) as f64;
+            self.average_buffer[index] = self.envelope;
+            self.average_index = (index + 1) % self.lookahead;
+
+            *gain = (self.average_sum / self.lookahead as f64) as f32;
+            min_gain = min_gain.min(*gain);
+        }
+
+        self.reduction
+            .store(20. * min_gain.log10(), Ordering::Relaxed);
+
+        // apply the gain to the delayed signal
+        let delay_length = self.latency + 1;
+        output
+            .channels_mut()
+            .iter_mut()
+            .zip(self.delay_lines.iter_mut())
+            .for_each(|(channel, delay_line)| {
+                channel.iter_mut().zip(gains.iter()).enumerate().for_each(
+                    |(i, (sample, gain))| {
+                        let write_index = (self.delay_index + i) % delay_length;
+                        delay_line[write_index] = *sample;
+                        let read_index = (write_index + 1) % delay_length;
+                        *sample = delay_line[read_index] * gain;
+                    },
+                );
+            });
+
+        self.delay_index = (self.delay_index + RENDER_QUANTUM_SIZE) % delay_length;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::AudioBuffer;
+
+    use super::*;
+
+    fn render_sine(frequency: f32, amplitude: f32, options: LimiterOptions) -> (Vec<f32>, usize) {
+        let sample_rate = 44_100.;
+        let length = RENDER_QUANTUM_SIZE * 20;
+        let context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let signal: Vec<f32> = (0..length)
+            .map(|i| amplitude * (2. * PI * frequency * i as f32 / sample_rate + PI / 4.).sin())
+            .collect();
+        let buffer = AudioBuffer::from(vec![signal], sample_rate);
+
+        let limiter = LimiterNode::new(&context, options);
+        limiter.connect(&context.destination());
+        let latency = (limiter.latency() * sample_rate as f64).round() as usize;
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&limiter);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        (output.get_channel_data(0).to_vec(), latency)
+    }
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(1, 1, 44_100.);
+        let limiter = LimiterNode::new(&context, LimiterOptions::default());
+
+        assert_float_eq!(limiter.ceiling().value(), -1., abs <= 0.);
+        assert_float_eq!(limiter.release().value(), 0.05, abs <= 0.);
+        assert_float_eq!(limiter.latency(), 220. / 44_100., abs <= 1e-9);
+        assert!(!limiter.true_peak());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_lookahead() {
+        let context = OfflineAudioContext::new(1, 1, 44_100.);
+        let options = LimiterOptions {
+            lookahead: 2.,
+            ..LimiterOptions::default()
+        };
+        let _ = LimiterNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_passthrough_below_ceiling() {
+        let (output, latency) = render_sine(441., 0.5, LimiterOptions::default());
+
+        let sample_rate = 44_100.;
+        let expected: Vec<f32> = (0..output.len() - latency)
+            .map(|i| 0.5 * (2. * PI * 441. * i as f32 / sample_rate + PI / 4.).sin())
+            .collect();
+
+        assert_float_eq!(output[..latency], vec![0.; latency][..], abs_all <= 0.);
+        assert_float_eq!(output[latency..], expected[..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_output_below_ceiling() {
+        let options = LimiterOptions {
+            ceiling: -6.,
+            ..LimiterOptions::default()
+        };
+        let (output, _) = render_sine(441., 4., options);
+
+        let ceiling = 10_f32.powf(-6. / 20.);
+        output.iter().for_each(|s| assert!(s.abs() <= ceiling + 1e-6));
+
+        // the limiter is effectively engaged
+        let max = output.iter().fold(0_f32, |m, s| m.max(s.abs()));
+        assert_float_eq!(max, ceiling, abs <= 0.01);
+    }
+
+    #[test]
+    fn test_true_peak() {
+        // sine at a quarter of the sample rate sampled at 45 degrees, the sample peaks are at
+        // `amplitude * sqrt(2) / 2` while the true peak is at `amplitude`
+        let frequency = 44_100. / 4.;
+        let amplitude = 1.2;
+
+        let options = LimiterOptions {
+            ceiling: 0.,
+            ..LimiterOptions::default()
+        };
+        let (output, _) = render_sine(frequency, amplitude, options);
+        let sample_peak = output.iter().fold(0_f32, |m, s| m.max(s.abs()));
+        assert_float_eq!(sample_peak, amplitude / 2_f32.sqrt(), abs <= 1e-3);
+
+        let options = LimiterOptions {
+            ceiling: 0.,
+            true_peak: true,
+            ..LimiterOptions::default()
+        };
+        let (output, latency) = render_sine(frequency, amplitude, options);
+        let steady_state = &output[latency + RENDER_QUANTUM_SIZE..];
+        let sample_peak = steady_state.iter().fold(0_f32, |m, s| m.max(s.abs()));
+        // reconstructed peak is brought down to the ceiling
+        assert_float_eq!(sample_peak, 1. / 2_f32.sqrt(), abs <= 0.02);
+    }
+}
@@ -0,0 +1,315 @@
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, FirFilterOptions, FirFilterRenderer};
+
+/// Assert that the bands of the equalizer are valid for the given sample rate
+///
+/// # Panics
+///
+/// This function panics if:
+/// - no bands are given, or `frequencies` and `gains` have different lengths
+/// - the frequencies are not strictly increasing
+/// - any frequency is outside the ]0, nyquist[ range
+/// - any gain is not finite
+#[track_caller]
+#[inline(always)]
+fn assert_valid_bands(frequencies: &[f32], gains: &[f32], sample_rate: f32) {
+    assert!(
+        !frequencies.is_empty() && frequencies.len() == gains.len(),
+        "NotSupportedError - LinearPhaseEQNode requires one gain per band, got {} frequencies and {} gains",
+        frequencies.len(),
+        gains.len()
+    );
+
+    assert!(
+        frequencies.windows(2).all(|w| w[0] < w[1]),
+        "NotSupportedError - LinearPhaseEQNode frequencies must be strictly increasing, got {:?}",
+        frequencies
+    );
+
+    let nyquist = sample_rate / 2.;
+    assert!(
+        frequencies.iter().all(|&f| f > 0. && f < nyquist),
+        "NotSupportedError - LinearPhaseEQNode frequencies must be in the range ]0, {}[, got {:?}",
+        nyquist,
+        frequencies
+    );
+
+    assert!(
+        gains.iter().all(|g| g.is_finite()),
+        "NotSupportedError - LinearPhaseEQNode gains must be finite, got {:?}",
+        gains
+    );
+}
+
+/// Design the taps of the equalizer
+///
+/// The gains are interpolated in dB on a logarithmic frequency axis, and held constant below
+/// the first and above the last band.
+fn design_taps(
+    frequencies: &[f32],
+    gains: &[f32],
+    number_of_taps: usize,
+    sample_rate: f32,
+) -> Vec<f32> {
+    let gain_at = |frequency: f32| -> f32 {
+        match frequencies.iter().position(|&f| f > frequency) {
+            Some(0) => gains[0],
+            None => gains[gains.len() - 1],
+            Some(i) => {
+                let t = (frequency / frequencies[i - 1]).log2()
+                    / (frequencies[i] / frequencies[i - 1]).log2();
+                gains[i - 1] + t * (gains[i] - gains[i - 1])
+            }
+        }
+    };
+
+    // sample the response densely enough for the linear interpolation of the FIR design
+    let nyquist = sample_rate / 2.;
+    let (grid, magnitudes): (Vec<f32>, Vec<f32>) = (0..=number_of_taps)
+        .map(|k| {
+            let frequency = k as f32 * nyquist / number_of_taps as f32;
+            (frequency, 10_f32.powf(gain_at(frequency) / 20.))
+        })
+        .unzip();
+
+    FirFilterOptions::from_magnitude_response(&grid, &magnitudes, number_of_taps, sample_rate).taps
+}
+
+/// Options for constructing a [`LinearPhaseEQNode`]
+#[derive(Clone, Debug)]
+pub struct LinearPhaseEQOptions {
+    /// Center frequencies of the bands in Hz, in increasing order
+    pub frequencies: Vec<f32>,
+    /// Gain of each band in dB
+    pub gains: Vec<f32>,
+    /// Length of the filter, must be odd. Longer filters resolve lower frequencies, at the cost
+    /// of more latency.
+    pub number_of_taps: usize,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for LinearPhaseEQOptions {
+    fn default() -> Self {
+        // octave bands from 31.25Hz to 16kHz
+        let frequencies: Vec<f32> = (0..10).map(|i| 31.25 * 2_f32.powi(i)).collect();
+        let gains = vec![0.; frequencies.len()];
+
+        Self {
+            frequencies,
+            gains,
+            number_of_taps: 4095,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `LinearPhaseEQNode` is a graphic equalizer without phase distortion
+///
+/// The target response is given as gains at the band center frequencies, interpolated in dB on
+/// a logarithmic frequency axis. The response is realized with a linear phase FIR filter
+/// applied with FFT convolution, so all frequencies are delayed by the same amount, see
+/// [`LinearPhaseEQNode::latency`]. Unlike with a chain of
+/// [`BiquadFilterNode`](super::BiquadFilterNode)s, boosting or cutting a band does not smear
+/// transients or alter the relative phase of the bands, which matters e.g. for mastering.
+///
+/// The frequency resolution of the equalizer is about `6 * sample_rate / number_of_taps`, the
+/// default of 4095 taps resolves octave bands down to ~60Hz at 44.1kHz.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{LinearPhaseEQNode, LinearPhaseEQOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // gentle smile curve
+/// let options = LinearPhaseEQOptions {
+///     frequencies: vec![60., 250., 1000., 4000., 12000.],
+///     gains: vec![3., 0., -1., 0., 3.],
+///     ..LinearPhaseEQOptions::default()
+/// };
+/// let eq = LinearPhaseEQNode::new(&context, options);
+/// eq.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&eq);
+/// osc.start();
+/// ```
+#[derive(Debug)]
+pub struct LinearPhaseEQNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    frequencies: Vec<f32>,
+    gains: Vec<f32>,
+    number_of_taps: usize,
+}
+
+impl AudioNode for LinearPhaseEQNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl LinearPhaseEQNode {
+    /// Create a new `LinearPhaseEQNode`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - no bands are given, or `frequencies` and `gains` have different lengths
+    /// - the frequencies are not strictly increasing, or outside the ]0, nyquist[ range
+    /// - `number_of_taps` is even
+    pub fn new<C: BaseAudioContext>(context: &C, options: LinearPhaseEQOptions) -> Self {
+        let LinearPhaseEQOptions {
+            frequencies,
+            gains,
+            number_of_taps,
+            audio_node_options,
+        } = options;
+
+        let sample_rate = context.sample_rate();
+        assert_valid_bands(&frequencies, &gains, sample_rate);
+        let taps = design_taps(&frequencies, &gains, number_of_taps, sample_rate);
+
+        context.base().register(move |registration| {
+            let render = FirFilterRenderer::new(taps);
+
+            let node = LinearPhaseEQNode {
+                registration,
+                channel_config: audio_node_options.into(),
+                frequencies,
+                gains,
+                number_of_taps,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The center frequencies of the bands in Hz
+    pub fn frequencies(&self) -> &[f32] {
+        &self.frequencies
+    }
+
+    /// The gains of the bands in dB
+    pub fn gains(&self) -> &[f32] {
+        &self.gains
+    }
+
+    /// The latency in seconds of the equalizer
+    ///
+    /// The linear phase filter delays the signal by half its length.
+    #[must_use]
+    pub fn latency(&self) -> f64 {
+        (self.number_of_taps - 1) as f64 / 2. / self.registration.context().sample_rate() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::dsp::design::IirCoefficients;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.;
+
+    // magnitude response of the taps in dB at the given frequency
+    fn gain(taps: &[f32], frequency: f32) -> f64 {
+        let coefs = IirCoefficients {
+            feedforward: taps.iter().map(|&t| f64::from(t)).collect(),
+            feedback: vec![1.],
+        };
+        20. * coefs.magnitude(frequency, SAMPLE_RATE).log10()
+    }
+
+    #[test]
+    fn test_default_options() {
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let eq = LinearPhaseEQNode::new(&context, LinearPhaseEQOptions::default());
+
+        assert_eq!(eq.frequencies().len(), 10);
+        assert_float_eq!(eq.frequencies()[9], 16_000., abs <= 0.);
+        assert!(eq.gains().iter().all(|&g| g == 0.));
+        assert_float_eq!(eq.latency(), 2047. / SAMPLE_RATE as f64, abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mismatched_bands() {
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let options = LinearPhaseEQOptions {
+            gains: vec![0.; 3],
+            ..LinearPhaseEQOptions::default()
+        };
+        let _ = LinearPhaseEQNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_response() {
+        let frequencies = [250., 1000., 4000.];
+        let gains = [0., 6., -6.];
+        let taps = design_taps(&frequencies, &gains, 2047, SAMPLE_RATE);
+
+        // linear phase
+        let reversed: Vec<f32> = taps.iter().rev().copied().collect();
+        assert_float_eq!(taps[..], reversed[..], abs_all <= 1e-6);
+
+        assert_float_eq!(gain(&taps, 100.), 0., abs <= 0.1);
+        assert_float_eq!(gain(&taps, 1000.), 6., abs <= 0.2);
+        // interpolated in dB on a logarithmic axis
+        assert_float_eq!(gain(&taps, 2000.), 0., abs <= 0.1);
+        assert_float_eq!(gain(&taps, 10_000.), -6., abs <= 0.1);
+    }
+
+    #[test]
+    fn test_flat_response_is_delay() {
+        let number_of_taps = 255;
+        let latency = number_of_taps / 2;
+        let mut context = OfflineAudioContext::new(1, 512, SAMPLE_RATE);
+
+        let options = LinearPhaseEQOptions {
+            number_of_taps,
+            ..LinearPhaseEQOptions::default()
+        };
+        let eq = LinearPhaseEQNode::new(&context, options);
+        eq.connect(&context.destination());
+
+        let mut buffer = context.create_buffer(1, 1, SAMPLE_RATE);
+        buffer.copy_to_channel(&[1.], 0);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&eq);
+        src.start();
+
+        let output = context.start_rendering_sync();
+
+        let mut expected = vec![0.; 512];
+        expected[latency] = 1.;
+        assert_float_eq!(
+            output.get_channel_data(0)[..],
+            expected[..],
+            abs_all <= 1e-5
+        );
+    }
+}
@@ -0,0 +1,356 @@
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::param::AudioParam;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+
+use super::{
+    AudioNode, AudioNodeOptions, AudioScheduledSourceNode, ChannelConfig, ChannelCountMode,
+    ChannelInterpretation, ConstantSourceNode, ConstantSourceOptions,
+};
+
+/// Mapping of the [`MacroControl`] value to the range of a target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MacroCurve {
+    /// `min + x * (max - min)`
+    Linear,
+    /// `min * (max / min) ^ x`, e.g. for frequencies. `min` and `max` must be non zero and
+    /// have the same sign.
+    Exponential,
+    /// `min + x ^ exponent * (max - min)`
+    Power(f32),
+}
+
+impl Default for MacroCurve {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+impl MacroCurve {
+    #[inline(always)]
+    fn map(&self, x: f32, min: f32, max: f32) -> f32 {
+        match *self {
+            Self::Linear => min + x * (max - min),
+            Self::Exponential => min * (max / min).powf(x),
+            Self::Power(exponent) => min + x.powf(exponent) * (max - min),
+        }
+    }
+}
+
+/// A target of a [`MacroControl`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacroTarget {
+    /// Value of the target when the control value is 0
+    pub min: f32,
+    /// Value of the target when the control value is 1, may be smaller than `min` to invert
+    /// the direction of the control
+    pub max: f32,
+    /// Shape of the mapping between `min` and `max`
+    pub curve: MacroCurve,
+}
+
+/// Options for constructing a [`MacroControl`]
+#[derive(Clone, Debug)]
+pub struct MacroControlOptions {
+    /// Initial control value, in the [0, 1] range
+    pub value: f32,
+}
+
+impl Default for MacroControlOptions {
+    fn default() -> Self {
+        Self { value: 0. }
+    }
+}
+
+/// `MacroControl` drives several [`AudioParam`]s from a single automatable value
+///
+/// Like a macro knob of a hardware controller, a single control value in the [0, 1] range is
+/// mapped to the range and curve of each of its targets, so that e.g. a filter cutoff, a
+/// resonance and a dry/wet gain of an effect chain can be swept together. Automating
+/// [`MacroControl::value`] automates all the targets, with sample accuracy.
+///
+/// The mapped values are fed to the targets as modulation inputs, their own value is set to
+/// zero (or the closest value in their nominal range) when added to the control, and must not
+/// be changed afterwards. The targets are released when the `MacroControl` is dropped.
+///
+/// This type is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{MacroControl, MacroControlOptions, MacroCurve, MacroTarget};
+///
+/// let context = AudioContext::default();
+///
+/// let filter = context.create_biquad_filter();
+/// let gain = context.create_gain();
+/// filter.connect(&gain);
+/// gain.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&filter);
+/// osc.start();
+///
+/// // "brightness" knob: open the filter and compensate the level
+/// let mut brightness = MacroControl::new(&context, MacroControlOptions::default());
+/// brightness.add_target(
+///     filter.frequency(),
+///     MacroTarget { min: 200., max: 8000., curve: MacroCurve::Exponential },
+/// );
+/// brightness.add_target(
+///     gain.gain(),
+///     MacroTarget { min: 1., max: 0.5, curve: MacroCurve::Linear },
+/// );
+///
+/// // turn the knob in 4 seconds
+/// brightness.value().linear_ramp_to_value_at_time(1., context.current_time() + 4.);
+/// ```
+#[derive(Debug)]
+pub struct MacroControl {
+    control: ConstantSourceNode,
+    targets: Vec<MacroTargetNode>,
+}
+
+impl MacroControl {
+    /// Create a new `MacroControl` without targets
+    pub fn new<C: BaseAudioContext>(context: &C, options: MacroControlOptions) -> Self {
+        let options = ConstantSourceOptions {
+            offset: options.value,
+        };
+        let mut control = ConstantSourceNode::new(context, options);
+        control.start();
+
+        Self {
+            control,
+            targets: vec![],
+        }
+    }
+
+    /// A-rate [`AudioParam`] representing the control value, clamped to the [0, 1] range
+    /// when mapped to the targets
+    pub fn value(&self) -> &AudioParam {
+        self.control.offset()
+    }
+
+    /// Add a target to the control
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - `min` or `max` is not finite
+    /// - the curve is [`MacroCurve::Exponential`] and `min` and `max` are zero or have
+    ///   different signs
+    /// - the curve is [`MacroCurve::Power`] and the exponent is not strictly positive
+    pub fn add_target(&mut self, param: &AudioParam, target: MacroTarget) {
+        let MacroTarget { min, max, curve } = target;
+
+        assert!(
+            min.is_finite() && max.is_finite(),
+            "RangeError - macro target range must be finite, got [{}, {}]",
+            min,
+            max
+        );
+
+        match curve {
+            MacroCurve::Linear => (),
+            MacroCurve::Exponential => assert!(
+                min * max > 0.,
+                "RangeError - exponential macro target range must be non zero with the same sign, got [{}, {}]",
+                min,
+                max
+            ),
+            MacroCurve::Power(exponent) => assert!(
+                exponent > 0. && exponent.is_finite(),
+                "RangeError - macro curve exponent must be strictly positive, got {}",
+                exponent
+            ),
+        }
+
+        // the computed value of the param is its intrinsic value plus the modulation input
+        let offset = 0_f32.clamp(param.min_value(), param.max_value());
+        param.set_value(offset);
+
+        let context = self.control.registration().context();
+        let node = context.base().register(move |registration| {
+            let render = MacroTargetRenderer { target, offset };
+
+            let node = MacroTargetNode {
+                registration,
+                channel_config: AudioNodeOptions {
+                    channel_count: 1,
+                    channel_count_mode: ChannelCountMode::Explicit,
+                    channel_interpretation: ChannelInterpretation::Discrete,
+                }
+                .into(),
+            };
+
+            (node, Box::new(render))
+        });
+
+        self.control.connect(&node);
+        node.connect(param);
+        self.targets.push(node);
+    }
+
+    /// Number of targets driven by the control
+    #[must_use]
+    pub fn number_of_targets(&self) -> usize {
+        self.targets.len()
+    }
+}
+
+impl Drop for MacroControl {
+    fn drop(&mut self) {
+        // release the source so that the target nodes are dropped in the render thread
+        self.control.stop();
+    }
+}
+
+/// Maps the control signal to the range of a single target
+#[derive(Debug)]
+struct MacroTargetNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for MacroTargetNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+struct MacroTargetRenderer {
+    target: MacroTarget,
+    /// intrinsic value of the target param
+    offset: f32,
+}
+
+impl AudioProcessor for MacroTargetRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let MacroTarget { min, max, curve } = self.target;
+        let offset = self.offset;
+
+        // a silent input is a control value of zero, which maps to `min`
+        *output = input.clone();
+        output.force_mono();
+        output.modify_channels(|channel| {
+            channel
+                .iter_mut()
+                .for_each(|o| *o = curve.map(o.clamp(0., 1.), min, max) - offset);
+        });
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+
+    use super::*;
+
+    #[test]
+    fn test_curves() {
+        assert_float_eq!(MacroCurve::Linear.map(0.25, 1., 5.), 2., abs <= 0.);
+        assert_float_eq!(MacroCurve::Linear.map(0.25, 5., 1.), 4., abs <= 0.);
+        assert_float_eq!(
+            MacroCurve::Exponential.map(0.5, 100., 10_000.),
+            1000.,
+            abs <= 1e-3
+        );
+        assert_float_eq!(MacroCurve::Power(2.).map(0.5, 0., 1.), 0.25, abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_exponential_range() {
+        let context = OfflineAudioContext::new(1, 1, 48_000.);
+        let gain = context.create_gain();
+
+        let mut control = MacroControl::new(&context, MacroControlOptions::default());
+        let target = MacroTarget {
+            min: 0.,
+            max: 1.,
+            curve: MacroCurve::Exponential,
+        };
+        control.add_target(gain.gain(), target);
+    }
+
+    #[test]
+    fn test_fan_out() {
+        let mut context = OfflineAudioContext::new(2, 128, 48_000.);
+
+        let merger = context.create_channel_merger(2);
+        merger.connect(&context.destination());
+
+        // two constant sources whose offsets are driven by the control
+        let mut src1 = context.create_constant_source();
+        src1.connect_from_output_to_input(&merger, 0, 0);
+        src1.start();
+        let mut src2 = context.create_constant_source();
+        src2.connect_from_output_to_input(&merger, 0, 1);
+        src2.start();
+
+        let options = MacroControlOptions { value: 0.5 };
+        let mut control = MacroControl::new(&context, options);
+        control.add_target(
+            src1.offset(),
+            MacroTarget {
+                min: 0.,
+                max: 2.,
+                curve: MacroCurve::Linear,
+            },
+        );
+        control.add_target(
+            src2.offset(),
+            MacroTarget {
+                min: 10.,
+                max: 1000.,
+                curve: MacroCurve::Exponential,
+            },
+        );
+        assert_eq!(control.number_of_targets(), 2);
+
+        // ramp the control from 0.5 to 1 over the render quantum
+        control
+            .value()
+            .linear_ramp_to_value_at_time(1., 128. / 48_000.);
+
+        let output = context.start_rendering_sync();
+
+        let linear = output.get_channel_data(0);
+        assert_float_eq!(linear[0], 1., abs <= 1e-5);
+        assert!(linear[127] > 1.95);
+
+        let exponential = output.get_channel_data(1);
+        assert_float_eq!(exponential[0], 100., abs <= 1e-3);
+        assert!(exponential[127] > 900.);
+    }
+}
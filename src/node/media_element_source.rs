@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::resampling::Resampler;
 use crate::MediaElement;
@@ -90,9 +92,10 @@ impl MediaElementAudioSourceNode {
                 channel_config: ChannelConfig::default(),
             };
 
+            let idle_monitor = Arc::clone(context.base().idle_monitor());
             let stream = options
                 .media_element
-                .take_stream()
+                .take_stream(idle_monitor)
                 .expect("InvalidStateError - stream already taken");
 
             let resampler = Resampler::new(context.sample_rate(), RENDER_QUANTUM_SIZE, stream);
@@ -55,6 +55,31 @@ use crossbeam_channel::{self, Receiver, Sender};
 /// # Examples
 ///
 /// - `cargo run --release --example recorder`
+///
+/// # Multiple tracks
+///
+/// A node created with [`MediaStreamAudioDestinationNode::with_number_of_tracks`] has one input
+/// per track, so separate stems can be recorded or sent from a single context:
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioNodeOptions, MediaStreamAudioDestinationNode};
+///
+/// let context = AudioContext::default();
+/// let dest = MediaStreamAudioDestinationNode::with_number_of_tracks(
+///     &context,
+///     AudioNodeOptions::default(),
+///     2,
+/// );
+///
+/// let drums_bus = context.create_gain();
+/// drums_bus.connect_from_output_to_input(&dest, 0, 0);
+/// let vocals_bus = context.create_gain();
+/// vocals_bus.connect_from_output_to_input(&dest, 0, 1);
+///
+/// let drums_track = &dest.stream().get_tracks()[0];
+/// let vocals_track = &dest.stream().get_tracks()[1];
+/// ```
 #[derive(Debug)]
 pub struct MediaStreamAudioDestinationNode {
     registration: AudioContextRegistration,
@@ -72,7 +97,7 @@ impl AudioNode for MediaStreamAudioDestinationNode {
     }
 
     fn number_of_inputs(&self) -> usize {
-        1
+        self.stream.get_tracks().len()
     }
 
     fn number_of_outputs(&self) -> usize {
@@ -83,14 +108,40 @@ impl AudioNode for MediaStreamAudioDestinationNode {
 impl MediaStreamAudioDestinationNode {
     /// Create a new MediaStreamAudioDestinationNode
     pub fn new<C: BaseAudioContext>(context: &C, options: AudioNodeOptions) -> Self {
+        Self::with_number_of_tracks(context, options, 1)
+    }
+
+    /// Create a new MediaStreamAudioDestinationNode with one input per track of its stream
+    ///
+    /// This is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `number_of_tracks` is zero
+    pub fn with_number_of_tracks<C: BaseAudioContext>(
+        context: &C,
+        options: AudioNodeOptions,
+        number_of_tracks: usize,
+    ) -> Self {
+        assert!(
+            number_of_tracks > 0,
+            "NotSupportedError - MediaStreamAudioDestinationNode needs at least one track"
+        );
+
         context.base().register(move |registration| {
-            let (send, recv) = crossbeam_channel::bounded(1);
+            let (channels, tracks): (Vec<_>, Vec<_>) = (0..number_of_tracks)
+                .map(|_| {
+                    let (send, recv) = crossbeam_channel::bounded(1);
 
-            let iter = AudioDestinationNodeStream {
-                receiver: recv.clone(),
-            };
-            let track = MediaStreamTrack::from_iter(iter);
-            let stream = MediaStream::from_tracks(vec![track]);
+                    let iter = AudioDestinationNodeStream {
+                        receiver: recv.clone(),
+                    };
+                    let track = MediaStreamTrack::from_iter(iter);
+
+                    ((send, recv), track)
+                })
+                .unzip();
+            let stream = MediaStream::from_tracks(tracks);
 
             let node = MediaStreamAudioDestinationNode {
                 registration,
@@ -98,22 +149,22 @@ impl MediaStreamAudioDestinationNode {
                 stream,
             };
 
-            let render = DestinationRenderer { send, recv };
+            let render = DestinationRenderer { channels };
 
             (node, Box::new(render))
         })
     }
 
     /// A [`MediaStream`] producing audio buffers with the same number of channels as the node
-    /// itself
+    /// itself, with one track per input
     pub fn stream(&self) -> &MediaStream {
         &self.stream
     }
 }
 
 struct DestinationRenderer {
-    send: Sender<AudioBuffer>,
-    recv: Receiver<AudioBuffer>,
+    /// sending and receiving ends of the channel of each track
+    channels: Vec<(Sender<AudioBuffer>, Receiver<AudioBuffer>)>,
 }
 
 impl AudioProcessor for DestinationRenderer {
@@ -124,20 +175,23 @@ impl AudioProcessor for DestinationRenderer {
         _params: AudioParamValues<'_>,
         scope: &AudioWorkletGlobalScope,
     ) -> bool {
-        // single input, no output
-        let input = &inputs[0];
-
-        // convert AudioRenderQuantum to AudioBuffer
-        let samples: Vec<_> = input.channels().iter().map(|c| c.to_vec()).collect();
-        let buffer = AudioBuffer::from(samples, scope.sample_rate);
-
-        // clear previous entry if it was not consumed
-        if self.recv.try_recv().is_ok() {
-            log::warn!("MediaStreamDestination buffer dropped");
-        }
-
-        // ship out AudioBuffer
-        let _ = self.send.send(buffer);
+        // one input per track, no output
+        inputs
+            .iter()
+            .zip(self.channels.iter())
+            .for_each(|(input, (send, recv))| {
+                // convert AudioRenderQuantum to AudioBuffer
+                let samples: Vec<_> = input.channels().iter().map(|c| c.to_vec()).collect();
+                let buffer = AudioBuffer::from(samples, scope.sample_rate);
+
+                // clear previous entry if it was not consumed
+                if recv.try_recv().is_ok() {
+                    log::warn!("MediaStreamDestination buffer dropped");
+                }
+
+                // ship out AudioBuffer
+                let _ = send.send(buffer);
+            });
 
         false
     }
@@ -157,3 +211,42 @@ impl Iterator for AudioDestinationNodeStream {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_multiple_tracks() {
+        let mut context = OfflineAudioContext::new(1, 128, 48_000.);
+        let dest = MediaStreamAudioDestinationNode::with_number_of_tracks(
+            &context,
+            AudioNodeOptions::default(),
+            2,
+        );
+        assert_eq!(dest.number_of_inputs(), 2);
+
+        let mut first = context.create_constant_source();
+        first.offset().set_value(1.);
+        first.connect_from_output_to_input(&dest, 0, 0);
+        first.start();
+
+        let mut second = context.create_constant_source();
+        second.offset().set_value(2.);
+        second.connect_from_output_to_input(&dest, 0, 1);
+        second.start();
+
+        let _ = context.start_rendering_sync();
+
+        let tracks = dest.stream().get_tracks();
+        let buffer = tracks[0].iter().next().unwrap().unwrap();
+        assert_float_eq!(buffer.get_channel_data(0)[..], [1.; 128][..], abs_all <= 0.);
+        let buffer = tracks[1].iter().next().unwrap().unwrap();
+        assert_float_eq!(buffer.get_channel_data(0)[..], [2.; 128][..], abs_all <= 0.);
+    }
+}
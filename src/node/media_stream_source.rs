@@ -1,5 +1,5 @@
 use crate::context::{AudioContextRegistration, BaseAudioContext};
-use crate::media_streams::MediaStream;
+use crate::media_streams::{MediaStream, ResampleQuality};
 use crate::resampling::Resampler;
 use crate::RENDER_QUANTUM_SIZE;
 
@@ -16,6 +16,8 @@ use super::{AudioNode, ChannelConfig, MediaStreamRenderer};
 #[derive(Debug)]
 pub struct MediaStreamAudioSourceOptions<'a> {
     pub media_stream: &'a MediaStream,
+    /// Quality of the conversion when the stream runs at another sample rate than the context
+    pub resample_quality: ResampleQuality,
 }
 
 /// An audio source from a [`MediaStream`] (e.g. microphone input)
@@ -58,21 +60,27 @@ impl MediaStreamAudioSourceNode {
         context: &C,
         options: MediaStreamAudioSourceOptions<'_>,
     ) -> Self {
-        context.base().register(move |registration| {
+        let node = context.base().register(move |registration| {
             let node = MediaStreamAudioSourceNode {
                 registration,
                 channel_config: ChannelConfig::default(),
             };
 
-            let resampler = Resampler::new(
+            let resampler = Resampler::with_quality(
                 context.sample_rate(),
                 RENDER_QUANTUM_SIZE,
                 options.media_stream.get_tracks()[0].iter(),
+                options.resample_quality,
             );
 
             let render = MediaStreamRenderer::new(resampler);
 
             (node, Box::new(render))
-        })
+        });
+
+        // the stream is live from the start
+        context.base().notify_source_start(0.);
+
+        node
     }
 }
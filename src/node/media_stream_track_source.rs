@@ -1,5 +1,5 @@
 use crate::context::{AudioContextRegistration, BaseAudioContext};
-use crate::media_streams::MediaStreamTrack;
+use crate::media_streams::{MediaStreamTrack, ResampleQuality};
 use crate::resampling::Resampler;
 use crate::RENDER_QUANTUM_SIZE;
 
@@ -16,6 +16,8 @@ use super::{AudioNode, ChannelConfig, MediaStreamRenderer};
 #[derive(Debug)]
 pub struct MediaStreamTrackAudioSourceOptions<'a> {
     pub media_stream_track: &'a MediaStreamTrack,
+    /// Quality of the conversion when the stream runs at another sample rate than the context
+    pub resample_quality: ResampleQuality,
 }
 
 /// An audio source from a [`MediaStreamTrack`] (e.g. the audio track of the microphone input)
@@ -87,21 +89,27 @@ impl MediaStreamTrackAudioSourceNode {
         context: &C,
         options: MediaStreamTrackAudioSourceOptions<'_>,
     ) -> Self {
-        context.base().register(move |registration| {
+        let node = context.base().register(move |registration| {
             let node = MediaStreamTrackAudioSourceNode {
                 registration,
                 channel_config: ChannelConfig::default(),
             };
 
-            let resampler = Resampler::new(
+            let resampler = Resampler::with_quality(
                 context.sample_rate(),
                 RENDER_QUANTUM_SIZE,
                 options.media_stream_track.iter(),
+                options.resample_quality,
             );
 
             let render = MediaStreamRenderer::new(resampler);
 
             (node, Box::new(render))
-        })
+        });
+
+        // the stream is live from the start
+        context.base().notify_source_start(0.);
+
+        node
     }
 }
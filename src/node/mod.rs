@@ -15,30 +15,62 @@ mod scheduled_source;
 pub use scheduled_source::*;
 
 // nodes
+mod ambisonics;
+pub use ambisonics::*;
 mod analyser;
 pub use analyser::*;
 mod audio_buffer_source;
 pub use audio_buffer_source::*;
+mod auxiliary_destination;
+pub use auxiliary_destination::*;
 mod biquad_filter;
 pub use biquad_filter::*;
+mod bus;
+pub use bus::*;
+mod channel_map;
+pub use channel_map::*;
 mod channel_merger;
 pub use channel_merger::*;
 mod channel_splitter;
 pub use channel_splitter::*;
+mod connection;
+pub use connection::*;
 mod constant_source;
 pub use constant_source::*;
 mod convolver;
 pub use convolver::*;
+mod crossover;
+pub use crossover::*;
 mod delay;
 pub use delay::*;
 mod destination;
 pub use destination::*;
 mod dynamics_compressor;
 pub use dynamics_compressor::*;
+mod echo;
+pub use echo::*;
+mod envelope;
+pub use envelope::*;
+mod envelope_follower;
+pub use envelope_follower::*;
+mod fir_filter;
+pub use fir_filter::*;
 mod gain;
 pub use gain::*;
+mod gain_stage;
+pub use gain_stage::*;
+mod gate;
+pub use gate::*;
+mod guard;
+pub use guard::*;
 mod iir_filter;
 pub use iir_filter::*;
+mod limiter;
+pub use limiter::*;
+mod linear_phase_eq;
+pub use linear_phase_eq::*;
+mod macro_control;
+pub use macro_control::*;
 mod media_element_source;
 pub use media_element_source::*;
 mod media_stream_destination;
@@ -47,17 +79,38 @@ mod media_stream_source;
 pub use media_stream_source::*;
 mod media_stream_track_source;
 pub use media_stream_track_source::*;
+#[cfg(feature = "network")]
+mod network_stream;
+#[cfg(feature = "network")]
+pub use network_stream::*;
+mod onset_detector;
+pub use onset_detector::*;
 mod oscillator;
 pub use oscillator::*;
+mod oversampled;
+pub use oversampled::*;
 mod panner;
 pub use panner::*;
+mod playlist_source;
+pub use playlist_source::*;
 mod script_processor;
 pub use script_processor::*;
+mod spatial_source;
+pub use spatial_source::*;
+mod state_variable_filter;
+pub use state_variable_filter::*;
 mod stereo_panner;
 pub use stereo_panner::*;
+mod stereo_width;
+pub use stereo_width::*;
+mod vocoder;
+pub use vocoder::*;
 mod waveshaper;
 pub use waveshaper::*;
 
+// effects
+pub mod effects;
+
 pub(crate) const TABLE_LENGTH_USIZE: usize = 8192;
 pub(crate) const TABLE_LENGTH_BY_4_USIZE: usize = TABLE_LENGTH_USIZE / 4;
 
@@ -0,0 +1,997 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+
+use crate::buffer::AudioBuffer;
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::media_streams::ResampleQuality;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::resampling::Resampler;
+use crate::{SeededRng, RENDER_QUANTUM_SIZE};
+
+use super::{
+    AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation,
+    MediaStreamRenderer,
+};
+
+/// Size of the fixed RTP header, without contributing sources
+const RTP_HEADER_LEN: usize = 12;
+
+/// Version field of the RTP header
+const RTP_VERSION: u8 = 2;
+
+/// Maximum payload size of a packet, to stay below the MTU of common networks
+const MAX_PAYLOAD: usize = 1200;
+
+/// Maximum number of frames of an L16 packet, 5 ms at 48 kHz
+const L16_PACKET_FRAMES: usize = 240;
+
+/// Sample rate of Opus streams, which is also their RTP clock rate (RFC 7587)
+#[cfg(feature = "network-opus")]
+const OPUS_SAMPLE_RATE: u32 = 48_000;
+
+/// Number of frames of an Opus packet, 20 ms
+#[cfg(feature = "network-opus")]
+const OPUS_PACKET_FRAMES: usize = 960;
+
+/// Maximum number of frames of a received Opus packet, 120 ms
+#[cfg(feature = "network-opus")]
+const OPUS_MAX_FRAMES: usize = 5760;
+
+/// Interval at which the receiving thread checks if the node has been dropped
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Number of render quanta that can be in flight between the render and network threads
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Fill level of the jitter buffer, relative to its target latency, at which frames are skipped
+const MAX_FILL: u32 = 4;
+
+/// Default port of the network streams
+const DEFAULT_PORT: u16 = 5004;
+
+/// Default payload type, the first dynamic one
+const DEFAULT_PAYLOAD_TYPE: u8 = 96;
+
+/// Encoding of the audio sent over the network
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NetworkAudioCodec {
+    /// Uncompressed 16 bit linear PCM (L16, RFC 3551) at the sample rate of the context
+    #[default]
+    L16,
+    /// Opus (RFC 7587) at 48 kHz and the given bitrate, in bits per second
+    ///
+    /// Opus streams have one or two channels. This codec requires the `network-opus` feature.
+    Opus { bitrate: u32 },
+}
+
+/// Options for constructing a [`NetworkStreamSinkNode`]
+#[derive(Clone, Debug)]
+pub struct NetworkStreamSinkOptions {
+    /// Address the stream is sent to
+    pub address: SocketAddr,
+    pub codec: NetworkAudioCodec,
+    /// Payload type of the RTP packets, see [`NetworkStreamSourceOptions::payload_type`]
+    pub payload_type: u8,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for NetworkStreamSinkOptions {
+    fn default() -> Self {
+        Self {
+            address: SocketAddr::from(([127, 0, 0, 1], DEFAULT_PORT)),
+            codec: NetworkAudioCodec::default(),
+            payload_type: DEFAULT_PAYLOAD_TYPE,
+            audio_node_options: AudioNodeOptions {
+                channel_count: 2,
+                channel_count_mode: ChannelCountMode::Explicit,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// Options for constructing a [`NetworkStreamSourceNode`]
+#[derive(Clone, Debug)]
+pub struct NetworkStreamSourceOptions {
+    /// Local address the stream is received on
+    pub address: SocketAddr,
+    pub codec: NetworkAudioCodec,
+    /// Payload type of the RTP packets, packets of other types are ignored
+    pub payload_type: u8,
+    /// Number of channels of the stream
+    pub number_of_channels: usize,
+    /// Sample rate of an L16 stream, Opus streams always run at 48 kHz
+    pub sample_rate: f32,
+    /// Delay of the jitter buffer, in seconds
+    pub latency: f64,
+    /// Quality of the conversion when the stream runs at another sample rate than the context
+    pub resample_quality: ResampleQuality,
+}
+
+impl Default for NetworkStreamSourceOptions {
+    fn default() -> Self {
+        Self {
+            address: SocketAddr::from(([0, 0, 0, 0], DEFAULT_PORT)),
+            codec: NetworkAudioCodec::default(),
+            payload_type: DEFAULT_PAYLOAD_TYPE,
+            number_of_channels: 2,
+            sample_rate: 48_000.,
+            latency: 0.04,
+            resample_quality: ResampleQuality::default(),
+        }
+    }
+}
+
+/// Sends its input to another machine, as an RTP stream over UDP
+///
+/// The stream carries uncompressed 16 bit PCM or Opus, see [`NetworkAudioCodec`], and can be
+/// received by a [`NetworkStreamSourceNode`] or any RTP receiver, e.g. `ffplay` or GStreamer,
+/// given the payload type, the number of channels and the sample rate of the stream. The packets
+/// are sent from a dedicated thread, as the render thread produces them.
+///
+/// This node is not part of the Web Audio API specification, it requires the `network` feature.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{
+///     AudioNode, AudioScheduledSourceNode, NetworkStreamSinkNode, NetworkStreamSinkOptions,
+/// };
+///
+/// let context = AudioContext::default();
+///
+/// let options = NetworkStreamSinkOptions {
+///     address: "192.168.1.20:5004".parse().unwrap(),
+///     ..NetworkStreamSinkOptions::default()
+/// };
+/// let sink = NetworkStreamSinkNode::new(&context, options).unwrap();
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&sink);
+/// osc.start();
+/// ```
+#[derive(Debug)]
+pub struct NetworkStreamSinkNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    address: SocketAddr,
+}
+
+impl AudioNode for NetworkStreamSinkNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        0
+    }
+}
+
+impl NetworkStreamSinkNode {
+    /// Create a new `NetworkStreamSinkNode`
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error when the socket cannot be opened, or when the codec does
+    /// not support the number of channels of the node.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the channel count is zero or greater than
+    /// [`MAX_CHANNELS`](crate::MAX_CHANNELS).
+    pub fn new<C: BaseAudioContext>(
+        context: &C,
+        options: NetworkStreamSinkOptions,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let NetworkStreamSinkOptions {
+            address,
+            codec,
+            payload_type,
+            audio_node_options,
+        } = options;
+
+        let number_of_channels = audio_node_options.channel_count;
+        crate::assert_valid_number_of_channels(number_of_channels);
+        let encoder = Encoder::new(codec, number_of_channels)?;
+        let (sample_rate, packet_frames) = match &encoder {
+            Encoder::L16 => {
+                let frames = (MAX_PAYLOAD / (2 * number_of_channels)).min(L16_PACKET_FRAMES);
+                (context.sample_rate(), frames)
+            }
+            #[cfg(feature = "network-opus")]
+            Encoder::Opus(_) => (OPUS_SAMPLE_RATE as f32, OPUS_PACKET_FRAMES),
+        };
+
+        let socket = UdpSocket::bind(if address.is_ipv4() {
+            SocketAddr::from(([0, 0, 0, 0], 0))
+        } else {
+            SocketAddr::from(([0; 16], 0))
+        })?;
+
+        let mut rng = SeededRng::new(random_seed());
+        let header = RtpHeader {
+            payload_type,
+            sequence: rng.next_u32() as u16,
+            timestamp: rng.next_u32(),
+            ssrc: rng.next_u32(),
+        };
+
+        let (sender, receiver) = crossbeam_channel::bounded(CHANNEL_CAPACITY);
+        let packets = Resampler::new(
+            sample_rate,
+            packet_frames,
+            receiver
+                .into_iter()
+                .map(Ok::<_, Box<dyn Error + Send + Sync>>),
+        );
+        std::thread::Builder::new()
+            .name("network-stream-sink".into())
+            .spawn(move || send_packets(packets, socket, address, encoder, header))?;
+
+        let node = context.base().register(move |registration| {
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                address,
+            };
+            let render = NetworkStreamSinkRenderer {
+                sender,
+                number_of_channels,
+            };
+
+            (node, Box::new(render))
+        });
+
+        Ok(node)
+    }
+
+    /// Address the stream is sent to
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+struct NetworkStreamSinkRenderer {
+    sender: Sender<AudioBuffer>,
+    number_of_channels: usize,
+}
+
+impl AudioProcessor for NetworkStreamSinkRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        _outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input node, no output
+        let input = &inputs[0];
+
+        // the packets have a fixed number of channels, a silent quantum may have a single one
+        let samples = (0..self.number_of_channels)
+            .map(|i| match input.channels().get(i) {
+                Some(channel) if !input.is_silent() => channel.to_vec(),
+                _ => vec![0.; RENDER_QUANTUM_SIZE],
+            })
+            .collect();
+        let buffer = AudioBuffer::from(samples, scope.sample_rate);
+
+        if self.sender.try_send(buffer).is_err() {
+            log::debug!("NetworkStreamSink buffer dropped");
+        }
+
+        false
+    }
+}
+
+/// Encode and send the packets, until the node is dropped
+fn send_packets<I: Iterator<Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>>>(
+    packets: I,
+    socket: UdpSocket,
+    address: SocketAddr,
+    mut encoder: Encoder,
+    mut header: RtpHeader,
+) {
+    let mut packet = Vec::with_capacity(RTP_HEADER_LEN + MAX_PAYLOAD);
+
+    for buffer in packets {
+        let Ok(buffer) = buffer else { break };
+
+        packet.clear();
+        header.write(&mut packet);
+        if let Err(e) = encoder.encode(&buffer, &mut packet) {
+            log::warn!("NetworkStreamSink encoding failed: {}", e);
+            break;
+        }
+
+        // the receiver may not be listening yet, keep on sending
+        if let Err(e) = socket.send_to(&packet, address) {
+            log::debug!("NetworkStreamSink packet not sent: {}", e);
+        }
+
+        header.sequence = header.sequence.wrapping_add(1);
+        header.timestamp = header.timestamp.wrapping_add(buffer.length() as u32);
+    }
+}
+
+/// Receives audio from another machine, as an RTP stream over UDP
+///
+/// The stream is sent by a [`NetworkStreamSinkNode`] or any RTP sender, e.g. `ffmpeg` or
+/// GStreamer. As RTP does not describe the stream, the codec, number of channels and sample rate
+/// are set in the [`NetworkStreamSourceOptions`].
+///
+/// The packets are reordered in a jitter buffer, which delays the stream by the `latency` of
+/// the options to absorb the irregular arrival of the packets. Lost packets are played as
+/// silence. The clocks of both machines drift apart over time: the stream skips frames when the
+/// jitter buffer overflows and buffers again when it runs dry.
+///
+/// This node is not part of the Web Audio API specification, it requires the `network` feature.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, NetworkStreamSourceNode, NetworkStreamSourceOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let options = NetworkStreamSourceOptions {
+///     address: "0.0.0.0:5004".parse().unwrap(),
+///     latency: 0.02,
+///     ..NetworkStreamSourceOptions::default()
+/// };
+/// let source = NetworkStreamSourceNode::new(&context, options).unwrap();
+/// source.connect(&context.destination());
+/// ```
+#[derive(Debug)]
+pub struct NetworkStreamSourceNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    local_addr: SocketAddr,
+}
+
+impl AudioNode for NetworkStreamSourceNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl NetworkStreamSourceNode {
+    /// Create a new `NetworkStreamSourceNode`
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error when the socket cannot be bound, or when the codec does not
+    /// support the number of channels of the stream.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of channels is zero or greater than
+    /// [`MAX_CHANNELS`](crate::MAX_CHANNELS).
+    pub fn new<C: BaseAudioContext>(
+        context: &C,
+        options: NetworkStreamSourceOptions,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let NetworkStreamSourceOptions {
+            address,
+            codec,
+            payload_type,
+            number_of_channels,
+            sample_rate,
+            latency,
+            resample_quality,
+        } = options;
+
+        crate::assert_valid_number_of_channels(number_of_channels);
+
+        let decoder = Decoder::new(codec, number_of_channels)?;
+        let sample_rate = match &decoder {
+            Decoder::L16 => sample_rate,
+            #[cfg(feature = "network-opus")]
+            Decoder::Opus(_) => OPUS_SAMPLE_RATE as f32,
+        };
+
+        let socket = UdpSocket::bind(address)?;
+        socket.set_read_timeout(Some(RECEIVE_TIMEOUT))?;
+        let local_addr = socket.local_addr()?;
+
+        let closed = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = crossbeam_channel::bounded(CHANNEL_CAPACITY);
+        let receiving = ReceivingThread {
+            socket,
+            decoder,
+            payload_type,
+            number_of_channels,
+            sender,
+            closed: Arc::clone(&closed),
+        };
+        std::thread::Builder::new()
+            .name("network-stream-source".into())
+            .spawn(move || receiving.run())?;
+
+        let latency = ((latency * f64::from(sample_rate)) as u32).max(RENDER_QUANTUM_SIZE as u32);
+        let stream = JitterBuffer::new(receiver, closed, number_of_channels, sample_rate, latency);
+
+        let node = context.base().register(move |registration| {
+            let node = Self {
+                registration,
+                channel_config: ChannelConfig::default(),
+                local_addr,
+            };
+
+            let resampler = Resampler::with_quality(
+                context.sample_rate(),
+                RENDER_QUANTUM_SIZE,
+                stream,
+                resample_quality,
+            );
+            let render = MediaStreamRenderer::new(resampler);
+
+            (node, Box::new(render))
+        });
+
+        // packets are played as soon as they arrive
+        context.base().notify_source_start(0.);
+
+        Ok(node)
+    }
+
+    /// Local address the stream is received on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+/// Receive and decode the packets, until the node is dropped
+struct ReceivingThread {
+    socket: UdpSocket,
+    decoder: Decoder,
+    payload_type: u8,
+    number_of_channels: usize,
+    sender: Sender<Packet>,
+    closed: Arc<AtomicBool>,
+}
+
+impl ReceivingThread {
+    fn run(mut self) {
+        let mut data = [0; 2048];
+
+        while !self.closed.load(Ordering::Relaxed) {
+            let len = match self.socket.recv_from(&mut data) {
+                Ok((len, _)) => len,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::WouldBlock
+                            | ErrorKind::TimedOut
+                            | ErrorKind::Interrupted
+                            | ErrorKind::ConnectionReset
+                    ) =>
+                {
+                    continue
+                }
+                Err(e) => {
+                    log::warn!("NetworkStreamSource receiving failed: {}", e);
+                    break;
+                }
+            };
+
+            let Some((header, payload)) = RtpHeader::parse(&data[..len]) else {
+                log::debug!("NetworkStreamSource invalid packet ignored");
+                continue;
+            };
+            if header.payload_type != self.payload_type {
+                continue;
+            }
+
+            let samples = match self.decoder.decode(payload, self.number_of_channels) {
+                Ok(samples) if !samples[0].is_empty() => samples,
+                Ok(_) => continue,
+                Err(e) => {
+                    log::debug!("NetworkStreamSource decoding failed: {}", e);
+                    continue;
+                }
+            };
+
+            let packet = Packet {
+                ssrc: header.ssrc,
+                timestamp: header.timestamp,
+                samples,
+            };
+            if self.sender.try_send(packet).is_err() {
+                log::debug!("NetworkStreamSource packet dropped");
+            }
+        }
+    }
+}
+
+/// Decoded packet of the stream
+struct Packet {
+    ssrc: u32,
+    /// RTP timestamp of the first frame
+    timestamp: u32,
+    samples: Vec<Vec<f32>>,
+}
+
+impl Packet {
+    fn length(&self) -> usize {
+        self.samples[0].len()
+    }
+
+    /// RTP timestamp following the last frame
+    fn end(&self) -> u32 {
+        self.timestamp.wrapping_add(self.length() as u32)
+    }
+}
+
+/// Distance between two RTP timestamps, in frames, accounting for their wrap around
+fn offset(timestamp: u32, reference: u32) -> i32 {
+    timestamp.wrapping_sub(reference) as i32
+}
+
+/// Stream of the received packets, reordered and played with a fixed delay
+struct JitterBuffer {
+    receiver: Receiver<Packet>,
+    closed: Arc<AtomicBool>,
+    number_of_channels: usize,
+    sample_rate: f32,
+    /// Delay of the playhead behind the newest frame, in frames
+    latency: u32,
+    /// Source of the stream, a new source restarts the buffering
+    ssrc: Option<u32>,
+    /// Packets not played yet, ordered by timestamp
+    queue: VecDeque<Packet>,
+    /// RTP timestamp of the next frame to play, `None` while buffering
+    playhead: Option<u32>,
+}
+
+impl JitterBuffer {
+    fn new(
+        receiver: Receiver<Packet>,
+        closed: Arc<AtomicBool>,
+        number_of_channels: usize,
+        sample_rate: f32,
+        latency: u32,
+    ) -> Self {
+        Self {
+            receiver,
+            closed,
+            number_of_channels,
+            sample_rate,
+            latency,
+            ssrc: None,
+            queue: VecDeque::new(),
+            playhead: None,
+        }
+    }
+
+    fn silence(&self) -> AudioBuffer {
+        let samples = vec![vec![0.; RENDER_QUANTUM_SIZE]; self.number_of_channels];
+        AudioBuffer::from(samples, self.sample_rate)
+    }
+
+    /// Insert the received packets in the queue, returns false if the receiving thread stopped
+    fn receive(&mut self) -> bool {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(packet) => self.insert(packet),
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    fn insert(&mut self, packet: Packet) {
+        if self.ssrc != Some(packet.ssrc) {
+            // new stream, e.g. the sender restarted
+            self.ssrc = Some(packet.ssrc);
+            self.queue.clear();
+            self.playhead = None;
+        }
+
+        if let Some(playhead) = self.playhead {
+            if offset(packet.end(), playhead) <= 0 {
+                log::debug!("NetworkStreamSource late packet dropped");
+                return;
+            }
+        }
+
+        // packets mostly arrive in order, search from the back
+        let mut index = self.queue.len();
+        while index > 0 && offset(self.queue[index - 1].timestamp, packet.timestamp) > 0 {
+            index -= 1;
+        }
+        if index > 0 && self.queue[index - 1].timestamp == packet.timestamp {
+            return; // duplicate
+        }
+
+        self.queue.insert(index, packet);
+    }
+}
+
+impl Iterator for JitterBuffer {
+    type Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the stream ends when the receiving thread stopped and the queue has been played
+        let connected = self.receive();
+        if !connected && self.queue.is_empty() {
+            return None;
+        }
+
+        let Some(end) = self.queue.back().map(Packet::end) else {
+            if self.playhead.take().is_some() {
+                log::debug!("NetworkStreamSource buffer underrun");
+            }
+            return Some(Ok(self.silence()));
+        };
+
+        let target = end.wrapping_sub(self.latency);
+        let playhead = match self.playhead {
+            None => {
+                // wait for the queue to reach the latency before playing
+                if offset(end, self.queue[0].timestamp) < self.latency as i32 {
+                    return Some(Ok(self.silence()));
+                }
+                target
+            }
+            Some(playhead) if offset(end, playhead) > (MAX_FILL * self.latency) as i32 => {
+                log::debug!("NetworkStreamSource buffer overrun, skipping frames");
+                target
+            }
+            Some(playhead) => playhead,
+        };
+
+        // frames missing from the queue are lost packets, left silent
+        let mut samples = vec![vec![0.; RENDER_QUANTUM_SIZE]; self.number_of_channels];
+        for packet in &self.queue {
+            let start = offset(packet.timestamp, playhead);
+            if start >= RENDER_QUANTUM_SIZE as i32 {
+                break;
+            }
+
+            let skip = (-start).max(0) as usize;
+            let position = start.max(0) as usize;
+            let count = packet
+                .length()
+                .saturating_sub(skip)
+                .min(RENDER_QUANTUM_SIZE - position);
+            samples
+                .iter_mut()
+                .zip(&packet.samples)
+                .for_each(|(output, input)| {
+                    output[position..position + count].copy_from_slice(&input[skip..skip + count])
+                });
+        }
+
+        let playhead = playhead.wrapping_add(RENDER_QUANTUM_SIZE as u32);
+        while self
+            .queue
+            .front()
+            .is_some_and(|packet| offset(packet.end(), playhead) <= 0)
+        {
+            self.queue.pop_front();
+        }
+        self.playhead = Some(playhead);
+
+        Some(Ok(AudioBuffer::from(samples, self.sample_rate)))
+    }
+}
+
+impl Drop for JitterBuffer {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Fixed part of the RTP header (RFC 3550)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RtpHeader {
+    payload_type: u8,
+    sequence: u16,
+    timestamp: u32,
+    ssrc: u32,
+}
+
+impl RtpHeader {
+    fn write(&self, packet: &mut Vec<u8>) {
+        packet.push(RTP_VERSION << 6);
+        packet.push(self.payload_type & 0x7f);
+        packet.extend_from_slice(&self.sequence.to_be_bytes());
+        packet.extend_from_slice(&self.timestamp.to_be_bytes());
+        packet.extend_from_slice(&self.ssrc.to_be_bytes());
+    }
+
+    /// Parse the header of a packet, returns the header and the payload
+    fn parse(data: &[u8]) -> Option<(Self, &[u8])> {
+        if data.len() < RTP_HEADER_LEN || data[0] >> 6 != RTP_VERSION {
+            return None;
+        }
+
+        // skip the contributing sources and the header extension
+        let mut start = RTP_HEADER_LEN + 4 * usize::from(data[0] & 0x0f);
+        if data[0] & 0x10 != 0 {
+            let extension = data.get(start..start + 4)?;
+            start += 4 + 4 * usize::from(u16::from_be_bytes([extension[2], extension[3]]));
+        }
+
+        // strip the padding
+        let mut end = data.len();
+        if data[0] & 0x20 != 0 {
+            end = end.checked_sub(usize::from(data[end - 1]))?;
+        }
+
+        let header = Self {
+            payload_type: data[1] & 0x7f,
+            sequence: u16::from_be_bytes([data[2], data[3]]),
+            timestamp: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            ssrc: u32::from_be_bytes([data[8], data[9], data[10], data[11]]),
+        };
+
+        Some((header, data.get(start..end)?))
+    }
+}
+
+/// Seed of the random sequence number, timestamp and source identifier of a sink
+fn random_seed() -> u64 {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    time ^ (u64::from(std::process::id()) << 32)
+}
+
+#[cfg(not(feature = "network-opus"))]
+fn opus_unsupported() -> Box<dyn Error + Send + Sync> {
+    "NotSupportedError - the Opus codec requires the network-opus feature".into()
+}
+
+#[cfg(feature = "network-opus")]
+fn opus_channels(
+    number_of_channels: usize,
+) -> Result<opus::Channels, Box<dyn Error + Send + Sync>> {
+    match number_of_channels {
+        1 => Ok(opus::Channels::Mono),
+        2 => Ok(opus::Channels::Stereo),
+        _ => Err("NotSupportedError - Opus streams have one or two channels".into()),
+    }
+}
+
+enum Encoder {
+    L16,
+    #[cfg(feature = "network-opus")]
+    Opus(opus::Encoder),
+}
+
+impl Encoder {
+    fn new(
+        codec: NetworkAudioCodec,
+        number_of_channels: usize,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match codec {
+            NetworkAudioCodec::L16 => Ok(Self::L16),
+            #[cfg(feature = "network-opus")]
+            NetworkAudioCodec::Opus { bitrate } => {
+                let channels = opus_channels(number_of_channels)?;
+                let mut encoder =
+                    opus::Encoder::new(OPUS_SAMPLE_RATE, channels, opus::Application::Audio)?;
+                encoder.set_bitrate(opus::Bitrate::Bits(bitrate as i32))?;
+                Ok(Self::Opus(encoder))
+            }
+            #[cfg(not(feature = "network-opus"))]
+            NetworkAudioCodec::Opus { .. } => {
+                let _ = number_of_channels;
+                Err(opus_unsupported())
+            }
+        }
+    }
+
+    /// Append the encoded buffer to the packet
+    fn encode(
+        &mut self,
+        buffer: &AudioBuffer,
+        packet: &mut Vec<u8>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let interleaved = (0..buffer.length())
+            .flat_map(|i| buffer.channels().iter().map(move |c| c.as_slice()[i]));
+
+        match self {
+            Self::L16 => {
+                interleaved.for_each(|s| {
+                    let value = (s.clamp(-1., 1.) * f32::from(i16::MAX)).round() as i16;
+                    packet.extend_from_slice(&value.to_be_bytes());
+                });
+            }
+            #[cfg(feature = "network-opus")]
+            Self::Opus(encoder) => {
+                let interleaved: Vec<f32> = interleaved.collect();
+                let mut payload = [0; MAX_PAYLOAD];
+                let len = encoder.encode_float(&interleaved, &mut payload)?;
+                packet.extend_from_slice(&payload[..len]);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+enum Decoder {
+    L16,
+    #[cfg(feature = "network-opus")]
+    Opus(opus::Decoder),
+}
+
+impl Decoder {
+    fn new(
+        codec: NetworkAudioCodec,
+        number_of_channels: usize,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match codec {
+            NetworkAudioCodec::L16 => Ok(Self::L16),
+            #[cfg(feature = "network-opus")]
+            NetworkAudioCodec::Opus { .. } => {
+                let channels = opus_channels(number_of_channels)?;
+                Ok(Self::Opus(opus::Decoder::new(OPUS_SAMPLE_RATE, channels)?))
+            }
+            #[cfg(not(feature = "network-opus"))]
+            NetworkAudioCodec::Opus { .. } => {
+                let _ = number_of_channels;
+                Err(opus_unsupported())
+            }
+        }
+    }
+
+    /// Decode the payload of a packet, returns the samples of each channel
+    fn decode(
+        &mut self,
+        payload: &[u8],
+        number_of_channels: usize,
+    ) -> Result<Vec<Vec<f32>>, Box<dyn Error + Send + Sync>> {
+        let mut samples = vec![Vec::new(); number_of_channels];
+
+        match self {
+            Self::L16 => {
+                let frames = payload.len() / (2 * number_of_channels);
+                payload
+                    .chunks_exact(2)
+                    .take(frames * number_of_channels)
+                    .enumerate()
+                    .for_each(|(i, bytes)| {
+                        let value = i16::from_be_bytes([bytes[0], bytes[1]]);
+                        samples[i % number_of_channels].push(f32::from(value) / 32768.);
+                    });
+            }
+            #[cfg(feature = "network-opus")]
+            Self::Opus(decoder) => {
+                let mut interleaved = vec![0.; OPUS_MAX_FRAMES * number_of_channels];
+                let frames = decoder.decode_float(payload, &mut interleaved, false)?;
+                interleaved[..frames * number_of_channels]
+                    .iter()
+                    .enumerate()
+                    .for_each(|(i, &s)| samples[i % number_of_channels].push(s));
+            }
+        }
+
+        Ok(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_rtp_header() {
+        let header = RtpHeader {
+            payload_type: 96,
+            sequence: 65535,
+            timestamp: 1234,
+            ssrc: 42,
+        };
+        let mut packet = vec![];
+        header.write(&mut packet);
+        packet.extend_from_slice(&[1, 2, 3]);
+
+        let (parsed, payload) = RtpHeader::parse(&packet).unwrap();
+        assert_eq!(parsed, header);
+        assert_eq!(payload, &[1, 2, 3]);
+
+        // one contributing source and two bytes of padding
+        packet[0] |= 0x20 | 0x01;
+        packet.splice(12..12, [0; 4]);
+        packet.extend_from_slice(&[0, 2]);
+        let (parsed, payload) = RtpHeader::parse(&packet).unwrap();
+        assert_eq!(parsed, header);
+        assert_eq!(payload, &[1, 2, 3]);
+
+        // truncated
+        assert!(RtpHeader::parse(&packet[..8]).is_none());
+    }
+
+    #[test]
+    fn test_l16_codec() {
+        let mut encoder = Encoder::new(NetworkAudioCodec::L16, 2).unwrap();
+        let mut decoder = Decoder::new(NetworkAudioCodec::L16, 2).unwrap();
+
+        let left = vec![0., 0.5, -0.5, 1.];
+        let right = vec![-1., 0.25, 0.125, 0.];
+        let buffer = AudioBuffer::from(vec![left.clone(), right.clone()], 48_000.);
+
+        let mut payload = vec![];
+        encoder.encode(&buffer, &mut payload).unwrap();
+        assert_eq!(payload.len(), 16);
+
+        let samples = decoder.decode(&payload, 2).unwrap();
+        assert_float_eq!(samples[0][..], left[..], abs_all <= 1. / 16384.);
+        assert_float_eq!(samples[1][..], right[..], abs_all <= 1. / 16384.);
+    }
+
+    #[test]
+    fn test_jitter_buffer() {
+        let (sender, receiver) = crossbeam_channel::bounded(CHANNEL_CAPACITY);
+        let closed = Arc::new(AtomicBool::new(false));
+        let mut stream = JitterBuffer::new(receiver, closed, 1, 48_000., 256);
+
+        let packet = |i: u32| Packet {
+            ssrc: 1,
+            timestamp: u32::MAX - 200 + i * 128,
+            samples: vec![vec![i as f32 + 1.; 128]],
+        };
+        let mut next = || stream.next().unwrap().unwrap().get_channel_data(0)[0];
+
+        // reordered packets
+        sender.send(packet(1)).unwrap();
+        assert_float_eq!(next(), 0., abs <= 0.);
+        sender.send(packet(0)).unwrap();
+        assert_float_eq!(next(), 1., abs <= 0.);
+        assert_float_eq!(next(), 2., abs <= 0.);
+
+        // a lost packet is played as silence, it is dropped when it arrives late
+        sender.send(packet(2)).unwrap();
+        sender.send(packet(4)).unwrap();
+        assert_float_eq!(next(), 3., abs <= 0.);
+        assert_float_eq!(next(), 0., abs <= 0.);
+        sender.send(packet(3)).unwrap();
+        assert_float_eq!(next(), 5., abs <= 0.);
+
+        // buffering again after an underrun
+        assert_float_eq!(next(), 0., abs <= 0.);
+        assert!(stream.playhead.is_none());
+
+        // the stream ends when the receiving thread stops
+        drop(sender);
+        assert!(stream.next().is_none());
+    }
+}
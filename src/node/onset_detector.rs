@@ -0,0 +1,439 @@
+use std::any::Any;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use realfft::{num_complex::Complex, RealToComplex};
+
+use crate::analysis::fft_planner;
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::events::{EventHandler, EventPayload, EventType, OnsetEvent};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelInterpretation};
+
+/// Number of past analysis frames the adaptive threshold is computed from
+const HISTORY_LENGTH: usize = 16;
+/// Scaling of the magnitudes before the log compression
+const MAGNITUDE_COMPRESSION: f32 = 100.;
+/// Spectral flux below which no onset is detected, so that noise is not reported
+const FLUX_FLOOR: f32 = 1e-3;
+
+const MIN_FFT_SIZE: usize = 256;
+const MAX_FFT_SIZE: usize = 8192;
+
+#[track_caller]
+#[inline(always)]
+#[allow(clippy::manual_range_contains)]
+fn assert_valid_fft_size(fft_size: usize) {
+    assert!(
+        fft_size.is_power_of_two() && fft_size >= MIN_FFT_SIZE && fft_size <= MAX_FFT_SIZE,
+        "IndexSizeError - Invalid fft size: {:?} is not a power of two in range [{:?}, {:?}]",
+        fft_size,
+        MIN_FFT_SIZE,
+        MAX_FFT_SIZE
+    );
+}
+
+#[track_caller]
+#[inline(always)]
+fn assert_valid_threshold(threshold: f32) {
+    assert!(
+        threshold.is_finite() && threshold > 0.,
+        "RangeError - Invalid threshold: {:?} should be strictly positive",
+        threshold
+    );
+}
+
+/// Options for constructing an [`OnsetDetectorNode`]
+#[derive(Clone, Debug)]
+pub struct OnsetDetectorOptions {
+    /// Size of the analysis frames, a power of two in the range [256, 8192]. Successive frames
+    /// overlap by half their size.
+    pub fft_size: usize,
+    /// Factor applied to the mean spectral flux of the recent frames to get the adaptive
+    /// threshold, higher values detect less onsets
+    pub threshold: f32,
+    /// Minimum time in seconds between two onsets
+    pub min_interval: f64,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for OnsetDetectorOptions {
+    fn default() -> Self {
+        Self {
+            fft_size: 1024,
+            threshold: 1.5,
+            min_interval: 0.05,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `OnsetDetectorNode` detects the onsets (note attacks, drum hits) of its input and dispatches
+/// them as events on the control thread
+///
+/// The input is down-mixed to mono and analysed in overlapping frames. An onset is detected when
+/// the spectral flux, i.e. the increase of the (log compressed) magnitude spectrum from one
+/// frame to the next, rises above an adaptive threshold derived from the recent frames. The
+/// time of the onset is estimated with the accuracy of half a frame hop, i.e. `fft_size / 4`
+/// sample frames.
+///
+/// The input is passed through unchanged to the output, so the node can be inserted anywhere
+/// in the graph.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, OnsetDetectorNode, OnsetDetectorOptions};
+///
+/// let context = AudioContext::default();
+/// let mic = web_audio_api::media_devices::get_user_media_sync(
+///     web_audio_api::media_devices::MediaStreamConstraints::Audio,
+/// );
+/// let input = context.create_media_stream_source(&mic);
+///
+/// let detector = OnsetDetectorNode::new(&context, OnsetDetectorOptions::default());
+/// input.connect(&detector);
+///
+/// detector.set_ononset(|event| {
+///     println!("hit at {:.3}s (strength {:.1})", event.time, event.strength);
+/// });
+/// ```
+#[derive(Debug)]
+pub struct OnsetDetectorNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    fft_size: usize,
+    threshold: f32,
+    min_interval: f64,
+}
+
+impl AudioNode for OnsetDetectorNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl OnsetDetectorNode {
+    /// Create a new `OnsetDetectorNode`
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    /// - the fft size is not a power of two in the range [256, 8192]
+    /// - the threshold is not strictly positive
+    /// - the min interval is negative or not finite
+    pub fn new<C: BaseAudioContext>(context: &C, options: OnsetDetectorOptions) -> Self {
+        let OnsetDetectorOptions {
+            fft_size,
+            threshold,
+            min_interval,
+            audio_node_options,
+        } = options;
+
+        assert_valid_fft_size(fft_size);
+        assert_valid_threshold(threshold);
+        crate::assert_valid_time_value(min_interval);
+
+        context.base().register(move |registration| {
+            let fft = fft_planner().lock().unwrap().plan_fft_forward(fft_size);
+
+            // periodic Hann window
+            let window: Vec<f32> = (0..fft_size)
+                .map(|i| 0.5 - 0.5 * (2. * PI * i as f32 / fft_size as f32).cos())
+                .collect();
+            // magnitude of a sinusoid of amplitude 1
+            let normalize_factor = 2. / window.iter().sum::<f32>();
+
+            let render = OnsetDetectorRenderer {
+                frame: fft.make_input_vec(),
+                spectrum: fft.make_output_vec(),
+                scratch: fft.make_scratch_vec(),
+                magnitudes: vec![0.; fft_size / 2],
+                fft,
+                window,
+                normalize_factor,
+                ring_buffer: vec![0.; fft_size],
+                write_index: 0,
+                hop_size: fft_size / 2,
+                samples_until_hop: fft_size / 2,
+                history: [0.; HISTORY_LENGTH],
+                history_index: 0,
+                previous_flux: 0.,
+                threshold,
+                min_interval,
+                last_onset: f64::NEG_INFINITY,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                fft_size,
+                threshold,
+                min_interval,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Size of the analysis frames, in sample-frames
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Minimum time in seconds between two onsets
+    pub fn min_interval(&self) -> f64 {
+        self.min_interval
+    }
+
+    /// Factor applied to the mean spectral flux of the recent frames to get the adaptive
+    /// threshold
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Update the threshold factor, higher values detect less onsets
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the threshold is not strictly positive
+    pub fn set_threshold(&mut self, value: f32) {
+        assert_valid_threshold(value);
+        self.threshold = value;
+        self.registration.post_message(value);
+    }
+
+    /// Register callback to run when an onset is detected
+    ///
+    /// Only a single event handler is active at any time. Calling this method multiple times will
+    /// override the previous event handler.
+    pub fn set_ononset<F: FnMut(OnsetEvent) + Send + 'static>(&self, mut callback: F) {
+        let callback = move |v| match v {
+            EventPayload::Onset(v) => callback(v),
+            _ => unreachable!(),
+        };
+
+        self.context().set_event_handler(
+            EventType::Onset(self.registration().id()),
+            EventHandler::Multiple(Box::new(callback)),
+        );
+    }
+
+    /// Unset the callback to run when an onset is detected
+    pub fn clear_ononset(&self) {
+        self.context()
+            .clear_event_handler(EventType::Onset(self.registration().id()));
+    }
+}
+
+struct OnsetDetectorRenderer {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    normalize_factor: f32,
+    frame: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    /// log compressed magnitudes of the previous frame
+    magnitudes: Vec<f32>,
+    ring_buffer: Vec<f32>,
+    write_index: usize,
+    hop_size: usize,
+    samples_until_hop: usize,
+    /// spectral flux of the recent frames
+    history: [f32; HISTORY_LENGTH],
+    history_index: usize,
+    previous_flux: f32,
+    threshold: f32,
+    min_interval: f64,
+    last_onset: f64,
+}
+
+impl OnsetDetectorRenderer {
+    /// Compute the spectral flux of the frame ending at the current write index
+    fn spectral_flux(&mut self) -> f32 {
+        // unroll the ring buffer, oldest sample first
+        let (newest, oldest) = self.ring_buffer.split_at(self.write_index);
+        let (head, tail) = self.frame.split_at_mut(oldest.len());
+        head.copy_from_slice(oldest);
+        tail.copy_from_slice(newest);
+
+        self.frame
+            .iter_mut()
+            .zip(self.window.iter())
+            .for_each(|(s, w)| *s *= *w);
+
+        self.fft
+            .process_with_scratch(&mut self.frame, &mut self.spectrum, &mut self.scratch)
+            .unwrap();
+
+        let normalize_factor = self.normalize_factor;
+        let flux: f32 = self
+            .magnitudes
+            .iter_mut()
+            .zip(self.spectrum.iter())
+            .map(|(previous, c)| {
+                let magnitude = (MAGNITUDE_COMPRESSION * c.norm() * normalize_factor).ln_1p();
+                let increase = (magnitude - *previous).max(0.);
+                *previous = magnitude;
+                increase
+            })
+            .sum();
+
+        let flux = flux / self.magnitudes.len() as f32;
+        if flux.is_finite() {
+            flux
+        } else {
+            0.
+        }
+    }
+
+    /// Compare the flux of the last frame to the adaptive threshold, returns the strength of the
+    /// onset if any
+    fn detect(&mut self, flux: f32, time: f64) -> Option<f32> {
+        let mean = self.history.iter().sum::<f32>() / HISTORY_LENGTH as f32;
+        let threshold = self.threshold * mean + FLUX_FLOOR;
+
+        self.history[self.history_index] = flux;
+        self.history_index = (self.history_index + 1) % HISTORY_LENGTH;
+
+        let rising = flux > self.previous_flux;
+        self.previous_flux = flux;
+
+        if flux > threshold && rising && time - self.last_onset >= self.min_interval {
+            self.last_onset = time;
+            Some(flux / threshold)
+        } else {
+            None
+        }
+    }
+}
+
+impl AudioProcessor for OnsetDetectorRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // pass through input
+        *output = input.clone();
+
+        // down mix to mono
+        let mut mono = input.clone();
+        mono.mix(1, ChannelInterpretation::Speakers);
+
+        let sample_rate = f64::from(scope.sample_rate);
+        for (index, &sample) in mono.channel_data(0).iter().enumerate() {
+            self.ring_buffer[self.write_index] = sample;
+            self.write_index = (self.write_index + 1) % self.ring_buffer.len();
+
+            self.samples_until_hop -= 1;
+            if self.samples_until_hop > 0 {
+                continue;
+            }
+            self.samples_until_hop = self.hop_size;
+
+            // the onset is somewhere in the last hop of the frame
+            let frame_end = scope.current_time + (index + 1) as f64 / sample_rate;
+            let time = frame_end - self.hop_size as f64 / 2. / sample_rate;
+
+            let flux = self.spectral_flux();
+            if let Some(strength) = self.detect(flux, time) {
+                scope.send_onset_event(time, strength);
+            }
+        }
+
+        // no tail-time
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(&threshold) = msg.downcast_ref::<f32>() {
+            self.threshold = threshold;
+            return;
+        }
+
+        log::warn!("OnsetDetectorRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_onsets() {
+        let sample_rate = 44_100.;
+        let mut context = OfflineAudioContext::new(1, 44_100, sample_rate);
+
+        let detector = OnsetDetectorNode::new(&context, OnsetDetectorOptions::default());
+        detector.connect(&context.destination());
+
+        let onsets = Arc::new(Mutex::new(vec![]));
+        let onsets_clone = Arc::clone(&onsets);
+        detector.set_ononset(move |event| onsets_clone.lock().unwrap().push(event));
+
+        // two notes, with a smooth release in between
+        let gain = context.create_gain();
+        gain.gain()
+            .set_value_at_time(0., 0.)
+            .set_value_at_time(1., 0.25)
+            .set_value_at_time(1., 0.45)
+            .linear_ramp_to_value_at_time(0., 0.55)
+            .set_value_at_time(1., 0.75);
+        gain.connect(&detector);
+
+        let mut osc = context.create_oscillator();
+        osc.connect(&gain);
+        osc.start();
+
+        let _ = context.start_rendering_sync();
+
+        let onsets = onsets.lock().unwrap();
+        assert_eq!(onsets.len(), 2, "{onsets:?}");
+        // accuracy of a frame hop
+        let tolerance = 1.5 * 512. / sample_rate as f64;
+        assert!((onsets[0].time - 0.25).abs() < tolerance, "{onsets:?}");
+        assert!((onsets[1].time - 0.75).abs() < tolerance, "{onsets:?}");
+        assert!(onsets.iter().all(|onset| onset.strength > 1.));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_fft_size() {
+        let context = OfflineAudioContext::new(1, 128, 44_100.);
+        let options = OnsetDetectorOptions {
+            fft_size: 1000,
+            ..OnsetDetectorOptions::default()
+        };
+        let _ = OnsetDetectorNode::new(&context, options);
+    }
+}
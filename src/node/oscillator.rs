@@ -179,6 +179,7 @@ impl AudioScheduledSourceNode for OscillatorNode {
 
         self.start_stop_count += 1;
         self.registration.post_message(Schedule::Start(when));
+        self.registration.context().notify_source_start(when);
     }
 
     fn stop(&mut self) {
@@ -220,7 +221,7 @@ impl OscillatorNode {
 
             // frequency audio parameter
             let freq_param_options = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("frequency"),
                 min_value: -nyquist,
                 max_value: nyquist,
                 default_value: 440.,
@@ -231,7 +232,7 @@ impl OscillatorNode {
 
             // detune audio parameter
             let det_param_options = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("detune"),
                 min_value: -153_600.,
                 max_value: 153_600.,
                 default_value: 0.,
@@ -0,0 +1,362 @@
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::dsp::Oversampler;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, OverSampleType};
+
+/// Signal processing code run by an [`OversampledNode`] at the oversampled rate
+///
+/// The trait is implemented for closures with the same signature as
+/// [`OversampledProcessor::process`].
+pub trait OversampledProcessor: Send {
+    /// Process a block of the oversampled signal of a channel in place
+    ///
+    /// # Arguments
+    ///
+    /// - channel: index of the channel in the render quantum
+    /// - samples: oversampled signal, of `factor * RENDER_QUANTUM_SIZE` frames
+    /// - sample_rate: the oversampled rate, i.e. `factor` times the context sample rate
+    fn process(&mut self, channel: usize, samples: &mut [f32], sample_rate: f32);
+}
+
+impl<F: FnMut(usize, &mut [f32], f32) + Send> OversampledProcessor for F {
+    fn process(&mut self, channel: usize, samples: &mut [f32], sample_rate: f32) {
+        (self)(channel, samples, sample_rate)
+    }
+}
+
+/// Options for constructing an [`OversampledNode`]
+#[derive(Clone, Debug)]
+pub struct OversampledOptions {
+    /// Oversampling rate - default to `X2`
+    pub oversample: OverSampleType,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for OversampledOptions {
+    fn default() -> Self {
+        Self {
+            oversample: OverSampleType::X2,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `OversampledNode` runs user DSP at a multiple of the context sample rate
+///
+/// The input is upsampled with a polyphase FIR filter, handed to an [`OversampledProcessor`]
+/// and downsampled again, so that the harmonics generated by nonlinear processing (saturation,
+/// clipping, ...) above the original Nyquist frequency are filtered out instead of aliasing
+/// back into the audible band. The filters delay the signal, see [`OversampledNode::latency`].
+///
+/// The node is meant for effects that transform their input: once the input is silent and the
+/// filters are flushed the processor is not called anymore and the node outputs silence. Use an
+/// [`Oversampler`] in an [`AudioWorkletProcessor`](crate::worklet::AudioWorkletProcessor) for
+/// other use cases.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{OverSampleType, OversampledNode, OversampledOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let options = OversampledOptions {
+///     oversample: OverSampleType::X4,
+///     ..OversampledOptions::default()
+/// };
+/// let drive = 8.;
+/// let saturation = OversampledNode::new(
+///     &context,
+///     options,
+///     move |_channel: usize, samples: &mut [f32], _sample_rate: f32| {
+///         samples.iter_mut().for_each(|s| *s = (drive * *s).tanh());
+///     },
+/// );
+/// saturation.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&saturation);
+/// osc.start();
+/// ```
+#[derive(Debug)]
+pub struct OversampledNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    oversample: OverSampleType,
+}
+
+impl AudioNode for OversampledNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl OversampledNode {
+    /// Create a new `OversampledNode` running `processor` at the oversampled rate
+    ///
+    /// With [`OverSampleType::None`] the processor runs at the context sample rate.
+    pub fn new<C: BaseAudioContext, P: OversampledProcessor + 'static>(
+        context: &C,
+        options: OversampledOptions,
+        processor: P,
+    ) -> Self {
+        let OversampledOptions {
+            oversample,
+            audio_node_options,
+        } = options;
+
+        let factor = match oversample {
+            OverSampleType::None => None,
+            OverSampleType::X2 => Some(2),
+            OverSampleType::X4 => Some(4),
+            OverSampleType::X8 => Some(8),
+            OverSampleType::X16 => Some(16),
+        };
+
+        context.base().register(move |registration| {
+            let renderer = OversampledRenderer {
+                processor,
+                oversampler: factor.map(Oversampler::new),
+                tail_pending: false,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                oversample,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Returns the oversampling factor of this node
+    #[must_use]
+    pub fn oversample(&self) -> OverSampleType {
+        self.oversample
+    }
+
+    /// The latency in seconds introduced by the oversampling filters
+    ///
+    /// The up- and downsampling filters delay the signal by 31 frames, regardless of the
+    /// oversampling factor. Any latency of the processor itself comes on top of it.
+    #[must_use]
+    pub fn latency(&self) -> f64 {
+        if self.oversample == OverSampleType::None {
+            return 0.;
+        }
+
+        Oversampler::LATENCY as f64 / self.registration.context().sample_rate() as f64
+    }
+}
+
+struct OversampledRenderer<P> {
+    processor: P,
+    /// `None` if no oversampling is applied
+    oversampler: Option<Oversampler>,
+    /// the oversampling filters still contain signal to flush
+    tail_pending: bool,
+}
+
+impl<P: OversampledProcessor> AudioProcessor for OversampledRenderer<P> {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() && !self.tail_pending {
+            output.make_silent();
+            return false;
+        }
+
+        *output = input.clone();
+        let tail_pending = std::mem::take(&mut self.tail_pending);
+        let processor = &mut self.processor;
+
+        let oversampler = match &mut self.oversampler {
+            Some(oversampler) => oversampler,
+            None => {
+                output
+                    .channels_mut()
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(index, channel)| {
+                        processor.process(index, channel, scope.sample_rate);
+                    });
+
+                return false;
+            }
+        };
+
+        // flush the filters with the previous number of channels during tail time
+        if input.is_silent() && tail_pending {
+            output.set_number_of_channels(oversampler.number_of_channels());
+        } else if output.number_of_channels() != oversampler.number_of_channels() {
+            oversampler.set_number_of_channels(output.number_of_channels());
+        }
+
+        let sample_rate = scope.sample_rate * oversampler.factor() as f32;
+        output
+            .channels_mut()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, channel)| {
+                oversampler.process(index, channel, |oversampled| {
+                    processor.process(index, oversampled, sample_rate);
+                });
+            });
+
+        // the filter delay is shorter than a render quantum, a single quantum flushes the tail
+        self.tail_pending = !input.is_silent();
+        self.tail_pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::RENDER_QUANTUM_SIZE;
+
+    use super::*;
+
+    #[test]
+    fn test_latency() {
+        let context = OfflineAudioContext::new(1, 1, 44_100.);
+
+        let node = OversampledNode::new(
+            &context,
+            OversampledOptions::default(),
+            |_: usize, _: &mut [f32], _: f32| {},
+        );
+        assert_eq!(node.oversample(), OverSampleType::X2);
+        assert_float_eq!(node.latency(), 31. / 44_100., abs <= 0.);
+
+        let options = OversampledOptions {
+            oversample: OverSampleType::None,
+            ..OversampledOptions::default()
+        };
+        let node = OversampledNode::new(&context, options, |_: usize, _: &mut [f32], _: f32| {});
+        assert_float_eq!(node.latency(), 0., abs <= 0.);
+    }
+
+    #[test]
+    fn test_processor_rate() {
+        for (oversample, factor) in [
+            (OverSampleType::None, 1),
+            (OverSampleType::X2, 2),
+            (OverSampleType::X4, 4),
+        ] {
+            let mut context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 48_000.);
+
+            let options = OversampledOptions {
+                oversample,
+                ..OversampledOptions::default()
+            };
+            // the processor runs on the render thread, record the calls to check them here
+            let calls = Arc::new(Mutex::new(vec![]));
+            let calls_clone = Arc::clone(&calls);
+            let processor = move |channel: usize, samples: &mut [f32], sample_rate: f32| {
+                calls_clone
+                    .lock()
+                    .unwrap()
+                    .push((channel, samples.len(), sample_rate));
+            };
+            let node = OversampledNode::new(&context, options, processor);
+            node.connect(&context.destination());
+
+            let mut src = context.create_constant_source();
+            src.connect(&node);
+            src.start();
+
+            let _ = context.start_rendering_sync();
+
+            // a single call for the mono input
+            let expected = [(0, factor * RENDER_QUANTUM_SIZE, factor as f32 * 48_000.)];
+            assert_eq!(&calls.lock().unwrap()[..], &expected[..]);
+        }
+    }
+
+    #[test]
+    fn test_oversampled_gain() {
+        let sample_rate = 44_100.;
+        let length = 4 * RENDER_QUANTUM_SIZE;
+        let latency = Oversampler::LATENCY;
+
+        let input: Vec<f32> = (0..length)
+            .map(|i| (2. * std::f32::consts::PI * 1000. * i as f32 / sample_rate).sin())
+            .collect();
+
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let options = OversampledOptions {
+            oversample: OverSampleType::X4,
+            ..OversampledOptions::default()
+        };
+        let processor = |_: usize, samples: &mut [f32], _: f32| {
+            samples.iter_mut().for_each(|s| *s *= 0.5);
+        };
+        let node = OversampledNode::new(&context, options, processor);
+        node.connect(&context.destination());
+
+        // input stops halfway, the output tail must be flushed
+        let mut buffer = context.create_buffer(1, length / 2, sample_rate);
+        buffer.copy_to_channel(&input[..length / 2], 0);
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&node);
+        src.start();
+
+        let result = context.start_rendering_sync();
+        let output = result.get_channel_data(0);
+
+        let expected: Vec<f32> = (0..length)
+            .map(|i| {
+                if i < latency || i >= length / 2 + latency {
+                    0.
+                } else {
+                    input[i - latency] * 0.5
+                }
+            })
+            .collect();
+
+        // skip the filter transients at the start and end of the input
+        let settled = 2 * latency..length / 2;
+        assert_float_eq!(output[settled.clone()], expected[settled], abs_all <= 1e-3);
+        assert_float_eq!(
+            output[length / 2 + 2 * latency..],
+            expected[length / 2 + 2 * latency..],
+            abs_all <= 1e-3
+        );
+    }
+}
@@ -1,13 +1,15 @@
 use std::any::Any;
 use std::collections::HashMap;
+use std::error::Error;
 use std::f32::consts::PI;
-use std::sync::{Mutex, OnceLock};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
 
 use float_eq::float_eq;
 use hrtf::{HrirSphere, HrtfContext, HrtfProcessor, Vec3};
 
 use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
-use crate::param::{AudioParam, AudioParamDescriptor};
+use crate::param::AudioParam;
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
@@ -31,40 +33,127 @@ pub(crate) fn assert_valid_cone_outer_gain(value: f64) {
     );
 }
 
-/// Load the HRTF processor for the given sample_rate
+/// Number of steps in which the HRTF panner interpolates the source position over a render
+/// quantum, so the impulse responses are crossfaded when the azimuth or elevation changes
+const HRTF_INTERPOLATION_STEPS: usize = 8;
+
+/// Speed of sound in air in meters per second, the distances are assumed to be in meters for the
+/// doppler effect
+const SPEED_OF_SOUND: f64 = 343.;
+
+/// Maximum propagation delay of the doppler effect in seconds
+const MAX_DOPPLER_DELAY: f64 = 1.;
+
+/// Cutoff frequency of the air absorption lowpass filter at the reference distance
+const AIR_ABSORPTION_MAX_FREQUENCY: f64 = 20_000.;
+
+/// Distance in meters beyond the reference distance over which air absorption lowers the cutoff
+/// frequency by a factor `e`, with an air absorption of 1
+const AIR_ABSORPTION_DISTANCE: f64 = 100.;
+
+/// Set of head-related impulse responses used by the `HRTF` panning model
 ///
-/// The included data contains the impulse responses at 44100 Hertz, so it needs to be resampled
-/// for other values (which can easily take 100s of milliseconds). Therefore cache the result (per
-/// sample rate) in a global variable and clone it every time a new panner is created.
+/// The default dataset is the bundled IRCAM Listen subject 1003 sphere. Alternative datasets
+/// can be loaded from the HRIR sphere format of the [`hrtf`](https://crates.io/crates/hrtf)
+/// crate, or from a SOFA file with [`SofaDatabase`](crate::hrtf::SofaDatabase).
+///
+/// This is not part of the Web Audio API specification.
+#[derive(Clone, Default)]
+pub struct HrtfDataset {
+    /// contents of the HRIR sphere, `None` for the bundled dataset
+    data: Option<Arc<[u8]>>,
+}
+
+impl std::fmt::Debug for HrtfDataset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.data {
+            None => f.write_str("HrtfDataset(bundled)"),
+            Some(data) => write!(f, "HrtfDataset({} bytes)", data.len()),
+        }
+    }
+}
+
+impl PartialEq for HrtfDataset {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl HrtfDataset {
+    /// Load a dataset from the contents of an HRIR sphere file
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        // validate the data
+        HrirSphere::new(&data[..], 44_100)
+            .map_err(|e| format!("InvalidStateError - invalid HRIR sphere: {e:?}"))?;
+
+        Ok(Self {
+            data: Some(data.into()),
+        })
+    }
+
+    /// Load a dataset from an HRIR sphere file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Identity of the dataset, used to cache the processors
+    fn key(&self) -> usize {
+        self.data
+            .as_ref()
+            .map_or(0, |data| Arc::as_ptr(data).cast::<u8>() as usize)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match &self.data {
+            None => &include_bytes!("../../resources/IRC_1003_C.bin")[..],
+            Some(data) => data,
+        }
+    }
+}
+
+/// Load the HRTF processor of the bundled dataset for the given sample_rate
 pub(crate) fn load_hrtf_processor(sample_rate: u32) -> (HrtfProcessor, usize) {
-    static INSTANCE: OnceLock<Mutex<HashMap<u32, (HrtfProcessor, usize)>>> = OnceLock::new();
+    load_hrtf_dataset_processor(&HrtfDataset::default(), sample_rate)
+}
+
+/// Load the HRTF processor of the given dataset for the given sample_rate
+///
+/// The datasets contain the impulse responses at 44100 Hertz, so they need to be resampled
+/// for other values (which can easily take 100s of milliseconds). Therefore cache the result (per
+/// dataset and sample rate) in a global variable and clone it every time a new panner is created.
+pub(crate) fn load_hrtf_dataset_processor(
+    dataset: &HrtfDataset,
+    sample_rate: u32,
+) -> (HrtfProcessor, usize) {
+    type Cache = HashMap<(usize, u32), (HrtfDataset, HrtfProcessor, usize)>;
+    static INSTANCE: OnceLock<Mutex<Cache>> = OnceLock::new();
     let cache = INSTANCE.get_or_init(|| Mutex::new(HashMap::new()));
 
     // There's an upstream bug for low sample rates, so work around it by forcing sample_rate to be
     // 27k minimum. The HRTF response will be a bit distorted but I assume you won't be using it
     // anyway when running these low sample rates. <https://github.com/mrDIMAS/hrtf/issues/9>
     let sample_rate = sample_rate.max(27_000);
+    let key = (dataset.key(), sample_rate);
 
     // To avoid poisening the cache mutex, don't use the `entry()` API on HashMap
     {
-        if let Some(value) = cache.lock().unwrap().get(&sample_rate) {
-            return value.clone();
+        if let Some((_, processor, len)) = cache.lock().unwrap().get(&key) {
+            return (processor.clone(), *len);
         }
     }
 
-    // The following snippet might panic
-    let resource = include_bytes!("../../resources/IRC_1003_C.bin");
-    let hrir_sphere = HrirSphere::new(&resource[..], sample_rate).unwrap();
+    // The following snippet might panic, but the datasets are validated on construction
+    let hrir_sphere = HrirSphere::new(dataset.bytes(), sample_rate).unwrap();
     let len = hrir_sphere.len();
 
-    let interpolation_steps = 1; // TODO?
-    let samples_per_step = RENDER_QUANTUM_SIZE / interpolation_steps;
-    let processor = HrtfProcessor::new(hrir_sphere, interpolation_steps, samples_per_step);
+    let samples_per_step = RENDER_QUANTUM_SIZE / HRTF_INTERPOLATION_STEPS;
+    let processor = HrtfProcessor::new(hrir_sphere, HRTF_INTERPOLATION_STEPS, samples_per_step);
 
-    let value = (processor, len);
-    cache.lock().unwrap().insert(sample_rate, value.clone());
+    // the cache holds a clone of the dataset so its key is not reused by another allocation
+    let value = (dataset.clone(), processor.clone(), len);
+    cache.lock().unwrap().insert(key, value);
 
-    value
+    (processor, len)
 }
 
 /// Spatialization algorithm used to position the audio in 3D space
@@ -138,6 +227,13 @@ pub struct PannerOptions {
     pub cone_inner_angle: f64,
     pub cone_outer_angle: f64,
     pub cone_outer_gain: f64,
+    /// Impulse responses of the `HRTF` panning model
+    pub hrtf_dataset: HrtfDataset,
+    /// Amount of high frequency attenuation with distance, 0 disables the filter
+    pub air_absorption: f64,
+    /// Scaling of the pitch shift caused by the motion of the source or the listener, 0
+    /// disables the doppler effect
+    pub doppler_factor: f64,
     pub audio_node_options: AudioNodeOptions,
 }
 
@@ -158,6 +254,9 @@ impl Default for PannerOptions {
             cone_inner_angle: 360.,
             cone_outer_angle: 360.,
             cone_outer_gain: 0.,
+            hrtf_dataset: HrtfDataset::default(),
+            air_absorption: 0.,
+            doppler_factor: 0.,
             audio_node_options: AudioNodeOptions {
                 channel_count: 2,
                 channel_count_mode: ChannelCountMode::ClampedMax,
@@ -177,6 +276,10 @@ enum ControlMessage {
     ConeInnerAngle(f64),
     ConeOuterAngle(f64),
     ConeOuterGain(f64),
+    AirAbsorption(f64),
+    // Box this payload - one large variant can penalize the memory layout of this enum
+    Doppler(Box<Option<DopplerState>>),
+    DopplerFactor(f64),
 }
 
 /// Assert that the channel count is valid for the PannerNode
@@ -275,6 +378,89 @@ impl HrtfState {
     }
 }
 
+/// Internal state of the doppler effect, a delay line with the propagation delay of the sound
+struct DopplerState {
+    factor: f64,
+    /// ring buffers of both channels
+    buffers: [Vec<f32>; 2],
+    write_index: usize,
+    max_delay: f64,
+    /// delay in samples at the end of the previous render quantum
+    delay: Option<f64>,
+}
+
+impl DopplerState {
+    fn new(factor: f64, sample_rate: f32) -> Self {
+        let max_delay = (MAX_DOPPLER_DELAY * f64::from(sample_rate)).ceil();
+        let len = max_delay as usize + RENDER_QUANTUM_SIZE + 2;
+
+        Self {
+            factor,
+            buffers: [vec![0.; len], vec![0.; len]],
+            write_index: 0,
+            max_delay,
+            delay: None,
+        }
+    }
+
+    fn set_factor(&mut self, factor: f64) {
+        if self.factor == 0. && factor > 0. {
+            // discard the signal of the previous activation
+            self.buffers.iter_mut().for_each(|buffer| buffer.fill(0.));
+        }
+        if factor == 0. {
+            self.delay = None;
+        }
+        self.factor = factor;
+    }
+
+    fn is_active(&self) -> bool {
+        self.factor > 0.
+    }
+
+    /// Delay the signal by its propagation time, the variation of the delay over the render
+    /// quantum produces the pitch shift
+    fn process(&mut self, quantum: &mut AudioRenderQuantum, distance: f64, sample_rate: f32) {
+        let target = (self.factor * distance / SPEED_OF_SOUND * f64::from(sample_rate))
+            .clamp(0., self.max_delay);
+        let start = self.delay.unwrap_or(target);
+        let len = self.buffers[0].len();
+
+        for (channel, buffer) in self
+            .buffers
+            .iter_mut()
+            .enumerate()
+            .take(quantum.number_of_channels())
+        {
+            let data = quantum.channel_data_mut(channel);
+            for (i, sample) in data.iter_mut().enumerate() {
+                let write_index = (self.write_index + i) % len;
+                buffer[write_index] = *sample;
+
+                let t = (i + 1) as f64 / RENDER_QUANTUM_SIZE as f64;
+                let delay = start + (target - start) * t;
+                let position = (write_index + len) as f64 - delay;
+                let index = position.floor();
+                let frac = (position - index) as f32;
+                let a = buffer[index as usize % len];
+                let b = buffer[(index as usize + 1) % len];
+                *sample = a + frac * (b - a);
+            }
+        }
+
+        self.write_index = (self.write_index + RENDER_QUANTUM_SIZE) % len;
+        self.delay = Some(target);
+    }
+
+    fn tail_time_samples(&self) -> usize {
+        if self.is_active() {
+            self.delay.unwrap_or_default().ceil() as usize + RENDER_QUANTUM_SIZE
+        } else {
+            0
+        }
+    }
+}
+
 /// `PannerNode` positions / spatializes an incoming audio stream in three-dimensional space.
 ///
 /// - MDN documentation: <https://developer.mozilla.org/en-US/docs/Web/API/PannerNode>
@@ -339,6 +525,11 @@ pub struct PannerNode {
     max_distance: f64,
     rolloff_factor: f64,
     panning_model: PanningModelType,
+    hrtf_dataset: HrtfDataset,
+    air_absorption: f64,
+    doppler_factor: f64,
+    /// The renderer holds a delay line for the doppler effect
+    doppler_allocated: bool,
 }
 
 impl AudioNode for PannerNode {
@@ -386,12 +577,14 @@ impl PannerNode {
     ///
     /// * `options.channel_config.count` is greater than 2
     /// * `options.channel_config.mode` is `ChannelCountMode::Max`
+    /// * `options.air_absorption` or `options.doppler_factor` is negative
     ///
     /// Can panic when loading HRIR-sphere
     #[allow(clippy::missing_panics_doc)]
     pub fn new<C: BaseAudioContext>(context: &C, options: PannerOptions) -> Self {
+        let sample_rate = context.sample_rate();
         let mut node = context.base().register(|registration| {
-            use crate::spatial::PARAM_OPTS;
+            use crate::spatial::param_opts;
 
             let PannerOptions {
                 position_x,
@@ -409,6 +602,9 @@ impl PannerNode {
                 cone_outer_gain,
                 audio_node_options: channel_config,
                 panning_model,
+                hrtf_dataset,
+                air_absorption,
+                doppler_factor,
             } = options;
 
             assert!(
@@ -424,26 +620,35 @@ impl PannerNode {
                 "RangeError - rolloffFactor cannot be negative"
             );
             assert_valid_cone_outer_gain(cone_outer_gain);
+            assert!(
+                air_absorption >= 0.,
+                "RangeError - airAbsorption cannot be negative"
+            );
+            assert!(
+                doppler_factor >= 0.,
+                "RangeError - dopplerFactor cannot be negative"
+            );
             assert_valid_channel_count(channel_config.channel_count);
             assert_valid_channel_count_mode(channel_config.channel_count_mode);
 
             // position params
-            let (param_px, render_px) = context.create_audio_param(PARAM_OPTS, &registration);
-            let (param_py, render_py) = context.create_audio_param(PARAM_OPTS, &registration);
-            let (param_pz, render_pz) = context.create_audio_param(PARAM_OPTS, &registration);
+            let (param_px, render_px) =
+                context.create_audio_param(param_opts("positionX", 0.), &registration);
+            let (param_py, render_py) =
+                context.create_audio_param(param_opts("positionY", 0.), &registration);
+            let (param_pz, render_pz) =
+                context.create_audio_param(param_opts("positionZ", 0.), &registration);
             param_px.set_value(position_x);
             param_py.set_value(position_y);
             param_pz.set_value(position_z);
 
             // orientation params
-            let orientation_x_opts = AudioParamDescriptor {
-                default_value: 1.0,
-                ..PARAM_OPTS
-            };
             let (param_ox, render_ox) =
-                context.create_audio_param(orientation_x_opts, &registration);
-            let (param_oy, render_oy) = context.create_audio_param(PARAM_OPTS, &registration);
-            let (param_oz, render_oz) = context.create_audio_param(PARAM_OPTS, &registration);
+                context.create_audio_param(param_opts("orientationX", 1.), &registration);
+            let (param_oy, render_oy) =
+                context.create_audio_param(param_opts("orientationY", 0.), &registration);
+            let (param_oz, render_oz) =
+                context.create_audio_param(param_opts("orientationZ", 0.), &registration);
             param_ox.set_value(orientation_x);
             param_oy.set_value(orientation_y);
             param_oz.set_value(orientation_z);
@@ -463,6 +668,10 @@ impl PannerNode {
                 cone_outer_angle,
                 cone_outer_gain,
                 hrtf_state: None,
+                air_absorption,
+                lowpass_state: [0.; 2],
+                doppler: (doppler_factor > 0.)
+                    .then(|| DopplerState::new(doppler_factor, sample_rate)),
                 tail_time_counter: 0,
             };
 
@@ -483,6 +692,10 @@ impl PannerNode {
                 cone_outer_angle,
                 cone_outer_gain,
                 panning_model,
+                hrtf_dataset,
+                air_absorption,
+                doppler_factor,
+                doppler_allocated: doppler_factor > 0.,
             };
 
             // instruct to BaseContext to add the AudioListener if it has not already
@@ -645,7 +858,7 @@ impl PannerNode {
             PanningModelType::EqualPower => None,
             PanningModelType::HRTF => {
                 let sample_rate = self.context().sample_rate() as u32;
-                let (processor, len) = load_hrtf_processor(sample_rate);
+                let (processor, len) = load_hrtf_dataset_processor(&self.hrtf_dataset, sample_rate);
                 Some(HrtfState::new(processor, len))
             }
         };
@@ -654,6 +867,83 @@ impl PannerNode {
         self.registration
             .post_message(ControlMessage::PanningModel(Box::new(hrtf_option)));
     }
+
+    /// The impulse responses of the `HRTF` panning model
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn hrtf_dataset(&self) -> &HrtfDataset {
+        &self.hrtf_dataset
+    }
+
+    /// Update the impulse responses of the `HRTF` panning model
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn set_hrtf_dataset(&mut self, value: HrtfDataset) {
+        self.hrtf_dataset = value;
+        if self.panning_model == PanningModelType::HRTF {
+            self.set_panning_model(PanningModelType::HRTF);
+        }
+    }
+
+    /// Amount of high frequency attenuation with distance
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn air_absorption(&self) -> f64 {
+        self.air_absorption
+    }
+
+    /// Set the amount of high frequency attenuation with distance, 0 disables the filter
+    ///
+    /// Beyond the `ref_distance` the signal is lowpass filtered, with a cutoff frequency
+    /// decreasing exponentially with the distance. With a value of 1, the cutoff frequency is
+    /// divided by `e` every 100 meters.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided value is negative.
+    pub fn set_air_absorption(&mut self, value: f64) {
+        assert!(value >= 0., "RangeError - airAbsorption cannot be negative");
+        self.air_absorption = value;
+        self.registration
+            .post_message(ControlMessage::AirAbsorption(value));
+    }
+
+    /// Scaling of the pitch shift caused by the motion of the source or the listener
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn doppler_factor(&self) -> f64 {
+        self.doppler_factor
+    }
+
+    /// Set the scaling of the pitch shift caused by the motion of the source or the listener, 0
+    /// disables the doppler effect
+    ///
+    /// The effect is rendered by delaying the signal by its propagation time, multiplied by the
+    /// doppler factor, assuming the distances are in meters. The delay is capped at 1 second.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided value is negative.
+    pub fn set_doppler_factor(&mut self, value: f64) {
+        assert!(value >= 0., "RangeError - dopplerFactor cannot be negative");
+        self.doppler_factor = value;
+
+        if value > 0. && !self.doppler_allocated {
+            // allocate the delay line on the control thread
+            let sample_rate = self.context().sample_rate();
+            let state = DopplerState::new(value, sample_rate);
+            self.doppler_allocated = true;
+            self.registration
+                .post_message(ControlMessage::Doppler(Box::new(Some(state))));
+        } else {
+            self.registration
+                .post_message(ControlMessage::DopplerFactor(value));
+        }
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -679,6 +969,9 @@ struct PannerRenderer {
     cone_outer_angle: f64,
     cone_outer_gain: f64,
     hrtf_state: Option<HrtfState>, // use EqualPower panning model if `None`
+    air_absorption: f64,
+    lowpass_state: [f32; 2],
+    doppler: Option<DopplerState>,
     tail_time_counter: usize,
 }
 
@@ -688,7 +981,7 @@ impl AudioProcessor for PannerRenderer {
         inputs: &[AudioRenderQuantum],
         outputs: &mut [AudioRenderQuantum],
         params: AudioParamValues<'_>,
-        _scope: &AudioWorkletGlobalScope,
+        scope: &AudioWorkletGlobalScope,
     ) -> bool {
         // Single input/output node
         let input = &inputs[0];
@@ -697,17 +990,23 @@ impl AudioProcessor for PannerRenderer {
         // early exit for silence
         if input.is_silent() {
             // HRTF panner has tail time equal to the max length of the impulse response buffers
-            // (12 ms)
-            let tail_time = match &self.hrtf_state {
-                None => false,
-                Some(hrtf_state) => hrtf_state.tail_time_samples() > self.tail_time_counter,
-            };
-            if !tail_time {
+            // (12 ms), the doppler effect has tail time equal to the propagation delay
+            let hrtf_tail_time = self
+                .hrtf_state
+                .as_ref()
+                .map_or(0, HrtfState::tail_time_samples);
+            let doppler_tail_time = self
+                .doppler
+                .as_ref()
+                .map_or(0, DopplerState::tail_time_samples);
+            if hrtf_tail_time.max(doppler_tail_time) <= self.tail_time_counter {
                 output.make_silent();
                 return false;
             }
 
             self.tail_time_counter += RENDER_QUANTUM_SIZE;
+        } else {
+            self.tail_time_counter = 0;
         }
 
         // for borrow reasons, take the hrtf_state out of self
@@ -725,6 +1024,28 @@ impl AudioProcessor for PannerRenderer {
         let [listener_position_x, listener_position_y, listener_position_z, listener_forward_x, listener_forward_y, listener_forward_z, listener_up_x, listener_up_y, listener_up_z] =
             params.listener_params();
 
+        // distance effects are applied to the input, at k-rate
+        let processed;
+        let doppler_active = self.doppler.as_ref().is_some_and(DopplerState::is_active);
+        let input = if self.air_absorption > 0. || doppler_active {
+            let distance = crate::spatial::distance(
+                [
+                    source_position_x[0],
+                    source_position_y[0],
+                    source_position_z[0],
+                ],
+                [
+                    listener_position_x[0],
+                    listener_position_y[0],
+                    listener_position_z[0],
+                ],
+            );
+            processed = self.apply_distance_effects(input, f64::from(distance), scope.sample_rate);
+            &processed
+        } else {
+            input
+        };
+
         // build up the a-rate iterator for spatial variables
         let mut a_rate_params = source_position_x
             .iter()
@@ -899,8 +1220,8 @@ impl AudioProcessor for PannerRenderer {
         // put the hrtf_state back into self (borrow reasons)
         self.hrtf_state = hrtf_state;
 
-        // tail time only for HRTF panning
-        self.hrtf_state.is_some()
+        // tail time only for HRTF panning and the doppler effect
+        self.hrtf_state.is_some() || doppler_active
     }
 
     fn onmessage(&mut self, msg: &mut dyn Any) {
@@ -914,6 +1235,14 @@ impl AudioProcessor for PannerRenderer {
                 ControlMessage::ConeOuterAngle(value) => self.cone_outer_angle = *value,
                 ControlMessage::ConeOuterGain(value) => self.cone_outer_gain = *value,
                 ControlMessage::PanningModel(value) => self.hrtf_state = value.take(),
+                ControlMessage::AirAbsorption(value) => self.air_absorption = *value,
+                // only sent when the renderer has no doppler state yet
+                ControlMessage::Doppler(value) => self.doppler = value.take(),
+                ControlMessage::DopplerFactor(value) => {
+                    if let Some(doppler) = &mut self.doppler {
+                        doppler.set_factor(*value);
+                    }
+                }
             }
 
             return;
@@ -924,6 +1253,46 @@ impl AudioProcessor for PannerRenderer {
 }
 
 impl PannerRenderer {
+    fn apply_distance_effects(
+        &mut self,
+        input: &AudioRenderQuantum,
+        distance: f64,
+        sample_rate: f32,
+    ) -> AudioRenderQuantum {
+        let mut processed = input.clone();
+
+        if let Some(doppler) = self.doppler.as_mut().filter(|d| d.is_active()) {
+            doppler.process(&mut processed, distance, sample_rate);
+        }
+
+        if self.air_absorption > 0. {
+            // one pole lowpass filter with the cutoff decreasing with distance
+            let excess_distance = (distance - self.ref_distance).max(0.);
+            let cutoff = AIR_ABSORPTION_MAX_FREQUENCY
+                * (-self.air_absorption * excess_distance / AIR_ABSORPTION_DISTANCE).exp();
+            let cutoff = cutoff.min(f64::from(sample_rate) / 2.);
+            let alpha = 1. - (-2. * std::f64::consts::PI * cutoff / f64::from(sample_rate)).exp();
+            let alpha = alpha as f32;
+
+            for (channel, state) in self
+                .lowpass_state
+                .iter_mut()
+                .enumerate()
+                .take(processed.number_of_channels())
+            {
+                processed
+                    .channel_data_mut(channel)
+                    .iter_mut()
+                    .for_each(|sample| {
+                        *state += alpha * (*sample - *state);
+                        *sample = *state;
+                    });
+            }
+        }
+
+        processed
+    }
+
     fn cone_gain(
         &self,
         source_position: [f32; 3],
@@ -1267,4 +1636,122 @@ mod tests {
         let right = output.channel_data(1).as_slice();
         assert!(right[128..256].iter().any(|v| *v >= 1E-6));
     }
+
+    #[test]
+    fn test_hrtf_dataset() {
+        assert!(HrtfDataset::from_bytes(vec![0; 16]).is_err());
+
+        let data = include_bytes!("../../resources/IRC_1003_C.bin").to_vec();
+        let dataset = HrtfDataset::from_bytes(data).unwrap();
+        assert_ne!(dataset, HrtfDataset::default());
+
+        let render = |dataset: Option<HrtfDataset>| {
+            let sample_rate = 44100.;
+            let length = RENDER_QUANTUM_SIZE * 4;
+            let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+            let input = AudioBuffer::from(vec![vec![1.; RENDER_QUANTUM_SIZE]], sample_rate);
+            let mut src = AudioBufferSourceNode::new(&context, AudioBufferSourceOptions::default());
+            src.set_buffer(input);
+            src.start();
+
+            let options = PannerOptions {
+                panning_model: PanningModelType::HRTF,
+                ..PannerOptions::default()
+            };
+            let mut panner = PannerNode::new(&context, options);
+            if let Some(dataset) = dataset {
+                panner.set_hrtf_dataset(dataset.clone());
+                assert_eq!(panner.hrtf_dataset(), &dataset);
+            }
+            panner.position_x().set_value(1.);
+
+            src.connect(&panner);
+            panner.connect(&context.destination());
+
+            context.start_rendering_sync()
+        };
+
+        // the same impulse responses yield the same output
+        let expected = render(None);
+        let output = render(Some(dataset));
+        assert_float_eq!(
+            output.get_channel_data(0),
+            expected.get_channel_data(0),
+            abs_all <= 0.
+        );
+        assert_float_eq!(
+            output.get_channel_data(1),
+            expected.get_channel_data(1),
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    fn test_air_absorption() {
+        let render = |air_absorption: f64| {
+            let sample_rate = 48_000.;
+            let mut context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 8, sample_rate);
+
+            // the source is far away, without distance attenuation
+            let options = PannerOptions {
+                position_z: -1000.,
+                rolloff_factor: 0.,
+                air_absorption,
+                ..PannerOptions::default()
+            };
+            let panner = PannerNode::new(&context, options);
+            assert_float_eq!(panner.air_absorption(), air_absorption, abs <= 0.);
+            panner.connect(&context.destination());
+
+            let mut src = context.create_oscillator();
+            src.frequency().set_value(10_000.);
+            src.connect(&panner);
+            src.start();
+
+            let output = context.start_rendering_sync();
+            output
+                .get_channel_data(0)
+                .iter()
+                .map(|v| v * v)
+                .sum::<f32>()
+        };
+
+        let dry = render(0.);
+        let filtered = render(1.);
+        assert!(filtered < 0.1 * dry);
+    }
+
+    #[test]
+    fn test_doppler() {
+        let sample_rate = 48_000.;
+        let length = RENDER_QUANTUM_SIZE * 8;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        // the sound takes 10 ms (480 samples) to reach the listener
+        let options = PannerOptions {
+            position_z: -(SPEED_OF_SOUND as f32) / 100.,
+            rolloff_factor: 0.,
+            doppler_factor: 1.,
+            ..PannerOptions::default()
+        };
+        let mut panner = PannerNode::new(&context, options);
+        panner.connect(&context.destination());
+
+        let input = AudioBuffer::from(vec![vec![1.; RENDER_QUANTUM_SIZE]], sample_rate);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(input);
+        src.connect(&panner);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        let left = output.get_channel_data(0);
+        let gain = (0.5_f32).sqrt();
+        assert_float_eq!(left[..479], [0.; 479][..], abs_all <= 1e-6);
+        assert_float_eq!(left[481..607], [gain; 126][..], abs_all <= 1e-5);
+        assert_float_eq!(left[610..], [0.; 414][..], abs_all <= 1e-6);
+
+        panner.set_doppler_factor(0.);
+        assert_float_eq!(panner.doppler_factor(), 0., abs <= 0.);
+    }
 }
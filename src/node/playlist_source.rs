@@ -0,0 +1,578 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::f32::consts::FRAC_PI_2;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::buffer::AudioBuffer;
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::media_streams::ResampleQuality;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{assert_valid_time_value, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, AudioScheduledSourceNode, ChannelConfig};
+
+/// Number of buffers the queue holds without allocating in the render thread
+const QUEUE_CAPACITY: usize = 64;
+
+/// Options for constructing a [`PlaylistSourceNode`]
+// @note - Does not extend AudioNodeOptions because AudioNodeOptions are
+// useless for source nodes, because they instruct how to upmix the inputs.
+#[derive(Clone, Debug, Default)]
+pub struct PlaylistSourceOptions {
+    /// Buffers initially in the queue, played in order
+    pub buffers: Vec<AudioBuffer>,
+    /// Duration in seconds of the equal-power crossfade between consecutive buffers, zero for
+    /// gapless playback
+    pub crossfade: f64,
+    /// Quality of the conversion of the buffers to the sample rate of the context
+    pub resample_quality: ResampleQuality,
+}
+
+/// Instructions to start or stop processing
+#[derive(Debug, Copy, Clone)]
+enum Schedule {
+    Start(f64),
+    Stop(f64),
+}
+
+/// Buffer appended to the queue of the renderer
+#[derive(Debug)]
+struct Enqueue {
+    buffer: Option<AudioBuffer>,
+    /// Storage for the buffers played by the renderer, to be dropped outside the render thread
+    played: Vec<AudioBuffer>,
+}
+
+/// New crossfade duration, in seconds
+#[derive(Debug, Copy, Clone)]
+struct Crossfade(f64);
+
+/// `PlaylistSourceNode` plays a queue of [`AudioBuffer`]s back to back
+///
+/// Each buffer starts on the sample frame following the last frame of the previous one, so
+/// consecutive tracks of an album, or consecutive chunks of a long recording, play back without
+/// any gap. Alternatively, consecutive buffers overlap with an equal-power crossfade of the given
+/// duration, which is shortened for buffers shorter than twice the crossfade.
+///
+/// MP3 and AAC encoders add silent frames at the start (priming) and at the end (padding) of a
+/// stream. [`BaseAudioContext::decode_audio_data_sync`] removes them when the container reports
+/// them, e.g. in the LAME header of MP3 files, so decoded buffers can be queued as is.
+///
+/// Buffers can be appended at any time with [`enqueue`](Self::enqueue). The node keeps running
+/// while its queue is empty and plays the next enqueued buffer right away, call
+/// [`stop`](AudioScheduledSourceNode::stop) to end it.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{PlaylistSourceNode, PlaylistSourceOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let mut playlist = PlaylistSourceNode::new(&context, PlaylistSourceOptions::default());
+/// playlist.connect(&context.destination());
+/// playlist.start();
+///
+/// for path in ["samples/sample.wav", "samples/major-scale.ogg"] {
+///     let file = std::fs::File::open(path).unwrap();
+///     playlist.enqueue(context.decode_audio_data_sync(file).unwrap());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PlaylistSourceNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    crossfade: f64,
+    resample_quality: ResampleQuality,
+    sample_rate: f32,
+    enqueued: usize,
+    played: Arc<AtomicUsize>,
+    start_stop_count: u8,
+}
+
+impl AudioNode for PlaylistSourceNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl AudioScheduledSourceNode for PlaylistSourceNode {
+    fn start(&mut self) {
+        let when = self.registration.context().current_time();
+        self.start_at(when);
+    }
+
+    fn start_at(&mut self, when: f64) {
+        assert_valid_time_value(when);
+        assert_eq!(
+            self.start_stop_count, 0,
+            "InvalidStateError - Cannot call `start` twice"
+        );
+
+        self.start_stop_count += 1;
+        self.registration.post_message(Schedule::Start(when));
+        self.registration.context().notify_source_start(when);
+    }
+
+    fn stop(&mut self) {
+        let when = self.registration.context().current_time();
+        self.stop_at(when);
+    }
+
+    fn stop_at(&mut self, when: f64) {
+        assert_valid_time_value(when);
+        assert_eq!(
+            self.start_stop_count, 1,
+            "InvalidStateError cannot stop before start"
+        );
+
+        self.start_stop_count += 1;
+        self.registration.post_message(Schedule::Stop(when));
+    }
+}
+
+impl PlaylistSourceNode {
+    /// # Panics
+    ///
+    /// This function panics if the crossfade is negative or not finite
+    pub fn new<C: BaseAudioContext>(context: &C, options: PlaylistSourceOptions) -> Self {
+        let PlaylistSourceOptions {
+            buffers,
+            crossfade,
+            resample_quality,
+        } = options;
+        assert_valid_crossfade(crossfade);
+
+        context.base().register(move |registration| {
+            let sample_rate = context.sample_rate();
+
+            let entries: VecDeque<_> = buffers
+                .into_iter()
+                .filter(|buffer| buffer.length() > 0)
+                .map(|mut buffer| {
+                    buffer.resample(sample_rate, resample_quality);
+                    buffer
+                })
+                .collect();
+            let capacity = entries.len().max(QUEUE_CAPACITY);
+            let enqueued = entries.len();
+            let played = Arc::new(AtomicUsize::new(0));
+
+            let mut queue = VecDeque::with_capacity(capacity);
+            queue.extend(entries);
+
+            let render = PlaylistSourceRenderer {
+                entries: queue,
+                played: Vec::with_capacity(capacity),
+                played_count: Arc::clone(&played),
+                position: 0,
+                overlap: None,
+                crossfade,
+                start_time: f64::MAX,
+                stop_time: f64::MAX,
+                ended_triggered: false,
+            };
+
+            let node = PlaylistSourceNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+                crossfade,
+                resample_quality,
+                sample_rate,
+                enqueued,
+                played,
+                start_stop_count: 0,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Append a buffer to the queue
+    ///
+    /// The buffer is converted to the sample rate of the context first. Empty buffers are
+    /// ignored. The queue holds 64 buffers that have not been played yet, additional buffers are
+    /// dropped with a warning.
+    pub fn enqueue(&mut self, mut buffer: AudioBuffer) {
+        if buffer.length() == 0 {
+            return;
+        }
+
+        buffer.resample(self.sample_rate, self.resample_quality);
+        self.enqueued += 1;
+        self.registration.post_message(Enqueue {
+            buffer: Some(buffer),
+            played: Vec::with_capacity(QUEUE_CAPACITY),
+        });
+    }
+
+    /// Number of buffers played in full
+    pub fn played(&self) -> usize {
+        self.played.load(Ordering::Relaxed)
+    }
+
+    /// Number of buffers in the queue, including the one playing
+    pub fn queued(&self) -> usize {
+        self.enqueued.saturating_sub(self.played())
+    }
+
+    /// Duration in seconds of the crossfade between consecutive buffers
+    pub fn crossfade(&self) -> f64 {
+        self.crossfade
+    }
+
+    /// Update the duration in seconds of the crossfade between consecutive buffers
+    ///
+    /// A crossfade in progress keeps its duration.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the crossfade is negative or not finite
+    pub fn set_crossfade(&mut self, crossfade: f64) {
+        assert_valid_crossfade(crossfade);
+        self.crossfade = crossfade;
+        self.registration.post_message(Crossfade(crossfade));
+    }
+}
+
+#[track_caller]
+#[inline(always)]
+fn assert_valid_crossfade(crossfade: f64) {
+    assert!(
+        crossfade.is_finite() && crossfade >= 0.,
+        "RangeError - crossfade must be a positive finite number, got {crossfade:?}"
+    );
+}
+
+/// Contribution of a buffer of the queue to a sample frame of the output
+#[derive(Clone, Copy, Default)]
+struct Contribution {
+    /// Index of the buffer in the queue
+    entry: usize,
+    /// Index of the sample frame in the buffer
+    position: usize,
+    gain: f32,
+}
+
+struct PlaylistSourceRenderer {
+    entries: VecDeque<AudioBuffer>,
+    /// Buffers played in full, handed back to the control thread with the next message
+    played: Vec<AudioBuffer>,
+    played_count: Arc<AtomicUsize>,
+    /// Position of the playhead in the first buffer of the queue
+    position: usize,
+    /// First frame and length of the crossfade at the end of the first buffer of the queue, known
+    /// once the next buffer is queued
+    overlap: Option<(usize, usize)>,
+    crossfade: f64,
+    start_time: f64,
+    stop_time: f64,
+    ended_triggered: bool,
+}
+
+impl PlaylistSourceRenderer {
+    /// Contributions of the current buffer, and of the next one while crossfading, to the next
+    /// sample frame, advancing the playhead
+    fn advance(
+        &mut self,
+        entry: &mut usize,
+        sample_rate: f32,
+    ) -> Option<(Contribution, Option<Contribution>)> {
+        let length = self.entries.get(*entry)?.length();
+
+        if self.overlap.is_none() {
+            if let Some(next) = self.entries.get(*entry + 1) {
+                let frames = (self.crossfade * sample_rate as f64).round() as usize;
+                let frames = frames.min(length / 2).min(next.length() / 2);
+                // the next buffer may have been queued within the crossfade
+                let start = (length - frames).max(self.position);
+                self.overlap = Some((start, length - start));
+            }
+        }
+
+        let current = Contribution {
+            entry: *entry,
+            position: self.position,
+            gain: 1.,
+        };
+        let contributions = match self.overlap {
+            Some((start, frames)) if self.position >= start => {
+                let t = ((self.position - start) as f32 + 0.5) / frames as f32;
+                let (fade_in, fade_out) = (t * FRAC_PI_2).sin_cos();
+                let next = Contribution {
+                    entry: *entry + 1,
+                    position: self.position - start,
+                    gain: fade_in,
+                };
+                (
+                    Contribution {
+                        gain: fade_out,
+                        ..current
+                    },
+                    Some(next),
+                )
+            }
+            _ => (current, None),
+        };
+
+        self.position += 1;
+        if self.position == length {
+            // the next buffer continues after the frames played during the crossfade
+            self.position = self.overlap.map_or(0, |(_, frames)| frames);
+            self.overlap = None;
+            *entry += 1;
+        }
+
+        Some(contributions)
+    }
+}
+
+impl AudioProcessor for PlaylistSourceRenderer {
+    fn process(
+        &mut self,
+        _inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single output node
+        let output = &mut outputs[0];
+
+        let dt = 1. / scope.sample_rate as f64;
+        let next_block_time = scope.current_time + dt * RENDER_QUANTUM_SIZE as f64;
+
+        if self.start_time >= next_block_time {
+            output.make_silent();
+            // AudioScheduledSourceNodes that have not been scheduled to start can safely
+            // return tail_time false in order to be collected if their control handle drops.
+            return self.start_time != f64::MAX;
+        }
+
+        // compute the contributions of the buffers to each frame of the block first, so the
+        // channels are rendered independently afterwards
+        let mut plan = [(Contribution::default(), None); RENDER_QUANTUM_SIZE];
+        let mut sounding = [false; RENDER_QUANTUM_SIZE];
+        let mut entry = 0;
+        let mut current_time = scope.current_time;
+
+        plan.iter_mut()
+            .zip(sounding.iter_mut())
+            .for_each(|(frame, sounding)| {
+                if current_time >= self.start_time && current_time < self.stop_time {
+                    if let Some(contributions) = self.advance(&mut entry, scope.sample_rate) {
+                        *frame = contributions;
+                        *sounding = true;
+                    }
+                }
+                current_time += dt;
+            });
+
+        if sounding.iter().any(|&s| s) {
+            let number_of_channels = self
+                .entries
+                .iter()
+                .take(entry + 2)
+                .map(AudioBuffer::number_of_channels)
+                .max()
+                .unwrap_or(1);
+            output.set_number_of_channels(number_of_channels);
+
+            let entries = &self.entries;
+            let sample = |contribution: &Contribution, channel: usize| {
+                let buffer = &entries[contribution.entry];
+                // mono buffers are played on all channels, missing channels are silent
+                let channel = match buffer.number_of_channels() {
+                    1 => 0,
+                    n if channel < n => channel,
+                    _ => return 0.,
+                };
+                buffer.channel_data(channel).as_slice()[contribution.position] * contribution.gain
+            };
+
+            output
+                .channels_mut()
+                .iter_mut()
+                .enumerate()
+                .for_each(|(channel, output_channel)| {
+                    output_channel
+                        .iter_mut()
+                        .zip(plan.iter().zip(sounding.iter()))
+                        .for_each(|(o, ((current, next), &sounding))| {
+                            *o = if sounding {
+                                sample(current, channel) + next.map_or(0., |n| sample(&n, channel))
+                            } else {
+                                0.
+                            };
+                        });
+                });
+        } else {
+            output.make_silent();
+        }
+
+        if entry > 0 {
+            // keep the played buffers until the next message, to avoid deallocating here
+            self.played.extend(self.entries.drain(..entry));
+            self.played_count.fetch_add(entry, Ordering::Relaxed);
+        }
+
+        // tail_time false when output has ended this quantum
+        let still_running = self.stop_time >= next_block_time;
+
+        if !still_running {
+            // @note: we need this check because this is called a until the program
+            // ends, such as if the node was never removed from the graph
+            if !self.ended_triggered {
+                scope.send_ended_event();
+                self.ended_triggered = true;
+            }
+        }
+
+        still_running
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(schedule) = msg.downcast_ref::<Schedule>() {
+            match *schedule {
+                Schedule::Start(v) => self.start_time = v,
+                Schedule::Stop(v) => self.stop_time = v,
+            }
+            return;
+        }
+
+        if let Some(enqueue) = msg.downcast_mut::<Enqueue>() {
+            if let Some(buffer) = enqueue.buffer.take() {
+                if self.entries.len() < self.entries.capacity() {
+                    self.entries.push_back(buffer);
+                } else {
+                    log::warn!("PlaylistSourceRenderer: queue is full, dropping buffer");
+                    // the buffer is dropped along with the message
+                    enqueue.buffer = Some(buffer);
+                }
+            }
+            // Avoid deallocation in the render thread by swapping the played buffers into
+            // the message
+            std::mem::swap(&mut self.played, &mut enqueue.played);
+            return;
+        }
+
+        if let Some(&Crossfade(crossfade)) = msg.downcast_ref::<Crossfade>() {
+            self.crossfade = crossfade;
+            return;
+        }
+
+        log::warn!("PlaylistSourceRenderer: Dropping incoming message {msg:?}");
+    }
+
+    fn before_drop(&mut self, scope: &AudioWorkletGlobalScope) {
+        if !self.ended_triggered && scope.current_time >= self.start_time {
+            scope.send_ended_event();
+            self.ended_triggered = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+
+    use super::*;
+
+    fn ramp(start: f32, length: usize) -> AudioBuffer {
+        let samples = (0..length).map(|i| start + i as f32).collect();
+        AudioBuffer::from(vec![samples], 48000.)
+    }
+
+    #[test]
+    fn test_gapless() {
+        let mut context = OfflineAudioContext::new(1, 128 * 4, 48000.);
+
+        // buffers that do not line up with the render quanta
+        let options = PlaylistSourceOptions {
+            buffers: vec![ramp(0., 100), ramp(100., 3)],
+            ..PlaylistSourceOptions::default()
+        };
+        let mut playlist = PlaylistSourceNode::new(&context, options);
+        playlist.enqueue(ramp(103., 200));
+        playlist.connect(&context.destination());
+        playlist.start_at(128. / 48000.);
+
+        let output = context.start_rendering_sync();
+        let channel = output.get_channel_data(0);
+
+        assert_float_eq!(channel[..128], [0.; 128][..], abs_all <= 0.);
+        let expected: Vec<f32> = (0..303).map(|i| i as f32).collect();
+        assert_float_eq!(channel[128..431], expected[..], abs_all <= 0.);
+        assert_float_eq!(channel[431..], [0.; 81][..], abs_all <= 0.);
+
+        assert_eq!(playlist.played(), 3);
+        assert_eq!(playlist.queued(), 0);
+    }
+
+    #[test]
+    fn test_crossfade() {
+        let mut context = OfflineAudioContext::new(2, 128 * 4, 48000.);
+
+        let options = PlaylistSourceOptions {
+            buffers: vec![
+                AudioBuffer::from(vec![vec![1.; 200]], 48000.),
+                AudioBuffer::from(vec![vec![1.; 200], vec![1.; 200]], 48000.),
+            ],
+            crossfade: 64. / 48000.,
+            ..PlaylistSourceOptions::default()
+        };
+        let mut playlist = PlaylistSourceNode::new(&context, options);
+        playlist.connect(&context.destination());
+        playlist.start();
+
+        let output = context.start_rendering_sync();
+        let left = output.get_channel_data(0);
+
+        // the buffers overlap during 64 frames
+        assert_float_eq!(left[..136], [1.; 136][..], abs_all <= 0.);
+        assert_float_eq!(left[336..], [0.; 176][..], abs_all <= 0.);
+
+        // equal-power crossfade of correlated signals
+        left[136..200].iter().enumerate().for_each(|(i, &s)| {
+            let t = (i as f32 + 0.5) / 64. * FRAC_PI_2;
+            assert_float_eq!(s, t.sin() + t.cos(), abs <= 1e-6);
+        });
+        assert_float_eq!(left[200..336], [1.; 136][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_resample_on_enqueue() {
+        let mut context = OfflineAudioContext::new(1, 128, 48000.);
+
+        let mut playlist = PlaylistSourceNode::new(&context, PlaylistSourceOptions::default());
+        playlist.enqueue(AudioBuffer::from(vec![vec![1.; 12]], 24000.));
+        playlist.connect(&context.destination());
+        playlist.start();
+
+        let output = context.start_rendering_sync();
+        let channel = output.get_channel_data(0);
+
+        assert_float_eq!(channel[24..], [0.; 104][..], abs_all <= 0.);
+        assert_eq!(playlist.played(), 1);
+    }
+}
@@ -1,8 +1,34 @@
 use super::AudioNode;
-use crate::events::{Event, EventHandler, EventType};
+use crate::events::{Event, EventHandler, EventKind, EventPayload, EventType, ListenerHandle};
+#[cfg(feature = "async")]
+use crate::EventFuture;
 
 /// Interface of source nodes, controlling start and stop times.
 /// The node will emit silence before it is started, and after it has ended.
+///
+/// The trait is object safe, so that sources of different types can be held in a single
+/// collection, e.g. by a sequencer. Use [`set_onended_boxed`](Self::set_onended_boxed) to
+/// register the ended callback of a `dyn AudioScheduledSourceNode`.
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let mut sources: Vec<Box<dyn AudioScheduledSourceNode>> = vec![
+///     Box::new(context.create_oscillator()),
+///     Box::new(context.create_constant_source()),
+/// ];
+///
+/// let now = context.current_time();
+/// for source in sources.iter_mut() {
+///     source.connect(&context.destination());
+///     source.start_at(now + 1.);
+///     source.stop_at(now + 2.);
+///     source.set_onended_boxed(Box::new(|_| println!("ended")));
+/// }
+/// ```
 pub trait AudioScheduledSourceNode: AudioNode {
     /// Play immediately
     ///
@@ -41,7 +67,20 @@ pub trait AudioScheduledSourceNode: AudioNode {
     ///
     /// Only a single event handler is active at any time. Calling this method multiple times will
     /// override the previous event handler.
-    fn set_onended<F: FnOnce(Event) + Send + 'static>(&self, callback: F) {
+    fn set_onended<F: FnOnce(Event) + Send + 'static>(&self, callback: F)
+    where
+        Self: Sized,
+    {
+        self.set_onended_boxed(Box::new(callback));
+    }
+
+    /// Register callback to run when the source node has stopped playing, see
+    /// [`set_onended`](Self::set_onended)
+    ///
+    /// Unlike `set_onended`, this method can be called on a `dyn AudioScheduledSourceNode`.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    fn set_onended_boxed(&self, callback: Box<dyn FnOnce(Event) + Send + 'static>) {
         let callback = move |_| callback(Event { type_: "ended" });
 
         self.context().set_event_handler(
@@ -55,15 +94,100 @@ pub trait AudioScheduledSourceNode: AudioNode {
         self.context()
             .clear_event_handler(EventType::Ended(self.registration().id()));
     }
+
+    /// Add a listener to run when the source node has stopped playing
+    ///
+    /// Unlike [`set_onended`](Self::set_onended), the listeners do not replace each other. They
+    /// run in the order they were added, before the callback of `set_onended`. Use the returned
+    /// [`ListenerHandle`] to remove the listener.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    fn add_ended_listener(
+        &self,
+        callback: Box<dyn FnOnce(Event) + Send + 'static>,
+    ) -> ListenerHandle {
+        let mut callback = Some(callback);
+        let listener = move |_: &EventPayload| {
+            if let Some(f) = callback.take() {
+                f(Event { type_: "ended" });
+            }
+        };
+
+        self.context()
+            .add_listener(EventType::Ended(self.registration().id()), listener)
+    }
+
+    /// Add a listener for the given event of the source node
+    ///
+    /// Generic form of [`add_ended_listener`](Self::add_ended_listener), the same listener can
+    /// be added to the events of nodes and contexts, see
+    /// [`BaseAudioContext::add_event_listener`](crate::context::BaseAudioContext::add_event_listener).
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the event is not dispatched by source nodes, i.e. is not
+    /// [`EventKind::Ended`]
+    fn add_event_listener(
+        &self,
+        kind: EventKind,
+        mut callback: Box<dyn FnMut(Event) + Send + 'static>,
+    ) -> ListenerHandle {
+        assert!(
+            kind == EventKind::Ended,
+            "NotSupportedError - source nodes do not dispatch {:?} events",
+            kind
+        );
+
+        let listener = move |_: &EventPayload| {
+            callback(Event {
+                type_: kind.type_(),
+            })
+        };
+        self.context()
+            .add_listener(EventType::Ended(self.registration().id()), listener)
+    }
+
+    /// Future resolving when the source node has stopped playing
+    ///
+    /// The future only resolves for an `ended` event dispatched after its creation: create it
+    /// before the node is scheduled to stop.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext};
+    /// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+    ///
+    /// # async fn play() {
+    /// let context = AudioContext::default();
+    ///
+    /// let mut src = context.create_oscillator();
+    /// src.connect(&context.destination());
+    /// let ended = src.ended();
+    /// src.start();
+    /// src.stop_at(context.current_time() + 1.);
+    ///
+    /// ended.await;
+    /// # }
+    /// ```
+    #[cfg(feature = "async")]
+    fn ended(&self) -> EventFuture<Event> {
+        EventFuture::new(|sender| {
+            self.add_ended_listener(Box::new(move |event| sender.send(event)))
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::context::{AudioContextRegistration, BaseAudioContext, OfflineAudioContext};
+    use crate::events::{Event, EventKind};
     use crate::node::{AudioNode, AudioScheduledSourceNode, ChannelConfig};
 
-    use std::sync::atomic::{AtomicBool, Ordering};
-    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
 
     enum ConcreteAudioScheduledSourceNode {
         Buffer(crate::node::AudioBufferSourceNode),
@@ -140,6 +264,109 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ended_listeners() {
+        let mut context = OfflineAudioContext::new(1, 44_100, 44_100.);
+
+        let mut src = context.create_constant_source();
+        src.start_at(0.);
+        src.stop_at(0.5);
+
+        let ended = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..3)
+            .map(|i| {
+                let ended = Arc::clone(&ended);
+                src.add_ended_listener(Box::new(move |_event| {
+                    ended.fetch_add(1 << i, Ordering::Relaxed);
+                }))
+            })
+            .collect();
+
+        let ended_clone = Arc::clone(&ended);
+        src.set_onended(move |_event| {
+            ended_clone.fetch_add(8, Ordering::Relaxed);
+        });
+
+        // listeners of other nodes are left untouched
+        let mut other = context.create_oscillator();
+        other.start_at(0.);
+        let ended_clone = Arc::clone(&ended);
+        let _ = other.add_ended_listener(Box::new(move |_event| {
+            ended_clone.fetch_add(16, Ordering::Relaxed);
+        }));
+
+        let mut handles = handles.into_iter();
+        let _ = handles.next();
+        handles.next().unwrap().remove();
+
+        let _ = context.start_rendering_sync();
+        assert_eq!(ended.load(Ordering::Relaxed), 1 + 4 + 8 + 16);
+
+        // the listeners are released once the node has ended
+        handles.next().unwrap().remove();
+    }
+
+    #[test]
+    fn test_event_listeners_across_nodes_and_contexts() {
+        let mut context = OfflineAudioContext::new(1, 44_100, 44_100.);
+
+        let mut src = context.create_constant_source();
+        src.start_at(0.);
+        src.stop_at(0.5);
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let listener = || {
+            let events = Arc::clone(&events);
+            move |event: Event| events.lock().unwrap().push(event.type_)
+        };
+        let _ = src.add_event_listener(EventKind::Ended, Box::new(listener()));
+        let _ = context.add_event_listener(EventKind::StateChange, listener());
+        context
+            .add_event_listener(EventKind::StateChange, listener())
+            .remove();
+
+        let _ = context.start_rendering_sync();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.iter().filter(|e| **e == "ended").count(), 1);
+        assert!(events.contains(&"statechange"));
+        assert!(events.iter().all(|e| *e == "ended" || *e == "statechange"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unsupported_event_listener() {
+        let context = OfflineAudioContext::new(1, 128, 44_100.);
+        let src = context.create_constant_source();
+        let _ = src.add_event_listener(EventKind::StateChange, Box::new(|_| {}));
+    }
+
+    #[test]
+    fn test_heterogeneous_sources() {
+        let mut context = OfflineAudioContext::new(1, 44_100, 44_100.);
+
+        let mut sources: Vec<Box<dyn AudioScheduledSourceNode>> = vec![
+            Box::new(context.create_constant_source()),
+            Box::new(context.create_buffer_source()),
+            Box::new(context.create_oscillator()),
+        ];
+
+        let ended = Arc::new(AtomicUsize::new(0));
+        for source in sources.iter_mut() {
+            source.connect(&context.destination());
+            source.start_at(0.);
+            source.stop_at(0.5);
+
+            let ended = Arc::clone(&ended);
+            source.set_onended_boxed(Box::new(move |_event| {
+                ended.fetch_add(1, Ordering::Relaxed);
+            }));
+        }
+
+        let _ = context.start_rendering_sync();
+        assert_eq!(ended.load(Ordering::Relaxed), 3);
+    }
+
     fn run_ended_event(f: impl FnOnce(&OfflineAudioContext) -> ConcreteAudioScheduledSourceNode) {
         let mut context = OfflineAudioContext::new(2, 44_100, 44_100.);
         let mut src = f(&context);
@@ -180,12 +180,6 @@ struct ScriptProcessorRenderer {
     number_of_output_channels: usize,
 }
 
-// SAFETY:
-// AudioRenderQuantums are not Send but we promise the `buffer` VecDeque is empty before we ship it
-// to the render thread.
-#[allow(clippy::non_send_fields_in_send_ty)]
-unsafe impl Send for ScriptProcessorRenderer {}
-
 impl AudioProcessor for ScriptProcessorRenderer {
     fn process(
         &mut self,
@@ -0,0 +1,604 @@
+use crate::context::BaseAudioContext;
+use crate::param::AudioParam;
+
+use super::{
+    AudioNode, BiquadFilterNode, BiquadFilterOptions, BiquadFilterType, GainNode, GainOptions,
+    PannerNode, PannerOptions,
+};
+
+/// Callback computing the occlusion of an emitter from its position and the listener position
+type OcclusionCallback = Box<dyn FnMut([f32; 3], [f32; 3]) -> OcclusionAmounts + Send + 'static>;
+
+/// Curve mapping the distance between an emitter and the listener to the gain of a send
+type SendCurve = Box<dyn Fn(f32) -> f32 + Send + 'static>;
+
+/// Q of the lowpass filters in dB, i.e. a Butterworth response without resonance
+const LOWPASS_Q: f32 = -3.010_3;
+
+/// Assert that the given occlusion or obstruction amount is in the [0, 1] range
+///
+/// # Panics
+///
+/// This function panics if the given amount is not finite or outside the [0, 1] range
+#[track_caller]
+#[inline(always)]
+fn assert_valid_amount(value: f32) {
+    assert!(
+        (0. ..=1.).contains(&value),
+        "RangeError - Invalid amount: {:?} is outside range [0, 1]",
+        value
+    );
+}
+
+/// Options for constructing a [`SpatialSource`]
+#[derive(Clone, Debug)]
+pub struct SpatialSourceOptions {
+    /// Options of the underlying [`PannerNode`]
+    pub panner: PannerOptions,
+    /// Attenuation in dB of a fully occluded source
+    pub occlusion_attenuation: f32,
+    /// Lowpass cutoff frequency in Hz of a fully occluded source
+    pub occlusion_cutoff: f32,
+    /// Attenuation in dB of a fully obstructed source
+    pub obstruction_attenuation: f32,
+    /// Lowpass cutoff frequency in Hz of a fully obstructed source
+    pub obstruction_cutoff: f32,
+    /// Time constant in seconds of the transitions when the occlusion or the obstruction
+    /// changes, 0 applies the changes instantly
+    pub smoothing: f64,
+}
+
+impl Default for SpatialSourceOptions {
+    fn default() -> Self {
+        Self {
+            panner: PannerOptions::default(),
+            occlusion_attenuation: 24.,
+            occlusion_cutoff: 500.,
+            obstruction_attenuation: 12.,
+            obstruction_cutoff: 1000.,
+            smoothing: 0.05,
+        }
+    }
+}
+
+/// Occlusion and obstruction amounts of an emitter, returned by the callback registered with
+/// [`SpatialSource::set_occlusion_callback`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OcclusionAmounts {
+    /// Occlusion amount, from 0 (no occlusion) to 1 (fully occluded)
+    pub occlusion: f32,
+    /// Obstruction amount, from 0 (no obstruction) to 1 (fully obstructed)
+    pub obstruction: f32,
+}
+
+/// Set the param to the given value, with an exponential transition if `smoothing` is positive
+fn set_smoothed(param: &AudioParam, value: f32, smoothing: f64) {
+    let now = param.context().current_time();
+    param.cancel_scheduled_values(now);
+    if smoothing > 0. {
+        param.set_target_at_time(value, now, smoothing);
+    } else {
+        param.set_value_at_time(value, now);
+    }
+}
+
+/// Current position of an emitter or of the listener
+fn position(x: &AudioParam, y: &AudioParam, z: &AudioParam) -> [f32; 3] {
+    let now = x.context().current_time();
+    [x, y, z].map(|param| param.value_at_time(now))
+}
+
+/// Send whose gain is driven by the distance between the emitter and the listener
+struct DistanceSend {
+    gain: GainNode,
+    curve: SendCurve,
+    value: Option<f32>,
+}
+
+impl std::fmt::Debug for DistanceSend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DistanceSend")
+            .field("gain", &self.gain)
+            .field("value", &self.value)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A lowpass filter followed by a gain, driven by an amount in the [0, 1] range
+#[derive(Debug)]
+struct Attenuation {
+    filter: BiquadFilterNode,
+    gain: GainNode,
+    attenuation: f32,
+    cutoff: f32,
+    amount: f32,
+}
+
+impl Attenuation {
+    fn new<C: BaseAudioContext>(context: &C, attenuation: f32, cutoff: f32) -> Self {
+        let options = BiquadFilterOptions {
+            q: LOWPASS_Q,
+            frequency: context.sample_rate() / 2.,
+            type_: BiquadFilterType::Lowpass,
+            ..BiquadFilterOptions::default()
+        };
+        let filter = BiquadFilterNode::new(context, options);
+        let gain = GainNode::new(context, GainOptions::default());
+        filter.connect(&gain);
+
+        Self {
+            filter,
+            gain,
+            attenuation,
+            cutoff,
+            amount: 0.,
+        }
+    }
+
+    fn set_amount(&mut self, amount: f32, smoothing: f64) {
+        self.amount = amount;
+
+        // the cutoff moves exponentially from the nyquist frequency, where the lowpass is
+        // transparent, to the cutoff of the fully attenuated source
+        let nyquist = self.filter.context().sample_rate() / 2.;
+        let cutoff = self.cutoff.clamp(10., nyquist);
+        let frequency = nyquist * (cutoff / nyquist).powf(amount);
+        let gain = 10_f32.powf(-self.attenuation * amount / 20.);
+
+        set_smoothed(self.filter.frequency(), frequency, smoothing);
+        set_smoothed(self.gain.gain(), gain, smoothing);
+    }
+}
+
+/// `SpatialSource` bundles a [`PannerNode`] with the filters simulating the occlusion and the
+/// obstruction of a sound emitter
+///
+/// Game engines typically compute, for each emitter, how much of the sound is blocked by the
+/// geometry between the emitter and the listener:
+/// - the occlusion is the amount of sound going through a wall, it muffles both the direct
+///   sound and the sound sent to the reverberation of the room,
+/// - the obstruction is the amount of direct sound blocked by an obstacle in the same room, it
+///   muffles the direct sound only.
+///
+/// Both amounts are mapped to a gain and a lowpass cutoff frequency, with a smooth transition
+/// when they change. The signal to spatialize is connected to [`SpatialSource::input`], the
+/// panned signal is connected with [`SpatialSource::connect`], and [`SpatialSource::send`]
+/// provides the occluded but unobstructed signal for a reverberation bus.
+///
+/// This type is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{SpatialSource, SpatialSourceOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let mut emitter = SpatialSource::new(&context, SpatialSourceOptions::default());
+/// emitter.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(emitter.input());
+/// osc.start();
+///
+/// // the emitter moves behind a pillar, then in the next room
+/// emitter.panner().set_position(3., 0., -2.);
+/// emitter.set_obstruction(0.8);
+/// emitter.set_occlusion(0.5);
+/// ```
+///
+/// # Occlusion callback
+///
+/// Instead of setting the amounts by hand, a game can register a callback with
+/// [`SpatialSource::set_occlusion_callback`] and call [`SpatialSource::update`] on each tick:
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::AudioNode;
+/// use web_audio_api::node::{OcclusionAmounts, SpatialSource, SpatialSourceOptions};
+///
+/// # fn raycast(_: [f32; 3], _: [f32; 3]) -> usize { 0 }
+/// let context = AudioContext::default();
+///
+/// let mut emitter = SpatialSource::new(&context, SpatialSourceOptions::default());
+/// emitter.connect(&context.destination());
+///
+/// // the number of walls between the emitter and the listener, from the physics engine
+/// emitter.set_occlusion_callback(|emitter, listener| OcclusionAmounts {
+///     occlusion: (raycast(emitter, listener) as f32 / 2.).min(1.),
+///     obstruction: 0.,
+/// });
+///
+/// loop {
+///     // game logic
+///     emitter.update();
+/// }
+/// ```
+///
+/// # Distance-based sends
+///
+/// [`SpatialSource::add_send`] creates additional sends of the occluded signal, e.g. one per
+/// reverberation bus, whose gains follow a curve of the distance to the listener on each
+/// [`SpatialSource::update`]:
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::AudioNode;
+/// use web_audio_api::node::{SpatialSource, SpatialSourceOptions};
+///
+/// let context = AudioContext::default();
+/// let small_room = context.create_convolver();
+/// let large_hall = context.create_convolver();
+///
+/// let mut emitter = SpatialSource::new(&context, SpatialSourceOptions::default());
+/// // close emitters mostly excite the early reflections of the room
+/// emitter
+///     .add_send(|distance| (1. - distance / 20.).max(0.))
+///     .connect(&small_room);
+/// // while distant emitters sound further away in the hall
+/// emitter
+///     .add_send(|distance| (distance / 50.).min(1.))
+///     .connect(&large_hall);
+///
+/// emitter.panner().set_position(10., 0., 0.);
+/// emitter.update();
+/// ```
+pub struct SpatialSource {
+    occlusion: Attenuation,
+    obstruction: Attenuation,
+    send: GainNode,
+    panner: PannerNode,
+    smoothing: f64,
+    occlusion_callback: Option<OcclusionCallback>,
+    sends: Vec<DistanceSend>,
+}
+
+impl std::fmt::Debug for SpatialSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpatialSource")
+            .field("occlusion", &self.occlusion)
+            .field("obstruction", &self.obstruction)
+            .field("send", &self.send)
+            .field("panner", &self.panner)
+            .field("smoothing", &self.smoothing)
+            .field("occlusion_callback", &self.occlusion_callback.is_some())
+            .field("sends", &self.sends)
+            .finish()
+    }
+}
+
+impl SpatialSource {
+    /// Create a new `SpatialSource`, neither occluded nor obstructed
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the smoothing time constant is negative or not finite, or if the panner
+    /// options are invalid
+    pub fn new<C: BaseAudioContext>(context: &C, options: SpatialSourceOptions) -> Self {
+        let SpatialSourceOptions {
+            panner,
+            occlusion_attenuation,
+            occlusion_cutoff,
+            obstruction_attenuation,
+            obstruction_cutoff,
+            smoothing,
+        } = options;
+        crate::assert_valid_time_value(smoothing);
+
+        let occlusion = Attenuation::new(context, occlusion_attenuation, occlusion_cutoff);
+        let obstruction = Attenuation::new(context, obstruction_attenuation, obstruction_cutoff);
+        let send = GainNode::new(context, GainOptions::default());
+        let panner = PannerNode::new(context, panner);
+
+        occlusion.gain.connect(&obstruction.filter);
+        occlusion.gain.connect(&send);
+        obstruction.gain.connect(&panner);
+
+        Self {
+            occlusion,
+            obstruction,
+            send,
+            panner,
+            smoothing,
+            occlusion_callback: None,
+            sends: Vec::new(),
+        }
+    }
+
+    /// The node the signal to spatialize must be connected to
+    pub fn input(&self) -> &dyn AudioNode {
+        &self.occlusion.filter
+    }
+
+    /// Connect the panned output to the given destination
+    pub fn connect<'a>(&self, dest: &'a dyn AudioNode) -> &'a dyn AudioNode {
+        self.panner.connect(dest)
+    }
+
+    /// Disconnect the panned output from all its destinations
+    pub fn disconnect(&self) {
+        self.panner.disconnect();
+    }
+
+    /// The underlying [`PannerNode`], to control the position and orientation of the emitter
+    pub fn panner(&self) -> &PannerNode {
+        &self.panner
+    }
+
+    /// Mutable access to the underlying [`PannerNode`], to change its distance and cone
+    /// attributes
+    pub fn panner_mut(&mut self) -> &mut PannerNode {
+        &mut self.panner
+    }
+
+    /// Send of the occluded, unobstructed and unpanned signal, to connect to a reverberation
+    /// bus
+    ///
+    /// The gain of the send defaults to 1 and can be freely changed.
+    pub fn send(&self) -> &GainNode {
+        &self.send
+    }
+
+    /// The current occlusion amount
+    pub fn occlusion(&self) -> f32 {
+        self.occlusion.amount
+    }
+
+    /// Set the occlusion amount, from 0 (no occlusion) to 1 (fully occluded)
+    ///
+    /// The occlusion attenuates and muffles both the panned output and the send.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the amount is outside the [0, 1] range
+    pub fn set_occlusion(&mut self, amount: f32) {
+        assert_valid_amount(amount);
+        self.occlusion.set_amount(amount, self.smoothing);
+    }
+
+    /// The current obstruction amount
+    pub fn obstruction(&self) -> f32 {
+        self.obstruction.amount
+    }
+
+    /// Set the obstruction amount, from 0 (no obstruction) to 1 (fully obstructed)
+    ///
+    /// The obstruction attenuates and muffles the panned output only.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the amount is outside the [0, 1] range
+    pub fn set_obstruction(&mut self, amount: f32) {
+        assert_valid_amount(amount);
+        self.obstruction.set_amount(amount, self.smoothing);
+    }
+
+    /// Register the callback supplying the occlusion and obstruction amounts on each
+    /// [`update`](Self::update)
+    ///
+    /// The callback receives the positions of the emitter and of the listener, e.g. to cast a
+    /// ray in the physics engine of a game. It replaces any previously registered callback.
+    pub fn set_occlusion_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut([f32; 3], [f32; 3]) -> OcclusionAmounts + Send + 'static,
+    {
+        self.occlusion_callback = Some(Box::new(callback));
+    }
+
+    /// Unregister the occlusion callback, the amounts keep their current values
+    pub fn clear_occlusion_callback(&mut self) {
+        self.occlusion_callback = None;
+    }
+
+    /// Add a send of the occluded, unobstructed and unpanned signal, with a gain following the
+    /// given curve of the distance between the emitter and the listener
+    ///
+    /// The returned node is to be connected to a reverberation bus. Its gain is set on each
+    /// [`update`](Self::update). The curve receives the distance in the units of the positions
+    /// and may close over other state of the game, e.g. the zone the listener is in.
+    pub fn add_send<F>(&mut self, curve: F) -> &GainNode
+    where
+        F: Fn(f32) -> f32 + Send + 'static,
+    {
+        let gain = GainNode::new(self.panner.context(), GainOptions::default());
+        self.occlusion.gain.connect(&gain);
+        let index = self.sends.len();
+        self.sends.push(DistanceSend {
+            gain,
+            curve: Box::new(curve),
+            value: None,
+        });
+        &self.sends[index].gain
+    }
+
+    /// Update the emitter, to be called on each tick of the game loop
+    ///
+    /// Calls the occlusion callback, if any, and applies the returned amounts, then sets the
+    /// gains of the sends added with [`add_send`](Self::add_send) from their curves. All the
+    /// changes use the smooth transition of [`SpatialSourceOptions::smoothing`] and are only
+    /// applied when the values change.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the callback returns an amount outside the [0, 1] range, or if a curve
+    /// returns a value that is not finite
+    pub fn update(&mut self) {
+        if self.occlusion_callback.is_none() && self.sends.is_empty() {
+            return;
+        }
+
+        let emitter = position(
+            self.panner.position_x(),
+            self.panner.position_y(),
+            self.panner.position_z(),
+        );
+        let listener = BaseAudioContext::listener(self.panner.context());
+        let listener = position(
+            listener.position_x(),
+            listener.position_y(),
+            listener.position_z(),
+        );
+
+        if let Some(callback) = self.occlusion_callback.as_mut() {
+            let amounts = callback(emitter, listener);
+            if amounts.occlusion != self.occlusion.amount {
+                self.set_occlusion(amounts.occlusion);
+            }
+            if amounts.obstruction != self.obstruction.amount {
+                self.set_obstruction(amounts.obstruction);
+            }
+        }
+
+        let distance = emitter
+            .iter()
+            .zip(listener)
+            .map(|(e, l)| (e - l) * (e - l))
+            .sum::<f32>()
+            .sqrt();
+        for send in &mut self.sends {
+            let value = (send.curve)(distance);
+            if send.value != Some(value) {
+                send.value = Some(value);
+                set_smoothed(send.gain.gain(), value, self.smoothing);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::OfflineAudioContext;
+    use crate::node::{AudioScheduledSourceNode, OscillatorNode, OscillatorOptions};
+
+    use super::*;
+
+    fn render_rms(occlusion: f32, obstruction: f32, frequency: f32) -> (f32, f32) {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(2, 4800, sample_rate);
+
+        let options = SpatialSourceOptions {
+            smoothing: 0.,
+            ..SpatialSourceOptions::default()
+        };
+        let mut emitter = SpatialSource::new(&context, options);
+        emitter.set_occlusion(occlusion);
+        emitter.set_obstruction(obstruction);
+        assert_eq!(emitter.occlusion(), occlusion);
+        assert_eq!(emitter.obstruction(), obstruction);
+
+        // panned output (down-mixed to mono) on the left channel, send on the right channel
+        let merger = context.create_channel_merger(2);
+        emitter.connect(&merger);
+        emitter.send().connect_from_output_to_input(&merger, 0, 1);
+        merger.connect(&context.destination());
+
+        let options = OscillatorOptions {
+            frequency,
+            ..OscillatorOptions::default()
+        };
+        let mut osc = OscillatorNode::new(&context, options);
+        osc.connect(emitter.input());
+        osc.start();
+
+        let output = context.start_rendering_sync();
+        let rms = |channel: &[f32]| {
+            let tail = &channel[2400..];
+            (tail.iter().map(|v| v * v).sum::<f32>() / tail.len() as f32).sqrt()
+        };
+        (
+            rms(output.get_channel_data(0)),
+            rms(output.get_channel_data(1)),
+        )
+    }
+
+    #[test]
+    fn test_occlusion_obstruction() {
+        let (direct, send) = render_rms(0., 0., 5000.);
+        assert!(direct > 0.1);
+        assert!((send - 1. / 2_f32.sqrt()).abs() < 0.01);
+
+        // obstruction muffles the direct sound only
+        let (obstructed, send_obstructed) = render_rms(0., 1., 5000.);
+        assert!(obstructed < direct * 0.05);
+        assert!((send_obstructed - send).abs() < 0.01);
+
+        // occlusion muffles both
+        let (occluded, send_occluded) = render_rms(1., 0., 5000.);
+        assert!(occluded < direct * 0.05);
+        assert!(send_occluded < send * 0.05);
+
+        // low frequencies are only attenuated by the gain
+        let (direct_low, _) = render_rms(0., 0., 100.);
+        let (obstructed_low, _) = render_rms(0., 1., 100.);
+        let expected = 10_f32.powf(-12. / 20.);
+        assert!((obstructed_low / direct_low - expected).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_occlusion_callback() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let options = SpatialSourceOptions {
+            smoothing: 0.,
+            ..SpatialSourceOptions::default()
+        };
+        let mut emitter = SpatialSource::new(&context, options);
+        emitter.panner().set_position(3., 0., -4.);
+
+        // without callback, update leaves the amounts untouched
+        emitter.set_obstruction(0.2);
+        emitter.update();
+        assert_eq!(emitter.obstruction(), 0.2);
+
+        emitter.set_occlusion_callback(|emitter, listener| {
+            let [x, y, z] = [0, 1, 2].map(|i| emitter[i] - listener[i]);
+            let distance = (x * x + y * y + z * z).sqrt();
+            OcclusionAmounts {
+                occlusion: distance / 10.,
+                obstruction: 1.,
+            }
+        });
+        emitter.update();
+        assert_eq!(emitter.occlusion(), 0.5);
+        assert_eq!(emitter.obstruction(), 1.);
+
+        emitter.clear_occlusion_callback();
+        emitter.set_occlusion(0.);
+        emitter.update();
+        assert_eq!(emitter.occlusion(), 0.);
+    }
+
+    #[test]
+    fn test_distance_sends() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let options = SpatialSourceOptions {
+            smoothing: 0.,
+            ..SpatialSourceOptions::default()
+        };
+        let mut emitter = SpatialSource::new(&context, options);
+        emitter.panner().set_position(0., 6., 8.);
+
+        emitter.add_send(|distance| (1. - distance / 20.).max(0.));
+        emitter.add_send(|distance| (distance / 50.).min(1.));
+        emitter.update();
+
+        let gains: Vec<_> = emitter
+            .sends
+            .iter()
+            .map(|send| send.gain.gain().value_at_time(0.))
+            .collect();
+        assert_eq!(gains, [0.5, 0.2]);
+
+        // the send of the constructor is not driven by the distance
+        assert_eq!(emitter.send().gain().value_at_time(0.), 1.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_amount() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let mut emitter = SpatialSource::new(&context, SpatialSourceOptions::default());
+        emitter.set_occlusion(1.5);
+    }
+}
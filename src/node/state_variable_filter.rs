@@ -0,0 +1,441 @@
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+use arrayvec::ArrayVec;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Lowest Q value used for rendering, avoids a division by zero
+const MIN_Q: f32 = 1e-4;
+
+/// Outputs of the [`StateVariableFilterNode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateVariableFilterOutput {
+    Lowpass,
+    Highpass,
+    Bandpass,
+    Notch,
+}
+
+impl StateVariableFilterOutput {
+    /// Index of the output, to be used with
+    /// [`connect_from_output_to_input`](AudioNode::connect_from_output_to_input)
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Options for constructing a [`StateVariableFilterNode`]
+#[derive(Clone, Debug)]
+pub struct StateVariableFilterOptions {
+    /// Cutoff frequency in Hz
+    pub frequency: f32,
+    /// Quality factor, controls the resonance at the cutoff frequency
+    pub q: f32,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for StateVariableFilterOptions {
+    fn default() -> Self {
+        Self {
+            frequency: 350.,
+            q: FRAC_1_SQRT_2 as f32,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `StateVariableFilterNode` is a resonant second order filter with simultaneous lowpass,
+/// highpass, bandpass and notch outputs.
+///
+/// The filter uses the topology-preserving transform (zero-delay feedback) of the analog state
+/// variable filter. Unlike the direct form structure of the
+/// [`BiquadFilterNode`](super::BiquadFilterNode), its internal state stays meaningful when the
+/// coefficients change, so the cutoff frequency and Q can be modulated at audio rate, e.g. for
+/// fast sweeps or filter FM, without zipper noise or instability.
+///
+/// The outputs are, in order (see [`StateVariableFilterOutput`]):
+/// - 0: lowpass
+/// - 1: highpass
+/// - 2: bandpass, normalized to unity gain at the cutoff frequency
+/// - 3: notch
+///
+/// The sum of the lowpass, highpass and bandpass outputs is equal to the input.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{
+///     StateVariableFilterNode, StateVariableFilterOptions, StateVariableFilterOutput,
+/// };
+///
+/// let context = AudioContext::default();
+///
+/// let filter = StateVariableFilterNode::new(&context, StateVariableFilterOptions::default());
+/// filter.q().set_value(8.);
+/// filter.connect_from_output_to_input(
+///     &context.destination(),
+///     StateVariableFilterOutput::Bandpass.index(),
+///     0,
+/// );
+///
+/// // sweep the cutoff frequency with an LFO
+/// let mut lfo = context.create_oscillator();
+/// lfo.frequency().set_value(8.);
+/// let depth = context.create_gain();
+/// depth.gain().set_value(300.);
+/// lfo.connect(&depth);
+/// depth.connect(filter.frequency());
+/// lfo.start();
+///
+/// let mut osc = context.create_oscillator();
+/// osc.set_type(web_audio_api::node::OscillatorType::Sawtooth);
+/// osc.connect(&filter);
+/// osc.start();
+/// ```
+#[derive(Debug)]
+pub struct StateVariableFilterNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    frequency: AudioParam,
+    q: AudioParam,
+}
+
+impl AudioNode for StateVariableFilterNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        4
+    }
+}
+
+impl StateVariableFilterNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: StateVariableFilterOptions) -> Self {
+        context.base().register(move |registration| {
+            let StateVariableFilterOptions {
+                frequency,
+                q,
+                audio_node_options,
+            } = options;
+
+            let freq_options = AudioParamDescriptor {
+                name: String::from("frequency"),
+                min_value: 0.,
+                max_value: context.sample_rate() / 2.,
+                default_value: 350.,
+                automation_rate: AutomationRate::A,
+            };
+            let (f_param, f_proc) = context.create_audio_param(freq_options, &registration);
+            f_param.set_value(frequency);
+
+            let q_options = AudioParamDescriptor {
+                name: String::from("Q"),
+                min_value: MIN_Q,
+                max_value: 1000.,
+                default_value: FRAC_1_SQRT_2 as f32,
+                automation_rate: AutomationRate::A,
+            };
+            let (q_param, q_proc) = context.create_audio_param(q_options, &registration);
+            q_param.set_value(q);
+
+            let renderer = StateVariableFilterRenderer {
+                frequency: f_proc,
+                q: q_proc,
+                ic: ArrayVec::new(),
+            };
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                frequency: f_param,
+                q: q_param,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Returns the cutoff frequency audio parameter
+    #[must_use]
+    pub fn frequency(&self) -> &AudioParam {
+        &self.frequency
+    }
+
+    /// Returns the Q audio parameter
+    #[must_use]
+    pub fn q(&self) -> &AudioParam {
+        &self.q
+    }
+}
+
+/// Coefficients of the zero-delay feedback state variable filter, see
+/// <https://cytomic.com/files/dsp/SvfLinearTrapOptimised2.pdf>
+#[derive(Clone, Copy, Debug, Default)]
+struct Coefficients {
+    k: f64,
+    a1: f64,
+    a2: f64,
+    a3: f64,
+}
+
+impl Coefficients {
+    fn new(frequency: f32, q: f32, sample_rate: f32) -> Self {
+        // keep the cutoff just below nyquist, where the prewarping diverges
+        let frequency = f64::from(frequency.clamp(0., sample_rate * 0.49));
+        let g = (PI * frequency / f64::from(sample_rate)).tan();
+        let k = 1. / f64::from(q.max(MIN_Q));
+
+        let a1 = 1. / (1. + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+
+        Self { k, a1, a2, a3 }
+    }
+}
+
+struct StateVariableFilterRenderer {
+    frequency: AudioParamId,
+    q: AudioParamId,
+    // integrator states for each channel
+    ic: ArrayVec<[f64; 2], MAX_CHANNELS>,
+}
+
+impl AudioProcessor for StateVariableFilterRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input node
+        let input = &inputs[0];
+        let sample_rate = scope.sample_rate;
+
+        // handle tail time
+        if input.is_silent() && !self.ic.iter().flatten().any(|v| v.is_normal()) {
+            outputs.iter_mut().for_each(AudioRenderQuantum::make_silent);
+            return false;
+        }
+
+        // eventually resize state according to input number of channels
+        // if in tail time, we should continue with previous number of channels
+        let number_of_channels = if input.is_silent() {
+            self.ic.len()
+        } else {
+            input.number_of_channels()
+        };
+        self.ic.truncate(number_of_channels);
+        for _ in self.ic.len()..number_of_channels {
+            self.ic.push([0.; 2]);
+        }
+
+        outputs
+            .iter_mut()
+            .for_each(|output| output.set_number_of_channels(number_of_channels));
+
+        // get a-rate parameters
+        let frequency = params.get(&self.frequency);
+        let q = params.get(&self.q);
+
+        let coefs = Coefficients::new(frequency[0], q[0], sample_rate);
+        let mut coefs_list = [coefs; RENDER_QUANTUM_SIZE];
+        // only compute the coefs for each frame when the params are modulated
+        if frequency.len() != 1 || q.len() != 1 {
+            coefs_list
+                .iter_mut()
+                .zip(frequency.iter().cycle())
+                .zip(q.iter().cycle())
+                .skip(1)
+                .for_each(|((coefs, &f), &q)| *coefs = Coefficients::new(f, q, sample_rate));
+        }
+
+        let [lowpass, highpass, bandpass, notch] = outputs else {
+            unreachable!()
+        };
+
+        for (channel_number, [ic1eq, ic2eq]) in self.ic.iter_mut().enumerate() {
+            let input_channel = if input.is_silent() {
+                input.channel_data(0)
+            } else {
+                input.channel_data(channel_number)
+            };
+
+            let lowpass = lowpass.channel_data_mut(channel_number);
+            let highpass = highpass.channel_data_mut(channel_number);
+            let bandpass = bandpass.channel_data_mut(channel_number);
+            let notch = notch.channel_data_mut(channel_number);
+
+            for (i, (&x, c)) in input_channel.iter().zip(coefs_list.iter()).enumerate() {
+                let v0 = f64::from(x);
+                let v3 = v0 - *ic2eq;
+                let v1 = c.a1 * *ic1eq + c.a2 * v3;
+                let v2 = *ic2eq + c.a2 * *ic1eq + c.a3 * v3;
+                *ic1eq = 2. * v1 - *ic1eq;
+                *ic2eq = 2. * v2 - *ic2eq;
+
+                let band = c.k * v1;
+                lowpass[i] = v2 as f32;
+                highpass[i] = (v0 - band - v2) as f32;
+                bandpass[i] = band as f32;
+                notch[i] = (v0 - band) as f32;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::{AudioScheduledSourceNode, ChannelMergerNode, ChannelMergerOptions};
+    use crate::AudioBuffer;
+
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.;
+    const LENGTH: usize = RENDER_QUANTUM_SIZE * 100;
+
+    // render a sine through the filter, each output to its own channel
+    fn render_sine(frequency: f32, cutoff: f32) -> AudioBuffer {
+        let context = OfflineAudioContext::new(4, LENGTH, SAMPLE_RATE);
+
+        let options = StateVariableFilterOptions {
+            frequency: cutoff,
+            ..StateVariableFilterOptions::default()
+        };
+        let filter = StateVariableFilterNode::new(&context, options);
+
+        let merger = ChannelMergerNode::new(
+            &context,
+            ChannelMergerOptions {
+                number_of_inputs: 4,
+                ..ChannelMergerOptions::default()
+            },
+        );
+        merger.connect(&context.destination());
+        for output in 0..4 {
+            filter.connect_from_output_to_input(&merger, output, output);
+        }
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(frequency);
+        osc.connect(&filter);
+        osc.start();
+
+        context.start_rendering_sync()
+    }
+
+    // peak amplitude in steady state
+    fn peak(data: &[f32]) -> f32 {
+        data[LENGTH / 2..]
+            .iter()
+            .fold(0., |max, s| s.abs().max(max))
+    }
+
+    #[test]
+    fn test_constructor() {
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let filter = StateVariableFilterNode::new(&context, StateVariableFilterOptions::default());
+
+        assert_eq!(filter.number_of_outputs(), 4);
+        assert_float_eq!(filter.frequency().value(), 350., abs <= 0.);
+        assert_float_eq!(filter.q().value(), FRAC_1_SQRT_2 as f32, abs <= 0.);
+        assert_eq!(StateVariableFilterOutput::Bandpass.index(), 2);
+    }
+
+    #[test]
+    fn test_outputs() {
+        let low = render_sine(100., 1000.);
+        assert_float_eq!(peak(low.get_channel_data(0)), 1., abs <= 1e-2);
+        assert!(peak(low.get_channel_data(1)) < 0.02);
+        assert!(peak(low.get_channel_data(2)) < 0.2);
+        assert_float_eq!(peak(low.get_channel_data(3)), 1., abs <= 1e-2);
+
+        let high = render_sine(10_000., 1000.);
+        assert!(peak(high.get_channel_data(0)) < 0.02);
+        assert_float_eq!(peak(high.get_channel_data(1)), 1., abs <= 1e-2);
+        assert!(peak(high.get_channel_data(2)) < 0.2);
+        assert_float_eq!(peak(high.get_channel_data(3)), 1., abs <= 1e-2);
+
+        let center = render_sine(1000., 1000.);
+        assert_float_eq!(peak(center.get_channel_data(2)), 1., abs <= 1e-2);
+        assert!(peak(center.get_channel_data(3)) < 1e-2);
+    }
+
+    #[test]
+    fn test_sum_is_input() {
+        let result = render_sine(440., 1000.);
+
+        let context = OfflineAudioContext::new(1, LENGTH, SAMPLE_RATE);
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(440.);
+        osc.connect(&context.destination());
+        osc.start();
+        let expected = context.start_rendering_sync();
+
+        let sum: Vec<f32> = (0..LENGTH)
+            .map(|i| {
+                result.get_channel_data(0)[i]
+                    + result.get_channel_data(1)[i]
+                    + result.get_channel_data(2)[i]
+            })
+            .collect();
+
+        assert_float_eq!(sum[..], expected.get_channel_data(0)[..], abs_all <= 1e-5);
+    }
+
+    #[test]
+    fn test_stable_under_fast_modulation() {
+        let context = OfflineAudioContext::new(1, LENGTH, SAMPLE_RATE);
+
+        let options = StateVariableFilterOptions {
+            frequency: 10_000.,
+            q: 5.,
+            ..StateVariableFilterOptions::default()
+        };
+        let filter = StateVariableFilterNode::new(&context, options);
+        filter.connect(&context.destination());
+
+        // sweep the whole frequency range at audio rate
+        let mut lfo = context.create_oscillator();
+        lfo.frequency().set_value(3000.);
+        let depth = context.create_gain();
+        depth.gain().set_value(10_000.);
+        lfo.connect(&depth);
+        depth.connect(filter.frequency());
+        lfo.start();
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(500.);
+        osc.connect(&filter);
+        osc.start();
+
+        let output = context.start_rendering_sync();
+        let data = output.get_channel_data(0);
+        assert!(data.iter().all(|s| s.is_finite() && s.abs() < 50.));
+    }
+}
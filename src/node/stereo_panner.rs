@@ -178,7 +178,7 @@ impl StereoPannerNode {
             assert_valid_channel_count(options.audio_node_options.channel_count);
 
             let pan_options = AudioParamDescriptor {
-                name: String::new(),
+                name: String::from("pan"),
                 min_value: -1.,
                 max_value: 1.,
                 default_value: 0.,
@@ -0,0 +1,348 @@
+use std::f32::consts::PI;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
+
+/// Maximum of the `width` param of the [`StereoWidthNode`]
+///
+/// Beyond twice the original side level the channels become mostly out of phase
+const MAX_WIDTH: f32 = 2.;
+
+/// Options for constructing a [`StereoWidthNode`]
+#[derive(Clone, Debug)]
+pub struct StereoWidthOptions {
+    /// Gain applied to the side signal, 0 collapses the image to mono and 1 leaves it unchanged
+    pub width: f32,
+    /// Frequency in Hz below which the side signal is removed, 0 disables the filter
+    pub mono_frequency: f32,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for StereoWidthOptions {
+    fn default() -> Self {
+        Self {
+            width: 1.,
+            mono_frequency: 0.,
+            audio_node_options: AudioNodeOptions {
+                channel_count: 2,
+                channel_count_mode: ChannelCountMode::Explicit,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// Assert that the channel count is valid for the StereoWidthNode
+///
+/// # Panics
+///
+/// This function panics if given count is greater than 2
+#[track_caller]
+#[inline(always)]
+fn assert_valid_channel_count(count: usize) {
+    assert!(
+        count <= 2,
+        "NotSupportedError - StereoWidthNode channel count cannot be greater than two"
+    );
+}
+
+/// Assert that the channel count mode is valid for the StereoWidthNode
+///
+/// # Panics
+///
+/// This function panics if given count mode is [`ChannelCountMode::Max`]
+#[track_caller]
+#[inline(always)]
+fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
+    assert_ne!(
+        mode,
+        ChannelCountMode::Max,
+        "NotSupportedError - StereoWidthNode channel count mode cannot be set to max",
+    );
+}
+
+/// `StereoWidthNode` narrows or widens the stereo image of its input by mid/side processing
+///
+/// The input is split into its mid `(L + R) / 2` and side `(L - R) / 2` components, the side is
+/// scaled by `width` and the result converted back to left and right.
+///
+/// To keep the output mono compatible:
+/// - the mid signal is never altered, so the mono downmix of the output is the mono downmix of
+///   the input whatever the width,
+/// - `width` is capped at 2, after which the channels get mostly out of phase,
+/// - the side signal below `mono_frequency` is removed, keeping the low end centered.
+///
+/// Mono inputs have no side component and are passed through.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, StereoWidthNode, StereoWidthOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let options = StereoWidthOptions {
+///     width: 1.5,
+///     mono_frequency: 120.,
+///     ..StereoWidthOptions::default()
+/// };
+/// let width = StereoWidthNode::new(&context, options);
+/// width.connect(&context.destination());
+/// ```
+#[derive(Debug)]
+pub struct StereoWidthNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    width: AudioParam,
+    mono_frequency: AudioParam,
+}
+
+impl AudioNode for StereoWidthNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_channel_count_mode(mode);
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_channel_count(count);
+        self.channel_config.set_count(count, self.registration());
+    }
+}
+
+impl StereoWidthNode {
+    /// Create a new `StereoWidthNode`
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// * `options.audio_node_options.channel_count` is greater than 2
+    /// * `options.audio_node_options.channel_count_mode` is `ChannelCountMode::Max`
+    pub fn new<C: BaseAudioContext>(context: &C, options: StereoWidthOptions) -> Self {
+        assert_valid_channel_count_mode(options.audio_node_options.channel_count_mode);
+        assert_valid_channel_count(options.audio_node_options.channel_count);
+        let nyquist = context.sample_rate() / 2.;
+
+        context.base().register(move |registration| {
+            let width_descriptor = AudioParamDescriptor {
+                name: String::from("width"),
+                min_value: 0.,
+                max_value: MAX_WIDTH,
+                default_value: 1.,
+                automation_rate: AutomationRate::A,
+            };
+            let (width_param, width_proc) =
+                context.create_audio_param(width_descriptor, &registration);
+            width_param.set_value(options.width);
+
+            let mono_frequency_descriptor = AudioParamDescriptor {
+                name: String::from("monoFrequency"),
+                min_value: 0.,
+                max_value: nyquist,
+                default_value: 0.,
+                automation_rate: AutomationRate::K,
+            };
+            let (mono_frequency_param, mono_frequency_proc) =
+                context.create_audio_param(mono_frequency_descriptor, &registration);
+            mono_frequency_param.set_value(options.mono_frequency);
+
+            let render = StereoWidthRenderer {
+                width: width_proc,
+                mono_frequency: mono_frequency_proc,
+                side_lowpass: 0.,
+            };
+
+            let node = StereoWidthNode {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                width: width_param,
+                mono_frequency: mono_frequency_param,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// A-rate [`AudioParam`] representing the gain applied to the side signal, in the [0, 2]
+    /// range
+    #[must_use]
+    pub fn width(&self) -> &AudioParam {
+        &self.width
+    }
+
+    /// K-rate [`AudioParam`] representing the frequency in Hz below which the side signal is
+    /// removed
+    #[must_use]
+    pub fn mono_frequency(&self) -> &AudioParam {
+        &self.mono_frequency
+    }
+}
+
+struct StereoWidthRenderer {
+    width: AudioParamId,
+    mono_frequency: AudioParamId,
+    /// state of the one-pole lowpass filter, subtracted from the side signal
+    side_lowpass: f32,
+}
+
+impl AudioProcessor for StereoWidthRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        *output = input.clone();
+
+        if input.is_silent() {
+            self.side_lowpass = 0.;
+            return false;
+        }
+
+        // mono inputs have no side signal
+        if input.number_of_channels() != 2 {
+            return false;
+        }
+
+        let width = params.get(&self.width);
+        let mono_frequency = params.get(&self.mono_frequency)[0];
+        let alpha = 1. - (-2. * PI * mono_frequency / scope.sample_rate).exp();
+
+        let mut side_lowpass = self.side_lowpass;
+        let [left, right] = output.stereo_mut();
+
+        left.iter_mut()
+            .zip(right.iter_mut())
+            .enumerate()
+            .for_each(|(i, (l, r))| {
+                let mid = (*l + *r) * 0.5;
+                let side = (*l - *r) * 0.5;
+
+                side_lowpass += alpha * (side - side_lowpass);
+                let side = (side - side_lowpass) * width[i.min(width.len() - 1)];
+
+                *l = mid + side;
+                *r = mid - side;
+            });
+
+        self.side_lowpass = side_lowpass;
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    fn render_stereo(options: StereoWidthOptions, left: f32, right: f32) -> [Vec<f32>; 2] {
+        let sample_rate = 48_000.;
+        let length = 1024;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        let width = StereoWidthNode::new(&context, options);
+        width.connect(&context.destination());
+
+        let mut buffer = context.create_buffer(2, length, sample_rate);
+        buffer.copy_to_channel(&vec![left; length], 0);
+        buffer.copy_to_channel(&vec![right; length], 1);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&width);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        [
+            output.get_channel_data(0).to_vec(),
+            output.get_channel_data(1).to_vec(),
+        ]
+    }
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(2, 1, 48_000.);
+        let width = StereoWidthNode::new(&context, StereoWidthOptions::default());
+
+        assert_float_eq!(width.width().value(), 1., abs <= 0.);
+        assert_float_eq!(width.mono_frequency().value(), 0., abs <= 0.);
+        assert_eq!(width.channel_count(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_count() {
+        let context = OfflineAudioContext::new(2, 1, 48_000.);
+        let width = StereoWidthNode::new(&context, StereoWidthOptions::default());
+        width.set_channel_count(3);
+    }
+
+    #[test]
+    fn test_width() {
+        for (value, expected_left, expected_right) in
+            [(0., 0.5, 0.5), (1., 1., 0.), (2., 1.5, -0.5)]
+        {
+            let options = StereoWidthOptions {
+                width: value,
+                ..StereoWidthOptions::default()
+            };
+            let [left, right] = render_stereo(options, 1., 0.);
+
+            assert_float_eq!(left[..], vec![expected_left; 1024][..], abs_all <= 1e-6);
+            assert_float_eq!(right[..], vec![expected_right; 1024][..], abs_all <= 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_mono_frequency() {
+        // a constant side signal is entirely below any cutoff frequency
+        let options = StereoWidthOptions {
+            width: 2.,
+            mono_frequency: 1000.,
+            ..StereoWidthOptions::default()
+        };
+        let [left, right] = render_stereo(options, 1., 0.);
+
+        // the mono downmix is preserved
+        left.iter()
+            .zip(right.iter())
+            .for_each(|(l, r)| assert_float_eq!(l + r, 1., abs <= 1e-6));
+
+        assert_float_eq!(left[1023], 0.5, abs <= 1e-3);
+        assert_float_eq!(right[1023], 0.5, abs <= 1e-3);
+    }
+}
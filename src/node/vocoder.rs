@@ -0,0 +1,514 @@
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{
+    calculate_coefs, AudioNode, AudioNodeOptions, BiquadFilterType, ChannelConfig, Coefficients,
+};
+
+/// Maximum number of bands of the [`VocoderNode`]
+const MAX_BANDS: usize = 64;
+
+/// Level below which the output of the vocoder is considered silent
+const SILENCE_THRESHOLD: f32 = 1e-5;
+
+/// Assert that the band count and frequency range of the vocoder are valid
+///
+/// # Panics
+///
+/// This function panics if:
+/// - the number of bands is lower than 2 or greater than 64
+/// - the frequency range is empty or not within ]0, nyquist[
+#[track_caller]
+#[inline(always)]
+fn assert_valid_bands(bands: usize, min_frequency: f32, max_frequency: f32, sample_rate: f32) {
+    assert!(
+        (2..=MAX_BANDS).contains(&bands),
+        "NotSupportedError - VocoderNode bands must be between 2 and {}, got {}",
+        MAX_BANDS,
+        bands
+    );
+    assert!(
+        min_frequency > 0. && min_frequency < max_frequency && max_frequency < sample_rate / 2.,
+        "NotSupportedError - VocoderNode frequency range must be within ]0, {}[, got [{}, {}]",
+        sample_rate / 2.,
+        min_frequency,
+        max_frequency
+    );
+}
+
+/// Options for constructing a [`VocoderNode`]
+#[derive(Clone, Debug)]
+pub struct VocoderOptions {
+    /// Number of analysis and synthesis bands
+    pub bands: usize,
+    /// Center frequency in Hz of the lowest band
+    pub min_frequency: f32,
+    /// Center frequency in Hz of the highest band, the sibilance pass-through starts above
+    pub max_frequency: f32,
+    /// Time in seconds for the band envelopes to rise
+    pub attack: f32,
+    /// Time in seconds for the band envelopes to fall
+    pub release: f32,
+    /// Gain of the high frequencies of the modulator mixed into the output
+    pub sibilance: f32,
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for VocoderOptions {
+    fn default() -> Self {
+        Self {
+            bands: 16,
+            min_frequency: 100.,  // Hz
+            max_frequency: 6000., // Hz
+            attack: 0.005,        // seconds
+            release: 0.05,        // seconds
+            sibilance: 0.,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `VocoderNode` imposes the spectral envelope of a modulator on a carrier
+///
+/// The first input is the carrier, typically a harmonically rich synthesizer, and the second
+/// input is the modulator, typically a voice. Both are split into `bands` bandpass filters
+/// spaced logarithmically between `min_frequency` and `max_frequency`. The level of each band
+/// of the modulator, tracked by an envelope follower, sets the gain of the same band of the
+/// carrier.
+///
+/// Consonants such as "s" or "t" mostly lie above the bands and get lost in the process. The
+/// `sibilance` param mixes the modulator above `max_frequency` directly into the output to
+/// keep the speech intelligible.
+///
+/// The modulator is mixed down to mono, the output has the channels of the carrier.
+///
+/// This node is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::media_devices;
+/// use web_audio_api::media_devices::MediaStreamConstraints;
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{OscillatorType, VocoderNode, VocoderOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let vocoder = VocoderNode::new(&context, VocoderOptions::default());
+/// vocoder.connect(&context.destination());
+///
+/// // carrier
+/// let mut osc = context.create_oscillator();
+/// osc.set_type(OscillatorType::Sawtooth);
+/// osc.frequency().set_value(110.);
+/// osc.connect_from_output_to_input(&vocoder, 0, 0);
+/// osc.start();
+///
+/// // modulator
+/// let mic = media_devices::get_user_media_sync(MediaStreamConstraints::Audio);
+/// let voice = context.create_media_stream_source(&mic);
+/// voice.connect_from_output_to_input(&vocoder, 0, 1);
+/// ```
+#[derive(Debug)]
+pub struct VocoderNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    bands: usize,
+    attack: AudioParam,
+    release: AudioParam,
+    sibilance: AudioParam,
+}
+
+impl AudioNode for VocoderNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        2
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl VocoderNode {
+    /// Create a new `VocoderNode`
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - the number of bands is lower than 2 or greater than 64
+    /// - the frequency range is empty or not within ]0, nyquist[
+    pub fn new<C: BaseAudioContext>(context: &C, options: VocoderOptions) -> Self {
+        let sample_rate = context.sample_rate();
+        assert_valid_bands(
+            options.bands,
+            options.min_frequency,
+            options.max_frequency,
+            sample_rate,
+        );
+
+        context.base().register(move |registration| {
+            let create_param = |name: &str, default_value, min_value, max_value, value| {
+                let descriptor = AudioParamDescriptor {
+                    name: name.to_string(),
+                    min_value,
+                    max_value,
+                    default_value,
+                    automation_rate: AutomationRate::K,
+                };
+                let (mut param, proc) = context.create_audio_param(descriptor, &registration);
+                param.set_automation_rate_constrained(true);
+                param.set_value(value);
+                (param, proc)
+            };
+
+            let (attack_param, attack_proc) = create_param("attack", 0.005, 0., 1., options.attack);
+            let (release_param, release_proc) =
+                create_param("release", 0.05, 0., 1., options.release);
+            let (sibilance_param, sibilance_proc) =
+                create_param("sibilance", 0., 0., 4., options.sibilance);
+
+            let render = VocoderRenderer::new(
+                &options,
+                sample_rate,
+                attack_proc,
+                release_proc,
+                sibilance_proc,
+            );
+
+            let node = VocoderNode {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                bands: options.bands,
+                attack: attack_param,
+                release: release_param,
+                sibilance: sibilance_param,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The number of analysis and synthesis bands
+    #[must_use]
+    pub fn bands(&self) -> usize {
+        self.bands
+    }
+
+    /// K-rate [`AudioParam`] representing the time in seconds for the band envelopes to rise
+    #[must_use]
+    pub fn attack(&self) -> &AudioParam {
+        &self.attack
+    }
+
+    /// K-rate [`AudioParam`] representing the time in seconds for the band envelopes to fall
+    #[must_use]
+    pub fn release(&self) -> &AudioParam {
+        &self.release
+    }
+
+    /// K-rate [`AudioParam`] representing the gain of the high frequencies of the modulator
+    /// mixed into the output
+    #[must_use]
+    pub fn sibilance(&self) -> &AudioParam {
+        &self.sibilance
+    }
+}
+
+/// Transposed direct form II biquad
+#[derive(Clone, Copy, Default)]
+struct Biquad {
+    coefs: Coefficients,
+    s1: f64,
+    s2: f64,
+}
+
+impl Biquad {
+    fn new(coefs: Coefficients) -> Self {
+        Self {
+            coefs,
+            s1: 0.,
+            s2: 0.,
+        }
+    }
+
+    #[inline(always)]
+    fn tick(&mut self, input: f32) -> f32 {
+        let Coefficients { b0, b1, b2, a1, a2 } = self.coefs;
+        let x = f64::from(input);
+        let y = b0 * x + self.s1;
+        self.s1 = b1 * x - a1 * y + self.s2;
+        self.s2 = b2 * x - a2 * y;
+        y as f32
+    }
+}
+
+struct VocoderRenderer {
+    attack: AudioParamId,
+    release: AudioParamId,
+    sibilance: AudioParamId,
+    /// bandpass filters of the modulator
+    analysis: Vec<Biquad>,
+    /// envelope of each band of the modulator
+    envelopes: Vec<f32>,
+    /// envelope of each band of the modulator for each frame of the render quantum
+    envelope_frames: Vec<[f32; RENDER_QUANTUM_SIZE]>,
+    /// bandpass filters of the carrier, one set per channel
+    synthesis: Vec<Vec<Biquad>>,
+    /// highpass filter of the modulator for the sibilance pass-through
+    sibilance_filter: Biquad,
+    /// filters and envelopes still ring
+    tail_pending: bool,
+}
+
+impl VocoderRenderer {
+    fn new(
+        options: &VocoderOptions,
+        sample_rate: f32,
+        attack: AudioParamId,
+        release: AudioParamId,
+        sibilance: AudioParamId,
+    ) -> Self {
+        let sample_rate = f64::from(sample_rate);
+        let min_frequency = f64::from(options.min_frequency);
+        let max_frequency = f64::from(options.max_frequency);
+
+        // each band spans the ratio between consecutive center frequencies
+        let ratio = (max_frequency / min_frequency).powf(1. / (options.bands - 1) as f64);
+        let q = ratio.sqrt() / (ratio - 1.);
+
+        let analysis = (0..options.bands)
+            .map(|band| {
+                let frequency = min_frequency * ratio.powi(band as i32);
+                let coefs =
+                    calculate_coefs(BiquadFilterType::Bandpass, sample_rate, frequency, 0., q);
+                Biquad::new(coefs)
+            })
+            .collect();
+
+        // Butterworth highpass, the q of the highpass is expressed in dB
+        let coefs = calculate_coefs(
+            BiquadFilterType::Highpass,
+            sample_rate,
+            max_frequency * ratio.sqrt(),
+            0.,
+            20. * std::f64::consts::FRAC_1_SQRT_2.log10(),
+        );
+
+        Self {
+            attack,
+            release,
+            sibilance,
+            analysis,
+            envelopes: vec![0.; options.bands],
+            envelope_frames: vec![[0.; RENDER_QUANTUM_SIZE]; options.bands],
+            synthesis: vec![],
+            sibilance_filter: Biquad::new(coefs),
+            tail_pending: false,
+        }
+    }
+}
+
+impl AudioProcessor for VocoderRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        let carrier = &inputs[0];
+        let modulator = &inputs[1];
+        let output = &mut outputs[0];
+
+        if carrier.is_silent() && modulator.is_silent() && !self.tail_pending {
+            output.make_silent();
+            return false;
+        }
+
+        *output = carrier.clone();
+        if carrier.is_silent() {
+            // render the tail with the previous number of channels
+            output.set_number_of_channels(self.synthesis.len().max(1));
+        }
+        let number_of_channels = output.number_of_channels();
+        if self.synthesis.len() != number_of_channels {
+            let bands = self.analysis.clone();
+            self.synthesis.resize(number_of_channels, bands);
+        }
+
+        let sample_rate = scope.sample_rate;
+        let attack = params.get(&self.attack)[0];
+        let release = params.get(&self.release)[0];
+        let sibilance = params.get(&self.sibilance)[0];
+
+        let attack_tau = (-1. / (attack * sample_rate)).exp();
+        let release_tau = (-1. / (release * sample_rate)).exp();
+
+        // mono downmix of the modulator
+        let mut modulation = [0.; RENDER_QUANTUM_SIZE];
+        if !modulator.is_silent() {
+            let gain = 1. / modulator.number_of_channels() as f32;
+            modulator.channels().iter().for_each(|channel| {
+                modulation
+                    .iter_mut()
+                    .zip(channel.iter())
+                    .for_each(|(m, c)| *m += c * gain);
+            });
+        }
+
+        // sibilance pass-through
+        let mut sibilants = [0.; RENDER_QUANTUM_SIZE];
+        sibilants
+            .iter_mut()
+            .zip(modulation.iter())
+            .for_each(|(s, m)| *s = sibilance * self.sibilance_filter.tick(*m));
+
+        let mut loud = false;
+
+        // band envelopes, stored per frame for the synthesis
+        self.analysis
+            .iter_mut()
+            .zip(self.envelopes.iter_mut())
+            .zip(self.envelope_frames.iter_mut())
+            .for_each(|((filter, envelope), frames)| {
+                modulation
+                    .iter()
+                    .zip(frames.iter_mut())
+                    .for_each(|(m, frame)| {
+                        let level = filter.tick(*m).abs();
+                        let tau = if level > *envelope {
+                            attack_tau
+                        } else {
+                            release_tau
+                        };
+                        *envelope = level + tau * (*envelope - level);
+                        *frame = *envelope;
+                    });
+                loud |= *envelope > SILENCE_THRESHOLD;
+            });
+
+        let envelope_frames = &self.envelope_frames;
+        output
+            .channels_mut()
+            .iter_mut()
+            .zip(self.synthesis.iter_mut())
+            .for_each(|(channel, filters)| {
+                let mut mixed = sibilants;
+                filters
+                    .iter_mut()
+                    .zip(envelope_frames.iter())
+                    .for_each(|(filter, frames)| {
+                        channel
+                            .iter()
+                            .zip(frames.iter())
+                            .zip(mixed.iter_mut())
+                            .for_each(|((c, e), o)| *o += filter.tick(*c) * e);
+                    });
+                loud |= mixed.iter().any(|o| o.abs() > SILENCE_THRESHOLD);
+                channel.copy_from_slice(&mixed);
+            });
+
+        self.tail_pending = loud;
+        loud
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    fn render_vocoder(
+        carrier_frequency: Option<f32>,
+        modulator_frequency: f32,
+        options: VocoderOptions,
+    ) -> f32 {
+        let sample_rate = 48_000.;
+        let length = 4096;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let vocoder = VocoderNode::new(&context, options);
+        vocoder.connect(&context.destination());
+
+        if let Some(frequency) = carrier_frequency {
+            let mut carrier = context.create_oscillator();
+            carrier.frequency().set_value(frequency);
+            carrier.connect_from_output_to_input(&vocoder, 0, 0);
+            carrier.start();
+        }
+
+        let mut modulator = context.create_oscillator();
+        modulator.frequency().set_value(modulator_frequency);
+        modulator.connect_from_output_to_input(&vocoder, 0, 1);
+        modulator.start();
+
+        let output = context.start_rendering_sync();
+        output.get_channel_data(0)[length / 2..]
+            .iter()
+            .fold(0_f32, |max, s| max.max(s.abs()))
+    }
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(1, 1, 48_000.);
+        let vocoder = VocoderNode::new(&context, VocoderOptions::default());
+
+        assert_eq!(vocoder.number_of_inputs(), 2);
+        assert_eq!(vocoder.bands(), 16);
+        assert_float_eq!(vocoder.attack().value(), 0.005, abs <= 0.);
+        assert_float_eq!(vocoder.release().value(), 0.05, abs <= 0.);
+        assert_float_eq!(vocoder.sibilance().value(), 0., abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_bands() {
+        let context = OfflineAudioContext::new(1, 1, 48_000.);
+        let options = VocoderOptions {
+            bands: 1,
+            ..VocoderOptions::default()
+        };
+        let _ = VocoderNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_matching_bands() {
+        // the carrier passes where the modulator has energy
+        let peak = render_vocoder(Some(1000.), 1000., VocoderOptions::default());
+        assert!(peak > 0.5, "{}", peak);
+
+        // and is mostly rejected elsewhere
+        let peak = render_vocoder(Some(3000.), 200., VocoderOptions::default());
+        assert!(peak < 0.1, "{}", peak);
+    }
+
+    #[test]
+    fn test_sibilance() {
+        let options = VocoderOptions {
+            sibilance: 1.,
+            ..VocoderOptions::default()
+        };
+        let peak = render_vocoder(None, 15_000., options);
+        assert_float_eq!(peak, 1., abs <= 0.05);
+
+        let peak = render_vocoder(None, 15_000., VocoderOptions::default());
+        assert_float_eq!(peak, 0., abs <= 1e-3);
+    }
+}
@@ -1,9 +1,8 @@
 use std::any::Any;
 
-use rubato::{FftFixedInOut, Resampler as _};
-
 use crate::{
     context::{AudioContextRegistration, BaseAudioContext},
+    dsp::Oversampler,
     render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope},
     RENDER_QUANTUM_SIZE,
 };
@@ -11,7 +10,10 @@ use crate::{
 use super::{AudioNode, AudioNodeOptions, ChannelConfig};
 
 /// enumerates the oversampling rate available for `WaveShaperNode`
+///
+/// More rates may be added in a minor release, so matching on this enum requires a wildcard arm.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 // the naming comes from the web audio specification
 pub enum OverSampleType {
     /// No oversampling is applied
@@ -20,6 +22,14 @@ pub enum OverSampleType {
     X2,
     /// Oversampled by a factor of 4
     X4,
+    /// Oversampled by a factor of 8
+    ///
+    /// This variant is not part of the Web Audio API specification.
+    X8,
+    /// Oversampled by a factor of 16
+    ///
+    /// This variant is not part of the Web Audio API specification.
+    X16,
 }
 
 impl Default for OverSampleType {
@@ -34,6 +44,8 @@ impl From<u32> for OverSampleType {
             0 => OverSampleType::None,
             1 => OverSampleType::X2,
             2 => OverSampleType::X4,
+            3 => OverSampleType::X8,
+            4 => OverSampleType::X16,
             _ => unreachable!(),
         }
     }
@@ -64,6 +76,90 @@ impl Default for WaveShaperOptions {
     }
 }
 
+/// Evaluate `f` into a distortion curve of `resolution` points
+///
+/// The points are evenly spread over the [-1, 1] input range, as expected by the
+/// [`WaveShaperNode`].
+///
+/// # Panics
+///
+/// Panics if `resolution` is less than 2
+fn curve_from_fn<F: Fn(f32) -> f32>(resolution: usize, f: F) -> Vec<f32> {
+    assert!(
+        resolution >= 2,
+        "RangeError - curve resolution should be at least 2, got {}",
+        resolution
+    );
+
+    let step = 2. / (resolution - 1) as f64;
+    (0..resolution)
+        .map(|i| f((i as f64 * step - 1.) as f32))
+        .collect()
+}
+
+/// Built-in distortion curves for the [`WaveShaperNode`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::ShapingCurve;
+///
+/// let context = AudioContext::default();
+///
+/// let mut shaper = context.create_wave_shaper();
+/// shaper.set_curve(ShapingCurve::Tanh { drive: 4. }.to_vec(2048));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShapingCurve {
+    /// Hyperbolic tangent saturation, normalized so that full scale input maps to full scale
+    /// output. Higher `drive` values yield more distortion.
+    Tanh { drive: f32 },
+    /// Cubic soft clipping, `1.5x - 0.5x^3`
+    SoftClip,
+    /// Clip the signal at `threshold`
+    HardClip { threshold: f32 },
+    /// Chebyshev polynomial of the first kind, a full scale sine input yields its harmonic of
+    /// the given `order`
+    Chebyshev { order: u32 },
+}
+
+impl ShapingCurve {
+    /// Evaluate the curve at `x`, for `x` in the [-1, 1] range
+    #[must_use]
+    pub fn evaluate(&self, x: f32) -> f32 {
+        match *self {
+            Self::Tanh { drive } => (drive * x).tanh() / drive.tanh(),
+            Self::SoftClip => 1.5 * x - 0.5 * x * x * x,
+            Self::HardClip { threshold } => x.max(-threshold).min(threshold),
+            Self::Chebyshev { order } => {
+                // T(0) = 1, T(1) = x, T(n + 1) = 2x T(n) - T(n - 1)
+                let (mut previous, mut current) = (1., x);
+                if order == 0 {
+                    return previous;
+                }
+                for _ in 1..order {
+                    let next = 2. * x * current - previous;
+                    previous = current;
+                    current = next;
+                }
+                current
+            }
+        }
+    }
+
+    /// Evaluate the curve into a table of `resolution` points, to be used with
+    /// [`WaveShaperNode::set_curve`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if `resolution` is less than 2
+    #[must_use]
+    pub fn to_vec(&self, resolution: usize) -> Vec<f32> {
+        curve_from_fn(resolution, |x| self.evaluate(x))
+    }
+}
+
 /// `WaveShaperNode` allows to apply non-linear distortion effect on a audio
 /// signal. Arbitrary non-linear shaping curves may be specified.
 ///
@@ -163,12 +259,7 @@ impl WaveShaperNode {
         } = options;
 
         let mut node = context.base().register(move |registration| {
-            let sample_rate = context.sample_rate() as usize;
-
-            let renderer = WaveShaperRenderer::new(RendererConfig {
-                oversample,
-                sample_rate,
-            });
+            let renderer = WaveShaperRenderer::new(RendererConfig { oversample });
 
             let node = Self {
                 registration,
@@ -216,12 +307,40 @@ impl WaveShaperNode {
         self.registration.post_message(Some(clone));
     }
 
+    /// Set the distortion curve of this node by evaluating `f` at `resolution` points
+    ///
+    /// The points are evenly spread over the [-1, 1] input range. Higher resolutions yield a
+    /// more accurate curve at the expense of memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a curve has already been given to the source, or if `resolution` is less than 2
+    pub fn set_curve_fn<F: Fn(f32) -> f32>(&mut self, resolution: usize, f: F) {
+        self.set_curve(curve_from_fn(resolution, f));
+    }
+
     /// Returns the `oversample` faactor of this node
     #[must_use]
     pub fn oversample(&self) -> OverSampleType {
         self.oversample
     }
 
+    /// The latency in seconds introduced by the oversampling filters
+    ///
+    /// The up- and downsampling filters delay the signal by 31 frames when oversampling is
+    /// enabled, regardless of the oversampling factor. No latency is introduced when no
+    /// oversampling is applied, or when no curve is set.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    #[must_use]
+    pub fn latency(&self) -> f64 {
+        if self.oversample == OverSampleType::None || self.curve.is_none() {
+            return 0.;
+        }
+
+        Oversampler::LATENCY as f64 / self.registration.context().sample_rate() as f64
+    }
+
     /// set the `oversample` factor of this node
     ///
     /// # Arguments
@@ -233,130 +352,11 @@ impl WaveShaperNode {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct ResamplerConfig {
-    channels: usize,
-    chunk_size_in: usize,
-    sample_rate_in: usize,
-    sample_rate_out: usize,
-}
-
-impl ResamplerConfig {
-    fn upsample_x2(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE;
-        let sample_rate_in = sample_rate;
-        let sample_rate_out = sample_rate * 2;
-        Self {
-            channels,
-            chunk_size_in,
-            sample_rate_in,
-            sample_rate_out,
-        }
-    }
-
-    fn upsample_x4(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE;
-        let sample_rate_in = sample_rate;
-        let sample_rate_out = sample_rate * 4;
-        Self {
-            channels,
-            chunk_size_in,
-            sample_rate_in,
-            sample_rate_out,
-        }
-    }
-
-    fn downsample_x2(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE * 2;
-        let sample_rate_in = sample_rate * 2;
-        let sample_rate_out = sample_rate;
-        Self {
-            channels,
-            chunk_size_in,
-            sample_rate_in,
-            sample_rate_out,
-        }
-    }
-
-    fn downsample_x4(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE * 4;
-        let sample_rate_in = sample_rate * 4;
-        let sample_rate_out = sample_rate;
-        Self {
-            channels,
-            chunk_size_in,
-            sample_rate_in,
-            sample_rate_out,
-        }
-    }
-}
-
-struct Resampler {
-    config: ResamplerConfig,
-    processor: FftFixedInOut<f32>,
-    samples_out: Vec<Vec<f32>>,
-}
-
-impl Resampler {
-    fn new(config: ResamplerConfig) -> Self {
-        let ResamplerConfig {
-            channels,
-            chunk_size_in,
-            sample_rate_in,
-            sample_rate_out,
-        } = &config;
-
-        let processor =
-            FftFixedInOut::new(*sample_rate_in, *sample_rate_out, *chunk_size_in, *channels)
-                .unwrap();
-
-        let samples_out = processor.output_buffer_allocate(true);
-
-        Self {
-            config,
-            processor,
-            samples_out,
-        }
-    }
-
-    fn process<T>(&mut self, samples_in: &[T])
-    where
-        T: AsRef<[f32]>,
-    {
-        debug_assert_eq!(self.config.channels, samples_in.len());
-        // Processing the output from another resampler directly as input requires this assumption.
-        debug_assert!(samples_in
-            .iter()
-            .all(|channel| channel.as_ref().len() == self.processor.input_frames_next()));
-        let (in_len, out_len) = self
-            .processor
-            .process_into_buffer(samples_in, &mut self.samples_out[..], None)
-            .unwrap();
-        // All input samples must have been consumed.
-        debug_assert_eq!(in_len, samples_in[0].as_ref().len());
-        // All output samples must have been initialized.
-        debug_assert!(self
-            .samples_out
-            .iter()
-            .all(|channel| channel.len() == out_len));
-    }
-
-    fn samples_out(&self) -> &[Vec<f32>] {
-        &self.samples_out[..]
-    }
-
-    fn samples_out_mut(&mut self) -> &mut [Vec<f32>] {
-        &mut self.samples_out[..]
-    }
-}
-
 /// Helper struct which regroups all parameters
 /// required to build `WaveShaperRenderer`
 struct RendererConfig {
     /// oversample factor
     oversample: OverSampleType,
-    /// Sample rate (equals to audio context sample rate)
-    sample_rate: usize,
 }
 
 /// `WaveShaperRenderer` represents the rendering part of `WaveShaperNode`
@@ -365,23 +365,13 @@ struct WaveShaperRenderer {
     oversample: OverSampleType,
     /// distortion curve
     curve: Option<Vec<f32>>,
-    /// Sample rate (equals to audio context sample rate)
-    sample_rate: usize,
-    /// Number of channels used to build the up/down sampler X2
-    channels_x2: usize,
-    /// Number of channels used to build the up/down sampler X4
-    channels_x4: usize,
-    // up sampler configured to multiply by 2 the input signal
-    upsampler_x2: Resampler,
-    // up sampler configured to multiply by 4 the input signal
-    upsampler_x4: Resampler,
-    // down sampler configured to divide by 2 the upsampled signal
-    downsampler_x2: Resampler,
-    // down sampler configured to divide by 4 the upsampled signal
-    downsampler_x4: Resampler,
-    // check if silence can be propagated, i.e. if curve if None or if
-    // it's output value for zero signal is zero (i.e. < 1e-9)
+    /// oversamplers for the X2, X4, X8 and X16 factors
+    oversamplers: [Oversampler; 4],
+    /// check if silence can be propagated, i.e. if curve if None or if
+    /// it's output value for zero signal is zero (i.e. < 1e-9)
     can_propagate_silence: bool,
+    /// the oversampling filters still contain signal to flush
+    tail_pending: bool,
 }
 
 impl AudioProcessor for WaveShaperRenderer {
@@ -396,98 +386,55 @@ impl AudioProcessor for WaveShaperRenderer {
         let input = &inputs[0];
         let output = &mut outputs[0];
 
-        if input.is_silent() && self.can_propagate_silence {
+        if input.is_silent() && self.can_propagate_silence && !self.tail_pending {
             output.make_silent();
             return false;
         }
 
         *output = input.clone();
+        let tail_pending = std::mem::take(&mut self.tail_pending);
 
-        if let Some(curve) = &self.curve {
-            match self.oversample {
-                OverSampleType::None => {
-                    output.modify_channels(|channel| {
-                        channel.iter_mut().for_each(|o| *o = apply_curve(curve, *o));
-                    });
-                }
-                OverSampleType::X2 => {
-                    let channels = output.channels();
-
-                    // recreate up/down sampler if number of channels changed
-                    if channels.len() != self.channels_x2 {
-                        self.channels_x2 = channels.len();
-
-                        self.upsampler_x2 = Resampler::new(ResamplerConfig::upsample_x2(
-                            self.channels_x2,
-                            self.sample_rate,
-                        ));
-
-                        self.downsampler_x2 = Resampler::new(ResamplerConfig::downsample_x2(
-                            self.channels_x2,
-                            self.sample_rate,
-                        ));
-                    }
-
-                    self.upsampler_x2.process(channels);
-                    for channel in self.upsampler_x2.samples_out_mut().iter_mut() {
-                        for s in channel.iter_mut() {
-                            *s = apply_curve(curve, *s);
-                        }
-                    }
-
-                    self.downsampler_x2.process(self.upsampler_x2.samples_out());
-
-                    for (processed, output) in self
-                        .downsampler_x2
-                        .samples_out()
-                        .iter()
-                        .zip(output.channels_mut())
-                    {
-                        output.copy_from_slice(&processed[..]);
-                    }
-                }
-                OverSampleType::X4 => {
-                    let channels = output.channels();
-
-                    // recreate up/down sampler if number of channels changed
-                    if channels.len() != self.channels_x4 {
-                        self.channels_x4 = channels.len();
-
-                        self.upsampler_x4 = Resampler::new(ResamplerConfig::upsample_x4(
-                            self.channels_x4,
-                            self.sample_rate,
-                        ));
-
-                        self.downsampler_x4 = Resampler::new(ResamplerConfig::downsample_x4(
-                            self.channels_x4,
-                            self.sample_rate,
-                        ));
-                    }
-
-                    self.upsampler_x4.process(channels);
-
-                    for channel in self.upsampler_x4.samples_out_mut().iter_mut() {
-                        for s in channel.iter_mut() {
-                            *s = apply_curve(curve, *s);
-                        }
-                    }
-
-                    self.downsampler_x4.process(self.upsampler_x4.samples_out());
-
-                    for (processed, output) in self
-                        .downsampler_x4
-                        .samples_out()
-                        .iter()
-                        .zip(output.channels_mut())
-                    {
-                        output.copy_from_slice(&processed[..]);
-                    }
-                }
+        let curve = match &self.curve {
+            Some(curve) => curve,
+            None => return false,
+        };
+
+        let oversampler = match self.oversample {
+            OverSampleType::None => {
+                output.modify_channels(|channel| {
+                    channel.iter_mut().for_each(|o| *o = apply_curve(curve, *o));
+                });
+
+                return false;
             }
+            OverSampleType::X2 => &mut self.oversamplers[0],
+            OverSampleType::X4 => &mut self.oversamplers[1],
+            OverSampleType::X8 => &mut self.oversamplers[2],
+            OverSampleType::X16 => &mut self.oversamplers[3],
+        };
+
+        // flush the filters with the previous number of channels during tail time
+        if input.is_silent() && tail_pending {
+            output.set_number_of_channels(oversampler.number_of_channels());
+        } else if output.number_of_channels() != oversampler.number_of_channels() {
+            oversampler.set_number_of_channels(output.number_of_channels());
         }
 
-        // @tbc - rubato::FftFixedInOut doesn't seem to introduce any latency
-        false
+        output
+            .channels_mut()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, channel)| {
+                oversampler.process(index, channel, |oversampled| {
+                    oversampled
+                        .iter_mut()
+                        .for_each(|s| *s = apply_curve(curve, *s));
+                });
+            });
+
+        // the filter delay is shorter than a render quantum, a single quantum flushes the tail
+        self.tail_pending = !input.is_silent();
+        self.tail_pending
     }
 
     fn onmessage(&mut self, msg: &mut dyn Any) {
@@ -521,37 +468,15 @@ impl AudioProcessor for WaveShaperRenderer {
 
 impl WaveShaperRenderer {
     /// returns an `WaveShaperRenderer` instance
-    #[allow(clippy::missing_const_for_fn)]
     fn new(config: RendererConfig) -> Self {
-        let RendererConfig {
-            sample_rate,
-            oversample,
-        } = config;
-
-        let channels_x2 = 1;
-        let channels_x4 = 1;
-
-        let upsampler_x2 = Resampler::new(ResamplerConfig::upsample_x2(channels_x2, sample_rate));
-
-        let downsampler_x2 =
-            Resampler::new(ResamplerConfig::downsample_x2(channels_x2, sample_rate));
-
-        let upsampler_x4 = Resampler::new(ResamplerConfig::upsample_x4(channels_x2, sample_rate));
-
-        let downsampler_x4 =
-            Resampler::new(ResamplerConfig::downsample_x4(channels_x2, sample_rate));
+        let RendererConfig { oversample } = config;
 
         Self {
             oversample,
             curve: None,
-            sample_rate,
-            channels_x2,
-            channels_x4,
-            upsampler_x2,
-            upsampler_x4,
-            downsampler_x2,
-            downsampler_x4,
+            oversamplers: [2, 4, 8, 16].map(Oversampler::new),
             can_propagate_silence: true,
+            tail_pending: false,
         }
     }
 }
@@ -743,4 +668,117 @@ mod tests {
 
         assert_float_eq!(channel[..], expected[..], abs_all <= 0.);
     }
+
+    #[test]
+    fn test_set_curve_fn() {
+        let context = OfflineAudioContext::new(1, LENGTH, 44_100.);
+
+        let mut shaper = context.create_wave_shaper();
+        shaper.set_curve_fn(5, |x| x * 2.);
+        assert_float_eq!(
+            shaper.curve().unwrap()[..],
+            [-2., -1., 0., 1., 2.][..],
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_curve_fn_resolution() {
+        let context = OfflineAudioContext::new(1, LENGTH, 44_100.);
+
+        let mut shaper = context.create_wave_shaper();
+        shaper.set_curve_fn(1, |x| x);
+    }
+
+    #[test]
+    fn test_shaping_curves() {
+        let curve = ShapingCurve::Tanh { drive: 3. }.to_vec(3);
+        assert_float_eq!(curve[..], [-1., 0., 1.][..], abs_all <= 1e-6);
+
+        let curve = ShapingCurve::SoftClip.to_vec(5);
+        assert_float_eq!(
+            curve[..],
+            [-1., -0.6875, 0., 0.6875, 1.][..],
+            abs_all <= 1e-6
+        );
+
+        let curve = ShapingCurve::HardClip { threshold: 0.5 }.to_vec(5);
+        assert_float_eq!(curve[..], [-0.5, -0.5, 0., 0.5, 0.5][..], abs_all <= 0.);
+
+        // T2(x) = 2x^2 - 1, T3(x) = 4x^3 - 3x
+        let curve = ShapingCurve::Chebyshev { order: 2 }.to_vec(5);
+        assert_float_eq!(curve[..], [1., -0.5, -1., -0.5, 1.][..], abs_all <= 1e-6);
+        let curve = ShapingCurve::Chebyshev { order: 3 }.to_vec(5);
+        assert_float_eq!(curve[..], [-1., 1., 0., -1., 1.][..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_chebyshev_harmonic() {
+        // a full scale sine shaped by T2 yields the second harmonic: cos(2 * acos(x))
+        let curve = ShapingCurve::Chebyshev { order: 2 };
+        for i in 0..16 {
+            let phase = i as f32 / 16. * std::f32::consts::PI;
+            assert_float_eq!(curve.evaluate(phase.cos()), (2. * phase).cos(), abs <= 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_latency() {
+        let context = OfflineAudioContext::new(1, LENGTH, 44_100.);
+
+        let mut shaper = context.create_wave_shaper();
+        shaper.set_oversample(OverSampleType::X4);
+        assert_float_eq!(shaper.latency(), 0., abs <= 0.); // no curve
+
+        shaper.set_curve(vec![-1., 1.]);
+        assert_float_eq!(shaper.latency(), 31. / 44_100., abs <= 0.);
+
+        shaper.set_oversample(OverSampleType::None);
+        assert_float_eq!(shaper.latency(), 0., abs <= 0.);
+    }
+
+    #[test]
+    fn test_oversampled_identity() {
+        let sample_rate = 44_100.;
+        let length = 4 * RENDER_QUANTUM_SIZE;
+        let latency = 31;
+
+        let input: Vec<f32> = (0..length)
+            .map(|i| (2. * std::f32::consts::PI * 1000. * i as f32 / sample_rate).sin())
+            .collect();
+
+        for oversample in [
+            OverSampleType::X2,
+            OverSampleType::X4,
+            OverSampleType::X8,
+            OverSampleType::X16,
+        ] {
+            let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+            let mut shaper = context.create_wave_shaper();
+            shaper.set_curve(vec![-1., 1.]);
+            shaper.set_oversample(oversample);
+            shaper.connect(&context.destination());
+
+            // input stops halfway, the output tail must be flushed
+            let mut buffer = context.create_buffer(1, length / 2, sample_rate);
+            buffer.copy_to_channel(&input[..length / 2], 0);
+
+            let mut src = context.create_buffer_source();
+            src.set_buffer(buffer);
+            src.connect(&shaper);
+            src.start();
+
+            let result = context.start_rendering_sync();
+            let output = result.get_channel_data(0);
+
+            assert_float_eq!(output[..latency], [0.; 31][..], abs_all <= 1e-3);
+            assert_float_eq!(
+                output[latency..latency + length / 2],
+                input[..length / 2],
+                abs_all <= 1e-3
+            );
+        }
+    }
 }
@@ -0,0 +1,254 @@
+//! Offline rendering of audio graph descriptions to WAV files
+//!
+//! The graph is described by a [`GraphDescription`], e.g. deserialized from a preset file with
+//! the `serde` feature, or exported from another context with
+//! [`BaseAudioContext::export_graph`]. It is rendered with an [`OfflineAudioContext`], so the
+//! crate can be driven as an audio processing engine by other programs.
+//!
+//! ```no_run
+//! use web_audio_api::offline_render::{render_graph_to_file, OfflineRenderOptions};
+//! # use web_audio_api::context::GraphDescription;
+//! # let description = GraphDescription::default();
+//!
+//! let mut options = OfflineRenderOptions::default();
+//! options.duration = 10.;
+//! options
+//!     .assets
+//!     .insert(String::from("drums"), "samples/sample.wav".into());
+//!
+//! render_graph_to_file(&description, &options, "out.wav", |progress| {
+//!     println!("{:.0}%", progress * 100.);
+//! })
+//! .unwrap();
+//! ```
+//!
+//! The functions and their options are also re-exported at the crate root.
+//!
+//! This module is not part of the Web Audio API specification.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::context::{
+    BaseAudioContext, GraphDescription, GraphNode, OfflineAudioContext, WavFormat,
+};
+use crate::node::{AudioNode, AudioScheduledSourceNode};
+use crate::{assert_valid_time_value, RENDER_QUANTUM_SIZE};
+
+/// Options of [`render_graph`]
+///
+/// This type is not part of the Web Audio API specification.
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct OfflineRenderOptions {
+    /// Number of channels of the rendered file, defaults to 2
+    pub number_of_channels: usize,
+    /// Sample rate of the rendered file, defaults to 48 kHz
+    pub sample_rate: f32,
+    /// Duration of the rendered file in seconds, defaults to one second
+    pub duration: f64,
+    /// Sample format of the rendered file
+    pub format: WavFormat,
+    /// The audio files played by the `AudioBufferSourceNode`s of the graph, by label of the node
+    pub assets: HashMap<String, PathBuf>,
+    /// Number of rendered seconds between two progress reports, defaults to one second
+    pub progress_interval: f64,
+}
+
+impl Default for OfflineRenderOptions {
+    fn default() -> Self {
+        Self {
+            number_of_channels: 2,
+            sample_rate: 48_000.,
+            duration: 1.,
+            format: WavFormat::default(),
+            assets: HashMap::new(),
+            progress_interval: 1.,
+        }
+    }
+}
+
+/// Render the described graph and write it as a WAV file to the given writer
+///
+/// The graph is built with [`BaseAudioContext::build_graph`]. The `AudioBufferSourceNode`s play
+/// the decoded asset of their label, and all the source nodes are started at time zero.
+///
+/// The `progress` callback is called with the rendered fraction of the duration, at every
+/// [`progress_interval`](OfflineRenderOptions::progress_interval) and when the rendering is done.
+///
+/// # Errors
+///
+/// Returns an error if the graph cannot be built, if the asset of an `AudioBufferSourceNode` is
+/// missing or cannot be decoded, or if writing fails.
+///
+/// # Panics
+///
+/// Panics if the duration is negative or not finite, or if the number of channels or the sample
+/// rate is not valid for an [`OfflineAudioContext`].
+pub fn render_graph<W: Write, F: Fn(f64) + Send + Sync + 'static>(
+    description: &GraphDescription,
+    options: &OfflineRenderOptions,
+    writer: W,
+    progress: F,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    assert_valid_time_value(options.duration);
+    let sample_rate = options.sample_rate;
+    let length = (options.duration * f64::from(sample_rate)).round() as usize;
+    let mut context = OfflineAudioContext::new(options.number_of_channels, length, sample_rate);
+
+    let mut graph = context.build_graph(description)?;
+    let ids: Vec<_> = graph.nodes().map(|(id, _)| id).collect();
+    for id in ids {
+        match graph.node_mut(id).unwrap() {
+            GraphNode::AudioBufferSource(src) => {
+                let path = src
+                    .label()
+                    .and_then(|label| options.assets.get(&label))
+                    .ok_or_else(|| {
+                        format!("NotFoundError - No asset for AudioBufferSourceNode {id}")
+                    })?;
+                let buffer = context.decode_audio_data_sync(File::open(path)?)?;
+                src.set_buffer(buffer);
+                src.start();
+            }
+            GraphNode::ConstantSource(src) => src.start(),
+            GraphNode::Oscillator(src) => src.start(),
+            _ => (),
+        }
+    }
+
+    // report the progress from suspensions at the render quantum boundaries
+    let progress = Arc::new(progress);
+    let quanta = options.progress_interval * f64::from(sample_rate) / RENDER_QUANTUM_SIZE as f64;
+    let interval = (quanta.round().max(1.) as usize).saturating_mul(RENDER_QUANTUM_SIZE);
+    for frame in (interval..length).step_by(interval) {
+        let progress = Arc::clone(&progress);
+        let fraction = frame as f64 / length as f64;
+        // the suspension is rounded up to the next quantum, so ask for halfway the previous one
+        let time = (frame - RENDER_QUANTUM_SIZE / 2) as f64 / f64::from(sample_rate);
+        context.suspend_sync(time, move |_| progress(fraction));
+    }
+
+    context.render_to_writer(writer, options.format)?;
+    progress(1.);
+
+    Ok(())
+}
+
+/// Render the described graph to a WAV file at the given path, see [`render_graph`]
+///
+/// This function is not part of the Web Audio API specification.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be created, or for the errors of [`render_graph`].
+///
+/// # Panics
+///
+/// Panics for the invalid options of [`render_graph`].
+pub fn render_graph_to_file<P: AsRef<Path>, F: Fn(f64) + Send + Sync + 'static>(
+    description: &GraphDescription,
+    options: &OfflineRenderOptions,
+    path: P,
+    progress: F,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let file = BufWriter::new(File::create(path)?);
+    render_graph(description, options, file, progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::Mutex;
+
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    /// Render the description to memory and decode the result, with the reported progress
+    fn render(
+        description: &GraphDescription,
+        options: &OfflineRenderOptions,
+    ) -> (crate::AudioBuffer, Vec<f64>) {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reports_clone = Arc::clone(&reports);
+        let mut wav = Vec::new();
+        render_graph(description, options, Cursor::new(&mut wav), move |p| {
+            reports_clone.lock().unwrap().push(p)
+        })
+        .unwrap();
+
+        let context = OfflineAudioContext::new(1, 1, options.sample_rate);
+        let buffer = context.decode_audio_data_sync(Cursor::new(wav)).unwrap();
+        let reports = reports.lock().unwrap().clone();
+        (buffer, reports)
+    }
+
+    #[test]
+    fn test_render_graph() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let src = context.create_constant_source();
+        let gain = context.create_gain();
+        gain.gain().set_value(0.5);
+        src.connect(&gain);
+        gain.connect(&context.destination());
+        let description = context.export_graph();
+
+        let mut options = OfflineRenderOptions::default();
+        options.number_of_channels = 1;
+        options.duration = 0.5;
+        options.progress_interval = 0.1;
+        let (buffer, reports) = render(&description, &options);
+
+        assert_eq!(buffer.number_of_channels(), 1);
+        assert_eq!(buffer.length(), 24_000);
+        assert_float_eq!(
+            buffer.get_channel_data(0)[..],
+            [0.5; 24_000][..],
+            abs_all <= 0.
+        );
+
+        // every 0.1 second, rounded to the render quantum
+        assert_eq!(reports.len(), 5);
+        assert!(reports.windows(2).all(|w| w[0] < w[1]));
+        assert_float_eq!(reports[0], 4864. / 24_000., abs <= 1e-9);
+        assert_eq!(reports[4], 1.);
+    }
+
+    #[test]
+    fn test_render_graph_assets() {
+        let context = OfflineAudioContext::new(2, 128, 44_100.);
+        let src = context.create_buffer_source();
+        src.set_label(String::from("sample"));
+        src.connect(&context.destination());
+        let description = context.export_graph();
+
+        let mut options = OfflineRenderOptions::default();
+        options.sample_rate = 44_100.;
+        options.duration = 0.1;
+        let error =
+            render_graph(&description, &options, Cursor::new(Vec::new()), |_| ()).unwrap_err();
+        assert!(error.to_string().starts_with("NotFoundError"));
+
+        options
+            .assets
+            .insert(String::from("sample"), "samples/sample-44100.wav".into());
+        let (buffer, reports) = render(&description, &options);
+        assert_eq!(reports, [1.]);
+
+        let file = File::open("samples/sample-44100.wav").unwrap();
+        let expected = context.decode_audio_data_sync(file).unwrap();
+        assert_eq!(buffer.number_of_channels(), 2);
+        for channel in 0..2 {
+            assert_float_eq!(
+                buffer.get_channel_data(channel)[..],
+                expected.get_channel_data(channel)[..4410],
+                abs_all <= 0.
+            );
+        }
+    }
+}
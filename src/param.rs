@@ -3,11 +3,11 @@
 use std::any::Any;
 use std::slice::{Iter, IterMut};
 use std::sync::atomic::Ordering;
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
 
 use arrayvec::ArrayVec;
 
-use crate::context::AudioContextRegistration;
+use crate::context::{AudioContextRegistration, ParamDescription};
 use crate::node::{
     AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation,
 };
@@ -121,6 +121,7 @@ fn compute_set_value_curve_sample(
 
 /// Precision of AudioParam value calculation per render quantum
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AutomationRate {
     /// Audio Rate - sampled for each sample-frame of the block
     A,
@@ -160,7 +161,7 @@ enum AudioParamEventType {
     SetValueCurveAtTime,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct AudioParamEvent {
     event_type: AudioParamEventType,
     value: f32,
@@ -180,7 +181,7 @@ pub(crate) struct AudioParamEvent {
 // occurs during the insertion of events)
 // After this point, the queue should be considered sorted and no operations that
 // breaks the ordering should be done.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 struct AudioParamEventTimeline {
     inner: Vec<AudioParamEvent>,
     dirty: bool,
@@ -289,12 +290,49 @@ impl std::fmt::Debug for AudioParam {
 // helper struct to attach / detach to context (for borrow reasons)
 #[derive(Debug, Clone)]
 pub(crate) struct AudioParamInner {
+    name: String,                                // immutable
     default_value: f32,                          // immutable
     min_value: f32,                              // immutable
     max_value: f32,                              // immutable
     automation_rate_constrained: bool,           // effectively immutable
     automation_rate: Arc<Mutex<AutomationRate>>, // shared with clones
     current_value: Arc<AtomicF32>,               // shared with clones and with render thread
+    automation: Arc<Mutex<AutomationReplica>>,   // shared with clones
+}
+
+impl AudioParamInner {
+    /// Describe the current state of the param with the given id
+    pub(crate) fn description(&self, id: u64) -> ParamDescription {
+        ParamDescription {
+            id,
+            value: self.current_value.load(Ordering::Acquire),
+            default_value: self.default_value,
+            min_value: self.min_value,
+            max_value: self.max_value,
+            automation_rate: *self.automation_rate.lock().unwrap(),
+        }
+    }
+}
+
+/// Handle of an [`AudioParam`] that does not keep it alive, see [`AudioParam::downgrade`]
+#[derive(Debug, Clone)]
+pub(crate) struct WeakAudioParam {
+    registration: Weak<AudioContextRegistration>,
+    raw_parts: AudioParamInner,
+}
+
+impl WeakAudioParam {
+    /// The param, if a handle to it is still alive
+    pub(crate) fn upgrade(&self) -> Option<AudioParam> {
+        self.registration.upgrade().map(|registration| AudioParam {
+            registration,
+            raw_parts: self.raw_parts.clone(),
+        })
+    }
+
+    pub(crate) fn raw_parts(&self) -> &AudioParamInner {
+        &self.raw_parts
+    }
 }
 
 impl AudioNode for AudioParam {
@@ -362,6 +400,16 @@ impl AudioParam {
         self.raw_parts.automation_rate_constrained = value;
     }
 
+    /// Name of the `AudioParam`, given by its descriptor, e.g. `"frequency"`
+    ///
+    /// The params of the nodes of this library are named after their attribute in the
+    /// specification, in camel case. See [`AudioNode::audio_param`] to look up a param by name.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    pub fn name(&self) -> &str {
+        &self.raw_parts.name
+    }
+
     pub fn default_value(&self) -> f32 {
         self.raw_parts.default_value
     }
@@ -644,6 +692,14 @@ impl AudioParam {
         raw_parts
     }
 
+    // helper function to describe the param without holding on to its registration
+    pub(crate) fn downgrade(&self) -> WeakAudioParam {
+        WeakAudioParam {
+            registration: Arc::downgrade(&self.registration),
+            raw_parts: self.raw_parts.clone(),
+        }
+    }
+
     // helper function to attach to context (for borrow reasons)
     pub(crate) fn from_raw_parts(
         registration: AudioContextRegistration,
@@ -655,12 +711,113 @@ impl AudioParam {
         }
     }
 
+    /// Returns the value of the `AudioParam` at the given context time, taking the scheduled
+    /// automation events into account
+    ///
+    /// This allows to inspect the automation on the control thread, e.g. to draw a curve that
+    /// will be in effect in the future. Audio signals connected to the `AudioParam` are not taken
+    /// into account. Times before [`BaseAudioContext::current_time`](crate::context::BaseAudioContext::current_time)
+    /// are evaluated on a best effort basis.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn value_at_time(&self, time: f64) -> f32 {
+        self.raw_parts
+            .automation
+            .lock()
+            .unwrap()
+            .value_at_time(time)
+    }
+
     fn send_event(&self, event: AudioParamEvent) -> &Self {
+        let now = self.registration().context().current_time();
+        self.raw_parts
+            .automation
+            .lock()
+            .unwrap()
+            .record(now, event.clone());
+
         self.registration().post_message(event);
         self
     }
 }
 
+/// Number of recorded events after which they are folded into the snapshot of the
+/// [`AutomationReplica`]
+const AUTOMATION_REPLICA_CAPACITY: usize = 32;
+
+/// Control thread replica of the automation timeline
+///
+/// The automation events are consumed on the render thread. The replica records the events along
+/// with the context time at which they were sent, and replays them through its own
+/// `AudioParamProcessor` so the control thread can evaluate the automation at any time with the
+/// exact same logic as the render thread.
+#[derive(Debug)]
+struct AutomationReplica {
+    /// k-rate processor holding the state of the timeline at `time`
+    snapshot: AudioParamProcessor,
+    time: f64,
+    /// events sent after `time`, along with the context time at which they were sent
+    events: Vec<(f64, AudioParamEvent)>,
+}
+
+impl AutomationReplica {
+    fn new(processor: &AudioParamProcessor) -> Self {
+        let mut snapshot = processor.clone();
+        // detach from the value shared with the control and render thread
+        snapshot.current_value = Arc::new(AtomicF32::new(processor.default_value));
+        snapshot.automation_rate = AutomationRate::K;
+
+        Self {
+            snapshot,
+            time: 0.,
+            events: Vec::with_capacity(AUTOMATION_REPLICA_CAPACITY),
+        }
+    }
+
+    fn record(&mut self, now: f64, event: AudioParamEvent) {
+        self.events.push((now, event));
+
+        // fold all events into the snapshot to prevent unbounded growth
+        if self.events.len() >= AUTOMATION_REPLICA_CAPACITY {
+            let events = std::mem::take(&mut self.events);
+            self.time = Self::replay(&mut self.snapshot, self.time, events, now);
+        }
+    }
+
+    fn value_at_time(&self, time: f64) -> f32 {
+        let mut processor = self.snapshot.clone();
+        let events = self
+            .events
+            .iter()
+            .map(|(sent, event)| (*sent, event.clone()));
+        Self::replay(&mut processor, self.time, events, time);
+
+        processor
+            .intrinsic_value
+            .clamp(processor.min_value, processor.max_value)
+    }
+
+    /// Feed the events to the processor as if they arrived on the render thread at the time they
+    /// were sent, and advance the processor up to `until`
+    fn replay<I>(processor: &mut AudioParamProcessor, mut now: f64, events: I, until: f64) -> f64
+    where
+        I: IntoIterator<Item = (f64, AudioParamEvent)>,
+    {
+        for (sent, event) in events {
+            let sent = sent.min(until);
+            if sent > now {
+                processor.compute_buffer(now, sent - now, 1);
+                now = sent;
+            }
+            processor.handle_incoming_event(event);
+        }
+
+        processor.compute_buffer(now, (until - now).max(0.), 1);
+        now.max(until)
+    }
+}
+
 struct BlockInfos {
     block_time: f64,
     dt: f64,
@@ -669,7 +826,7 @@ struct BlockInfos {
     next_block_time: f64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct AudioParamProcessor {
     default_value: f32, // immutable
     min_value: f32,     // immutable
@@ -680,6 +837,8 @@ pub(crate) struct AudioParamProcessor {
     event_timeline: AudioParamEventTimeline,
     last_event: Option<AudioParamEvent>,
     buffer: ArrayVec<f32, RENDER_QUANTUM_SIZE>,
+    #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+    invariant_violated: bool,
 }
 
 impl AudioProcessor for AudioParamProcessor {
@@ -698,6 +857,24 @@ impl AudioProcessor for AudioParamProcessor {
         self.compute_intrinsic_values(scope.current_time, period, RENDER_QUANTUM_SIZE);
         self.mix_to_output(input, output);
 
+        #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+        if !self.invariant_violated {
+            let values = output.channel_data(0);
+            let values = if output.single_valued() {
+                &values[..1]
+            } else {
+                &values[..]
+            };
+            if let Some(message) = crate::render::invariants::check_param_values(
+                values,
+                self.min_value,
+                self.max_value,
+            ) {
+                self.invariant_violated = true;
+                scope.report_invariant_violation(self.name(), message);
+            }
+        }
+
         true // has intrinsic value
     }
 
@@ -1606,11 +1783,11 @@ pub(crate) fn audio_param_pair(
     registration: AudioContextRegistration,
 ) -> (AudioParam, AudioParamProcessor) {
     let AudioParamDescriptor {
+        name,
         automation_rate,
         default_value,
         max_value,
         min_value,
-        ..
     } = descriptor;
 
     assert_is_finite(default_value);
@@ -1627,21 +1804,9 @@ pub(crate) fn audio_param_pair(
 
     let current_value = Arc::new(AtomicF32::new(default_value));
 
-    let param = AudioParam {
-        registration: registration.into(),
-        raw_parts: AudioParamInner {
-            default_value,
-            max_value,
-            min_value,
-            automation_rate_constrained: false,
-            automation_rate: Arc::new(Mutex::new(automation_rate)),
-            current_value: Arc::clone(&current_value),
-        },
-    };
-
     let processor = AudioParamProcessor {
         intrinsic_value: default_value,
-        current_value,
+        current_value: Arc::clone(&current_value),
         default_value,
         min_value,
         max_value,
@@ -1649,6 +1814,22 @@ pub(crate) fn audio_param_pair(
         event_timeline: AudioParamEventTimeline::new(),
         last_event: None,
         buffer: ArrayVec::new(),
+        #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+        invariant_violated: false,
+    };
+
+    let param = AudioParam {
+        registration: registration.into(),
+        raw_parts: AudioParamInner {
+            name,
+            default_value,
+            max_value,
+            min_value,
+            automation_rate_constrained: false,
+            automation_rate: Arc::new(Mutex::new(automation_rate)),
+            current_value,
+            automation: Arc::new(Mutex::new(AutomationReplica::new(&processor))),
+        },
     };
 
     (param, processor)
@@ -3543,4 +3724,43 @@ mod tests {
 
         assert_float_eq!(output.channel_data(0)[..], &expected[..], abs_all <= 0.);
     }
+
+    #[test]
+    fn test_value_at_time() {
+        let context = OfflineAudioContext::new(1, 1, 48_000.);
+        let gain = context.create_gain();
+        let param = gain.gain();
+
+        param.set_value_at_time(0., 0.);
+        param.linear_ramp_to_value_at_time(1., 1.);
+        param.set_target_at_time(0., 2., 0.5);
+
+        assert_float_eq!(param.value_at_time(0.25), 0.25, abs <= 1e-6);
+        assert_float_eq!(param.value_at_time(0.5), 0.5, abs <= 1e-6);
+        assert_float_eq!(param.value_at_time(1.5), 1., abs <= 0.);
+        assert_float_eq!(param.value_at_time(2.5), (-1_f32).exp(), abs <= 1e-6);
+
+        // the current value is not affected
+        assert_float_eq!(param.value(), 1., abs <= 0.);
+
+        // cancelled events are taken into account
+        param.cancel_scheduled_values(1.);
+        assert_float_eq!(param.value_at_time(2.5), 0., abs <= 0.);
+    }
+
+    #[test]
+    fn test_value_at_time_many_events() {
+        let context = OfflineAudioContext::new(1, 1, 48_000.);
+        let gain = context.create_gain();
+        let param = gain.gain();
+
+        // exceed the replica capacity so the events are folded into the snapshot
+        for i in 0..(AUTOMATION_REPLICA_CAPACITY * 2 + 3) {
+            param.set_value_at_time(i as f32, 0.1 + i as f64 * 0.1);
+        }
+
+        assert_float_eq!(param.value_at_time(0.05), 1., abs <= 0.);
+        assert_float_eq!(param.value_at_time(1.05), 9., abs <= 0.);
+        assert_float_eq!(param.value_at_time(100.), 66., abs <= 0.);
+    }
 }
@@ -0,0 +1,160 @@
+//! Profiling of the render time of the audio nodes
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use arc_swap::ArcSwapOption;
+
+use crate::context::AudioNodeId;
+
+/// Label and render time statistics of a single node, shared between the control and render
+/// thread
+#[derive(Debug, Default)]
+pub(crate) struct ProcessorStats {
+    label: ArcSwapOption<String>,
+    calls: AtomicU64,
+    total_nanos: AtomicU64,
+    max_nanos: AtomicU64,
+}
+
+impl ProcessorStats {
+    /// Record the duration of a `process` call, called from the render thread
+    pub fn record(&self, elapsed: Duration) {
+        let nanos = elapsed.as_nanos() as u64;
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.total_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.max_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    /// Label assigned to the node with [`AudioNode::set_label`](crate::node::AudioNode::set_label)
+    pub fn label(&self) -> Option<Arc<String>> {
+        self.label.load_full()
+    }
+
+    fn reset(&self) {
+        self.calls.store(0, Ordering::Relaxed);
+        self.total_nanos.store(0, Ordering::Relaxed);
+        self.max_nanos.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Render time statistics of an audio node, see
+/// [`BaseAudioContext::profile`](crate::context::BaseAudioContext::profile)
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct NodeProfile {
+    /// Label of the node, see [`AudioNode::set_label`](crate::node::AudioNode::set_label)
+    pub label: Option<String>,
+    /// Name of the processor rendering the node
+    pub processor: &'static str,
+    /// Number of rendered quanta
+    pub calls: u64,
+    /// Total render time
+    pub total: Duration,
+    /// Average render time of a quantum
+    pub average: Duration,
+    /// Maximum render time of a quantum
+    pub max: Duration,
+}
+
+/// Control thread side of the profiler, keeps track of the statistics of all the nodes
+#[derive(Debug, Default)]
+pub(crate) struct Profiler {
+    nodes: Mutex<Vec<ProfiledNode>>,
+}
+
+/// Node tracked by the profiler
+#[derive(Debug)]
+struct ProfiledNode {
+    id: AudioNodeId,
+    processor: &'static str,
+    stats: Arc<ProcessorStats>,
+}
+
+impl ProfiledNode {
+    /// Indicates if the render thread has dropped the node
+    fn is_dropped(&self) -> bool {
+        Arc::strong_count(&self.stats) == 1
+    }
+}
+
+impl Profiler {
+    /// Create the statistics of a new node, to be shipped to the render thread along with its
+    /// processor
+    pub fn add_node(&self, id: AudioNodeId, processor: &'static str) -> Arc<ProcessorStats> {
+        let stats = Arc::new(ProcessorStats::default());
+        let mut nodes = self.nodes.lock().unwrap();
+
+        // the render thread has dropped the other nodes, possibly with the same id
+        nodes.retain(|node| !node.is_dropped());
+        nodes.push(ProfiledNode {
+            id,
+            processor,
+            stats: Arc::clone(&stats),
+        });
+
+        stats
+    }
+
+    /// Label of the given node
+    pub fn label(&self, id: AudioNodeId) -> Option<String> {
+        self.nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|node| node.id == id && !node.is_dropped())
+            .and_then(|node| node.stats.label())
+            .map(|label| label.as_ref().clone())
+    }
+
+    /// Assign a label to the given node, a no-op if the node is no longer rendered
+    pub fn set_label(&self, id: AudioNodeId, label: String) {
+        if let Some(node) = self
+            .nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|node| node.id == id && !node.is_dropped())
+        {
+            node.stats.label.store(Some(Arc::new(label)));
+        }
+    }
+
+    /// Clear the statistics of all nodes
+    pub fn reset(&self) {
+        self.nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .for_each(|node| node.stats.reset());
+    }
+
+    /// Snapshot of the statistics of the nodes alive in the render thread that have rendered at
+    /// least one quantum, most expensive first
+    pub fn snapshot(&self) -> Vec<NodeProfile> {
+        let mut nodes = self.nodes.lock().unwrap();
+        nodes.retain(|node| !node.is_dropped());
+
+        let mut profile: Vec<_> = nodes
+            .iter()
+            .filter(|node| node.stats.calls.load(Ordering::Relaxed) > 0)
+            .map(|node| {
+                let stats = &node.stats;
+                let calls = stats.calls.load(Ordering::Relaxed);
+                let total_nanos = stats.total_nanos.load(Ordering::Relaxed);
+                NodeProfile {
+                    label: stats.label().map(|label| label.as_ref().clone()),
+                    processor: node.processor,
+                    calls,
+                    total: Duration::from_nanos(total_nanos),
+                    average: Duration::from_nanos(total_nanos.checked_div(calls).unwrap_or(0)),
+                    max: Duration::from_nanos(stats.max_nanos.load(Ordering::Relaxed)),
+                }
+            })
+            .collect();
+
+        profile.sort_by(|a, b| b.total.cmp(&a.total));
+        profile
+    }
+}
@@ -0,0 +1,139 @@
+//! Seeded pseudo random number generation
+
+/// Seeded pseudo random number generator, suitable for use in the render thread
+///
+/// Generative patches (noise sources, granular jitter, humanization of timing and velocity...)
+/// can be reproduced exactly by using the same seed. The generator does not allocate nor lock, so
+/// it can be owned by an [`AudioWorkletProcessor`](crate::worklet::AudioWorkletProcessor) or
+/// any other render thread code.
+///
+/// The implementation is based on SplitMix64, which is fast and has good statistical quality. It
+/// is not suitable for cryptographic purposes.
+///
+/// # Usage
+///
+/// ```
+/// use web_audio_api::SeededRng;
+///
+/// let mut a = SeededRng::new(42);
+/// let mut b = SeededRng::new(42);
+///
+/// // white noise in the [-1, 1) range
+/// let noise: Vec<f32> = (0..128).map(|_| a.next_bipolar()).collect();
+/// let other: Vec<f32> = (0..128).map(|_| b.next_bipolar()).collect();
+///
+/// assert_eq!(noise, other);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Create a new generator from the given seed
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Create an independent generator, e.g. to give each voice of a patch its own stream
+    ///
+    /// The derived generator is fully determined by the state of this generator, so forking is
+    /// reproducible as well.
+    pub fn fork(&mut self) -> Self {
+        Self::new(self.next_u64())
+    }
+
+    /// Returns the next random `u64`
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns the next random `u32`
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Returns the next random `f32` in the [0, 1) range
+    pub fn next_f32(&mut self) -> f32 {
+        // use the 24 most significant bits to fill the mantissa
+        (self.next_u64() >> 40) as f32 / (1_u32 << 24) as f32
+    }
+
+    /// Returns the next random `f64` in the [0, 1) range
+    pub fn next_f64(&mut self) -> f64 {
+        // use the 53 most significant bits to fill the mantissa
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// Returns the next random `f32` in the [-1, 1) range, e.g. for white noise
+    pub fn next_bipolar(&mut self) -> f32 {
+        self.next_f32() * 2. - 1.
+    }
+
+    /// Returns the next random `f32` in the [min, max) range
+    pub fn next_range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.next_f32() * (max - min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = SeededRng::new(1);
+        let mut b = SeededRng::new(1);
+        let mut c = SeededRng::new(2);
+
+        let a: Vec<_> = (0..100).map(|_| a.next_u64()).collect();
+        let b: Vec<_> = (0..100).map(|_| b.next_u64()).collect();
+        let c: Vec<_> = (0..100).map(|_| c.next_u64()).collect();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut a = SeededRng::new(1);
+        let mut b = SeededRng::new(1);
+
+        let mut fork_a = a.fork();
+        let mut fork_b = b.fork();
+        assert_eq!(fork_a.next_u64(), fork_b.next_u64());
+
+        // the fork yields a different stream than its parent
+        assert_ne!(fork_a.next_u64(), a.next_u64());
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = SeededRng::new(0);
+
+        let mut min = f32::MAX;
+        let mut max = f32::MIN;
+
+        for _ in 0..10_000 {
+            let v = rng.next_f32();
+            assert!((0. ..1.).contains(&v));
+
+            let v = rng.next_f64();
+            assert!((0. ..1.).contains(&v));
+
+            let v = rng.next_range(-3., 5.);
+            assert!((-3. ..5.).contains(&v));
+
+            let v = rng.next_bipolar();
+            min = min.min(v);
+            max = max.max(v);
+        }
+
+        assert!(min >= -1. && min < -0.99);
+        assert!(max < 1. && max > 0.99);
+    }
+}
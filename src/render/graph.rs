@@ -4,16 +4,24 @@
 mod test;
 
 use std::any::Any;
-use std::cell::RefCell;
+use std::cell::Cell;
 use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::Instant;
 
-use crate::context::AudioNodeId;
+use crate::context::{AudioNodeId, DESTINATION_NODE_ID};
 use smallvec::{smallvec, SmallVec};
 
-use super::{Alloc, AudioParamValues, AudioProcessor, AudioRenderQuantum, NodeCollection};
+use super::{
+    Alloc, AudioParamValues, AudioProcessor, AudioRenderQuantum, NodeCell, NodeCollection,
+    SharedNodes,
+};
 use crate::node::{ChannelConfigInner, ChannelCountMode, ChannelInterpretation};
+use crate::profiler::ProcessorStats;
 use crate::render::AudioWorkletGlobalScope;
 
+use super::workers::RenderWorkers;
+
 /// Connection between two audio nodes
 struct OutgoingEdge {
     /// index of the current Nodes output port
@@ -24,6 +32,25 @@ struct OutgoingEdge {
     other_index: usize,
 }
 
+impl OutgoingEdge {
+    /// The node receiving the signal of this edge of node `from`
+    ///
+    /// The connections to the destination are routed through the inserts of the main bus: the
+    /// other nodes feed the first insert, each insert feeds the next one and the last insert feeds
+    /// the destination.
+    fn target(&self, from: AudioNodeId, main_bus: &[AudioNodeId]) -> AudioNodeId {
+        if self.other_id != DESTINATION_NODE_ID || self.other_index != 0 {
+            return self.other_id;
+        }
+
+        let next = match main_bus.iter().position(|&id| id == from) {
+            Some(index) => main_bus.get(index + 1),
+            None => main_bus.first(),
+        };
+        next.copied().unwrap_or(DESTINATION_NODE_ID)
+    }
+}
+
 impl std::fmt::Debug for OutgoingEdge {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut format = f.debug_struct("OutgoingEdge");
@@ -43,6 +70,8 @@ impl std::fmt::Debug for OutgoingEdge {
 pub struct Node {
     /// AudioNodeId, to be sent back to the control thread when this node is dropped
     reclaim_id: Option<llq::Node<AudioNodeId>>,
+    /// Preallocated slot to ship this node to the garbage collector when it is dropped
+    garbage: Option<llq::Node<Box<dyn Any + Send>>>,
     /// Renderer: converts inputs to outputs
     processor: Box<dyn AudioProcessor>,
     /// Reusable input buffers
@@ -59,17 +88,33 @@ pub struct Node {
     has_inputs_connected: bool,
     /// Indicates if the node can act as a cycle breaker (only DelayNode for now)
     cycle_breaker: bool,
+    /// Indicates if the node shares state with another node without a connection ordering them
+    /// (a DelayNode in a cycle), so it is not rendered in parallel with other nodes
+    render_serially: bool,
+    /// Rendering level, the nodes of a level only depend on the nodes of the previous levels
+    level: usize,
+    /// Indicates if the node can be dropped after rendering the current quantum
+    free_after_quantum: bool,
+    /// Indicates if the processor panicked, the node is muted and its processor no longer runs
+    quarantined: bool,
+    /// Render time statistics, shared with the control thread
+    stats: Option<Arc<ProcessorStats>>,
+    /// Indicates if an invariant violation has already been reported for this node
+    #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+    invariant_violated: bool,
 }
 
 impl std::fmt::Debug for Node {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Node")
             .field("id", &self.reclaim_id.as_deref())
+            .field("label", &self.label())
             .field("processor", &self.processor)
             .field("channel_config", &self.channel_config)
             .field("outgoing_edges", &self.outgoing_edges)
             .field("control_handle_dropped", &self.control_handle_dropped)
             .field("cycle_breaker", &self.cycle_breaker)
+            .field("quarantined", &self.quarantined)
             .finish_non_exhaustive()
     }
 }
@@ -81,6 +126,22 @@ impl Node {
             .process(&self.inputs[..], &mut self.outputs[..], params, scope)
     }
 
+    /// Label assigned to this node on the control thread
+    fn label(&self) -> Option<Arc<String>> {
+        self.stats.as_ref().and_then(|stats| stats.label())
+    }
+
+    /// Mute the node after its processor panicked, the processor is no longer run
+    ///
+    /// The node stays in the graph until its control handle is dropped, so its id and
+    /// connections remain valid for the control thread.
+    fn quarantine(&mut self) {
+        self.quarantined = true;
+        self.outputs
+            .iter_mut()
+            .for_each(AudioRenderQuantum::make_silent);
+    }
+
     /// Determine if this node is done playing and can be removed from the audio graph
     fn can_free(&self, tail_time: bool) -> bool {
         // Only drop when the Control thread has dropped its handle.
@@ -89,6 +150,11 @@ impl Node {
             return false;
         }
 
+        // A quarantined node only outputs silence
+        if self.quarantined {
+            return true;
+        }
+
         // When the nodes has no incoming connections:
         if !self.has_inputs_connected {
             // Drop when the processor reports it won't yield output.
@@ -112,12 +178,27 @@ impl Node {
         false
     }
 
-    /// Get the current buffer for AudioParam values
-    pub fn get_buffer(&self) -> &AudioRenderQuantum {
-        self.outputs.first().unwrap()
+    /// Check the outputs for invariant violations, silence and report the offending outputs
+    #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+    fn check_invariants(&mut self, scope: &AudioWorkletGlobalScope) {
+        if let Some(message) = super::invariants::check_outputs(&self.outputs) {
+            self.outputs
+                .iter_mut()
+                .for_each(AudioRenderQuantum::make_silent);
+
+            if !self.invariant_violated {
+                self.invariant_violated = true;
+                scope.report_invariant_violation(self.processor.name(), message);
+            }
+        }
     }
 }
 
+/// Preallocated slot to ship a dropped node to the garbage collector, see `Node::garbage`
+pub(crate) fn garbage_slot() -> llq::Node<Box<dyn Any + Send>> {
+    llq::Node::new(Box::new(None::<Node>))
+}
+
 /// The audio graph
 pub(crate) struct Graph {
     /// Processing Nodes
@@ -126,6 +207,11 @@ pub(crate) struct Graph {
     alloc: Alloc,
     /// Message channel to notify control thread of reclaimable AudioNodeIds
     reclaim_id_channel: llq::Producer<AudioNodeId>,
+    /// Dropped nodes, to be freed off the render thread
+    garbage: (
+        llq::Producer<Box<dyn Any + Send>>,
+        llq::Consumer<Box<dyn Any + Send>>,
+    ),
     /// Topological ordering of the nodes
     ordered: Vec<AudioNodeId>,
     /// Topological sorting helper
@@ -136,6 +222,14 @@ pub(crate) struct Graph {
     in_cycle: Vec<AudioNodeId>,
     /// Topological sorting helper
     cycle_breakers: Vec<AudioNodeId>,
+    /// Indicates if the render time of the nodes is recorded
+    profiling: bool,
+    /// Worker threads rendering the nodes of a level in parallel, if any
+    workers: Option<RenderWorkers>,
+    /// End index in `ordered` of each rendering level, only determined when there are workers
+    levels: Vec<usize>,
+    /// Nodes processing the input of the destination, in order
+    main_bus: Vec<AudioNodeId>,
 }
 
 impl std::fmt::Debug for Graph {
@@ -143,6 +237,7 @@ impl std::fmt::Debug for Graph {
         f.debug_struct("Graph")
             .field("nodes", &self.nodes)
             .field("ordered", &self.ordered)
+            .field("main_bus", &self.main_bus)
             .finish_non_exhaustive()
     }
 }
@@ -153,11 +248,16 @@ impl Graph {
             nodes: NodeCollection::new(),
             alloc: Alloc::with_capacity(64),
             reclaim_id_channel,
+            garbage: llq::Queue::new().split(),
             ordered: vec![],
             marked: vec![],
             marked_temp: vec![],
             in_cycle: vec![],
             cycle_breakers: vec![],
+            profiling: false,
+            workers: None,
+            levels: vec![],
+            main_bus: vec![],
         }
     }
 
@@ -185,8 +285,9 @@ impl Graph {
 
         self.nodes.insert(
             index,
-            RefCell::new(Node {
+            NodeCell::new(Node {
                 reclaim_id: Some(reclaim_id),
+                garbage: None,
                 processor,
                 inputs,
                 outputs,
@@ -195,6 +296,13 @@ impl Graph {
                 control_handle_dropped: false,
                 has_inputs_connected: false,
                 cycle_breaker: false,
+                render_serially: false,
+                level: 0,
+                free_after_quantum: false,
+                quarantined: false,
+                stats: None,
+                #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+                invariant_violated: false,
             }),
         );
     }
@@ -236,6 +344,42 @@ impl Graph {
         self.nodes.get_unchecked_mut(index).cycle_breaker = true;
     }
 
+    pub fn set_garbage_slot(&mut self, index: AudioNodeId, slot: llq::Node<Box<dyn Any + Send>>) {
+        self.nodes.get_unchecked_mut(index).garbage = Some(slot);
+    }
+
+    /// Hand out the nodes dropped since the last call
+    pub fn collect_garbage(&mut self, mut f: impl FnMut(llq::Node<Box<dyn Any + Send>>)) {
+        while let Some(garbage) = self.garbage.1.pop() {
+            f(garbage);
+        }
+    }
+
+    pub fn set_stats(&mut self, index: AudioNodeId, stats: Arc<ProcessorStats>) {
+        self.nodes.get_unchecked_mut(index).stats = Some(stats);
+    }
+
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+    }
+
+    /// Replace the worker threads, the previous workers are returned in `workers`
+    pub fn swap_workers(&mut self, workers: &mut Option<RenderWorkers>) {
+        std::mem::swap(&mut self.workers, workers);
+        self.ordered.clear(); // void current ordering, to determine the levels
+    }
+
+    /// Give up the buffer pool of the current thread, before the graph is sent to another thread
+    pub fn release_buffers(&mut self) {
+        self.alloc.release();
+    }
+
+    /// Replace the inserts of the main bus, the previous inserts are returned in `main_bus`
+    pub fn swap_main_bus(&mut self, main_bus: &mut Vec<AudioNodeId>) {
+        std::mem::swap(&mut self.main_bus, main_bus);
+        self.ordered.clear(); // void current ordering
+    }
+
     pub fn set_channel_count(&mut self, index: AudioNodeId, v: usize) {
         self.nodes.get_unchecked_mut(index).channel_config.count = v;
     }
@@ -252,8 +396,26 @@ impl Graph {
             .interpretation = v;
     }
 
-    pub fn route_message(&mut self, index: AudioNodeId, msg: &mut dyn Any) {
-        self.nodes.get_unchecked_mut(index).processor.onmessage(msg);
+    pub fn route_message(
+        &mut self,
+        index: AudioNodeId,
+        msg: &mut dyn Any,
+        scope: &AudioWorkletGlobalScope,
+    ) {
+        // the node may be gone already when it was an insert of the main bus
+        let Some(node) = self.nodes.get_mut(index) else {
+            return;
+        };
+        let node = node.get_mut();
+        if node.quarantined {
+            return;
+        }
+
+        let catch_me = AssertUnwindSafe(|| node.processor.onmessage(msg));
+        if let Err(e) = panic::catch_unwind(catch_me) {
+            scope.report_error(e, node.label().as_deref().map(String::as_str));
+            node.quarantine();
+        }
     }
 
     /// Helper function for `order_nodes` - traverse node and outgoing edges
@@ -313,7 +475,7 @@ impl Graph {
             .iter()
         {
             let cycle_breaker_applied = self.visit(
-                edge.other_id,
+                edge.target(node_id, &self.main_bus),
                 marked,
                 marked_temp,
                 ordered,
@@ -391,10 +553,17 @@ impl Graph {
             if cycle_breaker_applied {
                 // clear the outgoing edges of the nodes that have been recognized as cycle breaker
                 cycle_breakers.iter().for_each(|node_id| {
-                    self.nodes
-                        .get_unchecked_mut(*node_id)
-                        .outgoing_edges
-                        .clear();
+                    let node = self.nodes.get_unchecked_mut(*node_id);
+                    node.render_serially = true;
+                    let mut edges = std::mem::take(&mut node.outgoing_edges);
+
+                    // the other end of the DelayNode is no longer ordered after the cycle breaker
+                    edges.iter().for_each(|edge| {
+                        self.nodes.get_unchecked_mut(edge.other_id).render_serially = true;
+                    });
+
+                    edges.clear();
+                    self.nodes.get_unchecked_mut(*node_id).outgoing_edges = edges;
                 });
 
                 continue;
@@ -411,6 +580,11 @@ impl Graph {
         // The `visit` function adds child nodes before their parent, so reverse the order
         ordered.reverse();
 
+        if self.workers.is_some() {
+            // reuse the `marked` Vec, it can hold all nodes
+            self.order_levels(&mut ordered, &mut marked);
+        }
+
         // Re-instate Vecs
         self.ordered = ordered;
         self.marked = marked;
@@ -419,8 +593,46 @@ impl Graph {
         self.cycle_breakers = cycle_breakers;
     }
 
+    /// Group the ordered nodes in levels that can be rendered in parallel
+    ///
+    /// A node is placed in the level after the last level of the nodes connected to it (including
+    /// its audio params), keeping the topological order within a level.
+    fn order_levels(&mut self, ordered: &mut Vec<AudioNodeId>, scratch: &mut Vec<AudioNodeId>) {
+        ordered
+            .iter()
+            .for_each(|id| self.nodes.get_unchecked_mut(*id).level = 0);
+
+        let mut max_level = 0;
+        ordered.iter().for_each(|id| {
+            let node = self.nodes.get_unchecked(*id).borrow();
+            let level = node.level;
+            max_level = max_level.max(level);
+            node.outgoing_edges.iter().for_each(|edge| {
+                let target = edge.target(*id, &self.main_bus);
+                let mut other = self.nodes.get_unchecked(target).borrow_mut();
+                other.level = other.level.max(level + 1);
+            });
+        });
+
+        scratch.clear();
+        self.levels.clear();
+        for level in 0..=max_level {
+            scratch.extend(
+                ordered
+                    .iter()
+                    .filter(|id| self.nodes.get_unchecked(**id).borrow().level == level),
+            );
+            self.levels.push(scratch.len());
+        }
+
+        std::mem::swap(ordered, scratch);
+    }
+
     /// Render a single audio quantum by traversing the node list
     pub fn render(&mut self, scope: &AudioWorkletGlobalScope) -> &AudioRenderQuantum {
+        // the buffer pool is owned by the thread rendering the graph
+        self.alloc.claim(self.workers.is_some());
+
         // if the audio graph was changed, determine the new ordering
         if self.ordered.is_empty() {
             self.order_nodes();
@@ -429,83 +641,76 @@ impl Graph {
         // keep track of end-of-lifecyle nodes
         let mut nodes_dropped = false;
 
-        // process every node, in topological sorted order
-        self.ordered.iter().for_each(|index| {
-            // acquire a mutable borrow of the current processing node
-            let mut node = self.nodes.get_unchecked(*index).borrow_mut();
-
-            // let the current node process (catch any panics that may occur)
-            let params = AudioParamValues::from(&self.nodes);
-            scope.node_id.set(*index);
-            let (success, tail_time) = {
-                // We are abusing AssertUnwindSafe here, we cannot guarantee it upholds.
-                // This may lead to logic bugs later on, but it is the best that we can do.
-                // The alternative is to crash and reboot the render thread.
-                let catch_me = AssertUnwindSafe(|| node.process(params, scope));
-
-                match panic::catch_unwind(catch_me) {
-                    Ok(tail_time) => (true, tail_time),
-                    Err(e) => {
-                        node.outgoing_edges.clear();
-                        scope.report_error(e);
-                        (false, false)
+        match &self.workers {
+            // process every node, in topological sorted order
+            None => self.ordered.iter().for_each(|index| {
+                let nodes = self.nodes.shared();
+                Self::process_node(nodes, &self.main_bus, *index, scope, self.profiling);
+                nodes_dropped |= Self::finish_node(
+                    &mut self.nodes,
+                    &mut self.reclaim_id_channel,
+                    &mut self.garbage.0,
+                    &self.main_bus,
+                    *index,
+                    scope,
+                );
+            }),
+            // process the levels in order, and the nodes of a level in parallel
+            Some(workers) => {
+                let mut start = 0;
+                for &end in &self.levels {
+                    let level = &self.ordered[start..end];
+                    start = end;
+
+                    let nodes = self.nodes.shared();
+                    let main_bus = &self.main_bus[..];
+                    let profiling = self.profiling;
+                    if level.len() == 1 {
+                        Self::process_node(nodes, main_bus, level[0], scope, profiling);
+                    } else {
+                        let serial = |id: AudioNodeId| nodes.render_serially(id);
+
+                        // nodes sharing state with another node are rendered on this thread only
+                        level.iter().filter(|&&id| serial(id)).for_each(|&id| {
+                            Self::process_node(nodes, main_bus, id, scope, profiling)
+                        });
+
+                        // the scope is not shared between threads, each task creates its own
+                        let current_frame = scope.current_frame;
+                        let current_time = scope.current_time;
+                        let sample_rate = scope.sample_rate;
+                        let event_sender = &scope.event_sender;
+
+                        workers.run(level.len(), &|i| {
+                            let index = level[i];
+                            if serial(index) {
+                                return;
+                            }
+                            let scope = AudioWorkletGlobalScope {
+                                current_frame,
+                                current_time,
+                                sample_rate,
+                                node_id: Cell::new(index),
+                                event_sender: event_sender.clone(),
+                            };
+                            Self::process_node(nodes, main_bus, index, &scope, profiling);
+                        });
                     }
-                }
-            };
-
-            // iterate all outgoing edges, lookup these nodes and add to their input
-            node.outgoing_edges
-                .iter()
-                // audio params are connected to the 'hidden' usize::MAX output, ignore them here
-                .filter(|edge| edge.other_index != usize::MAX)
-                .for_each(|edge| {
-                    let mut output_node = self.nodes.get_unchecked(edge.other_id).borrow_mut();
-                    output_node.has_inputs_connected = true;
-                    let signal = &node.outputs[edge.self_index];
-                    let channel_config = &output_node.channel_config.clone();
-
-                    output_node.inputs[edge.other_index].add(signal, channel_config);
-                });
-
-            let can_free = !success || node.can_free(tail_time);
-
-            // Node is not dropped.
-            if !can_free {
-                // Reset input buffers as they will be summed up in the next render quantum.
-                node.inputs
-                    .iter_mut()
-                    .for_each(AudioRenderQuantum::make_silent);
-
-                // Reset input state
-                node.has_inputs_connected = false;
-            }
-
-            drop(node); // release borrow of self.nodes
 
-            // Check if we can decommission this node (end of life)
-            if can_free {
-                // Node is dropped, remove it from the node list
-                let mut node = self.nodes.remove(*index).into_inner();
-                self.reclaim_id_channel
-                    .push(node.reclaim_id.take().unwrap());
-                node.processor.before_drop(scope);
-                drop(node);
-
-                // And remove it from the ordering after we have processed all nodes
-                nodes_dropped = true;
-
-                // Nodes are only dropped when they do not have incoming connections.
-                // But they may have AudioParams feeding into them, these can de dropped too.
-                self.nodes.values_mut().for_each(|node| {
-                    // Check if this node was connected to the dropped node. In that case, it is
-                    // either an AudioParam or the AudioListener that feeds into a PannerNode.
-                    // These should be disconnected
-                    node.get_mut()
-                        .outgoing_edges
-                        .retain(|e| e.other_id != *index);
-                });
+                    // propagate the outputs to the next levels
+                    level.iter().for_each(|index| {
+                        nodes_dropped |= Self::finish_node(
+                            &mut self.nodes,
+                            &mut self.reclaim_id_channel,
+                            &mut self.garbage.0,
+                            &self.main_bus,
+                            *index,
+                            scope,
+                        );
+                    });
+                }
             }
-        });
+        }
 
         // If there were any nodes decommissioned, remove from graph order
         if nodes_dropped {
@@ -517,16 +722,177 @@ impl Graph {
                     i += 1;
                 }
             }
+
+            // the levels are no longer valid, determine them again
+            if self.workers.is_some() {
+                self.ordered.clear();
+            }
+
+            // an insert of the main bus is only dropped when its processor failed
+            let nodes = &self.nodes;
+            self.main_bus.retain(|&id| nodes.contains(id));
         }
 
         // Return the output buffer of destination node
         &self.nodes.get_unchecked_mut(AudioNodeId(0)).outputs[0]
     }
 
+    /// Let the node render the current quantum (catching any panics that may occur)
+    ///
+    /// A node whose processor panics is quarantined: it outputs silence from then on.
+    fn process_node(
+        nodes: SharedNodes<'_>,
+        main_bus: &[AudioNodeId],
+        index: AudioNodeId,
+        scope: &AudioWorkletGlobalScope,
+        profiling: bool,
+    ) {
+        // acquire a mutable borrow of the current processing node
+        let mut node = nodes.borrow_mut(index);
+
+        if node.quarantined {
+            node.free_after_quantum = node.can_free(false);
+        } else {
+            Self::run_processor(&mut node, nodes, index, scope, profiling);
+        }
+
+        // a failed insert of the main bus is dropped, so the main bus bypasses it
+        let failed_insert = node.quarantined && main_bus.contains(&index);
+        node.free_after_quantum |= failed_insert;
+    }
+
+    /// Run the processor of the node, and quarantine the node when the processor panics
+    fn run_processor(
+        node: &mut Node,
+        nodes: SharedNodes<'_>,
+        index: AudioNodeId,
+        scope: &AudioWorkletGlobalScope,
+        profiling: bool,
+    ) {
+        let params = AudioParamValues::from(nodes);
+        scope.node_id.set(index);
+        let render_start = profiling.then(Instant::now);
+        let tail_time = {
+            // We are abusing AssertUnwindSafe here, we cannot guarantee it upholds.
+            // This may lead to logic bugs later on, but it is the best that we can do.
+            // The alternative is to crash and reboot the render thread.
+            let catch_me = AssertUnwindSafe(|| {
+                #[cfg(feature = "rt-audit")]
+                let audit = crate::audit::arm();
+                let tail_time = node.process(params, scope);
+                #[cfg(feature = "rt-audit")]
+                crate::audit::check(audit, node.processor.name(), index.0, || node.label());
+                tail_time
+            });
+
+            match panic::catch_unwind(catch_me) {
+                Ok(tail_time) => {
+                    #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+                    node.check_invariants(scope);
+                    tail_time
+                }
+                Err(e) => {
+                    scope.report_error(e, node.label().as_deref().map(String::as_str));
+                    node.quarantine();
+                    false
+                }
+            }
+        };
+
+        if let (Some(render_start), Some(stats)) = (render_start, node.stats.as_ref()) {
+            stats.record(render_start.elapsed());
+        }
+
+        node.free_after_quantum = node.can_free(tail_time);
+    }
+
+    /// Add the outputs of the rendered node to the inputs of the connected nodes, and remove the
+    /// node when it is done playing
+    ///
+    /// Returns true when the node was dropped.
+    fn finish_node(
+        nodes: &mut NodeCollection,
+        reclaim_id_channel: &mut llq::Producer<AudioNodeId>,
+        garbage: &mut llq::Producer<Box<dyn Any + Send>>,
+        main_bus: &[AudioNodeId],
+        index: AudioNodeId,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        let mut node = nodes.get_unchecked(index).borrow_mut();
+
+        // iterate all outgoing edges, lookup these nodes and add to their input
+        node.outgoing_edges
+            .iter()
+            // audio params are connected to the 'hidden' usize::MAX output, ignore them here
+            .filter(|edge| edge.other_index != usize::MAX)
+            .for_each(|edge| {
+                let target = edge.target(index, main_bus);
+                let mut output_node = nodes.get_unchecked(target).borrow_mut();
+                output_node.has_inputs_connected = true;
+                let signal = &node.outputs[edge.self_index];
+                let channel_config = &output_node.channel_config.clone();
+
+                output_node.inputs[edge.other_index].add(signal, channel_config);
+            });
+
+        let can_free = node.free_after_quantum;
+
+        // Node is not dropped.
+        if !can_free {
+            // Reset input buffers as they will be summed up in the next render quantum.
+            node.inputs
+                .iter_mut()
+                .for_each(AudioRenderQuantum::make_silent);
+
+            // Reset input state
+            node.has_inputs_connected = false;
+        }
+
+        drop(node); // release borrow of nodes
+
+        // Check if we can decommission this node (end of life)
+        if can_free {
+            // Node is dropped, remove it from the node list
+            let mut node = nodes.remove(index).into_inner();
+            reclaim_id_channel.push(node.reclaim_id.take().unwrap());
+            scope.node_id.set(index);
+            if !node.quarantined {
+                node.processor.before_drop(scope);
+            }
+
+            // Free the node (its processor and buffers) off the render thread
+            match node.garbage.take() {
+                Some(mut slot) => {
+                    if let Some(slot) = slot.as_mut().downcast_mut::<Option<Node>>() {
+                        *slot = Some(node);
+                    }
+                    garbage.push(slot);
+                }
+                None => drop(node),
+            }
+
+            // Nodes are only dropped when they do not have incoming connections.
+            // But they may have AudioParams feeding into them, these can de dropped too.
+            nodes.values_mut().for_each(|node| {
+                // Check if this node was connected to the dropped node. In that case, it is
+                // either an AudioParam or the AudioListener that feeds into a PannerNode.
+                // These should be disconnected
+                node.get_mut()
+                    .outgoing_edges
+                    .retain(|e| e.other_id != index);
+            });
+        }
+
+        can_free
+    }
+
     pub fn before_drop(&mut self, scope: &AudioWorkletGlobalScope) {
         self.nodes.iter_mut().for_each(|(id, node)| {
-            scope.node_id.set(id);
-            node.get_mut().processor.before_drop(scope);
+            let node = node.get_mut();
+            if !node.quarantined {
+                scope.node_id.set(id);
+                node.processor.before_drop(scope);
+            }
         });
     }
 }
@@ -565,6 +931,7 @@ mod tests {
         let id = AudioNodeId(id);
         let reclaim_id = llq::Node::new(id);
         graph.add_node(id, reclaim_id, node, 1, 1, config());
+        graph.set_garbage_slot(id, garbage_slot());
     }
 
     fn add_edge(graph: &mut Graph, from: u64, to: u64) {
@@ -891,4 +1258,114 @@ mod tests {
         // No other dropped nodes
         assert!(node_id_consumer.pop().is_none());
     }
+
+    #[test]
+    fn test_quarantine() {
+        #[derive(Debug)]
+        struct PanicNode;
+
+        impl AudioProcessor for PanicNode {
+            fn process(
+                &mut self,
+                _inputs: &[AudioRenderQuantum],
+                _outputs: &mut [AudioRenderQuantum],
+                _params: AudioParamValues<'_>,
+                _scope: &AudioWorkletGlobalScope,
+            ) -> bool {
+                panic!("process failed")
+            }
+
+            fn onmessage(&mut self, _msg: &mut dyn Any) {
+                panic!("onmessage failed")
+            }
+        }
+
+        let (node_id_producer, mut node_id_consumer) = llq::Queue::new().split();
+        let mut graph = Graph::new(node_id_producer);
+        add_node(&mut graph, 0, Box::new(TestNode { tail_time: false }));
+        add_node(&mut graph, 2, Box::new(PanicNode));
+        add_edge(&mut graph, 2, 0);
+        add_node(&mut graph, 3, Box::new(PanicNode));
+        add_edge(&mut graph, 3, 0);
+
+        let (event_sender, event_receiver) = crossbeam_channel::unbounded();
+        let scope = AudioWorkletGlobalScope {
+            current_frame: 0,
+            current_time: 0.,
+            sample_rate: 48000.,
+            node_id: std::cell::Cell::new(AudioNodeId(0)),
+            event_sender,
+        };
+
+        graph.route_message(AudioNodeId(3), &mut (), &scope);
+        graph.render(&scope);
+
+        // both nodes are muted, but kept while the control thread holds them
+        for id in [2, 3] {
+            assert!(graph.nodes.get_unchecked_mut(AudioNodeId(id)).quarantined);
+        }
+        assert_eq!(event_receiver.try_iter().count(), 2);
+
+        // the processors are no longer run
+        graph.route_message(AudioNodeId(2), &mut (), &scope);
+        graph.render(&scope);
+        assert_eq!(event_receiver.try_iter().count(), 0);
+
+        // a quarantined node is dropped with its control handle
+        graph
+            .nodes
+            .get_unchecked_mut(AudioNodeId(2))
+            .control_handle_dropped = true;
+        graph.render(&scope);
+        assert!(!graph.nodes.contains(AudioNodeId(2)));
+        assert_eq!(node_id_consumer.pop().unwrap().0, 2);
+        assert!(node_id_consumer.pop().is_none());
+    }
+
+    #[test]
+    fn test_dropped_nodes_are_collected() {
+        struct MarkerNode(Arc<()>);
+
+        impl AudioProcessor for MarkerNode {
+            fn process(
+                &mut self,
+                _inputs: &[AudioRenderQuantum],
+                _outputs: &mut [AudioRenderQuantum],
+                _params: AudioParamValues<'_>,
+                _scope: &AudioWorkletGlobalScope,
+            ) -> bool {
+                false
+            }
+        }
+
+        let mut graph = Graph::new(llq::Queue::new().split().0);
+        add_node(&mut graph, 0, Box::new(TestNode { tail_time: false }));
+
+        let marker = Arc::new(());
+        add_node(&mut graph, 2, Box::new(MarkerNode(Arc::clone(&marker))));
+        graph
+            .nodes
+            .get_unchecked_mut(AudioNodeId(2))
+            .control_handle_dropped = true;
+        add_edge(&mut graph, 2, 0);
+
+        let scope = AudioWorkletGlobalScope {
+            current_frame: 0,
+            current_time: 0.,
+            sample_rate: 48000.,
+            node_id: std::cell::Cell::new(AudioNodeId(0)),
+            event_sender: crossbeam_channel::unbounded().0,
+        };
+        graph.render(&scope);
+
+        // the node is removed from the graph, but not freed by the render thread
+        assert!(!graph.nodes.contains(AudioNodeId(2)));
+        assert_eq!(Arc::strong_count(&marker), 2);
+
+        let mut collected = vec![];
+        graph.collect_garbage(|garbage| collected.push(garbage));
+        assert_eq!(collected.len(), 1);
+        drop(collected);
+        assert_eq!(Arc::strong_count(&marker), 1);
+    }
 }
@@ -0,0 +1,147 @@
+//! Checks of engine invariants, enabled in debug builds with the `debug-invariants` feature
+//!
+//! A processor emitting NaN or infinite values will silently poison all downstream nodes (e.g. a
+//! biquad filter that receives a single NaN will output NaN forever). With these checks enabled,
+//! the outputs of every node are inspected after each render quantum. Outputs violating the
+//! invariants are silenced, and on the first violation an [`ErrorEvent`](crate::ErrorEvent) is
+//! dispatched to the `onprocessorerror` handler of the node, with a message naming the processor.
+//!
+//! The checks are expensive, they should not be enabled in release builds.
+
+use crate::MAX_CHANNELS;
+
+use super::AudioRenderQuantum;
+
+/// Check the outputs of a node after it has processed a render quantum
+///
+/// Returns a description of the first violation, if any.
+pub(crate) fn check_outputs(outputs: &[AudioRenderQuantum]) -> Option<String> {
+    outputs.iter().enumerate().find_map(|(index, output)| {
+        let number_of_channels = output.number_of_channels();
+        if number_of_channels == 0 || number_of_channels > MAX_CHANNELS {
+            return Some(format!(
+                "output {index} has {number_of_channels} channels, expected 1 to {MAX_CHANNELS}"
+            ));
+        }
+
+        output
+            .channels()
+            .iter()
+            .enumerate()
+            .find_map(|(channel_number, channel)| {
+                channel.iter().position(|s| !s.is_finite()).map(|frame| {
+                    format!(
+                        "output {index} channel {channel_number} contains {} at frame {frame}",
+                        channel[frame]
+                    )
+                })
+            })
+    })
+}
+
+/// Check the computed values of an AudioParam against its nominal range
+///
+/// Returns a description of the first violation, if any.
+pub(crate) fn check_param_values(values: &[f32], min_value: f32, max_value: f32) -> Option<String> {
+    values
+        .iter()
+        .position(|v| !(min_value..=max_value).contains(v))
+        .map(|frame| {
+            format!(
+                "value {} at frame {frame} is outside the nominal range [{min_value}, {max_value}]",
+                values[frame]
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+    use crate::render::{AudioParamValues, AudioWorkletGlobalScope};
+    use crate::worklet::{AudioWorkletNode, AudioWorkletNodeOptions, AudioWorkletProcessor};
+    use crate::RENDER_QUANTUM_SIZE;
+
+    use super::*;
+
+    struct NanProcessor;
+
+    impl AudioWorkletProcessor for NanProcessor {
+        type ProcessorOptions = ();
+
+        fn constructor(_opts: Self::ProcessorOptions) -> Self {
+            NanProcessor
+        }
+
+        fn process<'a, 'b>(
+            &mut self,
+            _inputs: &'b [&'a [&'a [f32]]],
+            outputs: &'b mut [&'a mut [&'a mut [f32]]],
+            _params: AudioParamValues<'b>,
+            _scope: &'b AudioWorkletGlobalScope,
+        ) -> bool {
+            outputs[0][0][3] = f32::NAN;
+            true
+        }
+    }
+
+    #[test]
+    fn test_check_outputs() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 2, 44_100.);
+
+        let options = AudioWorkletNodeOptions {
+            output_channel_count: vec![1],
+            ..AudioWorkletNodeOptions::default()
+        };
+        let worklet = AudioWorkletNode::new::<NanProcessor>(&context, options);
+        worklet.connect(&context.destination());
+
+        let errors = Arc::new(Mutex::new(vec![]));
+        let errors_clone = Arc::clone(&errors);
+        worklet.set_onprocessorerror(Box::new(move |e| {
+            errors_clone.lock().unwrap().push(e.message);
+        }));
+
+        let output = context.start_rendering_sync();
+
+        // the offending output is silenced
+        assert!(output.get_channel_data(0).iter().all(|&s| s == 0.));
+
+        // the error is reported once, naming the processor
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("NanProcessor"));
+        assert!(errors[0].contains("output 0 channel 0 contains NaN at frame 3"));
+    }
+
+    #[test]
+    fn test_valid_outputs() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44_100.);
+
+        let mut src = context.create_oscillator();
+        src.connect(&context.destination());
+        src.start();
+
+        let errors = Arc::new(Mutex::new(vec![]));
+        let errors_clone = Arc::clone(&errors);
+        src.set_onprocessorerror(Box::new(move |e| {
+            errors_clone.lock().unwrap().push(e.message);
+        }));
+
+        let output = context.start_rendering_sync();
+        assert!(output.get_channel_data(0).iter().any(|&s| s != 0.));
+        assert!(errors.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_param_values() {
+        assert!(check_param_values(&[0., 0.5, 1.], 0., 1.).is_none());
+
+        let message = check_param_values(&[0., 2.], 0., 1.).unwrap();
+        assert!(message.contains("value 2 at frame 1"));
+
+        assert!(check_param_values(&[f32::NAN], 0., 1.).is_some());
+    }
+}
@@ -2,6 +2,8 @@
 
 // private mods
 pub(crate) mod graph;
+#[cfg(all(debug_assertions, feature = "debug-invariants"))]
+pub(crate) mod invariants;
 
 // pub(crate) mods
 mod thread;
@@ -12,7 +14,10 @@ mod processor;
 pub use processor::*;
 mod quantum;
 
+mod workers;
+pub(crate) use workers::RenderWorkers;
+
 mod node_collection;
-pub(crate) use node_collection::NodeCollection;
+pub(crate) use node_collection::{NodeCell, NodeCollection, NodeOutputs, SharedNodes};
 
 pub use quantum::*;
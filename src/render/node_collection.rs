@@ -1,11 +1,187 @@
 use crate::context::{AudioNodeId, DESTINATION_NODE_ID};
 use crate::render::graph::Node;
+use crate::render::AudioRenderQuantum;
 
-use std::cell::RefCell;
+use std::cell::UnsafeCell;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Borrow flag value of an exclusively borrowed `NodeCell`
+const BORROWED_MUT: usize = usize::MAX;
+
+/// A `RefCell` for a render `Node` with atomic borrow flags
+///
+/// The cell is not `Sync` by itself, the render workers access it through [`SharedNodes`].
+pub(crate) struct NodeCell {
+    /// Number of shared borrows, or `BORROWED_MUT`
+    borrow: AtomicUsize,
+    value: UnsafeCell<Node>,
+}
+
+impl NodeCell {
+    pub fn new(value: Node) -> Self {
+        Self {
+            borrow: AtomicUsize::new(0),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[track_caller]
+    #[inline(always)]
+    pub fn borrow(&self) -> NodeRef<'_> {
+        let mut current = self.borrow.load(Ordering::Relaxed);
+        loop {
+            assert!(current < BORROWED_MUT - 1, "Node already mutably borrowed");
+            match self.borrow.compare_exchange_weak(
+                current,
+                current + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return NodeRef { cell: self },
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    #[track_caller]
+    #[inline(always)]
+    pub fn borrow_mut(&self) -> NodeRefMut<'_> {
+        let result =
+            self.borrow
+                .compare_exchange(0, BORROWED_MUT, Ordering::Acquire, Ordering::Relaxed);
+        assert!(result.is_ok(), "Node already borrowed");
+        NodeRefMut { cell: self }
+    }
+
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut Node {
+        self.value.get_mut()
+    }
+
+    pub fn into_inner(self) -> Node {
+        self.value.into_inner()
+    }
+}
+
+impl std::fmt::Debug for NodeCell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.borrow.load(Ordering::Relaxed) == BORROWED_MUT {
+            f.write_str("NodeCell { <borrowed> }")
+        } else {
+            f.debug_struct("NodeCell")
+                .field("value", &*self.borrow())
+                .finish()
+        }
+    }
+}
+
+/// Shared borrow of a `NodeCell`
+pub(crate) struct NodeRef<'a> {
+    cell: &'a NodeCell,
+}
+
+impl Deref for NodeRef<'_> {
+    type Target = Node;
+
+    fn deref(&self) -> &Node {
+        // SAFETY: the borrow flag excludes mutable borrows while this borrow is alive
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl Drop for NodeRef<'_> {
+    fn drop(&mut self) {
+        self.cell.borrow.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Exclusive borrow of a `NodeCell`
+pub(crate) struct NodeRefMut<'a> {
+    cell: &'a NodeCell,
+}
+
+impl Deref for NodeRefMut<'_> {
+    type Target = Node;
+
+    fn deref(&self) -> &Node {
+        // SAFETY: the borrow flag excludes other borrows while this borrow is alive
+        unsafe { &*self.cell.value.get() }
+    }
+}
+
+impl DerefMut for NodeRefMut<'_> {
+    fn deref_mut(&mut self) -> &mut Node {
+        // SAFETY: the borrow flag excludes other borrows while this borrow is alive
+        unsafe { &mut *self.cell.value.get() }
+    }
+}
+
+impl Drop for NodeRefMut<'_> {
+    fn drop(&mut self) {
+        self.cell.borrow.store(0, Ordering::Release);
+    }
+}
+
+/// Shared borrow of the outputs of a node, see [`SharedNodes::borrow_outputs`]
+pub(crate) struct NodeOutputs<'a>(NodeRef<'a>);
+
+impl Deref for NodeOutputs<'_> {
+    type Target = [AudioRenderQuantum];
+
+    fn deref(&self) -> &[AudioRenderQuantum] {
+        &self.0.outputs
+    }
+}
+
+/// View of the nodes that can be shared with the render workers
+///
+/// The graph borrows the nodes of a level mutably from multiple threads at once, while their
+/// processors read the audio params of the previous levels. A `Node` is `Send` but not `Sync`
+/// (its processor is not), so the view only hands out shared borrows of the outputs.
+#[derive(Clone, Copy)]
+pub(crate) struct SharedNodes<'a> {
+    nodes: &'a NodeCollection,
+}
+
+// SAFETY:
+// The borrow flags guarantee a mutable borrow is exclusive, like a `RefCell`, but with atomic
+// operations so the borrows are checked across threads. A mutable borrow moves access to the
+// `Node` to another thread, which requires `Send`. Shared borrows only reach other threads as
+// `NodeOutputs` or a copied flag, which requires the outputs to be `Sync`.
+unsafe impl Send for SharedNodes<'_> {}
+unsafe impl Sync for SharedNodes<'_> {}
+
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    const fn assert_sync<T: Sync>() {}
+    assert_send::<Node>();
+    assert_sync::<AudioRenderQuantum>();
+};
+
+impl<'a> SharedNodes<'a> {
+    #[track_caller]
+    #[inline(always)]
+    pub fn borrow_mut(&self, index: AudioNodeId) -> NodeRefMut<'a> {
+        self.nodes.get_unchecked(index).borrow_mut()
+    }
+
+    #[track_caller]
+    #[inline(always)]
+    pub fn borrow_outputs(&self, index: AudioNodeId) -> NodeOutputs<'a> {
+        NodeOutputs(self.nodes.get_unchecked(index).borrow())
+    }
+
+    #[track_caller]
+    #[inline(always)]
+    pub fn render_serially(&self, index: AudioNodeId) -> bool {
+        self.nodes.get_unchecked(index).borrow().render_serially
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct NodeCollection {
-    nodes: Vec<Option<RefCell<Node>>>,
+    nodes: Vec<Option<NodeCell>>,
 }
 
 impl NodeCollection {
@@ -31,14 +207,14 @@ impl NodeCollection {
     }
 
     #[inline(always)]
-    pub fn insert(&mut self, index: AudioNodeId, value: RefCell<Node>) {
+    pub fn insert(&mut self, index: AudioNodeId, value: NodeCell) {
         let index = index.0 as usize;
         self.ensure_capacity(index + 1);
         self.nodes[index] = Some(value);
     }
 
     #[inline(always)]
-    pub fn remove(&mut self, index: AudioNodeId) -> RefCell<Node> {
+    pub fn remove(&mut self, index: AudioNodeId) -> NodeCell {
         self.nodes[index.0 as usize]
             .take()
             .expect("Unable to remove non-existing Node in NodeCollection")
@@ -53,12 +229,12 @@ impl NodeCollection {
     }
 
     #[inline(always)]
-    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut RefCell<Node>> {
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut NodeCell> {
         self.nodes.iter_mut().filter_map(Option::as_mut)
     }
 
     #[inline(always)]
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = (AudioNodeId, &mut RefCell<Node>)> {
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (AudioNodeId, &mut NodeCell)> {
         self.nodes
             .iter_mut()
             .enumerate()
@@ -71,13 +247,13 @@ impl NodeCollection {
     }
 
     #[inline(always)]
-    pub fn get_mut(&mut self, index: AudioNodeId) -> Option<&mut RefCell<Node>> {
+    pub fn get_mut(&mut self, index: AudioNodeId) -> Option<&mut NodeCell> {
         self.nodes[index.0 as usize].as_mut()
     }
 
     #[track_caller]
     #[inline(always)]
-    pub fn get_unchecked(&self, index: AudioNodeId) -> &RefCell<Node> {
+    pub fn get_unchecked(&self, index: AudioNodeId) -> &NodeCell {
         self.nodes[index.0 as usize].as_ref().unwrap()
     }
 
@@ -86,6 +262,12 @@ impl NodeCollection {
     pub fn get_unchecked_mut(&mut self, index: AudioNodeId) -> &mut Node {
         self.nodes[index.0 as usize].as_mut().unwrap().get_mut()
     }
+
+    /// View of the nodes to share with the render workers
+    #[inline(always)]
+    pub fn shared(&self) -> SharedNodes<'_> {
+        SharedNodes { nodes: self }
+    }
 }
 
 #[cfg(test)]
@@ -1,9 +1,11 @@
 //! Audio processing code that runs on the audio rendering thread
 use crate::context::{AudioNodeId, AudioParamId};
-use crate::events::{AudioProcessingEvent, ErrorEvent, EventDispatch};
+use crate::events::{
+    AudioProcessingEvent, ErrorEvent, EventDispatch, EventPayload, OnsetEvent, SpectrogramEvent,
+};
 use crate::{AudioBuffer, Event, RENDER_QUANTUM_SIZE};
 
-use super::{graph::Node, AudioRenderQuantum, NodeCollection};
+use super::{AudioRenderQuantum, NodeOutputs, SharedNodes};
 
 use crossbeam_channel::Sender;
 use std::cell::Cell;
@@ -74,25 +76,90 @@ impl AudioWorkletGlobalScope {
         let _ = self.event_sender.try_send(dispatch);
     }
 
-    pub(crate) fn report_error(&self, error: Box<dyn Any + Send>) {
-        pub fn type_name_of_val<T: ?Sized>(_val: &T) -> &'static str {
-            std::any::type_name::<T>()
+    pub(crate) fn send_onset_event(&self, time: f64, strength: f32) {
+        // sending could fail if the channel is saturated or the main thread is shutting down
+        let event = OnsetEvent {
+            time,
+            strength,
+            event: Event { type_: "onset" },
+        };
+        let _ = self
+            .event_sender
+            .try_send(EventDispatch::onset(self.node_id.get(), event));
+    }
+
+    /// Send a spectrogram frame, returns the buffer when the event could not be sent so it can
+    /// be reused
+    pub(crate) fn send_spectrogram_event(
+        &self,
+        time: f64,
+        magnitudes: Vec<f32>,
+        dropped_frames: usize,
+    ) -> Result<(), Vec<f32>> {
+        let event = SpectrogramEvent {
+            time,
+            magnitudes,
+            dropped_frames,
+            event: Event {
+                type_: "spectrogram",
+            },
+            registration: None,
+        };
+        let dispatch = EventDispatch::spectrogram(self.node_id.get(), event);
+
+        // sending could fail if the channel is saturated or the main thread is shutting down
+        self.event_sender
+            .try_send(dispatch)
+            .map_err(|e| match e.into_inner().into_payload() {
+                EventPayload::Spectrogram(mut event) => std::mem::take(&mut event.magnitudes),
+                _ => unreachable!(),
+            })
+    }
+
+    pub(crate) fn report_error(&self, error: Box<dyn Any + Send>, label: Option<&str>) {
+        let message = crate::events::panic_message(&*error);
+        match label {
+            Some(label) => log::error!(
+                "Panic occurred in Audio Processor of node '{}': '{}'. Muting the node.",
+                label,
+                &message
+            ),
+            None => log::error!(
+                "Panic occurred in Audio Processor: '{}'. Muting the node.",
+                &message
+            ),
         }
-        let message = if let Some(v) = error.downcast_ref::<String>() {
-            v.to_string()
-        } else if let Some(v) = error.downcast_ref::<&str>() {
-            v.to_string()
-        } else {
-            type_name_of_val(&error).to_string()
+
+        let event = ErrorEvent {
+            message,
+            error,
+            event: Event {
+                type_: "ErrorEvent",
+            },
         };
-        eprintln!(
-            "Panic occurred in Audio Processor: '{}'. Removing node from graph.",
-            &message
+        let _ = self
+            .event_sender
+            .try_send(EventDispatch::processor_error(self.node_id.get(), event));
+    }
+
+    #[cfg(all(debug_assertions, feature = "debug-invariants"))]
+    pub(crate) fn report_invariant_violation(&self, processor: &str, message: String) {
+        let message = format!(
+            "Invariant violated by {} (node {}): {}",
+            processor,
+            self.node_id.get().0,
+            message
         );
+        log::error!("{}", &message);
+        self.report_error_message(message);
+    }
 
+    /// Report an error that does not stop the processor to the `processorerror` handler of the
+    /// node, without logging from the render thread
+    pub(crate) fn report_error_message(&self, message: String) {
         let event = ErrorEvent {
+            error: Box::new(message.clone()),
             message,
-            error,
             event: Event {
                 type_: "ErrorEvent",
             },
@@ -109,6 +176,10 @@ impl AudioWorkletGlobalScope {
 /// [`AudioNode`](crate::node::AudioNode) (the user facing object that lives in the control
 /// thread). See [`ConcreteBaseAudioContext::register`](crate::context::ConcreteBaseAudioContext::register).
 ///
+/// A panic in [`process`](Self::process) or [`onmessage`](Self::onmessage) is caught: the node
+/// is muted for the rest of its lifetime and a `processorerror` event is dispatched to it, see
+/// [`AudioNode::set_onprocessorerror`](crate::node::AudioNode::set_onprocessorerror).
+///
 /// Check the `examples/worklet.rs` file for example usage of this trait.
 pub trait AudioProcessor: Send {
     /// Audio processing function
@@ -183,13 +254,13 @@ impl std::fmt::Debug for dyn AudioProcessor {
     }
 }
 
-struct DerefAudioRenderQuantumChannel<'a>(std::cell::Ref<'a, Node>);
+struct DerefAudioRenderQuantumChannel<'a>(NodeOutputs<'a>);
 
 impl Deref for DerefAudioRenderQuantumChannel<'_> {
     type Target = [f32];
 
     fn deref(&self) -> &Self::Target {
-        let buffer = self.0.get_buffer();
+        let buffer = self.0.first().unwrap();
         let len = if buffer.single_valued() {
             1
         } else {
@@ -204,7 +275,7 @@ impl Deref for DerefAudioRenderQuantumChannel<'_> {
 ///
 /// Provided to implementations of [`AudioProcessor`] in the render thread
 pub struct AudioParamValues<'a> {
-    nodes: &'a NodeCollection,
+    nodes: SharedNodes<'a>,
 }
 
 impl std::fmt::Debug for AudioParamValues<'_> {
@@ -214,7 +285,7 @@ impl std::fmt::Debug for AudioParamValues<'_> {
 }
 
 impl<'a> AudioParamValues<'a> {
-    pub(crate) fn from(nodes: &'a NodeCollection) -> Self {
+    pub(crate) fn from(nodes: SharedNodes<'a>) -> Self {
         Self { nodes }
     }
 
@@ -225,7 +296,7 @@ impl<'a> AudioParamValues<'a> {
     /// provide a slice of length equal to the render quantum size (default: 128)
     #[allow(clippy::missing_panics_doc)]
     pub fn get(&self, index: &AudioParamId) -> impl Deref<Target = [f32]> + '_ {
-        DerefAudioRenderQuantumChannel(self.nodes.get_unchecked(index.into()).borrow())
+        DerefAudioRenderQuantumChannel(self.nodes.borrow_outputs(index.into()))
     }
 
     pub(crate) fn listener_params(&self) -> [impl Deref<Target = [f32]> + '_; 9] {
@@ -1,81 +1,177 @@
 //! Optimized audio signal data structures, used in `AudioProcessors`
 use arrayvec::ArrayVec;
-use std::cell::RefCell;
-use std::rc::Rc;
+use crossbeam_channel::{Receiver, Sender};
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
 
 use crate::node::{ChannelConfigInner, ChannelCountMode, ChannelInterpretation};
 
 use crate::assert_valid_number_of_channels;
 use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
 
+/// Number of buffers kept in the pool shared between threads
+const SHARED_POOL_CAPACITY: usize = 1024;
+
+/// Source of the thread tokens, 0 means no thread owns the pool
+static NEXT_THREAD_TOKEN: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_TOKEN: u64 = NEXT_THREAD_TOKEN.fetch_add(1, Ordering::Relaxed);
+}
+
+fn thread_token() -> u64 {
+    THREAD_TOKEN.with(|token| *token)
+}
+
 // object pool for `AudioRenderQuantumChannel`s, only allocate if the pool is empty
 pub(crate) struct Alloc {
-    inner: Rc<AllocInner>,
+    inner: Arc<AllocInner>,
 }
 
-#[derive(Debug)]
+/// The pool is owned by the thread rendering the graph, which uses the `local` pool without any
+/// synchronization. Other threads (the render workers, the garbage collector) use the lock-free
+/// `shared` pool. When the graph is rendered in parallel, all threads use the `shared` pool.
 struct AllocInner {
-    pool: RefCell<Vec<Rc<[f32; RENDER_QUANTUM_SIZE]>>>,
-    zeroes: Rc<[f32; RENDER_QUANTUM_SIZE]>,
+    local: UnsafeCell<Vec<Arc<[f32; RENDER_QUANTUM_SIZE]>>>,
+    shared: (
+        Sender<Arc<[f32; RENDER_QUANTUM_SIZE]>>,
+        Receiver<Arc<[f32; RENDER_QUANTUM_SIZE]>>,
+    ),
+    owner: AtomicU64,
+    parallel: AtomicBool,
+    zeroes: Arc<[f32; RENDER_QUANTUM_SIZE]>,
+}
+
+// SAFETY:
+// `local` is only accessed by the owner thread, and only when not rendering in parallel. The
+// ownership is taken by the thread rendering the graph with `Alloc::claim`, and given up with
+// `Alloc::release` before the graph is sent to another thread, so at any time at most one thread
+// sees itself as the owner. The parallel mode is only changed by the owner, between two render
+// quanta, when the workers are idle.
+unsafe impl Sync for AllocInner {}
+
+impl std::fmt::Debug for AllocInner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AllocInner")
+            .field("owner", &self.owner)
+            .field("parallel", &self.parallel)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Alloc {
     pub fn with_capacity(n: usize) -> Self {
-        let pool: Vec<_> = (0..n).map(|_| Rc::new([0.; RENDER_QUANTUM_SIZE])).collect();
-        let zeroes = Rc::new([0.; RENDER_QUANTUM_SIZE]);
+        let pool: Vec<_> = (0..n)
+            .map(|_| Arc::new([0.; RENDER_QUANTUM_SIZE]))
+            .collect();
+        let zeroes = Arc::new([0.; RENDER_QUANTUM_SIZE]);
 
         let inner = AllocInner {
-            pool: RefCell::new(pool),
+            local: UnsafeCell::new(pool),
+            shared: crossbeam_channel::bounded(SHARED_POOL_CAPACITY),
+            owner: AtomicU64::new(0),
+            parallel: AtomicBool::new(false),
             zeroes,
         };
 
         Self {
-            inner: Rc::new(inner),
+            inner: Arc::new(inner),
         }
     }
 
+    /// Make the current thread the owner of the pool
+    ///
+    /// Called by the thread rendering the graph, before each render quantum. The pool switches
+    /// to the shared pool for all threads when the graph is rendered in `parallel`.
+    pub fn claim(&mut self, parallel: bool) {
+        let inner = &self.inner;
+        let token = thread_token();
+        if inner.owner.load(Ordering::Relaxed) != token {
+            inner.owner.store(token, Ordering::Relaxed);
+        }
+
+        if inner.parallel.load(Ordering::Relaxed) != parallel {
+            // SAFETY: this thread is the owner, and no worker is rendering
+            let local = unsafe { &mut *inner.local.get() };
+            if parallel {
+                local
+                    .drain(..)
+                    .for_each(|buf| drop(inner.shared.0.try_send(buf)));
+            } else {
+                local.extend(inner.shared.1.try_iter());
+            }
+            inner.parallel.store(parallel, Ordering::Relaxed);
+        }
+    }
+
+    /// Give up the ownership of the pool, before the graph is sent to another thread
+    pub fn release(&mut self) {
+        self.inner.owner.store(0, Ordering::Relaxed);
+    }
+
     #[cfg(test)]
     pub fn allocate(&self) -> AudioRenderQuantumChannel {
         AudioRenderQuantumChannel {
             data: self.inner.allocate(),
-            alloc: Rc::clone(&self.inner),
+            alloc: Arc::clone(&self.inner),
         }
     }
 
     pub fn silence(&self) -> AudioRenderQuantumChannel {
         AudioRenderQuantumChannel {
-            data: Rc::clone(&self.inner.zeroes),
-            alloc: Rc::clone(&self.inner),
+            data: Arc::clone(&self.inner.zeroes),
+            alloc: Arc::clone(&self.inner),
         }
     }
 
     #[cfg(test)]
     pub fn pool_size(&self) -> usize {
-        self.inner.pool.borrow().len()
+        // SAFETY: the tests do not share the pool between threads
+        let local = unsafe { &*self.inner.local.get() };
+        local.len() + self.inner.shared.1.len()
     }
 }
 
 impl AllocInner {
-    fn allocate(&self) -> Rc<[f32; RENDER_QUANTUM_SIZE]> {
-        if let Some(rc) = self.pool.borrow_mut().pop() {
-            // reuse from pool
-            rc
-        } else {
-            // allocate
-            Rc::new([0.; RENDER_QUANTUM_SIZE])
+    /// The local pool, if the current thread may use it
+    #[allow(clippy::mut_from_ref)]
+    fn local(&self) -> Option<&mut Vec<Arc<[f32; RENDER_QUANTUM_SIZE]>>> {
+        if self.parallel.load(Ordering::Relaxed)
+            || self.owner.load(Ordering::Relaxed) != thread_token()
+        {
+            return None;
         }
+
+        // SAFETY: only the owner thread gets here, see `unsafe impl Sync`. The reference does
+        // not escape `allocate` or `push`, which do not reenter.
+        Some(unsafe { &mut *self.local.get() })
+    }
+
+    fn allocate(&self) -> Arc<[f32; RENDER_QUANTUM_SIZE]> {
+        let reused = match self.local() {
+            Some(local) => local.pop(),
+            None => None,
+        };
+
+        // reuse from pool, or allocate
+        reused
+            .or_else(|| self.shared.1.try_recv().ok())
+            .unwrap_or_else(|| Arc::new([0.; RENDER_QUANTUM_SIZE]))
     }
 
-    fn push(&self, data: Rc<[f32; RENDER_QUANTUM_SIZE]>) {
-        self.pool
-            .borrow_mut() // infallible when single threaded
-            .push(data);
+    fn push(&self, data: Arc<[f32; RENDER_QUANTUM_SIZE]>) {
+        match self.local() {
+            Some(local) => local.push(data),
+            // when the shared pool is full, the buffer is deallocated
+            None => drop(self.shared.0.try_send(data)),
+        }
     }
 }
 
 /// Render thread channel buffer
 ///
-/// Basically wraps a `Rc<[f32; render_quantum_size]>`, which means it derefs to a (mutable) slice
+/// Basically wraps an `Arc<[f32; render_quantum_size]>`, which means it derefs to a (mutable) slice
 /// of `[f32]` sample values. Plus it has copy-on-write semantics, so it is cheap to clone.
 ///
 /// The `render_quantum_size` is equal to 128 by default, but in future versions it may be equal to
@@ -88,26 +184,26 @@ impl AllocInner {
 /// mutate it from there.
 #[derive(Clone, Debug)]
 pub struct AudioRenderQuantumChannel {
-    data: Rc<[f32; RENDER_QUANTUM_SIZE]>,
-    alloc: Rc<AllocInner>,
+    data: Arc<[f32; RENDER_QUANTUM_SIZE]>,
+    alloc: Arc<AllocInner>,
 }
 
 impl AudioRenderQuantumChannel {
     fn make_mut(&mut self) -> &mut [f32; RENDER_QUANTUM_SIZE] {
-        if Rc::strong_count(&self.data) != 1 {
+        if Arc::strong_count(&self.data) != 1 {
             let mut new = self.alloc.allocate();
-            Rc::make_mut(&mut new).copy_from_slice(self.data.deref());
+            Arc::make_mut(&mut new).copy_from_slice(self.data.deref());
             self.data = new;
         }
 
-        Rc::make_mut(&mut self.data)
+        Arc::make_mut(&mut self.data)
     }
 
     /// `O(1)` check if this buffer is equal to the 'silence buffer'
     ///
     /// If this function returns false, it is still possible for all samples to be zero.
     pub(crate) fn is_silent(&self) -> bool {
-        Rc::ptr_eq(&self.data, &self.alloc.zeroes)
+        Arc::ptr_eq(&self.data, &self.alloc.zeroes)
     }
 
     /// Sum two channels
@@ -121,8 +217,8 @@ impl AudioRenderQuantumChannel {
 
     pub(crate) fn silence(&self) -> Self {
         Self {
-            data: Rc::clone(&self.alloc.zeroes),
-            alloc: Rc::clone(&self.alloc),
+            data: Arc::clone(&self.alloc.zeroes),
+            alloc: Arc::clone(&self.alloc),
         }
     }
 }
@@ -151,8 +247,8 @@ impl AsRef<[f32]> for AudioRenderQuantumChannel {
 
 impl std::ops::Drop for AudioRenderQuantumChannel {
     fn drop(&mut self) {
-        if Rc::strong_count(&self.data) == 1 {
-            let zeroes = Rc::clone(&self.alloc.zeroes);
+        if Arc::strong_count(&self.data) == 1 {
+            let zeroes = Arc::clone(&self.alloc.zeroes);
             let rc = std::mem::replace(&mut self.data, zeroes);
             self.alloc.push(rc);
         }
@@ -292,10 +388,11 @@ impl AudioRenderQuantum {
         let silence = self.channels[0].silence();
 
         // Handle discrete interpretation or speaker layouts where the initial or desired number of
-        // channels is larger than 6 (undefined by the specification)
+        // channels is 7 or larger than 8 (undefined by the specification, nor by the 7.1 extension)
+        let is_speaker_layout = |n: usize| n <= 6 || n == 8;
         if interpretation == ChannelInterpretation::Discrete
-            || self.number_of_channels() > 6
-            || computed_number_of_channels > 6
+            || !is_speaker_layout(self.number_of_channels())
+            || !is_speaker_layout(computed_number_of_channels)
         {
             // upmix by filling with silence
             for _ in self.number_of_channels()..computed_number_of_channels {
@@ -304,6 +401,43 @@ impl AudioRenderQuantum {
 
             // downmix by truncating
             self.channels.truncate(computed_number_of_channels);
+        } else if computed_number_of_channels == 8 {
+            // 7.1 is not defined by the specification, upmix to 5.1 and add silent back channels
+            // output.L = input.L;
+            // output.R = input.R;
+            // output.C = input.C;
+            // output.LFE = input.LFE;
+            // output.SL = input.SL;
+            // output.SR = input.SR;
+            // output.BL = 0;
+            // output.BR = 0;
+            self.mix(6, interpretation);
+            self.channels.push(silence.clone());
+            self.channels.push(silence);
+        } else if self.number_of_channels() == 8 {
+            // 7.1 is not defined by the specification, fold the back channels into the surround
+            // channels and downmix the resulting 5.1
+            // output.L = input.L;
+            // output.R = input.R;
+            // output.C = input.C;
+            // output.LFE = input.LFE;
+            // output.SL = sqrt(0.5) * (input.SL + input.BL);
+            // output.SR = sqrt(0.5) * (input.SR + input.BR);
+            let b_right = self.channels.pop().unwrap();
+            let b_left = self.channels.pop().unwrap();
+            let sqrt05 = (0.5_f32).sqrt();
+
+            self.channels[4]
+                .iter_mut()
+                .zip(b_left.iter())
+                .for_each(|(sl, bl)| *sl = sqrt05 * (*sl + *bl));
+
+            self.channels[5]
+                .iter_mut()
+                .zip(b_right.iter())
+                .for_each(|(sr, br)| *sr = sqrt05 * (*sr + *br));
+
+            self.mix(computed_number_of_channels, interpretation);
         } else {
             match (self.number_of_channels(), computed_number_of_channels) {
                 // ------------------------------------------
@@ -649,7 +783,7 @@ impl AudioRenderQuantum {
         let mut channels = self.channels.iter();
         let first = channels.next().unwrap();
         for c in channels {
-            if !Rc::ptr_eq(&first.data, &c.data) {
+            if !Arc::ptr_eq(&first.data, &c.data) {
                 return false;
             }
         }
@@ -666,8 +800,9 @@ mod tests {
 
     #[test]
     fn test_pool() {
-        // Create pool of size 2
-        let alloc = Alloc::with_capacity(2);
+        // Create pool of size 2, owned by this thread
+        let mut alloc = Alloc::with_capacity(2);
+        alloc.claim(false);
         assert_eq!(alloc.pool_size(), 2);
 
         alloc_counter::deny_alloc(|| {
@@ -743,6 +878,44 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_pool_shared() {
+        let mut alloc = Alloc::with_capacity(2);
+        alloc.claim(false);
+
+        // a buffer dropped on another thread is returned to the shared pool
+        let a = alloc.allocate();
+        std::thread::spawn(move || drop(a)).join().unwrap();
+        assert_eq!(alloc.pool_size(), 2);
+
+        // and reused by the owner, when the local pool is empty
+        let a = alloc.allocate();
+        let b = alloc_counter::deny_alloc(|| alloc.allocate());
+        assert_eq!(alloc.pool_size(), 0);
+        drop((a, b));
+        assert_eq!(alloc.pool_size(), 2);
+
+        // when rendering in parallel, the other threads reuse the buffers of the owner
+        alloc.claim(true);
+        let alloc = std::sync::Arc::new(alloc);
+        let shared = std::sync::Arc::clone(&alloc);
+        std::thread::spawn(move || {
+            let mut a = shared.silence();
+            a[0] = 1.;
+        })
+        .join()
+        .unwrap();
+        assert_eq!(alloc.pool_size(), 2);
+
+        // after releasing the pool, the buffers of this thread are returned to the shared pool
+        let mut alloc = std::sync::Arc::try_unwrap(alloc).ok().unwrap();
+        alloc.claim(false);
+        let a = alloc.allocate();
+        alloc.release();
+        drop(a);
+        assert_eq!(alloc.pool_size(), 2);
+    }
+
     #[test]
     fn test_silence() {
         let alloc = Alloc::with_capacity(1);
@@ -1511,6 +1684,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_audiobuffer_mix_speakers_7_1() {
+        let alloc = Alloc::with_capacity(1);
+
+        // 2 -> 8
+        let mut signal = alloc.silence();
+        signal.copy_from_slice(&[1.; RENDER_QUANTUM_SIZE]);
+        let mut buffer = AudioRenderQuantum::from(signal);
+        buffer.mix(2, ChannelInterpretation::Speakers);
+        buffer.mix(8, ChannelInterpretation::Speakers);
+        assert_eq!(buffer.number_of_channels(), 8);
+        for (channel, expected) in [1., 1., 0., 0., 0., 0., 0., 0.].into_iter().enumerate() {
+            assert_float_eq!(
+                &buffer.channel_data(channel)[..],
+                &[expected; RENDER_QUANTUM_SIZE][..],
+                abs_all <= 0.
+            );
+        }
+
+        // 8 -> 6, the back channels are folded into the surround channels
+        let mut buffer = AudioRenderQuantum::from(alloc.silence());
+        buffer.set_number_of_channels(8);
+        buffer.channel_data_mut(4).fill(1.);
+        buffer.channel_data_mut(7).fill(1.);
+        buffer.mix(6, ChannelInterpretation::Speakers);
+        assert_eq!(buffer.number_of_channels(), 6);
+        let sqrt05 = (0.5_f32).sqrt();
+        for (channel, expected) in [0., 0., 0., 0., sqrt05, sqrt05].into_iter().enumerate() {
+            assert_float_eq!(
+                &buffer.channel_data(channel)[..],
+                &[expected; RENDER_QUANTUM_SIZE][..],
+                abs_all <= 0.
+            );
+        }
+
+        // 8 -> 2
+        let mut buffer = AudioRenderQuantum::from(alloc.silence());
+        buffer.set_number_of_channels(8);
+        buffer.channel_data_mut(0).fill(1.);
+        buffer.channel_data_mut(6).fill(1.);
+        buffer.mix(2, ChannelInterpretation::Speakers);
+        assert_eq!(buffer.number_of_channels(), 2);
+        assert_float_eq!(
+            &buffer.channel_data(0)[..],
+            &[1.5; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 1e-6
+        );
+        assert_float_eq!(
+            &buffer.channel_data(1)[..],
+            &[0.; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 0.
+        );
+    }
+
     #[test]
     fn test_audiobuffer_add() {
         let alloc = Alloc::with_capacity(1);
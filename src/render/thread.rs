@@ -15,15 +15,18 @@ use futures_util::StreamExt as _;
 use super::AudioRenderQuantum;
 use crate::buffer::AudioBuffer;
 use crate::context::{
-    AudioContextState, AudioNodeId, OfflineAudioContext, OfflineAudioContextCallback,
+    AudioContextInterruptionPolicy, AudioContextState, AudioNodeId, OfflineAudioContext,
+    OfflineAudioContextCallback,
 };
 use crate::events::{EventDispatch, EventLoop};
-use crate::message::ControlMessage;
+use crate::message::{ControlMessage, OneshotNotify};
 use crate::node::ChannelInterpretation;
 use crate::render::AudioWorkletGlobalScope;
+use crate::resampling::OutputResampler;
 use crate::{AudioRenderCapacityLoad, RENDER_QUANTUM_SIZE};
 
 use super::graph::Graph;
+use super::RenderWorkers;
 
 /// Operations running off the system-level audio callback
 pub(crate) struct RenderThread {
@@ -41,13 +44,19 @@ pub(crate) struct RenderThread {
     load_value_sender: Option<Sender<AudioRenderCapacityLoad>>,
     event_sender: Sender<EventDispatch>,
     garbage_collector: Option<llq::Producer<Box<dyn Any + Send>>>,
+    /// scheduled end of the rendering, with its fade out duration
+    close_at: Option<(f64, f64, OneshotNotify)>,
+    interruption_policy: AudioContextInterruptionPolicy,
+    /// hands over the graph when dropped while the device is interrupted
+    graph_recycler: Option<Sender<Graph>>,
+    /// converts the output when the device runs at another sample rate than the graph
+    output_resampler: Option<OutputResampler>,
 }
 
 // SAFETY:
-// The RenderThread is not Send/Sync since it contains `AudioRenderQuantum`s (which use Rc), but
-// these are only accessed within the same thread (the render thread). Due to the cpal constraints
-// we can neither move the RenderThread object into the render thread, nor can we initialize the
-// Rc's in that thread.
+// The RenderThread is only accessed within the same thread (the render thread), except for the
+// render workers which only reach the nodes through `SharedNodes`. Due to the cpal constraints we can neither
+// move the RenderThread object into the render thread, nor can we initialize it in that thread.
 #[allow(clippy::non_send_fields_in_send_ty)]
 unsafe impl Send for Graph {}
 unsafe impl Sync for Graph {}
@@ -61,6 +70,13 @@ impl std::fmt::Debug for RenderThread {
             .field("buffer_size", &self.buffer_size)
             .field("frames_played", &self.frames_played.load(Ordering::Relaxed))
             .field("number_of_channels", &self.number_of_channels)
+            .field(
+                "output_sample_rate",
+                &self
+                    .output_resampler
+                    .as_ref()
+                    .map(|r| r.target_sample_rate()),
+            )
             .finish_non_exhaustive()
     }
 }
@@ -87,9 +103,32 @@ impl RenderThread {
             load_value_sender: None,
             event_sender,
             garbage_collector: None,
+            close_at: None,
+            interruption_policy: AudioContextInterruptionPolicy::default(),
+            graph_recycler: None,
+            output_resampler: None,
         }
     }
 
+    pub(crate) fn set_interruption_policy(&mut self, policy: AudioContextInterruptionPolicy) {
+        self.interruption_policy = policy;
+    }
+
+    /// Resample the output when the audio device runs at another sample rate than the graph
+    pub(crate) fn set_output_sample_rate(&mut self, sample_rate: f32) {
+        self.output_resampler = (sample_rate != self.sample_rate)
+            .then(|| OutputResampler::new(self.sample_rate, sample_rate, self.number_of_channels));
+    }
+
+    pub(crate) fn set_graph_recycler(&mut self, graph_recycler: Sender<Graph>) {
+        self.graph_recycler = Some(graph_recycler);
+    }
+
+    /// Handle to report the errors of the audio device from the threads of the backend
+    pub(crate) fn interruption_handle(&self) -> InterruptionHandle {
+        InterruptionHandle::new(Arc::clone(&self.state), self.event_sender.clone())
+    }
+
     pub(crate) fn set_load_value_sender(
         &mut self,
         load_value_sender: Sender<AudioRenderCapacityLoad>,
@@ -105,6 +144,17 @@ impl RenderThread {
         }
     }
 
+    /// Ship the nodes dropped by the graph to the garbage collector thread, so their memory is not
+    /// freed on the render thread
+    fn collect_garbage(&mut self) {
+        let graph = self.graph.as_mut().unwrap();
+        match self.garbage_collector.as_mut() {
+            Some(gc) => graph.collect_garbage(|garbage| gc.push(garbage)),
+            // not rendering in real time, free them right away
+            None => graph.collect_garbage(drop),
+        }
+    }
+
     #[inline(always)]
     fn handle_control_messages(&mut self) {
         if self.receiver.is_none() {
@@ -126,19 +176,17 @@ impl RenderThread {
             RegisterNode {
                 id: node_id,
                 reclaim_id,
+                garbage,
                 node,
                 inputs,
                 outputs,
                 channel_config,
+                stats,
             } => {
-                self.graph.as_mut().unwrap().add_node(
-                    node_id,
-                    reclaim_id,
-                    node,
-                    inputs,
-                    outputs,
-                    channel_config,
-                );
+                let graph = self.graph.as_mut().unwrap();
+                graph.add_node(node_id, reclaim_id, node, inputs, outputs, channel_config);
+                graph.set_garbage_slot(node_id, garbage);
+                graph.set_stats(node_id, stats);
             }
             ConnectNode {
                 from,
@@ -168,9 +216,30 @@ impl RenderThread {
             MarkCycleBreaker { id } => {
                 self.graph.as_mut().unwrap().mark_cycle_breaker(id);
             }
+            SetProfiling { enabled } => {
+                self.graph.as_mut().unwrap().set_profiling(enabled);
+            }
+            SetRenderWorkers { mut workers } => {
+                if let Some(workers) = workers.as_mut().downcast_mut::<Option<RenderWorkers>>() {
+                    self.graph.as_mut().unwrap().swap_workers(workers);
+                }
+                if let Some(gc) = self.garbage_collector.as_mut() {
+                    gc.push(workers)
+                }
+            }
+            SetMainBus { mut inserts } => {
+                if let Some(inserts) = inserts.as_mut().downcast_mut::<Vec<AudioNodeId>>() {
+                    self.graph.as_mut().unwrap().swap_main_bus(inserts);
+                }
+                if let Some(gc) = self.garbage_collector.as_mut() {
+                    gc.push(inserts)
+                }
+            }
             CloseAndRecycle { sender } => {
                 self.set_state(AudioContextState::Suspended);
-                let _ = sender.send(self.graph.take().unwrap());
+                let mut graph = self.graph.take().unwrap();
+                graph.release_buffers();
+                let _ = sender.send(graph);
                 self.receiver = None;
                 return ControlFlow::Break(()); // no further handling of ctrl msgs
             }
@@ -180,11 +249,38 @@ impl RenderThread {
                 self.set_state(AudioContextState::Running);
             }
             NodeMessage { id, mut msg } => {
-                self.graph.as_mut().unwrap().route_message(id, msg.as_mut());
+                // the processor may panic on the message, the error is reported to its node
+                let current_frame = self.frames_played.load(Ordering::Relaxed);
+                let scope = AudioWorkletGlobalScope {
+                    current_frame,
+                    current_time: current_frame as f64 / self.sample_rate as f64,
+                    sample_rate: self.sample_rate,
+                    event_sender: self.event_sender.clone(),
+                    node_id: Cell::new(id),
+                };
+                self.graph
+                    .as_mut()
+                    .unwrap()
+                    .route_message(id, msg.as_mut(), &scope);
                 if let Some(gc) = self.garbage_collector.as_mut() {
                     gc.push(msg)
                 }
             }
+            Batch { mut msgs } => {
+                let mut flow = ControlFlow::Continue(());
+                if let Some(batch) = msgs.as_mut().downcast_mut::<Vec<ControlMessage>>() {
+                    for msg in batch.drain(..) {
+                        flow = self.handle_control_message(msg);
+                        if flow.is_break() {
+                            break;
+                        }
+                    }
+                }
+                if let Some(gc) = self.garbage_collector.as_mut() {
+                    gc.push(msgs)
+                }
+                return flow;
+            }
             RunDiagnostics { mut buffer } => {
                 use std::io::Write;
                 writeln!(&mut buffer, "{:#?}", &self).ok();
@@ -197,6 +293,8 @@ impl RenderThread {
                 self.suspended = true;
                 self.set_state(AudioContextState::Suspended);
                 notify.send();
+                // time stops, so a pending scheduled close would never be reached
+                self.close_at = None;
             }
             Resume { notify } => {
                 self.suspended = false;
@@ -207,6 +305,15 @@ impl RenderThread {
                 self.suspended = true;
                 self.set_state(AudioContextState::Closed);
                 notify.send();
+                // a pending scheduled close is superseded
+                self.close_at = None;
+            }
+            CloseAt {
+                when,
+                fade_out,
+                notify,
+            } => {
+                self.close_at = Some((when, fade_out, notify));
             }
 
             SetChannelCount { id, count } => {
@@ -281,6 +388,67 @@ impl RenderThread {
         AudioBuffer::from(buffer, sample_rate)
     }
 
+    // Render method of the `OfflineAudioContext::render_to_writer`
+    //
+    // Same as `render_audiobuffer_sync`, but each rendered quantum is handed to the sink instead
+    // of being accumulated in the output buffer. Rendering stops at the first error of the sink.
+    pub fn render_quanta_sync<E>(
+        mut self,
+        context: &mut OfflineAudioContext,
+        mut suspend_callbacks: Vec<(usize, Box<OfflineAudioContextCallback>)>,
+        event_loop: &EventLoop,
+        mut sink: impl FnMut(&[Vec<f32>]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let length = context.length();
+
+        // a single quantum is buffered, the last one is truncated to the remaining frames
+        let mut buffer = Vec::with_capacity(self.number_of_channels);
+        buffer.resize_with(buffer.capacity(), || {
+            Vec::with_capacity(RENDER_QUANTUM_SIZE)
+        });
+
+        let num_frames = length.div_ceil(RENDER_QUANTUM_SIZE);
+        let mut result = Ok(());
+
+        // Handle initial control messages
+        self.handle_control_messages();
+
+        for quantum in 0..num_frames {
+            // Suspend at given times and run callbacks
+            if suspend_callbacks.first().map(|&(q, _)| q) == Some(quantum) {
+                let callback = suspend_callbacks.remove(0).1;
+                (callback)(context);
+
+                // Handle any control messages that may have been submitted by the callback
+                self.handle_control_messages();
+            }
+
+            buffer.iter_mut().for_each(Vec::clear);
+            self.render_offline_quantum(&mut buffer);
+
+            let remaining = length - quantum * RENDER_QUANTUM_SIZE;
+            buffer.iter_mut().for_each(|b| b.truncate(remaining));
+
+            result = sink(&buffer);
+
+            let events_were_handled = event_loop.handle_pending_events();
+            if events_were_handled {
+                // Handle any control messages that may have been submitted by the handler
+                self.handle_control_messages();
+            }
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        // call destructors of all alive nodes and handle any resulting events
+        self.unload_graph();
+        event_loop.handle_pending_events();
+
+        result
+    }
+
     // Render method of the `OfflineAudioContext::start_rendering`
     //
     // This is the async interface, as compared to render_audiobuffer_sync
@@ -373,6 +541,8 @@ impl RenderThread {
                 .unwrap_or(&[0.; RENDER_QUANTUM_SIZE]);
             b.extend_from_slice(&c[..remaining]);
         });
+
+        self.collect_garbage();
     }
 
     /// Run destructors of all alive nodes in the audio graph
@@ -458,41 +628,47 @@ impl RenderThread {
         // handle addition/removal of nodes/edges
         self.handle_control_messages();
 
+        // the audio device renders again after an interruption
+        if !self.suspended
+            && self.state.load(Ordering::Relaxed) == AudioContextState::Interrupted as u8
+        {
+            if self.interruption_policy == AudioContextInterruptionPolicy::Manual {
+                output_buffer.fill(S::from_sample_(0.));
+                return;
+            }
+            log::info!("Audio device recovered from interruption, resuming");
+            self.set_state(AudioContextState::Running);
+        }
+
         // if the thread is still booting, suspended, or shutting down, fill with silence
         if self.suspended || !self.graph.as_ref().is_some_and(Graph::is_active) {
             output_buffer.fill(S::from_sample_(0.));
             return;
         }
 
+        // the graph runs at the sample rate of the context, convert it to the rate of the device
+        if let Some(mut resampler) = self.output_resampler.take() {
+            resampler.process(output_buffer, || {
+                // the context was closed at a scheduled time in a previous quantum
+                (!self.suspended).then(|| self.render_quantum())
+            });
+            self.output_resampler = Some(resampler);
+            return;
+        }
+
         // The audio graph is rendered in chunks of RENDER_QUANTUM_SIZE frames.  But some audio backends
         // may not be able to emit chunks of this size.
         let chunk_size = RENDER_QUANTUM_SIZE * self.number_of_channels;
 
         for data in output_buffer.chunks_mut(chunk_size) {
-            // update time
-            let current_frame = self
-                .frames_played
-                .fetch_add(RENDER_QUANTUM_SIZE as u64, Ordering::Relaxed);
-            let current_time = current_frame as f64 / self.sample_rate as f64;
-
-            let scope = AudioWorkletGlobalScope {
-                current_frame,
-                current_time,
-                sample_rate: self.sample_rate,
-                event_sender: self.event_sender.clone(),
-                node_id: Cell::new(AudioNodeId(0)), // placeholder value
-            };
-
-            // render audio graph, clone it in case we need to mutate/store the value later
-            let mut destination_buffer = self.graph.as_mut().unwrap().render(&scope).clone();
-
-            // online AudioContext allows channel count to be less than the number
-            // of channels of the backend stream, i.e. number of channels of the
-            // soundcard clamped to MAX_CHANNELS.
-            if destination_buffer.number_of_channels() < self.number_of_channels {
-                destination_buffer.mix(self.number_of_channels, ChannelInterpretation::Discrete);
+            // the context was closed at a scheduled time in a previous chunk
+            if self.suspended {
+                data.fill(S::from_sample_(0.));
+                continue;
             }
 
+            let destination_buffer = self.render_quantum();
+
             // copy rendered audio into output slice
             for i in 0..self.number_of_channels {
                 let output = data.iter_mut().skip(i).step_by(self.number_of_channels);
@@ -509,22 +685,131 @@ impl RenderThread {
                 debug_assert!(channel_offset < RENDER_QUANTUM_SIZE);
                 self.buffer_offset = Some((channel_offset, destination_buffer));
             }
+        }
+    }
+
+    /// Render a single quantum of the graph for the audio device, with a channel per channel of
+    /// the device
+    fn render_quantum(&mut self) -> AudioRenderQuantum {
+        // update time
+        let current_frame = self
+            .frames_played
+            .fetch_add(RENDER_QUANTUM_SIZE as u64, Ordering::Relaxed);
+        let current_time = current_frame as f64 / self.sample_rate as f64;
 
-            // handle addition/removal of nodes/edges
-            self.handle_control_messages();
+        let scope = AudioWorkletGlobalScope {
+            current_frame,
+            current_time,
+            sample_rate: self.sample_rate,
+            event_sender: self.event_sender.clone(),
+            node_id: Cell::new(AudioNodeId(0)), // placeholder value
+        };
+
+        // render audio graph, clone it in case we need to mutate/store the value later
+        let mut destination_buffer = self.graph.as_mut().unwrap().render(&scope).clone();
+        self.collect_garbage();
+
+        // online AudioContext allows channel count to be less than the number
+        // of channels of the backend stream, i.e. number of channels of the
+        // soundcard clamped to MAX_CHANNELS.
+        if destination_buffer.number_of_channels() < self.number_of_channels {
+            destination_buffer.mix(self.number_of_channels, ChannelInterpretation::Discrete);
+        }
+
+        // apply the fade out of a scheduled close
+        let closing = self.close_at.as_ref().is_some_and(|&(when, fade_out, _)| {
+            let end_time = current_time + RENDER_QUANTUM_SIZE as f64 / self.sample_rate as f64;
+            if end_time > when - fade_out {
+                let dt = 1. / self.sample_rate as f64;
+                destination_buffer
+                    .channels_mut()
+                    .iter_mut()
+                    .for_each(|channel| {
+                        channel.iter_mut().enumerate().for_each(|(i, sample)| {
+                            let remaining = when - (current_time + i as f64 * dt);
+                            let gain = if fade_out > 0. {
+                                (remaining / fade_out).clamp(0., 1.)
+                            } else if remaining > 0. {
+                                1.
+                            } else {
+                                0.
+                            };
+                            *sample *= gain as f32;
+                        });
+                    });
+            }
+            end_time >= when
+        });
+
+        // feed the echo cancellers of the microphone inputs
+        #[cfg(feature = "voice-processing")]
+        crate::io::voice::push_echo_reference(&destination_buffer, self.sample_rate);
+
+        if closing {
+            let (_, _, notify) = self.close_at.take().unwrap();
+            self.suspended = true;
+            self.set_state(AudioContextState::Closed);
+            notify.send();
         }
+
+        // handle addition/removal of nodes/edges
+        self.handle_control_messages();
+
+        destination_buffer
     }
 
     fn set_state(&self, state: AudioContextState) {
-        self.state.store(state as u8, Ordering::Relaxed);
-        self.event_sender
-            .try_send(EventDispatch::state_change(state))
-            .ok();
+        let previous_state = self.state.swap(state as u8, Ordering::Relaxed).into();
+        if previous_state != state {
+            self.event_sender
+                .try_send(EventDispatch::state_change(previous_state, state))
+                .ok();
+        }
+    }
+}
+
+/// Moves a running context to the interrupted state when its audio device reports an error
+#[derive(Clone, Debug)]
+pub(crate) struct InterruptionHandle {
+    state: Arc<AtomicU8>,
+    event_sender: Sender<EventDispatch>,
+}
+
+impl InterruptionHandle {
+    pub fn new(state: Arc<AtomicU8>, event_sender: Sender<EventDispatch>) -> Self {
+        Self {
+            state,
+            event_sender,
+        }
+    }
+
+    pub fn interrupt(&self) {
+        let running = AudioContextState::Running as u8;
+        let interrupted = AudioContextState::Interrupted as u8;
+        if self
+            .state
+            .compare_exchange(running, interrupted, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            self.event_sender
+                .try_send(EventDispatch::state_change(
+                    AudioContextState::Running,
+                    AudioContextState::Interrupted,
+                ))
+                .ok();
+        }
     }
 }
 
 impl Drop for RenderThread {
     fn drop(&mut self) {
+        // the stream of an interrupted device is closed, so the graph can move to another device
+        if self.state.load(Ordering::Relaxed) == AudioContextState::Interrupted as u8 {
+            if let (Some(mut graph), Some(recycler)) = (self.graph.take(), &self.graph_recycler) {
+                graph.release_buffers();
+                let _ = recycler.try_send(graph);
+            }
+        }
         if let Some(gc) = self.garbage_collector.as_mut() {
             gc.push(llq::Node::new(Box::new(TerminateGarbageCollectorThread)))
         }
@@ -0,0 +1,263 @@
+//! Pool of worker threads rendering independent nodes of the audio graph in parallel
+
+use std::any::Any;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// Number of times an idle worker checks for a new job before it parks
+const SPIN_LIMIT: usize = 10_000;
+
+/// Tasks to be run by the render thread and the workers, claimed one by one
+struct Job<'a> {
+    task: &'a (dyn Fn(usize) + Sync),
+    len: usize,
+    next: AtomicUsize,
+    /// Payload of the first panicking task, re-raised by the render thread
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+impl Job<'_> {
+    /// Run tasks until all of them have been claimed
+    ///
+    /// A panicking task does not unwind: the remaining tasks are skipped and the panic is stored
+    /// in the job, so the workers stay alive and the render thread does not wait forever.
+    fn run(&self) {
+        loop {
+            let index = self.next.fetch_add(1, Ordering::Relaxed);
+            if index >= self.len {
+                break;
+            }
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| (self.task)(index))) {
+                self.next.store(self.len, Ordering::Relaxed);
+                self.panic.lock().unwrap().get_or_insert(payload);
+                break;
+            }
+        }
+    }
+}
+
+/// Decrements the number of active workers when dropped, also when unwinding
+struct ActiveGuard<'a>(&'a AtomicUsize);
+
+impl Drop for ActiveGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Retracts the job and waits for the workers to leave it when dropped, also when unwinding, so
+/// the job is never freed while a worker may access it
+struct RetractGuard<'a>(&'a Shared);
+
+impl Drop for RetractGuard<'_> {
+    fn drop(&mut self) {
+        self.0.job.store(ptr::null_mut(), Ordering::SeqCst);
+        while self.0.active.load(Ordering::SeqCst) != 0 {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// State shared between the render thread and the workers
+#[derive(Default)]
+struct Shared {
+    /// The current job, living on the stack of the render thread
+    job: AtomicPtr<Job<'static>>,
+    /// Incremented for every new job
+    epoch: AtomicUsize,
+    /// Number of workers that may access the current job
+    active: AtomicUsize,
+    shutdown: AtomicBool,
+}
+
+/// Worker threads assisting the render thread, see
+/// [`BaseAudioContext::set_render_threads`](crate::context::BaseAudioContext::set_render_threads)
+///
+/// The render thread stays in charge: it publishes a job, takes part in it and returns when all
+/// tasks are done. The workers spin for a short while between jobs and park when idle.
+pub(crate) struct RenderWorkers {
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for RenderWorkers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderWorkers")
+            .field("threads", &self.threads.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RenderWorkers {
+    /// Spawn the given number of worker threads
+    ///
+    /// On Linux, the workers are pinned to a core each, skipping the first core which is left to
+    /// the render thread.
+    pub fn new(count: usize) -> Self {
+        let shared = Arc::new(Shared::default());
+        let cores = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+
+        let threads = (0..count)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                thread::Builder::new()
+                    .name(format!("web-audio-render-worker-{}", i))
+                    .spawn(move || {
+                        pin_to_core((i + 1) % cores);
+                        run_worker(&shared);
+                    })
+                    .expect("Unable to spawn render worker thread")
+            })
+            .collect();
+
+        Self { shared, threads }
+    }
+
+    /// Run the task for all indices `0..len`, on the current thread and the workers
+    ///
+    /// Returns when all tasks are done.
+    ///
+    /// # Panics
+    ///
+    /// When a task panics, on any thread, the remaining tasks are skipped and the panic is
+    /// resumed on the current thread once all workers have left the job.
+    pub fn run(&self, len: usize, task: &(dyn Fn(usize) + Sync)) {
+        let job = Job {
+            task,
+            len,
+            next: AtomicUsize::new(0),
+            panic: Mutex::new(None),
+        };
+
+        // publish the job, the workers only access it while they are counted as active
+        let job_ptr = ptr::addr_of!(job).cast_mut().cast::<Job<'static>>();
+        self.shared.job.store(job_ptr, Ordering::SeqCst);
+        let retract = RetractGuard(&self.shared);
+        self.shared.epoch.fetch_add(1, Ordering::SeqCst);
+        self.threads.iter().for_each(|t| t.thread().unpark());
+
+        job.run();
+
+        // retract the job and wait for the workers to finish their claimed tasks
+        drop(retract);
+
+        if let Some(payload) = job.panic.into_inner().unwrap() {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+impl Drop for RenderWorkers {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+        self.threads.iter().for_each(|t| t.thread().unpark());
+        self.threads.drain(..).for_each(|t| {
+            let _ = t.join();
+        });
+    }
+}
+
+fn run_worker(shared: &Shared) {
+    // For x64 and aarch, process with denormal floats disabled, like the render thread
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+    unsafe {
+        // SAFETY: see `RenderThread::render_offline_quantum`
+        no_denormals::no_denormals(|| worker_loop(shared))
+    };
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    worker_loop(shared);
+}
+
+fn worker_loop(shared: &Shared) {
+    let mut seen_epoch = 0;
+    let mut idle = 0;
+
+    while !shared.shutdown.load(Ordering::SeqCst) {
+        let epoch = shared.epoch.load(Ordering::SeqCst);
+        if epoch == seen_epoch {
+            idle += 1;
+            if idle < SPIN_LIMIT {
+                std::hint::spin_loop();
+            } else {
+                thread::park();
+            }
+            continue;
+        }
+
+        seen_epoch = epoch;
+        idle = 0;
+
+        shared.active.fetch_add(1, Ordering::SeqCst);
+        let _active = ActiveGuard(&shared.active);
+        let job = shared.job.load(Ordering::SeqCst);
+        if !job.is_null() {
+            // SAFETY: the render thread keeps the job alive until no worker is active anymore.
+            // A worker becoming active after the job is retracted observes the null pointer.
+            unsafe { &*job }.run();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn pin_to_core(core: usize) {
+    // SAFETY: the cpu set is initialized before it is passed on
+    let result = unsafe {
+        let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+        libc::CPU_SET(core, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    if result != 0 {
+        log::warn!("Unable to pin render worker thread to core {}", core);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_to_core(_core: usize) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_all_tasks() {
+        let workers = RenderWorkers::new(3);
+        let done: Vec<_> = (0..64).map(|_| AtomicUsize::new(0)).collect();
+
+        for _ in 0..100 {
+            workers.run(done.len(), &|i| {
+                done[i].fetch_add(1, Ordering::Relaxed);
+            });
+        }
+
+        assert!(done.iter().all(|d| d.load(Ordering::Relaxed) == 100));
+    }
+
+    #[test]
+    fn test_panicking_task() {
+        let workers = RenderWorkers::new(3);
+        let done = AtomicUsize::new(0);
+
+        for _ in 0..20 {
+            // the panic is resumed on the calling thread, wherever the task ran
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                workers.run(64, &|i| {
+                    assert!(i != 10, "task panicked");
+                })
+            }));
+            let payload = result.unwrap_err();
+            assert_eq!(payload.downcast_ref::<&str>(), Some(&"task panicked"));
+            assert_eq!(workers.shared.active.load(Ordering::SeqCst), 0);
+
+            // the workers are still alive
+            workers.run(64, &|_| {
+                done.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+
+        assert_eq!(done.load(Ordering::Relaxed), 20 * 64);
+    }
+}
@@ -1,7 +1,323 @@
+use std::collections::VecDeque;
 use std::error::Error;
+use std::f64::consts::PI;
 
-use crate::buffer::{AudioBuffer, AudioBufferOptions};
-use crate::AudioBufferIter;
+use dasp_sample::FromSample;
+
+use crate::buffer::AudioBuffer;
+use crate::media_streams::ResampleQuality;
+use crate::render::AudioRenderQuantum;
+use crate::{AudioBufferIter, RENDER_QUANTUM_SIZE};
+
+/// Resolution of the interpolation kernel table, in points per input frame
+const KERNEL_RESOLUTION: usize = 256;
+
+/// Interpolation kernel, tabulated over its positive half
+struct Kernel {
+    /// number of input frames on each side of the interpolated position
+    half_width: usize,
+    table: Vec<f64>,
+}
+
+impl Kernel {
+    fn new(quality: ResampleQuality, step: f64) -> Self {
+        let half_width = match quality {
+            ResampleQuality::Linear => 1,
+            ResampleQuality::Medium => 8,
+            ResampleQuality::High => 32,
+        };
+
+        // lower the cutoff when downsampling, and a bit further to leave room for the
+        // transition band of the window
+        let cutoff = 0.9 * step.recip().min(1.);
+
+        let table = (0..=half_width * KERNEL_RESOLUTION)
+            .map(|i| {
+                let x = i as f64 / KERNEL_RESOLUTION as f64;
+                match quality {
+                    ResampleQuality::Linear => 1. - x,
+                    ResampleQuality::Medium | ResampleQuality::High => {
+                        // Blackman windowed sinc
+                        let u = x / half_width as f64;
+                        let window = 0.42 + 0.5 * (PI * u).cos() + 0.08 * (2. * PI * u).cos();
+                        let sinc = if x == 0. {
+                            1.
+                        } else {
+                            (PI * cutoff * x).sin() / (PI * cutoff * x)
+                        };
+                        cutoff * sinc * window
+                    }
+                }
+            })
+            .collect();
+
+        Self { half_width, table }
+    }
+
+    /// Weight of the input frame at distance `x` of the interpolated position
+    #[inline(always)]
+    fn weight(&self, x: f64) -> f64 {
+        let position = x.abs() * KERNEL_RESOLUTION as f64;
+        let index = position as usize;
+        if index >= self.table.len() - 1 {
+            return 0.;
+        }
+        let k = position - index as f64;
+        self.table[index] + k * (self.table[index + 1] - self.table[index])
+    }
+}
+
+/// Streaming sample rate converter
+///
+/// The state is kept across buffers so that consecutive buffers of a stream are converted
+/// without discontinuities.
+struct Converter {
+    source_sample_rate: f32,
+    target_sample_rate: f32,
+    /// number of input frames per output frame
+    step: f64,
+    kernel: Kernel,
+    /// input frames not fully consumed yet, per channel
+    history: Vec<Vec<f32>>,
+    /// position of the next output frame in the history
+    position: f64,
+}
+
+impl Converter {
+    fn new(
+        source_sample_rate: f32,
+        target_sample_rate: f32,
+        number_of_channels: usize,
+        quality: ResampleQuality,
+    ) -> Self {
+        let step = f64::from(source_sample_rate) / f64::from(target_sample_rate);
+        let kernel = Kernel::new(quality, step);
+
+        // prepend silence so the first output frame is aligned with the first input frame
+        let half_width = kernel.half_width;
+        let history = vec![vec![0.; half_width]; number_of_channels];
+
+        Self {
+            source_sample_rate,
+            target_sample_rate,
+            step,
+            kernel,
+            history,
+            position: half_width as f64,
+        }
+    }
+
+    fn number_of_channels(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Convert the given buffer, the output contains as many frames as can be computed from
+    /// the input received so far
+    fn process(&mut self, buffer: &AudioBuffer) -> AudioBuffer {
+        self.history
+            .iter_mut()
+            .zip(buffer.channels())
+            .for_each(|(history, channel)| history.extend_from_slice(channel.as_slice()));
+
+        self.render(self.history[0].len())
+    }
+
+    /// Convert the remaining input frames, at the end of the stream
+    fn flush(&mut self) -> AudioBuffer {
+        let end = self.history[0].len();
+        let half_width = self.kernel.half_width;
+        self.history
+            .iter_mut()
+            .for_each(|history| history.resize(end + half_width, 0.));
+
+        self.render(end)
+    }
+
+    /// Render the output frames located before `end` in the history
+    fn render(&mut self, end: usize) -> AudioBuffer {
+        let half_width = self.kernel.half_width;
+        let available = self.history[0].len();
+        let mut output = vec![vec![]; self.number_of_channels()];
+
+        while self.position < end as f64 && self.position as usize + half_width < available {
+            let center = self.position as usize;
+            let frac = self.position - center as f64;
+            let first = center + 1 - half_width;
+
+            output
+                .iter_mut()
+                .zip(self.history.iter())
+                .for_each(|(output, history)| {
+                    let value = history[first..=center + half_width]
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &sample)| {
+                            let x = frac + (half_width - 1) as f64 - j as f64;
+                            self.kernel.weight(x) * f64::from(sample)
+                        })
+                        .sum::<f64>();
+                    output.push(value as f32);
+                });
+
+            self.position += self.step;
+        }
+
+        // drop the frames before the window of the next output frame
+        let consumed = (self.position as usize + 1)
+            .saturating_sub(half_width)
+            .min(available);
+        self.history.iter_mut().for_each(|history| {
+            history.drain(..consumed);
+        });
+        self.position -= consumed as f64;
+
+        AudioBuffer::from(output, self.target_sample_rate)
+    }
+}
+
+/// Convert a whole buffer to the given sample rate, the output has exactly `length` frames
+pub(crate) fn resample_buffer(
+    buffer: &AudioBuffer,
+    sample_rate: f32,
+    quality: ResampleQuality,
+    length: usize,
+) -> AudioBuffer {
+    let mut converter = Converter::new(
+        buffer.sample_rate(),
+        sample_rate,
+        buffer.number_of_channels(),
+        quality,
+    );
+
+    let output = converter.process(buffer);
+    let tail = converter.flush();
+
+    let channels = output
+        .channels()
+        .iter()
+        .zip(tail.channels())
+        .map(|(channel, tail)| {
+            let mut data = Vec::with_capacity(length.max(channel.len() + tail.len()));
+            data.extend_from_slice(channel.as_slice());
+            data.extend_from_slice(tail.as_slice());
+            data.resize(length, 0.);
+            data
+        })
+        .collect();
+    AudioBuffer::from(channels, sample_rate)
+}
+
+/// Sample rate converter of the output of a render thread
+///
+/// Converts the quanta rendered at the sample rate of the context to the sample rate of the audio
+/// device, e.g. after the context moved to a device running at another rate. The quanta are
+/// pulled on demand while filling the interleaved buffer of the device. All buffers are allocated
+/// up front, so it can run on the render thread.
+pub(crate) struct OutputResampler {
+    target_sample_rate: f32,
+    /// number of input frames per output frame
+    step: f64,
+    kernel: Kernel,
+    /// input frames not fully consumed yet, per channel
+    history: Vec<Vec<f32>>,
+    /// position of the next output frame in the history
+    position: f64,
+}
+
+impl OutputResampler {
+    pub fn new(
+        source_sample_rate: f32,
+        target_sample_rate: f32,
+        number_of_channels: usize,
+    ) -> Self {
+        let step = f64::from(source_sample_rate) / f64::from(target_sample_rate);
+        let kernel = Kernel::new(ResampleQuality::High, step);
+
+        // the history never exceeds the window of the kernel and a few quanta, prepend silence so
+        // the first output frame is aligned with the first input frame
+        let half_width = kernel.half_width;
+        let capacity = 2 * half_width + (step.ceil() as usize + 2) * RENDER_QUANTUM_SIZE;
+        let history = (0..number_of_channels)
+            .map(|_| {
+                let mut history = Vec::with_capacity(capacity);
+                history.resize(half_width, 0.);
+                history
+            })
+            .collect();
+
+        Self {
+            target_sample_rate,
+            step,
+            kernel,
+            history,
+            position: half_width as f64,
+        }
+    }
+
+    pub fn target_sample_rate(&self) -> f32 {
+        self.target_sample_rate
+    }
+
+    /// Fill the interleaved output buffer, calling `render` whenever another quantum is needed
+    ///
+    /// `render` returns `None` to insert a silent quantum, e.g. when the context is suspended.
+    pub fn process<S: FromSample<f32>>(
+        &mut self,
+        output: &mut [S],
+        mut render: impl FnMut() -> Option<AudioRenderQuantum>,
+    ) {
+        let half_width = self.kernel.half_width;
+
+        for frame in output.chunks_mut(self.history.len()) {
+            while self.position as usize + half_width >= self.history[0].len() {
+                self.drop_consumed();
+                let quantum = render();
+                self.history
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(i, history)| {
+                        match quantum.as_ref().and_then(|q| q.channels().get(i)) {
+                            Some(channel) => history.extend_from_slice(channel.as_ref()),
+                            None => history.resize(history.len() + RENDER_QUANTUM_SIZE, 0.),
+                        }
+                    });
+            }
+
+            let center = self.position as usize;
+            let frac = self.position - center as f64;
+            let first = center + 1 - half_width;
+
+            frame
+                .iter_mut()
+                .zip(self.history.iter())
+                .for_each(|(sample, history)| {
+                    let value = history[first..=center + half_width]
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &sample)| {
+                            let x = frac + (half_width - 1) as f64 - j as f64;
+                            self.kernel.weight(x) * f64::from(sample)
+                        })
+                        .sum::<f64>();
+                    *sample = S::from_sample_(value as f32);
+                });
+
+            self.position += self.step;
+        }
+    }
+
+    /// Drop the frames before the window of the next output frame
+    fn drop_consumed(&mut self) {
+        let half_width = self.kernel.half_width;
+        let consumed = (self.position as usize + 1)
+            .saturating_sub(half_width)
+            .min(self.history[0].len());
+        self.history.iter_mut().for_each(|history| {
+            history.drain(..consumed);
+        });
+        self.position -= consumed as f64;
+    }
+}
 
 /// Sample rate converter and buffer chunk splitter.
 ///
@@ -46,18 +362,113 @@ pub(crate) struct Resampler<I> {
     sample_len: usize,
     /// input stream
     input: I,
-    /// internal buffer
-    buffer: Option<AudioBuffer>,
+    /// quality of the sample rate conversion
+    quality: ResampleQuality,
+    /// sample rate converter, while the input sample rate differs from the desired one
+    converter: Option<Converter>,
+    /// converted frames of each channel, not yet emitted
+    pending: Vec<VecDeque<f32>>,
 }
 
 impl<M: AudioBufferIter> Resampler<M> {
     pub fn new(sample_rate: f32, sample_len: usize, input: M) -> Self {
+        Self::with_quality(sample_rate, sample_len, input, ResampleQuality::default())
+    }
+
+    pub fn with_quality(
+        sample_rate: f32,
+        sample_len: usize,
+        input: M,
+        quality: ResampleQuality,
+    ) -> Self {
         Self {
             sample_rate,
             sample_len,
             input,
-            buffer: None,
+            quality,
+            converter: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Convert a buffer of the input to the desired sample rate
+    ///
+    /// The converter adapts to the sample rate and number of channels of each buffer, so a
+    /// stream changing format is restarted instead of failing.
+    fn convert(&mut self, data: AudioBuffer) -> AudioBuffer {
+        // if the sample rate is very similar, do not resample
+        if float_eq::float_eq!(data.sample_rate(), self.sample_rate, abs <= 0.1)
+            || data.number_of_channels() == 0
+        {
+            self.converter = None;
+            let mut data = data;
+            data.resample(self.sample_rate, ResampleQuality::Linear);
+            return data;
         }
+
+        let converter = match self.converter.take() {
+            Some(converter)
+                if converter.source_sample_rate == data.sample_rate()
+                    && converter.number_of_channels() == data.number_of_channels() =>
+            {
+                converter
+            }
+            _ => Converter::new(
+                data.sample_rate(),
+                self.sample_rate,
+                data.number_of_channels(),
+                self.quality,
+            ),
+        };
+
+        let converter = self.converter.insert(converter);
+        converter.process(&data)
+    }
+
+    /// Convert the frames held back by the converter, at the end of the input
+    fn flush(&mut self) -> Option<AudioBuffer> {
+        self.converter.take().map(|mut converter| converter.flush())
+    }
+}
+
+impl<M: AudioBufferIter> Resampler<M> {
+    /// Number of frames not yet emitted
+    fn pending_len(&self) -> usize {
+        self.pending.first().map_or(0, VecDeque::len)
+    }
+
+    /// Append the frames of a converted buffer
+    fn push(&mut self, data: &AudioBuffer) {
+        if self.pending_len() == 0 {
+            self.pending
+                .resize_with(data.number_of_channels(), VecDeque::new);
+        }
+        assert_eq!(
+            self.pending.len(),
+            data.number_of_channels(),
+            "NotSupportedError - cannot concatenate buffers of different number of channels"
+        );
+
+        self.pending
+            .iter_mut()
+            .zip(data.channels())
+            .for_each(|(pending, channel)| pending.extend(channel.as_slice()));
+    }
+
+    /// Emit the next `sample_len` frames, padded with silence at the end of the input
+    fn take(&mut self) -> AudioBuffer {
+        let len = self.sample_len;
+        let channels = self
+            .pending
+            .iter_mut()
+            .map(|pending| {
+                let mut data: Vec<f32> = pending.drain(..len.min(pending.len())).collect();
+                data.resize(len, 0.);
+                data
+            })
+            .collect();
+
+        AudioBuffer::from(channels, self.sample_rate)
     }
 }
 
@@ -65,48 +476,26 @@ impl<M: AudioBufferIter> Iterator for Resampler<M> {
     type Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut buffer = match self.buffer.take() {
-            None => match self.input.next() {
-                None => return None,
-                Some(Err(e)) => return Some(Err(e)),
-                Some(Ok(mut data)) => {
-                    data.resample(self.sample_rate);
-                    data
-                }
-            },
-            Some(data) => data,
-        };
-
-        while buffer.length() < self.sample_len {
-            // buffer is smaller than desired len
+        while self.pending_len() < self.sample_len {
             match self.input.next() {
                 None => {
-                    let options = AudioBufferOptions {
-                        number_of_channels: buffer.number_of_channels(),
-                        length: self.sample_len - buffer.length(),
-                        sample_rate: self.sample_rate,
-                    };
-
-                    let padding = AudioBuffer::new(options);
-                    buffer.extend(&padding);
-
-                    return Some(Ok(buffer));
+                    if let Some(data) = self.flush() {
+                        self.push(&data);
+                    }
+                    if self.pending_len() == 0 {
+                        return None;
+                    }
+                    break;
                 }
                 Some(Err(e)) => return Some(Err(e)),
-                Some(Ok(mut data)) => {
-                    data.resample(self.sample_rate);
-                    buffer.extend(&data)
+                Some(Ok(data)) => {
+                    let data = self.convert(data);
+                    self.push(&data);
                 }
             }
         }
 
-        if buffer.length() == self.sample_len {
-            return Some(Ok(buffer));
-        }
-
-        self.buffer = Some(buffer.split_off(self.sample_len));
-
-        Some(Ok(buffer))
+        Some(Ok(self.take()))
     }
 }
 
@@ -116,6 +505,7 @@ mod tests {
 
     use super::*;
     use crate::buffer::{AudioBuffer, ChannelData};
+    use crate::render::Alloc;
 
     #[test]
     fn test_resampler_concat() {
@@ -168,4 +558,91 @@ mod tests {
 
         assert!(resampler.next().is_none());
     }
+
+    #[test]
+    fn test_resampler_small_chunks() {
+        // stereo input of 300 chunks of a single frame
+        let input = (0..300).map(|i| Ok(AudioBuffer::from(vec![vec![i as f32]; 2], 44_100.)));
+        let resampler = Resampler::new(44_100., 128, input);
+
+        let output: Vec<_> = resampler.map(Result::unwrap).collect();
+        assert_eq!(output.len(), 3);
+        assert!(output.iter().all(|buffer| buffer.length() == 128));
+        assert!(output.iter().all(|buffer| buffer.number_of_channels() == 2));
+
+        let expected: Vec<f32> = (0..384)
+            .map(|i| if i < 300 { i as f32 } else { 0. })
+            .collect();
+        let frames: Vec<f32> = output
+            .iter()
+            .flat_map(|buffer| buffer.get_channel_data(1).to_vec())
+            .collect();
+        assert_float_eq!(frames[..], expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_resampler_conversion() {
+        // 10 chunks of 10ms of a 1kHz sine, from 44.1kHz to 48kHz
+        let sine = |i: usize, sample_rate: f64| {
+            (2. * std::f64::consts::PI * 1000. * i as f64 / sample_rate).sin() as f32
+        };
+
+        for (quality, tolerance) in [
+            (ResampleQuality::Linear, 5e-3),
+            (ResampleQuality::Medium, 1e-4),
+            (ResampleQuality::High, 1e-5),
+        ] {
+            let input = (0..10).map(|chunk| {
+                let samples = (0..441).map(|i| sine(chunk * 441 + i, 44_100.)).collect();
+                Ok(AudioBuffer::from(vec![samples], 44_100.))
+            });
+            let resampler = Resampler::with_quality(48_000., 128, input, quality);
+
+            let output: Vec<f32> = resampler
+                .flat_map(|buffer| buffer.unwrap().get_channel_data(0).to_vec())
+                .collect();
+            assert!(output.len() >= 4800);
+            assert_eq!(output.len() % 128, 0);
+
+            // no discontinuities at the chunk boundaries
+            let expected: Vec<f32> = (0..4800).map(|i| sine(i, 48_000.)).collect();
+            assert_float_eq!(output[100..4700], expected[100..4700], abs_all <= tolerance);
+        }
+    }
+
+    #[test]
+    fn test_output_resampler() {
+        // 1kHz sine rendered at 44.1kHz, played on a stereo device at 48kHz
+        let sine = |i: usize, sample_rate: f64| {
+            (2. * std::f64::consts::PI * 1000. * i as f64 / sample_rate).sin() as f32
+        };
+
+        let alloc = Alloc::with_capacity(1);
+        let mut resampler = OutputResampler::new(44_100., 48_000., 2);
+        let mut rendered = 0;
+
+        // device buffers are not aligned with the render quanta
+        let mut output = vec![0.; 2 * 4800];
+        for buffer in output.chunks_mut(2 * 300) {
+            resampler.process(buffer, || {
+                let mut channel = alloc.silence();
+                channel
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(i, v)| *v = sine(rendered + i, 44_100.));
+                rendered += RENDER_QUANTUM_SIZE;
+                // a single channel, the other channel of the device is silent
+                Some(AudioRenderQuantum::from(channel))
+            });
+        }
+
+        // the graph keeps rendering at its own sample rate
+        assert!(rendered > 4410 && rendered <= 4410 + 32 + 2 * RENDER_QUANTUM_SIZE);
+
+        let left: Vec<f32> = output.iter().step_by(2).copied().collect();
+        let right: Vec<f32> = output.iter().skip(1).step_by(2).copied().collect();
+        let expected: Vec<f32> = (0..4800).map(|i| sine(i, 48_000.)).collect();
+        assert_float_eq!(left[100..4700], expected[100..4700], abs_all <= 1e-5);
+        assert_float_eq!(right[..], vec![0.; 4800][..], abs_all <= 0.);
+    }
 }
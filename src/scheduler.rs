@@ -0,0 +1,265 @@
+//! Scheduling of control thread callbacks in terms of the context time
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+
+/// Options for constructing a [`Scheduler`]
+#[derive(Clone, Debug)]
+pub struct SchedulerOptions {
+    /// How far ahead of the context time (in seconds) the callbacks are run
+    ///
+    /// The lookahead must be larger than the `interval` plus the jitter of the scheduler thread,
+    /// so the events scheduled by the callbacks reach the render thread in time.
+    pub lookahead: f64,
+    /// Time in seconds between two polls of the queue by the scheduler thread
+    pub interval: f64,
+}
+
+impl Default for SchedulerOptions {
+    fn default() -> Self {
+        Self {
+            lookahead: 0.1,
+            interval: 0.025,
+        }
+    }
+}
+
+struct ScheduledCallback {
+    when: f64,
+    /// insertion order, callbacks scheduled at the same time run in this order
+    index: u64,
+    callback: Box<dyn FnOnce(f64) + Send + 'static>,
+}
+
+impl PartialEq for ScheduledCallback {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScheduledCallback {}
+
+impl PartialOrd for ScheduledCallback {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScheduledCallback {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, the BinaryHeap is a max-heap
+        other
+            .when
+            .total_cmp(&self.when)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+#[derive(Default)]
+struct SchedulerQueue {
+    callbacks: BinaryHeap<ScheduledCallback>,
+    next_index: u64,
+    stopped: bool,
+}
+
+/// Runs callbacks on a dedicated thread, shortly before the context reaches their time
+///
+/// This replaces the hand-rolled "setTimeout" loop of sequencers: the callbacks are tagged with
+/// the context time of the event they schedule, and are run `lookahead` seconds in advance so
+/// they can schedule sample-accurate start times and automations on the graph.
+///
+/// The scheduler thread stops when the `Scheduler` is dropped, pending callbacks are discarded.
+///
+/// This is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::{Scheduler, SchedulerOptions};
+/// use std::sync::Arc;
+///
+/// let context = Arc::new(AudioContext::default());
+/// let scheduler = Scheduler::new(&*context, SchedulerOptions::default());
+///
+/// // a metronome at 120 bpm
+/// let start = context.current_time() + 0.1;
+/// for beat in 0..16 {
+///     let context = Arc::clone(&context);
+///     scheduler.schedule(start + beat as f64 * 0.5, move |when| {
+///         let mut osc = context.create_oscillator();
+///         osc.connect(&context.destination());
+///         osc.start_at(when);
+///         osc.stop_at(when + 0.05);
+///     });
+/// }
+/// ```
+pub struct Scheduler {
+    lookahead: f64,
+    shared: Arc<(Mutex<SchedulerQueue>, Condvar)>,
+}
+
+impl std::fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("lookahead", &self.lookahead)
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Scheduler {
+    /// Create a new `Scheduler` for the given context and spawn its thread
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lookahead or the interval is negative or not finite
+    pub fn new<C: BaseAudioContext>(context: &C, options: SchedulerOptions) -> Self {
+        let SchedulerOptions {
+            lookahead,
+            interval,
+        } = options;
+        crate::assert_valid_time_value(lookahead);
+        crate::assert_valid_time_value(interval);
+
+        let shared: Arc<(Mutex<SchedulerQueue>, Condvar)> = Default::default();
+        let context = context.base().clone();
+        let thread_shared = Arc::clone(&shared);
+        std::thread::spawn(move || {
+            run_scheduler_thread(&context, &thread_shared, lookahead, interval)
+        });
+
+        Self { lookahead, shared }
+    }
+
+    /// How far ahead of the context time (in seconds) the callbacks are run
+    pub fn lookahead(&self) -> f64 {
+        self.lookahead
+    }
+
+    /// Schedule a callback for the given context time
+    ///
+    /// The callback receives `when` as argument, and is run on the scheduler thread once the
+    /// current time of the context is within `lookahead` seconds of `when`. Callbacks for a time
+    /// in the past are run immediately. A callback may schedule further callbacks.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn schedule<F: FnOnce(f64) + Send + 'static>(&self, when: f64, callback: F) {
+        let (queue, condvar) = &*self.shared;
+        let mut queue = queue.lock().unwrap();
+
+        let index = queue.next_index;
+        queue.next_index += 1;
+        queue.callbacks.push(ScheduledCallback {
+            when,
+            index,
+            callback: Box::new(callback),
+        });
+
+        condvar.notify_one();
+    }
+
+    /// Discard all pending callbacks
+    #[allow(clippy::missing_panics_doc)]
+    pub fn clear(&self) {
+        self.shared.0.lock().unwrap().callbacks.clear();
+    }
+
+    /// The number of pending callbacks
+    #[allow(clippy::missing_panics_doc)]
+    pub fn len(&self) -> usize {
+        self.shared.0.lock().unwrap().callbacks.len()
+    }
+
+    /// Returns `true` if no callbacks are pending
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        let (queue, condvar) = &*self.shared;
+        if let Ok(mut queue) = queue.lock() {
+            queue.stopped = true;
+            queue.callbacks.clear();
+        }
+        condvar.notify_one();
+    }
+}
+
+fn run_scheduler_thread(
+    context: &ConcreteBaseAudioContext,
+    shared: &(Mutex<SchedulerQueue>, Condvar),
+    lookahead: f64,
+    interval: f64,
+) {
+    let (queue, condvar) = shared;
+    let interval = Duration::from_secs_f64(interval);
+    let mut due = Vec::new();
+
+    loop {
+        {
+            let mut guard = queue.lock().unwrap();
+            if guard.stopped {
+                break;
+            }
+
+            let horizon = context.current_time() + lookahead;
+            while guard
+                .callbacks
+                .peek()
+                .is_some_and(|entry| entry.when <= horizon)
+            {
+                due.push(guard.callbacks.pop().unwrap());
+            }
+
+            if due.is_empty() {
+                // sleep until the next poll, or until a callback is scheduled
+                let _ = condvar.wait_timeout(guard, interval).unwrap();
+                continue;
+            }
+        }
+
+        // run the callbacks without holding the lock, they may schedule new callbacks
+        due.drain(..).for_each(|entry| (entry.callback)(entry.when));
+    }
+
+    log::debug!("Scheduler thread has been stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::OfflineAudioContext;
+
+    use super::*;
+
+    #[test]
+    fn test_schedule() {
+        // the time of an offline context does not progress before rendering
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let scheduler = Scheduler::new(&context, SchedulerOptions::default());
+        assert!(scheduler.is_empty());
+
+        let (send, recv) = crossbeam_channel::unbounded();
+        for when in [10., 0.05, 0., 0.05] {
+            let send = send.clone();
+            scheduler.schedule(when, move |when| send.send(when).unwrap());
+        }
+
+        let timeout = Duration::from_secs(1);
+        assert_eq!(recv.recv_timeout(timeout), Ok(0.));
+        assert_eq!(recv.recv_timeout(timeout), Ok(0.05));
+        assert_eq!(recv.recv_timeout(timeout), Ok(0.05));
+
+        // beyond the lookahead
+        assert!(recv.recv_timeout(Duration::from_millis(100)).is_err());
+        assert_eq!(scheduler.len(), 1);
+
+        scheduler.clear();
+        assert!(scheduler.is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+//! Named storage of decoded audio assets
+use std::collections::HashMap;
+use std::error::Error;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use crate::buffer::AudioBuffer;
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::media_streams::ResampleQuality;
+
+/// Options for constructing a [`SoundBank`]
+#[derive(Clone, Debug)]
+pub struct SoundBankOptions {
+    /// The maximum amount of memory in bytes the sample data of the bank should occupy.
+    ///
+    /// When the budget is exceeded, the least recently used buffers that are not in use are
+    /// evicted. Buffers that are still referenced elsewhere are never evicted, so the budget may
+    /// be temporarily exceeded.
+    pub memory_budget: usize,
+}
+
+impl Default for SoundBankOptions {
+    fn default() -> Self {
+        Self {
+            memory_budget: usize::MAX,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SoundBankEntry {
+    buffer: AudioBuffer,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct SoundBankInner {
+    entries: HashMap<String, SoundBankEntry>,
+    memory_usage: usize,
+    clock: u64,
+}
+
+impl SoundBankInner {
+    fn insert(&mut self, name: String, buffer: AudioBuffer, memory_budget: usize) {
+        self.clock += 1;
+        self.memory_usage += buffer_size(&buffer);
+
+        let entry = SoundBankEntry {
+            buffer,
+            last_used: self.clock,
+        };
+        if let Some(previous) = self.entries.insert(name, entry) {
+            self.memory_usage -= buffer_size(&previous.buffer);
+        }
+
+        self.evict(memory_budget);
+    }
+
+    fn evict(&mut self, memory_budget: usize) {
+        while self.memory_usage > memory_budget {
+            // find the least recently used buffer that is not referenced outside the bank
+            let candidate = self
+                .entries
+                .iter()
+                .filter(|(_, entry)| !entry.buffer.is_shared())
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(name, _)| name.clone());
+
+            match candidate {
+                Some(name) => {
+                    let entry = self.entries.remove(&name).unwrap();
+                    self.memory_usage -= buffer_size(&entry.buffer);
+                    log::debug!("SoundBank: evicted buffer {:?}", name);
+                }
+                None => break, // all remaining buffers are in use
+            }
+        }
+    }
+}
+
+fn buffer_size(buffer: &AudioBuffer) -> usize {
+    buffer.number_of_channels() * buffer.length() * std::mem::size_of::<f32>()
+}
+
+/// Collection of decoded [`AudioBuffer`]s, managed by name
+///
+/// The sound bank takes care of the lifetime of audio assets: buffers are decoded (possibly in
+/// advance) and resampled to the sample rate of the context, handed out as cheap copy-on-write
+/// clones, and evicted in least recently used order when the memory budget is exceeded. A
+/// buffer is considered in use, and will not be evicted, as long as a clone handed out by the
+/// bank is alive (e.g. when it is set on an `AudioBufferSourceNode`).
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::{SoundBank, SoundBankOptions};
+///
+/// let context = AudioContext::default();
+/// let options = SoundBankOptions {
+///     memory_budget: 64 * 1024 * 1024,
+/// };
+/// let bank = SoundBank::new(&context, options);
+///
+/// let file = std::fs::File::open("samples/sample.wav").unwrap();
+/// let preload = bank.preload("sample", file);
+/// futures::executor::block_on(preload).unwrap();
+///
+/// let mut src = context.create_buffer_source();
+/// src.set_buffer(bank.get("sample").unwrap());
+/// src.connect(&context.destination());
+/// src.start();
+/// ```
+#[derive(Clone, Debug)]
+pub struct SoundBank {
+    context: ConcreteBaseAudioContext,
+    memory_budget: usize,
+    inner: Arc<Mutex<SoundBankInner>>,
+}
+
+impl SoundBank {
+    /// Create a new, empty `SoundBank` for the given context
+    pub fn new<C: BaseAudioContext>(context: &C, options: SoundBankOptions) -> Self {
+        Self {
+            context: context.base().clone(),
+            memory_budget: options.memory_budget,
+            inner: Default::default(),
+        }
+    }
+
+    /// The memory budget in bytes of this bank
+    pub fn memory_budget(&self) -> usize {
+        self.memory_budget
+    }
+
+    /// The amount of memory in bytes currently occupied by the buffers of this bank
+    #[allow(clippy::missing_panics_doc)]
+    pub fn memory_usage(&self) -> usize {
+        self.inner.lock().unwrap().memory_usage
+    }
+
+    /// Add a buffer under the given name, replacing any previous buffer with that name
+    ///
+    /// The buffer is resampled to the sample rate of the context if needed.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn insert(&self, name: impl Into<String>, mut buffer: AudioBuffer) {
+        buffer.resample(self.context.sample_rate(), ResampleQuality::Linear);
+        self.inner
+            .lock()
+            .unwrap()
+            .insert(name.into(), buffer, self.memory_budget);
+    }
+
+    /// Decode the given input and store the resulting buffer under the given name
+    ///
+    /// The buffer is only available in the bank once the returned future has completed. Just
+    /// like [`BaseAudioContext::decode_audio_data`], the current implementation uses blocking IO
+    /// so it's best to run the future on a thread dedicated to blocking operations.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding).
+    #[allow(clippy::missing_panics_doc)]
+    pub fn preload<R: std::io::Read + Send + Sync + 'static>(
+        &self,
+        name: impl Into<String>,
+        input: R,
+    ) -> impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send + 'static {
+        let name = name.into();
+        let memory_budget = self.memory_budget;
+        let inner = Arc::clone(&self.inner);
+        let decode = self.context.decode_audio_data(input);
+
+        async move {
+            let buffer = decode.await?;
+            inner.lock().unwrap().insert(name, buffer, memory_budget);
+            Ok(())
+        }
+    }
+
+    /// Returns a copy of the buffer stored under the given name, if present
+    ///
+    /// The buffer is considered in use for as long as the returned copy (or any clone of it) is
+    /// alive.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn get(&self, name: &str) -> Option<AudioBuffer> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.clock += 1;
+        let clock = inner.clock;
+
+        inner.entries.get_mut(name).map(|entry| {
+            entry.last_used = clock;
+            entry.buffer.clone()
+        })
+    }
+
+    /// Returns true if a buffer is stored under the given name
+    #[allow(clippy::missing_panics_doc)]
+    pub fn contains(&self, name: &str) -> bool {
+        self.inner.lock().unwrap().entries.contains_key(name)
+    }
+
+    /// Returns true if the buffer stored under the given name is referenced outside of the bank
+    #[allow(clippy::missing_panics_doc)]
+    pub fn is_in_use(&self, name: &str) -> bool {
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .get(name)
+            .is_some_and(|entry| entry.buffer.is_shared())
+    }
+
+    /// Remove the buffer stored under the given name from the bank, returning it if present
+    #[allow(clippy::missing_panics_doc)]
+    pub fn remove(&self, name: &str) -> Option<AudioBuffer> {
+        let mut inner = self.inner.lock().unwrap();
+        let entry = inner.entries.remove(name)?;
+        inner.memory_usage -= buffer_size(&entry.buffer);
+        Some(entry.buffer)
+    }
+
+    /// Evict the least recently used buffers that are not in use until the memory usage is
+    /// within the budget again
+    ///
+    /// Eviction runs automatically when a buffer is added, but buffers that were in use at that
+    /// moment may have been released since.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn evict(&self) {
+        self.inner.lock().unwrap().evict(self.memory_budget);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let bank = SoundBank::new(&context, SoundBankOptions::default());
+
+        bank.insert("a", AudioBuffer::from(vec![vec![0.; 100]; 2], 24000.));
+        assert!(bank.contains("a"));
+        assert_eq!(bank.memory_usage(), 2 * 200 * 4);
+
+        let buffer = bank.get("a").unwrap();
+        assert_eq!(buffer.sample_rate(), 48000.);
+        assert!(bank.is_in_use("a"));
+        drop(buffer);
+        assert!(!bank.is_in_use("a"));
+
+        // replace existing entry
+        bank.insert("a", AudioBuffer::from(vec![vec![0.; 10]], 48000.));
+        assert_eq!(bank.memory_usage(), 10 * 4);
+
+        assert!(bank.remove("a").is_some());
+        assert!(!bank.contains("a"));
+        assert_eq!(bank.memory_usage(), 0);
+    }
+
+    #[test]
+    fn test_eviction() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let options = SoundBankOptions {
+            memory_budget: 2 * 100 * 4,
+        };
+        let bank = SoundBank::new(&context, options);
+
+        // "a" is the least recently used, but in use
+        let a = AudioBuffer::from(vec![vec![0.; 100]], 48000.);
+        bank.insert("a", a.clone());
+        bank.insert("b", AudioBuffer::from(vec![vec![0.; 100]], 48000.));
+        bank.insert("c", AudioBuffer::from(vec![vec![0.; 100]], 48000.));
+
+        assert!(bank.contains("a"));
+        assert!(!bank.contains("b"));
+        assert!(bank.contains("c"));
+        assert_eq!(bank.memory_usage(), 2 * 100 * 4);
+    }
+
+    #[test]
+    fn test_preload() {
+        let context = OfflineAudioContext::new(1, 128, 44100.);
+        let bank = SoundBank::new(&context, SoundBankOptions::default());
+
+        let file = std::fs::File::open("samples/sample.wav").unwrap();
+        let future = bank.preload("sample", file);
+        assert!(!bank.contains("sample"));
+
+        futures::executor::block_on(future).unwrap();
+        let buffer = bank.get("sample").unwrap();
+        assert_eq!(buffer.sample_rate(), 44100.);
+        assert_eq!(buffer.length(), 142_187);
+    }
+}
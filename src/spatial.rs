@@ -14,14 +14,16 @@ use crate::render::{
 use std::f32::consts::PI;
 use std::sync::OnceLock;
 
-/// AudioParam settings for the cartesian coordinates
-pub(crate) const PARAM_OPTS: AudioParamDescriptor = AudioParamDescriptor {
-    name: String::new(),
-    min_value: f32::MIN,
-    max_value: f32::MAX,
-    default_value: 0.,
-    automation_rate: AutomationRate::A,
-};
+/// AudioParam settings for the cartesian coordinates, named after their attribute
+pub(crate) fn param_opts(name: &str, default_value: f32) -> AudioParamDescriptor {
+    AudioParamDescriptor {
+        name: name.to_string(),
+        min_value: f32::MIN,
+        max_value: f32::MAX,
+        default_value,
+        automation_rate: AutomationRate::A,
+    }
+}
 
 /// Represents the position and orientation of the person listening to the audio scene
 ///
@@ -71,6 +73,88 @@ impl AudioListener {
     pub fn up_z(&self) -> &AudioParam {
         &self.up_z
     }
+
+    /// Set the position and orientation of the listener from a 4x4 transform matrix
+    ///
+    /// The matrix is in column-major order, as used by OpenGL and most game engines, and maps
+    /// the listener space to the world space: the translation gives the position, the Y axis
+    /// the up vector and the negative Z axis the forward vector. A camera world matrix can thus
+    /// be passed as is.
+    ///
+    /// The 9 params are updated in the same render quantum, so the forward and up vectors never
+    /// tear.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if a component of the matrix is not finite
+    pub fn set_transform(&self, matrix: &[f32; 16]) {
+        assert!(
+            matrix.iter().all(|v| v.is_finite()),
+            "TypeError - The provided value is non-finite."
+        );
+
+        self.set_values(&[
+            (&self.position_x, matrix[12]),
+            (&self.position_y, matrix[13]),
+            (&self.position_z, matrix[14]),
+            (&self.forward_x, -matrix[8]),
+            (&self.forward_y, -matrix[9]),
+            (&self.forward_z, -matrix[10]),
+            (&self.up_x, matrix[4]),
+            (&self.up_y, matrix[5]),
+            (&self.up_z, matrix[6]),
+        ]);
+    }
+
+    /// Set the orientation of the listener from a rotation quaternion
+    ///
+    /// The quaternion rotates the default orientation of the listener, i.e. a forward vector of
+    /// `(0, 0, -1)` and an up vector of `(0, 1, 0)`. It does not need to be normalized.
+    ///
+    /// The 6 orientation params are updated in the same render quantum, so the forward and up
+    /// vectors never tear.
+    ///
+    /// This method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if a component of the quaternion is not finite, or if the quaternion is zero
+    pub fn set_orientation_quaternion(&self, x: f32, y: f32, z: f32, w: f32) {
+        let norm = (x * x + y * y + z * z + w * w).sqrt();
+        assert!(
+            norm.is_finite(),
+            "TypeError - The provided value is non-finite."
+        );
+        assert!(
+            norm > 0.,
+            "RangeError - Cannot derive an orientation from a zero quaternion"
+        );
+        let [x, y, z, w] = [x / norm, y / norm, z / norm, w / norm];
+
+        // second and (negated) third columns of the rotation matrix
+        self.set_values(&[
+            (&self.forward_x, -2. * (x * z + y * w)),
+            (&self.forward_y, -2. * (y * z - x * w)),
+            (&self.forward_z, 2. * (x * x + y * y) - 1.),
+            (&self.up_x, 2. * (x * y - z * w)),
+            (&self.up_y, 1. - 2. * (x * x + z * z)),
+            (&self.up_z, 2. * (y * z + x * w)),
+        ]);
+    }
+
+    /// Set the value of the given params, in the same render quantum
+    fn set_values(&self, values: &[(&AudioParam, f32)]) {
+        self.position_x
+            .registration()
+            .context()
+            .batch_control_msgs(|| {
+                values.iter().for_each(|(param, value)| {
+                    param.set_value(*value);
+                })
+            });
+    }
 }
 
 /// Wrapper for the [`AudioListener`] so it can be placed in the audio graph.
@@ -124,24 +208,19 @@ impl AudioNode for AudioListenerNode {
 impl AudioListenerNode {
     pub fn new<C: BaseAudioContext>(context: &C) -> Self {
         context.base().register(move |registration| {
-            let forward_z_opts = AudioParamDescriptor {
-                default_value: -1.,
-                ..PARAM_OPTS
-            };
-            let up_y_opts = AudioParamDescriptor {
-                default_value: 1.,
-                ..PARAM_OPTS
+            let param = |name, default_value| {
+                context.create_audio_param(param_opts(name, default_value), &registration)
             };
 
-            let (p1, _v1) = context.create_audio_param(PARAM_OPTS, &registration);
-            let (p2, _v2) = context.create_audio_param(PARAM_OPTS, &registration);
-            let (p3, _v3) = context.create_audio_param(PARAM_OPTS, &registration);
-            let (p4, _v4) = context.create_audio_param(PARAM_OPTS, &registration);
-            let (p5, _v5) = context.create_audio_param(PARAM_OPTS, &registration);
-            let (p6, _v6) = context.create_audio_param(forward_z_opts, &registration);
-            let (p7, _v7) = context.create_audio_param(PARAM_OPTS, &registration);
-            let (p8, _v8) = context.create_audio_param(up_y_opts, &registration);
-            let (p9, _v9) = context.create_audio_param(PARAM_OPTS, &registration);
+            let (p1, _v1) = param("positionX", 0.);
+            let (p2, _v2) = param("positionY", 0.);
+            let (p3, _v3) = param("positionZ", 0.);
+            let (p4, _v4) = param("forwardX", 0.);
+            let (p5, _v5) = param("forwardY", 0.);
+            let (p6, _v6) = param("forwardZ", -1.);
+            let (p7, _v7) = param("upX", 0.);
+            let (p8, _v8) = param("upY", 1.);
+            let (p9, _v9) = param("upZ", 0.);
 
             let node = Self {
                 registration,
@@ -302,8 +381,109 @@ pub fn angle(
 mod tests {
     use float_eq::assert_float_eq;
 
+    use crate::context::OfflineAudioContext;
+
     use super::*;
 
+    fn listener_values(listener: &AudioListener) -> [f32; 9] {
+        [
+            listener.position_x().value(),
+            listener.position_y().value(),
+            listener.position_z().value(),
+            listener.forward_x().value(),
+            listener.forward_y().value(),
+            listener.forward_z().value(),
+            listener.up_x().value(),
+            listener.up_y().value(),
+            listener.up_z().value(),
+        ]
+    }
+
+    #[test]
+    fn test_listener_param_names() {
+        let context = OfflineAudioContext::new(2, 128, 48_000.);
+        let listener = context.listener();
+        let names = [
+            listener.position_x().name(),
+            listener.position_y().name(),
+            listener.position_z().name(),
+            listener.forward_x().name(),
+            listener.forward_y().name(),
+            listener.forward_z().name(),
+            listener.up_x().name(),
+            listener.up_y().name(),
+            listener.up_z().name(),
+        ];
+        assert_eq!(
+            names,
+            [
+                "positionX",
+                "positionY",
+                "positionZ",
+                "forwardX",
+                "forwardY",
+                "forwardZ",
+                "upX",
+                "upY",
+                "upZ"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_listener_set_transform() {
+        let mut context = OfflineAudioContext::new(2, 128, 48_000.);
+        let _panner = context.create_panner();
+        let listener = context.listener();
+
+        // rotation of 90 degrees around the Y axis (turn left), then translation
+        #[rustfmt::skip]
+        let matrix = [
+            0., 0., -1., 0.,
+            0., 1., 0., 0.,
+            1., 0., 0., 0.,
+            1., 2., 3., 1.,
+        ];
+        listener.set_transform(&matrix);
+        let expected = [1., 2., 3., -1., 0., 0., 0., 1., 0.];
+        assert_float_eq!(listener_values(&listener), expected, abs_all <= 0.);
+
+        // the batched messages are handled by the render thread
+        let _ = context.start_rendering_sync();
+        assert_float_eq!(listener_values(&listener), expected, abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_listener_set_orientation_quaternion() {
+        let context = OfflineAudioContext::new(2, 128, 48_000.);
+        let listener = context.listener();
+
+        // identity
+        listener.set_orientation_quaternion(0., 0., 0., 2.);
+        let expected = [0., 0., 0., 0., 0., -1., 0., 1., 0.];
+        assert_float_eq!(listener_values(&listener), expected, abs_all <= 1e-6);
+
+        // rotation of 90 degrees around the Y axis (turn left)
+        let half = std::f32::consts::FRAC_PI_4;
+        listener.set_orientation_quaternion(0., half.sin(), 0., half.cos());
+        let expected = [0., 0., 0., -1., 0., 0., 0., 1., 0.];
+        assert_float_eq!(listener_values(&listener), expected, abs_all <= 1e-6);
+
+        // rotation of 90 degrees around the X axis (look up)
+        listener.set_orientation_quaternion(half.sin(), 0., 0., half.cos());
+        let expected = [0., 0., 0., 0., 1., 0., 0., 0., 1.];
+        assert_float_eq!(listener_values(&listener), expected, abs_all <= 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_listener_zero_quaternion() {
+        let context = OfflineAudioContext::new(2, 128, 48_000.);
+        context
+            .listener()
+            .set_orientation_quaternion(0., 0., 0., 0.);
+    }
+
     // listener coordinates/directions
     const LP: [f32; 3] = [0., 0., 0.];
     const LF: [f32; 3] = [0., 0., -1.];
@@ -0,0 +1,438 @@
+//! Audio assets streamed from disk instead of being decoded in memory
+use std::error::Error;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use creek::{ReadDiskStream, ReadError, SeekMode, SymphoniaDecoder};
+
+use crate::render::AudioRenderQuantumChannel;
+use crate::RENDER_QUANTUM_SIZE;
+
+/// Maximum number of frames of the file per output sample, i.e. the maximum effective playback
+/// rate of a stream
+const MAX_STEP: f64 = 16.;
+
+/// Maximum number of frames requested from the disk stream at once
+const READ_FRAMES: usize = 1024;
+
+/// Index of the cache holding the start of the loop in the disk stream, the start of the file is
+/// no longer needed once the playback has started
+const LOOP_CACHE_INDEX: usize = 0;
+
+/// Audio asset streamed from disk, an alternative to an in-memory
+/// [`AudioBuffer`](crate::AudioBuffer) for large files
+///
+/// The file is decoded on a loader thread, ahead of the playhead, so only a small window of it is
+/// kept in memory. Assign it to an
+/// [`AudioBufferSourceNode`](crate::node::AudioBufferSourceNode) with
+/// [`set_streaming_buffer`](crate::node::AudioBufferSourceNode::set_streaming_buffer). A
+/// `StreamingAudioBuffer` is cheap to clone, every source node opens its own stream of the file.
+///
+/// Compared to an `AudioBuffer`, the following limitations apply:
+/// - the samples are not resampled to the sample rate of the context, but linearly interpolated
+/// while rendering
+/// - negative playback rates are not supported, they pause the playback, and the effective
+/// playback rate is limited to 16
+/// - starting at an offset, or looping from a loop start other than the start of the file, may
+/// render silence until the loader thread has caught up
+/// - errors reading the file are reported to the
+/// [`onprocessorerror`](crate::node::AudioNode::set_onprocessorerror) handler of the source node
+///
+/// This type is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::StreamingAudioBuffer;
+///
+/// let context = AudioContext::default();
+/// let buffer = StreamingAudioBuffer::new("samples/sample.wav").unwrap();
+///
+/// let mut src = context.create_buffer_source();
+/// src.set_streaming_buffer(buffer).unwrap();
+/// src.connect(&context.destination());
+/// src.start();
+/// ```
+#[derive(Clone, Debug)]
+pub struct StreamingAudioBuffer {
+    path: Arc<PathBuf>,
+    number_of_channels: usize,
+    length: usize,
+    sample_rate: f32,
+}
+
+impl StreamingAudioBuffer {
+    /// Create a new instance for a given file path
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error if the file cannot be opened or its format is not supported
+    pub fn new<P: Into<PathBuf>>(file: P) -> Result<Self, Box<dyn Error>> {
+        let path = file.into();
+        let stream = ReadDiskStream::<SymphoniaDecoder>::new(path.clone(), 0, Default::default())?;
+        let info = stream.info();
+        let sample_rate = info
+            .sample_rate
+            .ok_or("NotSupportedError - unknown sample rate")?;
+
+        Ok(Self {
+            path: Arc::new(path),
+            number_of_channels: info.num_channels as usize,
+            length: info.num_frames,
+            sample_rate: sample_rate as f32,
+        })
+    }
+
+    /// Number of audio channels of the file
+    pub fn number_of_channels(&self) -> usize {
+        self.number_of_channels
+    }
+
+    /// Number of sample frames of the file
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Sample rate of the file, in Hz
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Duration of the file, in seconds
+    pub fn duration(&self) -> f64 {
+        self.length as f64 / self.sample_rate as f64
+    }
+
+    /// Open a stream of the file, with the start of the file ready to be rendered
+    pub(crate) fn open(&self) -> Result<BufferStream, Box<dyn Error>> {
+        let mut stream =
+            ReadDiskStream::<SymphoniaDecoder>::new(self.path.as_path(), 0, Default::default())?;
+        stream.block_until_ready()?;
+
+        let capacity = (MAX_STEP * RENDER_QUANTUM_SIZE as f64) as usize + 2;
+        let window = (0..self.number_of_channels)
+            .map(|_| Vec::with_capacity(capacity))
+            .collect();
+
+        Ok(BufferStream {
+            stream,
+            length: self.length,
+            sample_rate: self.sample_rate,
+            window,
+            frac: 0.,
+            valid: 0,
+            end_of_file: false,
+            loop_frames: None,
+            error: None,
+        })
+    }
+}
+
+/// Render thread side of a [`StreamingAudioBuffer`]
+pub(crate) struct BufferStream {
+    stream: ReadDiskStream<SymphoniaDecoder>,
+    length: usize,
+    sample_rate: f32,
+    /// Frames read from the stream and not consumed yet, per channel
+    window: Vec<Vec<f32>>,
+    /// Position of the playhead relative to the first frame of the window
+    frac: f64,
+    /// Number of frames of the window read from the file, the rest is padding after its end
+    valid: usize,
+    end_of_file: bool,
+    loop_frames: Option<(usize, usize)>,
+    /// First error of the stream since the last `take_error`
+    error: Option<String>,
+}
+
+impl std::fmt::Debug for BufferStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferStream")
+            .field("number_of_channels", &self.window.len())
+            .field("length", &self.length)
+            .field("sample_rate", &self.sample_rate)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BufferStream {
+    pub fn number_of_channels(&self) -> usize {
+        self.window.len()
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Move the playhead to the given frame, discarding the frames read ahead
+    pub fn seek(&mut self, frame: usize) {
+        let frame = frame.min(self.length);
+        self.window.iter_mut().for_each(Vec::clear);
+        self.frac = 0.;
+        self.valid = 0;
+        self.end_of_file = false;
+        self.jump(frame);
+    }
+
+    /// Error of the stream since the last call, to be reported to the control thread
+    ///
+    /// The render thread does not log, an error is only formatted when it occurs.
+    pub fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
+    fn set_error(&mut self, error: impl FnOnce() -> String) {
+        if self.error.is_none() {
+            self.error = Some(error());
+        }
+    }
+
+    /// Move the playhead of the disk stream, the stream ends if it fails
+    fn jump(&mut self, frame: usize) {
+        if let Err(e) = self.stream.seek(frame, SeekMode::default()) {
+            self.set_error(|| format!("unable to seek to frame {frame}: {e:?}"));
+            self.end_of_file = true;
+        }
+
+        // the tests render faster than real time, let the loader thread catch up
+        #[cfg(test)]
+        let _ = self.stream.block_until_ready();
+    }
+
+    /// Loop between the given start and end frames, or stop looping
+    ///
+    /// The whole file is looped if the loop points are invalid. The start of the loop is cached,
+    /// so the stream does not have to wait for the loader thread when it jumps back.
+    pub fn set_loop(&mut self, loop_frames: Option<(usize, usize)>) {
+        let loop_frames = loop_frames.map(|(start, end)| {
+            let end = end.min(self.length);
+            if start < end {
+                (start, end)
+            } else {
+                (0, self.length)
+            }
+        });
+        // an empty file cannot be looped
+        let loop_frames = loop_frames.filter(|(start, end)| start < end);
+        if loop_frames == self.loop_frames {
+            return;
+        }
+
+        // requesting a cache does not block or allocate, a failure only delays the jump back
+        if let Some((start, _)) = loop_frames {
+            if let Err(e) = self.stream.cache(LOOP_CACHE_INDEX, start) {
+                self.set_error(|| format!("unable to cache frame {start}: {e:?}"));
+            }
+        }
+        self.loop_frames = loop_frames;
+    }
+
+    /// Current position of the playhead, in seconds
+    ///
+    /// The position is approximate right after jumping back to the start of a loop.
+    pub fn position(&self) -> f64 {
+        let pending = (self.window.first().map_or(0, Vec::len) as f64 - self.frac).max(0.);
+        let frame = self.stream.playhead() as f64 - pending;
+        frame.max(0.) / self.sample_rate as f64
+    }
+
+    /// Indicates if the playhead has passed the end of the file
+    pub fn has_ended(&self) -> bool {
+        self.end_of_file && self.frac >= self.valid as f64
+    }
+
+    /// Render the given range of the output, advancing `step` frames of the file per sample
+    pub fn render(
+        &mut self,
+        channels: &mut [AudioRenderQuantumChannel],
+        range: Range<usize>,
+        step: f64,
+    ) {
+        let step = step.clamp(0., MAX_STEP);
+        let count = range.len() as f64;
+        let end = self.frac + count * step;
+        // the last sample interpolates between the frames surrounding its position
+        let last = self.frac + (count - 1.).max(0.) * step;
+        self.fill((end.floor() as usize + 1).max(last.floor() as usize + 2));
+
+        channels
+            .iter_mut()
+            .zip(&self.window)
+            .for_each(|(output, window)| {
+                output[range.clone()]
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(i, o)| {
+                        let position = self.frac + i as f64 * step;
+                        let index = position.floor();
+                        let k = (position - index) as f32;
+                        let index = index as usize;
+                        *o = (1. - k).mul_add(window[index], k * window[index + 1]);
+                    });
+            });
+
+        let consumed = end.floor() as usize;
+        self.window.iter_mut().for_each(|w| {
+            w.drain(..consumed);
+        });
+        self.frac = end - consumed as f64;
+        self.valid = self.valid.saturating_sub(consumed);
+    }
+
+    /// Read frames from the stream until the window holds `frames` frames, the window is padded
+    /// with silence after the end of the file
+    fn fill(&mut self, frames: usize) {
+        while self.window.first().map_or(frames, Vec::len) < frames {
+            let missing = frames - self.window[0].len();
+
+            if self.end_of_file {
+                self.window
+                    .iter_mut()
+                    .for_each(|w| w.resize(w.len() + missing, 0.));
+                break;
+            }
+
+            let playhead = self.stream.playhead();
+            let available = match self.loop_frames {
+                Some((start, end)) if playhead >= end => {
+                    self.jump(start);
+                    continue;
+                }
+                Some((_, end)) => end - playhead,
+                None => usize::MAX,
+            };
+
+            let reached_end_of_file =
+                match self.stream.read(missing.min(available).min(READ_FRAMES)) {
+                    Ok(data) => {
+                        let read = data.num_frames();
+                        self.window
+                            .iter_mut()
+                            .enumerate()
+                            .for_each(|(i, w)| w.extend_from_slice(data.read_channel(i)));
+                        self.valid += read;
+                        data.reached_end_of_file()
+                    }
+                    Err(ReadError::EndOfFile) => true,
+                    // the loader thread is behind, render silence until it catches up
+                    Err(ReadError::IOServerChannelFull) => {
+                        self.window
+                            .iter_mut()
+                            .for_each(|w| w.resize(w.len() + missing, 0.));
+                        self.valid = self.window[0].len();
+                        break;
+                    }
+                    Err(e) => {
+                        // the data read borrows the stream, so only the fields are accessed
+                        if self.error.is_none() {
+                            self.error = Some(format!("unable to read from disk: {e:?}"));
+                        }
+                        self.end_of_file = true;
+                        false
+                    }
+                };
+
+            if reached_end_of_file {
+                match self.loop_frames {
+                    Some((start, _)) => self.jump(start),
+                    None => self.end_of_file = true,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::render::Alloc;
+
+    const FILE: &str = "samples/sample-44100.wav";
+
+    /// First channel of the file, decoded in memory
+    fn decoded() -> Vec<f32> {
+        let context = OfflineAudioContext::new(1, 1, 44_100.);
+        let file = std::fs::File::open(FILE).unwrap();
+        let buffer = context.decode_audio_data_sync(file).unwrap();
+        buffer.get_channel_data(0).to_vec()
+    }
+
+    /// Render the given number of quanta of the first channel of the stream
+    fn render(stream: &mut BufferStream, quanta: usize, step: f64) -> Vec<f32> {
+        let alloc = Alloc::with_capacity(1);
+        let mut channels = vec![alloc.silence(); stream.number_of_channels()];
+
+        let mut output = vec![];
+        for _ in 0..quanta {
+            stream.render(&mut channels, 0..RENDER_QUANTUM_SIZE, step);
+            output.extend_from_slice(&channels[0]);
+        }
+        output
+    }
+
+    #[test]
+    fn test_seek() {
+        let expected = decoded();
+        let mut stream = StreamingAudioBuffer::new(FILE).unwrap().open().unwrap();
+
+        stream.seek(1000);
+        let output = render(&mut stream, 2, 1.);
+        assert_float_eq!(output[..], expected[1000..1256], abs_all <= 1e-6);
+        assert_float_eq!(stream.position(), 1256. / 44_100., abs <= 1e-9);
+        assert!(!stream.has_ended());
+        assert!(stream.take_error().is_none());
+    }
+
+    #[test]
+    fn test_loop() {
+        let expected = decoded();
+        let mut stream = StreamingAudioBuffer::new(FILE).unwrap().open().unwrap();
+
+        // play the start of the file, then loop between frames 1000 and 1100
+        stream.set_loop(Some((1000, 1100)));
+        let output = render(&mut stream, 10, 1.);
+        let looped: Vec<f32> = (0..output.len())
+            .map(|i| match i {
+                0..=1099 => expected[i],
+                _ => expected[1000 + (i - 1100) % 100],
+            })
+            .collect();
+        assert_float_eq!(output[..], looped[..], abs_all <= 1e-6);
+
+        // stop looping, the playback continues after the loop end
+        stream.set_loop(None);
+        let output = render(&mut stream, 1, 1.);
+        assert_float_eq!(output[..20], expected[1080..1100], abs_all <= 1e-6);
+        assert_float_eq!(output[20..], expected[1100..1208], abs_all <= 1e-6);
+        assert!(stream.take_error().is_none());
+    }
+
+    #[test]
+    fn test_playback_rate() {
+        let expected = decoded();
+
+        // double speed, every other frame
+        let mut stream = StreamingAudioBuffer::new(FILE).unwrap().open().unwrap();
+        let output = render(&mut stream, 2, 2.);
+        let faster: Vec<f32> = (0..256).map(|i| expected[2 * i]).collect();
+        assert_float_eq!(output[..], faster[..], abs_all <= 1e-6);
+
+        // half speed, linearly interpolated between the frames
+        let mut stream = StreamingAudioBuffer::new(FILE).unwrap().open().unwrap();
+        let output = render(&mut stream, 2, 0.5);
+        let slower: Vec<f32> = (0..256)
+            .map(|i| (expected[i / 2] + expected[(i + 1) / 2]) / 2.)
+            .collect();
+        assert_float_eq!(output[..], slower[..], abs_all <= 1e-6);
+
+        // a paused stream holds the current frame
+        let output = render(&mut stream, 1, 0.);
+        assert_float_eq!(output[..], [expected[128]; 128][..], abs_all <= 1e-6);
+    }
+}
@@ -0,0 +1,463 @@
+//! Randomized graph mutation stress testing
+//!
+//! [`GraphStress`] performs a reproducible storm of random operations on an audio graph:
+//! creating and dropping nodes, connecting and disconnecting them (including cycles and
+//! connections to `AudioParam`s), starting and stopping sources, changing channel
+//! configurations and scheduling automation events. It can be used to harden custom nodes by
+//! mixing them into the storm with [`GraphStress::set_node_factory`].
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::context::{BaseAudioContext, OfflineAudioContext};
+use crate::node::{
+    AudioNode, AudioScheduledSourceNode, BiquadFilterNode, ChannelCountMode,
+    ChannelInterpretation, ConstantSourceNode, DelayNode, GainNode, OscillatorNode,
+    StereoPannerNode,
+};
+use crate::{AudioBuffer, AudioParam, SeededRng, RENDER_QUANTUM_SIZE};
+
+/// Number of random operations performed per render quantum in [`GraphStress::run_offline`]
+const OPERATIONS_PER_QUANTUM: usize = 4;
+
+/// Options for constructing a [`GraphStress`]
+#[derive(Clone, Debug)]
+pub struct GraphStressOptions {
+    /// The seed of the random generator, a failing run can be replayed by using the same seed
+    pub seed: u64,
+    /// The total number of random operations to perform
+    pub number_of_operations: usize,
+    /// The maximum number of nodes alive at any time
+    pub max_nodes: usize,
+}
+
+impl Default for GraphStressOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            number_of_operations: 1000,
+            max_nodes: 32,
+        }
+    }
+}
+
+type NodeFactory<C> = Box<dyn FnMut(&C) -> Box<dyn AudioNode + Send> + Send>;
+
+enum StressNode {
+    Oscillator(OscillatorNode, SourceState),
+    ConstantSource(ConstantSourceNode, SourceState),
+    Gain(GainNode),
+    Delay(DelayNode),
+    BiquadFilter(BiquadFilterNode),
+    StereoPanner(StereoPannerNode),
+    Custom(Box<dyn AudioNode + Send>),
+}
+
+#[derive(Default)]
+struct SourceState {
+    started: bool,
+    stopped: bool,
+}
+
+impl StressNode {
+    fn node(&self) -> &dyn AudioNode {
+        match self {
+            Self::Oscillator(n, _) => n,
+            Self::ConstantSource(n, _) => n,
+            Self::Gain(n) => n,
+            Self::Delay(n) => n,
+            Self::BiquadFilter(n) => n,
+            Self::StereoPanner(n) => n,
+            Self::Custom(n) => n.as_ref(),
+        }
+    }
+
+    /// A parameter of the node with a sensible value range
+    fn param(&self) -> Option<(&AudioParam, f32, f32)> {
+        match self {
+            Self::Oscillator(n, _) => Some((n.frequency(), 20., 2000.)),
+            Self::ConstantSource(n, _) => Some((n.offset(), -1., 1.)),
+            Self::Gain(n) => Some((n.gain(), 0., 1.)),
+            Self::Delay(n) => Some((n.delay_time(), 0., 1.)),
+            Self::BiquadFilter(n) => Some((n.frequency(), 20., 2000.)),
+            Self::StereoPanner(n) => Some((n.pan(), -1., 1.)),
+            Self::Custom(_) => None,
+        }
+    }
+
+    fn source_mut(&mut self) -> Option<(&mut dyn AudioScheduledSourceNode, &mut SourceState)> {
+        match self {
+            Self::Oscillator(n, s) => Some((n as &mut dyn AudioScheduledSourceNode, s)),
+            Self::ConstantSource(n, s) => Some((n as &mut dyn AudioScheduledSourceNode, s)),
+            _ => None,
+        }
+    }
+}
+
+/// Reproducible storm of random graph mutations
+///
+/// Each call to [`step`](Self::step) performs a single random operation on the given context.
+/// For online contexts (e.g. with the `"none"` sink) the caller drives the storm, while
+/// [`run_offline`](Self::run_offline) runs a complete storm in an [`OfflineAudioContext`].
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, OfflineAudioContext};
+/// use web_audio_api::stress::{GraphStress, GraphStressOptions};
+///
+/// let mut stress = GraphStress::<OfflineAudioContext>::new(GraphStressOptions::default());
+/// // mix your own nodes into the storm
+/// stress.set_node_factory(|context| Box::new(context.create_gain()));
+///
+/// // panics if the rendered output is corrupt or a processor raised an error
+/// let _ = stress.run_offline(44_100.);
+/// ```
+pub struct GraphStress<C> {
+    options: GraphStressOptions,
+    rng: SeededRng,
+    nodes: Vec<StressNode>,
+    factory: Option<NodeFactory<C>>,
+    operations: usize,
+    processor_errors: Arc<AtomicUsize>,
+}
+
+impl<C> std::fmt::Debug for GraphStress<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GraphStress")
+            .field("options", &self.options)
+            .field("nodes", &self.nodes.len())
+            .field("operations", &self.operations)
+            .field(
+                "processor_errors",
+                &self.processor_errors.load(Ordering::Relaxed),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: BaseAudioContext> GraphStress<C> {
+    /// Create a new stress test
+    pub fn new(options: GraphStressOptions) -> Self {
+        Self {
+            rng: SeededRng::new(options.seed),
+            options,
+            nodes: Vec::new(),
+            factory: None,
+            operations: 0,
+            processor_errors: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Register a factory for custom nodes, which will be created alongside the built-in nodes
+    pub fn set_node_factory<F>(&mut self, factory: F)
+    where
+        F: FnMut(&C) -> Box<dyn AudioNode + Send> + Send + 'static,
+    {
+        self.factory = Some(Box::new(factory));
+    }
+
+    /// The number of operations performed so far
+    pub fn operations(&self) -> usize {
+        self.operations
+    }
+
+    /// Returns true when all operations have been performed
+    pub fn is_done(&self) -> bool {
+        self.operations >= self.options.number_of_operations
+    }
+
+    /// The number of processor errors reported by the nodes of the storm
+    pub fn processor_errors(&self) -> usize {
+        self.processor_errors.load(Ordering::Relaxed)
+    }
+
+    /// Perform a single random operation on the given context
+    pub fn step(&mut self, context: &C) {
+        self.operations += 1;
+
+        match self.rng.next_u32() % 8 {
+            0 | 1 => self.create_node(context),
+            2 => self.drop_node(),
+            3 | 4 => self.connect(context),
+            5 => self.disconnect(),
+            6 => self.schedule_source(context),
+            _ => {
+                if self.rng.next_u32() % 2 == 0 {
+                    self.schedule_automation(context)
+                } else {
+                    self.change_channel_config()
+                }
+            }
+        }
+    }
+
+    fn pick(&mut self) -> Option<usize> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        Some(self.rng.next_u32() as usize % self.nodes.len())
+    }
+
+    fn create_node(&mut self, context: &C) {
+        if self.nodes.len() >= self.options.max_nodes {
+            return;
+        }
+
+        let kinds = if self.factory.is_some() { 7 } else { 6 };
+        let node = match self.rng.next_u32() % kinds {
+            0 => StressNode::Oscillator(context.create_oscillator(), SourceState::default()),
+            1 => StressNode::ConstantSource(
+                context.create_constant_source(),
+                SourceState::default(),
+            ),
+            2 => StressNode::Gain(context.create_gain()),
+            3 => StressNode::Delay(context.create_delay(1.)),
+            4 => StressNode::BiquadFilter(context.create_biquad_filter()),
+            5 => StressNode::StereoPanner(context.create_stereo_panner()),
+            _ => StressNode::Custom((self.factory.as_mut().unwrap())(context)),
+        };
+
+        let processor_errors = Arc::clone(&self.processor_errors);
+        node.node().set_onprocessorerror(Box::new(move |e| {
+            log::error!("GraphStress: processor error {:?}", e.message);
+            processor_errors.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        self.nodes.push(node);
+    }
+
+    fn drop_node(&mut self) {
+        if let Some(index) = self.pick() {
+            self.nodes.swap_remove(index);
+        }
+    }
+
+    fn connect(&mut self, context: &C) {
+        let (Some(from), Some(to)) = (self.pick(), self.pick()) else {
+            return;
+        };
+        let target = self.rng.next_u32() % 4;
+
+        let source = self.nodes[from].node();
+        if source.number_of_outputs() == 0 {
+            return;
+        }
+
+        match target {
+            // connect to the destination
+            0 => {
+                source.connect(&context.destination());
+            }
+            // connect to an AudioParam
+            1 => {
+                if let Some((param, _, _)) = self.nodes[to].param() {
+                    source.connect(param);
+                }
+            }
+            // connect to another node, possibly forming a cycle
+            _ => {
+                let dest = self.nodes[to].node();
+                if dest.number_of_inputs() > 0 {
+                    source.connect(dest);
+                }
+            }
+        }
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(index) = self.pick() {
+            self.nodes[index].node().disconnect();
+        }
+    }
+
+    fn schedule_source(&mut self, context: &C) {
+        let Some(index) = self.pick() else {
+            return;
+        };
+
+        let when = context.current_time() + self.rng.next_f64() * 0.1;
+        if let Some((source, state)) = self.nodes[index].source_mut() {
+            if !state.started {
+                source.start_at(when);
+                state.started = true;
+            } else if !state.stopped {
+                source.stop_at(when);
+                state.stopped = true;
+            }
+        }
+    }
+
+    fn schedule_automation(&mut self, context: &C) {
+        let Some(index) = self.pick() else {
+            return;
+        };
+
+        let now = context.current_time();
+        let offsets = [
+            self.rng.next_f64() * 0.1,
+            self.rng.next_f64() * 0.1,
+            self.rng.next_f64() * 0.1,
+        ];
+        let values = [
+            self.rng.next_f32(),
+            self.rng.next_f32(),
+            self.rng.next_f32(),
+        ];
+        let event = self.rng.next_u32() % 4;
+
+        if let Some((param, min, max)) = self.nodes[index].param() {
+            let value = |v: f32| min + v * (max - min);
+
+            match event {
+                0 => {
+                    param.set_value(value(values[0]));
+                }
+                1 => {
+                    param.set_value_at_time(value(values[0]), now + offsets[0]);
+                    param.linear_ramp_to_value_at_time(value(values[1]), now + offsets[1]);
+                }
+                2 => {
+                    param.set_target_at_time(value(values[0]), now + offsets[0], offsets[1]);
+                    param.set_value_at_time(value(values[2]), now + offsets[2]);
+                }
+                _ => {
+                    param.cancel_scheduled_values(now + offsets[0]);
+                }
+            }
+        }
+    }
+
+    fn change_channel_config(&mut self) {
+        let Some(index) = self.pick() else {
+            return;
+        };
+
+        let count = 1 + self.rng.next_u32() as usize % 6;
+        let mode = match self.rng.next_u32() % 3 {
+            0 => ChannelCountMode::Max,
+            1 => ChannelCountMode::ClampedMax,
+            _ => ChannelCountMode::Explicit,
+        };
+        let interpretation = if self.rng.next_u32() % 2 == 0 {
+            ChannelInterpretation::Speakers
+        } else {
+            ChannelInterpretation::Discrete
+        };
+
+        // only touch built-in nodes without channel config constraints
+        if matches!(
+            self.nodes[index],
+            StressNode::Gain(_) | StressNode::Delay(_) | StressNode::BiquadFilter(_)
+        ) {
+            let node = self.nodes[index].node();
+            node.set_channel_count(count);
+            node.set_channel_count_mode(mode);
+            node.set_channel_interpretation(interpretation);
+        }
+    }
+}
+
+impl GraphStress<OfflineAudioContext> {
+    /// Run the complete storm in a new stereo [`OfflineAudioContext`]
+    ///
+    /// The operations are performed in batches at the start of consecutive render quanta. The
+    /// rendered output is returned for further inspection.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the rendered output contains non-finite samples, or when a
+    /// processor error has been reported.
+    pub fn run_offline(self, sample_rate: f32) -> AudioBuffer {
+        let number_of_batches = self
+            .options
+            .number_of_operations
+            .saturating_sub(self.operations)
+            .div_ceil(OPERATIONS_PER_QUANTUM);
+        // suspensions cannot happen at time zero, and leave one quantum to render the result
+        let length = (number_of_batches + 2) * RENDER_QUANTUM_SIZE;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        let stress = Arc::new(Mutex::new(self));
+
+        for batch in 1..=number_of_batches {
+            let stress = Arc::clone(&stress);
+            let when = (batch * RENDER_QUANTUM_SIZE) as f64 / sample_rate as f64;
+
+            context.suspend_sync(when, move |context| {
+                let mut stress = stress.lock().unwrap();
+                for _ in 0..OPERATIONS_PER_QUANTUM {
+                    if !stress.is_done() {
+                        stress.step(context);
+                    }
+                }
+            });
+        }
+
+        let output = context.start_rendering_sync();
+
+        let stress = stress.lock().unwrap();
+        assert_eq!(
+            stress.processor_errors(),
+            0,
+            "GraphStress - processor errors occurred (seed {})",
+            stress.options.seed
+        );
+
+        output.channels().iter().for_each(|channel| {
+            assert!(
+                channel.as_slice().iter().all(|s| s.is_finite()),
+                "GraphStress - rendered output contains non-finite samples (seed {})",
+                stress.options.seed
+            );
+        });
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_storm() {
+        let options = GraphStressOptions {
+            seed: 7,
+            number_of_operations: 500,
+            max_nodes: 16,
+        };
+        let stress = GraphStress::<OfflineAudioContext>::new(options);
+        let output = stress.run_offline(44_100.);
+
+        assert_eq!(output.number_of_channels(), 2);
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let options = GraphStressOptions {
+            seed: 42,
+            number_of_operations: 200,
+            max_nodes: 8,
+        };
+
+        let a = GraphStress::<OfflineAudioContext>::new(options.clone()).run_offline(44_100.);
+        let b = GraphStress::<OfflineAudioContext>::new(options).run_offline(44_100.);
+
+        assert_eq!(a.get_channel_data(0), b.get_channel_data(0));
+        assert_eq!(a.get_channel_data(1), b.get_channel_data(1));
+    }
+
+    #[test]
+    fn test_custom_nodes() {
+        let options = GraphStressOptions {
+            seed: 3,
+            number_of_operations: 200,
+            max_nodes: 8,
+        };
+        let mut stress = GraphStress::<OfflineAudioContext>::new(options);
+        stress.set_node_factory(|context| Box::new(context.create_channel_merger(2)));
+
+        let _ = stress.run_offline(44_100.);
+    }
+}
@@ -0,0 +1,377 @@
+//! Tone matching utility
+
+use std::f32::consts::{LN_2, PI};
+
+use realfft::RealFftPlanner;
+
+use crate::context::BaseAudioContext;
+use crate::node::{AudioNode, BiquadFilterNode, BiquadFilterOptions, BiquadFilterType};
+use crate::AudioBuffer;
+
+/// FFT size used to measure the long-term spectra
+const FFT_SIZE: usize = 4096;
+
+/// Options for constructing a [`ToneMatch`]
+#[derive(Clone, Debug)]
+pub struct ToneMatchOptions {
+    /// Number of correction bands per octave
+    pub bands_per_octave: usize,
+    /// Center frequency of the lowest correction band in Hz
+    pub min_frequency: f32,
+    /// Upper bound of the center frequency of the highest correction band in Hz
+    pub max_frequency: f32,
+    /// Maximum boost or cut applied by a single band in dB
+    pub max_gain: f32,
+    /// Amount of correction, from 0 (no correction) to 1 (full correction)
+    pub strength: f32,
+}
+
+impl Default for ToneMatchOptions {
+    fn default() -> Self {
+        Self {
+            bands_per_octave: 3,
+            min_frequency: 31.25,
+            max_frequency: 16_000.,
+            max_gain: 12.,
+            strength: 1.,
+        }
+    }
+}
+
+/// A single band of the correction EQ
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMatchBand {
+    /// Center frequency in Hz
+    pub frequency: f32,
+    /// Boost (positive) or cut (negative) in dB
+    pub gain: f32,
+    /// Quality factor of the peaking filter
+    pub q: f32,
+}
+
+/// Correction EQ matching the tonal balance of a signal to a reference
+///
+/// The long-term average spectra of both signals are measured and compared in fractional octave
+/// bands. The difference is turned into a chain of peaking [`BiquadFilterNode`]s, to be inserted
+/// on the bus carrying the source signal, e.g. to match stems to each other or a mix to a
+/// reference track.
+///
+/// Only the spectral balance is matched, the overall level difference is removed from the
+/// correction.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::{ToneMatch, ToneMatchOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let file = File::open("samples/sample.wav").unwrap();
+/// let source = context.decode_audio_data_sync(file).unwrap();
+/// let file = File::open("samples/reference.wav").unwrap();
+/// let reference = context.decode_audio_data_sync(file).unwrap();
+///
+/// let tone_match = ToneMatch::new(&source, &reference, ToneMatchOptions::default());
+/// let filters = tone_match.create_filters(&context);
+/// filters.last().unwrap().connect(&context.destination());
+///
+/// let mut src = context.create_buffer_source();
+/// src.set_buffer(source);
+/// src.connect(&filters[0]);
+/// src.start();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ToneMatch {
+    bands: Vec<ToneMatchBand>,
+}
+
+impl ToneMatch {
+    /// Measure the `source` and `reference` signals and compute the correction EQ
+    ///
+    /// The buffers may have different sample rates and channel counts, channels are mixed down
+    /// before analysis.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - `bands_per_octave` is zero
+    /// - `min_frequency` is not strictly positive or larger than `max_frequency`
+    /// - `strength` is outside the [0, 1] range
+    pub fn new(source: &AudioBuffer, reference: &AudioBuffer, options: ToneMatchOptions) -> Self {
+        let ToneMatchOptions {
+            bands_per_octave,
+            min_frequency,
+            max_frequency,
+            max_gain,
+            strength,
+        } = options;
+
+        assert!(
+            bands_per_octave > 0,
+            "RangeError - bands_per_octave should be strictly positive"
+        );
+        assert!(
+            min_frequency > 0. && min_frequency <= max_frequency,
+            "RangeError - invalid frequency range [{:?}, {:?}]",
+            min_frequency,
+            max_frequency
+        );
+        assert!(
+            (0. ..=1.).contains(&strength),
+            "RangeError - strength ({:?}) should be in the range [0, 1]",
+            strength
+        );
+
+        // bandwidth of a band in octaves, and the matching peaking filter Q
+        let bandwidth = 1. / bands_per_octave as f32;
+        let q = 1. / (2. * (LN_2 / 2. * bandwidth).sinh());
+
+        let nyquist = source.sample_rate().min(reference.sample_rate()) / 2.;
+        let max_frequency = max_frequency.min(nyquist * 0.9);
+
+        let source_spectrum = long_term_spectrum(source);
+        let reference_spectrum = long_term_spectrum(reference);
+
+        let mut bands: Vec<ToneMatchBand> = (0..)
+            .map(|i| min_frequency * 2_f32.powf(i as f32 / bands_per_octave as f32))
+            .take_while(|&frequency| frequency <= max_frequency)
+            .map(|frequency| {
+                let low = frequency * 2_f32.powf(-bandwidth / 2.);
+                let high = frequency * 2_f32.powf(bandwidth / 2.);
+
+                let source_power = band_power(&source_spectrum, source.sample_rate(), low, high);
+                let reference_power =
+                    band_power(&reference_spectrum, reference.sample_rate(), low, high);
+
+                let gain = if source_power > 0. && reference_power > 0. {
+                    10. * (reference_power / source_power).log10()
+                } else {
+                    0.
+                };
+
+                ToneMatchBand { frequency, gain, q }
+            })
+            .collect();
+
+        // remove the overall level difference, only the balance is matched
+        if !bands.is_empty() {
+            let mean = bands.iter().map(|b| b.gain).sum::<f32>() / bands.len() as f32;
+            bands.iter_mut().for_each(|band| {
+                band.gain = ((band.gain - mean) * strength).clamp(-max_gain, max_gain);
+            });
+        }
+
+        Self { bands }
+    }
+
+    /// The bands of the correction EQ, from low to high frequencies
+    pub fn bands(&self) -> &[ToneMatchBand] {
+        &self.bands
+    }
+
+    /// Options for constructing the peaking filters of the correction EQ
+    pub fn filter_options(&self) -> Vec<BiquadFilterOptions> {
+        self.bands
+            .iter()
+            .map(|band| BiquadFilterOptions {
+                type_: BiquadFilterType::Peaking,
+                frequency: band.frequency,
+                gain: band.gain,
+                q: band.q,
+                ..BiquadFilterOptions::default()
+            })
+            .collect()
+    }
+
+    /// Create the correction EQ as a chain of peaking filters connected in series
+    ///
+    /// Connect the signal to the first filter, and the last filter to the destination.
+    pub fn create_filters<C: BaseAudioContext>(&self, context: &C) -> Vec<BiquadFilterNode> {
+        let filters: Vec<_> = self
+            .filter_options()
+            .into_iter()
+            .map(|options| BiquadFilterNode::new(context, options))
+            .collect();
+
+        filters.windows(2).for_each(|pair| {
+            pair[0].connect(&pair[1]);
+        });
+
+        filters
+    }
+}
+
+/// Average power spectrum of the mixed down buffer, using Hann windowed frames with 50% overlap
+fn long_term_spectrum(buffer: &AudioBuffer) -> Vec<f32> {
+    let number_of_channels = buffer.number_of_channels();
+    let length = buffer.length();
+
+    let mut planner = RealFftPlanner::<f32>::new();
+    let r2c = planner.plan_fft_forward(FFT_SIZE);
+    let mut input = r2c.make_input_vec();
+    let mut output = r2c.make_output_vec();
+
+    let window: Vec<f32> = (0..FFT_SIZE)
+        .map(|i| 0.5 - 0.5 * (2. * PI * i as f32 / FFT_SIZE as f32).cos())
+        .collect();
+
+    let mut spectrum = vec![0.; output.len()];
+    let mut number_of_frames = 0;
+
+    let hop = FFT_SIZE / 2;
+    let mut start = 0;
+    loop {
+        // zero pad the last (or only) frame
+        input.iter_mut().enumerate().for_each(|(i, s)| {
+            let index = start + i;
+            *s = if index < length {
+                let sum: f32 = (0..number_of_channels)
+                    .map(|c| buffer.get_channel_data(c)[index])
+                    .sum();
+                sum / number_of_channels as f32 * window[i]
+            } else {
+                0.
+            };
+        });
+
+        r2c.process(&mut input, &mut output).unwrap();
+        spectrum
+            .iter_mut()
+            .zip(output.iter())
+            .for_each(|(p, c)| *p += c.norm_sqr());
+        number_of_frames += 1;
+
+        start += hop;
+        if start + FFT_SIZE > length {
+            break;
+        }
+    }
+
+    spectrum
+        .iter_mut()
+        .for_each(|p| *p /= number_of_frames as f32);
+
+    spectrum
+}
+
+/// Average power of the spectrum bins in the [low, high) frequency range
+fn band_power(spectrum: &[f32], sample_rate: f32, low: f32, high: f32) -> f32 {
+    let bin_width = sample_rate / FFT_SIZE as f32;
+    let last_bin = spectrum.len() - 1;
+
+    let first = ((low / bin_width).ceil() as usize).min(last_bin);
+    let last = ((high / bin_width).ceil() as usize).min(last_bin + 1);
+
+    if last > first {
+        spectrum[first..last].iter().sum::<f32>() / (last - first) as f32
+    } else {
+        // narrow band at low frequencies, use the nearest bin
+        let center = ((low * high).sqrt() / bin_width).round() as usize;
+        spectrum[center.min(last_bin)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::SeededRng;
+
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 44_100.;
+    const LENGTH: usize = 44_100;
+
+    fn noise() -> AudioBuffer {
+        let mut rng = SeededRng::new(0);
+        let data: Vec<f32> = (0..LENGTH).map(|_| rng.next_bipolar() * 0.5).collect();
+        AudioBuffer::from(vec![data], SAMPLE_RATE)
+    }
+
+    fn lowpass(buffer: &AudioBuffer) -> AudioBuffer {
+        let mut context = OfflineAudioContext::new(1, LENGTH, SAMPLE_RATE);
+
+        let filter = context.create_biquad_filter();
+        filter.frequency().set_value(1000.);
+        filter.connect(&context.destination());
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer.clone());
+        src.connect(&filter);
+        src.start();
+
+        context.start_rendering_sync()
+    }
+
+    #[test]
+    fn test_same_balance() {
+        let source = noise();
+
+        // level difference only, no correction
+        let mut reference = source.clone();
+        reference
+            .get_channel_data_mut(0)
+            .iter_mut()
+            .for_each(|s| *s *= 0.25);
+
+        let tone_match = ToneMatch::new(&source, &reference, ToneMatchOptions::default());
+        assert_eq!(tone_match.bands().len(), 28); // 31.25Hz to 16kHz in third octaves
+        tone_match
+            .bands()
+            .iter()
+            .for_each(|band| assert_float_eq!(band.gain, 0., abs <= 1e-3));
+    }
+
+    #[test]
+    fn test_match_lowpass() {
+        let source = noise();
+        let reference = lowpass(&source);
+
+        let tone_match = ToneMatch::new(&source, &reference, ToneMatchOptions::default());
+        let bands = tone_match.bands();
+
+        // relative to the overall balance, the low bands are boosted and the high bands are cut
+        let low = bands.iter().find(|b| b.frequency >= 250.).unwrap();
+        let high = bands.iter().find(|b| b.frequency >= 8000.).unwrap();
+        assert!(low.gain > 0.);
+        assert_float_eq!(high.gain, -12., abs <= 0.);
+
+        // no correction at zero strength
+        let options = ToneMatchOptions {
+            strength: 0.,
+            ..ToneMatchOptions::default()
+        };
+        let neutral = ToneMatch::new(&source, &reference, options);
+        neutral
+            .bands()
+            .iter()
+            .for_each(|band| assert_float_eq!(band.gain, 0., abs <= 0.));
+    }
+
+    #[test]
+    fn test_create_filters() {
+        let source = noise();
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+
+        let options = ToneMatchOptions {
+            bands_per_octave: 1,
+            min_frequency: 125.,
+            max_frequency: 4000.,
+            ..ToneMatchOptions::default()
+        };
+        let tone_match = ToneMatch::new(&source, &source, options);
+        let filters = tone_match.create_filters(&context);
+
+        assert_eq!(filters.len(), 6);
+        filters.iter().zip(tone_match.bands()).for_each(|(f, b)| {
+            assert_eq!(f.type_(), BiquadFilterType::Peaking);
+            assert_float_eq!(f.frequency().value(), b.frequency, abs <= 0.);
+            assert_float_eq!(f.q().value(), 1.4142135, abs <= 1e-3);
+        });
+    }
+}
@@ -0,0 +1,257 @@
+//! Conversion of musical and normalized units to param values
+//!
+//! Node options and [`AudioParam`] values are expressed in Hz, seconds or linear gain, which
+//! makes a preset depend on the sample rate and tempo it was created with. A preset expressed
+//! in [`UnitValue`]s instead (e.g. a filter cutoff as MIDI note, a delay time in beats) is
+//! resolved by a [`UnitConverter`] when it is applied, against the sample rate of the context
+//! and the current tempo.
+//!
+//! These helpers are not part of the Web Audio API specification.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use web_audio_api::context::{AudioContext, BaseAudioContext};
+//! use web_audio_api::units::{UnitConverter, UnitValue};
+//!
+//! let context = AudioContext::default();
+//! let mut units = UnitConverter::from_context(&context);
+//! units.set_bpm(96.);
+//!
+//! // a dotted eighth note echo, whatever the tempo
+//! let delay = context.create_delay(2.);
+//! units.set_value(delay.delay_time(), UnitValue::Beats(0.75));
+//!
+//! // the cutoff follows the tuning, not the sample rate
+//! let filter = context.create_biquad_filter();
+//! units.set_value(filter.frequency(), UnitValue::MidiNote(84.));
+//!
+//! // schedule a filter sweep to C8 over the next 4 beats
+//! let end_time = context.current_time() + units.seconds(UnitValue::Beats(4.));
+//! filter
+//!     .frequency()
+//!     .exponential_ramp_to_value_at_time(units.resolve(UnitValue::MidiNote(108.)), end_time);
+//! ```
+
+use crate::context::BaseAudioContext;
+use crate::AudioParam;
+
+/// A value expressed in a unit that is independent of the sample rate or tempo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnitValue {
+    /// Value in the native unit of the param, passed through unchanged
+    Value(f32),
+    /// Frequency in Hz
+    Hertz(f32),
+    /// Frequency as a (fractional) MIDI note number, note 69 is A4
+    MidiNote(f32),
+    /// Frequency relative to the Nyquist frequency, in the [0, 1] range
+    Normalized(f32),
+    /// Duration in seconds
+    Seconds(f64),
+    /// Duration in beats, at the tempo of the converter
+    Beats(f64),
+    /// Duration in sample frames, at the sample rate of the converter
+    Samples(f64),
+    /// Gain in dB, resolved to a linear gain
+    Decibels(f32),
+}
+
+/// Resolves [`UnitValue`]s to param values given a sample rate and tempo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitConverter {
+    sample_rate: f32,
+    bpm: f64,
+    tuning: f32,
+}
+
+impl UnitConverter {
+    /// Create a new converter for the given sample rate, at 120 BPM with A4 tuned to 440 Hz
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        crate::assert_valid_sample_rate(sample_rate);
+
+        Self {
+            sample_rate,
+            bpm: 120.,
+            tuning: 440.,
+        }
+    }
+
+    /// Create a new converter for the sample rate of the given context
+    #[must_use]
+    pub fn from_context<C: BaseAudioContext>(context: &C) -> Self {
+        Self::new(context.sample_rate())
+    }
+
+    /// The sample rate used to resolve [`UnitValue::Normalized`] and [`UnitValue::Samples`]
+    #[must_use]
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// The tempo in beats per minute used to resolve [`UnitValue::Beats`]
+    #[must_use]
+    pub fn bpm(&self) -> f64 {
+        self.bpm
+    }
+
+    /// Update the tempo in beats per minute
+    ///
+    /// Values already resolved, e.g. scheduled automation events, are not affected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bpm` is not strictly positive and finite
+    pub fn set_bpm(&mut self, bpm: f64) {
+        assert!(
+            bpm > 0. && bpm.is_finite(),
+            "RangeError - tempo should be strictly positive and finite, got {}",
+            bpm
+        );
+        self.bpm = bpm;
+    }
+
+    /// The frequency of A4 in Hz used to resolve [`UnitValue::MidiNote`]
+    #[must_use]
+    pub fn tuning(&self) -> f32 {
+        self.tuning
+    }
+
+    /// Update the frequency of A4 in Hz
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tuning` is not strictly positive and finite
+    pub fn set_tuning(&mut self, tuning: f32) {
+        assert!(
+            tuning > 0. && tuning.is_finite(),
+            "RangeError - tuning should be strictly positive and finite, got {}",
+            tuning
+        );
+        self.tuning = tuning;
+    }
+
+    /// Resolve the value in the native unit of a param: Hz for frequencies, seconds for
+    /// durations and linear gain for [`UnitValue::Decibels`]
+    #[must_use]
+    pub fn resolve(&self, value: UnitValue) -> f32 {
+        match value {
+            UnitValue::Value(v) | UnitValue::Hertz(v) => v,
+            UnitValue::MidiNote(note) => self.tuning * 2_f32.powf((note - 69.) / 12.),
+            UnitValue::Normalized(v) => v * self.sample_rate / 2.,
+            UnitValue::Seconds(_) | UnitValue::Beats(_) | UnitValue::Samples(_) => {
+                self.seconds(value) as f32
+            }
+            UnitValue::Decibels(db) => 10_f32.powf(db / 20.),
+        }
+    }
+
+    /// Resolve a duration in seconds, e.g. to compute automation times
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not a duration, [`UnitValue::Value`] is interpreted as seconds
+    #[must_use]
+    pub fn seconds(&self, value: UnitValue) -> f64 {
+        match value {
+            UnitValue::Value(v) => f64::from(v),
+            UnitValue::Seconds(s) => s,
+            UnitValue::Beats(beats) => beats * 60. / self.bpm,
+            UnitValue::Samples(frames) => frames / f64::from(self.sample_rate),
+            _ => panic!("TypeError - {:?} is not a duration", value),
+        }
+    }
+
+    /// Set the value of the param, see [`AudioParam::set_value`]
+    pub fn set_value<'a>(&self, param: &'a AudioParam, value: UnitValue) -> &'a AudioParam {
+        param.set_value(self.resolve(value))
+    }
+
+    /// Schedule a change of the value of the param, see [`AudioParam::set_value_at_time`]
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `start_time` is negative
+    pub fn set_value_at_time<'a>(
+        &self,
+        param: &'a AudioParam,
+        value: UnitValue,
+        start_time: f64,
+    ) -> &'a AudioParam {
+        param.set_value_at_time(self.resolve(value), start_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+
+    use super::*;
+
+    #[test]
+    fn test_frequencies() {
+        let mut units = UnitConverter::new(48_000.);
+
+        assert_float_eq!(units.resolve(UnitValue::Hertz(1000.)), 1000., abs <= 0.);
+        assert_float_eq!(units.resolve(UnitValue::MidiNote(69.)), 440., abs <= 1e-4);
+        assert_float_eq!(units.resolve(UnitValue::MidiNote(81.)), 880., abs <= 1e-3);
+        assert_float_eq!(
+            units.resolve(UnitValue::Normalized(0.5)),
+            12_000.,
+            abs <= 0.
+        );
+
+        units.set_tuning(432.);
+        assert_float_eq!(units.resolve(UnitValue::MidiNote(69.)), 432., abs <= 1e-4);
+    }
+
+    #[test]
+    fn test_durations() {
+        let mut units = UnitConverter::new(48_000.);
+
+        assert_float_eq!(units.seconds(UnitValue::Seconds(0.5)), 0.5, abs <= 0.);
+        assert_float_eq!(units.seconds(UnitValue::Beats(1.)), 0.5, abs <= 0.);
+        assert_float_eq!(units.seconds(UnitValue::Samples(480.)), 0.01, abs <= 1e-12);
+
+        units.set_bpm(90.);
+        assert_float_eq!(units.seconds(UnitValue::Beats(3.)), 2., abs <= 1e-12);
+        assert_float_eq!(units.resolve(UnitValue::Beats(3.)), 2., abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_decibels() {
+        let units = UnitConverter::new(48_000.);
+        assert_float_eq!(units.resolve(UnitValue::Decibels(0.)), 1., abs <= 0.);
+        assert_float_eq!(units.resolve(UnitValue::Decibels(-20.)), 0.1, abs <= 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_frequency_is_not_a_duration() {
+        let units = UnitConverter::new(48_000.);
+        let _ = units.seconds(UnitValue::Hertz(1.));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_bpm() {
+        let mut units = UnitConverter::new(48_000.);
+        units.set_bpm(0.);
+    }
+
+    #[test]
+    fn test_preset_survives_sample_rate() {
+        // the same preset resolves to the same frequency at any sample rate
+        for sample_rate in [44_100., 96_000.] {
+            let context = OfflineAudioContext::new(1, 1, sample_rate);
+            let units = UnitConverter::from_context(&context);
+
+            let filter = context.create_biquad_filter();
+            units.set_value(filter.frequency(), UnitValue::MidiNote(93.));
+            assert_float_eq!(filter.frequency().value(), 1760., abs <= 1e-2);
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//! Allocation of polyphonic voices
+
+/// A voice of a polyphonic instrument, i.e. a user-built subgraph playing one note at a time
+///
+/// The implementor owns the nodes of the voice (e.g. an oscillator, a filter and an envelope) and
+/// schedules them when a note is started or stopped.
+pub trait Voice {
+    /// Start playing the given MIDI note at time `when`, with a velocity in the range `[0, 1]`
+    fn note_on(&mut self, note: u8, velocity: f32, when: f64);
+
+    /// Release the note played by this voice at time `when`
+    fn note_off(&mut self, when: f64);
+
+    /// Time in seconds the voice keeps sounding after [`note_off`](Self::note_off)
+    ///
+    /// The voice is not considered free before the release has ended, unless it is stolen.
+    /// Defaults to zero.
+    fn release_time(&self) -> f64 {
+        0.
+    }
+}
+
+/// Policy used by the [`VoiceManager`] to pick a voice for a new note when all voices are busy
+///
+/// Voices that are releasing are always stolen before voices with a held note, the policy
+/// decides which voice is stolen among them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VoiceStealing {
+    /// Steal the voice that started its note first
+    Oldest,
+    /// Steal the voice playing the lowest note
+    Lowest,
+    /// Steal the voice playing the highest note
+    Highest,
+    /// Never steal a voice, the new note is dropped
+    None,
+}
+
+impl Default for VoiceStealing {
+    fn default() -> Self {
+        Self::Oldest
+    }
+}
+
+/// Options for constructing a [`VoiceManager`]
+#[derive(Clone, Debug, Default)]
+pub struct VoiceManagerOptions {
+    /// The voice stealing policy
+    pub stealing: VoiceStealing,
+}
+
+#[derive(Debug)]
+struct VoiceSlot<V> {
+    voice: V,
+    /// The note played (or released) by the voice, `None` if the voice never played
+    note: Option<u8>,
+    held: bool,
+    /// Allocation order, used to find the oldest voice
+    started: u64,
+    /// End time of the release of the last note
+    free_at: f64,
+}
+
+impl<V> VoiceSlot<V> {
+    fn is_free(&self, when: f64) -> bool {
+        self.note.is_none() || (!self.held && when >= self.free_at)
+    }
+}
+
+/// Pool of voices of a polyphonic instrument
+///
+/// The manager assigns incoming notes to free voices, steals a voice according to the
+/// [`VoiceStealing`] policy when all voices are busy, and routes note releases and parameter
+/// changes to the voice playing a given note.
+///
+/// This is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, GainNode, OscillatorNode};
+/// use web_audio_api::{Voice, VoiceManager, VoiceManagerOptions};
+///
+/// struct SineVoice {
+///     osc: OscillatorNode,
+///     amp: GainNode,
+/// }
+///
+/// impl Voice for SineVoice {
+///     fn note_on(&mut self, note: u8, velocity: f32, when: f64) {
+///         let frequency = 440. * 2_f32.powf((note as f32 - 69.) / 12.);
+///         self.osc.frequency().set_value_at_time(frequency, when);
+///         self.amp.gain().cancel_scheduled_values(when);
+///         self.amp.gain().set_target_at_time(velocity, when, 0.005);
+///     }
+///
+///     fn note_off(&mut self, when: f64) {
+///         self.amp.gain().cancel_scheduled_values(when);
+///         self.amp.gain().set_target_at_time(0., when, 0.05);
+///     }
+///
+///     fn release_time(&self) -> f64 {
+///         0.25
+///     }
+/// }
+///
+/// let context = AudioContext::default();
+///
+/// let voices = (0..8).map(|_| {
+///     let mut osc = context.create_oscillator();
+///     let amp = context.create_gain();
+///     amp.gain().set_value(0.);
+///     osc.connect(&amp);
+///     amp.connect(&context.destination());
+///     osc.start();
+///     SineVoice { osc, amp }
+/// });
+/// let mut manager = VoiceManager::new(voices, VoiceManagerOptions::default());
+///
+/// let now = context.current_time();
+/// manager.note_on(60, 0.8, now);
+/// manager.note_on(64, 0.8, now);
+/// manager.note_off(60, now + 1.);
+/// manager.note_off(64, now + 1.);
+/// ```
+#[derive(Debug)]
+pub struct VoiceManager<V> {
+    slots: Vec<VoiceSlot<V>>,
+    stealing: VoiceStealing,
+    clock: u64,
+}
+
+impl<V: Voice> VoiceManager<V> {
+    /// Create a new `VoiceManager` from a pool of voices
+    ///
+    /// The number of voices determines the polyphony of the instrument.
+    pub fn new<I: IntoIterator<Item = V>>(voices: I, options: VoiceManagerOptions) -> Self {
+        let slots = voices
+            .into_iter()
+            .map(|voice| VoiceSlot {
+                voice,
+                note: None,
+                held: false,
+                started: 0,
+                free_at: 0.,
+            })
+            .collect();
+
+        Self {
+            slots,
+            stealing: options.stealing,
+            clock: 0,
+        }
+    }
+
+    /// The maximum number of notes that can play at the same time
+    pub fn polyphony(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// The voice stealing policy
+    pub fn stealing(&self) -> VoiceStealing {
+        self.stealing
+    }
+
+    /// Update the voice stealing policy
+    pub fn set_stealing(&mut self, value: VoiceStealing) {
+        self.stealing = value;
+    }
+
+    /// Start a note at time `when`, returns the voice playing it
+    ///
+    /// A note that is already held is retriggered on the same voice. Returns `None` when all
+    /// voices are busy and the stealing policy is [`VoiceStealing::None`].
+    pub fn note_on(&mut self, note: u8, velocity: f32, when: f64) -> Option<&mut V> {
+        let index = self.find_voice(note, when)?;
+
+        self.clock += 1;
+        let slot = &mut self.slots[index];
+        slot.note = Some(note);
+        slot.held = true;
+        slot.started = self.clock;
+        slot.voice.note_on(note, velocity, when);
+
+        Some(&mut slot.voice)
+    }
+
+    /// Release a note at time `when`
+    ///
+    /// Does nothing if the note is not held.
+    pub fn note_off(&mut self, note: u8, when: f64) {
+        if let Some(slot) = self
+            .slots
+            .iter_mut()
+            .find(|slot| slot.held && slot.note == Some(note))
+        {
+            slot.release(when);
+        }
+    }
+
+    /// Release all held notes at time `when`
+    pub fn all_notes_off(&mut self, when: f64) {
+        self.slots
+            .iter_mut()
+            .filter(|slot| slot.held)
+            .for_each(|slot| slot.release(when));
+    }
+
+    /// The notes that are currently held, in the order they were started
+    pub fn held_notes(&self) -> Vec<u8> {
+        let mut held: Vec<_> = self.slots.iter().filter(|slot| slot.held).collect();
+        held.sort_by_key(|slot| slot.started);
+        held.into_iter().filter_map(|slot| slot.note).collect()
+    }
+
+    /// The voice holding the given note, e.g. to set its parameters
+    pub fn voice_for_note(&mut self, note: u8) -> Option<&mut V> {
+        self.slots
+            .iter_mut()
+            .find(|slot| slot.held && slot.note == Some(note))
+            .map(|slot| &mut slot.voice)
+    }
+
+    /// All voices of the pool, e.g. to set a parameter on every voice
+    pub fn voices_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.slots.iter_mut().map(|slot| &mut slot.voice)
+    }
+
+    fn find_voice(&self, note: u8, when: f64) -> Option<usize> {
+        // retrigger a held note on its own voice
+        if let Some(index) = self
+            .slots
+            .iter()
+            .position(|slot| slot.held && slot.note == Some(note))
+        {
+            return Some(index);
+        }
+
+        // pick the free voice that was least recently used
+        let free = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_free(when))
+            .min_by_key(|(_, slot)| slot.started)
+            .map(|(index, _)| index);
+        if free.is_some() {
+            return free;
+        }
+
+        // steal a releasing voice first, then a held voice
+        let key = |slot: &VoiceSlot<V>| match self.stealing {
+            VoiceStealing::Oldest => slot.started as i64,
+            VoiceStealing::Lowest => i64::from(slot.note.unwrap_or_default()),
+            VoiceStealing::Highest => -i64::from(slot.note.unwrap_or_default()),
+            VoiceStealing::None => 0,
+        };
+
+        match self.stealing {
+            VoiceStealing::None => None,
+            _ => self
+                .slots
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, slot)| (slot.held, key(slot)))
+                .map(|(index, _)| index),
+        }
+    }
+}
+
+impl<V: Voice> VoiceSlot<V> {
+    fn release(&mut self, when: f64) {
+        self.held = false;
+        self.free_at = when + self.voice.release_time();
+        self.voice.note_off(when);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct TestVoice {
+        id: usize,
+        playing: Option<u8>,
+        released_at: Option<f64>,
+    }
+
+    impl Voice for TestVoice {
+        fn note_on(&mut self, note: u8, _velocity: f32, _when: f64) {
+            self.playing = Some(note);
+            self.released_at = None;
+        }
+
+        fn note_off(&mut self, when: f64) {
+            self.released_at = Some(when);
+        }
+
+        fn release_time(&self) -> f64 {
+            1.
+        }
+    }
+
+    fn pool(stealing: VoiceStealing) -> VoiceManager<TestVoice> {
+        let voices = (0..2).map(|id| TestVoice {
+            id,
+            ..TestVoice::default()
+        });
+        VoiceManager::new(voices, VoiceManagerOptions { stealing })
+    }
+
+    #[test]
+    fn test_allocation() {
+        let mut manager = pool(VoiceStealing::Oldest);
+        assert_eq!(manager.polyphony(), 2);
+
+        assert_eq!(manager.note_on(60, 1., 0.).unwrap().id, 0);
+        assert_eq!(manager.note_on(64, 1., 0.).unwrap().id, 1);
+        assert_eq!(manager.held_notes(), vec![60, 64]);
+
+        // retrigger on the same voice
+        assert_eq!(manager.note_on(60, 1., 0.5).unwrap().id, 0);
+        assert_eq!(manager.held_notes(), vec![64, 60]);
+
+        manager.note_off(64, 1.);
+        assert_eq!(manager.held_notes(), vec![60]);
+        assert_eq!(manager.voices_mut().nth(1).unwrap().released_at, Some(1.));
+        assert!(manager.voice_for_note(64).is_none());
+        assert_eq!(manager.voice_for_note(60).unwrap().id, 0);
+
+        // the released voice is free after its release time
+        assert_eq!(manager.note_on(67, 1., 2.).unwrap().id, 1);
+
+        manager.all_notes_off(3.);
+        assert!(manager.held_notes().is_empty());
+    }
+
+    #[test]
+    fn test_stealing() {
+        // a releasing voice is stolen before a held voice
+        let mut manager = pool(VoiceStealing::Oldest);
+        manager.note_on(60, 1., 0.);
+        manager.note_on(64, 1., 0.);
+        manager.note_off(64, 1.);
+        assert_eq!(manager.note_on(67, 1., 1.5).unwrap().id, 1);
+        assert_eq!(manager.note_on(72, 1., 1.5).unwrap().id, 0);
+        assert_eq!(manager.held_notes(), vec![67, 72]);
+
+        let mut manager = manager_with(VoiceStealing::Lowest);
+        assert_eq!(manager.note_on(72, 1., 0.5).unwrap().playing, Some(72));
+        assert_eq!(manager.held_notes(), vec![64, 72]);
+
+        let mut manager = manager_with(VoiceStealing::Highest);
+        manager.note_on(72, 1., 0.5);
+        assert_eq!(manager.held_notes(), vec![60, 72]);
+
+        let mut manager = manager_with(VoiceStealing::None);
+        assert!(manager.note_on(72, 1., 0.5).is_none());
+        assert_eq!(manager.held_notes(), vec![60, 64]);
+    }
+
+    fn manager_with(stealing: VoiceStealing) -> VoiceManager<TestVoice> {
+        let mut manager = pool(stealing);
+        manager.note_on(60, 1., 0.);
+        manager.note_on(64, 1., 0.);
+        manager
+    }
+}
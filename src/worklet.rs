@@ -277,6 +277,11 @@ impl AudioWorkletNode {
             (node, Box::new(render))
         });
 
+        // a processor without inputs generates sound from the start
+        if number_of_inputs == 0 {
+            context.base().notify_source_start(0.);
+        }
+
         node
     }
 
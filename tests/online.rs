@@ -66,16 +66,12 @@ fn ensure_audio_node_object_safe() {
     let _object: Box<dyn AudioNode> = Box::new(node);
 }
 
-/*
- * AudioScheduledSourceNode trait is not object safe, see #249
- *
 #[allow(dead_code)]
 fn ensure_audio_scheduled_source_node_object_safe() {
     let context = AudioContext::default();
     let node = context.create_constant_source();
     let _object: Box<dyn AudioScheduledSourceNode> = Box::new(node);
 }
-*/
 
 #[test]
 fn test_none_sink_id() {
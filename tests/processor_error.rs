@@ -1,3 +1,6 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
 use float_eq::assert_float_eq;
 
 use web_audio_api::context::{BaseAudioContext, OfflineAudioContext};
@@ -52,3 +55,47 @@ fn test_processor_error() {
     // error branch should be muted, and other source should be processed
     assert_float_eq!(output.get_channel_data(0), &[1.; 128][..], abs_all <= 0.);
 }
+
+#[test]
+fn test_processor_error_event() {
+    let mut context = OfflineAudioContext::new(1, 256, 48000.);
+
+    let options = AudioWorkletNodeOptions::default();
+    let panic = AudioWorkletNode::new::<PanicProcessor>(&context, options);
+    panic.connect(&context.destination());
+
+    let message = Arc::new(Mutex::new(None));
+    let message_clone = Arc::clone(&message);
+    panic.set_onprocessorerror(Box::new(move |event| {
+        *message_clone.lock().unwrap() = Some(event.message);
+    }));
+
+    // the muted node can still be used by the control thread
+    context.suspend_sync(128. / 48000., move |context| {
+        panic.disconnect();
+        panic.connect(&context.destination());
+    });
+
+    let output = context.start_rendering_sync();
+    assert_float_eq!(output.get_channel_data(0), &[0.; 256][..], abs_all <= 0.);
+    assert_eq!(message.lock().unwrap().as_deref(), Some("panic message"));
+}
+
+#[test]
+fn test_event_callback_panic() {
+    let mut context = OfflineAudioContext::new(1, 128, 48000.);
+
+    let mut source1 = context.create_constant_source();
+    source1.start();
+    source1.set_onended(|_| panic!("callback panic"));
+
+    let mut source2 = context.create_constant_source();
+    source2.start();
+    let ended = Arc::new(AtomicBool::new(false));
+    let ended_clone = Arc::clone(&ended);
+    source2.set_onended(move |_| ended_clone.store(true, Ordering::Relaxed));
+
+    // the panic is logged, the other events are still dispatched
+    let _ = context.start_rendering_sync();
+    assert!(ended.load(Ordering::Relaxed));
+}